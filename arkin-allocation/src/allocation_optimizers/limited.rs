@@ -1,12 +1,17 @@
 #![allow(dead_code)]
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
+use parking_lot::RwLock;
 use rust_decimal::prelude::*;
+use time::OffsetDateTime;
 use tokio::select;
 use tokio_util::sync::CancellationToken;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 use typed_builder::TypedBuilder;
 
 use arkin_core::prelude::*;
@@ -14,19 +19,120 @@ use arkin_persistence::prelude::*;
 use arkin_portfolio::prelude::*;
 use uuid::Uuid;
 
-use crate::{AllocationOptim, AllocationOptimError};
+use crate::{AllocationOptim, AllocationOptimError, MarginTopUpConfig, Rebalancer, TargetExchange};
 
 #[derive(Debug, TypedBuilder)]
 pub struct LimitedAllocationOptim {
     pubsub: Arc<PubSub>,
     persistence: Arc<PersistenceService>,
     portfolio: Arc<dyn Accounting>,
+    /// Strategy the orders of the allocation are throttled and attributed under
+    strategy: Arc<Strategy>,
     #[builder(default = DashMap::new())]
-    optimal_allocation: DashMap<Arc<Instrument>, Arc<Insight>>,
+    optimal_allocation: DashMap<Arc<Instrument>, Weight>,
     leverage: Decimal,
     min_trade_value: Decimal,
     allocation_feature_id: FeatureId,
     reference_currency: Arc<Asset>,
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+    /// Exchange of the allocation problem and target weights with external optimizers
+    #[builder(default)]
+    target_exchange: Option<Arc<TargetExchange>>,
+    /// Decides at which ticks to rebalance, without it every tick rebalances
+    #[builder(default)]
+    rebalancer: Option<Arc<Rebalancer>>,
+    /// Parameters an operator changed at runtime, they take over from the configured ones
+    #[builder(default)]
+    overrides: DashMap<String, Decimal>,
+    /// Members of the universe past their warmup, every instrument is tradable without a universe
+    #[builder(default)]
+    tradable: RwLock<Option<HashSet<Arc<Instrument>>>>,
+    /// Tops up the futures wallet from the spot wallet when the capital runs low
+    #[builder(default)]
+    margin_top_up: Option<MarginTopUpConfig>,
+    /// Time the last top up was requested
+    #[builder(default)]
+    last_top_up: RwLock<Option<OffsetDateTime>>,
+}
+
+impl LimitedAllocationOptim {
+    fn leverage(&self) -> Decimal {
+        self.overrides.get("leverage").map(|v| *v).unwrap_or(self.leverage)
+    }
+
+    fn min_trade_value(&self) -> Decimal {
+        self.overrides
+            .get("min_trade_value")
+            .map(|v| *v)
+            .unwrap_or(self.min_trade_value)
+    }
+
+    /// Change `leverage` or `min_trade_value` from the next rebalance on.
+    fn operator_action(&self, action: &OperatorAction) {
+        let OperatorActionType::SetParameter { name, value } = &action.action else {
+            return;
+        };
+        match name.as_str() {
+            "leverage" | "min_trade_value" => {
+                info!("LimitedAllocationOptim setting {} to {}: {}", name, value, action.reason);
+                self.overrides.insert(name.clone(), *value);
+            }
+            _ => warn!("LimitedAllocationOptim has no parameter {}", name),
+        }
+    }
+
+    /// Target no position in the instruments that left the universe or still warm up.
+    fn universe_update(&self, update: &UniverseUpdate) {
+        for instrument in update.left.iter().chain(&update.warming) {
+            info!(
+                "LimitedAllocationOptim targets no position in {} outside of universe {}",
+                instrument, update.universe
+            );
+            self.optimal_allocation.insert(instrument.clone(), Weight::ZERO);
+        }
+        *self.tradable.write() = Some(update.members.iter().cloned().collect());
+    }
+
+    /// Request a transfer from the spot wallet once the capital falls below the minimum, enough to bring it back to the
+    /// target. The transfer manager checks it against the transfer limits like any other transfer.
+    fn top_up(&self, capital: Decimal, event_time: OffsetDateTime) {
+        let Some(top_up) = &self.margin_top_up else {
+            return;
+        };
+        if capital >= top_up.min_capital {
+            return;
+        }
+        let cooldown = time::Duration::seconds(top_up.cooldown_secs as i64);
+        if self.last_top_up.read().is_some_and(|last| event_time < last + cooldown) {
+            return;
+        }
+        *self.last_top_up.write() = Some(event_time);
+        let transfer = AccountTransfer::builder()
+            .event_time(event_time)
+            .portfolio(test_portfolio())
+            .asset(self.reference_currency.clone())
+            .quantity(top_up.target_capital - capital)
+            .from(AccountType::Spot)
+            .to(AccountType::UsdmFutures)
+            .requested_by("allocation".to_string())
+            .reason(format!("capital {} below {}", capital, top_up.min_capital))
+            .build();
+        info!("LimitedAllocationOptim requesting margin top up: {}", transfer);
+        self.pubsub.publish::<AccountTransfer>(transfer.into());
+    }
+
+    fn is_tradable(&self, instrument: &Arc<Instrument>) -> bool {
+        self.tradable.read().as_ref().is_none_or(|t| t.contains(instrument))
+    }
+
+    /// Allocation weight of the instrument, zero while it is not tradable.
+    fn target_weight(&self, instrument: &Arc<Instrument>, weight: Weight) -> Weight {
+        match self.is_tradable(instrument) {
+            true => weight,
+            false => Weight::ZERO,
+        }
+    }
 }
 
 pub struct OptimalPosition {
@@ -46,12 +152,35 @@ pub struct DiffPosition {
 impl AllocationOptim for LimitedAllocationOptim {
     async fn start(&self, shutdown: CancellationToken) -> Result<(), AllocationOptimError> {
         info!("Starting LimitedAllocation...");
+        if let Some(exchange) = self.target_exchange.clone() {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                if let Err(e) = exchange.serve(shutdown).await {
+                    error!("Allocation target exchange stopped: {}", e);
+                }
+            });
+        }
         let mut insight_tick = self.pubsub.subscribe::<InsightTick>();
+        let mut operator_actions = self.pubsub.subscribe::<OperatorAction>();
+        let mut universe_updates = self.pubsub.subscribe::<UniverseUpdate>();
+        let mut covariance_updates = self.pubsub.subscribe::<CovarianceUpdate>();
         loop {
             select! {
+                Ok(action) = operator_actions.recv() => {
+                    self.operator_action(&action);
+                }
+                Ok(update) = universe_updates.recv() => {
+                    self.universe_update(&update);
+                }
+                Ok(update) = covariance_updates.recv() => {
+                    if let Some(exchange) = &self.target_exchange {
+                        exchange.covariance_update(update);
+                    }
+                }
                 Ok(tick) = insight_tick.recv() => {
                     info!("LimitedAllocationOptim received insight tick: {}", tick.event_time);
-                    self.optimize(tick).await?;
+                    let _guard = self.watchdog.track("allocation", "insight_tick");
+                    skip_failed_event("allocation optimizer", self.optimize(tick).await)?;
                 }
                 _ = shutdown.cancelled() => {
                     break;
@@ -62,31 +191,42 @@ impl AllocationOptim for LimitedAllocationOptim {
     }
 
     async fn optimize(&self, tick: Arc<InsightTick>) -> Result<Vec<Arc<ExecutionOrder>>, AllocationOptimError> {
+        if let Some(exchange) = &self.target_exchange {
+            exchange.update(&tick);
+        }
+
         // Save down new allocation
         tick.insights
             .iter()
             .filter(|insight| insight.feature_id == self.allocation_feature_id)
             .for_each(|a| {
-                self.optimal_allocation
-                    .insert(a.instrument.clone().expect("Can't allocation empty instruments"), a.clone());
+                let instrument = a.instrument.clone().expect("Can't allocation empty instruments");
+                let weight = self.target_weight(&instrument, a.value);
+                self.optimal_allocation.insert(instrument, weight);
             });
 
-        // Check if we have any signals
-        if self.optimal_allocation.is_empty() {
-            warn!("No allocations found for optimization");
-            return Ok(Vec::new());
+        // Externally computed weights take over from the allocation feature once imported
+        if let Some(weights) = self.target_exchange.as_ref().and_then(|e| e.targets()) {
+            info!("Applying {} imported target weights", weights.len());
+            self.optimal_allocation.clear();
+            for (instrument, weight) in weights {
+                let weight = self.target_weight(&instrument, weight);
+                self.optimal_allocation.insert(instrument, weight);
+            }
         }
 
         // Calculate money allocated to each signal
         let capital = self.portfolio.available_balance(&self.reference_currency).await;
+        self.top_up(capital, tick.event_time);
         if capital.is_zero() {
             warn!("No capital available for allocation");
             return Ok(Vec::new());
         }
-        let leveraged_capital = capital * self.leverage;
+        let leverage = self.leverage();
+        let leveraged_capital = capital * leverage;
         info!(
             "Available capital for allocation: {} with {} times leverage becomes {}",
-            capital, self.leverage, leveraged_capital
+            capital, leverage, leveraged_capital
         );
 
         // Get current positions
@@ -105,30 +245,56 @@ impl AllocationOptim for LimitedAllocationOptim {
             info!("Current weight for {} is {}", instrument, weight);
         }
 
+        // Export the problem for external optimizers
+        if let Some(exchange) = &self.target_exchange {
+            exchange.export(&tick, &current_weights);
+        }
+
+        // Check if we have any signals
+        if self.optimal_allocation.is_empty() {
+            warn!("No allocations found for optimization");
+            return Ok(Vec::new());
+        }
+
         // Get our current optimal allocation
         let optimal_weights = self
             .optimal_allocation
             .iter()
             .map(|entry| (entry.key().clone(), entry.value().clone()))
             .collect::<HashMap<_, _>>();
-        for (instrument, insight) in optimal_weights.iter() {
-            info!("Optimal weight for {} is {}", instrument, insight.value);
+        for (instrument, weight) in optimal_weights.iter() {
+            info!("Optimal weight for {} is {}", instrument, weight);
         }
 
         // Calculate the difference between current and optimal allocation weights
         let mut allocation_change = HashMap::new();
         for (optimal_instrument, optimal_weight) in optimal_weights.iter() {
             if let Some(current_weight) = current_weights.get(optimal_instrument) {
-                let diff = optimal_weight.value - current_weight;
+                let diff = optimal_weight - current_weight;
                 allocation_change.insert(optimal_instrument.clone(), diff);
             } else {
-                allocation_change.insert(optimal_instrument.clone(), optimal_weight.value);
+                allocation_change.insert(optimal_instrument.clone(), *optimal_weight);
             }
         }
         for (instrument, weight) in allocation_change.iter() {
             info!("Change weight for {} with {}", instrument, weight);
         }
 
+        // Only trade when the rebalancer triggers and record why
+        if let Some(rebalancer) = &self.rebalancer {
+            let Some((trigger, detail)) = rebalancer.check(&tick, &allocation_change) else {
+                return Ok(Vec::new());
+            };
+            let rebalance = Rebalance::builder()
+                .event_time(tick.event_time)
+                .portfolio(test_portfolio())
+                .trigger(trigger)
+                .detail(detail)
+                .build();
+            info!("Rebalancing: {}", rebalance);
+            self.pubsub.publish::<Rebalance>(rebalance.into());
+        }
+
         // Create execution orders
         let mut execution_orders: Vec<Arc<ExecutionOrder>> = Vec::with_capacity(allocation_change.len());
         for (instrument, diff) in allocation_change.into_iter() {
@@ -170,10 +336,11 @@ impl AllocationOptim for LimitedAllocationOptim {
 
             // Skip if quantity is below minimum trade size
             let value = final_price * final_quantity.abs();
-            if value < self.min_trade_value {
+            let min_trade_value = self.min_trade_value();
+            if value < min_trade_value {
                 info!(
                     "Skipping trade for {} as value of {} is below minimum trade size of {}",
-                    instrument, value, self.min_trade_value
+                    instrument, value, min_trade_value
                 );
                 continue;
             }
@@ -182,6 +349,7 @@ impl AllocationOptim for LimitedAllocationOptim {
                 .id(Uuid::new_v4())
                 .portfolio(test_portfolio())
                 .instrument(instrument.clone())
+                .strategy(Some(self.strategy.clone()))
                 .order_type(ExecutionOrderType::Maker)
                 .side(order_side)
                 .quantity(final_quantity)
@@ -2,9 +2,16 @@ use arkin_core::FeatureId;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use crate::AllocationConstraints;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AllocationOptimConfig {
     pub allocation_optim: AllocationTypeConfig,
+    #[serde(default)]
+    pub delta_hedge: Option<DeltaHedgeConfig>,
+    /// Keep a short hedge against the exposure of the portfolio next to the strategies
+    #[serde(default)]
+    pub tail_hedge: Option<TailHedgeConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,7 +22,114 @@ pub enum AllocationTypeConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LimitedConfig {
+    /// Name of the strategy the allocation trades as, its orders are throttled and attributed under it
+    #[serde(default = "default_allocation_strategy")]
+    pub strategy: String,
     pub leverage: Decimal,
     pub min_trade_value: Decimal,
     pub allocation_feature_id: FeatureId,
+    #[serde(default)]
+    pub target_exchange: Option<TargetExchangeConfig>,
+    #[serde(default)]
+    pub rebalance: Option<RebalanceConfig>,
+    /// Request transfers from the spot wallet when the futures wallet runs low, the capital is left as is without it
+    #[serde(default)]
+    pub margin_top_up: Option<MarginTopUpConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarginTopUpConfig {
+    /// Capital in the futures wallet below which a transfer is requested
+    pub min_capital: Decimal,
+    /// Capital the transfer brings the futures wallet back to
+    pub target_capital: Decimal,
+    /// Seconds before another transfer is requested, so the pending one can land first
+    #[serde(default = "default_top_up_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_top_up_cooldown_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RebalanceConfig {
+    /// Cron expression in UTC, like `0 */4 * * *`
+    #[serde(default)]
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub drift_threshold: Option<Decimal>,
+    #[serde(default)]
+    pub regime_feature_id: Option<FeatureId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TargetExchangeConfig {
+    /// Address to serve the problem and take the targets on, like `127.0.0.1:8090`
+    pub address: String,
+    pub expected_returns_feature_id: FeatureId,
+    pub returns_feature_id: FeatureId,
+    /// Number of returns per instrument used for the covariance
+    pub periods: usize,
+    #[serde(default)]
+    pub constraints: AllocationConstraints,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeltaHedgeConfig {
+    /// Name of the strategy the hedge trades as, its orders are throttled and attributed under it
+    #[serde(default = "default_delta_hedge_strategy")]
+    pub strategy: String,
+    #[serde(default = "default_hedge_venue")]
+    pub venue: String,
+    /// Canonical symbol of the perpetual to hedge with
+    pub instrument: String,
+    #[serde(default)]
+    pub target_delta: Decimal,
+    pub band: Decimal,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub min_trade_value: Decimal,
+    #[serde(default)]
+    pub delta_feature_id: Option<FeatureId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TailHedgeConfig {
+    /// Name of the strategy the hedge trades as, its pnl is attributed to it
+    #[serde(default = "default_tail_hedge_strategy")]
+    pub strategy: String,
+    #[serde(default = "default_hedge_venue")]
+    pub venue: String,
+    /// Canonical symbol of the perpetual to hedge with
+    pub instrument: String,
+    /// Share of the beta weighted exposure of the portfolio held short
+    pub hedge_ratio: Decimal,
+    /// Largest notional of the hedge
+    #[serde(default)]
+    pub max_notional: Option<Decimal>,
+    /// Allowed deviation of the hedge notional from its target before trading
+    pub band: Decimal,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub min_trade_value: Decimal,
+    /// Feature with the beta of the instruments, every instrument counts with a beta of one without it
+    #[serde(default)]
+    pub beta_feature_id: Option<FeatureId>,
+}
+
+fn default_hedge_venue() -> String {
+    "binance".into()
+}
+
+fn default_allocation_strategy() -> String {
+    "allocation".into()
+}
+
+fn default_delta_hedge_strategy() -> String {
+    "delta_hedge".into()
+}
+
+fn default_tail_hedge_strategy() -> String {
+    "tail_hedge".into()
 }
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum AllocationOptimError {
     #[error(transparent)]
@@ -8,6 +10,28 @@ pub enum AllocationOptimError {
     #[error(transparent)]
     PortfolioError(#[from] arkin_portfolio::PortfolioError),
 
+    #[error("invalid target weights: {0}")]
+    InvalidTargets(String),
+
+    #[error("invalid rebalance schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
+
+impl ClassifyError for AllocationOptimError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            AllocationOptimError::PersistenceError(e) => e.class(),
+            AllocationOptimError::PortfolioError(e) => e.class(),
+            AllocationOptimError::InvalidTargets(_) => ErrorClass::InvalidInput,
+            AllocationOptimError::InvalidSchedule(_) => ErrorClass::Fatal,
+            AllocationOptimError::Io(_) => ErrorClass::Fatal,
+            AllocationOptimError::Anyhow(_) => ErrorClass::InvalidInput,
+        }
+    }
+}
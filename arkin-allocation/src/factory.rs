@@ -1,10 +1,13 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 use arkin_portfolio::prelude::*;
 
-use crate::{AllocationOptim, AllocationOptimConfig, AllocationTypeConfig, LimitedAllocationOptim};
+use crate::{
+    AllocationOptim, AllocationOptimConfig, AllocationTypeConfig, DeltaHedger, LimitedAllocationOptim, Rebalancer,
+    TailHedgeOverlay, TargetExchange,
+};
 
 pub struct AllocationFactory {}
 
@@ -14,6 +17,7 @@ impl AllocationFactory {
         pubsub: Arc<PubSub>,
         persistance: Arc<PersistenceService>,
         portfolio: Arc<dyn Accounting>,
+        watchdog: Arc<Watchdog>,
     ) -> Arc<dyn AllocationOptim> {
         let allocation: Arc<dyn AllocationOptim> = match &config.allocation_optim {
             AllocationTypeConfig::Limited(c) => Arc::new(
@@ -21,13 +25,106 @@ impl AllocationFactory {
                     .pubsub(pubsub.clone())
                     .persistence(persistance)
                     .portfolio(portfolio)
+                    .strategy(Arc::new(
+                        Strategy::builder()
+                            .name(c.strategy.clone())
+                            .description(Some("Allocation".into()))
+                            .build(),
+                    ))
                     .leverage(c.leverage)
                     .min_trade_value(c.min_trade_value)
                     .allocation_feature_id(c.allocation_feature_id.clone())
                     .reference_currency(test_usdt_asset())
+                    .watchdog(watchdog)
+                    .target_exchange(c.target_exchange.as_ref().map(|e| {
+                        Arc::new(
+                            TargetExchange::builder()
+                                .address(e.address.clone())
+                                .expected_returns_feature_id(e.expected_returns_feature_id.clone())
+                                .returns_feature_id(e.returns_feature_id.clone())
+                                .periods(e.periods)
+                                .constraints(e.constraints.clone())
+                                .build(),
+                        )
+                    }))
+                    .rebalancer(c.rebalance.as_ref().map(|r| {
+                        Arc::new(
+                            Rebalancer::builder()
+                                .schedule(r.schedule.as_ref().map(|s| s.parse().expect("Invalid rebalance schedule")))
+                                .drift_threshold(r.drift_threshold)
+                                .regime_feature_id(r.regime_feature_id.clone())
+                                .build(),
+                        )
+                    }))
+                    .margin_top_up(c.margin_top_up.clone())
                     .build(),
             ),
         };
         allocation
     }
+
+    /// Delta hedger running next to the allocation, if one is configured.
+    pub fn hedger_from_config(
+        config: &AllocationOptimConfig,
+        pubsub: Arc<PubSub>,
+        persistance: Arc<PersistenceService>,
+        portfolio: Arc<dyn Accounting>,
+        watchdog: Arc<Watchdog>,
+    ) -> Option<Arc<dyn AllocationOptim>> {
+        let c = config.delta_hedge.as_ref()?;
+        let strategy = Strategy::builder()
+            .name(c.strategy.clone())
+            .description(Some("Delta hedge".into()))
+            .build();
+        let hedger: Arc<dyn AllocationOptim> = Arc::new(
+            DeltaHedger::builder()
+                .pubsub(pubsub)
+                .persistence(persistance)
+                .portfolio(portfolio)
+                .strategy(Arc::new(strategy))
+                .venue(c.venue.clone())
+                .instrument(c.instrument.clone())
+                .target_delta(c.target_delta)
+                .band(c.band)
+                .interval(Duration::from_secs(c.interval_secs))
+                .min_trade_value(c.min_trade_value)
+                .delta_feature_id(c.delta_feature_id.clone())
+                .watchdog(watchdog)
+                .build(),
+        );
+        Some(hedger)
+    }
+
+    /// Tail hedge overlay running next to the allocation under its own strategy, if one is configured.
+    pub fn overlay_from_config(
+        config: &AllocationOptimConfig,
+        pubsub: Arc<PubSub>,
+        persistance: Arc<PersistenceService>,
+        portfolio: Arc<dyn Accounting>,
+        watchdog: Arc<Watchdog>,
+    ) -> Option<Arc<dyn AllocationOptim>> {
+        let c = config.tail_hedge.as_ref()?;
+        let strategy = Strategy::builder()
+            .name(c.strategy.clone())
+            .description(Some("Tail hedge overlay".into()))
+            .build();
+        let overlay: Arc<dyn AllocationOptim> = Arc::new(
+            TailHedgeOverlay::builder()
+                .pubsub(pubsub)
+                .persistence(persistance)
+                .portfolio(portfolio)
+                .strategy(Arc::new(strategy))
+                .venue(c.venue.clone())
+                .instrument(c.instrument.clone())
+                .hedge_ratio(c.hedge_ratio)
+                .max_notional(c.max_notional)
+                .band(c.band)
+                .interval(Duration::from_secs(c.interval_secs))
+                .min_trade_value(c.min_trade_value)
+                .beta_feature_id(c.beta_feature_id.clone())
+                .watchdog(watchdog)
+                .build(),
+        );
+        Some(overlay)
+    }
 }
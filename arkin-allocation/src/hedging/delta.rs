@@ -0,0 +1,250 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use rust_decimal::prelude::*;
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::{AllocationOptim, AllocationOptimError};
+
+/// Delta of a position in units of the base asset of the hedge instrument. Options contribute their quantity times
+/// the option delta, positions on other assets don't contribute.
+pub fn position_delta(hedge: &Instrument, position: &PositionUpdate, option_delta: Option<Decimal>) -> Option<Decimal> {
+    let instrument = &position.instrument;
+    let quantity = match position.position_side {
+        PositionSide::Long => position.quantity.abs(),
+        PositionSide::Short => -position.quantity.abs(),
+    } * instrument.contract_size;
+
+    if instrument.is_option() {
+        let underlying = instrument.underlying.as_ref()?;
+        if underlying.base_asset != hedge.base_asset {
+            return None;
+        }
+        Some(quantity * option_delta?)
+    } else if instrument.base_asset == hedge.base_asset {
+        Some(quantity)
+    } else {
+        None
+    }
+}
+
+/// Quantity of the hedge instrument to trade to bring the delta back to the target, rounded to the lot size. `None`
+/// while the delta is within the band around the target.
+pub fn hedge_quantity(hedge: &Instrument, delta: Decimal, target: Decimal, band: Decimal) -> Option<Quantity> {
+    let diff = target - delta;
+    if diff.abs() <= band {
+        return None;
+    }
+    let quantity = diff / hedge.contract_size;
+    let quantity = (quantity / hedge.lot_size).round() * hedge.lot_size;
+    let quantity = quantity.round_dp(hedge.quantity_precision);
+    (!quantity.is_zero()).then_some(quantity)
+}
+
+/// Keeps the delta of the portfolio on the underlying of a perpetual within a band around a target by trading the
+/// perpetual with taker orders. The delta is checked on a fixed interval, option positions use the deltas of the
+/// option greeks feature when one is configured.
+#[derive(Debug, TypedBuilder)]
+pub struct DeltaHedger {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    portfolio: Arc<dyn Accounting>,
+    /// Strategy the hedge orders are throttled and attributed under
+    strategy: Arc<Strategy>,
+    /// Venue of the hedge instrument
+    venue: String,
+    /// Canonical symbol of the perpetual used to hedge, like `perp-btc-usdt`
+    instrument: String,
+    /// Delta to keep in units of the base asset
+    #[builder(default)]
+    target_delta: Decimal,
+    /// Allowed deviation from the target before hedging
+    band: Decimal,
+    interval: Duration,
+    #[builder(default)]
+    min_trade_value: Decimal,
+    /// Feature with the delta of the options, without it options are left out of the portfolio delta
+    #[builder(default)]
+    delta_feature_id: Option<FeatureId>,
+    #[builder(default)]
+    option_deltas: DashMap<Arc<Instrument>, Decimal>,
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+}
+
+impl DeltaHedger {
+    /// Delta of all open positions on the underlying of the hedge instrument.
+    pub async fn portfolio_delta(&self, hedge: &Instrument) -> Decimal {
+        let positions = self.portfolio.get_positions().await;
+        positions
+            .values()
+            .filter_map(|position| {
+                let option_delta = self.option_deltas.get(&position.instrument).map(|d| *d.value());
+                if position.instrument.is_option() && option_delta.is_none() {
+                    warn!("No delta for option {}, leaving it out of the hedge", position.instrument);
+                }
+                position_delta(hedge, position, option_delta)
+            })
+            .sum()
+    }
+
+    /// Send a taker order on the hedge instrument when the portfolio delta left the band.
+    pub async fn hedge(&self, hedge: &Arc<Instrument>) -> Result<Option<Arc<ExecutionOrder>>, AllocationOptimError> {
+        let delta = self.portfolio_delta(hedge).await;
+        let Some(quantity) = hedge_quantity(hedge, delta, self.target_delta, self.band) else {
+            debug!(
+                "Delta {} on {} within band {} of {}",
+                delta, hedge, self.band, self.target_delta
+            );
+            return Ok(None);
+        };
+
+        let Some(tick) = self.persistence.tick_store.get_last_tick(hedge).await else {
+            warn!("No price found for {}, can't hedge delta {}", hedge, delta);
+            return Ok(None);
+        };
+        let (side, price) = if quantity.is_sign_positive() {
+            (MarketSide::Buy, tick.ask_price())
+        } else {
+            (MarketSide::Sell, tick.bid_price())
+        };
+        let price = ((price / hedge.tick_size).round() * hedge.tick_size).round_dp(hedge.price_precision);
+
+        let value = price * quantity.abs() * hedge.contract_size;
+        if value < self.min_trade_value {
+            info!(
+                "Skipping hedge for {} as value of {} is below minimum trade size of {}",
+                hedge, value, self.min_trade_value
+            );
+            return Ok(None);
+        }
+
+        info!(
+            "Hedging delta {} on {} to {}, {} {} at {}",
+            delta,
+            hedge,
+            self.target_delta,
+            side,
+            quantity.abs(),
+            price
+        );
+        let order: Arc<ExecutionOrder> = ExecutionOrder::builder()
+            .id(Uuid::new_v4())
+            .portfolio(test_portfolio())
+            .instrument(hedge.clone())
+            .strategy(Some(self.strategy.clone()))
+            .order_type(ExecutionOrderType::Taker)
+            .side(side)
+            .quantity(quantity.abs())
+            .price(price)
+            .created_at(tick.event_time)
+            .updated_at(tick.event_time)
+            .build()
+            .into();
+        self.pubsub.publish::<ExecutionOrder>(order.clone());
+        Ok(Some(order))
+    }
+
+    fn update_option_deltas(&self, tick: &InsightTick) {
+        let Some(feature_id) = &self.delta_feature_id else {
+            return;
+        };
+        tick.insights
+            .iter()
+            .filter(|insight| &insight.feature_id == feature_id)
+            .for_each(|insight| {
+                if let Some(instrument) = &insight.instrument {
+                    self.option_deltas.insert(instrument.clone(), insight.value);
+                }
+            });
+    }
+}
+
+#[async_trait]
+impl AllocationOptim for DeltaHedger {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), AllocationOptimError> {
+        info!("Starting DeltaHedger...");
+        let hedge = self.persistence.symbol_registry.resolve(&self.venue, &self.instrument).await?;
+        let mut insight_tick = self.pubsub.subscribe::<InsightTick>();
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            select! {
+                Ok(tick) = insight_tick.recv() => {
+                    self.update_option_deltas(&tick);
+                }
+                _ = interval.tick() => {
+                    let _guard = self.watchdog.track("hedger", "interval");
+                    skip_failed_event("delta hedger", self.hedge(&hedge).await)?;
+                }
+                _ = shutdown.cancelled() => {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The hedger trades on its own schedule, insight ticks only update the option deltas.
+    async fn optimize(&self, tick: Arc<InsightTick>) -> Result<Vec<Arc<ExecutionOrder>>, AllocationOptimError> {
+        self.update_option_deltas(&tick);
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn position(instrument: Arc<Instrument>, quantity: Decimal, side: PositionSide) -> PositionUpdate {
+        PositionUpdate::builder()
+            .event_time(OffsetDateTime::now_utc())
+            .portfolio(test_portfolio())
+            .instrument(instrument)
+            .entry_price(dec!(100000))
+            .quantity(quantity)
+            .realized_pnl(Decimal::ZERO)
+            .unrealized_pnl(Decimal::ZERO)
+            .position_side(side)
+            .build()
+    }
+
+    #[test]
+    fn test_position_delta() {
+        let perp = test_inst_binance_btc_usdt_perp();
+        let call = test_inst_binance_btc_usdt_call();
+        let eth = test_inst_binance_eth_usdt_perp();
+
+        let long = position(perp.clone(), dec!(0.5), PositionSide::Long);
+        assert_eq!(position_delta(&perp, &long, None), Some(dec!(0.5)));
+        let short = position(perp.clone(), dec!(-0.5), PositionSide::Short);
+        assert_eq!(position_delta(&perp, &short, None), Some(dec!(-0.5)));
+
+        let option = position(call, dec!(2), PositionSide::Long);
+        assert_eq!(position_delta(&perp, &option, Some(dec!(0.25))), Some(dec!(0.5)));
+        assert_eq!(position_delta(&perp, &option, None), None);
+
+        let other = position(eth, dec!(10), PositionSide::Long);
+        assert_eq!(position_delta(&perp, &other, None), None);
+    }
+
+    #[test]
+    fn test_hedge_quantity() {
+        let perp = test_inst_binance_btc_usdt_perp();
+        assert_eq!(hedge_quantity(&perp, dec!(0.05), Decimal::ZERO, dec!(0.1)), None);
+        assert_eq!(hedge_quantity(&perp, dec!(-0.1), Decimal::ZERO, dec!(0.1)), None);
+        assert_eq!(hedge_quantity(&perp, dec!(0.5), Decimal::ZERO, dec!(0.1)), Some(dec!(-0.5)));
+        assert_eq!(hedge_quantity(&perp, dec!(-0.3), dec!(0.2), dec!(0.1)), Some(dec!(0.5)));
+    }
+}
@@ -0,0 +1,5 @@
+mod delta;
+mod overlay;
+
+pub use delta::*;
+pub use overlay::*;
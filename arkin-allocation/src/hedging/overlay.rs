@@ -0,0 +1,262 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::{hedge_quantity, AllocationOptim, AllocationOptimError};
+
+/// Notional the tail hedge should hold against the beta weighted exposure of the portfolio, negative for a short.
+/// The hedge is only ever short, a portfolio without long exposure needs none.
+pub fn tail_hedge_target(exposure: Notional, hedge_ratio: Decimal, max_notional: Option<Notional>) -> Notional {
+    let target = (-exposure * hedge_ratio).min(Decimal::ZERO);
+    match max_notional {
+        Some(max) => target.max(-max.abs()),
+        None => target,
+    }
+}
+
+/// Keeps a short position in a perpetual proportional to the beta weighted exposure of the portfolio, independent of
+/// the strategies. The overlay trades under its own strategy so its pnl is attributed apart from the strategies, and
+/// books the fills of its own orders to leave its hedge out of the exposure. Betas come from an insight feature,
+/// instruments without one count with a beta of one. The hedge is checked on a fixed interval.
+#[derive(Debug, TypedBuilder)]
+pub struct TailHedgeOverlay {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    portfolio: Arc<dyn Accounting>,
+    /// Strategy the orders of the overlay trade as
+    strategy: Arc<Strategy>,
+    /// Venue of the hedge instrument
+    venue: String,
+    /// Canonical symbol of the perpetual used to hedge, like `perp-btc-usdt`
+    instrument: String,
+    /// Share of the beta weighted exposure held short
+    hedge_ratio: Decimal,
+    /// Largest notional of the hedge
+    #[builder(default)]
+    max_notional: Option<Notional>,
+    /// Allowed deviation of the hedge notional from its target before trading
+    band: Notional,
+    interval: Duration,
+    #[builder(default)]
+    min_trade_value: Decimal,
+    /// Feature with the beta of the instruments, without it every instrument counts with a beta of one
+    #[builder(default)]
+    beta_feature_id: Option<FeatureId>,
+    #[builder(default)]
+    betas: DashMap<Arc<Instrument>, Decimal>,
+    #[builder(default)]
+    orders: DashSet<ExecutionOrderId>,
+    /// Signed quantity of the hedge instrument held by the overlay
+    #[builder(default)]
+    position: Mutex<Quantity>,
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+}
+
+impl TailHedgeOverlay {
+    /// Beta weighted notional of the open positions, negative when short, without the hedge of the overlay. Options
+    /// are left out.
+    pub async fn exposure(&self, hedge: &Arc<Instrument>) -> Notional {
+        let own = *self.position.lock();
+        let mut exposure = Notional::ZERO;
+        for position in self.portfolio.get_positions().await.values() {
+            let instrument = &position.instrument;
+            if instrument.is_option() {
+                debug!("Leaving option {} out of the tail hedge", instrument);
+                continue;
+            }
+            let mut quantity = match position.position_side {
+                PositionSide::Long => position.quantity.abs(),
+                PositionSide::Short => -position.quantity.abs(),
+            };
+            if instrument == hedge {
+                quantity -= own;
+            }
+            if quantity.is_zero() {
+                continue;
+            }
+            let mark = match self.persistence.tick_store.get_last_tick(instrument).await {
+                Some(tick) => tick.mid_price(),
+                None => position.entry_price,
+            };
+            let beta = self.betas.get(instrument).map(|b| *b.value()).unwrap_or(Decimal::ONE);
+            exposure += quantity * instrument.contract_size * mark * beta;
+        }
+        exposure
+    }
+
+    /// Send a taker order on the hedge instrument when the hedge left the band around its target.
+    pub async fn hedge(&self, hedge: &Arc<Instrument>) -> Result<Option<Arc<ExecutionOrder>>, AllocationOptimError> {
+        let Some(tick) = self.persistence.tick_store.get_last_tick(hedge).await else {
+            warn!("No price found for {}, can't keep the tail hedge", hedge);
+            return Ok(None);
+        };
+        let mid = tick.mid_price();
+        if mid.is_zero() {
+            return Ok(None);
+        }
+
+        let exposure = self.exposure(hedge).await;
+        let target = tail_hedge_target(exposure, self.hedge_ratio, self.max_notional);
+        let own = *self.position.lock() * hedge.contract_size;
+        let Some(quantity) = hedge_quantity(hedge, own, target / mid, self.band / mid) else {
+            debug!(
+                "Tail hedge of {} on {} within band {} of {} for exposure {}",
+                own * mid,
+                hedge,
+                self.band,
+                target,
+                exposure
+            );
+            return Ok(None);
+        };
+
+        let (side, price) = if quantity.is_sign_positive() {
+            (MarketSide::Buy, tick.ask_price())
+        } else {
+            (MarketSide::Sell, tick.bid_price())
+        };
+        let price = ((price / hedge.tick_size).round() * hedge.tick_size).round_dp(hedge.price_precision);
+
+        let value = price * quantity.abs() * hedge.contract_size;
+        if value < self.min_trade_value {
+            info!(
+                "Skipping tail hedge for {} as value of {} is below minimum trade size of {}",
+                hedge, value, self.min_trade_value
+            );
+            return Ok(None);
+        }
+
+        info!(
+            "Tail hedge of {} on {} to {} for exposure {}, {} {} at {}",
+            own * mid,
+            hedge,
+            target,
+            exposure,
+            side,
+            quantity.abs(),
+            price
+        );
+        let order: Arc<ExecutionOrder> = ExecutionOrder::builder()
+            .id(Uuid::new_v4())
+            .portfolio(test_portfolio())
+            .instrument(hedge.clone())
+            .strategy(Some(self.strategy.clone()))
+            .order_type(ExecutionOrderType::Taker)
+            .side(side)
+            .quantity(quantity.abs())
+            .price(price)
+            .created_at(tick.event_time)
+            .updated_at(tick.event_time)
+            .build()
+            .into();
+        self.orders.insert(order.id);
+        self.pubsub.publish::<ExecutionOrder>(order.clone());
+        Ok(Some(order))
+    }
+
+    /// Book a fill of an order of the overlay on its hedge.
+    pub fn fill(&self, fill: &VenueOrderFill) {
+        if !self.orders.contains(&fill.venue_order.id) {
+            return;
+        }
+        let quantity = match fill.side {
+            MarketSide::Buy => fill.quantity,
+            MarketSide::Sell => -fill.quantity,
+        };
+        *self.position.lock() += quantity;
+        if fill.venue_order.status.is_finalized() {
+            self.orders.remove(&fill.venue_order.id);
+        }
+    }
+
+    /// Signed quantity of the hedge instrument held by the overlay.
+    pub fn position(&self) -> Quantity {
+        *self.position.lock()
+    }
+
+    fn update_betas(&self, tick: &InsightTick) {
+        let Some(feature_id) = &self.beta_feature_id else {
+            return;
+        };
+        tick.insights
+            .iter()
+            .filter(|insight| &insight.feature_id == feature_id)
+            .for_each(|insight| {
+                if let Some(instrument) = &insight.instrument {
+                    self.betas.insert(instrument.clone(), insight.value);
+                }
+            });
+    }
+}
+
+#[async_trait]
+impl AllocationOptim for TailHedgeOverlay {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), AllocationOptimError> {
+        info!("Starting TailHedgeOverlay as strategy {}...", self.strategy.name);
+        let hedge = self.persistence.symbol_registry.resolve(&self.venue, &self.instrument).await?;
+        let mut insight_tick = self.pubsub.subscribe::<InsightTick>();
+        let mut fills = self.pubsub.subscribe::<VenueOrderFill>();
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            select! {
+                Ok(tick) = insight_tick.recv() => {
+                    self.update_betas(&tick);
+                }
+                Ok(fill) = fills.recv() => {
+                    self.fill(&fill);
+                }
+                _ = interval.tick() => {
+                    let _guard = self.watchdog.track("tail_hedge", "interval");
+                    skip_failed_event("tail hedge overlay", self.hedge(&hedge).await)?;
+                }
+                _ = shutdown.cancelled() => {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The overlay trades on its own schedule, insight ticks only update the betas.
+    async fn optimize(&self, tick: Arc<InsightTick>) -> Result<Vec<Arc<ExecutionOrder>>, AllocationOptimError> {
+        self.update_betas(&tick);
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_tail_hedge_target() {
+        // A long exposure is hedged short, a short one needs no hedge
+        assert_eq!(tail_hedge_target(dec!(100000), dec!(0.3), None), dec!(-30000));
+        assert_eq!(tail_hedge_target(dec!(-100000), dec!(0.3), None), Decimal::ZERO);
+        assert_eq!(tail_hedge_target(dec!(100000), dec!(0.3), Some(dec!(20000))), dec!(-20000));
+
+        // Holding 0.1 BTC short at 100k against a target of -30k sells 0.2 more, within the band nothing trades
+        let perp = test_inst_binance_btc_usdt_perp();
+        let mid = dec!(100000);
+        assert_eq!(
+            hedge_quantity(&perp, dec!(-0.1), dec!(-30000) / mid, dec!(5000) / mid),
+            Some(dec!(-0.2))
+        );
+        assert_eq!(hedge_quantity(&perp, dec!(-0.28), dec!(-30000) / mid, dec!(5000) / mid), None);
+    }
+}
@@ -2,18 +2,27 @@ mod allocation_optimizers;
 mod config;
 mod errors;
 mod factory;
+mod hedging;
+mod rebalance;
+mod targets;
 mod traits;
 
 pub use allocation_optimizers::*;
 pub use config::*;
 pub use errors::*;
 pub use factory::AllocationFactory;
+pub use hedging::*;
+pub use rebalance::*;
+pub use targets::*;
 pub use traits::*;
 
 pub mod prelude {
     pub use crate::allocation_optimizers::*;
     pub use crate::config::*;
     pub use crate::errors::*;
+    pub use crate::hedging::*;
+    pub use crate::rebalance::*;
+    pub use crate::targets::*;
     pub use crate::traits::*;
     pub use crate::AllocationFactory;
 }
@@ -0,0 +1,5 @@
+mod rebalancer;
+mod schedule;
+
+pub use rebalancer::*;
+pub use schedule::*;
@@ -0,0 +1,169 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use super::CronSchedule;
+
+#[derive(Debug, Default)]
+struct RebalancerState {
+    last_check: Option<OffsetDateTime>,
+    regimes: HashMap<Option<Arc<Instrument>>, Decimal>,
+}
+
+/// Decides when the allocation rebalances instead of trading on every insight tick. A rebalance is triggered by the
+/// calendar schedule, by weights drifting from their optimum or by a change of a regime feature, whichever fires
+/// first. Without any trigger configured every tick rebalances.
+#[derive(Debug, TypedBuilder)]
+pub struct Rebalancer {
+    #[builder(default)]
+    schedule: Option<CronSchedule>,
+    /// Largest allowed absolute difference between the optimal and current weight of an instrument
+    #[builder(default)]
+    drift_threshold: Option<Decimal>,
+    /// Feature whose value changing for any instrument marks a new regime
+    #[builder(default)]
+    regime_feature_id: Option<FeatureId>,
+    #[builder(default)]
+    state: Mutex<RebalancerState>,
+}
+
+impl Rebalancer {
+    /// Trigger of a rebalance at this tick with what fired it, `None` when the allocation should hold. The drift is
+    /// the difference between the optimal and current weight per instrument.
+    pub fn check(
+        &self,
+        tick: &InsightTick,
+        drift: &HashMap<Arc<Instrument>, Weight>,
+    ) -> Option<(RebalanceTrigger, String)> {
+        let mut state = self.state.lock();
+        let last_check = state.last_check.replace(tick.event_time);
+        let regime = self.regime_change(&mut state, tick);
+
+        if self.schedule.is_none() && self.drift_threshold.is_none() && self.regime_feature_id.is_none() {
+            return Some((RebalanceTrigger::Schedule, "every tick".into()));
+        }
+
+        if let (Some(schedule), Some(last_check)) = (&self.schedule, last_check) {
+            if let Some(time) = schedule.next_between(last_check, tick.event_time) {
+                info!("Rebalance scheduled at {} by '{}'", time, schedule);
+                return Some((RebalanceTrigger::Schedule, format!("schedule '{}' at {}", schedule, time)));
+            }
+        }
+
+        if let Some(detail) = regime {
+            info!("Rebalance on regime change: {}", detail);
+            return Some((RebalanceTrigger::Regime, detail));
+        }
+
+        if let Some(threshold) = self.drift_threshold {
+            let largest = drift.iter().max_by_key(|(_, d)| d.abs());
+            if let Some((instrument, drift)) = largest.filter(|(_, d)| d.abs() > threshold) {
+                info!("Rebalance on drift of {} for {} above {}", drift, instrument, threshold);
+                return Some((
+                    RebalanceTrigger::Drift,
+                    format!("drift {} of {} above {}", drift, instrument.symbol, threshold),
+                ));
+            }
+        }
+
+        debug!("No rebalance triggered at {}", tick.event_time);
+        None
+    }
+
+    /// Record the regime values of the tick and describe the first change, the first value seen is no change.
+    fn regime_change(&self, state: &mut RebalancerState, tick: &InsightTick) -> Option<String> {
+        let feature_id = self.regime_feature_id.as_ref()?;
+        let mut change = None;
+        for insight in tick.insights.iter().filter(|i| &i.feature_id == feature_id) {
+            let previous = state.regimes.insert(insight.instrument.clone(), insight.value);
+            if let Some(previous) = previous.filter(|p| *p != insight.value) {
+                let scope = insight.instrument.as_ref().map(|i| i.symbol.as_str()).unwrap_or("portfolio");
+                change.get_or_insert_with(|| {
+                    format!("{} of {} from {} to {}", feature_id, scope, previous, insight.value)
+                });
+            }
+        }
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn tick(event_time: OffsetDateTime, insights: Vec<Arc<Insight>>) -> InsightTick {
+        InsightTick::builder()
+            .event_time(event_time)
+            .instruments(vec![test_inst_binance_btc_usdt_perp()])
+            .insights(insights)
+            .build()
+    }
+
+    fn regime(value: Decimal) -> Arc<Insight> {
+        Insight::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .pipeline(test_pipeline())
+            .instrument(None)
+            .feature_id(Arc::new("regime".to_string()))
+            .value(value)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_without_triggers() {
+        let rebalancer = Rebalancer::builder().build();
+        assert!(rebalancer
+            .check(&tick(datetime!(2025-01-01 00:00 UTC), vec![]), &HashMap::new())
+            .is_some());
+    }
+
+    #[test]
+    fn test_schedule() {
+        let rebalancer = Rebalancer::builder().schedule(Some("0 * * * *".parse().unwrap())).build();
+        let no_drift = HashMap::new();
+        assert!(rebalancer
+            .check(&tick(datetime!(2025-01-01 07:59 UTC), vec![]), &no_drift)
+            .is_none());
+        let (trigger, _) = rebalancer
+            .check(&tick(datetime!(2025-01-01 08:00 UTC), vec![]), &no_drift)
+            .unwrap();
+        assert_eq!(trigger, RebalanceTrigger::Schedule);
+        assert!(rebalancer
+            .check(&tick(datetime!(2025-01-01 08:01 UTC), vec![]), &no_drift)
+            .is_none());
+    }
+
+    #[test]
+    fn test_drift_and_regime() {
+        let rebalancer = Rebalancer::builder()
+            .drift_threshold(Some(dec!(0.1)))
+            .regime_feature_id(Some(Arc::new("regime".to_string())))
+            .build();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let small = HashMap::from([(btc.clone(), dec!(-0.05))]);
+        let large = HashMap::from([(btc.clone(), dec!(-0.2))]);
+
+        assert!(rebalancer
+            .check(&tick(datetime!(2025-01-01 00:00 UTC), vec![regime(dec!(1))]), &small)
+            .is_none());
+        let (trigger, detail) = rebalancer
+            .check(&tick(datetime!(2025-01-01 00:01 UTC), vec![regime(dec!(1))]), &large)
+            .unwrap();
+        assert_eq!(trigger, RebalanceTrigger::Drift);
+        assert!(detail.contains("perp-btc-usdt@binance"));
+        let (trigger, _) = rebalancer
+            .check(&tick(datetime!(2025-01-01 00:02 UTC), vec![regime(dec!(2))]), &small)
+            .unwrap();
+        assert_eq!(trigger, RebalanceTrigger::Regime);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use time::OffsetDateTime;
+
+use crate::AllocationOptimError;
+
+/// Longest gap between two checks that is searched for a matching minute.
+const MAX_SEARCH: Duration = Duration::from_secs(7 * 86400);
+
+/// Allowed values per field: minute, hour, day of month, month and day of week.
+const FIELDS: [(&str, u8, u8); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day of month", 1, 31),
+    ("month", 1, 12),
+    ("day of week", 0, 6),
+];
+
+/// Calendar schedule in cron syntax with five fields (`minute hour day-of-month month day-of-week`) evaluated in UTC.
+/// Fields take `*`, values, ranges (`1-5`), steps (`*/15`, `0-30/10`) and lists of those. Sunday is day 0. Like cron
+/// a day matches either restricted day field when both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    fields: [u64; 5],
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    fn parse_field(field: &str, name: &str, min: u8, max: u8) -> Result<u64, AllocationOptimError> {
+        let invalid = || AllocationOptimError::InvalidSchedule(format!("invalid {} field '{}'", name, field));
+        let mut mask = 0u64;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u8>().map_err(|_| invalid())?),
+                None => (part, 1),
+            };
+            if step == 0 {
+                return Err(invalid());
+            }
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (
+                        start.parse::<u8>().map_err(|_| invalid())?,
+                        end.parse::<u8>().map_err(|_| invalid())?,
+                    ),
+                    None => {
+                        let value = range.parse::<u8>().map_err(|_| invalid())?;
+                        // A step on a single value runs to the end of the field, like cron
+                        (value, if part.contains('/') { max } else { value })
+                    }
+                },
+            };
+            if start < min || end > max || start > end {
+                return Err(invalid());
+            }
+            for value in (start..=end).step_by(step as usize) {
+                mask |= 1u64 << value;
+            }
+        }
+        Ok(mask)
+    }
+
+    /// True if the minute of the time matches the schedule.
+    pub fn matches(&self, time: OffsetDateTime) -> bool {
+        let contains = |field: usize, value: u8| self.fields[field] & (1u64 << value) != 0;
+        let day_of_month = contains(2, time.day());
+        let day_of_week = contains(4, time.weekday().number_days_from_sunday());
+        let day = match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+        contains(0, time.minute()) && contains(1, time.hour()) && contains(3, time.month() as u8) && day
+    }
+
+    /// First minute matching the schedule after `from` up to and including `till`.
+    pub fn next_between(&self, from: OffsetDateTime, till: OffsetDateTime) -> Option<OffsetDateTime> {
+        let from = from.replace_second(0).ok()?.replace_nanosecond(0).ok()?;
+        let till = till.min(from + MAX_SEARCH);
+        let mut minute = from + Duration::from_secs(60);
+        while minute <= till {
+            if self.matches(minute) {
+                return Some(minute);
+            }
+            minute += Duration::from_secs(60);
+        }
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = AllocationOptimError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let parts = expression.split_whitespace().collect::<Vec<_>>();
+        if parts.len() != FIELDS.len() {
+            return Err(AllocationOptimError::InvalidSchedule(format!(
+                "expected 5 fields in '{}', got {}",
+                expression,
+                parts.len()
+            )));
+        }
+        let mut fields = [0u64; 5];
+        for (i, (part, (name, min, max))) in parts.iter().zip(FIELDS).enumerate() {
+            fields[i] = Self::parse_field(part, name, min, max)?;
+        }
+        Ok(Self {
+            expression: expression.to_owned(),
+            fields,
+            day_of_month_any: parts[2] == "*",
+            day_of_week_any: parts[4] == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert!("0 */4 * * *".parse::<CronSchedule>().is_ok());
+        assert!("0,30 8-16 * * 1-5".parse::<CronSchedule>().is_ok());
+        assert!("5/15 0 1 1 0".parse::<CronSchedule>().is_ok());
+        for invalid in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(invalid.parse::<CronSchedule>().is_err(), "{} should not parse", invalid);
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let every_four_hours = "0 */4 * * *".parse::<CronSchedule>().unwrap();
+        assert!(every_four_hours.matches(datetime!(2025-01-01 08:00:30 UTC)));
+        assert!(!every_four_hours.matches(datetime!(2025-01-01 09:00 UTC)));
+        assert!(!every_four_hours.matches(datetime!(2025-01-01 08:01 UTC)));
+
+        // 2025-01-06 is a Monday, 2025-01-05 a Sunday
+        let weekdays = "30 9 * * 1-5".parse::<CronSchedule>().unwrap();
+        assert!(weekdays.matches(datetime!(2025-01-06 09:30 UTC)));
+        assert!(!weekdays.matches(datetime!(2025-01-05 09:30 UTC)));
+
+        // Either day field matches when both are restricted
+        let first_or_sunday = "0 0 1 * 0".parse::<CronSchedule>().unwrap();
+        assert!(first_or_sunday.matches(datetime!(2025-01-01 00:00 UTC)));
+        assert!(first_or_sunday.matches(datetime!(2025-01-05 00:00 UTC)));
+        assert!(!first_or_sunday.matches(datetime!(2025-01-06 00:00 UTC)));
+    }
+
+    #[test]
+    fn test_next_between() {
+        let hourly = "0 * * * *".parse::<CronSchedule>().unwrap();
+        assert_eq!(
+            hourly.next_between(datetime!(2025-01-01 07:59:10 UTC), datetime!(2025-01-01 08:00:05 UTC)),
+            Some(datetime!(2025-01-01 08:00 UTC))
+        );
+        assert_eq!(
+            hourly.next_between(datetime!(2025-01-01 08:00 UTC), datetime!(2025-01-01 08:59 UTC)),
+            None
+        );
+        assert_eq!(
+            hourly.next_between(datetime!(2025-01-01 08:00 UTC), datetime!(2025-01-01 12:00 UTC)),
+            Some(datetime!(2025-01-01 09:00 UTC))
+        );
+    }
+}
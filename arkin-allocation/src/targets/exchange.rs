@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::prelude::*;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::AllocationOptimError;
+
+use super::{covariance, AllocationConstraints, AllocationProblem, TargetWeights};
+
+/// Hands the allocation problem to external optimizers and takes their target weights back over HTTP:
+///
+/// - `GET /allocation/problem` returns the [`AllocationProblem`] of the last rebalance tick
+/// - `POST /allocation/targets` takes [`TargetWeights`], they are validated against that problem and applied at the
+///   next rebalance tick
+#[derive(Debug, TypedBuilder)]
+pub struct TargetExchange {
+    address: String,
+    expected_returns_feature_id: FeatureId,
+    returns_feature_id: FeatureId,
+    /// Number of returns per instrument used for the covariance
+    periods: usize,
+    #[builder(default)]
+    constraints: AllocationConstraints,
+    #[builder(default)]
+    returns: Mutex<HashMap<Arc<Instrument>, VecDeque<f64>>>,
+    /// Last covariance published for the universe, the own estimate is used for instruments it misses
+    #[builder(default)]
+    covariance: RwLock<Option<Arc<CovarianceUpdate>>>,
+    #[builder(default)]
+    expected_returns: Mutex<HashMap<Arc<Instrument>, f64>>,
+    #[builder(default)]
+    problem: RwLock<Option<(AllocationProblem, Vec<Arc<Instrument>>)>>,
+    #[builder(default)]
+    pending: Mutex<Option<TargetWeights>>,
+    #[builder(default)]
+    active: RwLock<Option<HashMap<Arc<Instrument>, Decimal>>>,
+}
+
+impl TargetExchange {
+    /// Record the returns and expected returns of an insight tick.
+    pub fn update(&self, tick: &InsightTick) {
+        let mut returns = self.returns.lock();
+        let mut expected_returns = self.expected_returns.lock();
+        for insight in &tick.insights {
+            let (Some(instrument), Some(value)) = (&insight.instrument, insight.value.to_f64()) else {
+                continue;
+            };
+            if insight.feature_id == self.returns_feature_id {
+                let series = returns.entry(instrument.clone()).or_default();
+                series.push_back(value);
+                while series.len() > self.periods {
+                    series.pop_front();
+                }
+            } else if insight.feature_id == self.expected_returns_feature_id {
+                expected_returns.insert(instrument.clone(), value);
+            }
+        }
+    }
+
+    pub fn covariance_update(&self, update: Arc<CovarianceUpdate>) {
+        *self.covariance.write() = Some(update);
+    }
+
+    /// Build and publish the problem for the instruments with an expected return at this tick.
+    pub fn export(&self, tick: &InsightTick, current_weights: &HashMap<Arc<Instrument>, Decimal>) -> AllocationProblem {
+        let expected_returns = self.expected_returns.lock();
+        let returns = self.returns.lock();
+        let instruments = tick
+            .instruments
+            .iter()
+            .filter(|i| expected_returns.contains_key(*i))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let series = instruments
+            .iter()
+            .map(|i| returns.get(i).map(|r| r.iter().copied().collect()).unwrap_or_default())
+            .collect::<Vec<Vec<f64>>>();
+        let problem = AllocationProblem {
+            event_time: tick.event_time,
+            instruments: instruments.iter().map(|i| i.symbol.clone()).collect(),
+            expected_returns: instruments.iter().map(|i| expected_returns[i]).collect(),
+            covariance: self
+                .covariance
+                .read()
+                .as_ref()
+                .and_then(|c| c.submatrix(&instruments))
+                .unwrap_or_else(|| covariance(&series)),
+            current_weights: instruments
+                .iter()
+                .map(|i| current_weights.get(i).and_then(|w| w.to_f64()).unwrap_or(0.0))
+                .collect(),
+            constraints: self.constraints.clone(),
+        };
+        *self.problem.write() = Some((problem.clone(), instruments));
+        problem
+    }
+
+    /// Validate imported weights against the current problem and queue them for the next rebalance.
+    pub fn import(&self, targets: TargetWeights) -> Result<(), AllocationOptimError> {
+        let problem = self.problem.read();
+        let Some((problem, _)) = problem.as_ref() else {
+            return Err(AllocationOptimError::InvalidTargets(
+                "no allocation problem exported yet".into(),
+            ));
+        };
+        targets.validate(problem)?;
+        info!("Imported {} target weights for {}", targets.weights.len(), targets.event_time);
+        *self.pending.lock() = Some(targets);
+        Ok(())
+    }
+
+    /// Target weights of the last import. Weights imported since the previous call become active now, so they are
+    /// applied at the rebalance tick following their import and stay in place until the next import.
+    pub fn targets(&self) -> Option<HashMap<Arc<Instrument>, Decimal>> {
+        let pending = self.pending.lock().take();
+        if let Some(targets) = pending {
+            let problem = self.problem.read();
+            if let Some((problem, instruments)) = problem.as_ref() {
+                let weights = targets.by_instrument(problem);
+                let weights = instruments
+                    .iter()
+                    .filter_map(|i| weights.get(&i.symbol).map(|w| (i.clone(), *w)))
+                    .collect();
+                *self.active.write() = Some(weights);
+            }
+        }
+        self.active.read().clone()
+    }
+
+    pub fn router(self: &Arc<Self>) -> Router {
+        Router::new()
+            .route("/allocation/problem", get(problem))
+            .route("/allocation/targets", post(targets))
+            .with_state(self.clone())
+    }
+
+    pub async fn serve(self: Arc<Self>, shutdown: CancellationToken) -> Result<(), AllocationOptimError> {
+        let listener = TcpListener::bind(&self.address).await?;
+        info!("Allocation target exchange listening on {}", self.address);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await?;
+        Ok(())
+    }
+}
+
+async fn problem(State(exchange): State<Arc<TargetExchange>>) -> Response {
+    match exchange.problem.read().as_ref() {
+        Some((problem, _)) => Json(problem.clone()).into_response(),
+        None => (StatusCode::NOT_FOUND, "no allocation problem exported yet").into_response(),
+    }
+}
+
+async fn targets(State(exchange): State<Arc<TargetExchange>>, Json(targets): Json<TargetWeights>) -> Response {
+    match exchange.import(targets) {
+        Ok(()) => StatusCode::ACCEPTED.into_response(),
+        Err(e) => {
+            warn!("Rejected target weights: {}", e);
+            (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::TargetWeight;
+
+    fn insight(instrument: Arc<Instrument>, feature_id: &FeatureId, value: Decimal) -> Arc<Insight> {
+        Insight::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .pipeline(test_pipeline())
+            .instrument(Some(instrument))
+            .feature_id(feature_id.clone())
+            .value(value)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_export_import() {
+        let expected: FeatureId = Arc::new("expected_return".into());
+        let returns: FeatureId = Arc::new("return".into());
+        let exchange = TargetExchange::builder()
+            .address("127.0.0.1:0".into())
+            .expected_returns_feature_id(expected.clone())
+            .returns_feature_id(returns.clone())
+            .periods(2)
+            .build();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+
+        // Nothing to import into before the first export
+        let targets = TargetWeights {
+            event_time: datetime!(2025-01-01 00:00 UTC),
+            weights: vec![TargetWeight {
+                instrument: btc.symbol.clone(),
+                weight: dec!(0.5),
+            }],
+        };
+        assert!(exchange.import(targets.clone()).is_err());
+
+        for r in [dec!(0.01), dec!(0.02), dec!(0.03)] {
+            exchange.update(&InsightTick {
+                event_time: datetime!(2025-01-01 00:00 UTC),
+                instruments: vec![btc.clone(), eth.clone()],
+                insights: vec![insight(btc.clone(), &returns, r), insight(btc.clone(), &expected, dec!(0.001))],
+            });
+        }
+        let tick = InsightTick {
+            event_time: datetime!(2025-01-01 00:00 UTC),
+            instruments: vec![btc.clone(), eth.clone()],
+            insights: vec![],
+        };
+        let problem = exchange.export(&tick, &HashMap::from([(btc.clone(), dec!(0.25))]));
+        assert_eq!(problem.instruments, vec![btc.symbol.clone()]);
+        assert_eq!(problem.current_weights, vec![0.25]);
+        assert!((problem.covariance[0][0] - 0.00005).abs() < 1e-12);
+
+        // A published covariance covering the instruments replaces the own estimate
+        exchange.covariance_update(Arc::new(CovarianceUpdate::from_returns(
+            tick.event_time,
+            vec![eth.clone(), btc.clone()],
+            &[vec![0.01, 0.03], vec![0.01, 0.05]],
+        )));
+        let problem = exchange.export(&tick, &HashMap::new());
+        assert!((problem.covariance[0][0] - 0.0008).abs() < 1e-12);
+
+        assert!(exchange.targets().is_none());
+        exchange.import(targets).unwrap();
+        let weights = exchange.targets().unwrap();
+        assert_eq!(weights[&btc], dec!(0.5));
+        assert_eq!(exchange.targets(), Some(weights));
+    }
+}
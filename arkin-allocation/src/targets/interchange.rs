@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::AllocationOptimError;
+
+/// Limits the imported weights have to respect. Weights are fractions of the leveraged capital.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllocationConstraints {
+    /// Maximum sum of the absolute weights
+    pub max_gross_exposure: Decimal,
+    /// Maximum absolute weight of a single instrument
+    pub max_weight_per_instrument: Decimal,
+    pub allow_short: bool,
+}
+
+impl Default for AllocationConstraints {
+    fn default() -> Self {
+        Self {
+            max_gross_exposure: Decimal::ONE,
+            max_weight_per_instrument: Decimal::ONE,
+            allow_short: true,
+        }
+    }
+}
+
+/// The allocation problem as seen at the last rebalance tick, exported for external optimizers. The expected returns,
+/// covariance rows and current weights are in the order of the instruments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllocationProblem {
+    pub event_time: OffsetDateTime,
+    /// Instrument symbols, like `perp-btc-usdt@binance`
+    pub instruments: Vec<String>,
+    pub expected_returns: Vec<f64>,
+    pub covariance: Vec<Vec<f64>>,
+    pub current_weights: Vec<f64>,
+    pub constraints: AllocationConstraints,
+}
+
+/// Weight of one instrument in imported targets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetWeight {
+    pub instrument: String,
+    pub weight: Decimal,
+}
+
+/// Externally computed target weights. Instruments of the problem that are missing get a weight of zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetWeights {
+    /// Time of the problem the weights were computed for
+    pub event_time: OffsetDateTime,
+    pub weights: Vec<TargetWeight>,
+}
+
+impl TargetWeights {
+    /// Check the weights against the instruments and constraints of the problem.
+    pub fn validate(&self, problem: &AllocationProblem) -> Result<(), AllocationOptimError> {
+        let invalid = |msg: String| Err(AllocationOptimError::InvalidTargets(msg));
+
+        if self.event_time > problem.event_time {
+            return invalid(format!(
+                "targets at {} are newer than the problem at {}",
+                self.event_time, problem.event_time
+            ));
+        }
+
+        let known = problem.instruments.iter().collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        for target in &self.weights {
+            if !known.contains(&target.instrument) {
+                return invalid(format!("unknown instrument {}", target.instrument));
+            }
+            if !seen.insert(&target.instrument) {
+                return invalid(format!("duplicate instrument {}", target.instrument));
+            }
+            if target.weight.is_sign_negative() && !target.weight.is_zero() && !problem.constraints.allow_short {
+                return invalid(format!("short weight {} for {}", target.weight, target.instrument));
+            }
+            if target.weight.abs() > problem.constraints.max_weight_per_instrument {
+                return invalid(format!(
+                    "weight {} for {} exceeds the maximum of {}",
+                    target.weight, target.instrument, problem.constraints.max_weight_per_instrument
+                ));
+            }
+        }
+
+        let gross = self.weights.iter().map(|t| t.weight.abs()).sum::<Decimal>();
+        if gross > problem.constraints.max_gross_exposure {
+            return invalid(format!(
+                "gross exposure {} exceeds the maximum of {}",
+                gross, problem.constraints.max_gross_exposure
+            ));
+        }
+        Ok(())
+    }
+
+    /// Weight per instrument symbol, zero for the instruments of the problem without a target.
+    pub fn by_instrument(&self, problem: &AllocationProblem) -> HashMap<String, Decimal> {
+        let mut weights = problem
+            .instruments
+            .iter()
+            .map(|i| (i.clone(), Decimal::ZERO))
+            .collect::<HashMap<_, _>>();
+        for target in &self.weights {
+            weights.insert(target.instrument.clone(), target.weight);
+        }
+        weights
+    }
+}
+
+/// Sample covariance of return series, the series are aligned on their most recent values.
+pub fn covariance(returns: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let periods = returns.iter().map(|r| r.len()).min().unwrap_or(0);
+    if periods < 2 {
+        return vec![vec![0.0; returns.len()]; returns.len()];
+    }
+    let series = returns.iter().map(|r| &r[r.len() - periods..]).collect::<Vec<_>>();
+    let means = series
+        .iter()
+        .map(|r| r.iter().sum::<f64>() / periods as f64)
+        .collect::<Vec<_>>();
+
+    let mut covariance = vec![vec![0.0; series.len()]; series.len()];
+    for i in 0..series.len() {
+        for j in i..series.len() {
+            let cov = series[i]
+                .iter()
+                .zip(series[j].iter())
+                .map(|(a, b)| (a - means[i]) * (b - means[j]))
+                .sum::<f64>()
+                / (periods - 1) as f64;
+            covariance[i][j] = cov;
+            covariance[j][i] = cov;
+        }
+    }
+    covariance
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn problem() -> AllocationProblem {
+        AllocationProblem {
+            event_time: datetime!(2025-01-01 00:00 UTC),
+            instruments: vec!["perp-btc-usdt@binance".into(), "perp-eth-usdt@binance".into()],
+            expected_returns: vec![0.01, 0.02],
+            covariance: vec![vec![0.1, 0.0], vec![0.0, 0.1]],
+            current_weights: vec![0.0, 0.0],
+            constraints: AllocationConstraints {
+                max_gross_exposure: dec!(1),
+                max_weight_per_instrument: dec!(0.6),
+                allow_short: false,
+            },
+        }
+    }
+
+    fn targets(weights: &[(&str, Decimal)]) -> TargetWeights {
+        TargetWeights {
+            event_time: datetime!(2025-01-01 00:00 UTC),
+            weights: weights
+                .iter()
+                .map(|(i, w)| TargetWeight {
+                    instrument: i.to_string(),
+                    weight: *w,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let problem = problem();
+        let valid = targets(&[("perp-btc-usdt@binance", dec!(0.4)), ("perp-eth-usdt@binance", dec!(0.6))]);
+        assert!(valid.validate(&problem).is_ok());
+        let weights = valid.by_instrument(&problem);
+        assert_eq!(weights["perp-eth-usdt@binance"], dec!(0.6));
+
+        let partial = targets(&[("perp-btc-usdt@binance", dec!(0.5))]);
+        assert!(partial.validate(&problem).is_ok());
+        assert_eq!(partial.by_instrument(&problem)["perp-eth-usdt@binance"], Decimal::ZERO);
+
+        for invalid in [
+            targets(&[("perp-sol-usdt@binance", dec!(0.1))]),
+            targets(&[("perp-btc-usdt@binance", dec!(0.1)), ("perp-btc-usdt@binance", dec!(0.1))]),
+            targets(&[("perp-btc-usdt@binance", dec!(-0.1))]),
+            targets(&[("perp-btc-usdt@binance", dec!(0.7))]),
+            targets(&[("perp-btc-usdt@binance", dec!(0.6)), ("perp-eth-usdt@binance", dec!(0.6))]),
+        ] {
+            assert!(invalid.validate(&problem).is_err(), "{:?} should be invalid", invalid);
+        }
+
+        let mut future = valid.clone();
+        future.event_time = datetime!(2025-01-01 00:01 UTC);
+        assert!(future.validate(&problem).is_err());
+    }
+
+    #[test]
+    fn test_covariance() {
+        let cov = covariance(&[vec![1.0, 2.0, 3.0], vec![9.0, 2.0, 4.0, 6.0]]);
+        assert!((cov[0][0] - 1.0).abs() < 1e-12);
+        assert!((cov[1][1] - 4.0).abs() < 1e-12);
+        assert!((cov[0][1] - 2.0).abs() < 1e-12);
+        assert_eq!(cov[0][1], cov[1][0]);
+
+        assert_eq!(covariance(&[vec![1.0], vec![2.0]]), vec![vec![0.0; 2]; 2]);
+    }
+}
@@ -0,0 +1,5 @@
+mod exchange;
+mod interchange;
+
+pub use exchange::*;
+pub use interchange::*;
@@ -0,0 +1,356 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::debug;
+use typed_builder::TypedBuilder;
+use url::Url;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    market::ExchangeInfoRequest,
+    trade::{BalanceRequest, CancelOpenOrdersRequest, NewOrderRequest, PositionInfoRequest},
+    BinanceHttpClient, Credentials, Request,
+};
+
+/// Request weight per minute of the USD-M futures api
+const MAX_WEIGHT_PER_MINUTE: u32 = 2400;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BinanceAdapterConfig {
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    #[serde(default = "default_market_data_url")]
+    pub market_data_url: String,
+    /// Only needed for order submission and account queries
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub api_secret: Option<String>,
+    #[serde(default = "default_max_weight_per_minute")]
+    pub max_weight_per_minute: u32,
+}
+
+impl Default for BinanceAdapterConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_base_url(),
+            market_data_url: default_market_data_url(),
+            api_key: None,
+            api_secret: None,
+            max_weight_per_minute: default_max_weight_per_minute(),
+        }
+    }
+}
+
+fn default_base_url() -> String {
+    "https://fapi.binance.com".to_string()
+}
+
+fn default_market_data_url() -> String {
+    "wss://fstream.binance.com/ws".to_string()
+}
+
+fn default_max_weight_per_minute() -> u32 {
+    MAX_WEIGHT_PER_MINUTE
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VenuesConfig {
+    #[serde(default)]
+    pub venues: VenueAdaptersConfig,
+}
+
+/// Adapters shared by the ingestors and executors, components fall back to their own settings when a venue is
+/// missing.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VenueAdaptersConfig {
+    #[serde(default)]
+    pub binance: Option<BinanceAdapterConfig>,
+}
+
+impl VenueAdaptersConfig {
+    pub fn register(&self, adapters: &VenueAdapters) {
+        if let Some(c) = &self.binance {
+            adapters.insert(Arc::new(BinanceAdapter::from_config(c)));
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceError {
+    code: i64,
+    msg: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceBalance {
+    asset: String,
+    balance: Decimal,
+    available_balance: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinancePosition {
+    symbol: String,
+    position_amt: Decimal,
+    entry_price: Decimal,
+    un_realized_profit: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceExchangeInfo {
+    symbols: Vec<BinanceSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceSymbol {
+    symbol: String,
+    status: String,
+}
+
+/// Map the contract status of binance, delivery and settlement states close out the open positions.
+fn instrument_status(status: &str) -> InstrumentStatus {
+    match status {
+        "TRADING" => InstrumentStatus::Trading,
+        "PRE_DELIVERING" | "DELIVERING" | "PRE_SETTLE" | "SETTLING" => InstrumentStatus::Settling,
+        "DELIVERED" | "CLOSE" => InstrumentStatus::Delisted,
+        _ => InstrumentStatus::Halted,
+    }
+}
+
+/// Parse a response body, turning the error payloads of binance into adapter errors. Binance also answers some
+/// successful requests with a code, only negative codes are errors.
+fn parse_body<T: DeserializeOwned>(body: &str, rate_limiter: &RateLimiter) -> Result<T, VenueAdapterError> {
+    if let Ok(err) = serde_json::from_str::<BinanceError>(body) {
+        let msg = format!("{} ({})", err.msg, err.code);
+        match err.code {
+            c if c >= 0 => {}
+            // Too many requests, the ip is banned for at least a minute when we keep going
+            -1003 => {
+                rate_limiter.back_off(Duration::from_secs(60));
+                return Err(VenueAdapterError::RateLimited(Duration::from_secs(60)));
+            }
+            -2014 | -2015 | -1022 | -1002 => return Err(VenueAdapterError::Authentication(msg)),
+            _ => return Err(VenueAdapterError::Rejected(msg)),
+        }
+    }
+    serde_json::from_str::<T>(body).map_err(|e| VenueAdapterError::Parse(e.to_string()))
+}
+
+/// Adapter for the Binance USD-M futures api.
+#[derive(Debug, TypedBuilder)]
+pub struct BinanceAdapter {
+    client: Arc<BinanceHttpClient>,
+    #[builder(default = default_market_data_url())]
+    market_data_url: String,
+    #[builder(default = Arc::new(RateLimiter::builder().max_weight(MAX_WEIGHT_PER_MINUTE).build()))]
+    rate_limiter: Arc<RateLimiter>,
+    #[builder(default)]
+    symbols: SymbolMap,
+}
+
+impl BinanceAdapter {
+    pub const VENUE: &'static str = "binance";
+
+    pub fn from_config(config: &BinanceAdapterConfig) -> Self {
+        let credentials = match (&config.api_key, &config.api_secret) {
+            (Some(key), Some(secret)) => Some(Credentials::from_hmac(key.clone(), secret.clone())),
+            _ => None,
+        };
+        let client = BinanceHttpClient::builder()
+            .base_url(Url::from_str(&config.base_url).expect("Invalid URL for binance http client"))
+            .credentials(credentials)
+            .build();
+        Self::builder()
+            .client(Arc::new(client))
+            .market_data_url(config.market_data_url.clone())
+            .rate_limiter(Arc::new(
+                RateLimiter::builder().max_weight(config.max_weight_per_minute).build(),
+            ))
+            .build()
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: impl Into<Request>,
+        weight: u32,
+    ) -> Result<T, VenueAdapterError> {
+        self.rate_limiter.acquire(weight).await;
+        let res = self
+            .client
+            .send(request)
+            .await
+            .map_err(|e| VenueAdapterError::Connection(e.to_string()))?;
+        debug!("Binance response: {}", res.body);
+        parse_body(&res.body, &self.rate_limiter)
+    }
+}
+
+#[async_trait]
+impl VenueAdapter for BinanceAdapter {
+    fn venue(&self) -> &str {
+        Self::VENUE
+    }
+
+    fn symbols(&self) -> &SymbolMap {
+        &self.symbols
+    }
+
+    fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    fn market_data_url(&self) -> String {
+        self.market_data_url.clone()
+    }
+
+    fn market_data_stream(&self, instrument: &Instrument, channel: &str) -> String {
+        format!("{}@{}", self.venue_symbol(instrument).to_lowercase(), channel)
+    }
+
+    async fn place_order(&self, order: &VenueOrder) -> Result<(), VenueAdapterError> {
+        let builder = NewOrderRequest::builder()
+            .symbol(self.venue_symbol(&order.instrument))
+            .side(order.side.into())
+            .quantity(Some(order.quantity))
+            .new_client_order_id(Some(order.id.to_string()));
+        let request: Request = match order.order_type {
+            VenueOrderType::Market => builder.order_type(order.order_type.into()).build().into(),
+            VenueOrderType::Limit => builder
+                .order_type(order.order_type.into())
+                .price(Some(order.price))
+                .time_in_force(Some(order.time_in_force.into()))
+                .build()
+                .into(),
+            other => return Err(VenueAdapterError::Unsupported(format!("order type {}", other))),
+        };
+        self.send::<serde_json::Value>(request, 1).await?;
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self, instrument: &Instrument) -> Result<(), VenueAdapterError> {
+        let request = CancelOpenOrdersRequest::new(&self.venue_symbol(instrument));
+        self.send::<serde_json::Value>(request, 1).await?;
+        Ok(())
+    }
+
+    async fn balances(&self) -> Result<Vec<VenueBalance>, VenueAdapterError> {
+        let balances = self.send::<Vec<BinanceBalance>>(BalanceRequest::new(), 5).await?;
+        Ok(balances
+            .into_iter()
+            .map(|b| VenueBalance {
+                asset: b.asset,
+                balance: b.balance,
+                available: b.available_balance,
+            })
+            .collect())
+    }
+
+    async fn positions(&self) -> Result<Vec<VenuePosition>, VenueAdapterError> {
+        let positions = self.send::<Vec<BinancePosition>>(PositionInfoRequest::new(), 5).await?;
+        Ok(positions
+            .into_iter()
+            .filter(|p| !p.position_amt.is_zero())
+            .map(|p| VenuePosition {
+                venue_symbol: p.symbol,
+                quantity: p.position_amt,
+                entry_price: p.entry_price,
+                unrealized_pnl: p.un_realized_profit,
+            })
+            .collect())
+    }
+
+    async fn instrument_statuses(&self) -> Result<Vec<VenueInstrumentStatus>, VenueAdapterError> {
+        let info = self.send::<BinanceExchangeInfo>(ExchangeInfoRequest::new(), 1).await?;
+        Ok(info
+            .symbols
+            .into_iter()
+            .map(|s| VenueInstrumentStatus {
+                status: instrument_status(&s.status),
+                venue_symbol: s.symbol,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn adapter() -> BinanceAdapter {
+        BinanceAdapter::builder()
+            .client(Arc::new(BinanceHttpClient::builder().credentials(None).build()))
+            .build()
+    }
+
+    #[test]
+    fn test_market_data_streams() {
+        let adapter = adapter();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let streams = vec![
+            adapter.market_data_stream(&instrument, "aggTrade"),
+            adapter.market_data_stream(&instrument, "bookTicker"),
+        ];
+        assert_eq!(streams, vec!["btcusdt@aggTrade", "btcusdt@bookTicker"]);
+
+        adapter.symbols().insert(instrument.clone());
+        assert_eq!(adapter.instrument("btcusdt"), Some(instrument));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let limiter = RateLimiter::builder().max_weight(10).build();
+        let res = parse_body::<serde_json::Value>(r#"{"code":-2015,"msg":"Invalid API-key"}"#, &limiter);
+        assert!(matches!(res, Err(VenueAdapterError::Authentication(_))));
+        let res = parse_body::<serde_json::Value>(r#"{"code":-2019,"msg":"Margin is insufficient."}"#, &limiter);
+        assert!(matches!(res, Err(VenueAdapterError::Rejected(_))));
+        let res = parse_body::<serde_json::Value>(
+            r#"{"code":200,"msg":"The operation of cancel all open order is done."}"#,
+            &limiter,
+        );
+        assert!(res.is_ok());
+        let res = parse_body::<Vec<BinanceBalance>>("<html>", &limiter);
+        assert!(matches!(res, Err(VenueAdapterError::Parse(_))));
+    }
+
+    #[test]
+    fn test_rate_limit_error_backs_off() {
+        let limiter = RateLimiter::builder().max_weight(10).build();
+        let res = parse_body::<serde_json::Value>(r#"{"code":-1003,"msg":"Too many requests."}"#, &limiter);
+        assert!(matches!(res, Err(VenueAdapterError::RateLimited(_))));
+        assert!(limiter.try_acquire(1).is_err());
+    }
+
+    #[test]
+    fn test_parse_exchange_info() {
+        let limiter = RateLimiter::builder().max_weight(10).build();
+        let body = r#"{"timezone":"UTC","serverTime":1565246363776,"symbols":[{"symbol":"BTCUSDT","pair":"BTCUSDT","contractType":"PERPETUAL","status":"TRADING"},{"symbol":"BTCUSDT_250328","pair":"BTCUSDT","contractType":"CURRENT_QUARTER","status":"PRE_SETTLE"},{"symbol":"XEMUSDT","pair":"XEMUSDT","contractType":"PERPETUAL","status":"CLOSE"}]}"#;
+        let info = parse_body::<BinanceExchangeInfo>(body, &limiter).unwrap();
+        let statuses = info.symbols.iter().map(|s| instrument_status(&s.status)).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                InstrumentStatus::Trading,
+                InstrumentStatus::Settling,
+                InstrumentStatus::Delisted
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_balances() {
+        let limiter = RateLimiter::builder().max_weight(10).build();
+        let body = r#"[{"accountAlias":"SgsR","asset":"USDT","balance":"122.60","crossWalletBalance":"122.60","crossUnPnl":"0.00","availableBalance":"100.50","maxWithdrawAmount":"100.50","marginAvailable":true,"updateTime":1617939110373}]"#;
+        let balances = parse_body::<Vec<BinanceBalance>>(body, &limiter).unwrap();
+        assert_eq!(balances[0].asset, "USDT");
+        assert_eq!(balances[0].balance, dec!(122.60));
+        assert_eq!(balances[0].available_balance, dec!(100.50));
+    }
+}
@@ -55,6 +55,23 @@ impl BinanceHttpClient {
 
 impl BinanceHttpClient {
     pub async fn send<R: Into<Request>>(&self, request: R) -> Result<Response, BinanceHttpClientError> {
+        let req = self.prepare(request)?;
+
+        // Send request
+        let response = match self.client.execute(req).await {
+            Ok(response) => Ok(response),
+            Err(err) => Err(BinanceHttpClientError::Send(err)),
+        }?;
+
+        debug!("{}", response.status());
+        debug!("{:?}", response.headers());
+
+        let body = response.text().await?;
+        Ok(Response { body })
+    }
+
+    /// Build and sign the request exactly as `send` would, without sending it.
+    pub fn prepare<R: Into<Request>>(&self, request: R) -> Result<reqwest::Request, BinanceHttpClientError> {
         let Request {
             method,
             path,
@@ -117,18 +134,7 @@ impl BinanceHttpClient {
         // Build request
         let req = req_builder.build()?;
         debug!("BinanceHttpClient request: {:?}", req);
-
-        // Send request
-        let response = match self.client.execute(req).await {
-            Ok(response) => Ok(response),
-            Err(err) => Err(BinanceHttpClientError::Send(err)),
-        }?;
-
-        debug!("{}", response.status());
-        debug!("{:?}", response.headers());
-
-        let body = response.text().await?;
-        Ok(Response { body })
+        Ok(req)
     }
 }
 
@@ -148,3 +154,23 @@ impl From<Method> for reqwest::Method {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade::CancelOpenOrdersRequest;
+
+    #[test]
+    fn prepare_signs_without_sending_test() {
+        let client = BinanceHttpClient::with_url("http://127.0.0.1:1/")
+            .credentials(Credentials::from_hmac("api-key".to_owned(), "api-secret".to_owned()));
+
+        let req = client.prepare(CancelOpenOrdersRequest::new("BTCUSDT")).unwrap();
+
+        assert_eq!(req.method(), reqwest::Method::DELETE);
+        assert_eq!(req.url().path(), "/fapi/v1/allOpenOrders");
+        assert_eq!(req.headers().get("X-MBX-APIKEY").unwrap(), "api-key");
+        let params = req.url().query_pairs().map(|(k, _)| k.to_string()).collect::<Vec<_>>();
+        assert_eq!(params, vec!["symbol", "timestamp", "signature"]);
+    }
+}
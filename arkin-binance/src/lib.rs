@@ -1,9 +1,11 @@
+mod adapter;
 mod http;
 mod utils;
 mod ws;
 
 mod usdm;
 
+pub use adapter::{BinanceAdapter, BinanceAdapterConfig, VenueAdaptersConfig, VenuesConfig};
 pub use http::{BinanceHttpClient, BinanceHttpClientError, Credentials, Method, Request, Response};
 pub use ws::{BinanceWebSocketClient, Stream};
 
@@ -11,6 +13,7 @@ pub use usdm::market_stream::*;
 pub use usdm::*;
 
 pub mod prelude {
+    pub use crate::adapter::{BinanceAdapter, BinanceAdapterConfig, VenueAdaptersConfig, VenuesConfig};
     pub use crate::usdm::*;
     pub use crate::ws::{BinanceWebSocketClient, Stream, WebSocketState};
     pub use crate::{BinanceHttpClient, BinanceHttpClientError, Credentials, Method, Request, Response};
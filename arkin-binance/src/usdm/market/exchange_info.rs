@@ -0,0 +1,57 @@
+use crate::http::{Method, Request};
+
+/// `GET /fapi/v1/exchangeInfo`
+///
+/// Current exchange trading rules and symbol information.
+///
+/// Weight(IP): 1
+///
+/// # Example
+///
+/// ```
+/// use arkin_binance::market::ExchangeInfoRequest;
+///
+/// let request = ExchangeInfoRequest::new();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExchangeInfoRequest {}
+
+impl ExchangeInfoRequest {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl From<ExchangeInfoRequest> for Request {
+    fn from(_request: ExchangeInfoRequest) -> Request {
+        Request {
+            path: "fapi/v1/exchangeInfo".to_owned(),
+            method: Method::Get,
+            params: vec![],
+            credentials: None,
+            sign: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExchangeInfoRequest;
+    use crate::http::{Method, Request};
+
+    #[test]
+    fn market_exchange_info_convert_to_request_test() {
+        let request: Request = ExchangeInfoRequest::new().into();
+
+        assert_eq!(
+            request,
+            Request {
+                path: "fapi/v1/exchangeInfo".to_owned(),
+                credentials: None,
+                method: Method::Get,
+                params: vec![],
+                sign: false
+            }
+        );
+    }
+}
@@ -0,0 +1,3 @@
+mod exchange_info;
+
+pub use exchange_info::*;
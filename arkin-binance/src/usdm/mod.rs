@@ -1,4 +1,5 @@
 pub mod listen_key;
+pub mod market;
 pub mod market_stream;
 pub mod models;
 pub mod trade;
@@ -0,0 +1,134 @@
+use serde::Deserialize;
+use typed_builder::TypedBuilder;
+
+use crate::http::{Credentials, Method, Request};
+
+/// `GET /sapi/v1/account/apiRestrictions`
+///
+/// Get the permissions of the api key. This endpoint lives on the spot api (`https://api.binance.com`).
+///
+/// Weight(IP): 1
+///
+/// # Example
+///
+/// ```
+/// use arkin_binance::trade::ApiRestrictionsRequest;
+///
+/// let request = ApiRestrictionsRequest::new().recv_window(5000);
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ApiRestrictionsRequest {
+    #[builder(default)]
+    recv_window: Option<i64>,
+    #[builder(default)]
+    credentials: Option<Credentials>,
+}
+
+impl ApiRestrictionsRequest {
+    pub fn new() -> Self {
+        Self {
+            recv_window: None,
+            credentials: None,
+        }
+    }
+
+    pub fn recv_window(mut self, recv_window: i64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    pub fn credentials(mut self, credentials: &Credentials) -> Self {
+        self.credentials = Some(credentials.clone());
+        self
+    }
+}
+
+impl From<ApiRestrictionsRequest> for Request {
+    fn from(request: ApiRestrictionsRequest) -> Request {
+        let mut params = vec![];
+
+        if let Some(recv_window) = request.recv_window {
+            params.push(("recvWindow".to_owned(), recv_window.to_string()));
+        }
+
+        Request {
+            path: "sapi/v1/account/apiRestrictions".to_owned(),
+            method: Method::Get,
+            params,
+            credentials: request.credentials,
+            sign: true,
+        }
+    }
+}
+
+impl Default for ApiRestrictionsRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Permissions of an api key, fields missing in the response count as not permitted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiRestrictions {
+    pub ip_restrict: bool,
+    pub enable_reading: bool,
+    pub enable_futures: bool,
+    pub enable_withdrawals: bool,
+    pub enable_internal_transfer: bool,
+    pub permits_universal_transfer: bool,
+    pub enable_margin: bool,
+    pub enable_spot_and_margin_trading: bool,
+    pub enable_vanilla_options: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiRestrictions, ApiRestrictionsRequest};
+    use crate::http::{Credentials, Method, Request};
+
+    static API_KEY: &str = "api-key";
+    static API_SECRET: &str = "api-secret";
+
+    #[test]
+    fn trade_api_restrictions_convert_to_request_test() {
+        let credentials = Credentials::from_hmac(API_KEY.to_owned(), API_SECRET.to_owned());
+
+        let request: Request = ApiRestrictionsRequest::new().recv_window(5000).credentials(&credentials).into();
+
+        assert_eq!(
+            request,
+            Request {
+                path: "sapi/v1/account/apiRestrictions".to_owned(),
+                credentials: Some(credentials),
+                method: Method::Get,
+                params: vec![("recvWindow".to_owned(), "5000".to_string()),],
+                sign: true
+            }
+        );
+    }
+
+    #[test]
+    fn trade_api_restrictions_parse_response_test() {
+        let body = r#"{
+            "ipRestrict": true,
+            "createTime": 1698645219000,
+            "enableReading": true,
+            "enableWithdrawals": false,
+            "enableInternalTransfer": false,
+            "enableMargin": false,
+            "enableFutures": true,
+            "permitsUniversalTransfer": false,
+            "enableVanillaOptions": false,
+            "enableFixApiTrade": false,
+            "enableFixReadOnly": true,
+            "enableSpotAndMarginTrading": false,
+            "enablePortfolioMarginTrading": false
+        }"#;
+        let restrictions = serde_json::from_str::<ApiRestrictions>(body).unwrap();
+
+        assert!(restrictions.ip_restrict);
+        assert!(restrictions.enable_futures);
+        assert!(!restrictions.enable_withdrawals);
+    }
+}
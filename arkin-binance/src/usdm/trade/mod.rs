@@ -1,4 +1,5 @@
 mod account;
+mod api_restrictions;
 mod balance;
 mod cancel_open_orders;
 mod cancel_order;
@@ -6,8 +7,12 @@ mod open_orders;
 mod order;
 mod order_new;
 mod position_info;
+mod position_mode;
+mod sub_account_transfer;
+mod universal_transfer;
 
 pub use account::*;
+pub use api_restrictions::*;
 pub use balance::*;
 pub use cancel_open_orders::*;
 pub use cancel_order::*;
@@ -15,3 +20,6 @@ pub use open_orders::*;
 pub use order::*;
 pub use order_new::*;
 pub use position_info::*;
+pub use position_mode::*;
+pub use sub_account_transfer::*;
+pub use universal_transfer::*;
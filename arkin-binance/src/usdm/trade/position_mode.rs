@@ -0,0 +1,104 @@
+use serde::Deserialize;
+use typed_builder::TypedBuilder;
+
+use crate::http::{Credentials, Method, Request};
+
+/// `GET /fapi/v1/positionSide/dual`
+///
+/// Get the position mode of the account, `true` means hedge mode and `false` one-way mode.
+///
+/// Weight(IP): 30
+///
+/// # Example
+///
+/// ```
+/// use arkin_binance::trade::PositionModeRequest;
+///
+/// let request = PositionModeRequest::new().recv_window(5000);
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PositionModeRequest {
+    #[builder(default)]
+    recv_window: Option<i64>,
+    #[builder(default)]
+    credentials: Option<Credentials>,
+}
+
+impl PositionModeRequest {
+    pub fn new() -> Self {
+        Self {
+            recv_window: None,
+            credentials: None,
+        }
+    }
+
+    pub fn recv_window(mut self, recv_window: i64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    pub fn credentials(mut self, credentials: &Credentials) -> Self {
+        self.credentials = Some(credentials.clone());
+        self
+    }
+}
+
+impl From<PositionModeRequest> for Request {
+    fn from(request: PositionModeRequest) -> Request {
+        let mut params = vec![];
+
+        if let Some(recv_window) = request.recv_window {
+            params.push(("recvWindow".to_owned(), recv_window.to_string()));
+        }
+
+        Request {
+            path: "fapi/v1/positionSide/dual".to_owned(),
+            method: Method::Get,
+            params,
+            credentials: request.credentials,
+            sign: true,
+        }
+    }
+}
+
+impl Default for PositionModeRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionModeResponse {
+    pub dual_side_position: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PositionModeRequest, PositionModeResponse};
+    use crate::http::{Credentials, Method, Request};
+
+    static API_KEY: &str = "api-key";
+    static API_SECRET: &str = "api-secret";
+
+    #[test]
+    fn trade_position_mode_convert_to_request_test() {
+        let credentials = Credentials::from_hmac(API_KEY.to_owned(), API_SECRET.to_owned());
+
+        let request: Request = PositionModeRequest::new().recv_window(5000).credentials(&credentials).into();
+
+        assert_eq!(
+            request,
+            Request {
+                path: "fapi/v1/positionSide/dual".to_owned(),
+                credentials: Some(credentials),
+                method: Method::Get,
+                params: vec![("recvWindow".to_owned(), "5000".to_string()),],
+                sign: true
+            }
+        );
+
+        let response = serde_json::from_str::<PositionModeResponse>(r#"{"dualSidePosition": true}"#).unwrap();
+        assert!(response.dual_side_position);
+    }
+}
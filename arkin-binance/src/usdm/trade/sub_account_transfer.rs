@@ -0,0 +1,185 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use strum::Display;
+use typed_builder::TypedBuilder;
+
+use crate::http::{Credentials, Method, Request};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubAccountType {
+    Spot,
+    UsdtFuture,
+}
+
+/// `POST /sapi/v1/sub-account/universalTransfer`
+///
+/// Transfer an asset between the master account and its sub-accounts, or between two sub-accounts. Without an email
+/// the master account is the side of the transfer. Only the master account can send it.
+///
+/// Weight(IP): 360
+///
+/// # Example
+///
+/// ```
+/// use arkin_binance::trade::{SubAccountTransferRequest, SubAccountType};
+/// use rust_decimal_macros::dec;
+///
+/// let request = SubAccountTransferRequest::new(SubAccountType::Spot, SubAccountType::UsdtFuture, "USDT", dec!(100))
+///     .to_email("strategy@example.com");
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SubAccountTransferRequest {
+    #[builder(default)]
+    from_email: Option<String>,
+    #[builder(default)]
+    to_email: Option<String>,
+    from_account_type: SubAccountType,
+    to_account_type: SubAccountType,
+    #[builder(default)]
+    client_tran_id: Option<String>,
+    asset: String,
+    amount: Decimal,
+    #[builder(default)]
+    recv_window: Option<i64>,
+    #[builder(default)]
+    credentials: Option<Credentials>,
+}
+
+impl SubAccountTransferRequest {
+    pub fn new(
+        from_account_type: SubAccountType,
+        to_account_type: SubAccountType,
+        asset: &str,
+        amount: Decimal,
+    ) -> Self {
+        Self {
+            from_email: None,
+            to_email: None,
+            from_account_type,
+            to_account_type,
+            client_tran_id: None,
+            asset: asset.to_owned(),
+            amount,
+            recv_window: None,
+            credentials: None,
+        }
+    }
+
+    pub fn from_email(mut self, from_email: &str) -> Self {
+        self.from_email = Some(from_email.to_owned());
+        self
+    }
+
+    pub fn to_email(mut self, to_email: &str) -> Self {
+        self.to_email = Some(to_email.to_owned());
+        self
+    }
+
+    pub fn client_tran_id(mut self, client_tran_id: &str) -> Self {
+        self.client_tran_id = Some(client_tran_id.to_owned());
+        self
+    }
+
+    pub fn recv_window(mut self, recv_window: i64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    pub fn credentials(mut self, credentials: &Credentials) -> Self {
+        self.credentials = Some(credentials.clone());
+        self
+    }
+}
+
+impl From<SubAccountTransferRequest> for Request {
+    fn from(request: SubAccountTransferRequest) -> Request {
+        let mut params = vec![];
+
+        if let Some(from_email) = request.from_email {
+            params.push(("fromEmail".to_owned(), from_email));
+        }
+
+        if let Some(to_email) = request.to_email {
+            params.push(("toEmail".to_owned(), to_email));
+        }
+
+        params.push(("fromAccountType".to_owned(), request.from_account_type.to_string()));
+        params.push(("toAccountType".to_owned(), request.to_account_type.to_string()));
+
+        if let Some(client_tran_id) = request.client_tran_id {
+            params.push(("clientTranId".to_owned(), client_tran_id));
+        }
+
+        params.push(("asset".to_owned(), request.asset));
+        params.push(("amount".to_owned(), request.amount.to_string()));
+
+        if let Some(recv_window) = request.recv_window {
+            params.push(("recvWindow".to_owned(), recv_window.to_string()));
+        }
+
+        Request {
+            path: "sapi/v1/sub-account/universalTransfer".to_owned(),
+            method: Method::Post,
+            params,
+            credentials: request.credentials,
+            sign: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountTransferResponse {
+    pub tran_id: u64,
+    #[serde(default)]
+    pub client_tran_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::{SubAccountTransferRequest, SubAccountTransferResponse, SubAccountType};
+    use crate::http::{Credentials, Method, Request};
+
+    static API_KEY: &str = "api-key";
+    static API_SECRET: &str = "api-secret";
+
+    #[test]
+    fn trade_sub_account_transfer_convert_to_request_test() {
+        let credentials = Credentials::from_hmac(API_KEY.to_owned(), API_SECRET.to_owned());
+
+        let request: Request =
+            SubAccountTransferRequest::new(SubAccountType::UsdtFuture, SubAccountType::UsdtFuture, "USDT", dec!(100))
+                .to_email("strategy@example.com")
+                .client_tran_id("transfer-1")
+                .credentials(&credentials)
+                .into();
+
+        assert_eq!(
+            request,
+            Request {
+                path: "sapi/v1/sub-account/universalTransfer".to_owned(),
+                credentials: Some(credentials),
+                method: Method::Post,
+                params: vec![
+                    ("toEmail".to_owned(), "strategy@example.com".to_string()),
+                    ("fromAccountType".to_owned(), "USDT_FUTURE".to_string()),
+                    ("toAccountType".to_owned(), "USDT_FUTURE".to_string()),
+                    ("clientTranId".to_owned(), "transfer-1".to_string()),
+                    ("asset".to_owned(), "USDT".to_string()),
+                    ("amount".to_owned(), "100".to_string()),
+                ],
+                sign: true
+            }
+        );
+
+        let response = serde_json::from_str::<SubAccountTransferResponse>(
+            r#"{"tranId": 11945860693, "clientTranId": "transfer-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.tran_id, 11945860693);
+        assert_eq!(response.client_tran_id.as_deref(), Some("transfer-1"));
+    }
+}
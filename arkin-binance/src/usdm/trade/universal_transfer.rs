@@ -0,0 +1,130 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use strum::Display;
+use typed_builder::TypedBuilder;
+
+use crate::http::{Credentials, Method, Request};
+
+/// Wallets funds move between with a universal transfer, the spot wallet is `MAIN` and the usd-m futures wallet
+/// `UMFUTURE`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum UniversalTransferType {
+    MainUmfuture,
+    UmfutureMain,
+}
+
+/// `POST /sapi/v1/asset/transfer`
+///
+/// Transfer an asset between the wallets of the account, the api key needs universal transfer rights.
+///
+/// Weight(UID): 900
+///
+/// # Example
+///
+/// ```
+/// use arkin_binance::trade::{UniversalTransferRequest, UniversalTransferType};
+/// use rust_decimal_macros::dec;
+///
+/// let request = UniversalTransferRequest::new(UniversalTransferType::MainUmfuture, "USDT", dec!(100));
+/// ```
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct UniversalTransferRequest {
+    transfer_type: UniversalTransferType,
+    asset: String,
+    amount: Decimal,
+    #[builder(default)]
+    recv_window: Option<i64>,
+    #[builder(default)]
+    credentials: Option<Credentials>,
+}
+
+impl UniversalTransferRequest {
+    pub fn new(transfer_type: UniversalTransferType, asset: &str, amount: Decimal) -> Self {
+        Self {
+            transfer_type,
+            asset: asset.to_owned(),
+            amount,
+            recv_window: None,
+            credentials: None,
+        }
+    }
+
+    pub fn recv_window(mut self, recv_window: i64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    pub fn credentials(mut self, credentials: &Credentials) -> Self {
+        self.credentials = Some(credentials.clone());
+        self
+    }
+}
+
+impl From<UniversalTransferRequest> for Request {
+    fn from(request: UniversalTransferRequest) -> Request {
+        let mut params = vec![
+            ("type".to_owned(), request.transfer_type.to_string()),
+            ("asset".to_owned(), request.asset),
+            ("amount".to_owned(), request.amount.to_string()),
+        ];
+
+        if let Some(recv_window) = request.recv_window {
+            params.push(("recvWindow".to_owned(), recv_window.to_string()));
+        }
+
+        Request {
+            path: "sapi/v1/asset/transfer".to_owned(),
+            method: Method::Post,
+            params,
+            credentials: request.credentials,
+            sign: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferResponse {
+    pub tran_id: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::{TransferResponse, UniversalTransferRequest, UniversalTransferType};
+    use crate::http::{Credentials, Method, Request};
+
+    static API_KEY: &str = "api-key";
+    static API_SECRET: &str = "api-secret";
+
+    #[test]
+    fn trade_universal_transfer_convert_to_request_test() {
+        let credentials = Credentials::from_hmac(API_KEY.to_owned(), API_SECRET.to_owned());
+
+        let request: Request = UniversalTransferRequest::new(UniversalTransferType::UmfutureMain, "USDT", dec!(250.5))
+            .recv_window(5000)
+            .credentials(&credentials)
+            .into();
+
+        assert_eq!(
+            request,
+            Request {
+                path: "sapi/v1/asset/transfer".to_owned(),
+                credentials: Some(credentials),
+                method: Method::Post,
+                params: vec![
+                    ("type".to_owned(), "UMFUTURE_MAIN".to_string()),
+                    ("asset".to_owned(), "USDT".to_string()),
+                    ("amount".to_owned(), "250.5".to_string()),
+                    ("recvWindow".to_owned(), "5000".to_string()),
+                ],
+                sign: true
+            }
+        );
+
+        let response = serde_json::from_str::<TransferResponse>(r#"{"tranId": 13526853623}"#).unwrap();
+        assert_eq!(response.tran_id, 13526853623);
+    }
+}
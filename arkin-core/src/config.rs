@@ -1,25 +1,123 @@
-use config::{Config, Environment, File};
-use serde::de::DeserializeOwned;
-use std::env;
+use config::{Config, ConfigError, Environment, File};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, env, path::Path};
 use tracing::{debug, error};
 
+/// Config files of a component in the order they are merged, e.g. `sim`, `sim_persistence`, ...
+const COMPONENTS: [&str; 4] = ["", "_persistence", "_ingestors", "_insights"];
+
+/// Keys with these words in them are masked when the resolved config is dumped.
+const SECRET_KEYS: [&str; 5] = ["password", "secret", "api_key", "token", "private_key"];
+
+/// Where the config of a deployment comes from. Layers are merged from low to high precedence:
+///
+/// 1. `base*`: shared by all profiles
+/// 2. `<profile>*`: the environment, e.g. `live`, `sim` or `test`
+/// 3. `instances/<instance>`: overrides of a single deployment
+/// 4. `<profile>_secrets`
+/// 5. `ARKIN_*` environment variables, nested keys are separated by `__`, e.g. `ARKIN_DATABASE__HOST`
+///
+/// Every file is optional, so a profile only holds what differs from the base. A single file replaces the file layers
+/// when set, containers then only need that file and the environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigProfile {
+    pub dir: String,
+    pub profile: String,
+    pub instance: Option<String>,
+    pub file: Option<String>,
+}
+
+/// A config file and whether it exists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigLayer {
+    pub path: String,
+    pub found: bool,
+}
+
+impl ConfigProfile {
+    /// Profile from `CONFIG_DIR`, `RUN_MODE`, `CONFIG_INSTANCE` and `CONFIG_FILE`.
+    pub fn from_env() -> Self {
+        Self {
+            dir: env::var("CONFIG_DIR").unwrap_or_else(|_| ".".into()),
+            profile: env::var("RUN_MODE").unwrap_or_else(|_| "dev".into()),
+            instance: env::var("CONFIG_INSTANCE").ok().filter(|i| !i.is_empty()),
+            file: env::var("CONFIG_FILE").ok().filter(|f| !f.is_empty()),
+        }
+    }
+
+    /// Config file names without extension, from low to high precedence.
+    fn files(&self) -> Vec<String> {
+        if let Some(file) = &self.file {
+            return vec![file.clone()];
+        }
+        let mut files = COMPONENTS
+            .iter()
+            .map(|c| format!("{}/base{}", self.dir, c))
+            .chain(COMPONENTS.iter().map(|c| format!("{}/{}{}", self.dir, self.profile, c)))
+            .collect::<Vec<_>>();
+        if let Some(instance) = &self.instance {
+            files.push(format!("{}/instances/{}", self.dir, instance));
+        }
+        files.push(format!("{}/{}_secrets", self.dir, self.profile));
+        files
+    }
+
+    /// The config files in the order they are merged.
+    pub fn layers(&self) -> Vec<ConfigLayer> {
+        self.files()
+            .into_iter()
+            .map(|file| {
+                let found = Path::new(&file).is_file()
+                    || ["yml", "yaml", "json", "toml"]
+                        .iter()
+                        .any(|ext| Path::new(&format!("{}.{}", file, ext)).exists());
+                ConfigLayer { path: file, found }
+            })
+            .collect()
+    }
+
+    pub fn build(&self) -> Result<Config, ConfigError> {
+        let builder = self.files().iter().fold(Config::builder(), |builder, file| {
+            builder.add_source(File::with_name(file).required(false))
+        });
+        let environment = Environment::with_prefix("ARKIN").prefix_separator("_").separator("__");
+        builder.add_source(environment).build()
+    }
+
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        debug!("Loading {} configuration from: {}", self.profile, self.dir);
+        self.build()?.try_deserialize::<T>()
+    }
+
+    /// The merged config of all layers as JSON, with secrets masked unless asked for.
+    pub fn resolved(&self, show_secrets: bool) -> Result<serde_json::Value, ConfigError> {
+        let mut value = self.build()?.try_deserialize::<serde_json::Value>()?;
+        if !show_secrets {
+            mask_secrets(&mut value);
+        }
+        Ok(value)
+    }
+}
+
+fn mask_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|s| key.contains(s)) && !value.is_object() {
+                    *value = serde_json::Value::String("***".into());
+                } else {
+                    mask_secrets(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(mask_secrets),
+        _ => {}
+    }
+}
+
 pub fn load<T: DeserializeOwned>() -> T {
-    let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "dev".into());
-    let config_dir = env::var("CONFIG_DIR").unwrap_or_else(|_| ".".into());
-
-    let config = Config::builder()
-        .add_source(File::with_name(&format!("{}/{}", config_dir, run_mode)).required(false))
-        .add_source(File::with_name(&format!("{}/{}_persistence", config_dir, run_mode)).required(false))
-        .add_source(File::with_name(&format!("{}/{}_ingestors", config_dir, run_mode)).required(false))
-        .add_source(File::with_name(&format!("{}/{}_insights", config_dir, run_mode)).required(false))
-        .add_source(File::with_name(&format!("{}/{}_secrets", config_dir, run_mode)).required(false))
-        .add_source(Environment::with_prefix("ARKIN"))
-        .build()
-        .expect("Failed to build configuration");
-
-    debug!("Loading configuration from: {}", config_dir);
-
-    match config.try_deserialize::<T>() {
+    match ConfigProfile::from_env().load::<T>() {
         Ok(c) => c,
         Err(e) => {
             error!("Configuration error: {:?}", e);
@@ -27,3 +125,185 @@ pub fn load<T: DeserializeOwned>() -> T {
         }
     }
 }
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub logging: LogConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Default level for all targets, RUST_LOG directives take precedence
+    pub level: Option<String>,
+    /// Level overrides per target, e.g. `sqlx: warn`
+    pub targets: HashMap<String, String>,
+    pub format: LogFormat,
+    pub ansi: bool,
+    pub thread_ids: bool,
+    pub stdout: bool,
+    /// Write the console sink to stderr, keeps stdout free for command output
+    pub stderr: bool,
+    pub file: Option<LogFileConfig>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: None,
+            targets: HashMap::new(),
+            format: LogFormat::Compact,
+            ansi: true,
+            thread_ids: true,
+            stdout: true,
+            stderr: false,
+            file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Compact,
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogFileConfig {
+    pub directory: String,
+    #[serde(default = "default_log_prefix")]
+    pub prefix: String,
+    #[serde(default = "default_log_rotation")]
+    pub rotation: LogRotation,
+    /// Keep at most this many rotated files, older ones are deleted
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    #[serde(default = "default_log_file_format")]
+    pub format: LogFormat,
+}
+
+fn default_log_prefix() -> String {
+    "arkin".into()
+}
+
+fn default_log_rotation() -> LogRotation {
+    LogRotation::Daily
+}
+
+fn default_log_file_format() -> LogFormat {
+    LogFormat::Json
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+/// Bucket or directory for large artifacts, like `s3://bucket/prefix`, `gs://bucket`, `az://container` or a local
+/// path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectStorageConfig {
+    pub url: String,
+    /// Client options of the backend, e.g. `aws_endpoint` and `aws_allow_http` for MinIO. Credentials are read from
+    /// the environment of the backend when left out.
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct TestConfig {
+        venue: String,
+        batch_size: u64,
+        database: TestDatabase,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestDatabase {
+        host: String,
+        password: String,
+    }
+
+    #[test]
+    fn test_profile_layering() {
+        let dir = env::temp_dir().join(format!("arkin-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("instances")).unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.join(name), content).unwrap();
+        write(
+            "base.yml",
+            "venue: binance\nbatch_size: 100\ndatabase:\n  host: localhost\n  password: base\n",
+        );
+        write("sim.yml", "batch_size: 1000\n");
+        write("instances/sim-2.yml", "database:\n  host: sim-2.internal\n");
+        write("sim_secrets.yml", "database:\n  password: hunter2\n");
+
+        let profile = ConfigProfile {
+            dir: dir.display().to_string(),
+            profile: "sim".into(),
+            instance: Some("sim-2".into()),
+            file: None,
+        };
+        let config = profile.load::<TestConfig>().unwrap();
+        assert_eq!(config.venue, "binance");
+        assert_eq!(config.batch_size, 1000);
+        assert_eq!(config.database.host, "sim-2.internal");
+        assert_eq!(config.database.password, "hunter2");
+
+        let layers = profile.layers();
+        assert_eq!(layers.iter().filter(|l| l.found).count(), 4);
+        assert!(layers.last().unwrap().path.ends_with("sim_secrets"));
+
+        let resolved = profile.resolved(false).unwrap();
+        assert_eq!(resolved["database"]["password"], "***");
+        assert_eq!(resolved["database"]["host"], "sim-2.internal");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestSingleFile {
+        single_file: TestSingleFileSection,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestSingleFileSection {
+        venue: String,
+        batch_size: u64,
+    }
+
+    #[test]
+    fn test_single_file() {
+        let dir = env::temp_dir().join(format!("arkin-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("agent.yml");
+        std::fs::write(&file, "single_file:\n  venue: binance\n  batch_size: 100\n").unwrap();
+        // The base layer would fail the test if it was merged
+        std::fs::write(dir.join("base.yml"), "single_file:\n  venue: okx\n").unwrap();
+        env::set_var("ARKIN_SINGLE_FILE__BATCH_SIZE", "5");
+
+        let profile = ConfigProfile {
+            dir: dir.display().to_string(),
+            profile: "live".into(),
+            instance: None,
+            file: Some(file.display().to_string()),
+        };
+        let config = profile.load::<TestSingleFile>().unwrap();
+        assert_eq!(config.single_file.venue, "binance");
+        assert_eq!(config.single_file.batch_size, 5);
+        assert_eq!(profile.layers().len(), 1);
+        assert!(profile.layers()[0].found);
+
+        env::remove_var("ARKIN_SINGLE_FILE__BATCH_SIZE");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
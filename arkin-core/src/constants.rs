@@ -19,6 +19,16 @@ pub static TICK_ASK_PRICE_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc
 pub static TICK_ASK_QUANTITY_FEATURE_ID: LazyLock<FeatureId> =
     LazyLock::new(|| Arc::new("tick_ask_quantity".to_string()));
 
+// Bar features (the names match the default OHLCV feature outputs)
+pub static BAR_OPEN_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("open".to_string()));
+pub static BAR_HIGH_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("high".to_string()));
+pub static BAR_LOW_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("low".to_string()));
+pub static BAR_CLOSE_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("close".to_string()));
+pub static BAR_VOLUME_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("volume".to_string()));
+pub static BAR_NOTIONAL_VOLUME_FEATURE_ID: LazyLock<FeatureId> =
+    LazyLock::new(|| Arc::new("notional_volume".to_string()));
+pub static BAR_TRADE_COUNT_FEATURE_ID: LazyLock<FeatureId> = LazyLock::new(|| Arc::new("trade_count".to_string()));
+
 pub static RAW_FEATURE_IDS: LazyLock<Vec<FeatureId>> = LazyLock::new(|| {
     vec![
         TRADE_PRICE_FEATURE_ID.clone(),
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use tracing::warn;
+
+/// Coarse classification of service errors, used to decide how the engine reacts to a failing service.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Likely to go away when retrying, like network or database connection problems
+    Transient,
+    /// A single event or request could not be processed, the service itself is healthy
+    InvalidInput,
+    /// The service can't continue, like failed authentication or a broken configuration
+    Fatal,
+}
+
+pub trait ClassifyError: std::error::Error {
+    fn class(&self) -> ErrorClass;
+}
+
+/// Handle the result of a single event in the loop of a service. Transient errors and invalid input only skip the
+/// event, a fatal error stops the service so its supervisor restarts it or halts trading. Restarting for the error of
+/// one event would subscribe again and lose the events published in between.
+pub fn skip_failed_event<T, E: ClassifyError>(service: &str, res: Result<T, E>) -> Result<Option<T>, E> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.class() == ErrorClass::Fatal => Err(e),
+        Err(e) => {
+            warn!("Service {} skipped an event after a {} error: {}", service, e.class(), e);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("test error")]
+    struct TestError(ErrorClass);
+
+    impl ClassifyError for TestError {
+        fn class(&self) -> ErrorClass {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_skip_failed_event() {
+        assert_eq!(skip_failed_event::<_, TestError>("test", Ok(1)).unwrap(), Some(1));
+        let res = skip_failed_event::<(), _>("test", Err(TestError(ErrorClass::Transient)));
+        assert!(res.unwrap().is_none());
+        let res = skip_failed_event::<(), _>("test", Err(TestError(ErrorClass::InvalidInput)));
+        assert!(res.unwrap().is_none());
+        let res = skip_failed_event::<(), _>("test", Err(TestError(ErrorClass::Fatal)));
+        assert!(res.is_err());
+    }
+}
@@ -1,25 +1,31 @@
 mod config;
 mod constants;
+mod errors;
 mod logging;
 mod models;
 mod pubsub;
 mod types;
 mod utils;
+mod venue;
 
-pub use config::load;
+pub use config::{load, ConfigLayer, ConfigProfile, ObjectStorageConfig};
+pub use errors::*;
 pub use models::*;
 pub use pubsub::*;
 pub use types::{FeatureId, Maturity, Notional, Price, Quantity, Weight};
+pub use venue::*;
 
 pub mod test_utils;
 
 pub mod prelude {
     pub use crate::config::*;
     pub use crate::constants::*;
+    pub use crate::errors::*;
     pub use crate::logging::*;
     pub use crate::models::*;
     pub use crate::pubsub::*;
     pub use crate::test_utils::*;
     pub use crate::types::*;
     pub use crate::utils::*;
+    pub use crate::venue::*;
 }
@@ -1,18 +1,51 @@
-use tracing::subscriber::set_global_default;
-use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+use std::env;
 
+use tracing::{subscriber::set_global_default, warn};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{
+    fmt::{format::FmtSpan, MakeWriter},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+use crate::config::{load, LogConfig, LogFileConfig, LogFormat, LogRotation, LoggingConfig};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Initialize tracing from the `logging` section of the configuration. Without any configuration this logs compact
+/// lines to stdout filtered by RUST_LOG.
+///
+/// The format and default level can be overridden with the `ARKIN_LOG_FORMAT` and `ARKIN_LOG_LEVEL` environment
+/// variables, `ARKIN_LOG_DIR` enables the file sink.
 pub fn init_tracing() {
-    tracing_subscriber::fmt::Subscriber::builder()
-        .with_span_events(FmtSpan::CLOSE)
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_thread_ids(true)
-        .with_target(false)
-        .with_span_events(FmtSpan::NONE)
-        .with_line_number(false)
-        .with_file(false)
-        .with_ansi(true)
-        .compact()
-        .init();
+    let config = apply_env_overrides(load::<LoggingConfig>().logging);
+    init_tracing_from_config(&config);
+    warn_unknown_format();
+}
+
+/// Like [`init_tracing`] with the console sink on stderr, for commands printing their result to stdout.
+pub fn init_tracing_stderr() {
+    let mut config = apply_env_overrides(load::<LoggingConfig>().logging);
+    config.stderr = true;
+    init_tracing_from_config(&config);
+    warn_unknown_format();
+}
+
+pub fn init_tracing_from_config(config: &LogConfig) {
+    let directives = filter_directives(config, env::var("RUST_LOG").ok().as_deref());
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    if config.stdout && config.stderr {
+        layers.push(fmt_layer(config.format, config, config.ansi, std::io::stderr, &directives));
+    } else if config.stdout {
+        layers.push(fmt_layer(config.format, config, config.ansi, std::io::stdout, &directives));
+    }
+    if let Some(file) = &config.file {
+        layers.push(fmt_layer(file.format, config, false, file_appender(file), &directives));
+    }
+
+    tracing_subscriber::registry().with(layers).init();
 }
 
 pub fn init_test_tracing() {
@@ -28,3 +61,189 @@ pub fn init_test_tracing() {
         .finish();
     set_global_default(subscriber).expect("Failed to set global default subscriber");
 }
+
+fn fmt_layer<W>(format: LogFormat, config: &LogConfig, ansi: bool, writer: W, directives: &str) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_span_events(FmtSpan::NONE)
+        .with_thread_ids(config.thread_ids)
+        .with_line_number(false)
+        .with_file(false);
+    let filter = EnvFilter::new(directives);
+
+    match format {
+        LogFormat::Compact => layer.with_target(false).with_ansi(ansi).compact().with_filter(filter).boxed(),
+        LogFormat::Pretty => layer.with_target(true).with_ansi(ansi).pretty().with_filter(filter).boxed(),
+        // Targets and span fields are kept so log shippers can index on them
+        LogFormat::Json => layer
+            .with_target(true)
+            .with_ansi(false)
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_filter(filter)
+            .boxed(),
+    }
+}
+
+fn file_appender(config: &LogFileConfig) -> RollingFileAppender {
+    let rotation = match config.rotation {
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let mut builder = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(&config.prefix)
+        .filename_suffix("log");
+    if let Some(max_files) = config.max_files {
+        builder = builder.max_log_files(max_files);
+    }
+    builder.build(&config.directory).expect("Failed to create log file appender")
+}
+
+fn parse_format(format: &str) -> Option<LogFormat> {
+    match format.to_lowercase().as_str() {
+        "compact" => Some(LogFormat::Compact),
+        "pretty" => Some(LogFormat::Pretty),
+        "json" => Some(LogFormat::Json),
+        _ => None,
+    }
+}
+
+fn apply_env_overrides(mut config: LogConfig) -> LogConfig {
+    if let Some(format) = env::var("ARKIN_LOG_FORMAT").ok().as_deref().and_then(parse_format) {
+        config.format = format;
+    }
+    if let Ok(level) = env::var("ARKIN_LOG_LEVEL") {
+        config.level = Some(level);
+    }
+    if let Ok(directory) = env::var("ARKIN_LOG_DIR") {
+        match config.file.as_mut() {
+            Some(file) => file.directory = directory,
+            None => {
+                config.file = Some(LogFileConfig {
+                    directory,
+                    prefix: "arkin".into(),
+                    rotation: LogRotation::Daily,
+                    max_files: None,
+                    format: LogFormat::Json,
+                })
+            }
+        }
+    }
+    config
+}
+
+/// The overrides are applied before there is a subscriber, so an unknown format is only reported once tracing is up.
+fn warn_unknown_format() {
+    if let Ok(format) = env::var("ARKIN_LOG_FORMAT") {
+        if parse_format(&format).is_none() {
+            warn!("Ignoring unknown log format {}", format);
+        }
+    }
+}
+
+/// Combine the configured level and target overrides with the RUST_LOG directives. A RUST_LOG directive replaces
+/// the configured one for the same target.
+fn filter_directives(config: &LogConfig, rust_log: Option<&str>) -> String {
+    let mut directives: Vec<(Option<String>, String)> = Vec::new();
+    if let Some(level) = &config.level {
+        directives.push((None, level.clone()));
+    }
+    let mut targets = config.targets.iter().collect::<Vec<_>>();
+    targets.sort();
+    for (target, level) in targets {
+        directives.push((Some(target.clone()), level.clone()));
+    }
+
+    for directive in rust_log
+        .unwrap_or_default()
+        .split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+    {
+        let (target, level) = match directive.split_once('=') {
+            Some((target, level)) => (Some(target.to_string()), level.to_string()),
+            None if directive.parse::<tracing::Level>().is_ok() || directive.eq_ignore_ascii_case("off") => {
+                (None, directive.to_string())
+            }
+            // A bare target enables all levels for it
+            None => (Some(directive.to_string()), "trace".to_string()),
+        };
+        directives.retain(|(t, _)| *t != target);
+        directives.push((target, level));
+    }
+
+    directives
+        .into_iter()
+        .map(|(target, level)| match target {
+            Some(target) => format!("{}={}", target, level),
+            None => level,
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn config(level: Option<&str>, targets: &[(&str, &str)]) -> LogConfig {
+        LogConfig {
+            level: level.map(|l| l.to_string()),
+            targets: targets
+                .iter()
+                .map(|(t, l)| (t.to_string(), l.to_string()))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_config_directives() {
+        let config = config(Some("info"), &[("sqlx", "warn"), ("arkin_persistence", "debug")]);
+        assert_eq!(filter_directives(&config, None), "info,arkin_persistence=debug,sqlx=warn");
+        assert_eq!(filter_directives(&LogConfig::default(), None), "");
+    }
+
+    #[test]
+    fn test_rust_log_overrides_config() {
+        let config = config(Some("info"), &[("sqlx", "warn"), ("arkin_persistence", "debug")]);
+        assert_eq!(
+            filter_directives(&config, Some("debug, sqlx=error,arkin_binance")),
+            "arkin_persistence=debug,debug,sqlx=error,arkin_binance=trace"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_config() {
+        let config: LoggingConfig = serde_json::from_value(serde_json::json!({
+            "logging": {
+                "level": "info",
+                "format": "json",
+                "targets": { "sqlx": "warn" },
+                "file": { "directory": "/var/log/arkin", "rotation": "hourly", "max_files": 24 }
+            }
+        }))
+        .unwrap();
+        let logging = config.logging;
+        assert_eq!(logging.format, LogFormat::Json);
+        assert!(logging.stdout);
+        let file = logging.file.unwrap();
+        assert_eq!(file.prefix, "arkin");
+        assert_eq!(file.rotation, LogRotation::Hourly);
+        assert_eq!(file.max_files, Some(24));
+        assert_eq!(file.format, LogFormat::Json);
+
+        let empty: LoggingConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(empty.logging.format, LogFormat::Compact);
+        assert!(empty.logging.file.is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::{Asset, Portfolio, Transaction, TransactionType};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Spot,
+    UsdmFutures,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AccountTransferStatus {
+    /// The venue moved the funds
+    Executed,
+    /// Stopped by the transfer limits before it was sent
+    Rejected,
+    /// Sent but refused by the venue or lost on the way
+    Failed,
+}
+
+/// Request to move funds between the wallets of the venue account, or between the account and its sub-accounts.
+/// Published by the operator or the allocation, executed by the transfer manager after its limits are checked.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct AccountTransfer {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    pub asset: Arc<Asset>,
+    pub quantity: Decimal,
+    pub from: AccountType,
+    pub to: AccountType,
+    /// Sub-account the funds are taken from, the master account without one
+    #[builder(default)]
+    pub from_sub_account: Option<String>,
+    /// Sub-account the funds are given to, the master account without one
+    #[builder(default)]
+    pub to_sub_account: Option<String>,
+    /// Who asked for the transfer, like `operator` or `allocation`
+    pub requested_by: String,
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl AccountTransfer {
+    pub fn is_sub_account(&self) -> bool {
+        self.from_sub_account.is_some() || self.to_sub_account.is_some()
+    }
+
+    /// Signed quantity the transfer adds to the futures wallet of the master account, the one the portfolio trades
+    /// with.
+    pub fn futures_quantity(&self) -> Decimal {
+        let into = self.to == AccountType::UsdmFutures && self.to_sub_account.is_none();
+        let out = self.from == AccountType::UsdmFutures && self.from_sub_account.is_none();
+        match (into, out) {
+            (true, false) => self.quantity,
+            (false, true) => -self.quantity,
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+impl EventTypeOf for AccountTransfer {
+    fn event_type() -> EventType {
+        EventType::AccountTransfer
+    }
+}
+
+impl From<Arc<AccountTransfer>> for Event {
+    fn from(event: Arc<AccountTransfer>) -> Self {
+        Event::AccountTransfer(event)
+    }
+}
+
+impl fmt::Display for AccountTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} from {}{} to {}{} requested_by={} reason={}",
+            self.quantity,
+            self.asset.symbol,
+            self.from,
+            self.from_sub_account.as_ref().map(|s| format!("@{}", s)).unwrap_or_default(),
+            self.to,
+            self.to_sub_account.as_ref().map(|s| format!("@{}", s)).unwrap_or_default(),
+            self.requested_by,
+            self.reason
+        )
+    }
+}
+
+/// Outcome of an [`AccountTransfer`], published for every request so each transfer leaves an audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct AccountTransferUpdate {
+    pub event_time: OffsetDateTime,
+    pub transfer: Arc<AccountTransfer>,
+    pub status: AccountTransferStatus,
+    /// Id the venue gave the transfer
+    #[builder(default)]
+    pub venue_transfer_id: Option<String>,
+    /// Why the transfer was rejected or failed
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl AccountTransferUpdate {
+    /// Ledger entry of an executed transfer, None if no funds moved in or out of the futures wallet.
+    pub fn to_transaction(&self) -> Option<Transaction> {
+        let quantity = self.transfer.futures_quantity();
+        if self.status != AccountTransferStatus::Executed || quantity.is_zero() {
+            return None;
+        }
+        let transaction = Transaction::builder()
+            .event_time(self.event_time)
+            .transaction_group_id(self.transfer.id)
+            .portfolio(self.transfer.portfolio.clone())
+            .asset(Some(self.transfer.asset.clone()))
+            .instrument(None)
+            .transaction_type(TransactionType::Transfer)
+            .price(None)
+            .quantity(quantity)
+            .total_value(quantity)
+            .build();
+        Some(transaction)
+    }
+}
+
+impl EventTypeOf for AccountTransferUpdate {
+    fn event_type() -> EventType {
+        EventType::AccountTransferUpdate
+    }
+}
+
+impl From<Arc<AccountTransferUpdate>> for Event {
+    fn from(event: Arc<AccountTransferUpdate>) -> Self {
+        Event::AccountTransferUpdate(event)
+    }
+}
+
+impl fmt::Display for AccountTransferUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} venue_id={} reason={}",
+            self.status,
+            self.transfer,
+            self.venue_transfer_id.as_deref().unwrap_or("-"),
+            self.reason
+        )
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use serde::Serialize;
+use sqlx::Type;
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::constants;
+
+#[derive(Clone, Display, Copy, PartialEq, Eq, Debug, Type, Serialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "annotation_target", rename_all = "snake_case")]
+pub enum AnnotationTarget {
+    /// An execution or venue order
+    Order,
+    /// A fill of one of our orders
+    Trade,
+    /// A period of time, of one instrument or all of them
+    Range,
+}
+
+/// Note an operator attached to an order, a trade or a period, like a manual intervention or an exchange outage.
+/// Reports list the annotations of their period, and leave out the periods marked as excluded.
+#[derive(Debug, Clone, TypedBuilder, PartialEq, Eq, Serialize)]
+pub struct Annotation {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub target: AnnotationTarget,
+    /// Id of the order or fill, none for ranges
+    #[builder(default)]
+    pub target_id: Option<Uuid>,
+    /// Symbol of the instrument a range is limited to, all instruments without it
+    #[builder(default)]
+    pub instrument: Option<String>,
+    pub start: OffsetDateTime,
+    #[builder(default = start)]
+    pub end: OffsetDateTime,
+    /// Short label to group annotations by, like `manual_intervention` or `exchange_outage`
+    pub tag: String,
+    #[builder(default)]
+    pub note: String,
+    /// Leave the period out of performance analysis instead of only flagging it
+    #[builder(default)]
+    pub exclude: bool,
+    pub author: String,
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub created_at: OffsetDateTime,
+}
+
+impl Annotation {
+    /// True if the annotation covers part of the range.
+    pub fn overlaps(&self, from: OffsetDateTime, till: OffsetDateTime) -> bool {
+        self.start <= till && self.end >= from
+    }
+
+    /// True if analysis should leave out the instrument at the given time.
+    pub fn excludes(&self, symbol: &str, time: OffsetDateTime) -> bool {
+        self.exclude
+            && self.target == AnnotationTarget::Range
+            && self.instrument.as_deref().is_none_or(|s| s == symbol)
+            && self.start <= time
+            && time <= self.end
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let start = self.start.format(constants::TIMESTAMP_FORMAT).expect("Failed to format start");
+        let end = self.end.format(constants::TIMESTAMP_FORMAT).expect("Failed to format end");
+        write!(
+            f,
+            "id={} target={} tag={} from={} till={} author={}",
+            self.id, self.target, self.tag, start, end, self.author
+        )?;
+        if let Some(target_id) = &self.target_id {
+            write!(f, " target_id={}", target_id)?;
+        }
+        if let Some(instrument) = &self.instrument {
+            write!(f, " instrument={}", instrument)?;
+        }
+        if self.exclude {
+            write!(f, " excluded")?;
+        }
+        if !self.note.is_empty() {
+            write!(f, " note=\"{}\"", self.note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Report together with the annotations of its period, the excluded periods were left out of the report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotatedReport<T> {
+    pub report: T,
+    pub annotations: Vec<Annotation>,
+}
+
+impl<T: fmt::Display> fmt::Display for AnnotatedReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.report)?;
+        if !self.annotations.is_empty() {
+            write!(f, "\nAnnotations:")?;
+            for annotation in &self.annotations {
+                write!(f, "\n  {}", annotation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_excludes() {
+        let outage = Annotation::builder()
+            .target(AnnotationTarget::Range)
+            .instrument(Some("perp-btc-usdt@binance".into()))
+            .start(datetime!(2025-01-01 10:00 UTC))
+            .end(datetime!(2025-01-01 11:00 UTC))
+            .tag("exchange_outage".into())
+            .exclude(true)
+            .author("ops".into())
+            .build();
+        assert!(outage.excludes("perp-btc-usdt@binance", datetime!(2025-01-01 10:30 UTC)));
+        assert!(!outage.excludes("perp-eth-usdt@binance", datetime!(2025-01-01 10:30 UTC)));
+        assert!(!outage.excludes("perp-btc-usdt@binance", datetime!(2025-01-01 11:01 UTC)));
+        assert!(outage.overlaps(datetime!(2025-01-01 11:00 UTC), datetime!(2025-01-02 00:00 UTC)));
+        assert!(!outage.overlaps(datetime!(2025-01-01 11:01 UTC), datetime!(2025-01-02 00:00 UTC)));
+
+        // Orders and flagged ranges are only listed
+        let flagged = Annotation {
+            exclude: false,
+            ..outage.clone()
+        };
+        assert!(!flagged.excludes("perp-btc-usdt@binance", datetime!(2025-01-01 10:30 UTC)));
+        let order = Annotation::builder()
+            .target(AnnotationTarget::Order)
+            .target_id(Some(Uuid::new_v4()))
+            .start(datetime!(2025-01-01 10:30 UTC))
+            .tag("manual_intervention".into())
+            .exclude(true)
+            .author("ops".into())
+            .build();
+        assert_eq!(order.end, order.start);
+        assert!(!order.excludes("perp-btc-usdt@binance", datetime!(2025-01-01 10:30 UTC)));
+    }
+}
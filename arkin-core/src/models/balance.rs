@@ -0,0 +1,65 @@
+use std::{fmt, sync::Arc};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf, Quantity};
+
+use super::{Asset, Portfolio};
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Balance {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub asset: Arc<Asset>,
+    pub quantity: Quantity,
+}
+
+impl EventTypeOf for Balance {
+    fn event_type() -> EventType {
+        EventType::Balance
+    }
+}
+
+impl From<Arc<Balance>> for Event {
+    fn from(balance: Arc<Balance>) -> Self {
+        Event::Balance(balance)
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "asset={} quantity={}", self.asset, self.quantity)
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct BalanceUpdate {
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    pub asset: Arc<Asset>,
+    pub quantity: Quantity,
+}
+
+impl EventTypeOf for BalanceUpdate {
+    fn event_type() -> EventType {
+        EventType::BalanceUpdate
+    }
+}
+
+impl From<Arc<BalanceUpdate>> for Event {
+    fn from(update: Arc<BalanceUpdate>) -> Self {
+        Event::BalanceUpdate(update)
+    }
+}
+
+impl fmt::Display for BalanceUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "event_time={} portfolio={} asset={} quantity={}",
+            self.event_time, self.portfolio, self.asset, self.quantity
+        )
+    }
+}
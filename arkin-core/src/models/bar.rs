@@ -0,0 +1,84 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{
+    constants::{
+        BAR_CLOSE_FEATURE_ID, BAR_HIGH_FEATURE_ID, BAR_LOW_FEATURE_ID, BAR_NOTIONAL_VOLUME_FEATURE_ID,
+        BAR_OPEN_FEATURE_ID, BAR_TRADE_COUNT_FEATURE_ID, BAR_VOLUME_FEATURE_ID,
+    },
+    models::Insight,
+    Notional, Price, Quantity,
+};
+
+use super::{Instrument, Pipeline};
+
+/// Pre-aggregated OHLCV bar. The event time is the start of the bar.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Bar {
+    pub event_time: OffsetDateTime,
+    pub instrument: Arc<Instrument>,
+    pub frequency: Duration,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Quantity,
+    pub notional_volume: Notional,
+    pub trade_count: u64,
+}
+
+impl Bar {
+    /// Time at which the bar is complete
+    pub fn end_time(&self) -> OffsetDateTime {
+        self.event_time + self.frequency
+    }
+
+    pub fn vwap(&self) -> Price {
+        if self.volume.is_zero() {
+            self.close
+        } else {
+            self.notional_volume / self.volume
+        }
+    }
+
+    /// Convert the bar into insights timestamped at the end of the bar, so they are only visible once the bar
+    /// has closed.
+    pub fn to_insights(self, pipeline: Arc<Pipeline>) -> Vec<Arc<Insight>> {
+        let event_time = self.end_time();
+        [
+            (BAR_OPEN_FEATURE_ID.clone(), self.open),
+            (BAR_HIGH_FEATURE_ID.clone(), self.high),
+            (BAR_LOW_FEATURE_ID.clone(), self.low),
+            (BAR_CLOSE_FEATURE_ID.clone(), self.close),
+            (BAR_VOLUME_FEATURE_ID.clone(), self.volume),
+            (BAR_NOTIONAL_VOLUME_FEATURE_ID.clone(), self.notional_volume),
+            (BAR_TRADE_COUNT_FEATURE_ID.clone(), Decimal::from(self.trade_count)),
+        ]
+        .into_iter()
+        .map(|(feature_id, value)| {
+            Arc::new(
+                Insight::builder()
+                    .event_time(event_time)
+                    .pipeline(pipeline.clone())
+                    .instrument(Some(self.instrument.clone()))
+                    .feature_id(feature_id)
+                    .value(value)
+                    .build(),
+            )
+        })
+        .collect()
+    }
+}
+
+impl fmt::Display for Bar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} o={} h={} l={} c={} v={}",
+            self.event_time, self.instrument, self.open, self.high, self.low, self.close, self.volume
+        )
+    }
+}
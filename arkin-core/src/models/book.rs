@@ -4,7 +4,7 @@ use time::OffsetDateTime;
 
 use crate::{
     types::{Price, Quantity},
-    EventType, EventTypeOf,
+    Event, EventType, EventTypeOf,
 };
 
 use super::Instrument;
@@ -39,6 +39,16 @@ impl EventTypeOf for Book {
     fn event_type() -> EventType {
         EventType::Book
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<Book>> for Event {
+    fn from(book: Arc<Book>) -> Self {
+        Event::Book(book)
+    }
 }
 
 impl fmt::Display for Book {
@@ -0,0 +1,84 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::{Duration, OffsetDateTime};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::Instrument;
+
+#[derive(Debug, Display, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarImportance {
+    Low,
+    #[default]
+    Medium,
+    /// Moves the whole market, like a rate decision or a CPI release
+    High,
+}
+
+/// Scheduled macro event or exchange announcement, like a rate decision, a data release or a delisting. Published
+/// ahead of time so strategies can reduce their exposure around it.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct CalendarEvent {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    /// Calendar the event was read from
+    pub source: String,
+    /// Id of the event in its calendar, an updated event keeps it
+    pub uid: String,
+    pub title: String,
+    pub importance: CalendarImportance,
+    /// Instrument the event is about, the whole market without one
+    #[builder(default)]
+    pub instrument: Option<Arc<Instrument>>,
+    pub start: OffsetDateTime,
+    /// Same as the start for events at a single point in time like a data release
+    pub end: OffsetDateTime,
+}
+
+impl CalendarEvent {
+    pub fn applies_to(&self, instrument: &Instrument) -> bool {
+        self.instrument.as_ref().is_none_or(|i| i.id == instrument.id)
+    }
+
+    /// True from `before` the start until `after` the end of the event.
+    pub fn is_near(&self, time: OffsetDateTime, before: Duration, after: Duration) -> bool {
+        self.start - before <= time && time <= self.end + after
+    }
+}
+
+impl EventTypeOf for CalendarEvent {
+    fn event_type() -> EventType {
+        EventType::CalendarEvent
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
+}
+
+impl From<Arc<CalendarEvent>> for Event {
+    fn from(event: Arc<CalendarEvent>) -> Self {
+        Event::CalendarEvent(event)
+    }
+}
+
+impl fmt::Display for CalendarEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = match &self.instrument {
+            Some(instrument) => instrument.symbol.as_str(),
+            None => "market",
+        };
+        write!(
+            f,
+            "{} {} ({}) on {} from {} to {}",
+            self.source, self.title, self.importance, scope, self.start, self.end
+        )
+    }
+}
@@ -0,0 +1,49 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::Portfolio;
+
+/// Moves capital between the sub-accounts of two strategies trading on the same portfolio. Nothing moves on the
+/// venue, only the capital the strategies are measured against changes.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct CapitalTransfer {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    /// Strategy the capital is taken from
+    pub from: String,
+    /// Strategy the capital is given to
+    pub to: String,
+    pub amount: Decimal,
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl EventTypeOf for CapitalTransfer {
+    fn event_type() -> EventType {
+        EventType::CapitalTransfer
+    }
+}
+
+impl From<Arc<CapitalTransfer>> for Event {
+    fn from(event: Arc<CapitalTransfer>) -> Self {
+        Event::CapitalTransfer(event)
+    }
+}
+
+impl fmt::Display for CapitalTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "portfolio={} from={} to={} amount={} reason={}",
+            self.portfolio.name, self.from, self.to, self.amount, self.reason
+        )
+    }
+}
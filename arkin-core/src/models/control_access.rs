@@ -0,0 +1,65 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+/// Role of an API token of the control server, every role may do what the roles before it may.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ControlRole {
+    /// Reads the state, like a dashboard
+    ReadOnly,
+    /// Halts and resumes trading and places and cancels orders by hand
+    Trader,
+    /// Moves capital and funds
+    Admin,
+}
+
+/// Published for every request to the control server once it checks tokens, allowed or not, so the audit records who
+/// called what.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct ControlAccess {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    /// Name of the token, none without a known token
+    #[builder(default)]
+    pub operator: Option<String>,
+    #[builder(default)]
+    pub role: Option<ControlRole>,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+}
+
+impl EventTypeOf for ControlAccess {
+    fn event_type() -> EventType {
+        EventType::ControlAccess
+    }
+}
+
+impl From<Arc<ControlAccess>> for Event {
+    fn from(event: Arc<ControlAccess>) -> Self {
+        Event::ControlAccess(event)
+    }
+}
+
+impl fmt::Display for ControlAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} operator={} role={} allowed={}",
+            self.method,
+            self.path,
+            self.operator.as_deref().unwrap_or("unknown"),
+            self.role.map(|r| r.to_string()).unwrap_or_else(|| "none".into()),
+            self.allowed
+        )
+    }
+}
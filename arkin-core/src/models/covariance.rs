@@ -0,0 +1,124 @@
+use std::{fmt, sync::Arc};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::Instrument;
+
+/// Rolling covariance and correlation of the returns across the instruments of the universe. Rows and columns follow
+/// the order of `instruments`.
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct CovarianceUpdate {
+    pub event_time: OffsetDateTime,
+    pub instruments: Vec<Arc<Instrument>>,
+    /// Number of aligned returns the matrix is estimated from
+    pub periods: usize,
+    pub covariance: Vec<Vec<f64>>,
+    pub correlation: Vec<Vec<f64>>,
+}
+
+impl CovarianceUpdate {
+    /// Sample covariance and correlation of the returns aligned on their most recent values, the series are cut to
+    /// the shortest one.
+    pub fn from_returns(event_time: OffsetDateTime, instruments: Vec<Arc<Instrument>>, returns: &[Vec<f64>]) -> Self {
+        let periods = returns.iter().map(|r| r.len()).min().unwrap_or(0);
+        let series = returns.iter().map(|r| &r[r.len() - periods..]).collect::<Vec<_>>();
+        let means = series
+            .iter()
+            .map(|s| s.iter().sum::<f64>() / periods.max(1) as f64)
+            .collect::<Vec<_>>();
+        let covariance = series
+            .iter()
+            .zip(&means)
+            .map(|(a, mean_a)| {
+                series
+                    .iter()
+                    .zip(&means)
+                    .map(|(b, mean_b)| match periods {
+                        0 | 1 => 0.,
+                        _ => {
+                            a.iter().zip(b.iter()).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum::<f64>()
+                                / (periods - 1) as f64
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let correlation = (0..covariance.len())
+            .map(|i| {
+                (0..covariance.len())
+                    .map(|j| {
+                        let scale = (covariance[i][i] * covariance[j][j]).sqrt();
+                        if scale > 0. {
+                            covariance[i][j] / scale
+                        } else {
+                            0.
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            event_time,
+            instruments,
+            periods,
+            covariance,
+            correlation,
+        }
+    }
+
+    pub fn index(&self, instrument: &Arc<Instrument>) -> Option<usize> {
+        self.instruments.iter().position(|i| i == instrument)
+    }
+
+    pub fn covariance_of(&self, a: &Arc<Instrument>, b: &Arc<Instrument>) -> Option<f64> {
+        Some(self.covariance[self.index(a)?][self.index(b)?])
+    }
+
+    pub fn correlation_of(&self, a: &Arc<Instrument>, b: &Arc<Instrument>) -> Option<f64> {
+        Some(self.correlation[self.index(a)?][self.index(b)?])
+    }
+
+    /// Standard deviation of the returns per period.
+    pub fn volatility(&self, instrument: &Arc<Instrument>) -> Option<f64> {
+        self.covariance_of(instrument, instrument).map(f64::sqrt)
+    }
+
+    /// Covariance of the given instruments in their order, None if one of them is not in the matrix.
+    pub fn submatrix(&self, instruments: &[Arc<Instrument>]) -> Option<Vec<Vec<f64>>> {
+        let index = instruments.iter().map(|i| self.index(i)).collect::<Option<Vec<_>>>()?;
+        Some(
+            index
+                .iter()
+                .map(|&i| index.iter().map(|&j| self.covariance[i][j]).collect())
+                .collect(),
+        )
+    }
+}
+
+impl EventTypeOf for CovarianceUpdate {
+    fn event_type() -> EventType {
+        EventType::CovarianceUpdate
+    }
+}
+
+impl From<Arc<CovarianceUpdate>> for Event {
+    fn from(event: Arc<CovarianceUpdate>) -> Self {
+        Event::CovarianceUpdate(event)
+    }
+}
+
+impl fmt::Display for CovarianceUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "instruments={} periods={}",
+            self.instruments.iter().map(|i| i.symbol.as_str()).collect::<Vec<_>>().join(","),
+            self.periods
+        )
+    }
+}
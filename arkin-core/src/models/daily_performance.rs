@@ -0,0 +1,72 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::Date;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf, Notional};
+
+use super::Portfolio;
+
+/// Performance of a strategy over one UTC day, frozen at the day boundary.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct DailyPerformance {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub date: Date,
+    pub portfolio: Arc<Portfolio>,
+    /// Strategy name, orders without a strategy are booked on `default`
+    pub strategy: String,
+    /// Pnl of the positions closed during the day
+    pub realized_pnl: Decimal,
+    /// Pnl of the positions still open at the end of the day, marked at the last mid price
+    pub unrealized_pnl: Decimal,
+    /// Mark to mark pnl of the day as if every fill happened at the decision price of its order
+    pub signal_pnl: Decimal,
+    /// Slippage of the fills of the day against the decision prices of their orders
+    pub execution_pnl: Decimal,
+    pub commission: Decimal,
+    pub funding: Decimal,
+    /// Notional traded during the day
+    pub turnover: Notional,
+    pub trades: u64,
+    /// Net asset value after the hypothetical management and performance fees
+    pub nav: Decimal,
+    pub high_water_mark: Decimal,
+    pub management_fee: Decimal,
+    pub performance_fee: Decimal,
+}
+
+impl EventTypeOf for DailyPerformance {
+    fn event_type() -> EventType {
+        EventType::DailyPerformance
+    }
+}
+
+impl From<Arc<DailyPerformance>> for Event {
+    fn from(event: Arc<DailyPerformance>) -> Self {
+        Event::DailyPerformance(event)
+    }
+}
+
+impl fmt::Display for DailyPerformance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "date={} strategy={} realized_pnl={} unrealized_pnl={} signal_pnl={} execution_pnl={} commission={} funding={} turnover={} trades={} nav={} high_water_mark={}",
+            self.date,
+            self.strategy,
+            self.realized_pnl,
+            self.unrealized_pnl,
+            self.signal_pnl,
+            self.execution_pnl,
+            self.commission,
+            self.funding,
+            self.turnover,
+            self.trades,
+            self.nav,
+            self.high_water_mark
+        )
+    }
+}
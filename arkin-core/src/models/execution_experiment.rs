@@ -0,0 +1,76 @@
+use std::{fmt, sync::Arc};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::ExecutionOrderType;
+
+/// Execution cost of the orders routed through one arm of an experiment, in basis points of the decision price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExperimentArm {
+    pub order_type: ExecutionOrderType,
+    /// Orders routed through the arm
+    pub orders: u64,
+    /// Orders finished with a fill, the costs are measured over these
+    pub filled: u64,
+    pub mean_cost_bps: f64,
+    pub std_cost_bps: f64,
+}
+
+impl fmt::Display for ExperimentArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} orders={} filled={} cost={:.2}bps std={:.2}bps",
+            self.order_type, self.orders, self.filled, self.mean_cost_bps, self.std_cost_bps
+        )
+    }
+}
+
+/// Cost difference between the control and the treatment arm of an execution routing experiment, with a Welch t-test
+/// on the costs of the filled orders. The test is left out while an arm has fewer than two filled orders.
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct ExecutionExperimentReport {
+    pub event_time: OffsetDateTime,
+    pub experiment: String,
+    pub control: ExperimentArm,
+    pub treatment: ExperimentArm,
+    /// Mean cost of the treatment minus the control, negative when the treatment is cheaper
+    pub difference_bps: f64,
+    #[builder(default)]
+    pub t_stat: Option<f64>,
+    /// Two-sided p-value of the difference
+    #[builder(default)]
+    pub p_value: Option<f64>,
+    pub significant: bool,
+}
+
+impl EventTypeOf for ExecutionExperimentReport {
+    fn event_type() -> EventType {
+        EventType::ExecutionExperimentReport
+    }
+}
+
+impl From<Arc<ExecutionExperimentReport>> for Event {
+    fn from(event: Arc<ExecutionExperimentReport>) -> Self {
+        Event::ExecutionExperimentReport(event)
+    }
+}
+
+impl fmt::Display for ExecutionExperimentReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "experiment={} control=({}) treatment=({}) difference={:.2}bps",
+            self.experiment, self.control, self.treatment, self.difference_bps
+        )?;
+        match (self.t_stat, self.p_value) {
+            (Some(t_stat), Some(p_value)) => {
+                write!(f, " t={:.3} p={:.4} significant={}", t_stat, p_value, self.significant)
+            }
+            _ => write!(f, " not enough fills to test"),
+        }
+    }
+}
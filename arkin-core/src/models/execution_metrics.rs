@@ -0,0 +1,92 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf, Price, Quantity};
+
+use super::{Instrument, MarketSide, VenueOrderId, VenueOrderStatus, VenueOrderType};
+
+/// Execution quality of an order, taken once it left the book. Prices are compared to the mid price, the arrival
+/// price is the mid when the order reached the venue. Costs are in basis points and positive when the order paid.
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct ExecutionMetrics {
+    /// Time the order left the book
+    pub event_time: OffsetDateTime,
+    pub order_id: VenueOrderId,
+    pub instrument: Arc<Instrument>,
+    pub side: MarketSide,
+    pub order_type: VenueOrderType,
+    pub status: VenueOrderStatus,
+    pub quantity: Quantity,
+    pub filled_quantity: Quantity,
+    /// Share of the quantity that filled
+    pub fill_ratio: f64,
+    /// Time from the arrival of the order to its last fill, none without fills
+    #[builder(default)]
+    pub time_to_fill: Option<Duration>,
+    /// Mid price on arrival, none without a book to take it from
+    #[builder(default)]
+    pub arrival_price: Option<Price>,
+    #[builder(default)]
+    pub avg_fill_price: Option<Price>,
+    /// Twice the distance of the fills from the mid at the time of the fill, weighted by the fill quantity
+    #[builder(default)]
+    pub effective_spread_bps: Option<f64>,
+    /// Distance of the average fill price from the arrival price
+    #[builder(default)]
+    pub implementation_shortfall_bps: Option<f64>,
+    /// Commission of the fills as a share of their notional, weighted by the fill quantity
+    #[builder(default)]
+    pub commission_bps: Option<f64>,
+    /// Share of the filled quantity that added liquidity to the book
+    #[builder(default)]
+    pub maker_ratio: Option<f64>,
+}
+
+impl EventTypeOf for ExecutionMetrics {
+    fn event_type() -> EventType {
+        EventType::ExecutionMetrics
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<ExecutionMetrics>> for Event {
+    fn from(event: Arc<ExecutionMetrics>) -> Self {
+        Event::ExecutionMetrics(event)
+    }
+}
+
+impl fmt::Display for ExecutionMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order={} instrument={} side={} type={} status={} filled={}/{} fill_ratio={:.2} time_to_fill={:?}",
+            self.order_id,
+            self.instrument.symbol,
+            self.side,
+            self.order_type,
+            self.status,
+            self.filled_quantity,
+            self.quantity,
+            self.fill_ratio,
+            self.time_to_fill
+        )?;
+        if let Some(spread) = self.effective_spread_bps {
+            write!(f, " effective_spread={:.2}bps", spread)?;
+        }
+        if let Some(shortfall) = self.implementation_shortfall_bps {
+            write!(f, " shortfall={:.2}bps", shortfall)?;
+        }
+        if let Some(commission) = self.commission_bps {
+            write!(f, " commission={:.2}bps", commission)?;
+        }
+        if let Some(maker) = self.maker_ratio {
+            write!(f, " maker_ratio={:.2}", maker)?;
+        }
+        Ok(())
+    }
+}
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::{types::Commission, Event, EventType, EventTypeOf, Notional, Price, Quantity};
 
-use super::{Instrument, MarketSide, Portfolio, VenueOrderFill};
+use super::{Instrument, MarketSide, OrderTrigger, Portfolio, Strategy, VenueOrderFill};
 
 pub type ExecutionOrderId = Uuid;
 
@@ -46,10 +46,16 @@ pub struct ExecutionOrder {
     pub id: ExecutionOrderId,
     pub portfolio: Arc<Portfolio>,
     pub instrument: Arc<Instrument>,
+    /// Strategy the order originates from, used to budget the order flow per strategy
+    #[builder(default)]
+    pub strategy: Option<Arc<Strategy>>,
     pub order_type: ExecutionOrderType,
     pub side: MarketSide,
     pub price: Price,
     pub quantity: Quantity,
+    /// Conditions the order manager waits for before sending the order, sent right away without them
+    #[builder(default)]
+    pub trigger: Option<OrderTrigger>,
     #[builder(default = Price::ZERO)]
     pub fill_price: Price,
     #[builder(default = Quantity::ZERO)]
@@ -169,6 +175,10 @@ impl EventTypeOf for ExecutionOrder {
     fn event_type() -> EventType {
         EventType::ExecutionOrderNew
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<ExecutionOrder>> for Event {
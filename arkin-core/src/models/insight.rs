@@ -24,6 +24,10 @@ impl EventTypeOf for Insight {
     fn event_type() -> EventType {
         EventType::Insight
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
 }
 
 impl From<Arc<Insight>> for Event {
@@ -3,13 +3,16 @@ use std::{fmt, sync::Arc};
 use rust_decimal::prelude::Decimal;
 use sqlx::Type;
 use strum::Display;
+use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::{Maturity, Price};
+use crate::{Event, EventType, EventTypeOf, Maturity, Price};
 
 use super::{Asset, Venue};
 
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
 #[derive(Debug, Clone, Display, PartialEq, Eq, Hash, Type)]
 #[sqlx(type_name = "instrument_type", rename_all = "snake_case")]
 pub enum InstrumentType {
@@ -31,6 +34,15 @@ pub enum InstrumentOptionType {
 pub enum InstrumentStatus {
     Trading,
     Halted,
+    /// Entering delivery or settlement, open positions get closed by the venue
+    Settling,
+    Delisted,
+}
+
+impl InstrumentStatus {
+    pub fn is_trading(&self) -> bool {
+        matches!(self, InstrumentStatus::Trading)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, TypedBuilder)]
@@ -48,6 +60,9 @@ pub struct Instrument {
     pub maturity: Option<Maturity>,
     pub strike: Option<Price>,
     pub option_type: Option<InstrumentOptionType>,
+    /// Instrument the option or future settles against
+    #[builder(default)]
+    pub underlying: Option<Arc<Instrument>>,
     pub contract_size: Decimal,
     pub price_precision: u32,
     pub quantity_precision: u32,
@@ -58,8 +73,65 @@ pub struct Instrument {
     pub status: InstrumentStatus,
 }
 
+impl Instrument {
+    pub fn is_option(&self) -> bool {
+        self.instrument_type == InstrumentType::Option
+    }
+
+    /// Time left until the maturity in years of 365 days, zero once expired. `None` for instruments without maturity.
+    pub fn time_to_expiry(&self, now: OffsetDateTime) -> Option<f64> {
+        self.maturity
+            .map(|maturity| ((maturity - now).as_seconds_f64() / SECONDS_PER_YEAR).max(0.0))
+    }
+}
+
 impl fmt::Display for Instrument {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.symbol)
     }
 }
+
+/// Published when the status of a symbol on a venue changes. New listings come without an instrument and without a
+/// previous status, symbols the venue no longer lists are reported as delisted.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct InstrumentStatusUpdate {
+    pub event_time: OffsetDateTime,
+    pub venue_symbol: String,
+    /// Known instrument of the symbol
+    #[builder(default)]
+    pub instrument: Option<Arc<Instrument>>,
+    #[builder(default)]
+    pub previous: Option<InstrumentStatus>,
+    pub status: InstrumentStatus,
+}
+
+impl InstrumentStatusUpdate {
+    pub fn is_listing(&self) -> bool {
+        self.previous.is_none()
+    }
+}
+
+impl EventTypeOf for InstrumentStatusUpdate {
+    fn event_type() -> EventType {
+        EventType::InstrumentStatusUpdate
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
+}
+
+impl From<Arc<InstrumentStatusUpdate>> for Event {
+    fn from(event: Arc<InstrumentStatusUpdate>) -> Self {
+        Event::InstrumentStatusUpdate(event)
+    }
+}
+
+impl fmt::Display for InstrumentStatusUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.previous {
+            Some(previous) => write!(f, "{} {} -> {}", self.venue_symbol, previous, self.status),
+            None => write!(f, "{} listed as {}", self.venue_symbol, self.status),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{fmt, time::Duration};
+
+use serde::Serialize;
+use sqlx::Type;
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::constants;
+
+#[derive(Clone, Display, Copy, PartialEq, Eq, Debug, Type, Serialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn can_pause(&self) -> bool {
+        matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+
+    pub fn can_resume(&self) -> bool {
+        matches!(self, JobStatus::Paused | JobStatus::Failed)
+    }
+
+    pub fn can_cancel(&self) -> bool {
+        !matches!(self, JobStatus::Cancelled | JobStatus::Completed)
+    }
+}
+
+/// Long running command that works through its time range in chunks. The cursor is the end of the last finished
+/// chunk, so a paused, failed or interrupted job continues from there.
+#[derive(Debug, Clone, TypedBuilder, PartialEq, Eq, Serialize)]
+pub struct Job {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    /// Kind of command, like `insights` or `tardis`
+    pub name: String,
+    /// Command line of the job without the binary
+    pub args: Vec<String>,
+    /// Shards of one submission share the group
+    #[builder(default)]
+    pub group_id: Option<Uuid>,
+    /// Instruments of the shard, replace the instruments of the command when set
+    #[builder(default)]
+    pub instruments: Vec<String>,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+    #[builder(default = start)]
+    pub cursor: OffsetDateTime,
+    pub chunk: Duration,
+    #[builder(default = JobStatus::Queued)]
+    pub status: JobStatus,
+    #[builder(default)]
+    pub error: Option<String>,
+    /// Worker that claimed the job last
+    #[builder(default)]
+    pub worker: Option<String>,
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub created_at: OffsetDateTime,
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub updated_at: OffsetDateTime,
+}
+
+impl Job {
+    /// Share of the time range that is done.
+    pub fn progress(&self) -> f64 {
+        let total = (self.end - self.start).as_seconds_f64();
+        if total <= 0. {
+            return 1.;
+        }
+        ((self.cursor - self.start).as_seconds_f64() / total).clamp(0., 1.)
+    }
+
+    /// Range of the chunk after the cursor, none when the job is through its range.
+    pub fn next_chunk(&self) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        if self.cursor >= self.end {
+            return None;
+        }
+        let end = (self.cursor + self.chunk).min(self.end);
+        Some((self.cursor, end))
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cursor = self
+            .cursor
+            .format(constants::TIMESTAMP_FORMAT)
+            .expect("Failed to format cursor");
+        write!(
+            f,
+            "id={} name={} status={} progress={:.1}% cursor={} args=\"{}\"",
+            self.id,
+            self.name,
+            self.status,
+            self.progress() * 100.,
+            cursor,
+            self.args.join(" ")
+        )?;
+        if !self.instruments.is_empty() {
+            write!(f, " instruments={}", self.instruments.join(","))?;
+        }
+        if let Some(worker) = &self.worker {
+            write!(f, " worker={}", worker)?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, " error=\"{}\"", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merged state of the shards of a job group.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct JobGroupReport {
+    pub group_id: Uuid,
+    pub shards: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub completed: usize,
+    /// Failed, paused and cancelled shards, they need someone to look at them
+    pub stopped: usize,
+    /// Mean progress of the shards
+    pub progress: f64,
+    pub workers: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl JobGroupReport {
+    pub fn from_jobs(group_id: Uuid, jobs: &[Job]) -> Self {
+        let count = |status: JobStatus| jobs.iter().filter(|j| j.status == status).count();
+        let mut workers = jobs.iter().filter_map(|j| j.worker.clone()).collect::<Vec<_>>();
+        workers.sort();
+        workers.dedup();
+        Self {
+            group_id,
+            shards: jobs.len(),
+            queued: count(JobStatus::Queued),
+            running: count(JobStatus::Running),
+            completed: count(JobStatus::Completed),
+            stopped: count(JobStatus::Failed) + count(JobStatus::Paused) + count(JobStatus::Cancelled),
+            progress: match jobs.len() {
+                0 => 0.,
+                n => jobs.iter().map(|j| j.progress()).sum::<f64>() / n as f64,
+            },
+            workers,
+            errors: jobs
+                .iter()
+                .filter_map(|j| j.error.as_ref().map(|e| format!("{}: {}", j.id, e)))
+                .collect(),
+        }
+    }
+
+    /// No shard is left to work on.
+    pub fn is_finished(&self) -> bool {
+        self.queued == 0 && self.running == 0
+    }
+}
+
+impl fmt::Display for JobGroupReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "group={} shards={} queued={} running={} completed={} stopped={} progress={:.1}% workers={}",
+            self.group_id,
+            self.shards,
+            self.queued,
+            self.running,
+            self.completed,
+            self.stopped,
+            self.progress * 100.,
+            self.workers.join(",")
+        )?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn job(status: JobStatus, cursor: OffsetDateTime) -> Job {
+        Job::builder()
+            .name("insights".into())
+            .args(vec![])
+            .start(datetime!(2025-01-01 00:00 UTC))
+            .end(datetime!(2025-01-03 00:00 UTC))
+            .cursor(cursor)
+            .chunk(Duration::from_secs(86400))
+            .status(status)
+            .build()
+    }
+
+    #[test]
+    fn test_chunks() {
+        let job = job(JobStatus::Running, datetime!(2025-01-02 12:00 UTC));
+        assert_eq!(job.progress(), 0.75);
+        assert_eq!(
+            job.next_chunk(),
+            Some((datetime!(2025-01-02 12:00 UTC), datetime!(2025-01-03 00:00 UTC)))
+        );
+
+        let done = Job {
+            cursor: job.end,
+            ..job
+        };
+        assert_eq!(done.progress(), 1.);
+        assert_eq!(done.next_chunk(), None);
+    }
+
+    #[test]
+    fn test_group_report() {
+        let mut failed = job(JobStatus::Failed, datetime!(2025-01-01 00:00 UTC));
+        failed.error = Some("no data".into());
+        failed.worker = Some("b".into());
+        let mut completed = job(JobStatus::Completed, datetime!(2025-01-03 00:00 UTC));
+        completed.worker = Some("a".into());
+        let report = JobGroupReport::from_jobs(Uuid::new_v4(), &[failed, completed]);
+        assert_eq!(report.shards, 2);
+        assert_eq!(report.stopped, 1);
+        assert_eq!(report.completed, 1);
+        assert_eq!(report.progress, 0.5);
+        assert_eq!(report.workers, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.is_finished());
+    }
+}
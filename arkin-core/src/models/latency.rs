@@ -0,0 +1,59 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::ExecutionOrderId;
+
+/// Stage of the order path a latency is measured over.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyStage {
+    /// From the decision of the strategy to the order manager sending the order
+    OrderManager,
+    /// From sending the order to the acknowledgement of the venue
+    VenueAck,
+    /// From the acknowledgement to the first fill
+    FirstFill,
+    /// From the decision of the strategy to the first fill
+    DecisionToFill,
+}
+
+/// Time an order spent in one stage of the order path. Simulations model it from the event times, live it is
+/// measured on the clock of the instance.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct OrderLatency {
+    pub event_time: OffsetDateTime,
+    pub order_id: ExecutionOrderId,
+    pub venue: String,
+    pub stage: LatencyStage,
+    pub latency: Duration,
+    pub modeled: bool,
+}
+
+impl EventTypeOf for OrderLatency {
+    fn event_type() -> EventType {
+        EventType::OrderLatency
+    }
+}
+
+impl From<Arc<OrderLatency>> for Event {
+    fn from(event: Arc<OrderLatency>) -> Self {
+        Event::OrderLatency(event)
+    }
+}
+
+impl fmt::Display for OrderLatency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order={} venue={} stage={} latency={:?} modeled={}",
+            self.order_id, self.venue, self.stage, self.latency, self.modeled
+        )
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether this instance may send orders to the venues. Instances without leader election always lead, a standby keeps
+/// its state in sync with the venue but leaves the orders to the leader.
+#[derive(Debug)]
+pub struct Leadership {
+    leader: AtomicBool,
+}
+
+impl Default for Leadership {
+    fn default() -> Self {
+        Self {
+            leader: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Leadership {
+    /// Starts as standby until the election says otherwise.
+    pub fn standby() -> Self {
+        Self {
+            leader: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Acquire)
+    }
+
+    /// Set the role, true when it changed.
+    pub fn set_leader(&self, leader: bool) -> bool {
+        self.leader.swap(leader, Ordering::AcqRel) != leader
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf, Price, Quantity};
+
+use super::{Instrument, MarketSide, Portfolio};
+
+/// Published when the venue force closes a position because the account equity fell below the maintenance margin.
+/// The closing fills are published as order updates like any other fill.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct Liquidation {
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    pub instrument: Arc<Instrument>,
+    /// Side of the closing order
+    pub side: MarketSide,
+    pub quantity: Quantity,
+    pub mark_price: Price,
+    pub fill_price: Price,
+    /// Account equity at the mark prices when the liquidation triggered
+    pub equity: Decimal,
+    pub maintenance_margin: Decimal,
+}
+
+impl EventTypeOf for Liquidation {
+    fn event_type() -> EventType {
+        EventType::Liquidation
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<Liquidation>> for Event {
+    fn from(event: Arc<Liquidation>) -> Self {
+        Event::Liquidation(event)
+    }
+}
+
+impl fmt::Display for Liquidation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} at {} mark={} equity={} maintenance={}",
+            self.instrument,
+            self.side,
+            self.quantity,
+            self.fill_price,
+            self.mark_price,
+            self.equity,
+            self.maintenance_margin
+        )
+    }
+}
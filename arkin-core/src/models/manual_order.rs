@@ -0,0 +1,69 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::{ExecutionOrderId, Strategy};
+
+/// Name of the strategy orders entered by hand trade under, so they can be disabled and budgeted like any strategy.
+pub const MANUAL_STRATEGY: &str = "manual";
+
+/// Strategy of the orders entered by hand.
+pub fn manual_strategy() -> Arc<Strategy> {
+    Arc::new(
+        Strategy::builder()
+            .id(Uuid::nil())
+            .name(MANUAL_STRATEGY.into())
+            .description(Some("Orders entered by an operator".into()))
+            .build(),
+    )
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ManualOrderAction {
+    Place,
+    Cancel,
+}
+
+/// Published when an operator places or cancels an order by hand, so the audit records who did it and why next to
+/// the order itself.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct ManualOrder {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub action: ManualOrderAction,
+    pub order: ExecutionOrderId,
+    pub operator: String,
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl EventTypeOf for ManualOrder {
+    fn event_type() -> EventType {
+        EventType::ManualOrder
+    }
+}
+
+impl From<Arc<ManualOrder>> for Event {
+    fn from(event: Arc<ManualOrder>) -> Self {
+        Event::ManualOrder(event)
+    }
+}
+
+impl fmt::Display for ManualOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} order={} operator={} reason={}",
+            self.action, self.order, self.operator, self.reason
+        )
+    }
+}
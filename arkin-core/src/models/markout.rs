@@ -0,0 +1,59 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf, Price, Quantity};
+
+use super::{ExecutionOrderId, Instrument, MarketSide};
+
+/// Move of the mid price a fixed time after a fill, in basis points of the fill price. Positive when the price moved
+/// in favor of the fill, persistently negative markouts mean the fills are picked off by better informed flow.
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct FillMarkout {
+    /// Time of the fill plus the horizon
+    pub event_time: OffsetDateTime,
+    pub order_id: ExecutionOrderId,
+    pub instrument: Arc<Instrument>,
+    pub strategy: String,
+    pub venue: String,
+    pub side: MarketSide,
+    pub fill_price: Price,
+    pub quantity: Quantity,
+    pub horizon: Duration,
+    pub markout_bps: f64,
+}
+
+impl EventTypeOf for FillMarkout {
+    fn event_type() -> EventType {
+        EventType::FillMarkout
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<FillMarkout>> for Event {
+    fn from(event: Arc<FillMarkout>) -> Self {
+        Event::FillMarkout(event)
+    }
+}
+
+impl fmt::Display for FillMarkout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order={} instrument={} strategy={} venue={} side={} price={} quantity={} horizon={:?} markout={:.2}bps",
+            self.order_id,
+            self.instrument.symbol,
+            self.strategy,
+            self.venue,
+            self.side,
+            self.fill_price,
+            self.quantity,
+            self.horizon,
+            self.markout_bps
+        )
+    }
+}
@@ -0,0 +1,83 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{models::Insight, Event, EventType, EventTypeOf};
+
+use super::{Instrument, Pipeline};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MetricType {
+    /// Sentiment score from news or social media
+    Sentiment,
+    /// Mentions or posts about an instrument
+    SocialVolume,
+    /// Metric read from the chain, like exchange flows or active addresses
+    OnChain,
+    Other,
+}
+
+/// Timestamped value of a third-party signal, like a sentiment score or an on-chain metric. The metric enters the
+/// insights pipelines under its name.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct Metric {
+    pub event_time: OffsetDateTime,
+    /// Instrument the metric is about, market-wide metrics have none
+    #[builder(default)]
+    pub instrument: Option<Arc<Instrument>>,
+    pub metric_type: MetricType,
+    /// Feature id of the metric in the insights pipelines (e.g., sentiment_score)
+    pub name: String,
+    pub value: Decimal,
+    /// Provider the metric came from
+    pub source: String,
+}
+
+impl Metric {
+    pub fn to_insights(self, pipeline: Arc<Pipeline>) -> Vec<Arc<Insight>> {
+        let insight = Insight::builder()
+            .event_time(self.event_time)
+            .pipeline(pipeline)
+            .instrument(self.instrument)
+            .feature_id(Arc::new(self.name))
+            .value(self.value)
+            .build();
+        vec![Arc::new(insight)]
+    }
+}
+
+impl EventTypeOf for Metric {
+    fn event_type() -> EventType {
+        EventType::Metric
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
+}
+
+impl From<Arc<Metric>> for Event {
+    fn from(event: Arc<Metric>) -> Self {
+        Event::Metric(event)
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = match &self.instrument {
+            Some(instrument) => instrument.symbol.as_str(),
+            None => "market",
+        };
+        write!(
+            f,
+            "{} {} {}={} from {}",
+            scope, self.metric_type, self.name, self.value, self.source
+        )
+    }
+}
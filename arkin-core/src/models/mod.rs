@@ -1,41 +1,95 @@
+mod account_transfer;
 mod allocation;
+mod annotation;
 mod asset;
 mod balance;
+mod bar;
 mod book;
+mod calendar_event;
+mod capital_transfer;
 mod common;
+mod control_access;
+mod covariance;
+mod daily_performance;
+mod execution_experiment;
+mod execution_metrics;
 mod execution_order;
 mod insight;
 mod instance;
 mod instrument;
+mod job;
+mod latency;
+mod leadership;
+mod liquidation;
+mod manual_order;
+mod markout;
+mod metric;
+mod operator_action;
+mod order_trigger;
 mod pipeline;
 mod portfolio;
 mod position;
+mod prediction;
+mod rate_limit;
+mod rebalance;
+mod settlement;
 mod signal;
 mod strategy;
 mod tick;
 mod trade;
+mod trading_control;
 mod transaction;
+mod universe;
 mod venue;
+mod venue_calendar;
 mod venue_order;
 mod venue_order_fill;
+mod wallet_transfer;
 
+pub use account_transfer::*;
 pub use allocation::*;
+pub use annotation::*;
 pub use asset::*;
 pub use balance::*;
+pub use bar::*;
 pub use book::*;
+pub use calendar_event::*;
+pub use capital_transfer::*;
 pub use common::*;
+pub use control_access::*;
+pub use covariance::*;
+pub use daily_performance::*;
+pub use execution_experiment::*;
+pub use execution_metrics::*;
 pub use execution_order::*;
 pub use insight::*;
 pub use instance::*;
 pub use instrument::*;
+pub use job::*;
+pub use latency::*;
+pub use leadership::*;
+pub use liquidation::*;
+pub use manual_order::*;
+pub use markout::*;
+pub use metric::*;
+pub use operator_action::*;
+pub use order_trigger::*;
 pub use pipeline::*;
 pub use portfolio::*;
 pub use position::*;
+pub use prediction::*;
+pub use rate_limit::*;
+pub use rebalance::*;
+pub use settlement::*;
 pub use signal::*;
 pub use strategy::*;
 pub use tick::*;
 pub use trade::*;
+pub use trading_control::*;
 pub use transaction::*;
+pub use universe::*;
 pub use venue::*;
+pub use venue_calendar::*;
 pub use venue_order::*;
 pub use venue_order_fill::*;
+pub use wallet_transfer::*;
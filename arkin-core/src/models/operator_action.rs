@@ -0,0 +1,65 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::TradingScope;
+
+/// What an operator does by hand, like the steps of a runbook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperatorActionType {
+    /// Disable trading for an instrument or strategy, its open orders are cancelled
+    Halt { scope: TradingScope },
+    /// Enable trading for an instrument or strategy again
+    Resume { scope: TradingScope },
+    /// Close the position in an instrument with a market order of the manual strategy
+    Flatten { instrument: String },
+    /// Change a parameter of the allocation, like `leverage`
+    SetParameter { name: String, value: Decimal },
+}
+
+impl fmt::Display for OperatorActionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperatorActionType::Halt { scope } => write!(f, "halt {}", scope),
+            OperatorActionType::Resume { scope } => write!(f, "resume {}", scope),
+            OperatorActionType::Flatten { instrument } => write!(f, "flatten {}", instrument),
+            OperatorActionType::SetParameter { name, value } => write!(f, "set {}={}", name, value),
+        }
+    }
+}
+
+/// Published when an operator action takes effect, in a backtest at the time it was scheduled at.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct OperatorAction {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub action: OperatorActionType,
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl EventTypeOf for OperatorAction {
+    fn event_type() -> EventType {
+        EventType::OperatorAction
+    }
+}
+
+impl From<Arc<OperatorAction>> for Event {
+    fn from(event: Arc<OperatorAction>) -> Self {
+        Event::OperatorAction(event)
+    }
+}
+
+impl fmt::Display for OperatorAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {} reason={}", self.action, self.event_time, self.reason)
+    }
+}
@@ -0,0 +1,206 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use anyhow::{bail, Error};
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+use crate::FeatureId;
+
+/// Comparison of a value with a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    pub fn holds(&self, value: Decimal, threshold: Decimal) -> bool {
+        match self {
+            Comparison::Gt => value > threshold,
+            Comparison::Ge => value >= threshold,
+            Comparison::Lt => value < threshold,
+            Comparison::Le => value <= threshold,
+            Comparison::Eq => value == threshold,
+            Comparison::Ne => value != threshold,
+        }
+    }
+}
+
+/// Parses the operators `>`, `>=`, `<`, `<=`, `==` and `!=`.
+impl FromStr for Comparison {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            ">" => Ok(Comparison::Gt),
+            ">=" => Ok(Comparison::Ge),
+            "<" => Ok(Comparison::Lt),
+            "<=" => Ok(Comparison::Le),
+            "==" => Ok(Comparison::Eq),
+            "!=" => Ok(Comparison::Ne),
+            op => bail!("invalid comparison: {}", op),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Value of the instrument of an order a trigger condition looks at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TriggerOperand {
+    /// Mid price of the top of book
+    Price,
+    Bid,
+    Ask,
+    /// Spread of the top of book in basis points of the mid
+    SpreadBps,
+    /// Last value of an insight feature
+    Feature(FeatureId),
+}
+
+impl fmt::Display for TriggerOperand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TriggerOperand::Price => write!(f, "price"),
+            TriggerOperand::Bid => write!(f, "bid"),
+            TriggerOperand::Ask => write!(f, "ask"),
+            TriggerOperand::SpreadBps => write!(f, "spread_bps"),
+            TriggerOperand::Feature(feature_id) => write!(f, "{}", feature_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TriggerCondition {
+    pub operand: TriggerOperand,
+    pub comparison: Comparison,
+    pub value: Decimal,
+}
+
+/// Conditions an order waits for in the order manager before it is sent, joined by `and`. Each condition is
+/// `<operand> <op> <value>` where the operand is `price`, `bid`, `ask`, `spread_bps` or the name of an insight feature
+/// of the instrument, like `spread_bps < 5 and vol_30m < 0.02`. Conditions on values that are not known yet don't
+/// hold. Orders with an expiry are dropped once it passes without the trigger firing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrderTrigger {
+    pub conditions: Vec<TriggerCondition>,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+impl OrderTrigger {
+    pub fn until(mut self, expires_at: OffsetDateTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at.is_some_and(|t| now >= t)
+    }
+
+    /// True once all conditions hold for the values given by the lookup.
+    pub fn holds(&self, value: impl Fn(&TriggerOperand) -> Option<Decimal>) -> bool {
+        self.conditions
+            .iter()
+            .all(|c| value(&c.operand).is_some_and(|v| c.comparison.holds(v, c.value)))
+    }
+}
+
+impl FromStr for OrderTrigger {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut conditions = Vec::new();
+        for condition in expression.split(" and ") {
+            let parts = condition.split_whitespace().collect::<Vec<_>>();
+            let [operand, op, value] = parts.as_slice() else {
+                bail!("invalid trigger condition '{}'", condition.trim());
+            };
+            let operand = match *operand {
+                "price" => TriggerOperand::Price,
+                "bid" => TriggerOperand::Bid,
+                "ask" => TriggerOperand::Ask,
+                "spread_bps" => TriggerOperand::SpreadBps,
+                feature => TriggerOperand::Feature(Arc::new(feature.to_string())),
+            };
+            conditions.push(TriggerCondition {
+                operand,
+                comparison: op.parse()?,
+                value: value.parse()?,
+            });
+        }
+        Ok(Self {
+            conditions,
+            expires_at: None,
+        })
+    }
+}
+
+impl fmt::Display for OrderTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|c| format!("{} {} {}", c.operand, c.comparison, c.value))
+            .collect::<Vec<_>>();
+        write!(f, "{}", conditions.join(" and "))?;
+        if let Some(expires_at) = self.expires_at {
+            write!(f, " until {}", expires_at)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_order_trigger() {
+        let trigger = "spread_bps < 5 and vol_30m <= 0.02".parse::<OrderTrigger>().unwrap();
+        assert_eq!(trigger.conditions.len(), 2);
+        assert_eq!(trigger.conditions[0].operand, TriggerOperand::SpreadBps);
+        assert_eq!(trigger.conditions[1].comparison, Comparison::Le);
+        assert_eq!(trigger.to_string(), "spread_bps < 5 and vol_30m <= 0.02");
+
+        let vol = Arc::new("vol_30m".to_string());
+        let lookup = |spread: Decimal, vol_30m: Option<Decimal>| {
+            let vol = vol.clone();
+            move |operand: &TriggerOperand| match operand {
+                TriggerOperand::SpreadBps => Some(spread),
+                TriggerOperand::Feature(feature_id) if *feature_id == vol => vol_30m,
+                _ => None,
+            }
+        };
+        assert!(trigger.holds(lookup(dec!(4), Some(dec!(0.02)))));
+        assert!(!trigger.holds(lookup(dec!(6), Some(dec!(0.01)))));
+        // Unknown values never hold
+        assert!(!trigger.holds(lookup(dec!(4), None)));
+
+        let trigger = trigger.until(datetime!(2025-01-01 12:00 UTC));
+        assert!(!trigger.is_expired(datetime!(2025-01-01 11:59 UTC)));
+        assert!(trigger.is_expired(datetime!(2025-01-01 12:00 UTC)));
+
+        assert!("price <".parse::<OrderTrigger>().is_err());
+        assert!("price ~ 100".parse::<OrderTrigger>().is_err());
+        assert!("price > abc".parse::<OrderTrigger>().is_err());
+    }
+}
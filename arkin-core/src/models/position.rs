@@ -221,6 +221,10 @@ impl EventTypeOf for Position {
     fn event_type() -> EventType {
         EventType::Position
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<Position>> for Event {
@@ -273,6 +277,10 @@ impl EventTypeOf for PositionUpdate {
     fn event_type() -> EventType {
         EventType::PositionUpdate
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<PositionUpdate>> for Event {
@@ -0,0 +1,56 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf, FeatureId};
+
+use super::{Instrument, Pipeline};
+
+/// Output of a model together with the model version and the inputs it saw, so versions running side by side in a
+/// shadow evaluation can be told apart and compared.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Prediction {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub pipeline: Arc<Pipeline>,
+    pub instrument: Arc<Instrument>,
+    /// Insight the prediction is published as
+    pub feature_id: FeatureId,
+    pub model_name: String,
+    pub model_version: String,
+    /// Hash of the model inputs, equal hashes mean the models saw the same features
+    pub feature_hash: String,
+    pub value: Decimal,
+    /// Time the model took to predict
+    pub latency: Duration,
+}
+
+impl EventTypeOf for Prediction {
+    fn event_type() -> EventType {
+        EventType::Prediction
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<Prediction>> for Event {
+    fn from(event: Arc<Prediction>) -> Self {
+        Event::Prediction(event)
+    }
+}
+
+impl fmt::Display for Prediction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instrument={} model={}@{} value={} feature_hash={} latency={:?}",
+            self.instrument.symbol, self.model_name, self.model_version, self.value, self.feature_hash, self.latency
+        )
+    }
+}
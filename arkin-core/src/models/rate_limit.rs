@@ -0,0 +1,79 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::{Duration, OffsetDateTime};
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::{Instrument, VenueOrderId};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitType {
+    /// Weight of all requests, orders and cancels alike
+    RequestWeight,
+    /// Number of new orders
+    Orders,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitAction {
+    /// The venue refused the request
+    Rejected,
+    /// The request went through once the budget was free again
+    Delayed,
+}
+
+/// Published when a request to the venue exceeds one of its rate limits, with the request it happened to and what
+/// the venue did with it.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct RateLimitExceeded {
+    pub event_time: OffsetDateTime,
+    #[builder(default)]
+    pub instrument: Option<Arc<Instrument>>,
+    /// Order that was placed or cancelled
+    pub order_id: VenueOrderId,
+    pub limit_type: RateLimitType,
+    /// Window the limit is counted over
+    pub interval: Duration,
+    pub limit: u32,
+    pub action: RateLimitAction,
+    /// Time the limit has budget again, when a delayed request went through
+    pub retry_at: OffsetDateTime,
+}
+
+impl EventTypeOf for RateLimitExceeded {
+    fn event_type() -> EventType {
+        EventType::RateLimitExceeded
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
+}
+
+impl From<Arc<RateLimitExceeded>> for Event {
+    fn from(event: Arc<RateLimitExceeded>) -> Self {
+        Event::RateLimitExceeded(event)
+    }
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} limit of {} per {}s exceeded by order {}, {} until {}",
+            self.limit_type,
+            self.limit,
+            self.interval.whole_seconds(),
+            self.order_id,
+            self.action,
+            self.retry_at
+        )
+    }
+}
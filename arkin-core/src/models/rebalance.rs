@@ -0,0 +1,57 @@
+use std::{fmt, sync::Arc};
+
+use sqlx::prelude::Type;
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::Portfolio;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Type)]
+#[strum(serialize_all = "snake_case")]
+#[sqlx(type_name = "rebalance_trigger", rename_all = "snake_case")]
+pub enum RebalanceTrigger {
+    /// The calendar schedule of the rebalancer fired
+    Schedule,
+    /// The current weights drifted too far from the optimal weights
+    Drift,
+    /// A regime feature changed its value
+    Regime,
+}
+
+/// Published when the allocation rebalances the portfolio, recording what triggered it.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct Rebalance {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    pub trigger: RebalanceTrigger,
+    /// What fired the trigger, like the schedule or the instrument that drifted
+    pub detail: String,
+}
+
+impl EventTypeOf for Rebalance {
+    fn event_type() -> EventType {
+        EventType::Rebalance
+    }
+}
+
+impl From<Arc<Rebalance>> for Event {
+    fn from(event: Arc<Rebalance>) -> Self {
+        Event::Rebalance(event)
+    }
+}
+
+impl fmt::Display for Rebalance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "portfolio={} trigger={} detail={}",
+            self.portfolio.name, self.trigger, self.detail
+        )
+    }
+}
@@ -0,0 +1,72 @@
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf, Price, Quantity};
+
+use super::{Instrument, PositionSide};
+
+/// Open position closed at its mark price when a simulation finished.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct SettledPosition {
+    pub instrument: Arc<Instrument>,
+    pub side: PositionSide,
+    pub quantity: Quantity,
+    pub entry_price: Price,
+    pub mark_price: Price,
+    /// Pnl of closing the position at the mark price, booked on the quote asset
+    pub carry: Decimal,
+}
+
+/// Positions the portfolio settled when a simulation finished with the carry they booked, so the results of a run
+/// show how much of its pnl was still open at the end.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct SettlementReport {
+    pub event_time: OffsetDateTime,
+    #[builder(default)]
+    pub positions: Vec<SettledPosition>,
+}
+
+impl SettlementReport {
+    /// Carry of all settled positions per quote asset.
+    pub fn carry(&self) -> BTreeMap<String, Decimal> {
+        let mut carry = BTreeMap::new();
+        for position in &self.positions {
+            *carry
+                .entry(position.instrument.quote_asset.symbol.clone())
+                .or_insert(Decimal::ZERO) += position.carry;
+        }
+        carry
+    }
+}
+
+impl EventTypeOf for SettlementReport {
+    fn event_type() -> EventType {
+        EventType::SettlementReport
+    }
+}
+
+impl From<Arc<SettlementReport>> for Event {
+    fn from(event: Arc<SettlementReport>) -> Self {
+        Event::SettlementReport(event)
+    }
+}
+
+impl fmt::Display for SettlementReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Settlement at {}", self.event_time)?;
+        for p in &self.positions {
+            write!(
+                f,
+                "\n  {} {} {} entry={} mark={} carry={}",
+                p.instrument, p.side, p.quantity, p.entry_price, p.mark_price, p.carry
+            )?;
+        }
+        for (asset, carry) in self.carry() {
+            write!(f, "\n  total carry {} {}", carry, asset)?;
+        }
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::{EventType, EventTypeOf, Weight};
+use crate::{Event, EventType, EventTypeOf, Weight};
 
 use super::{Instrument, Strategy};
 
@@ -23,6 +23,16 @@ impl EventTypeOf for Signal {
     fn event_type() -> EventType {
         EventType::Signal
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<Signal>> for Event {
+    fn from(signal: Arc<Signal>) -> Self {
+        Event::Signal(signal)
+    }
 }
 
 impl fmt::Display for Signal {
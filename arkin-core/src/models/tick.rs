@@ -103,6 +103,10 @@ impl EventTypeOf for Tick {
     fn event_type() -> EventType {
         EventType::Tick
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<Tick>> for Event {
@@ -67,6 +67,10 @@ impl EventTypeOf for Trade {
     fn event_type() -> EventType {
         EventType::Trade
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<Trade>> for Event {
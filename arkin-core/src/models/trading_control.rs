@@ -0,0 +1,149 @@
+use std::{fmt, sync::Arc};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::ExecutionOrder;
+
+/// What a trading control applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingScope {
+    /// Instrument symbol, like `perp-btc-usdt@binance`
+    Instrument(String),
+    /// Strategy name
+    Strategy(String),
+}
+
+impl TradingScope {
+    /// True if the order trades the instrument or originates from the strategy of the scope.
+    pub fn contains(&self, order: &ExecutionOrder) -> bool {
+        match self {
+            TradingScope::Instrument(symbol) => &order.instrument.symbol == symbol,
+            TradingScope::Strategy(name) => order.strategy.as_ref().is_some_and(|s| &s.name == name),
+        }
+    }
+}
+
+impl fmt::Display for TradingScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TradingScope::Instrument(symbol) => write!(f, "instrument {}", symbol),
+            TradingScope::Strategy(name) => write!(f, "strategy {}", name),
+        }
+    }
+}
+
+/// Published when trading gets disabled or enabled again for an instrument or strategy on a running instance.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct TradingControl {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub scope: TradingScope,
+    pub enabled: bool,
+    #[builder(default)]
+    pub reason: String,
+}
+
+impl EventTypeOf for TradingControl {
+    fn event_type() -> EventType {
+        EventType::TradingControl
+    }
+}
+
+impl From<Arc<TradingControl>> for Event {
+    fn from(event: Arc<TradingControl>) -> Self {
+        Event::TradingControl(event)
+    }
+}
+
+impl fmt::Display for TradingControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = if self.enabled { "enabled" } else { "disabled" };
+        write!(f, "{} {} reason={}", self.scope, state, self.reason)
+    }
+}
+
+/// Instruments and strategies trading is disabled for, with the reason given. Everything not listed trades.
+#[derive(Debug, Default)]
+pub struct TradingSwitch {
+    disabled: DashMap<TradingScope, String>,
+}
+
+impl TradingSwitch {
+    pub fn apply(&self, control: &TradingControl) {
+        if control.enabled {
+            self.disabled.remove(&control.scope);
+        } else {
+            self.disabled.insert(control.scope.clone(), control.reason.clone());
+        }
+    }
+
+    /// The first disabled scope the order falls in, `None` if it may trade.
+    pub fn blocked_by(&self, order: &ExecutionOrder) -> Option<TradingScope> {
+        self.disabled.iter().find(|e| e.key().contains(order)).map(|e| e.key().clone())
+    }
+
+    pub fn is_enabled(&self, scope: &TradingScope) -> bool {
+        !self.disabled.contains_key(scope)
+    }
+
+    /// Disabled scopes with their reason.
+    pub fn disabled(&self) -> Vec<(TradingScope, String)> {
+        self.disabled.iter().map(|e| (e.key().clone(), e.value().clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::{
+        test_utils::{test_inst_binance_btc_usdt_perp, test_portfolio},
+        ExecutionOrderType, MarketSide, Strategy,
+    };
+
+    fn control(scope: TradingScope, enabled: bool) -> TradingControl {
+        TradingControl::builder()
+            .event_time(OffsetDateTime::now_utc())
+            .scope(scope)
+            .enabled(enabled)
+            .build()
+    }
+
+    #[test]
+    fn test_switch() {
+        let order = ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .strategy(Some(Arc::new(
+                Strategy::builder().name("momentum".into()).description(None).build(),
+            )))
+            .order_type(ExecutionOrderType::Taker)
+            .side(MarketSide::Buy)
+            .price(dec!(60000))
+            .quantity(dec!(0.01))
+            .build();
+        let switch = TradingSwitch::default();
+        assert_eq!(switch.blocked_by(&order), None);
+
+        let instrument = TradingScope::Instrument("perp-btc-usdt@binance".into());
+        switch.apply(&control(instrument.clone(), false));
+        assert_eq!(switch.blocked_by(&order), Some(instrument.clone()));
+        switch.apply(&control(instrument.clone(), true));
+        assert!(switch.is_enabled(&instrument));
+
+        switch.apply(&control(TradingScope::Strategy("other".into()), false));
+        assert_eq!(switch.blocked_by(&order), None);
+        switch.apply(&control(TradingScope::Strategy("momentum".into()), false));
+        assert_eq!(switch.blocked_by(&order), Some(TradingScope::Strategy("momentum".into())));
+        assert_eq!(switch.disabled().len(), 2);
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fmt, sync::Arc};
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::Instrument;
+
+/// Published when the instruments of a universe change. Instruments that joined are warming up, their insights are
+/// computed but they are not traded until the warmup passed, instruments that left are flattened.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct UniverseUpdate {
+    pub event_time: OffsetDateTime,
+    pub universe: String,
+    #[builder(default)]
+    pub joined: Vec<Arc<Instrument>>,
+    #[builder(default)]
+    pub left: Vec<Arc<Instrument>>,
+    /// Instruments the strategies trade
+    pub members: Vec<Arc<Instrument>>,
+    /// Instruments that joined and still warm up
+    #[builder(default)]
+    pub warming: Vec<Arc<Instrument>>,
+}
+
+impl UniverseUpdate {
+    /// True if the instrument is a member past its warmup.
+    pub fn is_tradable(&self, instrument: &Arc<Instrument>) -> bool {
+        self.members.contains(instrument)
+    }
+}
+
+impl EventTypeOf for UniverseUpdate {
+    fn event_type() -> EventType {
+        EventType::UniverseUpdate
+    }
+}
+
+impl From<Arc<UniverseUpdate>> for Event {
+    fn from(event: Arc<UniverseUpdate>) -> Self {
+        Event::UniverseUpdate(event)
+    }
+}
+
+impl fmt::Display for UniverseUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbols = |instruments: &[Arc<Instrument>]| {
+            instruments.iter().map(|i| i.symbol.as_str()).collect::<Vec<_>>().join(",")
+        };
+        write!(
+            f,
+            "universe={} members={} warming=[{}] joined=[{}] left=[{}]",
+            self.universe,
+            self.members.len(),
+            symbols(&self.warming),
+            symbols(&self.joined),
+            symbols(&self.left)
+        )
+    }
+}
@@ -0,0 +1,123 @@
+use std::{fmt, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::{ExecutionOrderId, Instrument, Venue};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum VenueCalendarEventType {
+    /// Venue down or in cancel-only mode
+    Maintenance,
+    /// Funding settlement of perpetuals
+    Funding,
+}
+
+/// Scheduled venue event quoting around is risky, like a maintenance or a funding timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct VenueCalendarEvent {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub venue: Arc<Venue>,
+    /// Instrument the event is about, the whole venue without one
+    #[builder(default)]
+    pub instrument: Option<Arc<Instrument>>,
+    pub event_type: VenueCalendarEventType,
+    pub start: OffsetDateTime,
+    /// Same as the start for events at a single point in time like funding
+    pub end: OffsetDateTime,
+}
+
+impl VenueCalendarEvent {
+    pub fn applies_to(&self, instrument: &Instrument) -> bool {
+        match &self.instrument {
+            Some(i) => i.id == instrument.id,
+            None => self.venue.id == instrument.venue.id,
+        }
+    }
+}
+
+impl EventTypeOf for VenueCalendarEvent {
+    fn event_type() -> EventType {
+        EventType::VenueCalendarEvent
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        self.instrument.as_ref()
+    }
+}
+
+impl From<Arc<VenueCalendarEvent>> for Event {
+    fn from(event: Arc<VenueCalendarEvent>) -> Self {
+        Event::VenueCalendarEvent(event)
+    }
+}
+
+impl fmt::Display for VenueCalendarEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scope = match &self.instrument {
+            Some(instrument) => instrument.symbol.clone(),
+            None => self.venue.name.clone(),
+        };
+        write!(f, "{} on {} from {} to {}", self.event_type, scope, self.start, self.end)
+    }
+}
+
+/// How maker orders are handled while quoting is paused around a venue event.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum QuotingPauseMode {
+    /// Cancel the resting maker orders and reject new ones
+    PullQuotes,
+    /// Send new maker orders as taker orders
+    TakerOnly,
+}
+
+/// Published by the order manager when quoting pauses in an instrument, with the resting maker orders to cancel.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct QuotesPulled {
+    pub event_time: OffsetDateTime,
+    pub instrument: Arc<Instrument>,
+    pub event_type: VenueCalendarEventType,
+    /// Quoting resumes after this time
+    pub until: OffsetDateTime,
+    pub orders: Vec<ExecutionOrderId>,
+}
+
+impl EventTypeOf for QuotesPulled {
+    fn event_type() -> EventType {
+        EventType::QuotesPulled
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<QuotesPulled>> for Event {
+    fn from(event: Arc<QuotesPulled>) -> Self {
+        Event::QuotesPulled(event)
+    }
+}
+
+impl fmt::Display for QuotesPulled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} quotes pulled in {} for {} until {}",
+            self.orders.len(),
+            self.instrument,
+            self.event_type,
+            self.until
+        )
+    }
+}
@@ -1,5 +1,6 @@
 use std::{fmt, sync::Arc};
 
+use serde::{Deserialize, Serialize};
 use sqlx::Type;
 use strum::Display;
 use time::OffsetDateTime;
@@ -9,12 +10,15 @@ use uuid::Uuid;
 
 use crate::{types::Commission, Event, EventType, EventTypeOf, Price, Quantity};
 
-use super::{Asset, ExecutionOrder, ExecutionOrderType, Instrument, MarketSide, Portfolio, VenueOrderFill};
+use super::{
+    Asset, ExecutionOrder, ExecutionOrderType, Instrument, LiquidityRole, MarketSide, Portfolio, VenueOrderFill,
+};
 
 pub type VenueOrderId = Uuid;
 
-#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Type)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 #[sqlx(type_name = "venue_order_type", rename_all = "snake_case")]
 pub enum VenueOrderType {
     Market,
@@ -39,8 +43,9 @@ impl From<ExecutionOrderType> for VenueOrderType {
     }
 }
 
-#[derive(Debug, Display, Clone, Copy, Type)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 #[sqlx(type_name = "venue_order_time_in_force", rename_all = "snake_case")]
 pub enum VenueOrderTimeInForce {
     Gtc,
@@ -50,8 +55,9 @@ pub enum VenueOrderTimeInForce {
     Gtd,
 }
 
-#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Type)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 #[sqlx(type_name = "venue_order_status", rename_all = "snake_case")]
 pub enum VenueOrderStatus {
     New,
@@ -65,6 +71,21 @@ pub enum VenueOrderStatus {
     Expired,
 }
 
+impl VenueOrderStatus {
+    /// True once the order can't trade anymore.
+    pub fn is_finalized(&self) -> bool {
+        matches!(
+            self,
+            VenueOrderStatus::PartiallyFilledCanceled
+                | VenueOrderStatus::PartiallyFilledExpired
+                | VenueOrderStatus::Filled
+                | VenueOrderStatus::Canceled
+                | VenueOrderStatus::Rejected
+                | VenueOrderStatus::Expired
+        )
+    }
+}
+
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct VenueOrder {
     #[builder(default = Uuid::new_v4())]
@@ -75,6 +96,12 @@ pub struct VenueOrder {
     pub order_type: VenueOrderType,
     #[builder(default = VenueOrderTimeInForce::Gtc)]
     pub time_in_force: VenueOrderTimeInForce,
+    /// Time a good-till-date order expires at if it is still open
+    #[builder(default)]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Only reduce the position, the venue never lets the order open or grow it
+    #[builder(default)]
+    pub reduce_only: bool,
     pub price: Price,
     pub quantity: Quantity,
     #[builder(default = Price::ZERO)]
@@ -124,6 +151,19 @@ impl VenueOrder {
         }
     }
 
+    pub fn expire(&mut self) {
+        match self.status {
+            VenueOrderStatus::Placed => self.status = VenueOrderStatus::Expired,
+            VenueOrderStatus::PartiallyFilled => self.status = VenueOrderStatus::PartiallyFilledExpired,
+            _ => error!("Cannot expire order in state {}", self.status),
+        }
+    }
+
+    /// True if the order is good till a date that passed.
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.time_in_force == VenueOrderTimeInForce::Gtd && self.expires_at.is_some_and(|t| t <= now)
+    }
+
     pub fn remaining_quantity(&self) -> Quantity {
         self.quantity - self.filled_quantity
     }
@@ -182,6 +222,8 @@ impl From<ExecutionOrder> for VenueOrder {
             side: order.side,
             order_type: order.order_type.into(),
             time_in_force: VenueOrderTimeInForce::Gtc,
+            expires_at: None,
+            reduce_only: false,
             price: order.price,
             quantity: order.quantity,
             fill_price: Price::ZERO,
@@ -198,6 +240,10 @@ impl EventTypeOf for VenueOrder {
     fn event_type() -> EventType {
         EventType::VenueOrder
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<VenueOrder>> for Event {
@@ -239,20 +285,49 @@ pub struct VenueOrderUpdate {
     pub last_fill_price: Price,
     pub last_fill_quantity: Quantity,
     pub status: VenueOrderStatus,
+    /// Liquidity role of the last fill
+    #[builder(default)]
+    pub liquidity: LiquidityRole,
     pub commission_asset: Option<Arc<Asset>>,
+    /// Commission of the last fill
     pub commission: Commission,
+    /// Time the venue acknowledged or matched the order as reported by the venue, none if it didn't report one
+    #[builder(default)]
+    pub exchange_time: Option<OffsetDateTime>,
+    /// Time the update arrived at this instance
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub received_at: OffsetDateTime,
 }
 
 impl VenueOrderUpdate {
     pub fn total_value(&self) -> Price {
         self.price * self.quantity * self.instrument.contract_size
     }
+
+    /// Commission of the last fill in the quote asset of the instrument, None if the venue charged it in another
+    /// asset. Updates without a commission asset are taken to be charged in the quote asset.
+    pub fn quote_commission(&self) -> Option<Commission> {
+        match &self.commission_asset {
+            Some(asset) if asset.id != self.instrument.quote_asset.id => None,
+            _ => Some(self.commission),
+        }
+    }
+
+    /// Time the update arrived later than the venue reports it happened, negative if the clock of the venue is ahead
+    /// of ours. None if the venue didn't report a time.
+    pub fn clock_skew(&self) -> Option<time::Duration> {
+        self.exchange_time.map(|t| self.received_at - t)
+    }
 }
 
 impl EventTypeOf for VenueOrderUpdate {
     fn event_type() -> EventType {
         EventType::VenueOrderUpdate
     }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
 }
 
 impl From<Arc<VenueOrderUpdate>> for Event {
@@ -276,3 +351,84 @@ impl fmt::Display for VenueOrderUpdate {
         )
     }
 }
+
+/// Published when the venue expires an order because its time in force ran out, like an immediate-or-cancel order
+/// that couldn't fill completely or a good-till-date order past its expiry.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct VenueOrderExpired {
+    pub event_time: OffsetDateTime,
+    pub order_id: VenueOrderId,
+    pub instrument: Arc<Instrument>,
+    pub time_in_force: VenueOrderTimeInForce,
+    pub filled_quantity: Quantity,
+    /// Quantity that was left open and won't fill anymore
+    pub expired_quantity: Quantity,
+}
+
+impl EventTypeOf for VenueOrderExpired {
+    fn event_type() -> EventType {
+        EventType::VenueOrderExpired
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<VenueOrderExpired>> for Event {
+    fn from(event: Arc<VenueOrderExpired>) -> Self {
+        Event::VenueOrderExpired(event)
+    }
+}
+
+impl fmt::Display for VenueOrderExpired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order={} instrument={} time_in_force={} filled={} expired={}",
+            self.order_id, self.instrument, self.time_in_force, self.filled_quantity, self.expired_quantity
+        )
+    }
+}
+
+/// Published when the venue refuses an order on arrival, like a post-only order that would take liquidity or a
+/// reduce-only order without a position to reduce.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct VenueOrderRejected {
+    pub event_time: OffsetDateTime,
+    pub order_id: VenueOrderId,
+    pub instrument: Arc<Instrument>,
+    /// Error code of the venue, like -4164 for Binance orders below the minimum notional
+    #[builder(default)]
+    pub code: Option<i64>,
+    pub reason: String,
+}
+
+impl EventTypeOf for VenueOrderRejected {
+    fn event_type() -> EventType {
+        EventType::VenueOrderRejected
+    }
+
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        Some(&self.instrument)
+    }
+}
+
+impl From<Arc<VenueOrderRejected>> for Event {
+    fn from(event: Arc<VenueOrderRejected>) -> Self {
+        Event::VenueOrderRejected(event)
+    }
+}
+
+impl fmt::Display for VenueOrderRejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "order={} instrument={} code={} reason={}",
+            self.order_id,
+            self.instrument,
+            self.code.map(|c| c.to_string()).unwrap_or_default(),
+            self.reason
+        )
+    }
+}
@@ -1,7 +1,9 @@
 use std::{fmt, sync::Arc};
 
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use strum::Display;
 use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 
@@ -10,7 +12,17 @@ use crate::{
     Notional, Price, Quantity,
 };
 
-use super::{Instrument, MarketSide, VenueOrder};
+use super::{Asset, Instrument, MarketSide, VenueOrder};
+
+/// Whether a fill added liquidity to the book or took it.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidityRole {
+    Maker,
+    #[default]
+    Taker,
+}
 
 #[derive(Debug, Clone, TypedBuilder, FromRow)]
 
@@ -22,7 +34,18 @@ pub struct VenueOrderFill {
     pub side: MarketSide,
     pub price: Price,
     pub quantity: Quantity,
+    #[builder(default)]
+    pub liquidity: LiquidityRole,
+    /// Asset the commission is charged in as reported by the venue
+    #[builder(default)]
+    pub commission_asset: Option<Arc<Asset>>,
     pub commission: Commission,
+    /// Time the venue matched the fill as reported by the venue
+    #[builder(default)]
+    pub exchange_time: Option<OffsetDateTime>,
+    /// Time the fill arrived at this instance
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub received_at: OffsetDateTime,
 }
 
 impl VenueOrderFill {
@@ -36,6 +59,15 @@ impl VenueOrderFill {
         self.price * self.quantity * self.instrument.contract_size
     }
 
+    /// Commission as a fraction of the notional value, the effective fee rate of the fill.
+    pub fn commission_rate(&self) -> Decimal {
+        let notional = self.notional_value();
+        match notional.is_zero() {
+            true => Decimal::ZERO,
+            false => self.commission / notional,
+        }
+    }
+
     pub fn total_cost(&self) -> Decimal {
         self.market_value() - self.commission
     }
@@ -52,8 +84,8 @@ impl fmt::Display for VenueOrderFill {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "instrument={} side={} price={} quantity={} commission={}",
-            self.instrument, self.side, self.price, self.quantity, self.commission
+            "instrument={} side={} price={} quantity={} liquidity={} commission={}",
+            self.instrument, self.side, self.price, self.quantity, self.liquidity, self.commission
         )
     }
 }
@@ -0,0 +1,101 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{Event, EventType, EventTypeOf};
+
+use super::{Asset, Portfolio, Transaction, TransactionType};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WalletTransferDirection {
+    /// Capital sent from a funding wallet to the exchange
+    Deposit,
+    /// Capital sent from the exchange back to a funding wallet
+    Withdrawal,
+}
+
+/// Capital moved on chain between a funding wallet and the exchange. Booked on the ledger as a deposit or withdrawal,
+/// so the change of the venue balance is not taken for pnl.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder)]
+pub struct WalletTransfer {
+    #[builder(default = Uuid::new_v4())]
+    pub id: Uuid,
+    /// Time of the block the transfer was included in
+    pub event_time: OffsetDateTime,
+    pub portfolio: Arc<Portfolio>,
+    /// Strategy the capital is given to or taken from
+    pub strategy: String,
+    pub asset: Arc<Asset>,
+    pub direction: WalletTransferDirection,
+    pub quantity: Decimal,
+    /// Chain the transfer was made on, like `ethereum`
+    pub chain: String,
+    /// Tracked funding wallet
+    pub wallet: String,
+    /// Exchange address on the other side of the transfer
+    pub counterparty: String,
+    pub tx_hash: String,
+}
+
+impl WalletTransfer {
+    /// Signed quantity the transfer adds to the exchange balance.
+    pub fn net_quantity(&self) -> Decimal {
+        match self.direction {
+            WalletTransferDirection::Deposit => self.quantity,
+            WalletTransferDirection::Withdrawal => -self.quantity,
+        }
+    }
+
+    pub fn to_transaction(&self) -> Transaction {
+        Transaction::builder()
+            .event_time(self.event_time)
+            .transaction_group_id(self.id)
+            .portfolio(self.portfolio.clone())
+            .asset(Some(self.asset.clone()))
+            .instrument(None)
+            .transaction_type(match self.direction {
+                WalletTransferDirection::Deposit => TransactionType::Deposit,
+                WalletTransferDirection::Withdrawal => TransactionType::Withdrawal,
+            })
+            .price(None)
+            .quantity(self.net_quantity())
+            .total_value(self.net_quantity())
+            .build()
+    }
+}
+
+impl EventTypeOf for WalletTransfer {
+    fn event_type() -> EventType {
+        EventType::WalletTransfer
+    }
+}
+
+impl From<Arc<WalletTransfer>> for Event {
+    fn from(event: Arc<WalletTransfer>) -> Self {
+        Event::WalletTransfer(event)
+    }
+}
+
+impl fmt::Display for WalletTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} on {} wallet={} counterparty={} strategy={} tx={}",
+            self.direction,
+            self.quantity,
+            self.asset.symbol,
+            self.chain,
+            self.wallet,
+            self.counterparty,
+            self.strategy,
+            self.tx_hash
+        )
+    }
+}
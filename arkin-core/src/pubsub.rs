@@ -1,6 +1,6 @@
 use std::fmt;
-use std::sync::Arc;
-use std::{any::Any, time::Duration};
+use std::sync::{Arc, RwLock};
+use std::{any::Any, collections::HashSet, time::Duration};
 
 use dashmap::DashMap;
 use rust_decimal::Decimal;
@@ -12,12 +12,52 @@ use typed_builder::TypedBuilder;
 use strum::EnumDiscriminants;
 
 use crate::{
-    Balance, BalanceUpdate, Book, ExecutionOrder, Insight, Instrument, Position, PositionUpdate, Signal, Tick, Trade,
-    VenueOrder, VenueOrderUpdate,
+    AccountTransfer, AccountTransferUpdate, Balance, BalanceUpdate, Book, CalendarEvent, CapitalTransfer,
+    ControlAccess, CovarianceUpdate, DailyPerformance, ExecutionExperimentReport, ExecutionMetrics, ExecutionOrder,
+    FillMarkout, Insight, Instrument, InstrumentStatusUpdate, Liquidation, ManualOrder, Metric, OperatorAction,
+    OrderLatency, Position, PositionUpdate, Prediction, QuotesPulled, RateLimitExceeded, Rebalance, SettlementReport,
+    Signal, Tick, Trade, TradingControl, UniverseUpdate, Venue, VenueCalendarEvent, VenueOrder, VenueOrderExpired,
+    VenueOrderRejected, VenueOrderUpdate, WalletTransfer,
 };
 
+const CHANNEL_CAPACITY: usize = 1000000;
+
 pub trait EventTypeOf: fmt::Debug + Send + Sync + Clone + 'static {
     fn event_type() -> EventType;
+
+    /// Instrument the event is about, used by instrument filters of subscriptions.
+    fn instrument(&self) -> Option<&Arc<Instrument>> {
+        None
+    }
+}
+
+/// Narrows a subscription down to some event types and instruments, both match everything when not set. Events
+/// without an instrument, like interval ticks, pass the instrument filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    pub event_types: Option<HashSet<EventType>>,
+    pub instruments: Option<HashSet<Arc<Instrument>>>,
+}
+
+impl EventFilter {
+    pub fn event_types(mut self, event_types: impl IntoIterator<Item = EventType>) -> Self {
+        self.event_types = Some(event_types.into_iter().collect());
+        self
+    }
+
+    pub fn instruments(mut self, instruments: impl IntoIterator<Item = Arc<Instrument>>) -> Self {
+        self.instruments = Some(instruments.into_iter().collect());
+        self
+    }
+
+    pub fn matches(&self, event_type: EventType, instrument: Option<&Arc<Instrument>>) -> bool {
+        let event_type = self.event_types.as_ref().is_none_or(|types| types.contains(&event_type));
+        let instrument = match (&self.instruments, instrument) {
+            (Some(instruments), Some(instrument)) => instruments.contains(instrument),
+            _ => true,
+        };
+        event_type && instrument
+    }
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -86,6 +126,152 @@ impl From<Arc<AllocationTick>> for Event {
     }
 }
 
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SimulationProgress {
+    pub event_time: OffsetDateTime,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+    pub fraction: f64,
+    pub events: u64,
+    pub events_per_sec: f64,
+    pub elapsed: Duration,
+    pub eta: Option<Duration>,
+}
+
+impl EventTypeOf for SimulationProgress {
+    fn event_type() -> EventType {
+        EventType::SimulationProgress
+    }
+}
+
+impl From<Arc<SimulationProgress>> for Event {
+    fn from(progress: Arc<SimulationProgress>) -> Self {
+        Event::SimulationProgress(progress)
+    }
+}
+
+/// Published when a replayed data stream of a venue has no more data.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct StreamEnded {
+    pub event_time: OffsetDateTime,
+    pub venue: Arc<Venue>,
+    pub channel: String,
+}
+
+impl EventTypeOf for StreamEnded {
+    fn event_type() -> EventType {
+        EventType::StreamEnded
+    }
+}
+
+impl From<Arc<StreamEnded>> for Event {
+    fn from(event: Arc<StreamEnded>) -> Self {
+        Event::StreamEnded(event)
+    }
+}
+
+/// Published by the sim ingestor for a stretch without data on a replayed channel, from its last event before the
+/// gap to the first one after it.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct DataGap {
+    /// Start of the gap
+    pub event_time: OffsetDateTime,
+    pub channel: String,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+    /// The simulation skips over the gap instead of replaying the other channels through it
+    #[builder(default)]
+    pub skipped: bool,
+}
+
+impl EventTypeOf for DataGap {
+    fn event_type() -> EventType {
+        EventType::DataGap
+    }
+}
+
+impl From<Arc<DataGap>> for Event {
+    fn from(event: Arc<DataGap>) -> Self {
+        Event::DataGap(event)
+    }
+}
+
+impl fmt::Display for DataGap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "channel={} start={} end={} duration={} skipped={}",
+            self.channel,
+            self.start,
+            self.end,
+            self.end - self.start,
+            self.skipped
+        )
+    }
+}
+
+/// Published once all streams of a simulation have ended, so services can flush their state and
+/// finalize the run.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SimulationFinished {
+    pub event_time: OffsetDateTime,
+}
+
+impl EventTypeOf for SimulationFinished {
+    fn event_type() -> EventType {
+        EventType::SimulationFinished
+    }
+}
+
+impl From<Arc<SimulationFinished>> for Event {
+    fn from(event: Arc<SimulationFinished>) -> Self {
+        Event::SimulationFinished(event)
+    }
+}
+
+/// Published by the engine when a service handler runs longer than the hard deadline of the watchdog.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ServiceStalled {
+    pub event_time: OffsetDateTime,
+    pub service: String,
+    pub event: String,
+    pub elapsed: Duration,
+}
+
+impl EventTypeOf for ServiceStalled {
+    fn event_type() -> EventType {
+        EventType::ServiceStalled
+    }
+}
+
+impl From<Arc<ServiceStalled>> for Event {
+    fn from(event: Arc<ServiceStalled>) -> Self {
+        Event::ServiceStalled(event)
+    }
+}
+
+/// Published by the consistency checker when the state of two services disagrees for longer than a single check.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ConsistencyViolation {
+    pub event_time: OffsetDateTime,
+    /// Name of the check that failed, like `positions` or `open_orders`
+    pub check: String,
+    /// One line per difference found
+    pub diffs: Vec<String>,
+}
+
+impl EventTypeOf for ConsistencyViolation {
+    fn event_type() -> EventType {
+        EventType::ConsistencyViolation
+    }
+}
+
+impl From<Arc<ConsistencyViolation>> for Event {
+    fn from(event: Arc<ConsistencyViolation>) -> Self {
+        Event::ConsistencyViolation(event)
+    }
+}
+
 #[derive(Debug, Clone, EnumDiscriminants)]
 #[strum_discriminants(name(EventType))]
 #[strum_discriminants(derive(Hash))]
@@ -93,6 +279,7 @@ pub enum Event {
     IntervalTick(Arc<IntervalTick>),
     Tick(Arc<Tick>),
     Trade(Arc<Trade>),
+    Metric(Arc<Metric>),
     Book(Arc<Book>),
     Balance(Arc<Balance>),
     BalanceUpdate(Arc<BalanceUpdate>),
@@ -100,11 +287,43 @@ pub enum Event {
     PositionUpdate(Arc<PositionUpdate>),
     Insight(Arc<Insight>),
     InsightTick(Arc<InsightTick>),
+    Prediction(Arc<Prediction>),
     Signal(Arc<Signal>),
     AllocationTick(Arc<AllocationTick>),
+    Rebalance(Arc<Rebalance>),
+    DailyPerformance(Arc<DailyPerformance>),
+    CapitalTransfer(Arc<CapitalTransfer>),
+    WalletTransfer(Arc<WalletTransfer>),
+    AccountTransfer(Arc<AccountTransfer>),
+    AccountTransferUpdate(Arc<AccountTransferUpdate>),
+    InstrumentStatusUpdate(Arc<InstrumentStatusUpdate>),
+    VenueCalendarEvent(Arc<VenueCalendarEvent>),
+    CalendarEvent(Arc<CalendarEvent>),
+    QuotesPulled(Arc<QuotesPulled>),
     ExecutionOrderNew(Arc<ExecutionOrder>),
     VenueOrder(Arc<VenueOrder>),
     VenueOrderUpdate(Arc<VenueOrderUpdate>),
+    VenueOrderExpired(Arc<VenueOrderExpired>),
+    VenueOrderRejected(Arc<VenueOrderRejected>),
+    Liquidation(Arc<Liquidation>),
+    RateLimitExceeded(Arc<RateLimitExceeded>),
+    OrderLatency(Arc<OrderLatency>),
+    FillMarkout(Arc<FillMarkout>),
+    ExecutionMetrics(Arc<ExecutionMetrics>),
+    ExecutionExperimentReport(Arc<ExecutionExperimentReport>),
+    SimulationProgress(Arc<SimulationProgress>),
+    StreamEnded(Arc<StreamEnded>),
+    DataGap(Arc<DataGap>),
+    SimulationFinished(Arc<SimulationFinished>),
+    SettlementReport(Arc<SettlementReport>),
+    ServiceStalled(Arc<ServiceStalled>),
+    TradingControl(Arc<TradingControl>),
+    ManualOrder(Arc<ManualOrder>),
+    ControlAccess(Arc<ControlAccess>),
+    OperatorAction(Arc<OperatorAction>),
+    UniverseUpdate(Arc<UniverseUpdate>),
+    CovarianceUpdate(Arc<CovarianceUpdate>),
+    ConsistencyViolation(Arc<ConsistencyViolation>),
 }
 
 impl Event {
@@ -113,32 +332,85 @@ impl Event {
     }
 }
 
-#[derive(Debug)]
+type FilteredSender = (EventFilter, Box<dyn Any + Send + Sync>);
+
+/// Channel of an event type whatever the type, to count the events it holds.
+trait Queue: fmt::Debug + Send + Sync {
+    fn queued(&self) -> usize;
+}
+
+impl<T: Send> Queue for Sender<T> {
+    fn queued(&self) -> usize {
+        self.len()
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct PubSub {
     pub event_senders: DashMap<EventType, Box<dyn Any + Send + Sync>>,
+    /// Subscribers of an event type that only receive the events passing their filter
+    filtered_senders: DashMap<EventType, Vec<FilteredSender>>,
+    /// Subscribers of several event types, receiving them as [`Event`]
+    event_streams: RwLock<Vec<(EventFilter, Sender<Event>)>>,
+    /// Channels of the event types and filtered subscribers
+    queues: RwLock<Vec<Box<dyn Queue>>>,
 }
 
 impl PubSub {
     pub fn new() -> Self {
-        Self {
-            event_senders: DashMap::new(),
-        }
+        Self::default()
     }
 
     pub fn subscribe<E: EventTypeOf>(&self) -> Receiver<Arc<E>> {
         let event_type = E::event_type();
         let sender_any = self.event_senders.entry(event_type).or_insert_with(|| {
-            let (tx, _) = broadcast::channel::<Arc<E>>(1000000);
+            let (tx, _) = broadcast::channel::<Arc<E>>(CHANNEL_CAPACITY);
             info!("New subscriber to event: {:?}", event_type);
+            self.queues.write().expect("Queues lock poisoned").push(Box::new(tx.clone()));
             Box::new(tx)
         });
         let sender = sender_any.downcast_ref::<Sender<Arc<E>>>().expect("Type mismatch");
         sender.subscribe()
     }
 
-    pub fn publish<E: EventTypeOf>(&self, event: Arc<E>) {
+    /// Subscribe to the events of a type passing the filter only, so a subscriber interested in a few instruments
+    /// isn't woken up by the events of all others.
+    pub fn subscribe_filtered<E: EventTypeOf>(&self, filter: EventFilter) -> Receiver<Arc<E>> {
+        let event_type = E::event_type();
+        let (tx, rx) = broadcast::channel::<Arc<E>>(CHANNEL_CAPACITY);
+        info!("New filtered subscriber to event: {:?}", event_type);
+        self.queues.write().expect("Queues lock poisoned").push(Box::new(tx.clone()));
+        self.filtered_senders
+            .entry(event_type)
+            .or_default()
+            .push((filter, Box::new(tx)));
+        rx
+    }
+
+    /// Subscribe to all events passing the filter in a single stream, restrict it to the event types of interest.
+    pub fn subscribe_events(&self, filter: EventFilter) -> Receiver<Event> {
+        let (tx, rx) = broadcast::channel::<Event>(CHANNEL_CAPACITY);
+        info!("New event stream subscriber for: {:?}", filter.event_types);
+        let mut streams = self.event_streams.write().expect("Event streams lock poisoned");
+        streams.retain(|(_, sender)| sender.receiver_count() > 0);
+        streams.push((filter, tx));
+        rx
+    }
+
+    /// Events published that not every subscriber received yet, zero once all subscribers caught up.
+    pub fn queued(&self) -> usize {
+        let queues = self.queues.read().expect("Queues lock poisoned");
+        let streams = self.event_streams.read().expect("Event streams lock poisoned");
+        queues.iter().map(|q| q.queued()).sum::<usize>() + streams.iter().map(|(_, s)| s.len()).sum::<usize>()
+    }
+
+    pub fn publish<E: EventTypeOf>(&self, event: Arc<E>)
+    where
+        Arc<E>: Into<Event>,
+    {
         let event_type = E::event_type();
         debug!("Publishing event: {:?}", event_type);
+        self.publish_filtered(event_type, &event);
         if let Some(sender_any) = self.event_senders.get(&event_type) {
             let sender = sender_any.downcast_ref::<Sender<Arc<E>>>().expect("Type mismatch");
             // Check if we have any subscribers
@@ -150,4 +422,107 @@ impl PubSub {
             }
         }
     }
+
+    fn publish_filtered<E: EventTypeOf>(&self, event_type: EventType, event: &Arc<E>)
+    where
+        Arc<E>: Into<Event>,
+    {
+        let instrument = event.instrument();
+        if let Some(mut senders) = self.filtered_senders.get_mut(&event_type) {
+            senders.retain(|(filter, sender_any)| {
+                let sender = sender_any.downcast_ref::<Sender<Arc<E>>>().expect("Type mismatch");
+                if sender.receiver_count() == 0 {
+                    return false;
+                }
+                if filter.matches(event_type, instrument) {
+                    if let Err(e) = sender.send(event.clone()) {
+                        error!("Failed to publish filtered event: {:?}", e);
+                    }
+                }
+                true
+            });
+        }
+
+        let streams = self.event_streams.read().expect("Event streams lock poisoned");
+        for (filter, sender) in streams.iter().filter(|(filter, _)| filter.matches(event_type, instrument)) {
+            // Closed streams are dropped when the next stream subscribes
+            if sender.receiver_count() == 0 {
+                continue;
+            }
+            if let Err(e) = sender.send(event.clone().into()) {
+                error!("Failed to publish event to stream {:?}: {:?}", filter.event_types, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::test_utils::{test_inst_binance_btc_usdt_perp, test_inst_binance_eth_usdt_perp};
+
+    fn tick(instrument: Arc<Instrument>) -> Arc<Tick> {
+        Tick::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .instrument(instrument)
+            .tick_id(1)
+            .bid_price(Decimal::ONE)
+            .bid_quantity(Decimal::ONE)
+            .ask_price(Decimal::ONE)
+            .ask_quantity(Decimal::ONE)
+            .build()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscription() {
+        let pubsub = PubSub::new();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let mut all = pubsub.subscribe::<Tick>();
+        let mut btc_ticks = pubsub.subscribe_filtered::<Tick>(EventFilter::default().instruments([btc.clone()]));
+
+        pubsub.publish::<Tick>(tick(eth.clone()));
+        pubsub.publish::<Tick>(tick(btc.clone()));
+        assert_eq!(pubsub.queued(), 3);
+
+        assert_eq!(all.recv().await.unwrap().instrument, eth);
+        assert_eq!(all.recv().await.unwrap().instrument, btc);
+        assert_eq!(btc_ticks.recv().await.unwrap().instrument, btc);
+        assert!(btc_ticks.try_recv().is_err());
+        assert_eq!(pubsub.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let pubsub = PubSub::new();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let filter = EventFilter::default()
+            .event_types([EventType::Tick, EventType::IntervalTick])
+            .instruments([btc.clone()]);
+        let mut events = pubsub.subscribe_events(filter);
+
+        pubsub.publish::<Tick>(tick(test_inst_binance_eth_usdt_perp()));
+        pubsub.publish::<SimulationFinished>(
+            SimulationFinished::builder()
+                .event_time(datetime!(2025-01-01 00:00 UTC))
+                .build()
+                .into(),
+        );
+        pubsub.publish::<Tick>(tick(btc.clone()));
+        pubsub.publish::<IntervalTick>(
+            IntervalTick::builder()
+                .event_time(datetime!(2025-01-01 00:01 UTC))
+                .instruments(vec![])
+                .frequency(Duration::from_secs(60))
+                .build()
+                .into(),
+        );
+
+        assert_eq!(events.recv().await.unwrap().event_type(), EventType::Tick);
+        assert_eq!(events.recv().await.unwrap().event_type(), EventType::IntervalTick);
+        assert!(events.try_recv().is_err());
+    }
 }
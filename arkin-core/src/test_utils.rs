@@ -1,15 +1,19 @@
+mod market_data;
+
 use std::{str::FromStr, sync::Arc};
 
 use rust_decimal_macros::dec;
-use time::OffsetDateTime;
+use time::{macros::datetime, OffsetDateTime};
 use uuid::Uuid;
 
 use crate::{
     Asset, AssetType, ExecutionOrder, ExecutionOrderStatus, ExecutionOrderType, Instance, InstanceStatus, InstanceType,
-    Instrument, InstrumentStatus, InstrumentType, MarketSide, Portfolio, Price, Quantity, Strategy, Tick, Venue,
-    VenueOrder, VenueOrderStatus, VenueOrderTimeInForce, VenueOrderType, VenueType,
+    Instrument, InstrumentOptionType, InstrumentStatus, InstrumentType, MarketSide, Pipeline, Portfolio, Price,
+    Quantity, Strategy, Tick, Venue, VenueOrder, VenueOrderStatus, VenueOrderTimeInForce, VenueOrderType, VenueType,
 };
 
+pub use market_data::*;
+
 pub fn test_btc_asset() -> Arc<Asset> {
     let asset = Asset::builder()
         .id(Uuid::parse_str("894ff9df-e76e-4b2e-aaec-49988de26a84").expect("Invalid UUID"))
@@ -99,6 +103,33 @@ pub fn test_inst_binance_eth_usdt_perp() -> Arc<Instrument> {
     Arc::new(instrument)
 }
 
+/// BTC call option on Binance, strike 100000 expiring 2025-03-28 08:00 UTC, on the BTCUSDT perpetual.
+pub fn test_inst_binance_btc_usdt_call() -> Arc<Instrument> {
+    let instrument = Instrument::builder()
+        .id(Uuid::from_str("c3a3b1f0-5d8e-4c1a-9f3e-2b7d6a1e4f10").expect("Invalid UUID"))
+        .secondary_id(3)
+        .venue(test_binance_venue())
+        .symbol("option-btc-usdt-20250328-100000-c@binance".into())
+        .venue_symbol("BTC-250328-100000-C".into())
+        .instrument_type(InstrumentType::Option)
+        .base_asset(test_btc_asset())
+        .quote_asset(test_usdt_asset())
+        .maturity(Some(datetime!(2025-03-28 08:00 UTC)))
+        .strike(Some(dec!(100000)))
+        .option_type(Some(InstrumentOptionType::Call))
+        .underlying(Some(test_inst_binance_btc_usdt_perp()))
+        .contract_size(dec!(1.0))
+        .price_precision(1 as u32)
+        .quantity_precision(2 as u32)
+        .base_precision(8 as u32)
+        .quote_precision(8 as u32)
+        .tick_size(dec!(5))
+        .lot_size(dec!(0.01))
+        .status(InstrumentStatus::Trading)
+        .build();
+    Arc::new(instrument)
+}
+
 pub fn test_tick(
     instrument: Arc<Instrument>,
     bid_price: Price,
@@ -139,6 +170,17 @@ pub fn test_portfolio() -> Arc<Portfolio> {
     Arc::new(portfolio)
 }
 
+pub fn test_pipeline() -> Arc<Pipeline> {
+    let pipeline = Pipeline::builder()
+        .id(Uuid::from_str("f031d4e2-2ada-4651-83fa-aef515accb29").expect("Invalid UUID"))
+        .name("Test Pipeline".into())
+        .description("This Pipeline is for testing purposes".into())
+        .created_at(OffsetDateTime::now_utc())
+        .updated_at(OffsetDateTime::now_utc())
+        .build();
+    Arc::new(pipeline)
+}
+
 pub fn test_strategy() -> Arc<Strategy> {
     let strategy = Strategy::builder()
         .id(Uuid::from_str("a2d0951e-9bc6-47a4-b803-e4e0bb4e98a3").expect("Invalid UUID"))
@@ -0,0 +1,327 @@
+use std::{sync::Arc, time::Duration};
+
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use time::{macros::datetime, OffsetDateTime};
+use typed_builder::TypedBuilder;
+
+use crate::{Instrument, MarketSide, Price, Quantity, Tick, Trade};
+
+use super::test_inst_binance_btc_usdt_perp;
+
+/// Relative trading activity over the (UTC) hours of the day.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VolumeProfile {
+    /// Same activity every hour
+    Flat,
+    /// Quiet during the Asian night, busy around the European and US sessions
+    Intraday,
+    /// Relative activity for each hour starting at 00:00 UTC
+    Custom([f64; 24]),
+}
+
+impl VolumeProfile {
+    const INTRADAY: [f64; 24] = [
+        0.6, 0.5, 0.5, 0.5, 0.5, 0.6, 0.7, 0.9, 1.2, 1.2, 1.1, 1.0, 1.1, 1.4, 1.8, 1.9, 1.7, 1.4, 1.2, 1.1, 1.0, 0.9,
+        0.8, 0.7,
+    ];
+
+    /// Activity in the given hour relative to the daily average.
+    pub fn weight(&self, hour: u8) -> f64 {
+        let weights = match self {
+            VolumeProfile::Flat => return 1.,
+            VolumeProfile::Intraday => &Self::INTRADAY,
+            VolumeProfile::Custom(w) => w,
+        };
+        let mean = weights.iter().sum::<f64>() / 24.;
+        match mean > 0. {
+            true => weights[hour as usize % 24] / mean,
+            false => 0.,
+        }
+    }
+}
+
+/// Generated market data, both streams are sorted by event time.
+#[derive(Debug, Clone, Default)]
+pub struct MarketData {
+    pub ticks: Vec<Arc<Tick>>,
+    pub trades: Vec<Arc<Trade>>,
+}
+
+/// Generates a reproducible tick and trade stream for tests that need realistic market data without a database.
+///
+/// The mid price follows a geometric random walk with the configured daily volatility, trades arrive as a poisson
+/// process scaled by the volume profile and nothing is emitted during the configured gaps. The same seed always
+/// produces the same data.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct MarketDataGenerator {
+    #[builder(default = test_inst_binance_btc_usdt_perp())]
+    instrument: Arc<Instrument>,
+    #[builder(default = datetime!(2024-06-01 00:00 UTC))]
+    start: OffsetDateTime,
+    #[builder(default = Duration::from_secs(86400))]
+    duration: Duration,
+    #[builder(default = dec!(60000))]
+    start_price: Price,
+    /// Standard deviation of the daily log return
+    #[builder(default = 0.03)]
+    volatility: f64,
+    /// Average number of trades per hour, scaled by the volume profile
+    #[builder(default = 3600.)]
+    trades_per_hour: f64,
+    #[builder(default = dec!(0.05))]
+    mean_trade_size: Quantity,
+    #[builder(default = VolumeProfile::Intraday)]
+    volume_profile: VolumeProfile,
+    #[builder(default = 1)]
+    spread_ticks: u32,
+    #[builder(default = Duration::from_secs(1))]
+    tick_interval: Duration,
+    /// Periods without any data (start inclusive, end exclusive)
+    #[builder(default)]
+    gaps: Vec<(OffsetDateTime, OffsetDateTime)>,
+    #[builder(default = 42)]
+    seed: u64,
+}
+
+impl MarketDataGenerator {
+    pub fn end(&self) -> OffsetDateTime {
+        self.start + self.duration
+    }
+
+    pub fn in_gap(&self, event_time: OffsetDateTime) -> bool {
+        self.gaps.iter().any(|(start, end)| event_time >= *start && event_time < *end)
+    }
+
+    pub fn generate(&self) -> MarketData {
+        let mut rng = SplitMix64::new(self.seed);
+        let mut data = MarketData::default();
+
+        let step = self.tick_interval.as_secs_f64();
+        let step_volatility = self.volatility * (step / 86400.).sqrt();
+        let tick_size = self.instrument.tick_size;
+        let lot_size = self.instrument.lot_size;
+        let mean_size = self.mean_trade_size.to_f64().unwrap_or(0.);
+
+        let mut mid = self.start_price.to_f64().unwrap_or(0.);
+        let mut event_time = self.start;
+        while event_time < self.end() {
+            // The price keeps moving during gaps, so the stream resumes with a jump like it would after an outage
+            mid *= (step_volatility * rng.normal() - step_volatility.powi(2) / 2.).exp();
+            let current = event_time;
+            event_time += self.tick_interval;
+            if self.in_gap(current) {
+                continue;
+            }
+
+            let bid = round_down(Decimal::from_f64(mid).unwrap_or(self.start_price), tick_size).max(tick_size);
+            let ask = bid + tick_size * Decimal::from(self.spread_ticks.max(1));
+            let tick = Tick::builder()
+                .event_time(current)
+                .instrument(self.instrument.clone())
+                .tick_id(data.ticks.len() as u64 + 1)
+                .bid_price(bid)
+                .bid_quantity(self.quantity(&mut rng, mean_size * 10., lot_size))
+                .ask_price(ask)
+                .ask_quantity(self.quantity(&mut rng, mean_size * 10., lot_size))
+                .build();
+            data.ticks.push(Arc::new(tick));
+
+            let rate = self.trades_per_hour * self.volume_profile.weight(current.hour()) * step / 3600.;
+            let mut offsets = (0..rng.poisson(rate)).map(|_| rng.uniform() * step).collect::<Vec<_>>();
+            offsets.sort_by(|a, b| a.total_cmp(b));
+            for offset in offsets {
+                let trade_time = current + Duration::from_secs_f64(offset);
+                if self.in_gap(trade_time) {
+                    continue;
+                }
+                let side = match rng.uniform() < 0.5 {
+                    true => MarketSide::Buy,
+                    false => MarketSide::Sell,
+                };
+                let trade = Trade::builder()
+                    .event_time(trade_time)
+                    .instrument(self.instrument.clone())
+                    .trade_id(data.trades.len() as u64 + 1)
+                    .side(side)
+                    .price(match side {
+                        MarketSide::Buy => ask,
+                        MarketSide::Sell => bid,
+                    })
+                    .quantity(self.quantity(&mut rng, mean_size, lot_size))
+                    .build();
+                data.trades.push(Arc::new(trade));
+            }
+        }
+        data
+    }
+
+    /// Exponentially distributed size rounded to the lot size, never below one lot.
+    fn quantity(&self, rng: &mut SplitMix64, mean: f64, lot_size: Decimal) -> Quantity {
+        let size = -mean * (1. - rng.uniform()).ln();
+        let size = Decimal::from_f64(size).unwrap_or(lot_size);
+        round_down(size, lot_size).max(lot_size)
+    }
+}
+
+fn round_down(value: Decimal, increment: Decimal) -> Decimal {
+    match increment.is_zero() {
+        true => value,
+        false => (value / increment).floor() * increment,
+    }
+}
+
+/// Small self contained rng so the generated data doesn't change with external crate versions.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal using Box-Muller
+    fn normal(&mut self) -> f64 {
+        let u1 = 1. - self.uniform();
+        let u2 = self.uniform();
+        (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos()
+    }
+
+    fn poisson(&mut self, lambda: f64) -> u64 {
+        if lambda <= 0. {
+            return 0;
+        }
+        // Normal approximation for busy periods, Knuth's method otherwise
+        if lambda > 30. {
+            return (lambda + lambda.sqrt() * self.normal()).round().max(0.) as u64;
+        }
+        let limit = (-lambda).exp();
+        let mut count = 0;
+        let mut product = self.uniform();
+        while product > limit {
+            count += 1;
+            product *= self.uniform();
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    #[test]
+    fn test_same_seed_same_data() {
+        let generator = MarketDataGenerator::builder()
+            .duration(Duration::from_secs(3600))
+            .seed(7)
+            .build();
+        let first = generator.generate();
+        let second = generator.generate();
+
+        assert!(!first.trades.is_empty());
+        assert_eq!(first.ticks.len(), 3600);
+        assert_eq!(first.trades.len(), second.trades.len());
+        assert!(first.trades.iter().zip(&second.trades).all(|(a, b)| a == b));
+        assert!(first.ticks.iter().zip(&second.ticks).all(|(a, b)| a.bid_price == b.bid_price));
+    }
+
+    #[test]
+    fn test_streams_are_sorted_and_on_grid() {
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let data = MarketDataGenerator::builder()
+            .duration(Duration::from_secs(7200))
+            .spread_ticks(3)
+            .build()
+            .generate();
+
+        assert!(data.ticks.windows(2).all(|w| w[0].event_time < w[1].event_time));
+        assert!(data.trades.windows(2).all(|w| w[0].event_time <= w[1].event_time));
+        for tick in &data.ticks {
+            assert_eq!(tick.ask_price - tick.bid_price, instrument.tick_size * dec!(3));
+            assert!((tick.bid_price % instrument.tick_size).is_zero());
+            assert!(tick.bid_quantity >= instrument.lot_size);
+        }
+        for trade in &data.trades {
+            assert!((trade.quantity % instrument.lot_size).is_zero());
+            assert!(trade.quantity >= instrument.lot_size);
+        }
+    }
+
+    #[test]
+    fn test_gaps_have_no_data() {
+        let start = datetime!(2024-06-01 00:00 UTC);
+        let gap = (datetime!(2024-06-01 10:00 UTC), datetime!(2024-06-01 12:30 UTC));
+        let data = MarketDataGenerator::builder()
+            .start(start)
+            .tick_interval(Duration::from_secs(5))
+            .gaps(vec![gap])
+            .build()
+            .generate();
+
+        assert!(data.ticks.iter().all(|t| t.event_time < gap.0 || t.event_time >= gap.1));
+        assert!(data.trades.iter().all(|t| t.event_time < gap.0 || t.event_time >= gap.1));
+        // Data resumes right after the gap
+        assert!(data.ticks.iter().any(|t| t.event_time == gap.1));
+    }
+
+    #[test]
+    fn test_volume_profile_shapes_activity() {
+        let data = MarketDataGenerator::builder()
+            .duration(Duration::from_secs(86400 * 3))
+            .tick_interval(Duration::from_secs(10))
+            .volume_profile(VolumeProfile::Intraday)
+            .build()
+            .generate();
+
+        let count = |hour: u8| data.trades.iter().filter(|t| t.event_time.hour() == hour).count();
+        // 15:00 UTC is almost four times as busy as 03:00 UTC
+        assert!(count(15) > count(3) * 3);
+
+        let flat = MarketDataGenerator::builder()
+            .duration(Duration::from_secs(86400))
+            .tick_interval(Duration::from_secs(10))
+            .volume_profile(VolumeProfile::Flat)
+            .trades_per_hour(1000.)
+            .build()
+            .generate();
+        // Poisson with a mean of 24000 trades over the day
+        assert!((flat.trades.len() as i64 - 24000).abs() < 1000);
+    }
+
+    #[test]
+    fn test_realized_volatility_matches_config() {
+        let data = MarketDataGenerator::builder()
+            .duration(Duration::from_secs(86400 * 10))
+            .tick_interval(Duration::from_secs(60))
+            .volatility(0.04)
+            .trades_per_hour(0.)
+            .build()
+            .generate();
+        assert!(data.trades.is_empty());
+
+        let returns = data
+            .ticks
+            .windows(2)
+            .map(|w| (w[1].mid_price() / w[0].mid_price()).to_f64().unwrap().ln())
+            .collect::<Vec<_>>();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        let daily = (variance * 1440.).sqrt();
+        assert!((daily - 0.04).abs() < 0.004, "realized daily volatility {}", daily);
+    }
+}
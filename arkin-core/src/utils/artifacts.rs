@@ -0,0 +1,156 @@
+use std::{fs, sync::Arc};
+
+use anyhow::{Context, Result};
+use futures_util::TryStreamExt;
+use object_store::{
+    aws::AmazonS3Builder, azure::MicrosoftAzureBuilder, buffered::BufWriter, gcp::GoogleCloudStorageBuilder,
+    local::LocalFileSystem, path::Path, ObjectStore,
+};
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::config::ObjectStorageConfig;
+
+/// Object storage for large artifacts like raw feed archives, so they outlive the disk of the box that wrote them. Keys are `/` separated and relative to the prefix of the configured url.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    url: String,
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+impl ArtifactStore {
+    pub fn from_config(config: &ObjectStorageConfig) -> Result<Self> {
+        let (store, prefix): (Arc<dyn ObjectStore>, _) = match config.url.split_once("://") {
+            Some(("s3", location)) => {
+                let mut builder = AmazonS3Builder::from_env().with_url(&config.url);
+                for (key, value) in &config.options {
+                    builder = builder.with_config(key.parse()?, value);
+                }
+                (Arc::new(builder.build()?), bucket_prefix(location))
+            }
+            Some(("gs", location)) => {
+                let mut builder = GoogleCloudStorageBuilder::from_env().with_url(&config.url);
+                for (key, value) in &config.options {
+                    builder = builder.with_config(key.parse()?, value);
+                }
+                (Arc::new(builder.build()?), bucket_prefix(location))
+            }
+            Some(("az", location)) => {
+                let mut builder = MicrosoftAzureBuilder::from_env().with_url(&config.url);
+                for (key, value) in &config.options {
+                    builder = builder.with_config(key.parse()?, value);
+                }
+                (Arc::new(builder.build()?), bucket_prefix(location))
+            }
+            Some(("file", _)) | None => {
+                let dir = dir_of(&config.url);
+                fs::create_dir_all(dir).with_context(|| format!("Failed to create artifact dir {}", dir))?;
+                (Arc::new(LocalFileSystem::new_with_prefix(dir)?), Path::default())
+            }
+            _ => anyhow::bail!("Unsupported artifact storage url {}", config.url),
+        };
+        Ok(Self {
+            url: config.url.clone(),
+            store,
+            prefix,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn path(&self, key: &str) -> Path {
+        key.split('/')
+            .filter(|part| !part.is_empty())
+            .fold(self.prefix.clone(), |path, part| path.child(part))
+    }
+
+    pub async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.store.put(&self.path(key), data.into()).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let data = self.store.get(&self.path(key)).await?.bytes().await?;
+        Ok(data.to_vec())
+    }
+
+    /// Stream a local file to the key, large files are uploaded in parts.
+    pub async fn upload(&self, file: &std::path::Path, key: &str) -> Result<()> {
+        let mut reader = tokio::fs::File::open(file)
+            .await
+            .with_context(|| format!("Failed to open {}", file.display()))?;
+        let mut writer = BufWriter::new(self.store.clone(), self.path(key));
+        tokio::io::copy(&mut reader, &mut writer).await?;
+        writer.shutdown().await?;
+        debug!("Uploaded {} to {}/{}", file.display(), self.url, key);
+        Ok(())
+    }
+
+    pub async fn download(&self, key: &str, file: &std::path::Path) -> Result<()> {
+        if let Some(parent) = file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let data = self.get(key).await?;
+        tokio::fs::write(file, data)
+            .await
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        Ok(())
+    }
+
+    /// Keys under the given key prefix, sorted.
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let objects = self.store.list(Some(&self.path(prefix))).try_collect::<Vec<_>>().await?;
+        let mut keys = objects
+            .iter()
+            .filter_map(|o| o.location.prefix_match(&self.prefix))
+            .map(|parts| parts.map(|p| p.as_ref().to_owned()).collect::<Vec<_>>().join("/"))
+            .collect::<Vec<_>>();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Prefix within the bucket of `bucket/some/prefix`.
+fn bucket_prefix(location: &str) -> Path {
+    match location.split_once('/') {
+        Some((_, prefix)) => Path::from(prefix),
+        None => Path::default(),
+    }
+}
+
+fn dir_of(url: &str) -> &str {
+    url.strip_prefix("file://").unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_local_artifacts() {
+        let dir = std::env::temp_dir().join(format!("arkin-artifacts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = ArtifactStore::from_config(&ObjectStorageConfig {
+            url: dir.display().to_string(),
+            options: Default::default(),
+        })
+        .unwrap();
+
+        store.put("reports/run-1.json", b"{}".to_vec()).await.unwrap();
+        let file = dir.join("local.txt");
+        fs::write(&file, "raw").unwrap();
+        store.upload(&file, "archives/binance/day.raw.gz").await.unwrap();
+
+        assert_eq!(store.get("reports/run-1.json").await.unwrap(), b"{}");
+        assert_eq!(store.list("archives").await.unwrap(), vec!["archives/binance/day.raw.gz"]);
+        let copy = dir.join("copy/day.raw.gz");
+        store.download("archives/binance/day.raw.gz", &copy).await.unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "raw");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
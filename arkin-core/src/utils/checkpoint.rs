@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+/// Position of the simulation clock at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockState {
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+    pub frequency_secs: u64,
+    pub current_timestamp: OffsetDateTime,
+}
+
+/// Values of a single feature series, keyed by unix timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureState {
+    pub instrument_id: Option<Uuid>,
+    pub feature_id: String,
+    pub values: Vec<(i64, Decimal)>,
+}
+
+/// Open position of the (simulated) venue account at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionState {
+    pub instrument_id: Uuid,
+    pub side: String,
+    pub open_price: Decimal,
+    pub quantity: Decimal,
+    pub realized_pnl: Decimal,
+}
+
+/// Resting order on the (simulated) venue at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenOrderState {
+    pub id: Uuid,
+    pub instrument_id: Uuid,
+    pub side: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub filled_quantity: Decimal,
+}
+
+/// Wallet balance of the (simulated) venue account at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceState {
+    pub asset: String,
+    pub balance: Decimal,
+}
+
+/// Order book of an instrument the simulated venue rebuilt from the replayed depth, best price first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBookState {
+    pub instrument_id: Uuid,
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+/// Position of the seeded random generator of a simulated venue, the number of values drawn since it was seeded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngState {
+    /// None for the executor simulating every venue
+    pub venue: Option<String>,
+    pub seed: u64,
+    pub draws: u64,
+}
+
+/// State of the simulated venues at the time of the checkpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VenueState {
+    pub positions: Vec<PositionState>,
+    pub open_orders: Vec<OpenOrderState>,
+    pub balances: Vec<BalanceState>,
+    #[serde(default)]
+    pub order_books: Vec<OrderBookState>,
+    #[serde(default)]
+    pub rngs: Vec<RngState>,
+}
+
+/// Sub-account of a strategy at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategyState {
+    pub strategy: String,
+    pub capital: Decimal,
+    pub realized_pnl: Decimal,
+    pub commission: Decimal,
+    pub positions: Vec<PositionState>,
+}
+
+/// Sub-accounts of the strategies at the time of the checkpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubAccountsState {
+    pub accounts: Vec<StrategyState>,
+    /// Strategy of the execution orders still open, their fills are booked on it after the resume
+    pub orders: Vec<(Uuid, String)>,
+}
+
+/// Positions and balances the accounting of the portfolio booked at the time of the checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortfolioState {
+    /// Portfolio the positions and balances are booked under
+    pub portfolio_id: Uuid,
+    pub positions: Vec<PositionState>,
+    pub balances: Vec<BalanceState>,
+}
+
+/// State of a simulation run, written periodically into persistence so a run can be resumed. Runs without simulated
+/// venues, like insights runs, only checkpoint the clock and the features.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TypedBuilder)]
+pub struct SimulationCheckpoint {
+    #[builder(default = OffsetDateTime::now_utc())]
+    pub created_at: OffsetDateTime,
+    pub clock: ClockState,
+    #[builder(default)]
+    pub features: Vec<FeatureState>,
+    #[builder(default)]
+    #[serde(default)]
+    pub venue: Option<VenueState>,
+    #[builder(default)]
+    #[serde(default)]
+    pub portfolio: Option<PortfolioState>,
+    #[builder(default)]
+    #[serde(default)]
+    pub sub_accounts: Option<SubAccountsState>,
+}
+
+impl SimulationCheckpoint {
+    pub fn frequency(&self) -> Duration {
+        Duration::from_secs(self.clock.frequency_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use test_log::test;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let position = PositionState {
+            instrument_id: Uuid::new_v4(),
+            side: "long".into(),
+            open_price: dec!(42000),
+            quantity: dec!(0.5),
+            realized_pnl: dec!(-3.2),
+        };
+        let balance = BalanceState {
+            asset: "USDT".into(),
+            balance: dec!(10250.75),
+        };
+        let checkpoint = SimulationCheckpoint::builder()
+            .clock(ClockState {
+                start: datetime!(2024-01-01 00:00).assume_utc(),
+                end: datetime!(2024-01-10 00:00).assume_utc(),
+                frequency_secs: 60,
+                current_timestamp: datetime!(2024-01-05 12:00).assume_utc(),
+            })
+            .features(vec![FeatureState {
+                instrument_id: Some(Uuid::new_v4()),
+                feature_id: "close".into(),
+                values: vec![(1704067200, dec!(42000.5)), (1704067260, dec!(42001.0))],
+            }])
+            .venue(Some(VenueState {
+                positions: vec![position.clone()],
+                balances: vec![balance.clone()],
+                order_books: vec![OrderBookState {
+                    instrument_id: position.instrument_id,
+                    bids: vec![(dec!(41999.9), dec!(1.2)), (dec!(41999.8), dec!(3))],
+                    asks: vec![(dec!(42000), dec!(0.4))],
+                }],
+                rngs: vec![RngState {
+                    venue: None,
+                    seed: 42,
+                    draws: 1337,
+                }],
+                ..Default::default()
+            }))
+            .portfolio(Some(PortfolioState {
+                portfolio_id: Uuid::new_v4(),
+                positions: vec![position.clone()],
+                balances: vec![balance],
+            }))
+            .sub_accounts(Some(SubAccountsState {
+                accounts: vec![StrategyState {
+                    strategy: "momentum".into(),
+                    capital: dec!(1000),
+                    realized_pnl: dec!(12.5),
+                    commission: dec!(0.8),
+                    positions: vec![position],
+                }],
+                orders: vec![(Uuid::new_v4(), "momentum".into())],
+            }))
+            .build();
+
+        let state = serde_json::to_string(&checkpoint).unwrap();
+        assert_eq!(serde_json::from_str::<SimulationCheckpoint>(&state).unwrap(), checkpoint);
+
+        // Checkpoints written before the portfolio was part of them still load
+        let mut value = serde_json::to_value(&checkpoint).unwrap();
+        value.as_object_mut().unwrap().remove("portfolio");
+        let loaded = serde_json::from_value::<SimulationCheckpoint>(value).unwrap();
+        assert!(loaded.portfolio.is_none());
+    }
+}
@@ -4,6 +4,8 @@ use futures_util::{stream, Stream};
 use time::OffsetDateTime;
 use tracing::info;
 
+use super::ClockState;
+
 #[derive(Debug, Clone)]
 pub struct Clock {
     start: OffsetDateTime,
@@ -57,6 +59,34 @@ impl Clock {
     pub fn end(&self) -> OffsetDateTime {
         self.end
     }
+
+    pub fn current(&self) -> OffsetDateTime {
+        self.current_timestamp
+    }
+
+    /// Capture the current position of the clock for checkpointing.
+    pub fn state(&self) -> ClockState {
+        ClockState {
+            start: self.start,
+            end: self.end,
+            frequency_secs: self.frequency_secs.as_secs(),
+            current_timestamp: self.current_timestamp,
+        }
+    }
+
+    /// Recreate a clock that continues from a checkpointed position.
+    pub fn from_state(state: &ClockState) -> Self {
+        info!(
+            "Resuming clock at {} (start: {}, end: {})",
+            state.current_timestamp, state.start, state.end
+        );
+        Self {
+            start: state.start,
+            end: state.end,
+            frequency_secs: Duration::from_secs(state.frequency_secs),
+            current_timestamp: state.current_timestamp,
+        }
+    }
 }
 
 impl Iterator for Clock {
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::{bail, Context, Error, Result};
+use rust_decimal::Decimal;
+use time::{macros::format_description, OffsetDateTime, PrimitiveDateTime};
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::{Event, EventType, Instrument, PositionUpdate, Tick};
+
+/// Condition that pauses a debugged simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// First event at or after the time, fires once
+    At(OffsetDateTime),
+    /// Every replayed event of the type, of one instrument when the symbol is set
+    Event {
+        event_type: EventType,
+        instrument: Option<String>,
+    },
+    /// Pnl of the portfolio falls below the threshold
+    PnlBelow(Decimal),
+    /// Pnl of the portfolio rises above the threshold
+    PnlAbove(Decimal),
+}
+
+impl Breakpoint {
+    fn matches(&self, event: &Event) -> bool {
+        let (event_time, instrument) = match event {
+            Event::Tick(tick) => (tick.event_time, &tick.instrument),
+            Event::Trade(trade) => (trade.event_time, &trade.instrument),
+            _ => return false,
+        };
+        match self {
+            Breakpoint::At(time) => event_time >= *time,
+            Breakpoint::Event {
+                event_type,
+                instrument: symbol,
+            } => {
+                *event_type == event.event_type()
+                    && symbol
+                        .as_ref()
+                        .is_none_or(|s| *s == instrument.venue_symbol || *s == instrument.symbol)
+            }
+            Breakpoint::PnlBelow(_) | Breakpoint::PnlAbove(_) => false,
+        }
+    }
+
+    /// True if the pnl moved across the threshold in the direction of the breakpoint.
+    fn crossed(&self, previous: Decimal, pnl: Decimal) -> bool {
+        match self {
+            Breakpoint::PnlBelow(threshold) => previous >= *threshold && pnl < *threshold,
+            Breakpoint::PnlAbove(threshold) => previous <= *threshold && pnl > *threshold,
+            _ => false,
+        }
+    }
+}
+
+/// Parses `at=2025-01-01 12:00`, `event=trade`, `event=tick@BTCUSDT`, `pnl<-100` and `pnl>250`.
+impl FromStr for Breakpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(time) = s.strip_prefix("at=") {
+            let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+            let time = PrimitiveDateTime::parse(time.trim(), &format)
+                .with_context(|| format!("invalid breakpoint time: {}", time))?;
+            return Ok(Breakpoint::At(time.assume_utc()));
+        }
+        if let Some(event) = s.strip_prefix("event=") {
+            let (name, instrument) = match event.split_once('@') {
+                Some((name, instrument)) => (name, Some(instrument.trim().to_string())),
+                None => (event, None),
+            };
+            let event_type = match name.trim().to_lowercase().as_str() {
+                "trade" => EventType::Trade,
+                "tick" => EventType::Tick,
+                _ => bail!("invalid breakpoint event, only trade and tick are replayed: {}", name),
+            };
+            return Ok(Breakpoint::Event {
+                event_type,
+                instrument,
+            });
+        }
+        if let Some(threshold) = s.strip_prefix("pnl<") {
+            return Ok(Breakpoint::PnlBelow(threshold.trim().parse()?));
+        }
+        if let Some(threshold) = s.strip_prefix("pnl>") {
+            return Ok(Breakpoint::PnlAbove(threshold.trim().parse()?));
+        }
+        bail!("invalid breakpoint: {}", s)
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::At(time) => {
+                let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+                write!(f, "at={}", time.format(&format).expect("Failed to format breakpoint time"))
+            }
+            Breakpoint::Event {
+                event_type,
+                instrument,
+            } => {
+                let name = match event_type {
+                    EventType::Tick => "tick",
+                    _ => "trade",
+                };
+                match instrument {
+                    Some(instrument) => write!(f, "event={}@{}", name, instrument),
+                    None => write!(f, "event={}", name),
+                }
+            }
+            Breakpoint::PnlBelow(threshold) => write!(f, "pnl<{}", threshold),
+            Breakpoint::PnlAbove(threshold) => write!(f, "pnl>{}", threshold),
+        }
+    }
+}
+
+/// Where and why a debugged simulation paused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugStop {
+    pub event_time: OffsetDateTime,
+    pub reason: String,
+    /// Event that is published when the simulation resumes
+    pub event: String,
+}
+
+impl fmt::Display for DebugStop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}: {}", self.reason, self.event_time, self.event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebugMode {
+    Run,
+    /// Pause after this many more events
+    Step(u64),
+    /// Pause before the next event
+    Pause,
+}
+
+#[derive(Debug)]
+struct DebuggerState {
+    mode: DebugMode,
+    breakpoints: Vec<Breakpoint>,
+    /// Realized plus unrealized pnl of the open positions
+    positions: HashMap<Arc<Instrument>, Decimal>,
+    pnl: Decimal,
+    /// Pnl breakpoint crossed since the last event
+    pnl_hit: Option<Breakpoint>,
+    books: HashMap<Arc<Instrument>, Arc<Tick>>,
+}
+
+/// Pauses a simulation at breakpoints. The replay passes every event through [`SimDebugger::gate`] before publishing
+/// it, which holds the event while the simulation is paused. A console inspects the paused simulation and resumes it
+/// or steps through it event by event.
+#[derive(Debug)]
+pub struct SimDebugger {
+    state: Mutex<DebuggerState>,
+    stop: watch::Sender<Option<DebugStop>>,
+}
+
+impl SimDebugger {
+    pub fn new(breakpoints: Vec<Breakpoint>) -> Self {
+        let (stop, _) = watch::channel(None);
+        Self {
+            state: Mutex::new(DebuggerState {
+                mode: DebugMode::Run,
+                breakpoints,
+                positions: HashMap::new(),
+                pnl: Decimal::ZERO,
+                pnl_hit: None,
+                books: HashMap::new(),
+            }),
+            stop,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, DebuggerState> {
+        self.state.lock().expect("Debugger lock poisoned")
+    }
+
+    /// Wait while the simulation is paused, pausing it first if the event hits a breakpoint.
+    pub async fn gate(&self, event: &Event) {
+        if let Some(stop) = self.check(event) {
+            info!("Simulation paused, {}", stop);
+            self.stop.send_replace(Some(stop));
+        }
+        let mut stop = self.stop.subscribe();
+        let _ = stop.wait_for(|s| s.is_none()).await;
+
+        if let Event::Tick(tick) = event {
+            self.state().books.insert(tick.instrument.clone(), tick.clone());
+        }
+    }
+
+    /// Why the event pauses the simulation, none if it passes.
+    fn check(&self, event: &Event) -> Option<DebugStop> {
+        let (event_time, description) = match event {
+            Event::Tick(tick) => (tick.event_time, format!("tick {}", tick)),
+            Event::Trade(trade) => (trade.event_time, format!("trade {}", trade)),
+            _ => return None,
+        };
+
+        let mut state = self.state();
+        let reason = match state.mode {
+            DebugMode::Pause | DebugMode::Step(1) => Some("paused".to_string()),
+            DebugMode::Step(n) => {
+                state.mode = DebugMode::Step(n - 1);
+                None
+            }
+            DebugMode::Run => None,
+        };
+        let reason = match (reason, state.pnl_hit.take()) {
+            (Some(reason), _) => reason,
+            (None, Some(breakpoint)) => format!("breakpoint {} (pnl {})", breakpoint, state.pnl),
+            (None, None) => state
+                .breakpoints
+                .iter()
+                .find(|b| b.matches(event))
+                .map(|b| format!("breakpoint {}", b))?,
+        };
+
+        // Time breakpoints only fire once, and all channels stop until the console resumes
+        state
+            .breakpoints
+            .retain(|b| !matches!(b, Breakpoint::At(time) if *time <= event_time));
+        state.mode = DebugMode::Pause;
+        Some(DebugStop {
+            event_time,
+            reason,
+            event: description,
+        })
+    }
+
+    /// Where the simulation is paused, none while it runs.
+    pub fn stopped(&self) -> Option<DebugStop> {
+        self.stop.borrow().clone()
+    }
+
+    /// Receives the stops of the simulation, none when it resumes.
+    pub fn subscribe(&self) -> watch::Receiver<Option<DebugStop>> {
+        self.stop.subscribe()
+    }
+
+    /// Run until the next breakpoint.
+    pub fn resume(&self) {
+        self.state().mode = DebugMode::Run;
+        self.stop.send_replace(None);
+    }
+
+    /// Publish the given number of events and pause again.
+    pub fn step(&self, events: u64) {
+        self.state().mode = DebugMode::Step(events.max(1));
+        self.stop.send_replace(None);
+    }
+
+    /// Pause before the next event.
+    pub fn pause(&self) {
+        self.state().mode = DebugMode::Pause;
+    }
+
+    pub fn add_breakpoint(&self, breakpoint: Breakpoint) {
+        self.state().breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&self, index: usize) -> Option<Breakpoint> {
+        let mut state = self.state();
+        (index < state.breakpoints.len()).then(|| state.breakpoints.remove(index))
+    }
+
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        self.state().breakpoints.clone()
+    }
+
+    /// Track the pnl of the portfolio for the pnl breakpoints.
+    pub fn update_position(&self, update: &PositionUpdate) {
+        let mut state = self.state();
+        state
+            .positions
+            .insert(update.instrument.clone(), update.realized_pnl + update.unrealized_pnl);
+        let previous = state.pnl;
+        state.pnl = state.positions.values().sum();
+        let pnl = state.pnl;
+        if let Some(breakpoint) = state.breakpoints.iter().find(|b| b.crossed(previous, pnl)).cloned() {
+            state.pnl_hit = Some(breakpoint);
+        }
+    }
+
+    pub fn pnl(&self) -> Decimal {
+        self.state().pnl
+    }
+
+    /// Last published tick of every instrument.
+    pub fn books(&self) -> Vec<Arc<Tick>> {
+        let mut books = self.state().books.values().cloned().collect::<Vec<_>>();
+        books.sort_by(|a, b| a.instrument.symbol.cmp(&b.instrument.symbol));
+        books
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::{
+        test_utils::{test_inst_binance_btc_usdt_perp, test_portfolio},
+        MarketSide, PositionSide, Trade,
+    };
+
+    fn trade(event_time: OffsetDateTime) -> Event {
+        let trade = Trade::builder()
+            .event_time(event_time)
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .trade_id(1)
+            .side(MarketSide::Buy)
+            .price(dec!(100_000))
+            .quantity(dec!(0.1))
+            .build();
+        Event::Trade(Arc::new(trade))
+    }
+
+    #[test]
+    fn test_parse_breakpoints() {
+        for text in [
+            "at=2025-01-01 12:00",
+            "event=trade",
+            "event=tick@BTCUSDT",
+            "pnl<-100",
+            "pnl>250.5",
+        ] {
+            assert_eq!(text.parse::<Breakpoint>().unwrap().to_string(), text);
+        }
+        assert!("event=book".parse::<Breakpoint>().is_err());
+        assert!("after=10".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let debugger = SimDebugger::new(vec![Breakpoint::At(datetime!(2025-01-01 12:00 UTC))]);
+        assert_eq!(debugger.check(&trade(datetime!(2025-01-01 11:59 UTC))), None);
+        let stop = debugger.check(&trade(datetime!(2025-01-01 12:00 UTC))).unwrap();
+        assert_eq!(stop.reason, "breakpoint at=2025-01-01 12:00");
+        assert!(debugger.breakpoints().is_empty());
+
+        // The held event and one more are published, then it pauses again
+        debugger.step(2);
+        assert_eq!(debugger.check(&trade(datetime!(2025-01-01 12:01 UTC))), None);
+        assert_eq!(
+            debugger.check(&trade(datetime!(2025-01-01 12:02 UTC))).unwrap().reason,
+            "paused"
+        );
+
+        debugger.resume();
+        debugger.add_breakpoint(Breakpoint::PnlBelow(dec!(-100)));
+        let position = PositionUpdate::builder()
+            .event_time(datetime!(2025-01-01 12:04 UTC))
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .entry_price(dec!(100_000))
+            .quantity(dec!(0.1))
+            .realized_pnl(dec!(-20))
+            .unrealized_pnl(dec!(-90))
+            .position_side(PositionSide::Long)
+            .build();
+        debugger.update_position(&position);
+        assert_eq!(debugger.pnl(), dec!(-110));
+        let stop = debugger.check(&trade(datetime!(2025-01-01 12:05 UTC))).unwrap();
+        assert_eq!(stop.reason, "breakpoint pnl<-100 (pnl -110)");
+
+        // Staying below the threshold doesn't pause again
+        debugger.resume();
+        debugger.update_position(&position);
+        assert_eq!(debugger.check(&trade(datetime!(2025-01-01 12:06 UTC))), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fmt::Write,
+    sync::{LazyLock, RwLock},
+};
+
+use dashmap::DashMap;
+
+/// Process wide metrics, served in the Prometheus text format by the metrics exporter.
+pub static METRICS: LazyLock<MetricsRegistry> = LazyLock::new(MetricsRegistry::default);
+
+/// Writes the metrics of a component in the Prometheus text format on every scrape.
+pub type MetricsCollector = fn(&mut String);
+
+/// Gauges set by the services plus the collectors of the components keeping their own statistics.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    /// Help text of the gauges by name
+    help: DashMap<&'static str, &'static str>,
+    /// Value of every series by gauge name and rendered labels
+    gauges: DashMap<(&'static str, String), f64>,
+    collectors: RwLock<Vec<(&'static str, MetricsCollector)>>,
+}
+
+impl MetricsRegistry {
+    pub fn set_gauge(&self, name: &'static str, help: &'static str, labels: &[(&str, &str)], value: f64) {
+        self.help.insert(name, help);
+        self.gauges.insert((name, render_labels(labels)), value);
+    }
+
+    pub fn gauge(&self, name: &'static str, labels: &[(&str, &str)]) -> Option<f64> {
+        self.gauges.get(&(name, render_labels(labels))).map(|v| *v)
+    }
+
+    /// Add a collector called on every scrape, a collector registered again under the same name replaces it.
+    pub fn register(&self, name: &'static str, collector: MetricsCollector) {
+        let mut collectors = self.collectors.write().expect("Metrics collectors poisoned");
+        collectors.retain(|(n, _)| *n != name);
+        collectors.push((name, collector));
+    }
+
+    /// All metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut gauges = self.gauges.iter().map(|e| (e.key().clone(), *e.value())).collect::<Vec<_>>();
+        gauges.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = String::new();
+        let mut last = None;
+        for ((name, labels), value) in gauges {
+            if last != Some(name) {
+                let help = self.help.get(name).map(|h| *h).unwrap_or_default();
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} gauge", name);
+                last = Some(name);
+            }
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+        for (_, collector) in self.collectors.read().expect("Metrics collectors poisoned").iter() {
+            collector(&mut out);
+        }
+        out
+    }
+}
+
+/// Labels of a series as `{key="value",...}`, nothing without labels.
+pub fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", labels.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let registry = MetricsRegistry::default();
+        registry.set_gauge("arkin_test_lag", "Lag of the test", &[("service", "a")], 1.5);
+        registry.set_gauge("arkin_test_lag", "Lag of the test", &[("service", "b")], 2.);
+        registry.set_gauge("arkin_test_lag", "Lag of the test", &[("service", "a")], 3.);
+        registry.register("test", |out| out.push_str("arkin_test_collected 7\n"));
+        registry.register("test", |out| out.push_str("arkin_test_collected 8\n"));
+
+        assert_eq!(registry.gauge("arkin_test_lag", &[("service", "a")]), Some(3.));
+        assert_eq!(
+            registry.render(),
+            "# HELP arkin_test_lag Lag of the test\n\
+             # TYPE arkin_test_lag gauge\n\
+             arkin_test_lag{service=\"a\"} 3\n\
+             arkin_test_lag{service=\"b\"} 2\n\
+             arkin_test_collected 8\n"
+        );
+        assert_eq!(render_labels(&[("query", "say \"hi\"")]), "{query=\"say \\\"hi\\\"\"}");
+    }
+}
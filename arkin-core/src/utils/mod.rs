@@ -1,14 +1,32 @@
+#[cfg(feature = "object-storage")]
+mod artifacts;
+mod checkpoint;
 mod clock;
 mod composit_key;
 pub mod custom_serde;
+mod debugger;
 mod deduplicator;
 mod interval_helper;
+mod metrics;
+mod progress;
+mod replay_barrier;
 mod tick_helper;
 mod time_helper;
+mod watchdog;
+mod watermark;
 
+#[cfg(feature = "object-storage")]
+pub use artifacts::*;
+pub use checkpoint::*;
 pub use clock::*;
 pub use composit_key::*;
+pub use debugger::*;
 pub use deduplicator::*;
 pub use interval_helper::*;
+pub use metrics::*;
+pub use progress::*;
+pub use replay_barrier::*;
 pub use tick_helper::*;
 pub use time_helper::*;
+pub use watchdog::*;
+pub use watermark::*;
@@ -0,0 +1,163 @@
+use std::time::{Duration, Instant};
+
+use time::OffsetDateTime;
+
+use crate::{utils::METRICS, SimulationProgress};
+
+const BAR_WIDTH: usize = 30;
+
+/// Tracks how far a simulation has advanced through its time range and estimates the time remaining.
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    started_at: Instant,
+    events: u64,
+}
+
+impl ProgressTracker {
+    pub fn new(start: OffsetDateTime, end: OffsetDateTime) -> Self {
+        Self {
+            start,
+            end,
+            started_at: Instant::now(),
+            events: 0,
+        }
+    }
+
+    /// Register processed events (ticks, trades, insights, ...).
+    pub fn add_events(&mut self, count: u64) {
+        self.events += count;
+    }
+
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    /// Create a progress update for the given simulated time, the update is exported as metrics as well.
+    pub fn update(&self, sim_time: OffsetDateTime) -> SimulationProgress {
+        let elapsed = self.started_at.elapsed();
+        let total = (self.end - self.start).as_seconds_f64();
+        let processed = (sim_time - self.start).as_seconds_f64().clamp(0., total.max(0.));
+        let fraction = if total > 0. { processed / total } else { 1. };
+
+        let events_per_sec = if elapsed.as_secs_f64() > 0. {
+            self.events as f64 / elapsed.as_secs_f64()
+        } else {
+            0.
+        };
+
+        let eta = if fraction > 0. {
+            Some(Duration::from_secs_f64(elapsed.as_secs_f64() * (1. - fraction) / fraction))
+        } else {
+            None
+        };
+
+        let progress = SimulationProgress::builder()
+            .event_time(sim_time)
+            .start(self.start)
+            .end(self.end)
+            .fraction(fraction)
+            .events(self.events)
+            .events_per_sec(events_per_sec)
+            .elapsed(elapsed)
+            .eta(eta)
+            .build();
+        export_progress(&progress);
+        progress
+    }
+
+    /// Progress update of a simulation that ran through its whole range.
+    pub fn finish(&self) -> SimulationProgress {
+        self.update(self.end)
+    }
+}
+
+fn export_progress(progress: &SimulationProgress) {
+    METRICS.set_gauge(
+        "arkin_simulation_progress_ratio",
+        "Fraction of the simulated range processed",
+        &[],
+        progress.fraction,
+    );
+    METRICS.set_gauge(
+        "arkin_simulation_time_seconds",
+        "Simulated time as unix timestamp",
+        &[],
+        progress.event_time.unix_timestamp() as f64,
+    );
+    METRICS.set_gauge(
+        "arkin_simulation_events",
+        "Events processed by the simulation",
+        &[],
+        progress.events as f64,
+    );
+    METRICS.set_gauge(
+        "arkin_simulation_events_per_second",
+        "Events processed per second of wall clock",
+        &[],
+        progress.events_per_sec,
+    );
+    if let Some(eta) = progress.eta {
+        METRICS.set_gauge(
+            "arkin_simulation_eta_seconds",
+            "Estimated wall clock time until the simulation finishes",
+            &[],
+            eta.as_secs_f64(),
+        );
+    }
+}
+
+/// Render a progress update as a single line text progress bar.
+pub fn render_progress_bar(progress: &SimulationProgress) -> String {
+    let filled = ((progress.fraction * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    let eta = progress
+        .eta
+        .map(|d| format!("{}s", d.as_secs()))
+        .unwrap_or_else(|| "unknown".into());
+    format!(
+        "[{}{}] {:>5.1}% sim_time={} events={} events/s={:.0} eta={}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        progress.fraction * 100.,
+        progress.event_time,
+        progress.events,
+        progress.events_per_sec,
+        eta
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_progress_fraction() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let end = datetime!(2024-01-05 00:00).assume_utc();
+        let mut tracker = ProgressTracker::new(start, end);
+        tracker.add_events(100);
+
+        let progress = tracker.update(datetime!(2024-01-02 00:00).assume_utc());
+        assert!((progress.fraction - 0.25).abs() < f64::EPSILON);
+        assert_eq!(progress.events, 100);
+        assert!(progress.eta.is_some());
+
+        let progress = tracker.update(datetime!(2024-01-10 00:00).assume_utc());
+        assert!((progress.fraction - 1.).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_render_progress_bar() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let end = datetime!(2024-01-03 00:00).assume_utc();
+        let tracker = ProgressTracker::new(start, end);
+
+        let line = render_progress_bar(&tracker.update(datetime!(2024-01-02 00:00).assume_utc()));
+        assert!(line.starts_with(&format!("[{}{}]", "#".repeat(15), "-".repeat(15))));
+        assert!(line.contains("50.0%"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::{sync::Mutex, time::Duration};
+
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Holds the replay of a simulation at every multiple of an interval, so the simulation can be checkpointed between
+/// two events. The replay stops before the first event at or past the barrier and waits until it is released, every
+/// event before the barrier is published and none after it.
+#[derive(Debug)]
+pub struct ReplayBarrier {
+    every: Duration,
+    /// Next barrier the replay stops at
+    next: Mutex<OffsetDateTime>,
+    /// Barrier the replay is held at, none while it runs
+    held: watch::Sender<Option<OffsetDateTime>>,
+}
+
+impl ReplayBarrier {
+    /// Barriers at every multiple of `every` after `from`, the time the replay starts at.
+    pub fn new(from: OffsetDateTime, every: Duration) -> Self {
+        let (held, _) = watch::channel(None);
+        Self {
+            every,
+            next: Mutex::new(Self::floor(from, every) + every),
+            held,
+        }
+    }
+
+    /// Last multiple of `every` at or before the given time.
+    fn floor(time: OffsetDateTime, every: Duration) -> OffsetDateTime {
+        let every = every.as_nanos().max(1) as i128;
+        let nanos = time.unix_timestamp_nanos().div_euclid(every) * every;
+        OffsetDateTime::from_unix_timestamp_nanos(nanos).expect("Invalid replay barrier time")
+    }
+
+    /// Wait before publishing an event of the replay while it is held, holding it first if the event reached the next
+    /// barrier. A replay jumping over several barriers, like across a gap, is held once at the last of them.
+    pub async fn gate(&self, event_time: OffsetDateTime) {
+        let barrier = {
+            let mut next = self.next.lock().expect("Replay barrier lock poisoned");
+            if event_time < *next {
+                None
+            } else {
+                let barrier = Self::floor(event_time, self.every);
+                *next = barrier + self.every;
+                Some(barrier)
+            }
+        };
+        if let Some(barrier) = barrier {
+            debug!("Replay held at the barrier at {}", barrier);
+            self.held.send_replace(Some(barrier));
+        }
+        let mut held = self.held.subscribe();
+        let _ = held.wait_for(|h| h.is_none()).await;
+    }
+
+    /// Wait until the replay is held at a barrier and return its time.
+    pub async fn reached(&self) -> OffsetDateTime {
+        let mut held = self.held.subscribe();
+        let barrier = held.wait_for(|h| h.is_some()).await.expect("Replay barrier closed");
+        barrier.expect("Replay not held")
+    }
+
+    /// Let the held replay continue.
+    pub fn release(&self) {
+        self.held.send_replace(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use test_log::test;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_replay_barrier() {
+        let start = datetime!(2025-01-01 00:00:00 UTC);
+        let barrier = Arc::new(ReplayBarrier::new(start, Duration::from_secs(3600)));
+        let minutes = move |m: i64| start + time::Duration::minutes(m);
+
+        // Events before the first barrier pass
+        barrier.gate(minutes(0)).await;
+        barrier.gate(minutes(59)).await;
+
+        // The first event at the barrier is held until it is released
+        let gate = tokio::spawn({
+            let barrier = barrier.clone();
+            async move { barrier.gate(minutes(61)).await }
+        });
+        assert_eq!(barrier.reached().await, minutes(60));
+        assert!(!gate.is_finished());
+        barrier.release();
+        gate.await.unwrap();
+
+        // A jump over several barriers holds once at the last of them
+        let gate = tokio::spawn({
+            let barrier = barrier.clone();
+            async move { barrier.gate(minutes(250)).await }
+        });
+        assert_eq!(barrier.reached().await, minutes(240));
+        barrier.release();
+        gate.await.unwrap();
+        barrier.gate(minutes(299)).await;
+    }
+}
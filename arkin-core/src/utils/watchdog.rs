@@ -0,0 +1,218 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use tokio::{sync::watch, time::Instant};
+use tracing::warn;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, Clone)]
+struct InFlight {
+    service: &'static str,
+    event: &'static str,
+    started: Instant,
+    stalled: bool,
+}
+
+/// Duration statistics of the handled events of a single service.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HandlerStats {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+    pub over_soft_deadline: u64,
+}
+
+/// A handler that has been running for longer than the hard deadline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StalledHandler {
+    pub service: &'static str,
+    pub event: &'static str,
+    pub elapsed: Duration,
+}
+
+/// Measures how long services take to handle their events. Services wrap each handler in [`Watchdog::track`], the
+/// engine periodically calls [`Watchdog::check`] to find handlers that hang.
+#[derive(Debug, TypedBuilder)]
+pub struct Watchdog {
+    /// Handlers taking longer than this are logged
+    #[builder(default = Duration::from_millis(500))]
+    soft_deadline: Duration,
+    /// Handlers taking longer than this are reported as stalled
+    #[builder(default = Duration::from_secs(10))]
+    hard_deadline: Duration,
+    #[builder(default)]
+    in_flight: DashMap<u64, InFlight>,
+    /// Number of handlers running, to wait until the services are idle
+    #[builder(default = watch::channel(0).0)]
+    running: watch::Sender<usize>,
+    #[builder(default)]
+    stats: DashMap<&'static str, HandlerStats>,
+    #[builder(default)]
+    next_id: AtomicU64,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Watchdog {
+    pub fn hard_deadline(&self) -> Duration {
+        self.hard_deadline
+    }
+
+    /// Start measuring a handler, the measurement ends when the guard is dropped.
+    pub fn track(&self, service: &'static str, event: &'static str) -> WatchdogGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.insert(
+            id,
+            InFlight {
+                service,
+                event,
+                started: Instant::now(),
+                stalled: false,
+            },
+        );
+        self.running.send_modify(|running| *running += 1);
+        WatchdogGuard { watchdog: self, id }
+    }
+
+    /// Handlers that passed the hard deadline since the last check. Every stalled handler is reported only once.
+    pub fn check(&self) -> Vec<StalledHandler> {
+        let now = Instant::now();
+        let mut stalled = self
+            .in_flight
+            .iter_mut()
+            .filter_map(|mut entry| {
+                let handler = entry.value_mut();
+                let elapsed = now - handler.started;
+                if handler.stalled || elapsed <= self.hard_deadline {
+                    return None;
+                }
+                handler.stalled = true;
+                Some(StalledHandler {
+                    service: handler.service,
+                    event: handler.event,
+                    elapsed,
+                })
+            })
+            .collect::<Vec<_>>();
+        stalled.sort_by_key(|s| (s.service, s.event));
+        stalled
+    }
+
+    /// Number of handlers currently running.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Number of handlers running, updated whenever a handler starts or finishes.
+    pub fn running(&self) -> watch::Receiver<usize> {
+        self.running.subscribe()
+    }
+
+    pub fn stats(&self, service: &str) -> Option<HandlerStats> {
+        self.stats.get(service).map(|s| *s.value())
+    }
+
+    fn finish(&self, id: u64) {
+        let Some((_, handler)) = self.in_flight.remove(&id) else {
+            return;
+        };
+        self.running.send_modify(|running| *running -= 1);
+        let elapsed = handler.started.elapsed();
+        let mut stats = self.stats.entry(handler.service).or_default();
+        stats.count += 1;
+        stats.total += elapsed;
+        stats.max = stats.max.max(elapsed);
+        if elapsed > self.soft_deadline {
+            stats.over_soft_deadline += 1;
+            warn!(
+                "Service {} took {:?} to handle {}, soft deadline is {:?}",
+                handler.service, elapsed, handler.event, self.soft_deadline
+            );
+        }
+    }
+}
+
+/// Ends the measurement of a handler when dropped.
+#[derive(Debug)]
+pub struct WatchdogGuard<'a> {
+    watchdog: &'a Watchdog,
+    id: u64,
+}
+
+impl Drop for WatchdogGuard<'_> {
+    fn drop(&mut self) {
+        self.watchdog.finish(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    fn watchdog() -> Watchdog {
+        Watchdog::builder()
+            .soft_deadline(Duration::from_millis(100))
+            .hard_deadline(Duration::from_secs(1))
+            .build()
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_records_handler_stats() {
+        let watchdog = watchdog();
+        for millis in [50, 200] {
+            let _guard = watchdog.track("insights", "interval_tick");
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+        }
+
+        let stats = watchdog.stats("insights").unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.max, Duration::from_millis(200));
+        assert_eq!(stats.over_soft_deadline, 1);
+        assert_eq!(watchdog.in_flight(), 0);
+        assert!(watchdog.stats("portfolio").is_none());
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_reports_stalled_handler_once() {
+        let watchdog = watchdog();
+        let guard = watchdog.track("allocation", "insight_tick");
+        let _fast = watchdog.track("portfolio", "tick");
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(watchdog.check().is_empty());
+
+        tokio::time::advance(Duration::from_millis(600)).await;
+        let stalled = watchdog.check();
+        assert_eq!(stalled.len(), 2);
+        assert_eq!(stalled[0].service, "allocation");
+        assert_eq!(stalled[0].event, "insight_tick");
+        assert_eq!(stalled[0].elapsed, Duration::from_millis(1100));
+        assert!(watchdog.check().is_empty());
+
+        drop(guard);
+        assert_eq!(watchdog.in_flight(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn test_running_handlers() {
+        let watchdog = watchdog();
+        let mut running = watchdog.running();
+        let guard = watchdog.track("portfolio", "tick");
+        assert_eq!(*running.borrow_and_update(), 1);
+
+        let idle = tokio::spawn(async move { *running.wait_for(|n| *n == 0).await.unwrap() });
+        tokio::task::yield_now().await;
+        assert!(!idle.is_finished());
+        drop(guard);
+        assert_eq!(idle.await.unwrap(), 0);
+    }
+}
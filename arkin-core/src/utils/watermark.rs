@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use tracing::{debug, info};
+
+/// Synchronizes replay channels by event time.
+///
+/// Every channel advertises a watermark: the event time below which it will not publish anything anymore.
+/// Consumers (or the channels themselves) may only release events up to the minimum watermark over all
+/// channels, which guarantees that events across channels are released in event-time order without
+/// relying on fixed time windows.
+#[derive(Debug)]
+pub struct WatermarkSync {
+    watermarks: watch::Sender<HashMap<String, OffsetDateTime>>,
+}
+
+impl WatermarkSync {
+    pub fn new(channels: &[String], start: OffsetDateTime) -> Self {
+        let watermarks = channels.iter().map(|c| (c.clone(), start)).collect();
+        let (tx, _) = watch::channel(watermarks);
+        Self { watermarks: tx }
+    }
+
+    /// Register a channel while the replay is running.
+    ///
+    /// A late joining channel can't hold back events that have already been released, so its
+    /// watermark starts at the current global watermark if `watermark` lies before it.
+    pub fn register(&self, channel: &str, watermark: OffsetDateTime) {
+        self.watermarks.send_modify(|watermarks| {
+            let watermark = match Self::min_watermark(watermarks) {
+                Some(global) if watermark < global => global,
+                _ => watermark,
+            };
+            info!("Registering channel {} at watermark {}", channel, watermark);
+            watermarks.insert(channel.to_string(), watermark);
+        });
+    }
+
+    /// Remove a channel, for example when its stream has ended. The remaining channels are no longer held
+    /// back by it.
+    pub fn deregister(&self, channel: &str) {
+        self.watermarks.send_if_modified(|watermarks| {
+            let removed = watermarks.remove(channel).is_some();
+            if removed {
+                info!("Deregistered channel {}", channel);
+            }
+            removed
+        });
+    }
+
+    /// Number of registered channels.
+    pub fn parties(&self) -> usize {
+        self.watermarks.borrow().len()
+    }
+
+    /// Advance the watermark of a channel. Watermarks never move backwards.
+    pub fn advance(&self, channel: &str, watermark: OffsetDateTime) {
+        self.watermarks.send_if_modified(|watermarks| match watermarks.get_mut(channel) {
+            Some(current) if watermark > *current => {
+                debug!("Advancing watermark of {} to {}", channel, watermark);
+                *current = watermark;
+                true
+            }
+            _ => false,
+        });
+    }
+
+    /// Watermark of a single channel.
+    pub fn channel_watermark(&self, channel: &str) -> Option<OffsetDateTime> {
+        self.watermarks.borrow().get(channel).cloned()
+    }
+
+    /// The global watermark, which is the minimum watermark over all channels.
+    pub fn watermark(&self) -> Option<OffsetDateTime> {
+        Self::min_watermark(&self.watermarks.borrow())
+    }
+
+    /// Wait until the global watermark has reached `event_time` and return the global watermark.
+    ///
+    /// Returns immediately if no channels are registered.
+    pub async fn wait_for(&self, event_time: OffsetDateTime) -> Option<OffsetDateTime> {
+        let mut rx = self.watermarks.subscribe();
+        let res = rx
+            .wait_for(|watermarks| Self::min_watermark(watermarks).is_none_or(|w| w >= event_time))
+            .await;
+        match res {
+            Ok(watermarks) => Self::min_watermark(&watermarks),
+            // The sender lives in self so this can't happen
+            Err(_) => None,
+        }
+    }
+
+    fn min_watermark(watermarks: &HashMap<String, OffsetDateTime>) -> Option<OffsetDateTime> {
+        watermarks.values().min().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use test_log::test;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_global_watermark_is_minimum() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let sync = WatermarkSync::new(&["trades".into(), "ticks".into()], start);
+
+        sync.advance("trades", start + Duration::from_secs(10));
+        assert_eq!(sync.watermark(), Some(start));
+
+        sync.advance("ticks", start + Duration::from_secs(5));
+        assert_eq!(sync.watermark(), Some(start + Duration::from_secs(5)));
+
+        // Watermarks never move backwards
+        sync.advance("ticks", start);
+        assert_eq!(sync.channel_watermark("ticks"), Some(start + Duration::from_secs(5)));
+    }
+
+    #[test(tokio::test)]
+    async fn test_wait_for_releases_when_all_channels_advance() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let target = start + Duration::from_secs(60);
+        let sync = Arc::new(WatermarkSync::new(&["trades".into(), "ticks".into()], start));
+
+        let waiter = {
+            let sync = sync.clone();
+            tokio::spawn(async move { sync.wait_for(target).await })
+        };
+
+        sync.advance("trades", target);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        sync.advance("ticks", target + Duration::from_secs(1));
+        let watermark = tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert_eq!(watermark, Some(target));
+    }
+
+    #[test(tokio::test)]
+    async fn test_early_stream_end_does_not_block() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let target = start + Duration::from_secs(3600);
+        let sync = Arc::new(WatermarkSync::new(&["trades".into(), "ticks".into()], start));
+
+        let waiter = {
+            let sync = sync.clone();
+            tokio::spawn(async move { sync.wait_for(target).await })
+        };
+
+        // The ticks stream ends early and leaves the trades stream as the only party
+        sync.advance("trades", target);
+        sync.deregister("ticks");
+        assert_eq!(sync.parties(), 1);
+
+        let watermark = tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert_eq!(watermark, Some(target));
+
+        // With no parties left nothing can hold back consumers
+        sync.deregister("trades");
+        assert_eq!(sync.watermark(), None);
+        assert_eq!(sync.wait_for(target + Duration::from_secs(60)).await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn test_late_joining_channel() {
+        let start = datetime!(2024-01-01 00:00).assume_utc();
+        let sync = WatermarkSync::new(&["trades".into()], start);
+        sync.advance("trades", start + Duration::from_secs(120));
+
+        // Joining before the global watermark is clamped to it
+        sync.register("ticks", start);
+        assert_eq!(sync.channel_watermark("ticks"), Some(start + Duration::from_secs(120)));
+        assert_eq!(sync.parties(), 2);
+
+        // The late joiner now holds back the global watermark
+        sync.advance("trades", start + Duration::from_secs(300));
+        assert_eq!(sync.watermark(), Some(start + Duration::from_secs(120)));
+
+        sync.advance("ticks", start + Duration::from_secs(600));
+        assert_eq!(sync.watermark(), Some(start + Duration::from_secs(300)));
+    }
+}
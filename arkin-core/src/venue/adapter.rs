@@ -0,0 +1,123 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::{ClassifyError, ErrorClass, Instrument, InstrumentStatus, VenueOrder};
+
+use super::{RateLimiter, SymbolMap};
+
+#[derive(Error, Debug)]
+pub enum VenueAdapterError {
+    #[error("Authentication failed: {0}")]
+    Authentication(String),
+
+    #[error("Rate limited by the venue, retry after {0:?}")]
+    RateLimited(Duration),
+
+    #[error("Request rejected by the venue: {0}")]
+    Rejected(String),
+
+    #[error("Unsupported by the venue: {0}")]
+    Unsupported(String),
+
+    #[error("Connection error: {0}")]
+    Connection(String),
+
+    #[error("Could not parse the venue response: {0}")]
+    Parse(String),
+}
+
+impl ClassifyError for VenueAdapterError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            VenueAdapterError::RateLimited(_) | VenueAdapterError::Connection(_) => ErrorClass::Transient,
+            VenueAdapterError::Rejected(_) | VenueAdapterError::Unsupported(_) | VenueAdapterError::Parse(_) => {
+                ErrorClass::InvalidInput
+            }
+            VenueAdapterError::Authentication(_) => ErrorClass::Fatal,
+        }
+    }
+}
+
+/// Balance of a single asset as reported by the venue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueBalance {
+    pub asset: String,
+    pub balance: Decimal,
+    pub available: Decimal,
+}
+
+/// Open position as reported by the venue, the quantity is negative for short positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenuePosition {
+    pub venue_symbol: String,
+    pub quantity: Decimal,
+    pub entry_price: Decimal,
+    pub unrealized_pnl: Decimal,
+}
+
+/// Trading status of a symbol as listed by the venue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VenueInstrumentStatus {
+    pub venue_symbol: String,
+    pub status: InstrumentStatus,
+}
+
+/// Everything that differs per exchange behind one interface: symbol mapping, market data subscriptions, order
+/// submission and account queries. Ingestors and executors share the adapter of a venue, and with it the connection
+/// settings, credentials and rate limits, instead of each building their own clients.
+#[async_trait]
+pub trait VenueAdapter: fmt::Debug + Send + Sync {
+    /// Name of the venue, matches the name of the venue in persistence
+    fn venue(&self) -> &str;
+
+    fn symbols(&self) -> &SymbolMap;
+
+    /// Request weight budget shared by everything talking to the venue
+    fn rate_limiter(&self) -> &Arc<RateLimiter>;
+
+    fn venue_symbol(&self, instrument: &Instrument) -> String {
+        self.symbols().venue_symbol(instrument)
+    }
+
+    fn instrument(&self, venue_symbol: &str) -> Option<Arc<Instrument>> {
+        self.symbols().instrument(venue_symbol)
+    }
+
+    /// Websocket url serving the public market data
+    fn market_data_url(&self) -> String;
+
+    /// Name of the stream of a market data channel (like `aggTrade` or `bookTicker`) for an instrument.
+    fn market_data_stream(&self, instrument: &Instrument, channel: &str) -> String;
+
+    async fn place_order(&self, order: &VenueOrder) -> Result<(), VenueAdapterError>;
+
+    /// Cancel every open order of the instrument.
+    async fn cancel_all_orders(&self, instrument: &Instrument) -> Result<(), VenueAdapterError>;
+
+    async fn balances(&self) -> Result<Vec<VenueBalance>, VenueAdapterError>;
+
+    async fn positions(&self) -> Result<Vec<VenuePosition>, VenueAdapterError>;
+
+    /// Status of every symbol listed on the venue, symbols missing from the list are delisted.
+    async fn instrument_statuses(&self) -> Result<Vec<VenueInstrumentStatus>, VenueAdapterError>;
+}
+
+/// Adapters of all venues, so every component talking to a venue uses the same instance.
+#[derive(Debug, Default)]
+pub struct VenueAdapters {
+    adapters: DashMap<String, Arc<dyn VenueAdapter>>,
+}
+
+impl VenueAdapters {
+    pub fn insert(&self, adapter: Arc<dyn VenueAdapter>) {
+        self.adapters.insert(adapter.venue().to_owned(), adapter);
+    }
+
+    pub fn get(&self, venue: &str) -> Option<Arc<dyn VenueAdapter>> {
+        self.adapters.get(venue).map(|a| a.value().clone())
+    }
+}
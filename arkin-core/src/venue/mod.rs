@@ -0,0 +1,9 @@
+mod adapter;
+mod rate_limit;
+mod registry;
+mod symbols;
+
+pub use adapter::*;
+pub use rate_limit::*;
+pub use registry::*;
+pub use symbols::*;
@@ -0,0 +1,112 @@
+use std::{sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+use tracing::warn;
+use typed_builder::TypedBuilder;
+
+#[derive(Debug, Default)]
+struct RateLimitState {
+    window_start: Option<Instant>,
+    used: u32,
+    blocked_until: Option<Instant>,
+}
+
+/// Request weight budget of a venue over a fixed window. Every client of the venue draws from the same limiter, so
+/// the ingestors and the executor together stay below the limits of the exchange.
+#[derive(Debug, TypedBuilder)]
+pub struct RateLimiter {
+    max_weight: u32,
+    #[builder(default = Duration::from_secs(60))]
+    window: Duration,
+    #[builder(default)]
+    state: Mutex<RateLimitState>,
+}
+
+impl RateLimiter {
+    /// Take weight from the budget, returns how long to wait if the budget is used up.
+    pub fn try_acquire(&self, weight: u32) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("Rate limiter lock poisoned");
+        if let Some(until) = state.blocked_until {
+            if until > now {
+                return Err(until - now);
+            }
+            state.blocked_until = None;
+        }
+
+        let window_start = *state.window_start.get_or_insert(now);
+        if now - window_start >= self.window {
+            state.window_start = Some(now);
+            state.used = 0;
+        }
+        let window_end = state.window_start.expect("Window is started") + self.window;
+        if state.used + weight > self.max_weight {
+            return Err(window_end - now);
+        }
+        state.used += weight;
+        Ok(())
+    }
+
+    /// Wait until the weight fits in the budget and take it.
+    pub async fn acquire(&self, weight: u32) {
+        while let Err(wait) = self.try_acquire(weight) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Align the budget with the weight the venue reports as used, which includes requests from other processes.
+    pub fn update_used(&self, used: u32) {
+        let mut state = self.state.lock().expect("Rate limiter lock poisoned");
+        state.window_start.get_or_insert_with(Instant::now);
+        state.used = state.used.max(used);
+    }
+
+    /// Stop all requests for a while after the venue rejected one for exceeding its limits.
+    pub fn back_off(&self, retry_after: Duration) {
+        warn!("Rate limited by the venue, backing off for {:?}", retry_after);
+        let mut state = self.state.lock().expect("Rate limiter lock poisoned");
+        state.blocked_until = Some(Instant::now() + retry_after);
+    }
+
+    pub fn used(&self) -> u32 {
+        self.state.lock().expect("Rate limiter lock poisoned").used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_budget_resets_after_window() {
+        let limiter = RateLimiter::builder().max_weight(10).window(Duration::from_secs(60)).build();
+        assert!(limiter.try_acquire(6).is_ok());
+        assert!(limiter.try_acquire(4).is_ok());
+        assert_eq!(limiter.try_acquire(1), Err(Duration::from_secs(60)));
+
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert_eq!(limiter.try_acquire(1), Err(Duration::from_secs(15)));
+
+        let start = Instant::now();
+        limiter.acquire(5).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(15));
+        assert_eq!(limiter.used(), 5);
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_reported_weight_and_back_off() {
+        let limiter = RateLimiter::builder().max_weight(10).build();
+        limiter.update_used(9);
+        assert!(limiter.try_acquire(2).is_err());
+        assert!(limiter.try_acquire(1).is_ok());
+
+        limiter.back_off(Duration::from_secs(5));
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(limiter.try_acquire(1).is_ok());
+
+        limiter.back_off(Duration::from_secs(5));
+        assert_eq!(limiter.try_acquire(1), Err(Duration::from_secs(5)));
+    }
+}
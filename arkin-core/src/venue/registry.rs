@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::Instrument;
+
+use super::SymbolMap;
+
+/// Quote assets recognized when splitting a venue symbol into base and quote, longest first so `USDT` wins over `USD`.
+const QUOTE_ASSETS: [&str; 8] = ["FDUSD", "USDT", "USDC", "BUSD", "USD", "EUR", "BTC", "ETH"];
+
+/// Venue specific asset names and their canonical counterpart.
+const ASSET_ALIASES: [(&str, &str); 2] = [("XBT", "BTC"), ("XETH", "ETH")];
+
+/// Venue independent symbol of an instrument, like `perp-btc-usdt` for `perp-btc-usdt@binance`.
+pub fn canonical_symbol(instrument: &Instrument) -> String {
+    match instrument.symbol.split_once('@') {
+        Some((symbol, _)) => symbol.to_owned(),
+        None => instrument.symbol.clone(),
+    }
+}
+
+/// Normalize a venue symbol to a `base-quote` pair, so `BTCUSDT`, `btc_usdt` and `BTC-USD` or `XBTUSD` can be matched
+/// against each other. Returns `None` when no known quote asset can be split off.
+pub fn normalize_venue_symbol(venue_symbol: &str) -> Option<String> {
+    let symbol = venue_symbol
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+    let (base, quote) = QUOTE_ASSETS
+        .iter()
+        .find_map(|q| symbol.strip_suffix(q).filter(|b| !b.is_empty()).map(|b| (b, *q)))?;
+    let alias = |asset: &str| {
+        ASSET_ALIASES
+            .iter()
+            .find(|(a, _)| *a == asset)
+            .map(|(_, canonical)| canonical.to_string())
+            .unwrap_or_else(|| asset.to_owned())
+    };
+    Some(format!("{}-{}", alias(base), alias(quote)).to_lowercase())
+}
+
+/// Maps a venue symbol to a canonical symbol for one venue, overriding what is known from the instrument itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolOverride {
+    pub venue: String,
+    pub venue_symbol: String,
+    pub canonical_symbol: String,
+}
+
+/// Central mapping between the symbols of all venues and canonical instruments. Strategies and configs refer to
+/// instruments by canonical symbol, ingestors and executors translate from and to the venue symbols here so the same
+/// config works on every venue.
+///
+/// Venues are matched case insensitive.
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    /// Instruments per venue by venue symbol
+    venues: DashMap<String, SymbolMap>,
+    /// Instruments by venue and canonical symbol
+    canonical: DashMap<(String, String), Arc<Instrument>>,
+    /// Canonical symbol by venue and upper case venue symbol
+    overrides: DashMap<(String, String), String>,
+    /// Venue symbol by venue and canonical symbol, the reverse of the overrides
+    override_symbols: DashMap<(String, String), String>,
+}
+
+impl SymbolRegistry {
+    pub fn insert(&self, instrument: Arc<Instrument>) {
+        let venue = instrument.venue.name.to_lowercase();
+        let canonical = self
+            .overrides
+            .get(&(venue.clone(), instrument.venue_symbol.to_uppercase()))
+            .map(|c| c.value().clone())
+            .unwrap_or_else(|| canonical_symbol(&instrument));
+        self.venues.entry(venue.clone()).or_default().insert(instrument.clone());
+        self.canonical.insert((venue, canonical), instrument);
+    }
+
+    pub fn add_override(&self, symbol_override: SymbolOverride) {
+        let venue = symbol_override.venue.to_lowercase();
+        self.overrides.insert(
+            (venue.clone(), symbol_override.venue_symbol.to_uppercase()),
+            symbol_override.canonical_symbol.clone(),
+        );
+        self.override_symbols
+            .insert((venue, symbol_override.canonical_symbol), symbol_override.venue_symbol);
+    }
+
+    /// Canonical symbol the venue symbol is overridden to, if any.
+    pub fn override_for(&self, venue: &str, venue_symbol: &str) -> Option<String> {
+        self.overrides
+            .get(&(venue.to_lowercase(), venue_symbol.to_uppercase()))
+            .map(|c| c.value().clone())
+    }
+
+    /// Instrument of a symbol on a venue. The symbol is looked up as venue symbol first, then as canonical symbol and
+    /// last by its normalized pair when that matches exactly one instrument of the venue.
+    pub fn instrument(&self, venue: &str, symbol: &str) -> Option<Arc<Instrument>> {
+        let venue = venue.to_lowercase();
+        if let Some(canonical) = self.override_for(&venue, symbol) {
+            return self.resolve(&venue, &canonical);
+        }
+        if let Some(instrument) = self.venues.get(&venue).and_then(|s| s.instrument(symbol)) {
+            return Some(instrument);
+        }
+        if let Some(instrument) = self.resolve(&venue, symbol) {
+            return Some(instrument);
+        }
+
+        let pair = normalize_venue_symbol(symbol)?;
+        let suffix = format!("-{}", pair);
+        let mut matches = self
+            .canonical
+            .iter()
+            .filter(|e| e.key().0 == venue && e.key().1.ends_with(&suffix))
+            .map(|e| e.value().clone());
+        match (matches.next(), matches.next()) {
+            (Some(instrument), None) => Some(instrument),
+            _ => None,
+        }
+    }
+
+    /// Instrument of a canonical symbol on a venue.
+    pub fn resolve(&self, venue: &str, canonical_symbol: &str) -> Option<Arc<Instrument>> {
+        self.canonical
+            .get(&(venue.to_lowercase(), canonical_symbol.to_owned()))
+            .map(|i| i.value().clone())
+    }
+
+    /// Symbol to use for the instrument when talking to its venue.
+    pub fn venue_symbol(&self, instrument: &Instrument) -> String {
+        let venue = instrument.venue.name.to_lowercase();
+        self.override_symbols
+            .get(&(venue, canonical_symbol(instrument)))
+            .map(|s| s.value().clone())
+            .unwrap_or_else(|| instrument.venue_symbol.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.canonical.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.canonical.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_inst_binance_btc_usdt_perp, test_inst_binance_eth_usdt_perp};
+
+    #[test]
+    fn test_normalize_venue_symbol() {
+        assert_eq!(normalize_venue_symbol("BTCUSDT").as_deref(), Some("btc-usdt"));
+        assert_eq!(normalize_venue_symbol("btc_usdt").as_deref(), Some("btc-usdt"));
+        assert_eq!(normalize_venue_symbol("BTC-USD").as_deref(), Some("btc-usd"));
+        assert_eq!(normalize_venue_symbol("XBTUSD").as_deref(), Some("btc-usd"));
+        assert_eq!(normalize_venue_symbol("ETH/BTC").as_deref(), Some("eth-btc"));
+        assert_eq!(normalize_venue_symbol("USDT"), None);
+        assert_eq!(normalize_venue_symbol("FOOBAR"), None);
+    }
+
+    #[test]
+    fn test_lookup_by_venue_and_canonical_symbol() {
+        let registry = SymbolRegistry::default();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        registry.insert(btc.clone());
+        registry.insert(eth.clone());
+
+        assert_eq!(canonical_symbol(&btc), "perp-btc-usdt");
+        assert_eq!(registry.instrument("binance", "btcusdt"), Some(btc.clone()));
+        assert_eq!(registry.instrument("Binance", "perp-eth-usdt"), Some(eth.clone()));
+        assert_eq!(registry.instrument("binance", "BTC-USDT"), Some(btc.clone()));
+        assert_eq!(registry.resolve("binance", "perp-btc-usdt"), Some(btc.clone()));
+        assert!(registry.instrument("bybit", "BTCUSDT").is_none());
+        assert!(registry.instrument("binance", "SOLUSDT").is_none());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let registry = SymbolRegistry::default();
+        registry.add_override(SymbolOverride {
+            venue: "binance".into(),
+            venue_symbol: "XBTUSDT".into(),
+            canonical_symbol: "perp-btc-usdt".into(),
+        });
+        let btc = test_inst_binance_btc_usdt_perp();
+        registry.insert(btc.clone());
+
+        assert_eq!(registry.instrument("binance", "XBTUSDT"), Some(btc.clone()));
+        assert_eq!(registry.venue_symbol(&btc), "XBTUSDT");
+        assert_eq!(registry.override_for("BINANCE", "xbtusdt").as_deref(), Some("perp-btc-usdt"));
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::Instrument;
+
+/// Maps the symbols a venue uses to instruments and back.
+#[derive(Debug, Default)]
+pub struct SymbolMap {
+    instruments: DashMap<String, Arc<Instrument>>,
+}
+
+impl SymbolMap {
+    pub fn insert(&self, instrument: Arc<Instrument>) {
+        self.instruments.insert(instrument.venue_symbol.to_uppercase(), instrument);
+    }
+
+    /// Instrument of a venue symbol, symbols are matched case insensitive as streams use lower case names.
+    pub fn instrument(&self, venue_symbol: &str) -> Option<Arc<Instrument>> {
+        self.instruments.get(&venue_symbol.to_uppercase()).map(|i| i.value().clone())
+    }
+
+    pub fn venue_symbol(&self, instrument: &Instrument) -> String {
+        instrument.venue_symbol.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_inst_binance_btc_usdt_perp;
+
+    #[test]
+    fn test_lookup_is_case_insensitive() {
+        let symbols = SymbolMap::default();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        symbols.insert(instrument.clone());
+
+        assert_eq!(symbols.instrument("btcusdt"), Some(instrument.clone()));
+        assert_eq!(symbols.instrument("BTCUSDT"), Some(instrument.clone()));
+        assert_eq!(symbols.venue_symbol(&instrument), "BTCUSDT");
+        assert!(symbols.instrument("ETHUSDT").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use std::{collections::VecDeque, path::PathBuf, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::broadcast::error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{AuditSettings, TradingEngineError};
+
+/// Event as recorded by the audit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub recorded_at: OffsetDateTime,
+    pub event_type: EventType,
+    pub event: String,
+}
+
+impl AuditEntry {
+    fn line(&self) -> String {
+        format!("{} {:?} {}\n", self.recorded_at, self.event_type, self.event)
+    }
+}
+
+#[derive(Debug, Default)]
+struct AuditState {
+    /// Most recent events kept for inspection
+    recent: VecDeque<AuditEntry>,
+    /// Events not yet written to the file
+    unflushed: VecDeque<AuditEntry>,
+    dropped: u64,
+}
+
+/// Records every published event. Only the most recent events within the retention are kept in memory, the full
+/// trail is appended to a file in the background so a long running instance doesn't grow without limit.
+#[derive(Debug, TypedBuilder)]
+pub struct Audit {
+    pubsub: Arc<PubSub>,
+    /// Maximum number of events kept in memory, the oldest are dropped first
+    #[builder(default = 100_000)]
+    capacity: usize,
+    /// Events older than this are dropped from memory
+    #[builder(default = Duration::from_secs(3600))]
+    retention: Duration,
+    #[builder(default = Duration::from_secs(1))]
+    flush_interval: Duration,
+    /// File the events are appended to, without it they only live in memory
+    #[builder(default)]
+    path: Option<PathBuf>,
+    #[builder(default)]
+    state: Mutex<AuditState>,
+}
+
+impl Audit {
+    pub fn from_config(config: &AuditSettings, pubsub: Arc<PubSub>) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .capacity(config.capacity)
+            .retention(Duration::from_secs(config.retention_secs))
+            .flush_interval(Duration::from_millis(config.flush_interval_ms))
+            .path(config.path.clone())
+            .build()
+    }
+
+    pub fn record(&self, event: &Event) {
+        let entry = AuditEntry {
+            recorded_at: OffsetDateTime::now_utc(),
+            event_type: event.event_type(),
+            event: format!("{:?}", event),
+        };
+        let mut state = self.state.lock();
+        if self.path.is_some() {
+            // A file that can't keep up loses the oldest events instead of growing the backlog
+            if state.unflushed.len() >= self.capacity {
+                state.unflushed.pop_front();
+                state.dropped += 1;
+            }
+            state.unflushed.push_back(entry.clone());
+        }
+        if state.recent.len() >= self.capacity {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(entry);
+    }
+
+    /// Events in memory from oldest to newest.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.state.lock().recent.iter().cloned().collect()
+    }
+
+    /// Drop the events past the retention from memory.
+    pub fn expire(&self, now: OffsetDateTime) {
+        let mut state = self.state.lock();
+        while state.recent.front().is_some_and(|e| now - e.recorded_at > self.retention) {
+            state.recent.pop_front();
+        }
+    }
+
+    /// Append the events recorded since the last flush to the file.
+    pub async fn flush(&self) -> Result<(), TradingEngineError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let (entries, dropped) = {
+            let mut state = self.state.lock();
+            (
+                state.unflushed.drain(..).collect::<Vec<_>>(),
+                std::mem::take(&mut state.dropped),
+            )
+        };
+        if dropped > 0 {
+            warn!("Audit dropped {} events that were not written in time", dropped);
+        }
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+        let lines = entries.iter().map(|e| e.line()).collect::<String>();
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        debug!("Audit flushed {} events to {}", entries.len(), path.display());
+        Ok(())
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting audit...");
+        let mut events = self.pubsub.subscribe_events(EventFilter::default());
+        let mut interval = tokio::time::interval(self.flush_interval);
+        loop {
+            tokio::select! {
+                res = events.recv() => match res {
+                    Ok(event) => self.record(&event),
+                    Err(RecvError::Lagged(skipped)) => warn!("Audit lagged behind, skipped {} events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = interval.tick() => {
+                    self.expire(OffsetDateTime::now_utc());
+                    skip_failed_event("audit", self.flush().await)?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(seconds: u64) -> Event {
+        let tick = IntervalTick::builder()
+            .event_time(OffsetDateTime::UNIX_EPOCH)
+            .instruments(vec![])
+            .frequency(Duration::from_secs(seconds))
+            .build();
+        Event::IntervalTick(tick.into())
+    }
+
+    #[test]
+    fn test_capacity_and_retention() {
+        let audit = Audit::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .capacity(2)
+            .retention(Duration::from_secs(60))
+            .build();
+        for seconds in 1..=3 {
+            audit.record(&event(seconds));
+        }
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].event.contains("2s"));
+        assert!(audit.state.lock().unflushed.is_empty());
+
+        audit.expire(OffsetDateTime::now_utc() + Duration::from_secs(61));
+        assert!(audit.entries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flush_to_file() {
+        let path =
+            std::env::temp_dir().join(format!("arkin-audit-{}.log", OffsetDateTime::now_utc().unix_timestamp_nanos()));
+        let audit = Audit::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .capacity(10)
+            .path(Some(path.clone()))
+            .build();
+        audit.record(&event(1));
+        audit.flush().await.unwrap();
+        audit.record(&event(2));
+        audit.flush().await.unwrap();
+        audit.flush().await.unwrap();
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content.lines().all(|l| l.contains("IntervalTick")));
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,659 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_tungstenite::{tokio::connect_async, tungstenite::Message as WsMessage};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::{
+    net::TcpListener,
+    sync::broadcast::{error::RecvError, Receiver},
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{BridgeLinkSettings, EventBridgeSettings, TradingEngineError};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Bridged events remembered to extend their path when they are forwarded again
+const BRIDGED_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeDirection {
+    /// Receive events from the other instance
+    Subscribe,
+    /// Send events to the other instance
+    Publish,
+}
+
+/// Events that can cross the bridge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeEventType {
+    Tick,
+    Trade,
+    VenueOrderUpdate,
+}
+
+impl BridgeEventType {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            BridgeEventType::Tick => EventType::Tick,
+            BridgeEventType::Trade => EventType::Trade,
+            BridgeEventType::VenueOrderUpdate => EventType::VenueOrderUpdate,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BridgeEventType::Tick => "tick",
+            BridgeEventType::Trade => "trade",
+            BridgeEventType::VenueOrderUpdate => "venue_order_update",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tick" => Some(BridgeEventType::Tick),
+            "trade" => Some(BridgeEventType::Trade),
+            "venue_order_update" => Some(BridgeEventType::VenueOrderUpdate),
+            _ => None,
+        }
+    }
+}
+
+/// Event on the wire. Instruments and assets go by their symbol and portfolios by their name, the receiving instance
+/// looks them up in its own database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeEvent {
+    Tick {
+        event_time: OffsetDateTime,
+        instrument: String,
+        tick_id: u64,
+        bid_price: Price,
+        bid_quantity: Quantity,
+        ask_price: Price,
+        ask_quantity: Quantity,
+    },
+    Trade {
+        event_time: OffsetDateTime,
+        instrument: String,
+        trade_id: u64,
+        side: MarketSide,
+        price: Price,
+        quantity: Quantity,
+    },
+    VenueOrderUpdate {
+        event_time: OffsetDateTime,
+        portfolio: String,
+        instrument: String,
+        order_id: String,
+        venue_order_id: i64,
+        side: MarketSide,
+        order_type: VenueOrderType,
+        time_in_force: VenueOrderTimeInForce,
+        price: Price,
+        quantity: Quantity,
+        fill_price: Price,
+        fill_quantity: Quantity,
+        last_fill_price: Price,
+        last_fill_quantity: Quantity,
+        status: VenueOrderStatus,
+        liquidity: LiquidityRole,
+        commission_asset: Option<String>,
+        commission: Commission,
+        #[serde(default)]
+        exchange_time: Option<OffsetDateTime>,
+        #[serde(default)]
+        received_at: Option<OffsetDateTime>,
+    },
+}
+
+impl BridgeEvent {
+    /// Wire form of the event, none for events that can't cross the bridge.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let event = match event {
+            Event::Tick(tick) => BridgeEvent::Tick {
+                event_time: tick.event_time,
+                instrument: tick.instrument.symbol.clone(),
+                tick_id: tick.tick_id,
+                bid_price: tick.bid_price,
+                bid_quantity: tick.bid_quantity,
+                ask_price: tick.ask_price,
+                ask_quantity: tick.ask_quantity,
+            },
+            Event::Trade(trade) => BridgeEvent::Trade {
+                event_time: trade.event_time,
+                instrument: trade.instrument.symbol.clone(),
+                trade_id: trade.trade_id,
+                side: trade.side,
+                price: trade.price,
+                quantity: trade.quantity,
+            },
+            Event::VenueOrderUpdate(update) => BridgeEvent::VenueOrderUpdate {
+                event_time: update.event_time,
+                portfolio: update.portfolio.name.clone(),
+                instrument: update.instrument.symbol.clone(),
+                order_id: update.order_id.clone(),
+                venue_order_id: update.venue_order_id,
+                side: update.side,
+                order_type: update.order_type,
+                time_in_force: update.time_in_force,
+                price: update.price,
+                quantity: update.quantity,
+                fill_price: update.fill_price,
+                fill_quantity: update.fill_quantity,
+                last_fill_price: update.last_fill_price,
+                last_fill_quantity: update.last_fill_quantity,
+                status: update.status,
+                liquidity: update.liquidity,
+                commission_asset: update.commission_asset.as_ref().map(|a| a.symbol.clone()),
+                commission: update.commission,
+                exchange_time: update.exchange_time,
+                received_at: Some(update.received_at),
+            },
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    pub fn event_type(&self) -> BridgeEventType {
+        match self {
+            BridgeEvent::Tick { .. } => BridgeEventType::Tick,
+            BridgeEvent::Trade { .. } => BridgeEventType::Trade,
+            BridgeEvent::VenueOrderUpdate { .. } => BridgeEventType::VenueOrderUpdate,
+        }
+    }
+
+    pub fn instrument(&self) -> &str {
+        match self {
+            BridgeEvent::Tick { instrument, .. } => instrument,
+            BridgeEvent::Trade { instrument, .. } => instrument,
+            BridgeEvent::VenueOrderUpdate { instrument, .. } => instrument,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeMessage {
+    /// Instances the event passed, starting with the one it was published on
+    pub path: Vec<String>,
+    pub event: BridgeEvent,
+}
+
+/// Paths of the events received over the bridge, so forwarding one again extends its path instead of starting a new
+/// one. Events are keyed by their address and held until they are evicted, so the address can't be reused meanwhile.
+#[derive(Debug, Default)]
+struct BridgedEvents {
+    paths: HashMap<usize, Vec<String>>,
+    order: VecDeque<(usize, Event)>,
+}
+
+impl BridgedEvents {
+    fn insert(&mut self, event: Event, path: Vec<String>) {
+        let Some(key) = event_key(&event) else {
+            return;
+        };
+        if self.order.len() >= BRIDGED_CAPACITY {
+            if let Some((key, _)) = self.order.pop_front() {
+                self.paths.remove(&key);
+            }
+        }
+        self.paths.insert(key, path);
+        self.order.push_back((key, event));
+    }
+
+    /// Path of the event so far, empty for events published on this instance.
+    fn path(&self, event: &Event) -> Vec<String> {
+        event_key(event)
+            .and_then(|key| self.paths.get(&key).cloned())
+            .unwrap_or_default()
+    }
+}
+
+fn event_key(event: &Event) -> Option<usize> {
+    match event {
+        Event::Tick(e) => Some(Arc::as_ptr(e) as usize),
+        Event::Trade(e) => Some(Arc::as_ptr(e) as usize),
+        Event::VenueOrderUpdate(e) => Some(Arc::as_ptr(e) as usize),
+        _ => None,
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EventsQuery {
+    /// Comma separated event types to receive
+    #[serde(default)]
+    types: String,
+    /// Comma separated instrument symbols, all instruments when empty
+    #[serde(default)]
+    instruments: String,
+}
+
+/// Forwards selected events between arkin instances over websockets, for example to let a research instance follow
+/// the fills of production. Instances serve their exported events and accept the imported ones on `/events`, links
+/// connect to the bridges of other instances. Every message carries the instances it passed, an instance drops
+/// events that already passed it or that passed more than `max_hops` instances, so cycles of links can't loop.
+#[derive(Debug, TypedBuilder)]
+pub struct EventBridge {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    instance: String,
+    #[builder(default)]
+    address: Option<String>,
+    #[builder(default)]
+    export: HashSet<BridgeEventType>,
+    #[builder(default)]
+    import: HashSet<BridgeEventType>,
+    #[builder(default)]
+    links: Vec<BridgeLinkSettings>,
+    #[builder(default = 4)]
+    max_hops: usize,
+    #[builder(default)]
+    bridged: Mutex<BridgedEvents>,
+}
+
+impl EventBridge {
+    pub fn from_config(
+        config: &EventBridgeSettings,
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+    ) -> Arc<Self> {
+        Self::builder()
+            .pubsub(pubsub)
+            .persistence(persistence)
+            .instance(config.instance.clone())
+            .address(config.address.clone())
+            .export(config.export.iter().copied().collect())
+            .import(config.import.iter().copied().collect())
+            .links(config.links.clone())
+            .max_hops(config.max_hops)
+            .build()
+            .into()
+    }
+
+    pub async fn start(self: Arc<Self>, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting event bridge {}...", self.instance);
+        let tracker = TaskTracker::new();
+        for link in self.links.clone() {
+            tracker.spawn(self.clone().run_link(link, shutdown.clone()));
+        }
+
+        if let Some(address) = &self.address {
+            let listener = TcpListener::bind(address).await?;
+            info!("Event bridge listening on {}", address);
+            let router = Router::new().route("/events", get(events)).with_state(self.clone());
+            let shutdown = shutdown.clone();
+            axum::serve(listener, router)
+                .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                .await?;
+        } else {
+            shutdown.cancelled().await;
+        }
+
+        tracker.close();
+        tracker.wait().await;
+        info!("Event bridge {} stopped", self.instance);
+        Ok(())
+    }
+
+    fn subscribe(&self, types: &HashSet<BridgeEventType>) -> Receiver<Event> {
+        self.pubsub
+            .subscribe_events(EventFilter::default().event_types(types.iter().map(|t| t.event_type())))
+    }
+
+    /// Message for a local event, none when the event may not leave this instance.
+    fn outgoing(&self, event: &Event, instruments: &HashSet<String>) -> Option<String> {
+        let bridge_event = BridgeEvent::from_event(event)?;
+        if !instruments.is_empty() && !instruments.contains(bridge_event.instrument()) {
+            return None;
+        }
+        let mut path = self.bridged.lock().path(event);
+        path.push(self.instance.clone());
+        if path.len() > self.max_hops {
+            debug!("Dropping bridged event that passed {} instances", path.len() - 1);
+            return None;
+        }
+        let message = BridgeMessage {
+            path,
+            event: bridge_event,
+        };
+        Some(serde_json::to_string(&message).expect("Failed to serialize bridge message"))
+    }
+
+    /// Publish a received message locally if its type is allowed and it didn't pass this instance before.
+    async fn incoming(&self, text: &str, allowed: &HashSet<BridgeEventType>) {
+        let message = match serde_json::from_str::<BridgeMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Invalid bridge message: {}", e);
+                return;
+            }
+        };
+        if message.path.contains(&self.instance) {
+            debug!("Dropping bridged event that already passed {}", self.instance);
+            return;
+        }
+        if !allowed.contains(&message.event.event_type()) {
+            debug!(
+                "Dropping bridged {} event, it is not imported",
+                message.event.event_type().name()
+            );
+            return;
+        }
+
+        let event = match self.resolve(message.event).await {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Failed to resolve bridged event: {}", e);
+                return;
+            }
+        };
+        // Remember the path before publishing so a publish link forwarding the event sees it
+        self.bridged.lock().insert(event.clone(), message.path);
+        match event {
+            Event::Tick(tick) => self.pubsub.publish::<Tick>(tick),
+            Event::Trade(trade) => self.pubsub.publish::<Trade>(trade),
+            Event::VenueOrderUpdate(update) => self.pubsub.publish::<VenueOrderUpdate>(update),
+            _ => {}
+        }
+    }
+
+    async fn resolve(&self, event: BridgeEvent) -> Result<Event, PersistenceError> {
+        let event = match event {
+            BridgeEvent::Tick {
+                event_time,
+                instrument,
+                tick_id,
+                bid_price,
+                bid_quantity,
+                ask_price,
+                ask_quantity,
+            } => {
+                let tick = Tick::builder()
+                    .event_time(event_time)
+                    .instrument(self.persistence.instrument_store.read_by_symbol(&instrument).await?)
+                    .tick_id(tick_id)
+                    .bid_price(bid_price)
+                    .bid_quantity(bid_quantity)
+                    .ask_price(ask_price)
+                    .ask_quantity(ask_quantity)
+                    .build();
+                Event::Tick(Arc::new(tick))
+            }
+            BridgeEvent::Trade {
+                event_time,
+                instrument,
+                trade_id,
+                side,
+                price,
+                quantity,
+            } => {
+                let trade = Trade::builder()
+                    .event_time(event_time)
+                    .instrument(self.persistence.instrument_store.read_by_symbol(&instrument).await?)
+                    .trade_id(trade_id)
+                    .side(side)
+                    .price(price)
+                    .quantity(quantity)
+                    .build();
+                Event::Trade(Arc::new(trade))
+            }
+            BridgeEvent::VenueOrderUpdate {
+                event_time,
+                portfolio,
+                instrument,
+                order_id,
+                venue_order_id,
+                side,
+                order_type,
+                time_in_force,
+                price,
+                quantity,
+                fill_price,
+                fill_quantity,
+                last_fill_price,
+                last_fill_quantity,
+                status,
+                liquidity,
+                commission_asset,
+                commission,
+                exchange_time,
+                received_at,
+            } => {
+                let commission_asset = match commission_asset {
+                    Some(symbol) => Some(self.persistence.asset_store.read_by_symbol(&symbol).await?),
+                    None => None,
+                };
+                let update = VenueOrderUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(self.persistence.portfolio_store.read_by_name(&portfolio).await?)
+                    .instrument(self.persistence.instrument_store.read_by_symbol(&instrument).await?)
+                    .order_id(order_id)
+                    .venue_order_id(venue_order_id)
+                    .side(side)
+                    .order_type(order_type)
+                    .time_in_force(time_in_force)
+                    .price(price)
+                    .quantity(quantity)
+                    .fill_price(fill_price)
+                    .fill_quantity(fill_quantity)
+                    .last_fill_price(last_fill_price)
+                    .last_fill_quantity(last_fill_quantity)
+                    .status(status)
+                    .liquidity(liquidity)
+                    .commission_asset(commission_asset)
+                    .commission(commission)
+                    .exchange_time(exchange_time)
+                    .received_at(received_at.unwrap_or_else(OffsetDateTime::now_utc))
+                    .build();
+                Event::VenueOrderUpdate(Arc::new(update))
+            }
+        };
+        Ok(event)
+    }
+
+    /// Serve one connection, stream the requested exported events and publish the imported ones it sends.
+    async fn serve_connection(
+        self: Arc<Self>,
+        socket: WebSocket,
+        mut events: Receiver<Event>,
+        instruments: HashSet<String>,
+    ) {
+        let (mut sender, mut receiver) = socket.split();
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        let Some(text) = self.outgoing(&event, &instruments) else {
+                            continue;
+                        };
+                        if sender.send(Message::Text(text)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Event bridge connection skipped {} events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                msg = receiver.next() => match msg {
+                    Some(Ok(Message::Text(text))) => self.incoming(&text, &self.import).await,
+                    Some(Ok(Message::Ping(payload))) => {
+                        let _ = sender.send(Message::Pong(payload)).await;
+                    }
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        debug!("Event bridge connection closed");
+    }
+
+    async fn run_link(self: Arc<Self>, link: BridgeLinkSettings, shutdown: CancellationToken) {
+        loop {
+            let res = match link.direction {
+                BridgeDirection::Subscribe => self.subscribe_link(&link, &shutdown).await,
+                BridgeDirection::Publish => self.publish_link(&link, &shutdown).await,
+            };
+            if let Err(e) = res {
+                warn!("Event bridge link to {} failed: {}", link.url, e);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(RECONNECT_DELAY) => info!("Reconnecting event bridge link to {}", link.url),
+                _ = shutdown.cancelled() => break,
+            }
+        }
+    }
+
+    /// Receive the events of the link from the other instance and publish them here.
+    async fn subscribe_link(
+        &self,
+        link: &BridgeLinkSettings,
+        shutdown: &CancellationToken,
+    ) -> Result<(), async_tungstenite::tungstenite::Error> {
+        let types = link.event_types.iter().copied().collect::<HashSet<_>>();
+        let url = format!(
+            "{}/events?types={}&instruments={}",
+            link.url.trim_end_matches('/'),
+            link.event_types.iter().map(|t| t.name()).collect::<Vec<_>>().join(","),
+            link.instruments.join(",")
+        );
+        let (mut stream, _) = connect_async(url).await?;
+        info!("Event bridge subscribed to {}", link.url);
+        loop {
+            tokio::select! {
+                msg = stream.next() => match msg {
+                    Some(Ok(WsMessage::Text(text))) => self.incoming(&text, &types).await,
+                    Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e),
+                },
+                _ = shutdown.cancelled() => {
+                    stream.send(WsMessage::Close(None)).await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Send the local events of the link to the other instance.
+    async fn publish_link(
+        &self,
+        link: &BridgeLinkSettings,
+        shutdown: &CancellationToken,
+    ) -> Result<(), async_tungstenite::tungstenite::Error> {
+        let types = link.event_types.iter().copied().collect::<HashSet<_>>();
+        let instruments = link.instruments.iter().cloned().collect::<HashSet<_>>();
+        // Subscribe before connecting, the connection doesn't ask for events so none are sent back
+        let mut events = self.subscribe(&types);
+        let (mut stream, _) = connect_async(format!("{}/events", link.url.trim_end_matches('/'))).await?;
+        info!("Event bridge publishing to {}", link.url);
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if let Some(text) = self.outgoing(&event, &instruments) {
+                            stream.send(WsMessage::Text(text)).await?;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Event bridge link to {} skipped {} events", link.url, skipped),
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                msg = stream.next() => match msg {
+                    Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e),
+                },
+                _ = shutdown.cancelled() => {
+                    stream.send(WsMessage::Close(None)).await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn events(
+    State(bridge): State<Arc<EventBridge>>,
+    Query(query): Query<EventsQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let types = split_list(&query.types)
+        .filter_map(BridgeEventType::from_name)
+        .filter(|t| bridge.export.contains(t))
+        .collect::<HashSet<_>>();
+    let instruments = split_list(&query.instruments).map(str::to_owned).collect::<HashSet<_>>();
+    let events = bridge.subscribe(&types);
+    ws.on_upgrade(move |socket| bridge.serve_connection(socket, events, instruments))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn trade() -> Event {
+        let trade = Trade::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .trade_id(1)
+            .side(MarketSide::Buy)
+            .price(dec!(100_000))
+            .quantity(dec!(0.1))
+            .build();
+        Event::Trade(Arc::new(trade))
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let event = BridgeEvent::from_event(&trade()).unwrap();
+        let message = BridgeMessage {
+            path: vec!["prod".into()],
+            event,
+        };
+        let text = serde_json::to_string(&message).unwrap();
+        let decoded = serde_json::from_str::<BridgeMessage>(&text).unwrap();
+        assert_eq!(decoded.path, vec!["prod".to_string()]);
+        assert_eq!(decoded.event.event_type(), BridgeEventType::Trade);
+        assert_eq!(decoded.event.instrument(), test_inst_binance_btc_usdt_perp().symbol);
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), text);
+        assert_eq!(
+            BridgeEventType::from_name("venue_order_update"),
+            Some(BridgeEventType::VenueOrderUpdate)
+        );
+    }
+
+    #[test]
+    fn test_bridged_paths() {
+        let mut bridged = BridgedEvents::default();
+        let received = trade();
+        let local = trade();
+        bridged.insert(received.clone(), vec!["prod".into(), "relay".into()]);
+        assert_eq!(bridged.path(&received), vec!["prod".to_string(), "relay".to_string()]);
+        assert!(bridged.path(&local).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use std::{sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_execution::prelude::*;
+use arkin_insights::prelude::*;
+use arkin_persistence::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::TradingEngineError;
+
+/// Checkpoints a simulation into persistence at the barriers of the replay, so a crashed backtest can resume from its
+/// last checkpoint instead of from scratch. The replay is held at the barrier until the checkpoint is written, so it
+/// holds the state of every event before the barrier and none after it: the simulated clock, the feature state of the
+/// insights, the positions and balances of the portfolio, the sub-accounts of the strategies and the positions, open
+/// orders, order books, balances and fault generators of the simulated venues. Only the latest checkpoint of a run is
+/// kept.
+#[derive(Debug, TypedBuilder)]
+pub struct SimulationCheckpointer {
+    /// Holds the events before the barrier the services didn't receive yet
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    insights: Arc<InsightsService>,
+    portfolio: Arc<dyn Accounting>,
+    executor: Arc<dyn Executor>,
+    /// Books of the strategies, if they trade on sub-accounts
+    #[builder(default)]
+    sub_accounts: Option<Arc<SubAccountLedger>>,
+    /// Holds the replay of the sim ingestors at every checkpoint
+    barrier: Arc<ReplayBarrier>,
+    /// Tracks the handlers still running on the events before the barrier
+    watchdog: Arc<Watchdog>,
+    /// Name the checkpoints of the run are stored under
+    run: String,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    /// Frequency of the interval ticks driving the insights
+    frequency: Duration,
+}
+
+impl SimulationCheckpointer {
+    /// Wait until the services handled the events published before the barrier: every subscriber received them and
+    /// no handler is running. The watchdog wakes the wait whenever a handler finishes, subscribers that aren't tracked
+    /// get the turn to catch up. Services that don't settle within the hard deadline are checkpointed as they are.
+    async fn settle(&self) {
+        let mut running = self.watchdog.running();
+        let settled = async {
+            loop {
+                let _ = running.wait_for(|running| *running == 0).await;
+                if self.pubsub.queued() == 0 {
+                    break;
+                }
+                tokio::select! {
+                    _ = running.changed() => {}
+                    _ = tokio::task::yield_now() => {}
+                }
+            }
+        };
+        if tokio::time::timeout(self.watchdog.hard_deadline(), settled).await.is_err() {
+            warn!("Services of run {} didn't settle before the checkpoint", self.run);
+        }
+    }
+
+    /// Write a checkpoint of the simulation at the given simulated time.
+    pub async fn checkpoint(&self, event_time: OffsetDateTime) -> Result<SimulationCheckpoint, TradingEngineError> {
+        // Make sure everything up to the checkpoint is persisted before we write it
+        self.persistence.flush().await?;
+        let mut checkpoint = SimulationCheckpoint::builder()
+            .clock(ClockState {
+                start: self.start,
+                end: self.end,
+                frequency_secs: self.frequency.as_secs(),
+                current_timestamp: event_time,
+            })
+            .features(self.insights.checkpoint())
+            .build();
+        self.portfolio.checkpoint(&mut checkpoint);
+        self.executor.checkpoint(&mut checkpoint);
+        if let Some(sub_accounts) = &self.sub_accounts {
+            sub_accounts.checkpoint(&mut checkpoint);
+        }
+        self.persistence.checkpoint_store.insert(&self.run, &checkpoint).await?;
+        info!(
+            "Checkpointed run {} at {} with {} positions and {} open orders",
+            self.run,
+            event_time,
+            checkpoint.venue.as_ref().map_or(0, |v| v.positions.len()),
+            checkpoint.venue.as_ref().map_or(0, |v| v.open_orders.len())
+        );
+        Ok(checkpoint)
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting simulation checkpointer for run {}...", self.run);
+        loop {
+            tokio::select! {
+                barrier = self.barrier.reached() => {
+                    self.settle().await;
+                    let res = self.checkpoint(barrier).await;
+                    // The replay goes on also if the checkpoint failed
+                    self.barrier.release();
+                    skip_failed_event("simulation checkpointer", res)?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use std::path::PathBuf;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use arkin_core::prelude::*;
+
+use crate::{BridgeDirection, BridgeEventType};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Record the published events, nothing is recorded without it
+    #[serde(default)]
+    pub audit: Option<AuditSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditSettings {
+    /// Maximum number of events kept in memory
+    pub capacity: usize,
+    /// How long events are kept in memory
+    pub retention_secs: u64,
+    pub flush_interval_ms: u64,
+    /// File the events are appended to, they only live in memory without it
+    pub path: Option<PathBuf>,
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 100_000,
+            retention_secs: 3600,
+            flush_interval_ms: 1000,
+            path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExposureConfig {
+    /// Export the exposure matrices and heat map, nothing is recorded without it
+    #[serde(default)]
+    pub exposure: Option<ExposureSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExposureSettings {
+    /// Directory the matrices and the heat map are written to
+    pub dir: PathBuf,
+    /// How often the export is rewritten while running
+    pub flush_interval_secs: u64,
+}
+
+impl Default for ExposureSettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("exposure"),
+            flush_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyConfig {
+    /// Track the latency of the order path, nothing is measured without it
+    #[serde(default)]
+    pub latency: Option<LatencySettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencySettings {
+    /// File the percentile report is written to
+    pub path: PathBuf,
+    /// How often the report is rewritten while running
+    pub flush_interval_secs: u64,
+    /// Latencies kept per venue and stage for the percentiles
+    pub max_samples: usize,
+}
+
+impl Default for LatencySettings {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("latency.json"),
+            flush_interval_secs: 60,
+            max_samples: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkoutConfig {
+    /// Mark out the fills to measure adverse selection, nothing is measured without it
+    #[serde(default)]
+    pub markouts: Option<MarkoutSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkoutSettings {
+    /// Time after the fill the mid price is compared to the fill price
+    pub horizons_secs: Vec<u64>,
+    /// File the report per strategy and venue is written to
+    pub path: PathBuf,
+    /// How often the report is rewritten while running
+    pub flush_interval_secs: u64,
+}
+
+impl Default for MarkoutSettings {
+    fn default() -> Self {
+        Self {
+            horizons_secs: vec![1, 10, 60],
+            path: PathBuf::from("markouts.json"),
+            flush_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsistencyConfig {
+    /// Cross-validate the state of the services, nothing is checked without it
+    #[serde(default)]
+    pub consistency: Option<ConsistencySettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsistencySettings {
+    pub interval_secs: u64,
+    /// Largest position difference that is not reported
+    pub tolerance: Decimal,
+}
+
+impl Default for ConsistencySettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 60,
+            tolerance: Decimal::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifecycleConfig {
+    /// Follow listings and delistings of the venue, instrument statuses are not synced without it
+    #[serde(default)]
+    pub lifecycle: Option<LifecycleSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifecycleSettings {
+    /// Venue adapter the exchange info is synced from
+    pub venue: String,
+    pub interval_secs: u64,
+    /// Close the positions in instruments entering settlement or getting delisted
+    pub flatten: bool,
+}
+
+impl Default for LifecycleSettings {
+    fn default() -> Self {
+        Self {
+            venue: "binance".to_string(),
+            interval_secs: 300,
+            flatten: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UniverseConfig {
+    /// Trade a universe selected from the loaded instruments, all loaded instruments are traded without it
+    #[serde(default)]
+    pub universe: Option<UniverseSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniverseSettings {
+    #[serde(default = "default_universe_name")]
+    pub name: String,
+    /// How often the members are selected again
+    #[serde(default = "default_universe_refresh_secs")]
+    pub refresh_secs: u64,
+    /// How long instruments that joined only get their insights computed before they are traded
+    #[serde(default)]
+    pub warmup_secs: u64,
+    pub selection: UniverseSelectionConfig,
+}
+
+fn default_universe_name() -> String {
+    "default".to_string()
+}
+
+fn default_universe_refresh_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UniverseSelectionConfig {
+    #[serde(rename = "static")]
+    Static(StaticUniverseConfig),
+    #[serde(rename = "top_volume")]
+    TopVolume(TopVolumeUniverseConfig),
+    #[serde(rename = "metric_filter")]
+    MetricFilter(MetricFilterUniverseConfig),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaticUniverseConfig {
+    pub symbols: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopVolumeUniverseConfig {
+    /// Number of instruments with the most notional traded
+    pub n: usize,
+    pub lookback_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricFilterUniverseConfig {
+    /// Pipeline the insights were persisted by
+    pub pipeline: String,
+    /// Conditions on the last insights of an instrument, like `vol_std_60 > 0.002 and spread_bps < 5`
+    pub filter: String,
+    pub lookback_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CovarianceConfig {
+    /// Estimate the covariance of the returns across the universe, the allocation estimates its own without it
+    #[serde(default)]
+    pub covariance: Option<CovarianceSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CovarianceSettings {
+    /// Feature of the insight ticks holding the returns of an instrument (e.g., log_return_60)
+    pub returns_feature_id: String,
+    /// Number of returns per instrument the matrix is estimated from
+    #[serde(default = "default_covariance_periods")]
+    pub periods: usize,
+    /// How often the matrix is estimated and published, on the clock of the insight ticks
+    #[serde(default = "default_covariance_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_covariance_periods() -> usize {
+    500
+}
+
+fn default_covariance_interval_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LeaderElectionConfig {
+    /// Run hot/standby with the other instances holding the same lease, every instance leads without it
+    #[serde(default)]
+    pub leader_election: Option<LeaderElectionSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderElectionSettings {
+    /// Lease shared by the instances, one per account
+    pub lease: String,
+    /// Name of this instance in the lease, defaults to the hostname
+    pub holder: Option<String>,
+    /// A standby takes over this long after the leader stopped renewing
+    pub ttl_secs: u64,
+    pub renew_interval_secs: u64,
+}
+
+impl Default for LeaderElectionSettings {
+    fn default() -> Self {
+        Self {
+            lease: "execution".to_string(),
+            holder: None,
+            ttl_secs: 10,
+            renew_interval_secs: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventBridgeConfig {
+    /// Forward events to and from other instances, no event leaves the process without it
+    #[serde(default)]
+    pub event_bridge: Option<EventBridgeSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventBridgeSettings {
+    /// Name of this instance on the bridge, events that already passed it are dropped
+    pub instance: String,
+    /// Serve the events on this address for other instances to connect to (e.g., 0.0.0.0:8091)
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Event types other instances may subscribe to
+    #[serde(default)]
+    pub export: Vec<BridgeEventType>,
+    /// Event types other instances may publish here, empty for a read-only instance
+    #[serde(default)]
+    pub import: Vec<BridgeEventType>,
+    /// Connections to the bridges of other instances
+    #[serde(default)]
+    pub links: Vec<BridgeLinkSettings>,
+    /// Events are dropped once they passed this many instances
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+}
+
+fn default_max_hops() -> usize {
+    4
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeLinkSettings {
+    /// Bridge of the other instance (e.g., ws://prod-1:8091)
+    pub url: String,
+    pub direction: BridgeDirection,
+    pub event_types: Vec<BridgeEventType>,
+    /// Instrument symbols, all instruments when empty
+    #[serde(default)]
+    pub instruments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmUpConfig {
+    /// Warm up the insights of a live engine from one minute bars instead of the raw trades, the pipeline then takes
+    /// the bar features as inputs
+    #[serde(default)]
+    pub warm_up: Option<WarmUpSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarmUpSettings {
+    /// Frequency of the bars, a whole number of minutes is read from the `trades_1m` aggregate
+    pub bar_frequency_secs: u64,
+}
+
+impl Default for WarmUpSettings {
+    fn default() -> Self {
+        Self {
+            bar_frequency_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsExporterConfig {
+    /// Serve the metrics for scraping, they are not exported without it
+    #[serde(default)]
+    pub metrics_exporter: Option<MetricsExporterSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsExporterSettings {
+    /// Address the `/metrics` endpoint listens on, like `0.0.0.0:9100`
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlAuthConfig {
+    /// Require API tokens on the control server, anyone reaching its address may use it without
+    #[serde(default)]
+    pub control_auth: Option<ControlAuthSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlAuthSettings {
+    pub tokens: Vec<ControlTokenSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlTokenSettings {
+    /// Who holds the token, recorded with every request made with it
+    pub name: String,
+    /// Sent as `Authorization: Bearer <token>`
+    pub token: String,
+    pub role: ControlRole,
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+use arkin_execution::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::{ConsistencySettings, TradingEngineError};
+
+#[derive(Debug, Default)]
+struct LedgerState {
+    /// Positions of the accounting when the checker started
+    baseline: HashMap<Arc<Instrument>, Decimal>,
+    /// Net quantity filled per instrument since the start
+    fills: HashMap<Arc<Instrument>, Decimal>,
+    /// Last reported status of every order seen on the venue
+    orders: HashMap<Uuid, (Arc<Instrument>, VenueOrderStatus)>,
+    /// Differences found at the previous check per check name
+    suspects: HashMap<&'static str, HashSet<String>>,
+}
+
+/// Periodically cross-validates the state of the services against a ledger it keeps from the order updates of the
+/// venue: the positions of the accounting against the baseline plus the fills since, and the open orders of the
+/// order manager against the order status on the venue. A difference has to show up in two checks in a row before a
+/// [`ConsistencyViolation`] is published, so events still in flight don't raise false alarms.
+#[derive(Debug, TypedBuilder)]
+pub struct ConsistencyChecker {
+    pubsub: Arc<PubSub>,
+    portfolio: Arc<dyn Accounting>,
+    order_manager: Arc<dyn OrderManager>,
+    instruments: Vec<Arc<Instrument>>,
+    #[builder(default = Duration::from_secs(60))]
+    interval: Duration,
+    /// Largest position difference that is not reported
+    #[builder(default)]
+    tolerance: Decimal,
+    #[builder(default)]
+    state: Mutex<LedgerState>,
+}
+
+impl ConsistencyChecker {
+    pub fn from_config(
+        config: &ConsistencySettings,
+        pubsub: Arc<PubSub>,
+        portfolio: Arc<dyn Accounting>,
+        order_manager: Arc<dyn OrderManager>,
+        instruments: Vec<Arc<Instrument>>,
+    ) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .portfolio(portfolio)
+            .order_manager(order_manager)
+            .instruments(instruments)
+            .interval(Duration::from_secs(config.interval_secs))
+            .tolerance(config.tolerance)
+            .build()
+    }
+
+    /// Take the current positions of the accounting as starting point of the ledger.
+    pub async fn load_baseline(&self) {
+        let positions = self.portfolio.get_positions().await;
+        let mut state = self.state.lock();
+        state.baseline = positions.values().map(|p| (p.instrument.clone(), signed(p))).collect();
+    }
+
+    pub fn update(&self, update: &VenueOrderUpdate) {
+        let mut state = self.state.lock();
+        if !update.last_fill_quantity.is_zero() {
+            let quantity = Decimal::from(update.side) * update.last_fill_quantity.abs();
+            *state.fills.entry(update.instrument.clone()).or_default() += quantity;
+        }
+        match Uuid::parse_str(&update.order_id) {
+            Ok(id) => {
+                state.orders.insert(id, (update.instrument.clone(), update.status));
+            }
+            Err(e) => warn!("Order update with unknown order id {}: {}", update.order_id, e),
+        }
+    }
+
+    /// Compare the services against the ledger and return the violations confirmed by the previous check.
+    pub async fn check(&self, now: OffsetDateTime) -> Vec<Arc<ConsistencyViolation>> {
+        let positions = self.portfolio.get_positions().await;
+        let positions = positions
+            .values()
+            .map(|p| (p.instrument.clone(), signed(p)))
+            .collect::<HashMap<_, _>>();
+        let open_orders = self
+            .instruments
+            .iter()
+            .flat_map(|i| self.order_manager.open_orders(&TradingScope::Instrument(i.symbol.clone())))
+            .collect::<HashSet<_>>();
+
+        let mut state = self.state.lock();
+        let position_diffs = self.position_diffs(&state, &positions);
+        let order_diffs = order_diffs(&state, &open_orders);
+        // Finalized orders the order manager let go of are settled and leave the ledger
+        state
+            .orders
+            .retain(|id, (_, status)| !status.is_finalized() || open_orders.contains(id));
+
+        let mut violations = Vec::new();
+        for (check, diffs) in [("positions", position_diffs), ("open_orders", order_diffs)] {
+            let previous = state.suspects.insert(check, diffs.clone()).unwrap_or_default();
+            let mut confirmed = diffs.intersection(&previous).cloned().collect::<Vec<_>>();
+            if confirmed.is_empty() {
+                continue;
+            }
+            confirmed.sort();
+            warn!("Consistency check {} failed: {}", check, confirmed.join(", "));
+            let violation = ConsistencyViolation::builder()
+                .event_time(now)
+                .check(check.to_string())
+                .diffs(confirmed)
+                .build();
+            violations.push(Arc::new(violation));
+        }
+        violations
+    }
+
+    fn position_diffs(&self, state: &LedgerState, positions: &HashMap<Arc<Instrument>, Decimal>) -> HashSet<String> {
+        let instruments = state
+            .baseline
+            .keys()
+            .chain(state.fills.keys())
+            .chain(positions.keys())
+            .collect::<HashSet<_>>();
+        instruments
+            .into_iter()
+            .filter_map(|instrument| {
+                let expected = state.baseline.get(instrument).copied().unwrap_or_default()
+                    + state.fills.get(instrument).copied().unwrap_or_default();
+                let actual = positions.get(instrument).copied().unwrap_or_default();
+                ((expected - actual).abs() > self.tolerance).then(|| {
+                    format!(
+                        "position {} is {} in accounting but {} from fills",
+                        instrument.symbol, actual, expected
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting consistency checker...");
+        let mut order_updates = self.pubsub.subscribe::<VenueOrderUpdate>();
+        self.load_baseline().await;
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                Ok(update) = order_updates.recv() => self.update(&update),
+                _ = interval.tick() => {
+                    for violation in self.check(OffsetDateTime::now_utc()).await {
+                        self.pubsub.publish::<ConsistencyViolation>(violation);
+                    }
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn signed(position: &PositionUpdate) -> Decimal {
+    match position.position_side {
+        PositionSide::Long => position.quantity.abs(),
+        PositionSide::Short => -position.quantity.abs(),
+    }
+}
+
+fn order_diffs(state: &LedgerState, open_orders: &HashSet<Uuid>) -> HashSet<String> {
+    let mut diffs = HashSet::new();
+    for (id, (instrument, status)) in &state.orders {
+        let open = open_orders.contains(id);
+        if status.is_finalized() && open {
+            diffs.insert(format!(
+                "order {} on {} is {} on the venue but open in the order manager",
+                id, instrument.symbol, status
+            ));
+        } else if !status.is_finalized() && !open {
+            diffs.insert(format!(
+                "order {} on {} is {} on the venue but unknown to the order manager",
+                id, instrument.symbol, status
+            ));
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn update(id: Uuid, status: VenueOrderStatus, fill: Decimal) -> VenueOrderUpdate {
+        VenueOrderUpdate::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_id(id.to_string())
+            .venue_order_id(1)
+            .side(MarketSide::Buy)
+            .order_type(VenueOrderType::Market)
+            .time_in_force(VenueOrderTimeInForce::Gtc)
+            .price(dec!(100000))
+            .quantity(dec!(1))
+            .fill_price(dec!(100000))
+            .fill_quantity(fill)
+            .last_fill_price(dec!(100000))
+            .last_fill_quantity(fill)
+            .status(status)
+            .commission_asset(None)
+            .commission(Decimal::ZERO)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let mut portfolio = MockAccounting::new();
+        portfolio.expect_get_positions().returning(HashMap::new);
+        let mut order_manager = MockOrderManager::new();
+        order_manager.expect_open_orders().returning(|_| Vec::new());
+        let checker = ConsistencyChecker::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .portfolio(Arc::new(portfolio))
+            .order_manager(Arc::new(order_manager))
+            .instruments(vec![test_inst_binance_btc_usdt_perp()])
+            .build();
+        checker.load_baseline().await;
+        let now = datetime!(2025-01-01 00:01 UTC);
+        assert!(checker.check(now).await.is_empty());
+
+        // A fill the accounting never booked and an order the order manager doesn't know
+        let id = Uuid::new_v4();
+        checker.update(&update(id, VenueOrderStatus::PartiallyFilled, dec!(0.5)));
+        assert!(checker.check(now).await.is_empty());
+        let violations = checker.check(now).await;
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].check, "positions");
+        assert!(violations[0].diffs[0].contains("is 0 in accounting but 0.5 from fills"));
+        assert_eq!(violations[1].check, "open_orders");
+
+        // Finalized orders the order manager doesn't track anymore leave the ledger
+        checker.update(&update(id, VenueOrderStatus::Filled, Decimal::ZERO));
+        checker.check(now).await;
+        assert!(checker.state.lock().orders.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::{ControlAuth, TradingEngineError};
+
+/// Request to enable or disable trading for an instrument or strategy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradingControlRequest {
+    pub scope: TradingScope,
+    pub enabled: bool,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Request to move capital from the sub-account of one strategy to another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapitalTransferRequest {
+    pub from: String,
+    pub to: String,
+    pub amount: Decimal,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Request to move funds between the wallets of the venue account or its sub-accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountTransferRequest {
+    /// Asset symbol, like `USDT`
+    pub asset: String,
+    pub quantity: Decimal,
+    pub from: AccountType,
+    pub to: AccountType,
+    #[serde(default)]
+    pub from_sub_account: Option<String>,
+    #[serde(default)]
+    pub to_sub_account: Option<String>,
+    pub operator: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Answer to an account transfer, the id its [`AccountTransferUpdate`] is published with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountTransferAccepted {
+    pub id: Uuid,
+}
+
+/// Order an operator enters by hand, a market order without a price and a limit order with one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManualOrderRequest {
+    /// Instrument symbol, like `perp-btc-usdt@binance`
+    pub instrument: String,
+    pub side: MarketSide,
+    pub quantity: Decimal,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+    pub operator: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Request to cancel an open order by hand.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManualCancelRequest {
+    pub id: ExecutionOrderId,
+    pub operator: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Answer to a manual order, the id to cancel it with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManualOrderAccepted {
+    pub id: ExecutionOrderId,
+}
+
+/// Sub-account of a strategy as listed by the control server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubAccountState {
+    pub strategy: String,
+    pub capital: Decimal,
+    pub equity: Decimal,
+    pub margin_used: Decimal,
+    pub return_on_capital: Decimal,
+}
+
+/// Scope trading is disabled for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisabledTrading {
+    pub scope: TradingScope,
+    pub reason: String,
+}
+
+/// Turns trading on and off for instruments and strategies of a running engine over HTTP:
+///
+/// - `GET /trading/controls` lists the disabled instruments and strategies
+/// - `POST /trading/controls` takes a [`TradingControlRequest`] and publishes it as [`TradingControl`], the order
+///   manager rejects new orders in a disabled scope and the engine cancels its open orders
+/// - `GET /capital/accounts` lists the sub-accounts of the strategies
+/// - `POST /capital/transfers` takes a [`CapitalTransferRequest`] and publishes it as [`CapitalTransfer`], the ledger
+///   books it if the giving strategy has the capital free
+/// - `POST /account/transfers` takes an [`AccountTransferRequest`] and publishes it as [`AccountTransfer`], the
+///   transfer manager executes it on the venue if it is within the transfer limits
+/// - `POST /orders` takes a [`ManualOrderRequest`] and publishes it as an order of the [`MANUAL_STRATEGY`], it passes
+///   the same checks in the order manager as the orders of the other strategies
+/// - `POST /orders/cancel` takes a [`ManualCancelRequest`], the engine cancels the order at the venue
+///
+/// Every manual order and cancel is published as [`ManualOrder`] with the operator and reason for the audit. The `POST`
+/// routes are only served with [`ControlAuth`], every request then needs a bearer token whose role may use the route
+/// and is published as [`ControlAccess`] with the holder of the token and whether it was allowed.
+#[derive(Debug, TypedBuilder)]
+pub struct TradingControlServer {
+    address: String,
+    pubsub: Arc<PubSub>,
+    /// Shared with the order manager which applies the published controls
+    switch: Arc<TradingSwitch>,
+    /// Portfolio the engine trades, the manual orders and transfers are booked under it
+    portfolio: Arc<Portfolio>,
+    /// Sub-accounts of the strategies, the capital routes answer not found without it
+    #[builder(default)]
+    ledger: Option<Arc<SubAccountLedger>>,
+    /// Instruments manual orders can be entered for, their assets are the ones funds can be transferred in
+    #[builder(default)]
+    instruments: Vec<Arc<Instrument>>,
+    /// API tokens of the callers, without it only the read-only routes are served
+    #[builder(default)]
+    auth: Option<Arc<ControlAuth>>,
+}
+
+impl TradingControlServer {
+    /// Routes of the server, the ones that change the trading or move funds and orders only with [`ControlAuth`].
+    pub fn router(self: &Arc<Self>) -> Router {
+        let router = Router::new().route("/capital/accounts", get(sub_accounts));
+        let router = match &self.auth {
+            Some(_) => router
+                .route("/trading/controls", get(disabled).post(control))
+                .route("/capital/transfers", post(transfer))
+                .route("/account/transfers", post(account_transfer))
+                .route("/orders", post(place_order))
+                .route("/orders/cancel", post(cancel_order)),
+            None => {
+                warn!("Trading control without control auth, only the read-only routes are served");
+                router.route("/trading/controls", get(disabled))
+            }
+        };
+        router
+            .layer(middleware::from_fn_with_state(self.clone(), authorize))
+            .with_state(self.clone())
+    }
+
+    pub async fn serve(self: Arc<Self>, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        let listener = TcpListener::bind(&self.address).await?;
+        info!("Trading control listening on {}", self.address);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await?;
+        Ok(())
+    }
+}
+
+async fn authorize(State(server): State<Arc<TradingControlServer>>, request: Request, next: Next) -> Response {
+    let Some(auth) = &server.auth else {
+        return next.run(request).await;
+    };
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let res = auth.authorize(&method, &path, request.headers());
+    let holder = match &res {
+        Ok(holder) => Some(*holder),
+        Err(_) => auth.authenticate(request.headers()),
+    };
+    let access = ControlAccess::builder()
+        .event_time(OffsetDateTime::now_utc())
+        .operator(holder.map(|h| h.name.clone()))
+        .role(holder.map(|h| h.role))
+        .method(method.to_string())
+        .path(path)
+        .allowed(res.is_ok())
+        .build();
+    match res {
+        Ok(_) => {
+            debug!("Control access: {}", access);
+            server.pubsub.publish::<ControlAccess>(access.into());
+            next.run(request).await
+        }
+        Err(status) => {
+            warn!("Control access denied: {}", access);
+            server.pubsub.publish::<ControlAccess>(access.into());
+            status.into_response()
+        }
+    }
+}
+
+async fn disabled(State(server): State<Arc<TradingControlServer>>) -> Json<Vec<DisabledTrading>> {
+    let disabled = server
+        .switch
+        .disabled()
+        .into_iter()
+        .map(|(scope, reason)| DisabledTrading { scope, reason })
+        .collect();
+    Json(disabled)
+}
+
+async fn control(
+    State(server): State<Arc<TradingControlServer>>,
+    Json(request): Json<TradingControlRequest>,
+) -> Response {
+    let control = TradingControl::builder()
+        .event_time(OffsetDateTime::now_utc())
+        .scope(request.scope)
+        .enabled(request.enabled)
+        .reason(request.reason)
+        .build();
+    info!("Trading control received: {}", control);
+    server.pubsub.publish::<TradingControl>(control.into());
+    StatusCode::ACCEPTED.into_response()
+}
+
+async fn sub_accounts(State(server): State<Arc<TradingControlServer>>) -> Response {
+    let Some(ledger) = &server.ledger else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let accounts = ledger
+        .sub_accounts()
+        .into_iter()
+        .map(|a| SubAccountState {
+            strategy: a.strategy,
+            capital: a.capital,
+            equity: a.equity,
+            margin_used: a.margin_used,
+            return_on_capital: a.return_on_capital,
+        })
+        .collect::<Vec<_>>();
+    Json(accounts).into_response()
+}
+
+async fn transfer(
+    State(server): State<Arc<TradingControlServer>>,
+    Json(request): Json<CapitalTransferRequest>,
+) -> Response {
+    if server.ledger.is_none() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let transfer = CapitalTransfer::builder()
+        .event_time(OffsetDateTime::now_utc())
+        .portfolio(server.portfolio.clone())
+        .from(request.from)
+        .to(request.to)
+        .amount(request.amount)
+        .reason(request.reason)
+        .build();
+    info!("Capital transfer received: {}", transfer);
+    server.pubsub.publish::<CapitalTransfer>(transfer.into());
+    StatusCode::ACCEPTED.into_response()
+}
+
+async fn account_transfer(
+    State(server): State<Arc<TradingControlServer>>,
+    Json(request): Json<AccountTransferRequest>,
+) -> Response {
+    let asset = server
+        .instruments
+        .iter()
+        .flat_map(|i| [&i.base_asset, &i.quote_asset])
+        .find(|a| a.symbol == request.asset);
+    let Some(asset) = asset else {
+        return (StatusCode::NOT_FOUND, format!("Unknown asset {}", request.asset)).into_response();
+    };
+    if request.quantity <= Decimal::ZERO {
+        return (StatusCode::BAD_REQUEST, "Quantity has to be positive").into_response();
+    }
+    if request.operator.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Transfers need an operator").into_response();
+    }
+    let transfer = AccountTransfer::builder()
+        .event_time(OffsetDateTime::now_utc())
+        .portfolio(server.portfolio.clone())
+        .asset(asset.clone())
+        .quantity(request.quantity)
+        .from(request.from)
+        .to(request.to)
+        .from_sub_account(request.from_sub_account)
+        .to_sub_account(request.to_sub_account)
+        .requested_by(request.operator)
+        .reason(request.reason)
+        .build();
+    info!("Account transfer received: {}", transfer);
+    let accepted = AccountTransferAccepted { id: transfer.id };
+    server.pubsub.publish::<AccountTransfer>(transfer.into());
+    (StatusCode::ACCEPTED, Json(accepted)).into_response()
+}
+
+async fn place_order(
+    State(server): State<Arc<TradingControlServer>>,
+    Json(request): Json<ManualOrderRequest>,
+) -> Response {
+    let Some(instrument) = server.instruments.iter().find(|i| i.symbol == request.instrument) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown instrument {}", request.instrument)).into_response();
+    };
+    if request.quantity <= Decimal::ZERO || request.price.is_some_and(|p| p <= Decimal::ZERO) {
+        return (StatusCode::BAD_REQUEST, "Quantity and price have to be positive").into_response();
+    }
+    if request.operator.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Manual orders need an operator").into_response();
+    }
+    let order_type = match request.price {
+        Some(_) => ExecutionOrderType::Maker,
+        None => ExecutionOrderType::Taker,
+    };
+    let order = ExecutionOrder::builder()
+        .portfolio(server.portfolio.clone())
+        .instrument(instrument.clone())
+        .strategy(Some(manual_strategy()))
+        .order_type(order_type)
+        .side(request.side)
+        .price(request.price.unwrap_or_default())
+        .quantity(request.quantity)
+        .build();
+    // The order manager rejects it as well, checking here tells the operator right away
+    if let Some(scope) = server.switch.blocked_by(&order) {
+        return (StatusCode::CONFLICT, format!("Trading is disabled for {}", scope)).into_response();
+    }
+
+    let manual = ManualOrder::builder()
+        .event_time(order.created_at)
+        .action(ManualOrderAction::Place)
+        .order(order.id)
+        .operator(request.operator)
+        .reason(request.reason)
+        .build();
+    info!("Manual order received: {} {}", manual, order);
+    let accepted = ManualOrderAccepted { id: order.id };
+    server.pubsub.publish::<ManualOrder>(manual.into());
+    server.pubsub.publish::<ExecutionOrder>(order.into());
+    (StatusCode::ACCEPTED, Json(accepted)).into_response()
+}
+
+async fn cancel_order(
+    State(server): State<Arc<TradingControlServer>>,
+    Json(request): Json<ManualCancelRequest>,
+) -> Response {
+    if request.operator.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Manual cancels need an operator").into_response();
+    }
+    let manual = ManualOrder::builder()
+        .event_time(OffsetDateTime::now_utc())
+        .action(ManualOrderAction::Cancel)
+        .order(request.id)
+        .operator(request.operator)
+        .reason(request.reason)
+        .build();
+    info!("Manual cancel received: {}", manual);
+    server.pubsub.publish::<ManualOrder>(manual.into());
+    StatusCode::ACCEPTED.into_response()
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use axum::http::{header::AUTHORIZATION, HeaderMap, Method, StatusCode};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::ControlAuthSettings;
+
+/// Holder of an API token of the control server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlToken {
+    pub name: String,
+    pub role: ControlRole,
+}
+
+/// Checks the API tokens of the requests to the control server. Reading the state takes a read-only token, halting
+/// trading and manual orders a trader token and moving capital or funds an admin token.
+#[derive(Debug, Default, TypedBuilder)]
+pub struct ControlAuth {
+    /// Holders by their token
+    #[builder(default)]
+    tokens: HashMap<String, ControlToken>,
+}
+
+impl ControlAuth {
+    pub fn from_config(config: &ControlAuthSettings) -> Self {
+        Self::builder()
+            .tokens(
+                config
+                    .tokens
+                    .iter()
+                    .map(|t| {
+                        let holder = ControlToken {
+                            name: t.name.clone(),
+                            role: t.role,
+                        };
+                        (t.token.clone(), holder)
+                    })
+                    .collect(),
+            )
+            .build()
+    }
+
+    /// Role a request to the route takes.
+    pub fn required_role(method: &Method, path: &str) -> ControlRole {
+        match (method, path) {
+            (&Method::GET, _) => ControlRole::ReadOnly,
+            (_, "/capital/transfers" | "/account/transfers") => ControlRole::Admin,
+            _ => ControlRole::Trader,
+        }
+    }
+
+    /// Holder of the bearer token of the request, none without a known token.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<&ControlToken> {
+        let token = headers.get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")?;
+        self.tokens.get(token.trim())
+    }
+
+    /// Holder of the token if its role may use the route, otherwise the status to answer with.
+    pub fn authorize(&self, method: &Method, path: &str, headers: &HeaderMap) -> Result<&ControlToken, StatusCode> {
+        let holder = self.authenticate(headers).ok_or(StatusCode::UNAUTHORIZED)?;
+        if holder.role < Self::required_role(method, path) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Ok(holder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use crate::ControlTokenSettings;
+
+    use super::*;
+
+    #[test]
+    fn test_control_roles() {
+        let token = |name: &str, role: ControlRole| ControlTokenSettings {
+            name: name.into(),
+            token: format!("{}-secret", name),
+            role,
+        };
+        let config = ControlAuthSettings {
+            tokens: vec![
+                token("dashboard", ControlRole::ReadOnly),
+                token("alice", ControlRole::Trader),
+                token("ops", ControlRole::Admin),
+            ],
+        };
+        let auth = ControlAuth::from_config(&config);
+        let headers = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+            headers
+        };
+
+        // The dashboard reads the state but can't halt trading
+        let dashboard = headers("dashboard-secret");
+        assert_eq!(
+            auth.authorize(&Method::GET, "/trading/controls", &dashboard).unwrap().name,
+            "dashboard"
+        );
+        assert_eq!(
+            auth.authorize(&Method::POST, "/trading/controls", &dashboard),
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        // A trader places orders but doesn't move funds
+        let trader = headers("alice-secret");
+        assert!(auth.authorize(&Method::POST, "/orders", &trader).is_ok());
+        assert!(auth.authorize(&Method::POST, "/trading/controls", &trader).is_ok());
+        assert_eq!(
+            auth.authorize(&Method::POST, "/account/transfers", &trader),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert!(auth
+            .authorize(&Method::POST, "/account/transfers", &headers("ops-secret"))
+            .is_ok());
+
+        // Unknown or missing tokens are turned away
+        assert_eq!(
+            auth.authorize(&Method::GET, "/capital/accounts", &headers("guess")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            auth.authorize(&Method::GET, "/capital/accounts", &HeaderMap::new()),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::prelude::*;
+use time::{Duration, OffsetDateTime};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{CovarianceSettings, TradingEngineError};
+
+#[derive(Debug, Default)]
+struct CovarianceState {
+    /// Most recent returns per instrument, oldest first
+    returns: HashMap<Arc<Instrument>, VecDeque<f64>>,
+    /// Members of the universe, every instrument with returns is in the matrix until the first universe update
+    members: Option<HashSet<Arc<Instrument>>>,
+    /// Event time the matrix is estimated again
+    next: Option<OffsetDateTime>,
+}
+
+/// Estimates the covariance and correlation of the returns across the universe on the clock of the insight ticks and
+/// publishes them as a [`CovarianceUpdate`]. The last estimate is cached for the services reading it directly.
+#[derive(Debug, TypedBuilder)]
+pub struct CovarianceService {
+    pubsub: Arc<PubSub>,
+    returns_feature_id: FeatureId,
+    periods: usize,
+    interval: Duration,
+    #[builder(default)]
+    state: Mutex<CovarianceState>,
+    #[builder(default)]
+    latest: RwLock<Option<Arc<CovarianceUpdate>>>,
+}
+
+impl CovarianceService {
+    pub fn from_config(config: &CovarianceSettings, pubsub: Arc<PubSub>) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .returns_feature_id(Arc::new(config.returns_feature_id.clone()))
+            .periods(config.periods)
+            .interval(Duration::seconds(config.interval_secs as i64))
+            .build()
+    }
+
+    /// Last published estimate.
+    pub fn latest(&self) -> Option<Arc<CovarianceUpdate>> {
+        self.latest.read().clone()
+    }
+
+    pub fn universe_update(&self, update: &UniverseUpdate) {
+        let mut state = self.state.lock();
+        let members = update.members.iter().cloned().collect::<HashSet<_>>();
+        state.returns.retain(|i, _| members.contains(i) || update.warming.contains(i));
+        state.members = Some(members);
+    }
+
+    /// Record the returns of the tick, returns the new estimate once the interval passed.
+    pub fn update(&self, tick: &InsightTick) -> Option<Arc<CovarianceUpdate>> {
+        let mut state = self.state.lock();
+        for insight in tick.insights.iter().filter(|i| i.feature_id == self.returns_feature_id) {
+            let (Some(instrument), Some(value)) = (&insight.instrument, insight.value.to_f64()) else {
+                continue;
+            };
+            let returns = state.returns.entry(instrument.clone()).or_default();
+            returns.push_back(value);
+            if returns.len() > self.periods {
+                returns.pop_front();
+            }
+        }
+
+        if state.next.is_some_and(|next| tick.event_time < next) {
+            return None;
+        }
+        state.next = Some(tick.event_time + self.interval);
+
+        let mut instruments = state
+            .returns
+            .iter()
+            .filter(|(i, r)| r.len() > 1 && state.members.as_ref().is_none_or(|m| m.contains(*i)))
+            .map(|(i, _)| i.clone())
+            .collect::<Vec<_>>();
+        if instruments.is_empty() {
+            return None;
+        }
+        instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let returns = instruments
+            .iter()
+            .map(|i| state.returns[i].iter().copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let update = Arc::new(CovarianceUpdate::from_returns(tick.event_time, instruments, &returns));
+        debug!("Estimated covariance: {}", update);
+        *self.latest.write() = Some(update.clone());
+        Some(update)
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting covariance service...");
+        let mut insight_ticks = self.pubsub.subscribe::<InsightTick>();
+        let mut universe_updates = self.pubsub.subscribe::<UniverseUpdate>();
+        loop {
+            tokio::select! {
+                Ok(tick) = insight_ticks.recv() => {
+                    if let Some(update) = self.update(&tick) {
+                        self.pubsub.publish::<CovarianceUpdate>(update);
+                    }
+                }
+                Ok(update) = universe_updates.recv() => self.universe_update(&update),
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn tick(event_time: OffsetDateTime, returns: &[(Arc<Instrument>, Decimal)]) -> InsightTick {
+        let insights = returns
+            .iter()
+            .map(|(instrument, value)| {
+                Insight::builder()
+                    .event_time(event_time)
+                    .pipeline(test_pipeline())
+                    .instrument(Some(instrument.clone()))
+                    .feature_id(Arc::new("returns".to_string()))
+                    .value(*value)
+                    .build()
+                    .into()
+            })
+            .collect();
+        InsightTick::builder()
+            .event_time(event_time)
+            .instruments(returns.iter().map(|(i, _)| i.clone()).collect())
+            .insights(insights)
+            .build()
+    }
+
+    #[test]
+    fn test_rolling_covariance() {
+        let service = CovarianceService::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .returns_feature_id(Arc::new("returns".to_string()))
+            .periods(3)
+            .interval(Duration::minutes(5))
+            .build();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+
+        // ETH moves twice as much as BTC in the opposite direction, the oldest return rolls out of the window
+        let start = datetime!(2025-01-01 00:00 UTC);
+        let returns = [dec!(0.5), dec!(0.01), dec!(-0.01), dec!(0.03)];
+        let mut updates = returns
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| {
+                let event_time = start + Duration::minutes(5 * i as i64);
+                service.update(&tick(event_time, &[(btc.clone(), *r), (eth.clone(), -*r * dec!(2))]))
+            })
+            .collect::<Vec<_>>();
+        let update = updates.pop().unwrap();
+        assert_eq!(update.periods, 3);
+        assert_eq!(update.instruments, vec![btc.clone(), eth.clone()]);
+        assert!((update.volatility(&btc).unwrap() - 0.02).abs() < 1e-12);
+        assert!((update.covariance_of(&btc, &eth).unwrap() + 0.0008).abs() < 1e-12);
+        assert!((update.correlation_of(&btc, &eth).unwrap() + 1.).abs() < 1e-12);
+        assert_eq!(service.latest(), Some(update));
+
+        // Nothing is estimated before the interval passed
+        let event_time = start + Duration::minutes(16);
+        assert!(service.update(&tick(event_time, &[(btc.clone(), dec!(0.01))])).is_none());
+
+        // Instruments that left the universe leave the matrix
+        service.universe_update(
+            &UniverseUpdate::builder()
+                .event_time(event_time)
+                .universe("default".to_string())
+                .members(vec![eth.clone()])
+                .build(),
+        );
+        let event_time = start + Duration::minutes(20);
+        let update = service.update(&tick(event_time, &[(eth.clone(), dec!(0.01))])).unwrap();
+        assert_eq!(update.instruments, vec![eth.clone()]);
+        assert_eq!(update.submatrix(&[btc, eth]), None);
+    }
+}
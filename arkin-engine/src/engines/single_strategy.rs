@@ -5,8 +5,9 @@ use std::{
 
 use async_trait::async_trait;
 use time::OffsetDateTime;
+use tokio::sync::broadcast::Receiver;
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use typed_builder::TypedBuilder;
 
 use arkin_allocation::prelude::*;
@@ -17,24 +18,84 @@ use arkin_insights::prelude::*;
 use arkin_persistence::prelude::*;
 use arkin_portfolio::prelude::*;
 
-use crate::{TradingEngine, TradingEngineError};
+use crate::{
+    supervise, Audit, ConsistencyChecker, ErrorPolicies, ExposureRecorder, InstrumentLifecycle, LatencyTracker,
+    LeaderElection, MarkoutTracker, SimulationCheckpointer, TradingControlServer, TradingEngine, TradingEngineError,
+    Universe,
+};
 
 #[derive(Debug, TypedBuilder)]
 pub struct ForecastEngine {
     pubsub: Arc<PubSub>,
     instruments: Vec<Arc<Instrument>>,
+    /// Selects the loaded instruments the interval ticks carry, all of them without it
+    #[builder(default)]
+    universe: Option<Arc<Universe>>,
+    /// Frequency of the interval ticks driving the insights
+    #[builder(default = Duration::from_secs(6))]
+    frequency: Duration,
+    /// Drive the interval ticks from the replayed market data instead of the wall clock
+    #[builder(default = false)]
+    simulation: bool,
+    /// Warm up the insights from bars of this frequency instead of replaying the raw trades
+    #[builder(default)]
+    warm_up_bars: Option<Duration>,
+    /// How to react when a service stops with an error
+    #[builder(default)]
+    error_policies: ErrorPolicies,
+    #[builder(default)]
+    halt_trading: CancellationToken,
+    /// Measures the handlers of the services, shared with the services it watches
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+    /// How often the watchdog looks for stalled handlers
+    #[builder(default = Duration::from_secs(1))]
+    watchdog_interval: Duration,
+    /// Halt trading when a handler passes the hard deadline of the watchdog
+    #[builder(default = false)]
+    halt_on_stall: bool,
 
     #[builder(default)]
     persistor_task_tracker: TaskTracker,
     #[builder(default)]
     persistor_shutdown: CancellationToken,
     persistor: Arc<dyn Persistor>,
+    /// Records the published events, runs and stops together with the persistor
+    #[builder(default)]
+    audit: Option<Arc<Audit>>,
+    /// Times the stages of the order path, runs and stops together with the persistor so the fills of the last orders
+    /// make it into the report
+    #[builder(default)]
+    latency: Option<Arc<LatencyTracker>>,
+    /// Marks out the fills, runs and stops together with the persistor so the last fills make it into the report
+    #[builder(default)]
+    markouts: Option<Arc<MarkoutTracker>>,
+    /// Decides whether this instance sends orders, runs and stops together with the persistor so the lease is only
+    /// released once the executor is down
+    #[builder(default)]
+    leader_election: Option<Arc<LeaderElection>>,
+    /// Forwards events to and from other instances, runs and stops together with the persistor
+    #[builder(default)]
+    event_bridge: Option<Arc<EventBridge>>,
+    /// Serves the metrics for scraping, runs and stops together with the persistor
+    #[builder(default)]
+    metrics: Option<Arc<MetricsExporter>>,
 
     #[builder(default)]
     portfolio_task_tracker: TaskTracker,
     #[builder(default)]
     portfolio_shutdown: CancellationToken,
     portfolio: Arc<dyn Accounting>,
+    /// Freezes the performance of the strategies at every UTC day boundary, runs and stops together with the portfolio
+    #[builder(default)]
+    daily_performance: Option<Arc<DailyPerformanceTracker>>,
+    /// Exports the exposure per instrument and strategy, runs and stops together with the portfolio so the last
+    /// positions make it into the export
+    #[builder(default)]
+    exposure: Option<Arc<ExposureRecorder>>,
+    /// Splits the account into per-strategy sub-accounts, runs and stops together with the portfolio
+    #[builder(default)]
+    sub_accounts: Option<Arc<SubAccountLedger>>,
 
     #[builder(default)]
     ingestor_task_tracker: TaskTracker,
@@ -47,12 +108,27 @@ pub struct ForecastEngine {
     #[builder(default)]
     insights_shutdown: CancellationToken,
     insights: Arc<dyn Insights>,
+    /// Checkpoints a simulation so it can be resumed, runs and stops together with the insights
+    #[builder(default)]
+    checkpointer: Option<Arc<SimulationCheckpointer>>,
+    /// Barrier a resumed simulation continues at, the replay resumes right before its interval tick
+    #[builder(default)]
+    resume_at: Option<OffsetDateTime>,
 
     #[builder(default)]
     allocation_task_tracker: TaskTracker,
     #[builder(default)]
     allocation_shutdown: CancellationToken,
     allocation_optim: Arc<dyn AllocationOptim>,
+    /// Keeps the portfolio delta within a band, runs and halts together with the allocation
+    #[builder(default)]
+    hedger: Option<Arc<dyn AllocationOptim>>,
+    /// Keeps a tail hedge under its own strategy next to the strategies, runs and halts together with the allocation
+    #[builder(default)]
+    overlay: Option<Arc<dyn AllocationOptim>>,
+    /// Publishes the covariance of the returns across the universe, runs and halts together with the allocation
+    #[builder(default)]
+    covariance: Option<Arc<CovarianceService>>,
 
     #[builder(default)]
     order_manager_task_tracker: TaskTracker,
@@ -65,9 +141,206 @@ pub struct ForecastEngine {
     #[builder(default)]
     executor_shutdown: CancellationToken,
     executor: Arc<dyn Executor>,
+    /// Cross-validates the positions and open orders of the services, runs until the executor shuts down
+    #[builder(default)]
+    consistency: Option<Arc<ConsistencyChecker>>,
+    /// Follows listings and delistings of the venue, runs until the executor shuts down
+    #[builder(default)]
+    lifecycle: Option<Arc<InstrumentLifecycle>>,
+    /// Executes the account transfers within the transfer limits, runs until the executor shuts down
+    #[builder(default)]
+    transfers: Option<Arc<TransferManager>>,
+    /// Serves the runtime trading controls, runs until the executor shuts down
+    #[builder(default)]
+    control: Option<Arc<TradingControlServer>>,
 }
 
 impl ForecastEngine {
+    /// Instruments the insights are computed for at the given time.
+    async fn tick_instruments(&self, event_time: OffsetDateTime) -> Vec<Arc<Instrument>> {
+        match &self.universe {
+            Some(universe) => universe.instruments(event_time).await,
+            None => self.instruments.clone(),
+        }
+    }
+
+    /// True once a service failure stopped the trading side of the engine.
+    pub fn trading_halted(&self) -> bool {
+        self.halt_trading.is_cancelled()
+    }
+
+    /// Stop placing new orders and cancel the open ones once trading gets halted, market data and persistence keep
+    /// running.
+    fn watch_halt(&self) {
+        let halt_trading = self.halt_trading.clone();
+        let allocation_shutdown = self.allocation_shutdown.clone();
+        let order_manager_shutdown = self.order_manager_shutdown.clone();
+        let executor_shutdown = self.executor_shutdown.clone();
+        let executor = self.executor.clone();
+        self.executor_tracker.spawn(async move {
+            tokio::select! {
+                _ = halt_trading.cancelled() => {
+                    error!("Halting trading, stopping allocation and order manager");
+                    allocation_shutdown.cancel();
+                    order_manager_shutdown.cancel();
+                    if let Err(e) = executor.cancel_all_orders().await {
+                        error!("Failed to cancel open orders while halting trading: {}", e);
+                    }
+                }
+                _ = executor_shutdown.cancelled() => {}
+            }
+        });
+    }
+
+    /// Cancel the open orders of an instrument or strategy once trading gets disabled for it, the quotes the order
+    /// manager pulls around venue events and the orders an operator cancels by hand. The order manager already
+    /// rejects the new orders.
+    fn watch_controls(&self) {
+        let mut trading_controls = self.pubsub.subscribe::<TradingControl>();
+        let mut quotes_pulled = self.pubsub.subscribe::<QuotesPulled>();
+        let mut manual_orders = self.pubsub.subscribe::<ManualOrder>();
+        let instruments = self.instruments.clone();
+        let order_manager = self.order_manager.clone();
+        let executor = self.executor.clone();
+        let executor_shutdown = self.executor_shutdown.clone();
+        self.executor_tracker.spawn(async move {
+            loop {
+                tokio::select! {
+                    Ok(control) = trading_controls.recv() => {
+                        if control.enabled {
+                            info!("Trading enabled for {}", control.scope);
+                            continue;
+                        }
+                        warn!("Trading disabled for {}, cancelling open orders: {}", control.scope, control.reason);
+                        let instrument = match &control.scope {
+                            TradingScope::Instrument(symbol) => instruments.iter().find(|i| &i.symbol == symbol),
+                            TradingScope::Strategy(_) => None,
+                        };
+                        let res = match instrument {
+                            Some(instrument) => executor.cancel_orders_by_instrument(instrument.clone()).await,
+                            None => executor.cancel_orders(order_manager.open_orders(&control.scope)).await,
+                        };
+                        if let Err(e) = res {
+                            error!("Failed to cancel open orders of disabled {}: {}", control.scope, e);
+                        }
+                    }
+                    Ok(pulled) = quotes_pulled.recv() => {
+                        warn!("Quoting paused: {}", pulled);
+                        if let Err(e) = executor.cancel_orders(pulled.orders.clone()).await {
+                            error!("Failed to pull quotes in {}: {}", pulled.instrument, e);
+                        }
+                    }
+                    Ok(manual) = manual_orders.recv() => {
+                        if manual.action != ManualOrderAction::Cancel {
+                            continue;
+                        }
+                        warn!("Cancelling order by hand: {}", manual);
+                        if let Err(e) = executor.cancel_order(manual.order).await {
+                            error!("Failed to cancel order {} by hand: {}", manual.order, e);
+                        }
+                    }
+                    _ = executor_shutdown.cancelled() => break,
+                }
+            }
+        });
+    }
+
+    /// Carry out the operator actions, injected by a backtest to rehearse a runbook. Halts and resumes go out as
+    /// trading controls, a flatten closes the position with a market order of the manual strategy. Parameter changes
+    /// are applied by the allocation itself.
+    fn watch_operator_actions(&self) {
+        let mut operator_actions = self.pubsub.subscribe::<OperatorAction>();
+        let pubsub = self.pubsub.clone();
+        let portfolio = self.portfolio.clone();
+        let executor_shutdown = self.executor_shutdown.clone();
+        self.executor_tracker.spawn(async move {
+            loop {
+                tokio::select! {
+                    Ok(action) = operator_actions.recv() => {
+                        info!("Operator action: {}", action);
+                        let (scope, enabled) = match &action.action {
+                            OperatorActionType::Halt { scope } => (scope.clone(), false),
+                            OperatorActionType::Resume { scope } => (scope.clone(), true),
+                            OperatorActionType::Flatten { instrument } => {
+                                let positions = portfolio.get_positions().await;
+                                let position = positions
+                                    .values()
+                                    .find(|p| &p.instrument.symbol == instrument || &p.instrument.venue_symbol == instrument);
+                                let Some(position) = position.filter(|p| !p.quantity.is_zero()) else {
+                                    info!("No position in {} to flatten", instrument);
+                                    continue;
+                                };
+                                let side = match position.quantity.is_sign_positive() {
+                                    true => MarketSide::Sell,
+                                    false => MarketSide::Buy,
+                                };
+                                let order = ExecutionOrder::builder()
+                                    .portfolio(position.portfolio.clone())
+                                    .instrument(position.instrument.clone())
+                                    .strategy(Some(manual_strategy()))
+                                    .order_type(ExecutionOrderType::Taker)
+                                    .side(side)
+                                    .price(Price::ZERO)
+                                    .quantity(position.quantity.abs())
+                                    .created_at(action.event_time)
+                                    .updated_at(action.event_time)
+                                    .build();
+                                info!("Flattening {}: {}", instrument, order);
+                                pubsub.publish::<ExecutionOrder>(order.into());
+                                continue;
+                            }
+                            OperatorActionType::SetParameter { .. } => continue,
+                        };
+                        let control = TradingControl::builder()
+                            .event_time(action.event_time)
+                            .scope(scope)
+                            .enabled(enabled)
+                            .reason(action.reason.clone())
+                            .build();
+                        pubsub.publish::<TradingControl>(control.into());
+                    }
+                    _ = executor_shutdown.cancelled() => break,
+                }
+            }
+        });
+    }
+
+    /// Publish a [`ServiceStalled`] event for every handler running past the hard deadline of the watchdog and
+    /// optionally halt trading, a hung strategy should not keep orders open in the market.
+    fn watch_stalls(&self) {
+        let pubsub = self.pubsub.clone();
+        let watchdog = self.watchdog.clone();
+        let halt_trading = self.halt_trading.clone();
+        let halt_on_stall = self.halt_on_stall;
+        let executor_shutdown = self.executor_shutdown.clone();
+        let mut interval = tokio::time::interval(self.watchdog_interval);
+        self.executor_tracker.spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        for stalled in watchdog.check() {
+                            error!(
+                                "Service {} stalled handling {}, running for {:?}",
+                                stalled.service, stalled.event, stalled.elapsed
+                            );
+                            let event = ServiceStalled::builder()
+                                .event_time(OffsetDateTime::now_utc())
+                                .service(stalled.service.to_string())
+                                .event(stalled.event.to_string())
+                                .elapsed(stalled.elapsed)
+                                .build();
+                            pubsub.publish::<ServiceStalled>(event.into());
+                            if halt_on_stall {
+                                halt_trading.cancel();
+                            }
+                        }
+                    }
+                    _ = executor_shutdown.cancelled() => break,
+                }
+            }
+        });
+    }
+
     async fn load_state(&self) -> Result<(), TradingEngineError> {
         // Setup Insights
         let start = Instant::now();
@@ -79,8 +352,25 @@ impl ForecastEngine {
         let frequency = Duration::from_secs(10);
         let lookback_data = Duration::from_secs(2 * 86400);
         let lookback_insights = Duration::from_secs(86400);
-        self.insights.load(end_time, &self.instruments, lookback_data).await?;
-        let mut clock = Clock::new(end_time - lookback_insights, end_time, frequency);
+        // Continue from the persisted insights if they verify, otherwise replay the trades
+        let start_time = match self.insights.warm_start(end_time, &self.instruments).await? {
+            Some(last_time) => {
+                self.insights.load(end_time, &self.instruments, end_time - last_time).await?;
+                last_time
+            }
+            None => {
+                match self.warm_up_bars {
+                    Some(bar_frequency) => {
+                        self.insights
+                            .load_bars(end_time, &self.instruments, lookback_data, bar_frequency)
+                            .await?
+                    }
+                    None => self.insights.load(end_time, &self.instruments, lookback_data).await?,
+                }
+                end_time - lookback_insights
+            }
+        };
+        let mut clock = Clock::new(start_time, end_time, frequency);
         while let Some((_start, end)) = clock.next() {
             self.insights.process(end, &self.instruments, false).await?;
         }
@@ -102,7 +392,8 @@ impl ForecastEngine {
     }
 
     async fn pipeline(&self) -> Result<(), TradingEngineError> {
-        let mut time_helper = TickHelper::new(Duration::from_secs(6));
+        let mut time_helper = TickHelper::new(self.frequency);
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
 
         loop {
             tokio::select! {
@@ -110,11 +401,15 @@ impl ForecastEngine {
                     debug!("Interval tick: {}", event_time);
                     let interval_tick = IntervalTick::builder()
                         .event_time(event_time)
-                        .instruments(self.instruments.clone())
+                        .instruments(self.tick_instruments(event_time).await)
                         .frequency(frequency)
                         .build();
                    self.pubsub.publish::<IntervalTick>(interval_tick.into());
                 }
+                Ok(finished) = simulation_finished.recv() => {
+                    info!("Simulation finished at {}, shutting down...", finished.event_time);
+                    break;
+                }
                 _ = tokio::signal::ctrl_c() => {
                     info!("Received Ctrl-C, shutting down...");
                     break;
@@ -124,83 +419,390 @@ impl ForecastEngine {
 
         Ok(())
     }
+
+    /// Publish an interval tick for every frequency boundary the replayed ticks cross, so a simulation
+    /// produces the same ticks no matter how fast it is replayed.
+    async fn simulation_pipeline(
+        &self,
+        mut ticks: Receiver<Arc<Tick>>,
+        mut data_gaps: Receiver<Arc<DataGap>>,
+        mut simulation_finished: Receiver<Arc<SimulationFinished>>,
+    ) -> Result<(), TradingEngineError> {
+        let mut next_tick: Option<OffsetDateTime> = self.resume_at;
+
+        loop {
+            tokio::select! {
+                biased;
+                // A gap the simulation skips over produces no interval ticks within it
+                Ok(gap) = data_gaps.recv() => {
+                    if let Some(next) = next_tick.as_mut().filter(|_| gap.skipped) {
+                        *next = (*next).max(self.next_boundary(gap.end));
+                        info!("Simulation skips the gap on {}, next interval tick at {}", gap.channel, next);
+                    }
+                }
+                Ok(tick) = ticks.recv() => {
+                    let next = next_tick.get_or_insert_with(|| self.next_boundary(tick.event_time));
+                    while tick.event_time >= *next {
+                        debug!("Simulation interval tick: {}", next);
+                        let interval_tick = IntervalTick::builder()
+                            .event_time(*next)
+                            .instruments(self.tick_instruments(*next).await)
+                            .frequency(self.frequency)
+                            .build();
+                        self.pubsub.publish::<IntervalTick>(interval_tick.into());
+                        *next += self.frequency;
+                    }
+                }
+                Ok(finished) = simulation_finished.recv() => {
+                    info!("Simulation finished at {}, shutting down...", finished.event_time);
+                    break;
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    info!("Received Ctrl-C, shutting down...");
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// First frequency boundary at or after the given time.
+    fn next_boundary(&self, time: OffsetDateTime) -> OffsetDateTime {
+        let frequency = self.frequency.as_nanos() as i128;
+        let nanos = time.unix_timestamp_nanos();
+        let boundary = (nanos + frequency - 1).div_euclid(frequency) * frequency;
+        OffsetDateTime::from_unix_timestamp_nanos(boundary).expect("Invalid interval tick time")
+    }
+
+    async fn start_ingestors(&self) {
+        for ingestor in &self.ingestors {
+            let policy = self.error_policies.ingestors;
+            let shutdown = self.ingestor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            let ingestor = ingestor.clone();
+            self.ingestor_task_tracker.spawn(async move {
+                supervise("ingestor", policy, shutdown, halt_trading, |shutdown| ingestor.start(shutdown)).await
+            });
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
 }
 
 #[async_trait]
 impl TradingEngine for ForecastEngine {
     async fn start(&self) -> Result<(), TradingEngineError> {
+        self.watch_halt();
+        self.watch_stalls();
+        self.watch_controls();
+        self.watch_operator_actions();
+
+        // Start the audit
+        if let Some(audit) = self.audit.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("audit", policy, shutdown, halt_trading, |shutdown| audit.start(shutdown)).await
+            });
+        }
+
+        // Start the latency tracker
+        if let Some(tracker) = self.latency.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("latency tracker", policy, shutdown, halt_trading, |shutdown| {
+                    tracker.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the markout tracker
+        if let Some(tracker) = self.markouts.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("markout tracker", policy, shutdown, halt_trading, |shutdown| {
+                    tracker.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the metrics exporter
+        if let Some(exporter) = self.metrics.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("metrics exporter", policy, shutdown, halt_trading, |shutdown| {
+                    exporter.clone().serve(shutdown)
+                })
+                .await
+            });
+        }
+
         // Start the persistor
+        let policy = self.error_policies.persistor;
         let shutdown = self.persistor_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let persistor = self.persistor.clone();
         self.persistor_task_tracker.spawn(async move {
-            if let Err(e) = persistor.start(shutdown).await {
-                error!("Error in persistor: {}", e);
-            }
+            supervise("persistor", policy, shutdown, halt_trading, |shutdown| {
+                persistor.start(shutdown)
+            })
+            .await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
+        // Start the leader election
+        if let Some(election) = self.leader_election.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("leader election", policy, shutdown, halt_trading, |shutdown| {
+                    election.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the event bridge
+        if let Some(bridge) = self.event_bridge.clone() {
+            let policy = self.error_policies.persistor;
+            let shutdown = self.persistor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.persistor_task_tracker.spawn(async move {
+                supervise("event bridge", policy, shutdown, halt_trading, |shutdown| {
+                    bridge.clone().start(shutdown)
+                })
+                .await
+            });
+        }
+
         // Start the portfolio
+        let policy = self.error_policies.portfolio;
         let shutdown = self.portfolio_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let portfolio = self.portfolio.clone();
         self.portfolio_task_tracker.spawn(async move {
-            if let Err(e) = portfolio.start(shutdown).await {
-                error!("Error in portfolio: {}", e);
-            }
+            supervise("portfolio", policy, shutdown, halt_trading, |shutdown| {
+                portfolio.start(shutdown)
+            })
+            .await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
-        // Start the ingestors
-        for ingestor in &self.ingestors {
-            let shutdown = self.ingestor_shutdown.clone();
-            let ingestor = ingestor.clone();
-            self.ingestor_task_tracker.spawn(async move {
-                if let Err(e) = ingestor.start(shutdown).await {
-                    error!("Error in ingestor: {}", e);
-                }
+        // Start the daily performance tracker
+        if let Some(tracker) = self.daily_performance.clone() {
+            let policy = self.error_policies.portfolio;
+            let shutdown = self.portfolio_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.portfolio_task_tracker.spawn(async move {
+                supervise("daily performance", policy, shutdown, halt_trading, |shutdown| {
+                    tracker.start(shutdown)
+                })
+                .await
             });
         }
-        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Start the sub-account ledger
+        if let Some(ledger) = self.sub_accounts.clone() {
+            let policy = self.error_policies.portfolio;
+            let shutdown = self.portfolio_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.portfolio_task_tracker.spawn(async move {
+                supervise("sub-account ledger", policy, shutdown, halt_trading, |shutdown| {
+                    ledger.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the exposure recorder
+        if let Some(recorder) = self.exposure.clone() {
+            let policy = self.error_policies.portfolio;
+            let shutdown = self.portfolio_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.portfolio_task_tracker.spawn(async move {
+                supervise("exposure recorder", policy, shutdown, halt_trading, |shutdown| {
+                    recorder.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the ingestors, a replay only starts once all services are listening
+        if !self.simulation {
+            self.start_ingestors().await;
+        }
 
         // Start the insights
+        let policy = self.error_policies.insights;
         let shutdown = self.insights_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let insights = self.insights.clone();
         self.insights_task_tracker.spawn(async move {
-            if let Err(e) = insights.start(shutdown).await {
-                error!("Error in insights: {}", e);
-            }
+            supervise("insights", policy, shutdown, halt_trading, |shutdown| insights.start(shutdown)).await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
+        // Start the simulation checkpointer
+        if let Some(checkpointer) = self.checkpointer.clone() {
+            let policy = self.error_policies.insights;
+            let shutdown = self.insights_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.insights_task_tracker.spawn(async move {
+                supervise("simulation checkpointer", policy, shutdown, halt_trading, |shutdown| {
+                    checkpointer.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the covariance service
+        if let Some(covariance) = self.covariance.clone() {
+            let policy = self.error_policies.allocation;
+            let shutdown = self.allocation_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.allocation_task_tracker.spawn(async move {
+                supervise("covariance service", policy, shutdown, halt_trading, |shutdown| {
+                    covariance.start(shutdown)
+                })
+                .await
+            });
+        }
+
         // Start the allocation optimizer
+        let policy = self.error_policies.allocation;
         let shutdown = self.allocation_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let allocation_optim = self.allocation_optim.clone();
         self.allocation_task_tracker.spawn(async move {
-            if let Err(e) = allocation_optim.start(shutdown).await {
-                error!("Error in allocation optimizer: {}", e);
-            }
+            supervise("allocation optimizer", policy, shutdown, halt_trading, |shutdown| {
+                allocation_optim.start(shutdown)
+            })
+            .await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
+        // Start the delta hedger
+        if let Some(hedger) = self.hedger.clone() {
+            let policy = self.error_policies.allocation;
+            let shutdown = self.allocation_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.allocation_task_tracker.spawn(async move {
+                supervise("delta hedger", policy, shutdown, halt_trading, |shutdown| {
+                    hedger.start(shutdown)
+                })
+                .await
+            });
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        // Start the tail hedge overlay
+        if let Some(overlay) = self.overlay.clone() {
+            let policy = self.error_policies.allocation;
+            let shutdown = self.allocation_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.allocation_task_tracker.spawn(async move {
+                supervise("tail hedge overlay", policy, shutdown, halt_trading, |shutdown| {
+                    overlay.start(shutdown)
+                })
+                .await
+            });
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
         // Start the order manager
+        let policy = self.error_policies.order_manager;
         let shutdown = self.order_manager_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let order_manager = self.order_manager.clone();
         self.order_manager_task_tracker.spawn(async move {
-            if let Err(e) = order_manager.start(shutdown).await {
-                error!("Error in order manager: {}", e);
-            }
+            supervise("order manager", policy, shutdown, halt_trading, |shutdown| {
+                order_manager.start(shutdown)
+            })
+            .await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
         // Start the executor
+        let policy = self.error_policies.executor;
         let shutdown = self.executor_shutdown.clone();
+        let halt_trading = self.halt_trading.clone();
         let executor = self.executor.clone();
         self.executor_tracker.spawn(async move {
-            if let Err(e) = executor.start(shutdown).await {
-                error!("Error in executor: {}", e);
-            }
+            supervise("executor", policy, shutdown, halt_trading, |shutdown| executor.start(shutdown)).await
         });
         tokio::time::sleep(Duration::from_millis(100)).await;
 
+        // Start the consistency checker
+        if let Some(checker) = self.consistency.clone() {
+            let policy = self.error_policies.executor;
+            let shutdown = self.executor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.executor_tracker.spawn(async move {
+                supervise("consistency checker", policy, shutdown, halt_trading, |shutdown| {
+                    checker.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the instrument lifecycle
+        if let Some(lifecycle) = self.lifecycle.clone() {
+            let policy = self.error_policies.executor;
+            let shutdown = self.executor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.executor_tracker.spawn(async move {
+                supervise("instrument lifecycle", policy, shutdown, halt_trading, |shutdown| {
+                    lifecycle.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the transfer manager
+        if let Some(transfers) = self.transfers.clone() {
+            let policy = self.error_policies.executor;
+            let shutdown = self.executor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.executor_tracker.spawn(async move {
+                supervise("transfer manager", policy, shutdown, halt_trading, |shutdown| {
+                    transfers.start(shutdown)
+                })
+                .await
+            });
+        }
+
+        // Start the trading control
+        if let Some(control) = self.control.clone() {
+            let policy = self.error_policies.executor;
+            let shutdown = self.executor_shutdown.clone();
+            let halt_trading = self.halt_trading.clone();
+            self.executor_tracker.spawn(async move {
+                supervise("trading control", policy, shutdown, halt_trading, |shutdown| {
+                    control.clone().serve(shutdown)
+                })
+                .await
+            });
+        }
+
+        if self.simulation {
+            let ticks = self.pubsub.subscribe::<Tick>();
+            let data_gaps = self.pubsub.subscribe::<DataGap>();
+            let simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
+            self.start_ingestors().await;
+            self.simulation_pipeline(ticks, data_gaps, simulation_finished).await?;
+            return Ok(());
+        }
+
         // Load the state
         self.load_state().await?;
 
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum TradingEngineError {
     #[error(transparent)]
@@ -23,6 +25,31 @@ pub enum TradingEngineError {
     #[error(transparent)]
     OrderManagerError(#[from] arkin_execution::OrderManagerError),
 
+    #[error(transparent)]
+    ExecutorError(#[from] arkin_execution::ExecutorError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid universe: {0}")]
+    InvalidUniverse(String),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
+
+impl ClassifyError for TradingEngineError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            TradingEngineError::PersistenceError(e) => e.class(),
+            TradingEngineError::PortfolioError(e) => e.class(),
+            TradingEngineError::IngestorError(e) => e.class(),
+            TradingEngineError::InsightsError(e) => e.class(),
+            TradingEngineError::AllocationOptimError(e) => e.class(),
+            TradingEngineError::OrderManagerError(e) => e.class(),
+            TradingEngineError::ExecutorError(e) => e.class(),
+            TradingEngineError::Io(_) | TradingEngineError::InvalidUniverse(_) => ErrorClass::Fatal,
+            TradingEngineError::StrategyError(_) | TradingEngineError::UnexpectedError(_) => ErrorClass::Fatal,
+        }
+    }
+}
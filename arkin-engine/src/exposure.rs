@@ -0,0 +1,349 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{ExposureSettings, TradingEngineError};
+
+/// Column for the notional of instruments no strategy has a signal on.
+pub const UNATTRIBUTED: &str = "unattributed";
+
+/// Exposure matrices of a run. Instruments and strategies are listed once, every frame holds the signed notional of
+/// all instrument and strategy pairs row by row, so a frame is `values[instrument * strategies.len() + strategy]`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExposureMatrix {
+    pub instruments: Vec<String>,
+    pub strategies: Vec<String>,
+    /// Unix milliseconds of the frames
+    pub times: Vec<i64>,
+    pub values: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Default)]
+struct ExposureState {
+    positions: HashMap<Arc<Instrument>, Quantity>,
+    marks: HashMap<Arc<Instrument>, Price>,
+    /// Last weight of every strategy per instrument
+    weights: HashMap<Arc<Instrument>, HashMap<String, Weight>>,
+    instruments: Vec<String>,
+    strategies: Vec<String>,
+    /// Frames keep the pairs by index, pairs seen later are zero in the earlier frames
+    frames: Vec<(OffsetDateTime, Vec<(usize, usize, Decimal)>)>,
+}
+
+impl ExposureState {
+    fn index(names: &mut Vec<String>, name: &str) -> usize {
+        match names.iter().position(|n| n == name) {
+            Some(idx) => idx,
+            None => {
+                names.push(name.to_owned());
+                names.len() - 1
+            }
+        }
+    }
+}
+
+/// Records the notional per instrument and strategy at every insight tick and exports the matrices together with a
+/// static html heat map. The position of an instrument is split over the strategies by the size of their last signal
+/// weight on it, positions without any signal end up in the unattributed column.
+#[derive(Debug, TypedBuilder)]
+pub struct ExposureRecorder {
+    pubsub: Arc<PubSub>,
+    /// Directory the matrices and the heat map are written to
+    dir: PathBuf,
+    #[builder(default = Duration::from_secs(60))]
+    flush_interval: Duration,
+    #[builder(default)]
+    state: Mutex<ExposureState>,
+}
+
+impl ExposureRecorder {
+    pub fn from_config(config: &ExposureSettings, pubsub: Arc<PubSub>) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .dir(config.dir.clone())
+            .flush_interval(Duration::from_secs(config.flush_interval_secs))
+            .build()
+    }
+
+    pub fn update(&self, event: &Event) {
+        let mut state = self.state.lock();
+        match event {
+            Event::PositionUpdate(position) => {
+                state.positions.insert(position.instrument.clone(), position.quantity);
+            }
+            Event::Tick(tick) => {
+                state.marks.insert(tick.instrument.clone(), tick.mid_price());
+            }
+            Event::Signal(signal) => {
+                state
+                    .weights
+                    .entry(signal.instrument.clone())
+                    .or_default()
+                    .insert(signal.strategy.name.clone(), signal.weight);
+            }
+            Event::InsightTick(tick) => Self::snapshot(&mut state, tick.event_time),
+            _ => {}
+        }
+    }
+
+    fn snapshot(state: &mut ExposureState, event_time: OffsetDateTime) {
+        let mut positions = state
+            .positions
+            .iter()
+            .filter(|(_, quantity)| !quantity.is_zero())
+            .map(|(instrument, quantity)| (instrument.clone(), *quantity))
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.0.symbol.cmp(&b.0.symbol));
+
+        let mut cells = Vec::new();
+        for (instrument, quantity) in positions {
+            let Some(mark) = state.marks.get(&instrument).copied() else {
+                continue;
+            };
+            let notional = quantity * mark * instrument.contract_size;
+            let mut weights = state
+                .weights
+                .get(&instrument)
+                .map(|w| w.iter().map(|(s, w)| (s.clone(), w.abs())).collect::<Vec<_>>())
+                .unwrap_or_default();
+            weights.retain(|(_, w)| !w.is_zero());
+            weights.sort_by(|a, b| a.0.cmp(&b.0));
+            let total = weights.iter().map(|(_, w)| *w).sum::<Decimal>();
+
+            let row = ExposureState::index(&mut state.instruments, &instrument.symbol);
+            if total.is_zero() {
+                let col = ExposureState::index(&mut state.strategies, UNATTRIBUTED);
+                cells.push((row, col, notional));
+                continue;
+            }
+            for (strategy, weight) in weights {
+                let col = ExposureState::index(&mut state.strategies, &strategy);
+                cells.push((row, col, notional * weight / total));
+            }
+        }
+        state.frames.push((event_time, cells));
+    }
+
+    pub fn matrix(&self) -> ExposureMatrix {
+        let state = self.state.lock();
+        let width = state.strategies.len();
+        let size = state.instruments.len() * width;
+        let mut matrix = ExposureMatrix {
+            instruments: state.instruments.clone(),
+            strategies: state.strategies.clone(),
+            times: Vec::with_capacity(state.frames.len()),
+            values: Vec::with_capacity(state.frames.len()),
+        };
+        for (event_time, cells) in &state.frames {
+            let mut values = vec![0.; size];
+            for (row, col, notional) in cells {
+                values[row * width + col] = notional.round_dp(2).to_f64().unwrap_or_default();
+            }
+            matrix.times.push((event_time.unix_timestamp_nanos() / 1_000_000) as i64);
+            matrix.values.push(values);
+        }
+        matrix
+    }
+
+    /// Write `exposure.json` and the `exposure.html` heat map with the matrices embedded.
+    pub async fn export(&self) -> Result<(), TradingEngineError> {
+        let matrix = self.matrix();
+        if matrix.times.is_empty() {
+            return Ok(());
+        }
+        let json = serde_json::to_string(&matrix).map_err(|e| TradingEngineError::UnexpectedError(e.to_string()))?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.dir.join("exposure.json"), &json).await?;
+        tokio::fs::write(self.dir.join("exposure.html"), HEAT_MAP.replace("__EXPOSURE__", &json)).await?;
+        debug!("Exported {} exposure frames to {}", matrix.times.len(), self.dir.display());
+        Ok(())
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting exposure recorder...");
+        let filter = EventFilter::default().event_types(vec![
+            EventType::PositionUpdate,
+            EventType::Tick,
+            EventType::Signal,
+            EventType::InsightTick,
+        ]);
+        let mut events = self.pubsub.subscribe_events(filter);
+        let mut interval = tokio::time::interval(self.flush_interval);
+        loop {
+            tokio::select! {
+                res = events.recv() => match res {
+                    Ok(event) => self.update(&event),
+                    Err(RecvError::Lagged(skipped)) => warn!("Exposure recorder lagged behind, skipped {} events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = interval.tick() => {
+                    skip_failed_event("exposure recorder", self.export().await)?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        self.export().await
+    }
+}
+
+const HEAT_MAP: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Exposure</title>
+<style>
+  body { font-family: sans-serif; font-size: 12px; margin: 16px; }
+  table { border-collapse: collapse; }
+  td, th { padding: 4px 8px; text-align: right; border: 1px solid #ddd; }
+  th { background: #f4f4f4; }
+  #timeline td { padding: 0; width: 3px; height: 14px; border: none; }
+</style>
+</head>
+<body>
+<h3>Exposure by instrument and strategy</h3>
+<div><input id="frame" type="range" min="0" value="0" style="width: 100%"></div>
+<p id="summary"></p>
+<table id="matrix"></table>
+<h3>Gross notional per instrument over time</h3>
+<table id="timeline"></table>
+<script>
+const data = __EXPOSURE__;
+const width = data.strategies.length;
+const peak = Math.max(1, ...data.values.flat().map(Math.abs));
+const color = (v) => {
+  const a = Math.min(1, Math.abs(v) / peak);
+  return v >= 0 ? `rgba(0, 150, 60, ${a})` : `rgba(200, 30, 30, ${a})`;
+};
+const fmt = (v) => v.toLocaleString(undefined, { maximumFractionDigits: 0 });
+
+function render(f) {
+  const values = data.values[f];
+  const gross = values.reduce((s, v) => s + Math.abs(v), 0);
+  const net = values.reduce((s, v) => s + v, 0);
+  let largest = 0;
+  data.instruments.forEach((_, i) => {
+    const row = values.slice(i * width, (i + 1) * width).reduce((s, v) => s + Math.abs(v), 0);
+    largest = Math.max(largest, row);
+  });
+  document.getElementById("summary").textContent =
+    `${new Date(data.times[f]).toISOString()}  gross ${fmt(gross)}  net ${fmt(net)}  ` +
+    `largest instrument ${gross > 0 ? ((100 * largest) / gross).toFixed(1) : 0}% of gross`;
+  let html = "<tr><th></th>" + data.strategies.map((s) => `<th>${s}</th>`).join("") + "</tr>";
+  data.instruments.forEach((inst, i) => {
+    html += `<tr><th>${inst}</th>`;
+    for (let s = 0; s < width; s++) {
+      const v = values[i * width + s];
+      html += `<td style="background: ${color(v)}">${fmt(v)}</td>`;
+    }
+    html += "</tr>";
+  });
+  document.getElementById("matrix").innerHTML = html;
+}
+
+function timeline() {
+  let html = "";
+  data.instruments.forEach((inst, i) => {
+    html += `<tr><th>${inst}</th>`;
+    data.values.forEach((values, f) => {
+      const v = values.slice(i * width, (i + 1) * width).reduce((s, v) => s + v, 0);
+      html += `<td title="${new Date(data.times[f]).toISOString()} ${fmt(v)}" style="background: ${color(v)}"></td>`;
+    });
+    html += "</tr>";
+  });
+  document.getElementById("timeline").innerHTML = html;
+}
+
+const slider = document.getElementById("frame");
+slider.max = data.times.length - 1;
+slider.value = slider.max;
+slider.oninput = () => render(Number(slider.value));
+render(data.times.length - 1);
+timeline();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rust_decimal_macros::dec;
+
+    fn position(instrument: Arc<Instrument>, quantity: Decimal) -> Event {
+        let position = PositionUpdate::builder()
+            .event_time(OffsetDateTime::UNIX_EPOCH)
+            .portfolio(test_portfolio())
+            .instrument(instrument)
+            .entry_price(dec!(100))
+            .quantity(quantity)
+            .realized_pnl(Decimal::ZERO)
+            .unrealized_pnl(Decimal::ZERO)
+            .position_side(PositionSide::Long)
+            .build();
+        Event::PositionUpdate(position.into())
+    }
+
+    fn signal(instrument: Arc<Instrument>, strategy: &str, weight: Decimal) -> Event {
+        let strategy = Strategy::builder().name(strategy.into()).description(None).build();
+        let signal = Signal::builder()
+            .event_time(OffsetDateTime::UNIX_EPOCH)
+            .instrument(instrument)
+            .strategy(strategy.into())
+            .weight(weight)
+            .build();
+        Event::Signal(signal.into())
+    }
+
+    fn insight_tick(seconds: i64) -> Event {
+        let tick = InsightTick::builder()
+            .event_time(OffsetDateTime::UNIX_EPOCH + time::Duration::seconds(seconds))
+            .instruments(vec![])
+            .insights(vec![])
+            .build();
+        Event::InsightTick(tick.into())
+    }
+
+    #[tokio::test]
+    async fn test_exposure_matrix() {
+        let dir = std::env::temp_dir().join(format!("arkin-exposure-{}", std::process::id()));
+        let recorder = ExposureRecorder::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .dir(dir.clone())
+            .build();
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+
+        recorder.update(&Event::Tick(test_tick(btc.clone(), dec!(99), dec!(1), dec!(101), dec!(1))));
+        recorder.update(&position(btc.clone(), dec!(2)));
+        recorder.update(&insight_tick(1));
+
+        recorder.update(&signal(btc.clone(), "momentum", dec!(0.3)));
+        recorder.update(&signal(btc.clone(), "carry", dec!(-0.1)));
+        recorder.update(&Event::Tick(test_tick(eth.clone(), dec!(10), dec!(1), dec!(10), dec!(1))));
+        recorder.update(&position(eth.clone(), dec!(-5)));
+        recorder.update(&insight_tick(2));
+
+        let matrix = recorder.matrix();
+        assert_eq!(matrix.instruments, vec![btc.symbol.clone(), eth.symbol.clone()]);
+        assert_eq!(matrix.strategies, vec![UNATTRIBUTED, "carry", "momentum"]);
+        assert_eq!(matrix.times, vec![1000, 2000]);
+        assert_eq!(matrix.values[0], vec![200., 0., 0., 0., 0., 0.]);
+        assert_eq!(matrix.values[1], vec![0., 50., 150., -50., 0., 0.]);
+
+        recorder.export().await.unwrap();
+        let html = std::fs::read_to_string(dir.join("exposure.html")).unwrap();
+        assert!(html.contains("\"strategies\":[\"unattributed\",\"carry\",\"momentum\"]"));
+        assert!(!html.contains("__EXPOSURE__"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{LatencySettings, TradingEngineError};
+
+/// Orders still waiting for a stage after this long are forgotten, like orders the order manager rejected.
+const STALE_ORDER: time::Duration = time::Duration::hours(1);
+
+/// Percentiles of one stage at one venue, in microseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyStats {
+    pub venue: String,
+    pub stage: LatencyStage,
+    pub count: usize,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+/// Distribution of the time order updates of one venue arrive after the venue reports them, in microseconds.
+/// Negative values mean the clock of the venue runs ahead of ours.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClockSkewStats {
+    pub venue: String,
+    pub count: usize,
+    pub min_us: i64,
+    pub p50_us: i64,
+    pub p90_us: i64,
+    pub p99_us: i64,
+    pub max_us: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyReport {
+    /// Latencies of a simulation are modeled, live they are measured
+    pub modeled: bool,
+    pub stats: Vec<LatencyStats>,
+    pub skew: Vec<ClockSkewStats>,
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = if self.modeled { "modeled" } else { "measured" };
+        write!(f, "Order path latency ({})", mode)?;
+        for s in &self.stats {
+            write!(
+                f,
+                "\n  {} {} count={} p50={}us p90={}us p99={}us max={}us",
+                s.venue, s.stage, s.count, s.p50_us, s.p90_us, s.p99_us, s.max_us
+            )?;
+        }
+        for s in &self.skew {
+            write!(
+                f,
+                "\n  {} clock_skew count={} min={}us p50={}us p90={}us p99={}us max={}us",
+                s.venue, s.count, s.min_us, s.p50_us, s.p90_us, s.p99_us, s.max_us
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Times an order reached the stages of the order path.
+#[derive(Debug, Clone)]
+struct OrderTimes {
+    venue: String,
+    decision: OffsetDateTime,
+    sent: Option<OffsetDateTime>,
+    acked: Option<OffsetDateTime>,
+    /// Time the venue reports it acknowledged the order
+    acked_at_venue: Option<OffsetDateTime>,
+    filled: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Default)]
+struct LatencyState {
+    orders: HashMap<ExecutionOrderId, OrderTimes>,
+    samples: HashMap<(String, LatencyStage), VecDeque<Duration>>,
+    /// Receive time minus venue time of the order updates in microseconds
+    skews: HashMap<String, VecDeque<i64>>,
+}
+
+/// Value at the given quantile of sorted values, nearest rank.
+fn percentile<T: Copy>(sorted: &[T], q: f64) -> T {
+    let idx = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx]
+}
+
+/// Follows every order from the decision of the strategy through the order manager to the acknowledgement of the
+/// venue and the first fill, publishes the time spent in each stage as [`OrderLatency`] and writes the percentiles
+/// per venue and stage to a report. A simulation takes the times from the events, so the latencies are the ones the
+/// executor models, live the stages are timed on the clock of the instance when the events arrive. The time from
+/// the acknowledgement to the first fill is taken from the times the venue reports when it reports both, so it is
+/// free of the noise of our clock, and the skew between the reported and the receive times is reported per venue.
+#[derive(Debug, TypedBuilder)]
+pub struct LatencyTracker {
+    pubsub: Arc<PubSub>,
+    #[builder(default)]
+    simulation: bool,
+    /// File the report is written to
+    path: PathBuf,
+    #[builder(default = Duration::from_secs(60))]
+    flush_interval: Duration,
+    /// Latencies kept per venue and stage, the oldest are dropped first
+    #[builder(default = 10_000)]
+    max_samples: usize,
+    #[builder(default)]
+    state: Mutex<LatencyState>,
+}
+
+impl LatencyTracker {
+    pub fn from_config(config: &LatencySettings, pubsub: Arc<PubSub>, simulation: bool) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .simulation(simulation)
+            .path(config.path.clone())
+            .flush_interval(Duration::from_secs(config.flush_interval_secs))
+            .max_samples(config.max_samples)
+            .build()
+    }
+
+    fn stamp(&self, event_time: OffsetDateTime) -> OffsetDateTime {
+        match self.simulation {
+            true => event_time,
+            false => OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Record the stage the event completes and return the latencies it finished.
+    pub fn update(&self, event: &Event) -> Vec<OrderLatency> {
+        let mut state = self.state.lock();
+        let mut stages = Vec::new();
+        match event {
+            Event::ExecutionOrderNew(order) => {
+                let decision = self.stamp(order.created_at);
+                let times = OrderTimes {
+                    venue: order.instrument.venue.name.clone(),
+                    decision,
+                    sent: None,
+                    acked: None,
+                    acked_at_venue: None,
+                    filled: None,
+                };
+                state.orders.insert(order.id, times);
+                state.orders.retain(|_, t| decision - t.decision < STALE_ORDER);
+            }
+            Event::VenueOrder(order) => {
+                if let Some(times) = state.orders.get_mut(&order.id).filter(|t| t.sent.is_none()) {
+                    let sent = self.stamp(order.created_at);
+                    times.sent = Some(sent);
+                    stages.push((order.id, times.venue.clone(), LatencyStage::OrderManager, times.decision, sent));
+                }
+            }
+            Event::VenueOrderUpdate(update) => {
+                let Ok(id) = Uuid::parse_str(&update.order_id) else {
+                    return Vec::new();
+                };
+                let LatencyState { orders, skews, .. } = &mut *state;
+                let Some(times) = orders.get_mut(&id) else {
+                    return Vec::new();
+                };
+                if let Some(skew) = update.clock_skew() {
+                    let skews = skews.entry(times.venue.clone()).or_default();
+                    if skews.len() >= self.max_samples {
+                        skews.pop_front();
+                    }
+                    skews.push_back(skew.whole_microseconds() as i64);
+                }
+                let now = self.stamp(update.event_time);
+                if times.acked.is_none() && update.status != VenueOrderStatus::New {
+                    times.acked = Some(now);
+                    times.acked_at_venue = update.exchange_time;
+                    let sent = times.sent.unwrap_or(times.decision);
+                    stages.push((id, times.venue.clone(), LatencyStage::VenueAck, sent, now));
+                }
+                if times.filled.is_none() && update.fill_quantity > Quantity::ZERO {
+                    times.filled = Some(now);
+                    let (acked, filled) = match (times.acked_at_venue, update.exchange_time) {
+                        (Some(acked), Some(filled)) => (acked, filled),
+                        _ => (times.acked.unwrap_or(now), now),
+                    };
+                    stages.push((id, times.venue.clone(), LatencyStage::FirstFill, acked, filled));
+                    stages.push((id, times.venue.clone(), LatencyStage::DecisionToFill, times.decision, now));
+                }
+                if update.status.is_finalized() {
+                    state.orders.remove(&id);
+                }
+            }
+            _ => {}
+        }
+
+        stages
+            .into_iter()
+            .map(|(order_id, venue, stage, from, till)| {
+                // Clocks of the venue and the instance can disagree, a stage never takes less than nothing
+                let latency = Duration::try_from(till - from).unwrap_or_default();
+                let samples = state.samples.entry((venue.clone(), stage)).or_default();
+                if samples.len() >= self.max_samples {
+                    samples.pop_front();
+                }
+                samples.push_back(latency);
+                OrderLatency::builder()
+                    .event_time(till)
+                    .order_id(order_id)
+                    .venue(venue)
+                    .stage(stage)
+                    .latency(latency)
+                    .modeled(self.simulation)
+                    .build()
+            })
+            .collect()
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        let state = self.state.lock();
+        let mut stats = state
+            .samples
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|((venue, stage), samples)| {
+                let mut sorted = samples.iter().map(|d| d.as_micros() as u64).collect::<Vec<_>>();
+                sorted.sort();
+                LatencyStats {
+                    venue: venue.clone(),
+                    stage: *stage,
+                    count: sorted.len(),
+                    p50_us: percentile(&sorted, 0.5),
+                    p90_us: percentile(&sorted, 0.9),
+                    p99_us: percentile(&sorted, 0.99),
+                    max_us: percentile(&sorted, 1.),
+                }
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| (&a.venue, a.stage).cmp(&(&b.venue, b.stage)));
+        let mut skew = state
+            .skews
+            .iter()
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(venue, samples)| {
+                let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+                sorted.sort();
+                ClockSkewStats {
+                    venue: venue.clone(),
+                    count: sorted.len(),
+                    min_us: sorted[0],
+                    p50_us: percentile(&sorted, 0.5),
+                    p90_us: percentile(&sorted, 0.9),
+                    p99_us: percentile(&sorted, 0.99),
+                    max_us: percentile(&sorted, 1.),
+                }
+            })
+            .collect::<Vec<_>>();
+        skew.sort_by(|a, b| a.venue.cmp(&b.venue));
+        LatencyReport {
+            modeled: self.simulation,
+            stats,
+            skew,
+        }
+    }
+
+    pub async fn export(&self) -> Result<(), TradingEngineError> {
+        let report = self.report();
+        if report.stats.is_empty() {
+            return Ok(());
+        }
+        let json =
+            serde_json::to_string_pretty(&report).map_err(|e| TradingEngineError::UnexpectedError(e.to_string()))?;
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, json).await?;
+        debug!("Exported order path latency to {}", self.path.display());
+        Ok(())
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting latency tracker...");
+        let filter = EventFilter::default().event_types(vec![
+            EventType::ExecutionOrderNew,
+            EventType::VenueOrder,
+            EventType::VenueOrderUpdate,
+        ]);
+        let mut events = self.pubsub.subscribe_events(filter);
+        let mut interval = tokio::time::interval(self.flush_interval);
+        loop {
+            tokio::select! {
+                res = events.recv() => match res {
+                    Ok(event) => {
+                        for latency in self.update(&event) {
+                            self.pubsub.publish::<OrderLatency>(latency.into());
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Latency tracker lagged behind, skipped {} events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = interval.tick() => {
+                    skip_failed_event("latency tracker", self.export().await)?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        info!("{}", self.report());
+        self.export().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_order_path_latency() {
+        let tracker = LatencyTracker::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .simulation(true)
+            .path("latency.json".into())
+            .build();
+        let decision = datetime!(2025-01-01 12:00:00 UTC);
+        let order = ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_type(ExecutionOrderType::Taker)
+            .side(MarketSide::Buy)
+            .price(dec!(60000))
+            .quantity(dec!(0.1))
+            .created_at(decision)
+            .updated_at(decision)
+            .build();
+        assert!(tracker.update(&Event::ExecutionOrderNew(Arc::new(order.clone()))).is_empty());
+
+        let venue_order = VenueOrder::builder()
+            .id(order.id)
+            .portfolio(test_portfolio())
+            .instrument(order.instrument.clone())
+            .side(order.side)
+            .order_type(VenueOrderType::Market)
+            .price(order.price)
+            .quantity(order.quantity)
+            .created_at(decision + time::Duration::milliseconds(2))
+            .updated_at(decision + time::Duration::milliseconds(2))
+            .build();
+        let sent = tracker.update(&Event::VenueOrder(Arc::new(venue_order.clone())));
+        assert_eq!(sent[0].stage, LatencyStage::OrderManager);
+        assert_eq!(sent[0].latency, Duration::from_millis(2));
+
+        // Updates arrive a millisecond after the venue reports them
+        let update = |status: VenueOrderStatus, filled: Quantity, ms: i64| {
+            let exchange_time = decision + time::Duration::milliseconds(ms);
+            let update = VenueOrderUpdate::builder()
+                .event_time(exchange_time)
+                .portfolio(test_portfolio())
+                .instrument(order.instrument.clone())
+                .order_id(order.id.to_string())
+                .venue_order_id(1)
+                .side(order.side)
+                .order_type(VenueOrderType::Market)
+                .time_in_force(venue_order.time_in_force)
+                .price(order.price)
+                .quantity(order.quantity)
+                .fill_price(dec!(60000))
+                .fill_quantity(filled)
+                .last_fill_price(dec!(60000))
+                .last_fill_quantity(filled)
+                .status(status)
+                .liquidity(LiquidityRole::Taker)
+                .commission_asset(None)
+                .commission(Quantity::ZERO)
+                .exchange_time(Some(exchange_time))
+                .received_at(exchange_time + time::Duration::milliseconds(1))
+                .build();
+            Event::VenueOrderUpdate(Arc::new(update))
+        };
+        let acked = tracker.update(&update(VenueOrderStatus::Placed, Quantity::ZERO, 12));
+        assert_eq!(acked.len(), 1);
+        assert_eq!(acked[0].latency, Duration::from_millis(10));
+
+        let filled = tracker.update(&update(VenueOrderStatus::Filled, dec!(0.1), 15));
+        let stages = filled.iter().map(|l| (l.stage, l.latency)).collect::<Vec<_>>();
+        assert_eq!(
+            stages,
+            vec![
+                (LatencyStage::FirstFill, Duration::from_millis(3)),
+                (LatencyStage::DecisionToFill, Duration::from_millis(15))
+            ]
+        );
+        assert!(tracker.state.lock().orders.is_empty());
+
+        let report = tracker.report();
+        assert!(report.modeled);
+        assert_eq!(report.stats.len(), 4);
+        assert_eq!(report.stats[1].stage, LatencyStage::VenueAck);
+        assert_eq!(report.stats[1].p99_us, 10_000);
+        assert_eq!(report.skew.len(), 1);
+        assert_eq!(report.skew[0].count, 2);
+        assert_eq!(report.skew[0].p50_us, 1_000);
+    }
+}
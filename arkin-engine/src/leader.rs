@@ -0,0 +1,134 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{LeaderElectionSettings, TradingEngineError};
+
+/// Holds a lease in the database to decide which of the instances sharing it sends orders. The leader renews the lease
+/// well within its expiry, a standby tries to take it on every renewal and takes over once the leader stopped renewing.
+/// A leader that can't reach the database steps down before its lease expires, so two instances never lead at once.
+#[derive(Debug, TypedBuilder)]
+pub struct LeaderElection {
+    persistence: Arc<PersistenceService>,
+    leadership: Arc<Leadership>,
+    lease: String,
+    holder: String,
+    #[builder(default = Duration::from_secs(10))]
+    ttl: Duration,
+    #[builder(default = Duration::from_secs(2))]
+    renew_interval: Duration,
+    /// Last time the lease was taken or extended
+    #[builder(default)]
+    renewed_at: Mutex<Option<Instant>>,
+}
+
+impl LeaderElection {
+    pub fn from_config(
+        config: &LeaderElectionSettings,
+        persistence: Arc<PersistenceService>,
+        leadership: Arc<Leadership>,
+    ) -> Self {
+        let holder = config
+            .holder
+            .clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .unwrap_or_else(|| format!("instance-{}", std::process::id()));
+        Self::builder()
+            .persistence(persistence)
+            .leadership(leadership)
+            .lease(config.lease.clone())
+            .holder(holder)
+            .ttl(Duration::from_secs(config.ttl_secs))
+            .renew_interval(Duration::from_secs(config.renew_interval_secs))
+            .build()
+    }
+
+    /// Update the role with the outcome of a renewal sent at `sent_at`, `None` when the database could not be reached.
+    /// A taken lease counts from the moment the renewal was sent, the database may have extended it right away.
+    pub fn apply(&self, acquired: Option<bool>, sent_at: Instant) {
+        let mut renewed_at = self.renewed_at.lock();
+        let leader = match acquired {
+            Some(true) => {
+                *renewed_at = Some(sent_at);
+                true
+            }
+            Some(false) => false,
+            None => holds_lease(*renewed_at, sent_at, self.ttl, self.renew_interval),
+        };
+        if self.leadership.set_leader(leader) {
+            match leader {
+                true => info!("{} is now the leader of {}", self.holder, self.lease),
+                false => warn!("{} is now standby for {}", self.holder, self.lease),
+            }
+        }
+    }
+
+    /// Renew the lease, giving the database until the next renewal to answer.
+    async fn renew(&self) {
+        let sent_at = Instant::now();
+        let acquire = self.persistence.lease_store.acquire(&self.lease, &self.holder, self.ttl);
+        let acquired = match tokio::time::timeout(self.renew_interval, acquire).await {
+            Ok(Ok(acquired)) => Some(acquired),
+            Ok(Err(e)) => {
+                error!("Failed to renew lease {}: {}", self.lease, e);
+                None
+            }
+            // Whether the lease was extended is unknown, so step down before the standby may take it
+            Err(_) => {
+                warn!(
+                    "Renewal of lease {} timed out after {:?}, stepping down",
+                    self.lease, self.renew_interval
+                );
+                Some(false)
+            }
+        };
+        self.apply(acquired, sent_at);
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting leader election for {} as {}...", self.lease, self.holder);
+        let mut interval = tokio::time::interval(self.renew_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.renew().await,
+                _ = shutdown.cancelled() => break,
+            }
+        }
+
+        // Hand over right away instead of letting the standby wait for the expiry
+        self.leadership.set_leader(false);
+        self.persistence.lease_store.release(&self.lease, &self.holder).await?;
+        info!("Released lease {}", self.lease);
+        Ok(())
+    }
+}
+
+/// Whether a lease renewed at `renewed_at` is surely still ours, with a renewal to spare for the time the renewal takes.
+fn holds_lease(renewed_at: Option<Instant>, now: Instant, ttl: Duration, renew_interval: Duration) -> bool {
+    renewed_at.is_some_and(|t| now.duration_since(t) + renew_interval < ttl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holds_lease() {
+        let ttl = Duration::from_secs(10);
+        let interval = Duration::from_secs(2);
+        let renewed = Instant::now();
+        assert!(holds_lease(Some(renewed), renewed + Duration::from_secs(5), ttl, interval));
+        // Step down a renewal before the lease expires, the standby may take it right after
+        assert!(!holds_lease(Some(renewed), renewed + Duration::from_secs(8), ttl, interval));
+        assert!(!holds_lease(None, renewed, ttl, interval));
+    }
+}
@@ -1,13 +1,64 @@
+mod audit;
+mod bridge;
+mod checkpoint;
+mod config;
+mod consistency;
+mod control;
+mod control_auth;
+mod covariance;
 mod engines;
 mod errors;
+mod exposure;
+mod latency;
+mod leader;
+mod lifecycle;
+mod markouts;
+mod metrics;
+mod slice;
+mod supervisor;
 mod traits;
+mod universe;
 
+pub use audit::*;
+pub use bridge::*;
+pub use checkpoint::*;
+pub use config::*;
+pub use consistency::*;
+pub use control::*;
+pub use control_auth::*;
+pub use covariance::*;
 pub use engines::*;
 pub use errors::*;
+pub use exposure::*;
+pub use latency::*;
+pub use leader::*;
+pub use lifecycle::*;
+pub use markouts::*;
+pub use metrics::*;
+pub use slice::*;
+pub use supervisor::*;
 pub use traits::*;
+pub use universe::*;
 
 pub mod prelude {
+    pub use crate::audit::*;
+    pub use crate::bridge::*;
+    pub use crate::checkpoint::*;
+    pub use crate::config::*;
+    pub use crate::consistency::*;
+    pub use crate::control::*;
+    pub use crate::control_auth::*;
+    pub use crate::covariance::*;
     pub use crate::engines::*;
     pub use crate::errors::*;
+    pub use crate::exposure::*;
+    pub use crate::latency::*;
+    pub use crate::leader::*;
+    pub use crate::lifecycle::*;
+    pub use crate::markouts::*;
+    pub use crate::metrics::*;
+    pub use crate::slice::*;
+    pub use crate::supervisor::*;
     pub use crate::traits::*;
+    pub use crate::universe::*;
 }
@@ -0,0 +1,245 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_portfolio::prelude::*;
+
+use crate::{LifecycleSettings, TradingEngineError};
+
+/// Last known status of every symbol listed on a venue.
+#[derive(Debug, Default)]
+pub struct InstrumentStatuses {
+    /// Status by upper case venue symbol, empty until the first sync
+    statuses: Option<HashMap<String, InstrumentStatus>>,
+}
+
+impl InstrumentStatuses {
+    /// Compare the symbols listed by the venue with the known statuses. The first sync only reports the instruments
+    /// whose status differs from what they were loaded with, the rest of the venue is taken as it is.
+    pub fn sync(
+        &mut self,
+        now: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+        listed: Vec<VenueInstrumentStatus>,
+    ) -> Vec<InstrumentStatusUpdate> {
+        let first_sync = self.statuses.is_none();
+        let known = self.statuses.get_or_insert_with(|| {
+            instruments
+                .iter()
+                .map(|i| (i.venue_symbol.to_uppercase(), i.status.clone()))
+                .collect()
+        });
+        let update = |venue_symbol: String, previous: Option<InstrumentStatus>, status: InstrumentStatus| {
+            let instrument = instruments.iter().find(|i| i.venue_symbol.eq_ignore_ascii_case(&venue_symbol));
+            InstrumentStatusUpdate::builder()
+                .event_time(now)
+                .venue_symbol(venue_symbol)
+                .instrument(instrument.cloned())
+                .previous(previous)
+                .status(status)
+                .build()
+        };
+
+        let mut updates = Vec::new();
+        let mut seen = HashSet::new();
+        for listing in listed {
+            let symbol = listing.venue_symbol.to_uppercase();
+            seen.insert(symbol.clone());
+            match known.insert(symbol, listing.status.clone()) {
+                Some(previous) if previous == listing.status => {}
+                None if first_sync => {}
+                previous => updates.push(update(listing.venue_symbol, previous, listing.status)),
+            }
+        }
+        for (symbol, status) in known.iter_mut() {
+            if seen.contains(symbol) || *status == InstrumentStatus::Delisted {
+                continue;
+            }
+            let previous = std::mem::replace(status, InstrumentStatus::Delisted);
+            updates.push(update(symbol.clone(), Some(previous), InstrumentStatus::Delisted));
+        }
+        updates
+    }
+}
+
+/// Syncs the instrument statuses from the exchange info of a venue and publishes the listings, delistings and status
+/// changes it finds. Trading is disabled for instruments that stop trading, so strategies can't order in them, and the
+/// positions in instruments entering settlement or getting delisted are closed before the venue does it.
+#[derive(Debug, TypedBuilder)]
+pub struct InstrumentLifecycle {
+    pubsub: Arc<PubSub>,
+    adapter: Arc<dyn VenueAdapter>,
+    portfolio: Arc<dyn Accounting>,
+    instruments: Vec<Arc<Instrument>>,
+    #[builder(default = Duration::from_secs(300))]
+    interval: Duration,
+    #[builder(default = true)]
+    flatten: bool,
+    #[builder(default)]
+    statuses: Mutex<InstrumentStatuses>,
+}
+
+impl InstrumentLifecycle {
+    pub fn from_config(
+        config: &LifecycleSettings,
+        pubsub: Arc<PubSub>,
+        adapter: Arc<dyn VenueAdapter>,
+        portfolio: Arc<dyn Accounting>,
+        instruments: Vec<Arc<Instrument>>,
+    ) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .adapter(adapter)
+            .portfolio(portfolio)
+            .instruments(instruments)
+            .interval(Duration::from_secs(config.interval_secs))
+            .flatten(config.flatten)
+            .build()
+    }
+
+    pub async fn sync(&self) {
+        let listed = match self.adapter.instrument_statuses().await {
+            Ok(listed) => listed,
+            Err(e) => {
+                warn!("Failed to sync instrument statuses from {}: {}", self.adapter.venue(), e);
+                return;
+            }
+        };
+        let now = OffsetDateTime::now_utc();
+        let updates = self.statuses.lock().sync(now, &self.instruments, listed);
+        for update in updates {
+            info!("Instrument status update on {}: {}", self.adapter.venue(), update);
+            let update = Arc::new(update);
+            self.pubsub.publish::<InstrumentStatusUpdate>(update.clone());
+            if let Some(instrument) = &update.instrument {
+                self.apply(now, instrument, &update).await;
+            }
+        }
+    }
+
+    async fn apply(&self, now: OffsetDateTime, instrument: &Arc<Instrument>, update: &InstrumentStatusUpdate) {
+        let was_trading = update.previous.as_ref().is_some_and(|s| s.is_trading());
+        if update.status.is_trading() != was_trading {
+            let control = TradingControl::builder()
+                .event_time(now)
+                .scope(TradingScope::Instrument(instrument.symbol.clone()))
+                .enabled(update.status.is_trading())
+                .reason(format!("instrument {}", update.status))
+                .build();
+            self.pubsub.publish::<TradingControl>(control.into());
+        }
+
+        let closing =
+            |status: &InstrumentStatus| matches!(status, InstrumentStatus::Settling | InstrumentStatus::Delisted);
+        if self.flatten && closing(&update.status) && !update.previous.as_ref().is_some_and(closing) {
+            self.flatten(now, instrument).await;
+        }
+    }
+
+    /// Close the position in the instrument with a market order. The order goes to the executor directly, the order
+    /// manager rejects orders in instruments trading is disabled for.
+    async fn flatten(&self, now: OffsetDateTime, instrument: &Arc<Instrument>) {
+        let positions = self.portfolio.get_positions().await;
+        let Some(position) = positions.values().find(|p| p.instrument.id == instrument.id) else {
+            return;
+        };
+        if position.quantity.is_zero() {
+            return;
+        }
+        let side = match position.position_side {
+            PositionSide::Long => MarketSide::Sell,
+            PositionSide::Short => MarketSide::Buy,
+        };
+        let order = VenueOrder::builder()
+            .portfolio(position.portfolio.clone())
+            .instrument(instrument.clone())
+            .side(side)
+            .order_type(VenueOrderType::Market)
+            .price(position.entry_price)
+            .quantity(position.quantity.abs())
+            .created_at(now)
+            .updated_at(now)
+            .build();
+        warn!("Flattening position in {} with {} {}", instrument, side, order.quantity);
+        self.pubsub.publish::<VenueOrder>(order.into());
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting instrument lifecycle for {}...", self.adapter.venue());
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.sync().await,
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn listed(symbols: &[(&str, InstrumentStatus)]) -> Vec<VenueInstrumentStatus> {
+        symbols
+            .iter()
+            .map(|(symbol, status)| VenueInstrumentStatus {
+                venue_symbol: symbol.to_string(),
+                status: status.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sync() {
+        let now = datetime!(2025-01-01 00:00 UTC);
+        let instruments = vec![test_inst_binance_btc_usdt_perp()];
+        let mut statuses = InstrumentStatuses::default();
+
+        // The rest of the venue is taken as it is on the first sync
+        let updates = statuses.sync(
+            now,
+            &instruments,
+            listed(&[("BTCUSDT", InstrumentStatus::Trading), ("ETHUSDT", InstrumentStatus::Trading)]),
+        );
+        assert!(updates.is_empty());
+
+        let updates = statuses.sync(
+            now,
+            &instruments,
+            listed(&[
+                ("BTCUSDT", InstrumentStatus::Settling),
+                ("ETHUSDT", InstrumentStatus::Trading),
+                ("SOLUSDT", InstrumentStatus::Trading),
+            ]),
+        );
+        assert_eq!(updates.len(), 2);
+        let settling = updates.iter().find(|u| u.venue_symbol == "BTCUSDT").unwrap();
+        assert_eq!(settling.instrument, Some(test_inst_binance_btc_usdt_perp()));
+        assert_eq!(settling.previous, Some(InstrumentStatus::Trading));
+        assert_eq!(settling.status, InstrumentStatus::Settling);
+        let listing = updates.iter().find(|u| u.venue_symbol == "SOLUSDT").unwrap();
+        assert!(listing.is_listing());
+        assert!(listing.instrument.is_none());
+
+        // Symbols missing from the exchange info are delisted once
+        let updates = statuses.sync(now, &instruments, listed(&[("SOLUSDT", InstrumentStatus::Trading)]));
+        assert_eq!(updates.len(), 2);
+        assert!(updates.iter().all(|u| u.status == InstrumentStatus::Delisted));
+        assert!(statuses
+            .sync(now, &instruments, listed(&[("SOLUSDT", InstrumentStatus::Trading)]))
+            .is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+use std::{collections::HashMap, fmt, path::PathBuf, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+use arkin_execution::prelude::*;
+
+use crate::{MarkoutSettings, TradingEngineError};
+
+const BPS: f64 = 10_000.;
+
+/// Markouts of the fills of one strategy at one venue and horizon, weighted by the filled quantity.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarkoutStats {
+    pub strategy: String,
+    pub venue: String,
+    pub horizon_secs: f64,
+    pub fills: usize,
+    pub quantity: f64,
+    pub mean_bps: f64,
+    /// Share of the fills the price moved against
+    pub adverse_share: f64,
+}
+
+/// Adverse selection part of the transaction cost analysis of a run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarkoutReport {
+    pub stats: Vec<MarkoutStats>,
+}
+
+impl fmt::Display for MarkoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fill markouts")?;
+        for s in &self.stats {
+            write!(
+                f,
+                "\n  {} {} {}s fills={} quantity={} mean={:.2}bps adverse={:.1}%",
+                s.strategy,
+                s.venue,
+                s.horizon_secs,
+                s.fills,
+                s.quantity,
+                s.mean_bps,
+                s.adverse_share * 100.
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Fill waiting for the mid price at the end of one horizon.
+#[derive(Debug, Clone)]
+struct PendingMarkout {
+    due: OffsetDateTime,
+    horizon: Duration,
+    order_id: ExecutionOrderId,
+    instrument: Arc<Instrument>,
+    strategy: String,
+    side: MarketSide,
+    price: Price,
+    quantity: Quantity,
+}
+
+#[derive(Debug, Default)]
+struct MarkoutTotals {
+    fills: usize,
+    quantity: f64,
+    weighted_bps: f64,
+    adverse: usize,
+}
+
+#[derive(Debug, Default)]
+struct MarkoutState {
+    /// Strategy of the open orders
+    strategies: HashMap<ExecutionOrderId, String>,
+    mids: HashMap<Arc<Instrument>, Price>,
+    pending: Vec<PendingMarkout>,
+    totals: HashMap<(String, String, Duration), MarkoutTotals>,
+}
+
+/// Marks out every fill against the mid price at fixed horizons after it, publishes each markout as [`FillMarkout`]
+/// and writes the quantity weighted markouts per strategy, venue and horizon to a report. Passive strategies whose
+/// fills are followed by moves against them are exposed to toxic flow. The mid at a horizon is the last one at or
+/// before the fill time plus the horizon, it is known once the first tick after that arrives.
+#[derive(Debug, TypedBuilder)]
+pub struct MarkoutTracker {
+    pubsub: Arc<PubSub>,
+    #[builder(default = vec![Duration::from_secs(1), Duration::from_secs(10), Duration::from_secs(60)])]
+    horizons: Vec<Duration>,
+    /// File the report is written to
+    path: PathBuf,
+    #[builder(default = Duration::from_secs(60))]
+    flush_interval: Duration,
+    #[builder(default)]
+    state: Mutex<MarkoutState>,
+}
+
+impl MarkoutTracker {
+    pub fn from_config(config: &MarkoutSettings, pubsub: Arc<PubSub>) -> Self {
+        let mut horizons = config.horizons_secs.iter().map(|s| Duration::from_secs(*s)).collect::<Vec<_>>();
+        horizons.sort();
+        horizons.dedup();
+        Self::builder()
+            .pubsub(pubsub)
+            .horizons(horizons)
+            .path(config.path.clone())
+            .flush_interval(Duration::from_secs(config.flush_interval_secs))
+            .build()
+    }
+
+    /// Take the fills and ticks of the event and return the markouts it completed.
+    pub fn update(&self, event: &Event) -> Vec<FillMarkout> {
+        let mut state = self.state.lock();
+        match event {
+            Event::ExecutionOrderNew(order) => {
+                state.strategies.insert(order.id, OrderThrottle::strategy_of(order));
+                Vec::new()
+            }
+            Event::VenueOrderUpdate(update) => {
+                let Ok(id) = Uuid::parse_str(&update.order_id) else {
+                    return Vec::new();
+                };
+                let strategy = match update.status.is_finalized() {
+                    true => state.strategies.remove(&id),
+                    false => state.strategies.get(&id).cloned(),
+                };
+                if update.last_fill_quantity.is_zero() {
+                    return Vec::new();
+                }
+                let strategy = strategy.unwrap_or_else(|| OrderThrottle::DEFAULT_STRATEGY.to_string());
+                for horizon in &self.horizons {
+                    state.pending.push(PendingMarkout {
+                        due: update.event_time + *horizon,
+                        horizon: *horizon,
+                        order_id: id,
+                        instrument: update.instrument.clone(),
+                        strategy: strategy.clone(),
+                        side: update.side,
+                        price: update.last_fill_price,
+                        quantity: update.last_fill_quantity.abs(),
+                    });
+                }
+                Vec::new()
+            }
+            Event::Tick(tick) => {
+                // Horizons that ended before the tick take the mid before it, the ones ending on it take its mid
+                let mut markouts = match state.mids.get(&tick.instrument).copied() {
+                    Some(mid) => self.mark(&mut state, &tick.instrument, mid, |due| due < tick.event_time),
+                    None => Vec::new(),
+                };
+                let mid = tick.mid_price();
+                state.mids.insert(tick.instrument.clone(), mid);
+                markouts.extend(self.mark(&mut state, &tick.instrument, mid, |due| due <= tick.event_time));
+                markouts
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn mark(
+        &self,
+        state: &mut MarkoutState,
+        instrument: &Arc<Instrument>,
+        mid: Price,
+        due: impl Fn(OffsetDateTime) -> bool,
+    ) -> Vec<FillMarkout> {
+        let (ready, pending) = std::mem::take(&mut state.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| p.instrument == *instrument && due(p.due));
+        state.pending = pending;
+
+        let mut markouts = Vec::with_capacity(ready.len());
+        for p in ready {
+            if p.price.is_zero() {
+                continue;
+            }
+            let markout_bps = (Decimal::from(p.side) * (mid - p.price) / p.price).to_f64().unwrap_or_default() * BPS;
+            let quantity = p.quantity.to_f64().unwrap_or_default();
+            let venue = p.instrument.venue.name.clone();
+            let totals = state.totals.entry((p.strategy.clone(), venue.clone(), p.horizon)).or_default();
+            totals.fills += 1;
+            totals.quantity += quantity;
+            totals.weighted_bps += markout_bps * quantity;
+            if markout_bps < 0. {
+                totals.adverse += 1;
+            }
+            markouts.push(
+                FillMarkout::builder()
+                    .event_time(p.due)
+                    .order_id(p.order_id)
+                    .instrument(p.instrument)
+                    .strategy(p.strategy)
+                    .venue(venue)
+                    .side(p.side)
+                    .fill_price(p.price)
+                    .quantity(p.quantity)
+                    .horizon(p.horizon)
+                    .markout_bps(markout_bps)
+                    .build(),
+            );
+        }
+        markouts
+    }
+
+    pub fn report(&self) -> MarkoutReport {
+        let state = self.state.lock();
+        let mut stats = state
+            .totals
+            .iter()
+            .filter(|(_, t)| t.fills > 0)
+            .map(|((strategy, venue, horizon), t)| MarkoutStats {
+                strategy: strategy.clone(),
+                venue: venue.clone(),
+                horizon_secs: horizon.as_secs_f64(),
+                fills: t.fills,
+                quantity: t.quantity,
+                mean_bps: match t.quantity > 0. {
+                    true => t.weighted_bps / t.quantity,
+                    false => 0.,
+                },
+                adverse_share: t.adverse as f64 / t.fills as f64,
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| {
+            (&a.strategy, &a.venue)
+                .cmp(&(&b.strategy, &b.venue))
+                .then(a.horizon_secs.total_cmp(&b.horizon_secs))
+        });
+        MarkoutReport { stats }
+    }
+
+    pub async fn export(&self) -> Result<(), TradingEngineError> {
+        let report = self.report();
+        if report.stats.is_empty() {
+            return Ok(());
+        }
+        let json =
+            serde_json::to_string_pretty(&report).map_err(|e| TradingEngineError::UnexpectedError(e.to_string()))?;
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, json).await?;
+        debug!("Exported fill markouts to {}", self.path.display());
+        Ok(())
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        info!("Starting markout tracker...");
+        let filter = EventFilter::default().event_types(vec![
+            EventType::ExecutionOrderNew,
+            EventType::VenueOrderUpdate,
+            EventType::Tick,
+        ]);
+        let mut events = self.pubsub.subscribe_events(filter);
+        let mut interval = tokio::time::interval(self.flush_interval);
+        loop {
+            tokio::select! {
+                res = events.recv() => match res {
+                    Ok(event) => {
+                        for markout in self.update(&event) {
+                            self.pubsub.publish::<FillMarkout>(markout.into());
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Markout tracker lagged behind, skipped {} events", skipped),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = interval.tick() => {
+                    skip_failed_event("markout tracker", self.export().await)?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        info!("{}", self.report());
+        self.export().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_fill_markouts() {
+        let tracker = MarkoutTracker::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .horizons(vec![Duration::from_secs(1), Duration::from_secs(10)])
+            .path("markouts.json".into())
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let t0 = datetime!(2025-01-01 12:00:00 UTC);
+        let tick = |secs: u64, bid: Decimal, ask: Decimal| {
+            let mut tick = (*test_tick(instrument.clone(), bid, dec!(1), ask, dec!(1))).clone();
+            tick.event_time = t0 + Duration::from_secs(secs);
+            Event::Tick(Arc::new(tick))
+        };
+
+        let order = ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(instrument.clone())
+            .order_type(ExecutionOrderType::Maker)
+            .side(MarketSide::Buy)
+            .price(dec!(100))
+            .quantity(dec!(2))
+            .build();
+        assert!(tracker.update(&Event::ExecutionOrderNew(Arc::new(order.clone()))).is_empty());
+        assert!(tracker.update(&tick(0, dec!(99.9), dec!(100.1))).is_empty());
+
+        // Bought at 100, the mid drops to 99.9 right after and recovers to 100.2 later
+        let fill = VenueOrderUpdate::builder()
+            .event_time(t0)
+            .portfolio(test_portfolio())
+            .instrument(instrument.clone())
+            .order_id(order.id.to_string())
+            .venue_order_id(1)
+            .side(MarketSide::Buy)
+            .order_type(VenueOrderType::Limit)
+            .time_in_force(VenueOrderTimeInForce::Gtc)
+            .price(dec!(100))
+            .quantity(dec!(2))
+            .fill_price(dec!(100))
+            .fill_quantity(dec!(2))
+            .last_fill_price(dec!(100))
+            .last_fill_quantity(dec!(2))
+            .status(VenueOrderStatus::Filled)
+            .commission_asset(None)
+            .commission(dec!(0))
+            .build();
+        assert!(tracker.update(&Event::VenueOrderUpdate(Arc::new(fill))).is_empty());
+        assert!(tracker.state.lock().strategies.is_empty());
+
+        assert!(tracker.update(&tick(0, dec!(99.8), dec!(100))).is_empty());
+        // The first tick past the horizon marks out at the mid before it
+        let markouts = tracker.update(&tick(2, dec!(100.1), dec!(100.3)));
+        assert_eq!(markouts.len(), 1);
+        assert_eq!(markouts[0].horizon, Duration::from_secs(1));
+        assert!((markouts[0].markout_bps + 10.).abs() < 1e-9);
+        assert_eq!(markouts[0].strategy, OrderThrottle::DEFAULT_STRATEGY);
+
+        // A tick right on the horizon marks out at its own mid
+        let markouts = tracker.update(&tick(10, dec!(100.1), dec!(100.3)));
+        assert_eq!(markouts.len(), 1);
+        assert!((markouts[0].markout_bps - 20.).abs() < 1e-9);
+        assert!(tracker.state.lock().pending.is_empty());
+
+        let report = tracker.report();
+        assert_eq!(report.stats.len(), 2);
+        assert_eq!(report.stats[0].horizon_secs, 1.);
+        assert_eq!(report.stats[0].adverse_share, 1.);
+        assert_eq!(report.stats[1].adverse_share, 0.);
+        assert_eq!(report.stats[1].quantity, 2.);
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse, routing::get, Router};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{MetricsExporterSettings, TradingEngineError};
+
+/// Serves the process wide [`METRICS`] in the Prometheus text format on `GET /metrics`.
+#[derive(Debug, TypedBuilder)]
+pub struct MetricsExporter {
+    address: String,
+}
+
+impl MetricsExporter {
+    pub fn from_config(config: &MetricsExporterSettings) -> Self {
+        Self::builder().address(config.address.clone()).build()
+    }
+
+    pub fn router(self: &Arc<Self>) -> Router {
+        Router::new().route("/metrics", get(metrics))
+    }
+
+    pub async fn serve(self: Arc<Self>, shutdown: CancellationToken) -> Result<(), TradingEngineError> {
+        let listener = TcpListener::bind(&self.address).await?;
+        info!("Metrics exporter listening on {}", self.address);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await?;
+        Ok(())
+    }
+}
+
+async fn metrics() -> impl IntoResponse {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.render())
+}
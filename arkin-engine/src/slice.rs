@@ -0,0 +1,190 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::info;
+use typed_builder::TypedBuilder;
+
+use arkin_allocation::prelude::*;
+use arkin_core::prelude::*;
+use arkin_insights::prelude::*;
+use arkin_persistence::prelude::*;
+use arkin_strategies::prelude::*;
+
+use crate::TradingEngineError;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SliceValue {
+    pub feature: String,
+    pub value: Decimal,
+}
+
+/// Everything computed at one clock tick of a debug slice.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SliceStep {
+    pub event_time: OffsetDateTime,
+    /// Trades and ticks released since the previous step
+    pub trades: usize,
+    pub ticks: usize,
+    /// Last trade and tick of the step, the market the decisions were made on
+    pub last_trade: Option<String>,
+    pub last_tick: Option<String>,
+    pub insights: Vec<SliceValue>,
+    pub signals: Vec<String>,
+    pub orders: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SliceReport {
+    pub instrument: String,
+    pub pipeline: String,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+    pub trades: usize,
+    pub ticks: usize,
+    pub steps: Vec<SliceStep>,
+}
+
+impl fmt::Display for SliceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Slice of {} through {} from {} till {}: trades={} ticks={} steps={}",
+            self.instrument,
+            self.pipeline,
+            self.start,
+            self.end,
+            self.trades,
+            self.ticks,
+            self.steps.len()
+        )?;
+        for step in &self.steps {
+            write!(f, "\n{} trades={} ticks={}", step.event_time, step.trades, step.ticks)?;
+            if let Some(trade) = &step.last_trade {
+                write!(f, "\n  last trade {}", trade)?;
+            }
+            if let Some(tick) = &step.last_tick {
+                write!(f, "\n  last tick {}", tick)?;
+            }
+            for insight in &step.insights {
+                write!(f, "\n  {}={}", insight.feature, insight.value)?;
+            }
+            for signal in &step.signals {
+                write!(f, "\n  signal {}", signal)?;
+            }
+            for order in &step.orders {
+                write!(f, "\n  order {}", order)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replays the persisted events of one instrument around a point in time through the pipeline, the strategies and
+/// the allocation, and records every intermediate result. Nothing is published to the engine or persisted, the
+/// services get their own pubsub and the same slice always gives the same report.
+#[derive(Debug, TypedBuilder)]
+pub struct DebugSlice {
+    persistence: Arc<PersistenceService>,
+    pipeline: Arc<Pipeline>,
+    insights: Arc<dyn Insights>,
+    #[builder(default)]
+    strategies: Vec<Arc<dyn Algorithm>>,
+    #[builder(default)]
+    allocation: Option<Arc<dyn AllocationOptim>>,
+    instrument: Arc<Instrument>,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    frequency: Duration,
+    /// Trades before the start loaded into the feature state, so the features are warm at the first step
+    warmup: Duration,
+}
+
+impl DebugSlice {
+    pub async fn run(&self) -> Result<SliceReport, TradingEngineError> {
+        let instruments = vec![self.instrument.clone()];
+        info!(
+            "Running debug slice of {} from {} till {}",
+            self.instrument, self.start, self.end
+        );
+        self.insights.load(self.start, &instruments, self.warmup).await?;
+
+        let mut trades = self
+            .persistence
+            .trade_store
+            .read_range(&instruments, self.start, self.end)
+            .await?;
+        trades.sort_by_key(|t| t.event_time);
+        let mut ticks = self
+            .persistence
+            .tick_store
+            .read_range(&[self.instrument.id], self.start, self.end)
+            .await?;
+        ticks.sort_by_key(|t| t.event_time);
+        info!("Loaded {} trades and {} ticks", trades.len(), ticks.len());
+
+        let mut report = SliceReport {
+            instrument: self.instrument.symbol.clone(),
+            pipeline: self.pipeline.name.clone(),
+            start: self.start,
+            end: self.end,
+            trades: trades.len(),
+            ticks: ticks.len(),
+            steps: Vec::new(),
+        };
+        let (mut next_trade, mut next_tick) = (0, 0);
+        let mut clock = Clock::new(self.start, self.end, self.frequency);
+        while let Some((_, event_time)) = clock.next() {
+            // Release the events of the step in the order they happened
+            let step_trades = trades[next_trade..].iter().take_while(|t| t.event_time <= event_time).count();
+            let step_ticks = ticks[next_tick..].iter().take_while(|t| t.event_time <= event_time).count();
+            for trade in &trades[next_trade..next_trade + step_trades] {
+                let insights = trade.as_ref().clone().to_insights(self.pipeline.clone());
+                self.insights.insert_batch(&insights).await?;
+            }
+            for tick in &ticks[next_tick..next_tick + step_ticks] {
+                self.persistence.tick_store.update_tick_cache(tick.clone()).await;
+            }
+            next_trade += step_trades;
+            next_tick += step_ticks;
+
+            let insights = self.insights.process(event_time, &instruments, false).await?;
+            let mut signals = Vec::new();
+            for strategy in &self.strategies {
+                signals.extend(strategy.insight_update(&instruments, event_time, &insights).await?);
+            }
+            let orders = match &self.allocation {
+                Some(allocation) => {
+                    let tick = InsightTick::builder()
+                        .event_time(event_time)
+                        .instruments(instruments.clone())
+                        .insights(insights.clone())
+                        .build();
+                    allocation.optimize(Arc::new(tick)).await?
+                }
+                None => Vec::new(),
+            };
+
+            let mut values = insights
+                .iter()
+                .map(|i| SliceValue {
+                    feature: i.feature_id.to_string(),
+                    value: i.value,
+                })
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| a.feature.cmp(&b.feature));
+            report.steps.push(SliceStep {
+                event_time,
+                trades: step_trades,
+                ticks: step_ticks,
+                last_trade: trades[..next_trade].last().map(|t| t.to_string()),
+                last_tick: ticks[..next_tick].last().map(|t| t.to_string()),
+                insights: values,
+                signals: signals.iter().map(|s| s.to_string()).collect(),
+                orders: orders.iter().map(|o| o.to_string()).collect(),
+            });
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,188 @@
+use std::{future::Future, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use arkin_core::prelude::*;
+
+/// What the engine does when a service stops with an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    /// Log the error and resume the service right away
+    Ignore,
+    /// Resume the service after a backoff, halting trading once the restart budget is used up
+    Restart,
+    /// Stop placing new orders and cancel all open orders, market data keeps flowing
+    HaltTrading,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceErrorPolicy {
+    pub transient: ErrorPolicy,
+    pub invalid_input: ErrorPolicy,
+    pub fatal: ErrorPolicy,
+    pub max_restarts: u32,
+    pub restart_backoff_ms: u64,
+}
+
+impl Default for ServiceErrorPolicy {
+    fn default() -> Self {
+        Self {
+            transient: ErrorPolicy::Restart,
+            invalid_input: ErrorPolicy::Ignore,
+            fatal: ErrorPolicy::HaltTrading,
+            max_restarts: 5,
+            restart_backoff_ms: 1000,
+        }
+    }
+}
+
+impl ServiceErrorPolicy {
+    pub fn action(&self, class: ErrorClass) -> ErrorPolicy {
+        match class {
+            ErrorClass::Transient => self.transient,
+            ErrorClass::InvalidInput => self.invalid_input,
+            ErrorClass::Fatal => self.fatal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorPolicies {
+    pub persistor: ServiceErrorPolicy,
+    pub portfolio: ServiceErrorPolicy,
+    pub ingestors: ServiceErrorPolicy,
+    pub insights: ServiceErrorPolicy,
+    pub allocation: ServiceErrorPolicy,
+    pub order_manager: ServiceErrorPolicy,
+    pub executor: ServiceErrorPolicy,
+}
+
+/// Runs a service until it stops cleanly, the shutdown is triggered or its error policy gives up on it. Giving up
+/// cancels the halt token so the engine can stop trading. The services skip the events that fail with
+/// `skip_failed_event`, so a run only ends with an error when the service can't start or fails fatally, restarting
+/// never subscribes again over a single bad event.
+pub async fn supervise<F, Fut, E>(
+    service: &str,
+    policy: ServiceErrorPolicy,
+    shutdown: CancellationToken,
+    halt_trading: CancellationToken,
+    run: F,
+) where
+    F: Fn(CancellationToken) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: ClassifyError,
+{
+    let mut restarts = 0;
+    loop {
+        let Err(e) = run(shutdown.clone()).await else {
+            return;
+        };
+        if shutdown.is_cancelled() {
+            warn!("Service {} failed during shutdown: {}", service, e);
+            return;
+        }
+
+        let class = e.class();
+        match policy.action(class) {
+            ErrorPolicy::Ignore => {
+                warn!("Service {} failed with {} error, ignoring: {}", service, class, e);
+            }
+            ErrorPolicy::Restart if restarts < policy.max_restarts => {
+                restarts += 1;
+                warn!(
+                    "Service {} failed with {} error, restart {}/{}: {}",
+                    service, class, restarts, policy.max_restarts, e
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(policy.restart_backoff_ms)) => {}
+                    _ = shutdown.cancelled() => return,
+                }
+            }
+            ErrorPolicy::Restart => {
+                error!(
+                    "Service {} failed with {} error after {} restarts: {}",
+                    service, class, restarts, e
+                );
+                halt_trading.cancel();
+                return;
+            }
+            ErrorPolicy::HaltTrading => {
+                error!("Service {} failed with {} error, halting trading: {}", service, class, e);
+                halt_trading.cancel();
+                return;
+            }
+        }
+        info!("Resuming service {}", service);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    use test_log::test;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("test error")]
+    struct TestError(ErrorClass);
+
+    impl ClassifyError for TestError {
+        fn class(&self) -> ErrorClass {
+            self.0
+        }
+    }
+
+    /// Fails with the given classes in order and stops cleanly afterwards.
+    async fn run_failing(classes: Vec<ErrorClass>, policy: ServiceErrorPolicy) -> (u32, bool) {
+        let runs = Arc::new(AtomicU32::new(0));
+        let halt = CancellationToken::new();
+        let counter = runs.clone();
+        supervise("test", policy, CancellationToken::new(), halt.clone(), move |_| {
+            let run = counter.fetch_add(1, Ordering::SeqCst) as usize;
+            let result = match classes.get(run) {
+                Some(class) => Err(TestError(*class)),
+                None => Ok(()),
+            };
+            async move { result }
+        })
+        .await;
+        (runs.load(Ordering::SeqCst), halt.is_cancelled())
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_ignore_and_restart_resume_the_service() {
+        let classes = vec![ErrorClass::InvalidInput, ErrorClass::Transient, ErrorClass::InvalidInput];
+        let (runs, halted) = run_failing(classes, ServiceErrorPolicy::default()).await;
+        assert_eq!(runs, 4);
+        assert!(!halted);
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_fatal_error_halts_trading() {
+        let classes = vec![ErrorClass::Fatal, ErrorClass::Transient];
+        let (runs, halted) = run_failing(classes, ServiceErrorPolicy::default()).await;
+        assert_eq!(runs, 1);
+        assert!(halted);
+    }
+
+    #[test(tokio::test(start_paused = true))]
+    async fn test_restart_budget_escalates_to_halt() {
+        let policy = ServiceErrorPolicy {
+            max_restarts: 2,
+            ..Default::default()
+        };
+        let (runs, halted) = run_failing(vec![ErrorClass::Transient; 5], policy).await;
+        assert_eq!(runs, 3);
+        assert!(halted);
+    }
+}
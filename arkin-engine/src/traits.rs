@@ -1,4 +1,9 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
 
 use crate::TradingEngineError;
 
@@ -7,3 +12,13 @@ pub trait TradingEngine: Send + Sync {
     async fn start(&self) -> Result<(), TradingEngineError>;
     async fn stop(&self) -> Result<(), TradingEngineError>;
 }
+
+/// Picks the members of a universe from the candidate instruments.
+#[async_trait]
+pub trait UniverseSelector: std::fmt::Debug + Send + Sync {
+    async fn select(
+        &self,
+        event_time: OffsetDateTime,
+        candidates: &[Arc<Instrument>],
+    ) -> Result<Vec<Arc<Instrument>>, TradingEngineError>;
+}
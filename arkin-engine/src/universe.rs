@@ -0,0 +1,444 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{TradingEngineError, UniverseSelectionConfig, UniverseSelector, UniverseSettings};
+
+/// Fixed list of symbols, symbols that were not loaded are left out.
+#[derive(Debug, TypedBuilder)]
+pub struct StaticUniverse {
+    symbols: Vec<String>,
+}
+
+#[async_trait]
+impl UniverseSelector for StaticUniverse {
+    async fn select(
+        &self,
+        _event_time: OffsetDateTime,
+        candidates: &[Arc<Instrument>],
+    ) -> Result<Vec<Arc<Instrument>>, TradingEngineError> {
+        Ok(candidates
+            .iter()
+            .filter(|i| self.symbols.contains(&i.symbol))
+            .cloned()
+            .collect())
+    }
+}
+
+/// The instruments with the most notional traded, ties go to the symbol first in order so the selection is the same
+/// on every run.
+pub fn top_by_volume(trades: &[Arc<Trade>], n: usize) -> Vec<Arc<Instrument>> {
+    let mut volumes = HashMap::<Arc<Instrument>, Decimal>::new();
+    for trade in trades {
+        *volumes.entry(trade.instrument.clone()).or_default() +=
+            trade.price * trade.quantity * trade.instrument.contract_size;
+    }
+    let mut ranked = volumes.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|(a, va), (b, vb)| vb.cmp(va).then_with(|| a.symbol.cmp(&b.symbol)));
+    ranked.into_iter().take(n).map(|(instrument, _)| instrument).collect()
+}
+
+/// The instruments with the most notional traded over the lookback.
+#[derive(Debug, TypedBuilder)]
+pub struct TopVolumeUniverse {
+    persistence: Arc<PersistenceService>,
+    n: usize,
+    lookback: Duration,
+}
+
+#[async_trait]
+impl UniverseSelector for TopVolumeUniverse {
+    async fn select(
+        &self,
+        event_time: OffsetDateTime,
+        candidates: &[Arc<Instrument>],
+    ) -> Result<Vec<Arc<Instrument>>, TradingEngineError> {
+        let trades = self
+            .persistence
+            .trade_store
+            .read_range(candidates, event_time - self.lookback, event_time)
+            .await?;
+        let top = top_by_volume(&trades, self.n);
+        Ok(candidates.iter().filter(|i| top.contains(i)).cloned().collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricCondition {
+    pub feature_id: FeatureId,
+    pub comparison: Comparison,
+    pub value: Decimal,
+}
+
+/// Conditions on the insights of an instrument joined by `and`, each one `<feature> <op> <value>` with the operators
+/// `>`, `>=`, `<`, `<=`, `==` and `!=`. Instruments missing one of the features don't pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricFilter {
+    expression: String,
+    conditions: Vec<MetricCondition>,
+}
+
+impl MetricFilter {
+    pub fn conditions(&self) -> &[MetricCondition] {
+        &self.conditions
+    }
+
+    pub fn matches(&self, values: &HashMap<FeatureId, Decimal>) -> bool {
+        self.conditions
+            .iter()
+            .all(|c| values.get(&c.feature_id).is_some_and(|v| c.comparison.holds(*v, c.value)))
+    }
+}
+
+impl FromStr for MetricFilter {
+    type Err = TradingEngineError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut conditions = Vec::new();
+        for condition in expression.split(" and ") {
+            let invalid = || TradingEngineError::InvalidUniverse(format!("invalid condition '{}'", condition.trim()));
+            let parts = condition.split_whitespace().collect::<Vec<_>>();
+            let [feature, op, value] = parts.as_slice() else {
+                return Err(invalid());
+            };
+            let comparison = op.parse::<Comparison>().map_err(|_| invalid())?;
+            conditions.push(MetricCondition {
+                feature_id: FeatureId::new(feature.to_string()),
+                comparison,
+                value: Decimal::from_str(value).map_err(|_| invalid())?,
+            });
+        }
+        Ok(Self {
+            expression: expression.to_string(),
+            conditions,
+        })
+    }
+}
+
+impl fmt::Display for MetricFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+/// The instruments whose last insights persisted within the lookback pass the filter.
+#[derive(Debug, TypedBuilder)]
+pub struct MetricFilterUniverse {
+    persistence: Arc<PersistenceService>,
+    pipeline: Arc<Pipeline>,
+    filter: MetricFilter,
+    lookback: Duration,
+}
+
+#[async_trait]
+impl UniverseSelector for MetricFilterUniverse {
+    async fn select(
+        &self,
+        event_time: OffsetDateTime,
+        candidates: &[Arc<Instrument>],
+    ) -> Result<Vec<Arc<Instrument>>, TradingEngineError> {
+        let mut insights = self
+            .persistence
+            .insights_store
+            .read_range(&self.pipeline, candidates, event_time - self.lookback, event_time)
+            .await?;
+        insights.sort_by_key(|i| i.event_time);
+        let mut values = HashMap::<Arc<Instrument>, HashMap<FeatureId, Decimal>>::new();
+        for insight in insights {
+            if let Some(instrument) = &insight.instrument {
+                values
+                    .entry(instrument.clone())
+                    .or_default()
+                    .insert(insight.feature_id.clone(), insight.value);
+            }
+        }
+        Ok(candidates
+            .iter()
+            .filter(|i| values.get(*i).is_some_and(|v| self.filter.matches(v)))
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Debug, Default)]
+struct UniverseState {
+    next_refresh: Option<OffsetDateTime>,
+    /// Members with the time they joined
+    members: HashMap<Arc<Instrument>, OffsetDateTime>,
+    /// Members past their warmup
+    warm: HashSet<Arc<Instrument>>,
+}
+
+/// Instruments the engine computes and trades, selected from the loaded instruments. The members are selected again
+/// on the clock of the events, so a backtest selects the same members at the same time as it replays. Instruments
+/// that join get their insights computed for the warmup before they are traded, the first selection is traded right
+/// away as the engine warms up all loaded instruments on start. Every change is published as [`UniverseUpdate`], the
+/// allocation flattens the instruments that left.
+#[derive(Debug, TypedBuilder)]
+pub struct Universe {
+    pubsub: Arc<PubSub>,
+    #[builder(default = "default".to_string())]
+    name: String,
+    selector: Arc<dyn UniverseSelector>,
+    candidates: Vec<Arc<Instrument>>,
+    #[builder(default = Duration::from_secs(3600))]
+    refresh: Duration,
+    #[builder(default)]
+    warmup: Duration,
+    #[builder(default)]
+    state: Mutex<UniverseState>,
+}
+
+impl Universe {
+    pub async fn from_config(
+        config: &UniverseSettings,
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+        candidates: Vec<Arc<Instrument>>,
+    ) -> Result<Self, TradingEngineError> {
+        let selector: Arc<dyn UniverseSelector> = match &config.selection {
+            UniverseSelectionConfig::Static(c) => {
+                Arc::new(StaticUniverse::builder().symbols(c.symbols.clone()).build())
+            }
+            UniverseSelectionConfig::TopVolume(c) => Arc::new(
+                TopVolumeUniverse::builder()
+                    .persistence(persistence)
+                    .n(c.n)
+                    .lookback(Duration::from_secs(c.lookback_secs))
+                    .build(),
+            ),
+            UniverseSelectionConfig::MetricFilter(c) => {
+                let pipeline = persistence.pipeline_store.read_by_name(&c.pipeline).await?;
+                Arc::new(
+                    MetricFilterUniverse::builder()
+                        .persistence(persistence)
+                        .pipeline(pipeline)
+                        .filter(c.filter.parse()?)
+                        .lookback(Duration::from_secs(c.lookback_secs))
+                        .build(),
+                )
+            }
+        };
+        Ok(Self::builder()
+            .pubsub(pubsub)
+            .name(config.name.clone())
+            .selector(selector)
+            .candidates(candidates)
+            .refresh(Duration::from_secs(config.refresh_secs))
+            .warmup(Duration::from_secs(config.warmup_secs))
+            .build())
+    }
+
+    /// Instruments to compute the insights of at the given time, the members including the ones warming up in the
+    /// order they were loaded in. Selects the members again once the refresh is due.
+    pub async fn instruments(&self, event_time: OffsetDateTime) -> Vec<Arc<Instrument>> {
+        let due = self.state.lock().next_refresh.is_none_or(|t| event_time >= t);
+        let selected = match due {
+            true => match self.selector.select(event_time, &self.candidates).await {
+                Ok(selected) => Some(selected),
+                Err(e) => {
+                    warn!("Failed to select universe {}, keeping its members: {}", self.name, e);
+                    None
+                }
+            },
+            false => None,
+        };
+        if let Some(update) = self.update(event_time, selected) {
+            info!("Universe update: {}", update);
+            self.pubsub.publish::<UniverseUpdate>(update.into());
+        }
+        let state = self.state.lock();
+        self.candidates
+            .iter()
+            .filter(|i| state.members.contains_key(*i))
+            .cloned()
+            .collect()
+    }
+
+    /// Apply a new selection and finish the warmup of the joiners it is over for, the update if anything changed.
+    fn update(&self, event_time: OffsetDateTime, selected: Option<Vec<Arc<Instrument>>>) -> Option<UniverseUpdate> {
+        let mut state = self.state.lock();
+        let UniverseState {
+            next_refresh,
+            members,
+            warm,
+        } = &mut *state;
+
+        let mut joined = Vec::new();
+        let mut left = Vec::new();
+        if let Some(selected) = selected {
+            let first = next_refresh.is_none();
+            *next_refresh = Some(event_time + self.refresh);
+            left = self
+                .candidates
+                .iter()
+                .filter(|i| members.contains_key(*i) && !selected.contains(i))
+                .cloned()
+                .collect();
+            for instrument in &left {
+                members.remove(instrument);
+                warm.remove(instrument);
+            }
+            for instrument in selected {
+                if members.contains_key(&instrument) {
+                    continue;
+                }
+                members.insert(instrument.clone(), event_time);
+                if first {
+                    warm.insert(instrument.clone());
+                }
+                joined.push(instrument);
+            }
+        }
+
+        let mut warmed = false;
+        for (instrument, joined_at) in members.iter() {
+            if !warm.contains(instrument) && event_time >= *joined_at + self.warmup {
+                warm.insert(instrument.clone());
+                warmed = true;
+            }
+        }
+
+        if joined.is_empty() && left.is_empty() && !warmed {
+            return None;
+        }
+        let (tradable, warming): (Vec<_>, Vec<_>) = self
+            .candidates
+            .iter()
+            .filter(|i| members.contains_key(*i))
+            .cloned()
+            .partition(|i| warm.contains(i));
+        let update = UniverseUpdate::builder()
+            .event_time(event_time)
+            .universe(self.name.clone())
+            .joined(joined)
+            .left(left)
+            .members(tradable)
+            .warming(warming)
+            .build();
+        Some(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    /// Selects whatever the test set last.
+    #[derive(Debug, Default)]
+    struct FixedSelector {
+        selected: Mutex<Vec<Arc<Instrument>>>,
+    }
+
+    #[async_trait]
+    impl UniverseSelector for FixedSelector {
+        async fn select(
+            &self,
+            _event_time: OffsetDateTime,
+            _candidates: &[Arc<Instrument>],
+        ) -> Result<Vec<Arc<Instrument>>, TradingEngineError> {
+            Ok(self.selected.lock().clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_membership_changes() {
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let selector = Arc::new(FixedSelector::default());
+        *selector.selected.lock() = vec![btc.clone()];
+        let pubsub = Arc::new(PubSub::new());
+        let mut updates = pubsub.subscribe::<UniverseUpdate>();
+        let universe = Universe::builder()
+            .pubsub(pubsub.clone())
+            .selector(selector.clone())
+            .candidates(vec![btc.clone(), eth.clone()])
+            .refresh(Duration::from_secs(3600))
+            .warmup(Duration::from_secs(600))
+            .build();
+
+        // The first selection is traded right away
+        let start = datetime!(2025-01-01 00:00 UTC);
+        assert_eq!(universe.instruments(start).await, vec![btc.clone()]);
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.joined, vec![btc.clone()]);
+        assert!(update.is_tradable(&btc));
+
+        // Selections only change once the refresh is due
+        *selector.selected.lock() = vec![eth.clone()];
+        assert_eq!(
+            universe.instruments(start + time::Duration::minutes(59)).await,
+            vec![btc.clone()]
+        );
+
+        // Joiners warm up before they are traded, leavers are gone right away
+        let refresh = start + time::Duration::hours(1);
+        assert_eq!(universe.instruments(refresh).await, vec![eth.clone()]);
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.left, vec![btc.clone()]);
+        assert_eq!(update.joined, vec![eth.clone()]);
+        assert_eq!(update.warming, vec![eth.clone()]);
+        assert!(!update.is_tradable(&eth));
+
+        assert_eq!(
+            universe.instruments(refresh + time::Duration::minutes(10)).await,
+            vec![eth.clone()]
+        );
+        let update = updates.recv().await.unwrap();
+        assert!(update.joined.is_empty());
+        assert!(update.warming.is_empty());
+        assert!(update.is_tradable(&eth));
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_top_by_volume() {
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let time = datetime!(2025-01-01 00:00 UTC);
+        let trade = |instrument: &Arc<Instrument>, price: Decimal, quantity: Decimal| {
+            Arc::new(Trade::new(time, instrument.clone(), 1, MarketSide::Buy, price, quantity))
+        };
+        let trades = vec![
+            trade(&btc, dec!(100000), dec!(0.2)),
+            trade(&eth, dec!(3000), dec!(2)),
+            trade(&eth, dec!(3000), dec!(2)),
+        ];
+        assert_eq!(top_by_volume(&trades, 1), vec![btc.clone()]);
+        assert_eq!(top_by_volume(&trades, 5), vec![btc, eth]);
+    }
+
+    #[test]
+    fn test_metric_filter() {
+        let filter = "vol_std_60 > 0.002 and spread_bps <= 5".parse::<MetricFilter>().unwrap();
+        assert_eq!(filter.conditions().len(), 2);
+        let values = |vol: Decimal, spread: Decimal| {
+            HashMap::from([
+                (FeatureId::new("vol_std_60".into()), vol),
+                (FeatureId::new("spread_bps".into()), spread),
+            ])
+        };
+        assert!(filter.matches(&values(dec!(0.003), dec!(5))));
+        assert!(!filter.matches(&values(dec!(0.001), dec!(5))));
+        assert!(!filter.matches(&HashMap::from([(FeatureId::new("vol_std_60".into()), dec!(0.003))])));
+        assert!("vol_std_60 ~ 1".parse::<MetricFilter>().is_err());
+        assert!("vol_std_60 >".parse::<MetricFilter>().is_err());
+    }
+}
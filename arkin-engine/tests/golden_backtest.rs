@@ -0,0 +1,377 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use rust_decimal::prelude::*;
+use sha2::{Digest, Sha256};
+use test_log::test;
+use time::OffsetDateTime;
+use tokio::sync::broadcast::Receiver;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use arkin_allocation::prelude::*;
+use arkin_core::prelude::*;
+use arkin_engine::prelude::*;
+use arkin_execution::prelude::*;
+use arkin_ingestors::prelude::*;
+use arkin_insights::prelude::*;
+use arkin_persistence::prelude::*;
+use arkin_portfolio::prelude::*;
+
+const FIXTURE: &str = include_str!("fixtures/golden_ticks.csv");
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/golden_backtest.txt");
+const SEED: u64 = 0x5EED_A4C1;
+
+/// SplitMix64, the sequence is fixed by the algorithm so the golden values don't depend on the rand version.
+#[derive(Debug)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Replays the bundled ticks. Every event is followed by a short sleep, with the paused test clock the sleep
+/// only completes once all services are idle, so each event is fully processed before the next one.
+#[derive(Debug)]
+struct FixtureIngestor {
+    pubsub: Arc<PubSub>,
+    ticks: Vec<Arc<Tick>>,
+}
+
+impl FixtureIngestor {
+    fn new(pubsub: Arc<PubSub>, instrument: Arc<Instrument>) -> Self {
+        let ticks = FIXTURE
+            .lines()
+            .skip(1)
+            .filter(|l| !l.is_empty())
+            .enumerate()
+            .map(|(idx, line)| {
+                let fields = line.split(',').collect::<Vec<_>>();
+                let millis = i128::from_str(fields[0]).expect("Invalid event time");
+                let tick = Tick::builder()
+                    .event_time(OffsetDateTime::from_unix_timestamp_nanos(millis * 1_000_000).unwrap())
+                    .instrument(instrument.clone())
+                    .tick_id(idx as u64)
+                    .bid_price(Decimal::from_str(fields[1]).unwrap())
+                    .bid_quantity(Decimal::from_str(fields[2]).unwrap())
+                    .ask_price(Decimal::from_str(fields[3]).unwrap())
+                    .ask_quantity(Decimal::from_str(fields[4]).unwrap())
+                    .build();
+                Arc::new(tick)
+            })
+            .collect();
+        Self { pubsub, ticks }
+    }
+
+    async fn wait_idle(&self) {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+#[async_trait]
+impl Ingestor for FixtureIngestor {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        for tick in &self.ticks {
+            if shutdown.is_cancelled() {
+                return Ok(());
+            }
+            self.pubsub.publish::<Tick>(tick.clone());
+            self.wait_idle().await;
+        }
+
+        let last = self.ticks.last().expect("Empty fixture");
+        let ended = StreamEnded::builder()
+            .event_time(last.event_time)
+            .venue(last.instrument.venue.clone())
+            .channel("ticks".to_string())
+            .build();
+        self.pubsub.publish::<StreamEnded>(ended.into());
+        self.wait_idle().await;
+        let finished = SimulationFinished::builder().event_time(last.event_time).build();
+        self.pubsub.publish::<SimulationFinished>(finished.into());
+        Ok(())
+    }
+}
+
+/// Forwards every interval tick as an empty insight tick, the trader below makes its own decisions.
+#[derive(Debug)]
+struct PassthroughInsights {
+    pubsub: Arc<PubSub>,
+}
+
+#[async_trait]
+impl Insights for PassthroughInsights {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), InsightsError> {
+        let mut interval_ticks = self.pubsub.subscribe::<IntervalTick>();
+        loop {
+            tokio::select! {
+                Ok(tick) = interval_ticks.recv() => {
+                    self.process(tick.event_time, &tick.instruments, true).await?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+
+    async fn insert(&self, _insight: Arc<Insight>) -> Result<(), InsightsError> {
+        Ok(())
+    }
+
+    async fn insert_batch(&self, _insights: &[Arc<Insight>]) -> Result<(), InsightsError> {
+        Ok(())
+    }
+
+    async fn remove(&self, _event_time: OffsetDateTime) -> Result<(), InsightsError> {
+        Ok(())
+    }
+
+    async fn load(
+        &self,
+        _event_time: OffsetDateTime,
+        _instruments: &[Arc<Instrument>],
+        _lookback: Duration,
+    ) -> Result<(), InsightsError> {
+        Ok(())
+    }
+
+    async fn load_bars(
+        &self,
+        _event_time: OffsetDateTime,
+        _instruments: &[Arc<Instrument>],
+        _lookback: Duration,
+        _frequency: Duration,
+    ) -> Result<(), InsightsError> {
+        Ok(())
+    }
+
+    async fn process(
+        &self,
+        event_time: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+        publish: bool,
+    ) -> Result<Vec<Arc<Insight>>, InsightsError> {
+        if publish {
+            let tick = InsightTick::builder()
+                .event_time(event_time)
+                .instruments(instruments.to_vec())
+                .insights(vec![])
+                .build();
+            self.pubsub.publish::<InsightTick>(tick.into());
+        }
+        Ok(vec![])
+    }
+}
+
+/// Randomly opens a position when flat and randomly flattens it again, driven by a seeded RNG.
+#[derive(Debug)]
+struct SeededTrader {
+    pubsub: Arc<PubSub>,
+    portfolio: Arc<dyn Accounting>,
+    instrument: Arc<Instrument>,
+    rng: Mutex<SplitMix64>,
+}
+
+#[async_trait]
+impl AllocationOptim for SeededTrader {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), AllocationOptimError> {
+        let mut insight_ticks = self.pubsub.subscribe::<InsightTick>();
+        loop {
+            tokio::select! {
+                Ok(tick) = insight_ticks.recv() => {
+                    self.optimize(tick).await?;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+
+    async fn optimize(&self, tick: Arc<InsightTick>) -> Result<Vec<Arc<ExecutionOrder>>, AllocationOptimError> {
+        let draw = self.rng.lock().unwrap().next_u64();
+        let held = self
+            .portfolio
+            .get_position_by_instrument(&self.instrument)
+            .await
+            .map(|p| p.quantity)
+            .unwrap_or_default();
+
+        let (side, quantity) = if held.is_zero() {
+            if draw % 3 == 0 {
+                return Ok(vec![]);
+            }
+            let side = match (draw >> 8) & 1 {
+                0 => MarketSide::Buy,
+                _ => MarketSide::Sell,
+            };
+            (side, Decimal::new(((draw >> 16) % 10 + 1) as i64, 3))
+        } else {
+            if draw % 4 == 0 {
+                return Ok(vec![]);
+            }
+            let side = match held.is_sign_positive() {
+                true => MarketSide::Sell,
+                false => MarketSide::Buy,
+            };
+            (side, held.abs())
+        };
+
+        let order: Arc<ExecutionOrder> = ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(self.instrument.clone())
+            .order_type(ExecutionOrderType::Taker)
+            .side(side)
+            .price(Decimal::ZERO)
+            .quantity(quantity)
+            .created_at(tick.event_time)
+            .updated_at(tick.event_time)
+            .build()
+            .into();
+        self.pubsub.publish::<ExecutionOrder>(order.clone());
+        Ok(vec![order])
+    }
+}
+
+fn drain<E: EventTypeOf>(rx: &mut Receiver<Arc<E>>) -> Vec<Arc<E>> {
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    events
+}
+
+fn millis(t: OffsetDateTime) -> i128 {
+    t.unix_timestamp_nanos() / 1_000_000
+}
+
+/// Canonical audit log of the run. Ids and wall clock times are left out so the log is reproducible.
+fn audit_log(
+    orders: &[Arc<VenueOrderUpdate>],
+    balances: &[Arc<BalanceUpdate>],
+    positions: &[Arc<PositionUpdate>],
+) -> Vec<String> {
+    let mut log = Vec::new();
+    for o in orders {
+        log.push(format!(
+            "order {} {} {} {} {} {} {} {} {}",
+            millis(o.event_time),
+            o.venue_order_id,
+            o.side,
+            o.order_type,
+            o.status,
+            o.quantity.normalize(),
+            o.last_fill_quantity.normalize(),
+            o.last_fill_price.normalize(),
+            o.commission.normalize()
+        ));
+    }
+    for b in balances {
+        log.push(format!("balance {} {}", b.asset.symbol, b.quantity.normalize()));
+    }
+    for p in positions {
+        log.push(format!(
+            "position {} {} {} {} {} {}",
+            millis(p.event_time),
+            p.instrument.venue_symbol,
+            p.position_side,
+            p.quantity.normalize(),
+            p.entry_price.normalize(),
+            p.realized_pnl.normalize()
+        ));
+    }
+    log
+}
+
+fn sha256_hex(lines: &[String]) -> String {
+    Sha256::digest(lines.join("\n").as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[test(tokio::test(start_paused = true))]
+async fn test_golden_backtest() {
+    let pubsub = Arc::new(PubSub::new());
+    let instrument = test_inst_binance_btc_usdt_perp();
+
+    let mut persistor = MockPersistor::new();
+    persistor.expect_start().returning(|_| Ok(()));
+    let portfolio: Arc<dyn Accounting> = Arc::new(SingleStrategyPortfolio::builder().pubsub(pubsub.clone()).build());
+    let ingestor: Arc<dyn Ingestor> = Arc::new(FixtureIngestor::new(pubsub.clone(), instrument.clone()));
+    let trader = SeededTrader {
+        pubsub: pubsub.clone(),
+        portfolio: portfolio.clone(),
+        instrument: instrument.clone(),
+        rng: Mutex::new(SplitMix64(SEED)),
+    };
+
+    let engine = ForecastEngine::builder()
+        .pubsub(pubsub.clone())
+        .instruments(vec![instrument.clone()])
+        .frequency(Duration::from_secs(60))
+        .simulation(true)
+        .persistor(Arc::new(persistor))
+        .portfolio(portfolio.clone())
+        .ingestors(vec![ingestor])
+        .insights(Arc::new(PassthroughInsights {
+            pubsub: pubsub.clone(),
+        }))
+        .allocation_optim(Arc::new(trader))
+        .order_manager(Arc::new(SimpleOrderManager::builder().pubsub(pubsub.clone()).build()))
+        .executor(Arc::new(SimulationExecutor::builder().pubsub(pubsub.clone()).build()))
+        .build();
+
+    let mut order_updates = pubsub.subscribe::<VenueOrderUpdate>();
+    let mut balance_updates = pubsub.subscribe::<BalanceUpdate>();
+    let mut position_updates = pubsub.subscribe::<PositionUpdate>();
+
+    engine.start().await.unwrap();
+    // Let the portfolio settle the open positions
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    engine.stop().await.unwrap();
+
+    let orders = drain(&mut order_updates);
+    let log = audit_log(&orders, &drain(&mut balance_updates), &drain(&mut position_updates));
+    let trade_count = orders.iter().filter(|o| o.status == VenueOrderStatus::Filled).count();
+    let final_equity = portfolio.available_balance(&instrument.quote_asset).await;
+    let audit_hash = sha256_hex(&log);
+    let result = format!(
+        "final_equity={}\ntrade_count={}\naudit_hash={}\n",
+        final_equity.normalize(),
+        trade_count,
+        audit_hash
+    );
+    info!("Golden backtest result:\n{}", result);
+
+    // Record new golden values after an intended behavior change with ARKIN_BLESS=1
+    if std::env::var("ARKIN_BLESS").is_ok() {
+        std::fs::write(GOLDEN_PATH, &result).expect("Failed to write golden values");
+        return;
+    }
+    let golden = std::fs::read_to_string(GOLDEN_PATH).expect("Missing golden values, run with ARKIN_BLESS=1");
+    let golden = golden.lines().filter_map(|l| l.split_once('=')).collect::<HashMap<_, _>>();
+
+    assert!(
+        portfolio.get_positions().await.values().all(|p| p.quantity.is_zero()),
+        "Positions should be flat after settlement"
+    );
+    assert_eq!(final_equity.normalize().to_string(), golden["final_equity"]);
+    assert_eq!(trade_count.to_string(), golden["trade_count"]);
+    assert_eq!(
+        audit_hash,
+        golden["audit_hash"],
+        "Audit log diverged from the golden run:\n{}",
+        log.join("\n")
+    );
+}
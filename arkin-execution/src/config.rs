@@ -1,9 +1,174 @@
+use std::collections::HashMap;
+
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use arkin_core::{ExecutionOrderType, QuotingPauseMode};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OrderManagerConfig {
     pub order_manager: OrderManagerType,
+    /// Limit the order flow per strategy, orders are passed through right away without it
+    #[serde(default)]
+    pub throttle: Option<OrderThrottleConfig>,
+    /// Pause quoting around venue maintenance and funding, maker orders are never paused without it
+    #[serde(default)]
+    pub quoting_pause: Option<QuotingPauseConfig>,
+    /// Route a share of the orders through another order type and compare the execution costs, all orders keep their
+    /// order type without it
+    #[serde(default)]
+    pub experiment: Option<ExecutionExperimentConfig>,
+    /// Times of day and week the strategies trade, strategies trade around the clock without it
+    #[serde(default)]
+    pub trading_windows: Option<TradingWindowsConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TradingWindowsConfig {
+    /// Windows per strategy name, orders have to be within all windows of their strategy covering the instrument
+    pub strategies: HashMap<String, Vec<TradingWindowConfig>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TradingWindowConfig {
+    /// Hours of the day trading is open as HH:MM-HH:MM in UTC, like `00:00-08:00`, a window ending before its start
+    /// runs over midnight. Open all day without it
+    #[serde(default)]
+    pub hours: Option<String>,
+    /// Closed on saturdays and sundays in UTC
+    #[serde(default)]
+    pub skip_weekends: bool,
+    /// Instrument symbols the window covers, all instruments of the strategy when empty
+    #[serde(default)]
+    pub instruments: Vec<String>,
+    #[serde(default)]
+    pub policy: TradingWindowPolicy,
+}
+
+/// What happens to the orders of a strategy outside its trading window.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingWindowPolicy {
+    #[default]
+    Reject,
+    /// Hold the orders back and send them once the window opens
+    Queue,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionExperimentConfig {
+    pub name: String,
+    /// Order type of the orders in the experiment, orders of other types are left alone
+    pub control: ExperimentOrderType,
+    /// Order type the routed orders are sent as instead
+    pub treatment: ExperimentOrderType,
+    /// Share of the orders in the experiment routed through the treatment
+    pub fraction: f64,
+    /// Strategies whose orders take part, all strategies when empty
+    #[serde(default)]
+    pub strategies: Vec<String>,
+    /// Seconds between the reports of the cost difference
+    #[serde(default = "default_experiment_report_secs")]
+    pub report_secs: u64,
+    /// P-value below which the cost difference is reported as significant
+    #[serde(default = "default_experiment_significance")]
+    pub significance: f64,
+    /// Seed of the routing, the same seed routes the same orders on every run
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_experiment_report_secs() -> u64 {
+    3600
+}
+
+fn default_experiment_significance() -> f64 {
+    0.05
+}
+
+/// Order types the order manager can send, the arms of an execution experiment.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentOrderType {
+    Maker,
+    Taker,
+}
+
+impl From<ExperimentOrderType> for ExecutionOrderType {
+    fn from(order_type: ExperimentOrderType) -> Self {
+        match order_type {
+            ExperimentOrderType::Maker => ExecutionOrderType::Maker,
+            ExperimentOrderType::Taker => ExecutionOrderType::Taker,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuotingPauseConfig {
+    /// Pause of instruments without their own entry, only the listed instruments pause without it
+    #[serde(default)]
+    pub default: Option<QuotingPausePolicyConfig>,
+    /// Pause per instrument symbol
+    #[serde(default)]
+    pub instruments: HashMap<String, QuotingPausePolicyConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuotingPausePolicyConfig {
+    /// Seconds before the start of the event quoting pauses
+    pub before_secs: u64,
+    /// Seconds after the end of the event quoting resumes
+    pub after_secs: u64,
+    pub mode: QuotingPauseMode,
+}
+
+/// Protections of a quoter against being gamed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuoteGuardConfig {
+    /// Requotes per second, quotes stay where they are once the budget is used up
+    pub max_requotes_per_sec: u32,
+    /// Half width of the band the quote spread is drawn from, as a fraction of the spread (e.g. 0.2 for +-20%)
+    #[serde(default)]
+    pub offset_jitter: Decimal,
+    /// Widen the spread after repeated pick-offs, spreads never widen without it
+    #[serde(default)]
+    pub pick_off: Option<PickOffConfig>,
+    /// Seed of the offsets, the same seed draws the same offsets on every run
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PickOffConfig {
+    /// Milliseconds after a fill the mid is compared to the fill price
+    pub window_ms: u64,
+    /// Move of the mid against the fill that counts as a pick-off
+    pub adverse_move_bps: Decimal,
+    /// Pick-offs within the lookback that widen the spread
+    pub max_pick_offs: usize,
+    pub lookback_secs: u64,
+    /// Factor the spread is multiplied with on every widening
+    pub widen_factor: Decimal,
+    /// Largest factor the spread is widened to
+    pub max_widen: Decimal,
+    /// Seconds without widening before the spread returns to normal
+    pub cooldown_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrderThrottleConfig {
+    /// Orders per minute for the whole account
+    pub max_orders_per_minute: u32,
+    /// Budget of strategies without their own entry
+    pub default: StrategyBudgetConfig,
+    #[serde(default)]
+    pub strategies: HashMap<String, StrategyBudgetConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StrategyBudgetConfig {
+    pub max_orders_per_minute: u32,
+    pub max_open_orders: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,6 +177,32 @@ pub enum OrderManagerType {
     SimpleExecutor,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TransferConfig {
+    /// Execute the transfer requests of the operator and the allocation within these limits, nothing is transferred
+    /// without it
+    #[serde(default)]
+    pub transfers: Option<TransferLimitsConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferLimitsConfig {
+    /// Limits of the assets that can be transferred by symbol, other assets are never transferred
+    pub assets: HashMap<String, AssetTransferLimits>,
+    /// Emails of the sub-accounts funds can be moved to and from
+    #[serde(default)]
+    pub sub_accounts: Vec<String>,
+}
+
+/// Transfer limits of one asset, in quantities of the asset.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AssetTransferLimits {
+    /// Largest quantity of a single transfer
+    pub max_quantity: Decimal,
+    /// Largest quantity transferred per UTC day, all directions summed
+    pub max_daily_quantity: Decimal,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExecutorConfig {
     pub executor: ExecutorTypeConfig,
@@ -21,24 +212,161 @@ pub struct ExecutorConfig {
 pub enum ExecutorTypeConfig {
     #[serde(rename = "simulation")]
     Simulation(SimulationConfig),
+    #[serde(rename = "multi_venue_simulation")]
+    MultiVenueSimulation(MultiVenueSimulationConfig),
     #[serde(rename = "binance")]
     Binance(BinanceExecutionConfig),
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultiVenueSimulationConfig {
+    /// Simulation of every venue by venue name, orders go to the venue of their instrument
+    pub venues: HashMap<String, SimulationConfig>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SimulationConfig {
+    /// Milliseconds an order takes to reach the venue, the acknowledgement and fills on arrival come this much later
     pub latency: u64,
     pub commission_maker: Decimal,
     pub commission_taker: Decimal,
     pub max_orders_per_minute: u64,
     pub max_order_size_notional: Decimal,
+    /// Orders below this notional are rejected, except reduce-only orders
     pub min_order_size_notional: Decimal,
+    /// Fill resting limit orders from the replayed trades, at most the traded size per trade
+    #[serde(default)]
+    pub partial_fills: bool,
+    /// Queue resting limit orders behind the size shown at their price, they only fill once that much traded
+    #[serde(default)]
+    pub queue_position: bool,
+    /// Fill orders taking liquidity level by level from the order book rebuilt from the replayed depth channel
+    #[serde(default)]
+    pub depth_fills: bool,
+    /// Track the margin of the account, reject orders it can't cover and liquidate positions below maintenance
+    #[serde(default)]
+    pub margin: Option<SimulationMarginConfig>,
+    /// Inject venue misbehavior like random rejections, lost cancel acknowledgements, delayed fills and downtime
+    #[serde(default)]
+    pub faults: Option<SimulationFaultsConfig>,
+    /// Count the requests against the rate limits of the venue, requests are never limited without it
+    #[serde(default)]
+    pub rate_limit: Option<SimulationRateLimitConfig>,
+}
+
+/// Rate limits of the simulated venue, the defaults are the limits of Binance USD-M futures.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SimulationRateLimitConfig {
+    pub weight_per_minute: u32,
+    pub orders_per_10s: u32,
+    pub orders_per_minute: u32,
+    /// Weight of a new order
+    pub order_weight: u32,
+    /// Weight of a cancel
+    pub cancel_weight: u32,
+    pub mode: SimulationRateLimitMode,
+}
+
+impl Default for SimulationRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            weight_per_minute: 2400,
+            orders_per_10s: 300,
+            orders_per_minute: 1200,
+            order_weight: 1,
+            cancel_weight: 1,
+            mode: SimulationRateLimitMode::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulationRateLimitMode {
+    /// Reject the requests over the limits like the venue
+    Reject,
+    /// Hold the requests back until the limits reset, like a client waiting for its budget
+    Delay,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimulationMarginConfig {
+    pub leverage: Decimal,
+    /// Share of the position notional the equity has to cover, Binance charges 0.4% on the first tier of BTCUSDT
+    pub maintenance_rate: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimulationFaultsConfig {
+    /// Share of the orders rejected as if the venue was overloaded
+    #[serde(default)]
+    pub reject_rate: f64,
+    /// Share of the cancels that take effect without the cancelled order being published
+    #[serde(default)]
+    pub drop_cancel_ack_rate: f64,
+    /// Share of the orders that only fill `fill_delay_ms` after they could first fill
+    #[serde(default)]
+    pub delay_fill_rate: f64,
+    #[serde(default)]
+    pub fill_delay_ms: u64,
+    /// Windows the venue is down, orders are rejected, cancels fail and nothing fills
+    #[serde(default)]
+    pub downtime: Vec<SimulationDowntimeConfig>,
+    /// Seed of the random faults, the same seed injects the same faults on every run
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimulationDowntimeConfig {
+    /// Start of the downtime as YYYY-MM-DD HH:MM in UTC
+    pub start: String,
+    pub end: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BinanceExecutionConfig {
     pub base_url: String,
+    #[serde(default = "default_binance_ws_url")]
+    pub ws_url: String,
     pub api_key: String,
     pub api_secret: String,
     pub no_trade: bool,
+    /// Log the orders and the signed transfer requests instead of sending them
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub account_check: BinanceAccountCheckConfig,
+}
+
+fn default_binance_ws_url() -> String {
+    "wss://fstream.binance.com/ws".to_string()
+}
+
+/// Requirements on the api key and account verified before the executor starts trading.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct BinanceAccountCheckConfig {
+    pub enabled: bool,
+    /// Base url of the spot api which serves the permissions of the api key
+    pub spot_base_url: String,
+    /// Expect the account in hedge mode instead of one-way mode
+    pub hedge_mode: bool,
+    /// Require the api key to be restricted to whitelisted ips
+    pub require_ip_restriction: bool,
+    /// Accept universal transfer rights on the api key, needed to move funds between wallets and sub-accounts
+    pub allow_transfers: bool,
+}
+
+impl Default for BinanceAccountCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            spot_base_url: "https://api.binance.com".to_string(),
+            hedge_mode: false,
+            require_ip_restriction: true,
+            allow_transfers: false,
+        }
+    }
 }
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Debug, Error)]
 pub enum OrderManagerError {
     #[error("Instrument already has order: {0}")]
@@ -44,6 +46,51 @@ pub enum ExecutorError {
     #[error("Invalid order: {0}")]
     InvalidOrder(String),
 
+    #[error("Invalid transfer: {0}")]
+    InvalidTransfer(String),
+
+    #[error("Account misconfigured: {0}")]
+    AccountMisconfigured(String),
+
+    #[error(transparent)]
+    PersistenceError(#[from] arkin_persistence::PersistenceError),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
+
+impl ClassifyError for OrderManagerError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            OrderManagerError::OrderAlreadyExists(_)
+            | OrderManagerError::ExecutionOrderNotFound(_)
+            | OrderManagerError::VenueOrderNotFound(_) => ErrorClass::InvalidInput,
+            OrderManagerError::ExecutorError(e) => e.class(),
+            OrderManagerError::Unknown => ErrorClass::Fatal,
+        }
+    }
+}
+
+impl From<VenueAdapterError> for ExecutorError {
+    fn from(e: VenueAdapterError) -> Self {
+        match e {
+            VenueAdapterError::Authentication(msg) => ExecutorError::AuthenticationError(msg),
+            VenueAdapterError::RateLimited(_) => ExecutorError::ApiLimitExceeded,
+            VenueAdapterError::Rejected(msg) | VenueAdapterError::Unsupported(msg) => ExecutorError::InvalidOrder(msg),
+            VenueAdapterError::Connection(msg) | VenueAdapterError::Parse(msg) => ExecutorError::NetworkError(msg),
+        }
+    }
+}
+
+impl ClassifyError for ExecutorError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            ExecutorError::NetworkError(_) | ExecutorError::ApiLimitExceeded => ErrorClass::Transient,
+            ExecutorError::InvalidOrder(_) | ExecutorError::InvalidTransfer(_) => ErrorClass::InvalidInput,
+            ExecutorError::PersistenceError(e) => e.class(),
+            ExecutorError::AuthenticationError(_)
+            | ExecutorError::AccountMisconfigured(_)
+            | ExecutorError::Unknown(_) => ErrorClass::Fatal,
+        }
+    }
+}
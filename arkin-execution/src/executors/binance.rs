@@ -2,10 +2,13 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use async_tungstenite::tokio::ConnectStream;
 use async_tungstenite::tungstenite::Message;
 use dashmap::DashMap;
 use futures_util::StreamExt;
 use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize};
+use time::OffsetDateTime;
 use tokio::select;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
@@ -14,30 +17,181 @@ use uuid::Uuid;
 
 use arkin_binance::listen_key::NewListenKey;
 use arkin_binance::models::{
-    AccountSnapshot, BalanceDetails, BinancePositionSide, BinanceSwapsListenKeyResponse, BinanceUSDMUserStreamEvent,
-    PositionDetail,
+    AccountSnapshot, BinancePositionSide, BinanceSwapsListenKeyResponse, BinanceUSDMUserStreamEvent,
 };
+use arkin_binance::prelude::WebSocketState;
 use arkin_binance::trade::{
-    AccountRequest, BalanceRequest, CancelOpenOrdersRequest, NewOrderRequest, PositionInfoRequest,
+    AccountRequest, ApiRestrictions, ApiRestrictionsRequest, PositionModeRequest, PositionModeResponse,
+    SubAccountTransferRequest, SubAccountType, TransferResponse, UniversalTransferRequest, UniversalTransferType,
 };
-use arkin_binance::{BinanceHttpClient, BinanceWebSocketClient, Request};
+use arkin_binance::{BinanceHttpClient, BinanceWebSocketClient, Request, Response};
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 
-use crate::{Executor, ExecutorError};
+use crate::{BinanceAccountCheckConfig, Executor, ExecutorError};
 
 #[derive(Debug, TypedBuilder)]
 pub struct BinanceExecutor {
     pub pubsub: Arc<PubSub>,
     pub persistence: Arc<PersistenceService>,
     pub client: Arc<BinanceHttpClient>,
+    /// Shared with the ingestors, orders are mapped to venue symbols and count against the rate limit of the venue
+    pub adapter: Arc<dyn VenueAdapter>,
     pub api_key: String,
+    /// Base url of the user data stream, the listen key is appended to it
+    #[builder(default = "wss://fstream.binance.com/ws".to_string())]
+    pub ws_url: String,
     pub no_trade: bool,
+    /// Route orders as usual but only log them instead of sending them to the venue
+    #[builder(default)]
+    pub dry_run: bool,
     #[builder(default)]
     pub open_orders: DashMap<Arc<Instrument>, Uuid>,
+    /// Client for the spot api, the permissions of the api key are only served there
+    #[builder(default)]
+    pub spot_client: Option<Arc<BinanceHttpClient>>,
+    #[builder(default)]
+    pub account_check: BinanceAccountCheckConfig,
+    /// A standby follows the account on the user stream but leaves the orders to the leader
+    #[builder(default)]
+    pub leadership: Arc<Leadership>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceErrorResponse {
+    code: i64,
+    msg: String,
+}
+
+/// Parse a response body, turning the error payloads of binance into executor errors.
+fn parse_response<T: DeserializeOwned>(body: &str) -> Result<T, ExecutorError> {
+    serde_json::from_str::<T>(body).map_err(|e| match serde_json::from_str::<BinanceErrorResponse>(body) {
+        // Invalid api key, signature or permissions
+        Ok(err) if matches!(err.code, -2014 | -2015 | -1022 | -1002) => {
+            ExecutorError::AuthenticationError(format!("{} ({})", err.msg, err.code))
+        }
+        Ok(err) => ExecutorError::Unknown(format!("{} ({})", err.msg, err.code)),
+        Err(_) => ExecutorError::NetworkError(format!("Could not parse response: {}", e)),
+    })
+}
+
+fn sub_account_type(account: AccountType) -> SubAccountType {
+    match account {
+        AccountType::Spot => SubAccountType::Spot,
+        AccountType::UsdmFutures => SubAccountType::UsdtFuture,
+    }
+}
+
+/// Everything about the api key and account that does not match the requirements.
+pub fn account_check_problems(
+    check: &BinanceAccountCheckConfig,
+    restrictions: &ApiRestrictions,
+    dual_side_position: bool,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !restrictions.enable_futures {
+        problems.push("futures trading is not enabled for the api key".to_string());
+    }
+    if restrictions.enable_withdrawals {
+        problems.push("the api key has withdrawal rights".to_string());
+    }
+    match check.allow_transfers {
+        false if restrictions.enable_internal_transfer || restrictions.permits_universal_transfer => {
+            problems.push("the api key has transfer rights".to_string())
+        }
+        true if !restrictions.permits_universal_transfer => {
+            problems.push("transfers are allowed but the api key has no universal transfer rights".to_string())
+        }
+        _ => {}
+    }
+    if check.require_ip_restriction && !restrictions.ip_restrict {
+        problems.push("the api key is not restricted to whitelisted ips".to_string());
+    }
+    match (check.hedge_mode, dual_side_position) {
+        (false, true) => problems.push("the account is in hedge mode, expected one-way mode".to_string()),
+        (true, false) => problems.push("the account is in one-way mode, expected hedge mode".to_string()),
+        _ => {}
+    }
+    problems
 }
 
 impl BinanceExecutor {
+    /// Verify the permissions of the api key and the position mode of the account, so a misconfigured key fails at
+    /// startup instead of on the first rejected order.
+    pub async fn verify_account(&self) -> Result<(), ExecutorError> {
+        if !self.account_check.enabled {
+            warn!("Skipping the api key and account check");
+            return Ok(());
+        }
+
+        let Some(spot_client) = &self.spot_client else {
+            return Err(ExecutorError::AccountMisconfigured(
+                "no spot client configured to verify the api key permissions".to_string(),
+            ));
+        };
+        let res = spot_client
+            .send(ApiRestrictionsRequest::new())
+            .await
+            .map_err(|e| ExecutorError::NetworkError(e.to_string()))?;
+        let restrictions = parse_response::<ApiRestrictions>(&res.body)?;
+
+        let res = self
+            .client
+            .send(PositionModeRequest::new())
+            .await
+            .map_err(|e| ExecutorError::NetworkError(e.to_string()))?;
+        let mode = parse_response::<PositionModeResponse>(&res.body)?;
+
+        let problems = account_check_problems(&self.account_check, &restrictions, mode.dual_side_position);
+        if !problems.is_empty() {
+            for problem in &problems {
+                error!("Binance account check failed: {}", problem);
+            }
+            return Err(ExecutorError::AccountMisconfigured(problems.join(", ")));
+        }
+        let position_mode = match mode.dual_side_position {
+            true => "hedge",
+            false => "one-way",
+        };
+        info!("Binance account check passed, account in {} mode", position_mode);
+        Ok(())
+    }
+
+    /// Send a transfer to the spot api, which serves the wallet and sub-account endpoints. In dry run mode the signed
+    /// request is logged instead and no response is returned.
+    async fn send_transfer_request(&self, req: Request) -> Result<Option<Response>, ExecutorError> {
+        let Some(spot_client) = &self.spot_client else {
+            return Err(ExecutorError::AccountMisconfigured(
+                "no spot client configured to send transfers".to_string(),
+            ));
+        };
+        if self.dry_run {
+            let prepared = spot_client
+                .prepare(req)
+                .map_err(|e| ExecutorError::InvalidTransfer(e.to_string()))?;
+            info!("Dry run, not sending request: {} {}", prepared.method(), prepared.url());
+            return Ok(None);
+        }
+        match spot_client.send(req).await {
+            Ok(res) => Ok(Some(res)),
+            Err(e) => Err(ExecutorError::NetworkError(e.to_string())),
+        }
+    }
+
+    /// Instrument of a venue symbol, looked up in the symbol registry the first time the adapter sees the symbol.
+    async fn instrument(&self, venue_symbol: &str) -> Result<Arc<Instrument>, PersistenceError> {
+        if let Some(instrument) = self.adapter.instrument(venue_symbol) {
+            return Ok(instrument);
+        }
+        let instrument = self
+            .persistence
+            .symbol_registry
+            .instrument(self.adapter.venue(), venue_symbol)
+            .await?;
+        self.adapter.symbols().insert(instrument.clone());
+        Ok(instrument)
+    }
+
     pub async fn get_listen_key(&self) -> Result<String, ExecutorError> {
         let req: Request = NewListenKey::new().into();
         let listen_key = match self.client.send(req).await {
@@ -60,6 +214,20 @@ impl BinanceExecutor {
         Ok(listen_key.listen_key)
     }
 
+    pub async fn connect_user_stream(&self, listen_key: &str) -> Result<WebSocketState<ConnectStream>, ExecutorError> {
+        let url = format!("{}/{}", self.ws_url.trim_end_matches('/'), listen_key);
+        match BinanceWebSocketClient::connect(&url).await {
+            Ok((stream, _)) => {
+                info!("Connected to Binance WebSocket");
+                Ok(stream)
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                Err(ExecutorError::NetworkError(e.to_string()))
+            }
+        }
+    }
+
     pub async fn handle_websocket_message(&self, msg: Message) -> Result<Option<Message>, ExecutorError> {
         debug!("Received message: {:?}", msg);
 
@@ -99,13 +267,14 @@ impl BinanceExecutor {
 
     pub async fn handle_user_stream_update(&self, event: BinanceUSDMUserStreamEvent) -> Result<(), ExecutorError> {
         debug!("Received user stream event: {:?}", event);
+        let received_at = OffsetDateTime::now_utc();
         match event {
             BinanceUSDMUserStreamEvent::OrderTradeUpdate {
                 event_time,
                 transaction_time,
                 order,
             } => {
-                if let Ok(instrument) = self.persistence.instrument_store.read_by_venue_symbol(&order.symbol).await {
+                if let Ok(instrument) = self.instrument(&order.symbol).await {
                     // Check if there is a order.commission_asset
                     let commission_asset = if let Some(commission_asset) = order.commission_asset {
                         if let Ok(asset) = self.persistence.asset_store.read_by_symbol(&commission_asset).await {
@@ -133,9 +302,15 @@ impl BinanceExecutor {
                         .fill_quantity(order.filled_accumulated_quantity)
                         .last_fill_price(order.last_filled_price)
                         .last_fill_quantity(order.last_filled_quantity)
+                        .liquidity(match order.is_maker {
+                            true => LiquidityRole::Maker,
+                            false => LiquidityRole::Taker,
+                        })
                         .commission_asset(commission_asset)
                         .commission(order.commission.unwrap_or(Decimal::ZERO))
                         .status(order.order_status.into())
+                        .exchange_time(Some(transaction_time))
+                        .received_at(received_at)
                         .build()
                         .into();
                     self.pubsub.publish::<VenueOrderUpdate>(update);
@@ -162,9 +337,7 @@ impl BinanceExecutor {
                     }
                 }
                 for position in &account.positions {
-                    if let Ok(instrument) =
-                        self.persistence.instrument_store.read_by_venue_symbol(&position.symbol).await
-                    {
+                    if let Ok(instrument) = self.instrument(&position.symbol).await {
                         let position_side = match (position.position_side, position.position_amount) {
                             (BinancePositionSide::Long, _) => PositionSide::Long,
                             (BinancePositionSide::Short, _) => PositionSide::Short,
@@ -211,6 +384,9 @@ impl Executor for BinanceExecutor {
 
         let mut orders = self.pubsub.subscribe::<VenueOrder>();
 
+        // Fail fast on a misconfigured api key or account
+        self.verify_account().await?;
+
         // Get balances
         if let Err(e) = self.get_balances().await {
             error!("Failed to get balances: {}", e);
@@ -231,16 +407,7 @@ impl Executor for BinanceExecutor {
             }
         };
 
-        let mut ws_client = match BinanceWebSocketClient::connect_with_listen_key(&listen_key).await {
-            Ok((stream, _)) => {
-                info!("Connected to Binance WebSocket");
-                stream
-            }
-            Err(e) => {
-                error!("Error: {:?}", e);
-                return Err(ExecutorError::NetworkError(e.to_string()));
-            }
-        };
+        let mut ws_client = self.connect_user_stream(&listen_key).await?;
 
         loop {
             select! {
@@ -253,15 +420,9 @@ impl Executor for BinanceExecutor {
                             continue;
                         }
                     };
-                    ws_client = match BinanceWebSocketClient::connect_with_listen_key(&new_listen_key).await {
-                        Ok((stream, _)) => {
-                            info!("Connected to Binance WebSocket");
-                            stream
-                        }
-                        Err(e) => {
-                            error!("Error: {:?}", e);
-                            continue;
-                        }
+                    ws_client = match self.connect_user_stream(&new_listen_key).await {
+                        Ok(stream) => stream,
+                        Err(_) => continue,
                     };
                 }
                 res = ws_client.as_mut().next() => {
@@ -286,15 +447,9 @@ impl Executor for BinanceExecutor {
                         None => {
                             error!("WebSocket stream closed");
                             // Reconnect
-                            ws_client = match BinanceWebSocketClient::connect_with_listen_key(&listen_key).await {
-                                Ok((stream, _)) => {
-                                    info!("Connected to Binance WebSocket");
-                                    stream
-                                }
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                    continue;
-                                }
+                            ws_client = match self.connect_user_stream(&listen_key).await {
+                                Ok(stream) => stream,
+                                Err(_) => continue,
                             };
                         }
                     }
@@ -306,6 +461,10 @@ impl Executor for BinanceExecutor {
                         info!("No trade mode enabled, skipping order");
                         continue;
                     }
+                    if !self.leadership.is_leader() {
+                        info!("Standby, leaving order {} to the leader", order.id);
+                        continue;
+                    }
                     // First cancel all open orders for the instrument
                     match self.cancel_orders_by_instrument(order.instrument.clone()).await {
                         Ok(_) => info!("Cancelled all open orders for instrument: {}", order.instrument),
@@ -319,11 +478,12 @@ impl Executor for BinanceExecutor {
                 }
                 _ = shutdown.cancelled() => {
                     info!("Shutting down Binance executor...");
-                    info!("Cancelling all open orders");
-                    let instruments = self.open_orders.iter().map(|e| e.key().clone()).collect::<Vec<_>>();
-                    for inst in instruments {
-                      self.cancel_orders_by_instrument(inst).await?;
+                    // The orders of a standby are the orders of the leader
+                    if !self.leadership.is_leader() {
+                        break;
                     }
+                    info!("Cancelling all open orders");
+                    self.cancel_all_orders().await?;
                     break;
                 }
             }
@@ -352,9 +512,7 @@ impl Executor for BinanceExecutor {
                             }
                         }
                         for position in &snapshot.positions {
-                            if let Ok(instrument) =
-                                self.persistence.instrument_store.read_by_venue_symbol(&position.symbol).await
-                            {
+                            if let Ok(instrument) = self.instrument(&position.symbol).await {
                                 let position_side = match (position.position_side, position.position_amt) {
                                     (BinancePositionSide::Long, _) => PositionSide::Long,
                                     (BinancePositionSide::Short, _) => PositionSide::Short,
@@ -393,127 +551,83 @@ impl Executor for BinanceExecutor {
     }
 
     async fn get_balances(&self) -> Result<(), ExecutorError> {
-        let req: Request = BalanceRequest::builder().build().into();
-
-        match self.client.send(req).await {
-            Ok(res) => {
-                match serde_json::from_str::<Vec<BalanceDetails>>(&res.body) {
-                    Ok(balances) => {
-                        for balance in &balances {
-                            if let Ok(asset) = self.persistence.asset_store.read_by_symbol(&balance.asset).await {
-                                let update = BalanceUpdate::builder()
-                                    .event_time(balance.update_time)
-                                    .portfolio(test_portfolio())
-                                    .asset(asset)
-                                    .quantity(balance.balance)
-                                    // .balance_change(balance.balance_change)
-                                    .build()
-                                    .into();
-                                self.pubsub.publish::<BalanceUpdate>(update);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error: {:?}", e);
-                        return Err(ExecutorError::NetworkError(e.to_string()));
-                    }
-                }
-                Ok(())
-            }
-            Err(e) => {
-                error!("Error: {:?}", e);
-                Err(ExecutorError::NetworkError(e.to_string()))
+        let event_time = OffsetDateTime::now_utc();
+        for balance in self.adapter.balances().await? {
+            if let Ok(asset) = self.persistence.asset_store.read_by_symbol(&balance.asset).await {
+                let update = BalanceUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(test_portfolio())
+                    .asset(asset)
+                    .quantity(balance.balance)
+                    .build()
+                    .into();
+                self.pubsub.publish::<BalanceUpdate>(update);
             }
         }
+        Ok(())
     }
 
+    /// Open positions of the account, short positions have a negative quantity in one-way and hedge mode alike.
     async fn get_positions(&self) -> Result<(), ExecutorError> {
-        let req: Request = PositionInfoRequest::builder().build().into();
-        match self.client.send(req).await {
-            Ok(res) => {
-                match serde_json::from_str::<Vec<PositionDetail>>(&res.body) {
-                    Ok(positions) => {
-                        for position in &positions {
-                            if let Ok(instrument) =
-                                self.persistence.instrument_store.read_by_venue_symbol(&position.symbol).await
-                            {
-                                let position_side = match (position.position_side, position.position_amt) {
-                                    (BinancePositionSide::Long, _) => PositionSide::Long,
-                                    (BinancePositionSide::Short, _) => PositionSide::Short,
-                                    (BinancePositionSide::Both, q) => match q.is_sign_positive() {
-                                        true => PositionSide::Long,
-                                        false => PositionSide::Short,
-                                    },
-                                };
-                                let update = PositionUpdate::builder()
-                                    .event_time(position.update_time)
-                                    .portfolio(test_portfolio())
-                                    .instrument(instrument)
-                                    .entry_price(position.entry_price)
-                                    .quantity(position.position_amt)
-                                    .realized_pnl(Decimal::ZERO)
-                                    .unrealized_pnl(position.un_realized_profit)
-                                    .position_side(position_side)
-                                    .build()
-                                    .into();
-                                self.pubsub.publish::<PositionUpdate>(update);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error: {:?}", e);
-                        return Err(ExecutorError::NetworkError(e.to_string()));
-                    }
-                }
-                Ok(())
-            }
-            Err(e) => {
-                error!("Error: {:?}", e);
-                Err(ExecutorError::NetworkError(e.to_string()))
+        let event_time = OffsetDateTime::now_utc();
+        for position in self.adapter.positions().await? {
+            if let Ok(instrument) = self.instrument(&position.venue_symbol).await {
+                let position_side = match position.quantity.is_sign_positive() {
+                    true => PositionSide::Long,
+                    false => PositionSide::Short,
+                };
+                let update = PositionUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(test_portfolio())
+                    .instrument(instrument)
+                    .entry_price(position.entry_price)
+                    .quantity(position.quantity)
+                    .realized_pnl(Decimal::ZERO)
+                    .unrealized_pnl(position.unrealized_pnl)
+                    .position_side(position_side)
+                    .build()
+                    .into();
+                self.pubsub.publish::<PositionUpdate>(update);
             }
         }
+        Ok(())
     }
 
     async fn place_order(&self, order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
         self.open_orders.insert(order.instrument.clone(), order.id);
 
-        let req: Request = match order.order_type {
-            VenueOrderType::Market => NewOrderRequest::builder()
-                .symbol(order.instrument.venue_symbol.clone())
-                .order_type(order.order_type.into())
-                .side(order.side.into())
-                .quantity(order.quantity.into())
-                .new_client_order_id(order.id.to_string().into())
-                .build()
-                .into(),
-
-            VenueOrderType::Limit => NewOrderRequest::builder()
-                .symbol(order.instrument.venue_symbol.clone())
-                .order_type(order.order_type.into())
-                .side(order.side.into())
-                .price(Some(order.price))
-                .quantity(order.quantity.into())
-                .new_client_order_id(order.id.to_string().into())
-                .time_in_force(Some(order.time_in_force.into()))
-                .build()
-                .into(),
-            _ => {
-                warn!("Order type not supported");
-                return Ok(());
-            }
-        };
-
-        match self.client.send(req).await {
-            Ok(res) => {
-                debug!("Response: {:?}", res.body);
-                Ok(())
-            }
-            Err(e) => {
+        if !self.dry_run {
+            if let Err(e) = self.adapter.place_order(&order).await {
                 self.open_orders.remove(&order.instrument);
                 error!("Error: {:?}", e);
-                return Err(ExecutorError::NetworkError(e.to_string()));
+                return Err(e.into());
             }
+            return Ok(());
         }
+
+        // Acknowledge the order like the venue would, so the rest of the system sees it as placed
+        info!("Dry run, not sending order: {}", order);
+        let update = VenueOrderUpdate::builder()
+            .event_time(OffsetDateTime::now_utc())
+            .portfolio(order.portfolio.clone())
+            .instrument(order.instrument.clone())
+            .order_id(order.id.to_string())
+            .venue_order_id(0)
+            .side(order.side)
+            .order_type(order.order_type)
+            .time_in_force(order.time_in_force)
+            .price(order.price)
+            .quantity(order.quantity)
+            .fill_price(Decimal::ZERO)
+            .fill_quantity(Decimal::ZERO)
+            .last_fill_price(Decimal::ZERO)
+            .last_fill_quantity(Decimal::ZERO)
+            .commission_asset(None)
+            .commission(Decimal::ZERO)
+            .status(VenueOrderStatus::Placed)
+            .build();
+        self.pubsub.publish::<VenueOrderUpdate>(update.into());
+        Ok(())
     }
     async fn place_orders(&self, _orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
         unimplemented!()
@@ -534,19 +648,75 @@ impl Executor for BinanceExecutor {
     }
 
     async fn cancel_orders_by_instrument(&self, instrument: Arc<Instrument>) -> Result<(), ExecutorError> {
-        let req: Request = CancelOpenOrdersRequest::builder()
-            .symbol(instrument.venue_symbol.clone())
-            .build()
-            .into();
-
-        if let Err(e) = self.client.send(req).await {
-            error!("Error: {:?}", e);
-            return Err(ExecutorError::NetworkError(e.to_string()));
+        match self.dry_run {
+            true => info!("Dry run, not cancelling the open orders of {}", instrument),
+            false => {
+                if let Err(e) = self.adapter.cancel_all_orders(&instrument).await {
+                    error!("Error: {:?}", e);
+                    return Err(e.into());
+                }
+            }
         }
+        self.open_orders.remove(&instrument);
         Ok(())
     }
+    /// Cancel the open orders of every instrument with open orders. Keeps going past failed instruments so one bad
+    /// symbol doesn't leave the others in the book, and returns the first error.
     async fn cancel_all_orders(&self) -> Result<(), ExecutorError> {
-        unimplemented!()
+        let instruments = self.open_orders.iter().map(|e| e.key().clone()).collect::<Vec<_>>();
+        let mut res = Ok(());
+        for instrument in instruments {
+            if let Err(e) = self.cancel_orders_by_instrument(instrument.clone()).await {
+                error!("Failed to cancel the open orders of {}: {}", instrument, e);
+                if res.is_ok() {
+                    res = Err(e);
+                }
+            }
+        }
+        res
+    }
+
+    async fn transfer(&self, transfer: Arc<AccountTransfer>) -> Result<String, ExecutorError> {
+        let req: Request = match transfer.is_sub_account() {
+            false => {
+                let transfer_type = match (transfer.from, transfer.to) {
+                    (AccountType::Spot, AccountType::UsdmFutures) => UniversalTransferType::MainUmfuture,
+                    (AccountType::UsdmFutures, AccountType::Spot) => UniversalTransferType::UmfutureMain,
+                    (from, to) => {
+                        return Err(ExecutorError::InvalidTransfer(format!(
+                            "no wallet transfer from {} to {}",
+                            from, to
+                        )))
+                    }
+                };
+                UniversalTransferRequest::new(transfer_type, &transfer.asset.symbol, transfer.quantity).into()
+            }
+            true => {
+                let mut req = SubAccountTransferRequest::new(
+                    sub_account_type(transfer.from),
+                    sub_account_type(transfer.to),
+                    &transfer.asset.symbol,
+                    transfer.quantity,
+                )
+                .client_tran_id(&transfer.id.to_string());
+                if let Some(email) = &transfer.from_sub_account {
+                    req = req.from_email(email);
+                }
+                if let Some(email) = &transfer.to_sub_account {
+                    req = req.to_email(email);
+                }
+                req.into()
+            }
+        };
+
+        match self.send_transfer_request(req).await? {
+            Some(res) => {
+                let res = parse_response::<TransferResponse>(&res.body)?;
+                info!("Transfer {} executed with venue id {}", transfer.id, res.tran_id);
+                Ok(res.tran_id.to_string())
+            }
+            None => Ok(format!("dry-run-{}", transfer.id)),
+        }
     }
 }
 
@@ -556,12 +726,116 @@ mod tests {
 
     use super::*;
 
-    use arkin_binance::Credentials;
+    use arkin_binance::{BinanceAdapter, Credentials};
     use rust_decimal_macros::dec;
     use test_log::test;
     use tokio_rustls::rustls::crypto::{aws_lc_rs, CryptoProvider};
     use tokio_util::task::TaskTracker;
 
+    fn restrictions() -> ApiRestrictions {
+        ApiRestrictions {
+            ip_restrict: true,
+            enable_reading: true,
+            enable_futures: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_account_check_passes() {
+        let check = BinanceAccountCheckConfig::default();
+        assert!(account_check_problems(&check, &restrictions(), false).is_empty());
+
+        let hedge = BinanceAccountCheckConfig {
+            hedge_mode: true,
+            ..Default::default()
+        };
+        assert!(account_check_problems(&hedge, &restrictions(), true).is_empty());
+    }
+
+    #[test]
+    fn test_account_check_problems() {
+        let check = BinanceAccountCheckConfig::default();
+        let restrictions = ApiRestrictions {
+            ip_restrict: false,
+            enable_futures: false,
+            enable_withdrawals: true,
+            ..Default::default()
+        };
+        let problems = account_check_problems(&check, &restrictions, true);
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].contains("futures"));
+        assert!(problems[1].contains("withdrawal"));
+        assert!(problems[2].contains("ips"));
+        assert!(problems[3].contains("hedge mode"));
+
+        let no_ip_check = BinanceAccountCheckConfig {
+            require_ip_restriction: false,
+            ..Default::default()
+        };
+        let restrictions = ApiRestrictions {
+            ip_restrict: false,
+            ..self::restrictions()
+        };
+        assert!(account_check_problems(&no_ip_check, &restrictions, false).is_empty());
+
+        // Transfer rights are only accepted if transfers are allowed, which then require them
+        let transfer_rights = ApiRestrictions {
+            permits_universal_transfer: true,
+            ..self::restrictions()
+        };
+        let problems = account_check_problems(&check, &transfer_rights, false);
+        assert_eq!(problems, vec!["the api key has transfer rights".to_string()]);
+        let transfers = BinanceAccountCheckConfig {
+            allow_transfers: true,
+            ..Default::default()
+        };
+        assert!(account_check_problems(&transfers, &transfer_rights, false).is_empty());
+        assert_eq!(account_check_problems(&transfers, &self::restrictions(), false).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_error_response() {
+        let body = r#"{"code":-2015,"msg":"Invalid API-key, IP, or permissions for action."}"#;
+        assert!(matches!(
+            parse_response::<PositionModeResponse>(body),
+            Err(ExecutorError::AuthenticationError(_))
+        ));
+        assert!(matches!(
+            parse_response::<PositionModeResponse>(r#"{"code":-1000,"msg":"Unknown"}"#),
+            Err(ExecutorError::Unknown(_))
+        ));
+        assert!(parse_response::<PositionModeResponse>(r#"{"dualSidePosition":false}"#).is_ok());
+    }
+
+    #[test(tokio::test)]
+    async fn test_halt_cancels_all_orders() {
+        let pubsub = Arc::new(PubSub::new());
+        let config = load::<PersistenceConfig>();
+        let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+        let client = Arc::new(
+            BinanceHttpClient::builder()
+                .credentials(Some(Credentials::from_hmac("key", "secret")))
+                .build(),
+        );
+        // Dry run only logs the cancels
+        let executor = BinanceExecutor::builder()
+            .pubsub(pubsub.clone())
+            .persistence(persistence)
+            .client(client.clone())
+            .adapter(Arc::new(BinanceAdapter::builder().client(client).build()))
+            .api_key("key".to_string())
+            .no_trade(false)
+            .dry_run(true)
+            .build();
+        executor.open_orders.insert(test_inst_binance_btc_usdt_perp(), Uuid::new_v4());
+        executor.open_orders.insert(test_inst_binance_eth_usdt_perp(), Uuid::new_v4());
+
+        // Halting trading cancels the open orders of every instrument
+        executor.cancel_all_orders().await.unwrap();
+        assert!(executor.open_orders.is_empty());
+    }
+
     #[test(tokio::test)]
     async fn test_binance_executor() {
         CryptoProvider::install_default(aws_lc_rs::default_provider())
@@ -572,20 +846,26 @@ mod tests {
         let config = load::<PersistenceConfig>();
         let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
 
+        let client = Arc::new(
+            BinanceHttpClient::builder()
+                .credentials(Some(Credentials::from_hmac(
+                    "ppCYOYKlKLRVwGCzmcbXNf2Qn34aeDEN36A4I0Fwdj8WmpvfkxO9cmNIx5PwhmOd",
+                    "cs4wa0w860lgkViblUzua4ThRXpfD22ruG8d0GytU7fIrJCvz8jvCAzKpaKPwTl0",
+                )))
+                .build(),
+        );
         let executor = Arc::new(
             BinanceExecutor::builder()
                 .pubsub(pubsub.clone())
                 .persistence(persistence.clone())
-                .client(Arc::new(
-                    BinanceHttpClient::builder()
-                        .credentials(Some(Credentials::from_hmac(
-                            "ppCYOYKlKLRVwGCzmcbXNf2Qn34aeDEN36A4I0Fwdj8WmpvfkxO9cmNIx5PwhmOd",
-                            "cs4wa0w860lgkViblUzua4ThRXpfD22ruG8d0GytU7fIrJCvz8jvCAzKpaKPwTl0",
-                        )))
-                        .build(),
-                ))
+                .client(client.clone())
+                .adapter(Arc::new(BinanceAdapter::builder().client(client.clone()).build()))
                 .api_key("ppCYOYKlKLRVwGCzmcbXNf2Qn34aeDEN36A4I0Fwdj8WmpvfkxO9cmNIx5PwhmOd".to_string())
                 .no_trade(true)
+                .account_check(BinanceAccountCheckConfig {
+                    enabled: false,
+                    ..Default::default()
+                })
                 .build(),
         );
 
@@ -1,39 +1,104 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Duration};
 
-use arkin_binance::{BinanceHttpClient, Credentials};
-use arkin_core::PubSub;
+use arkin_binance::{BinanceAdapter, BinanceHttpClient, Credentials};
+use arkin_core::{Asset, Leadership, PubSub, VenueAdapter, VenueAdapters};
 use arkin_persistence::PersistenceService;
+use dashmap::DashMap;
+use rust_decimal::Decimal;
 use url::Url;
 
-use crate::{Executor, ExecutorConfig, ExecutorTypeConfig};
+use crate::{Executor, ExecutorConfig, ExecutorTypeConfig, SimulationConfig};
 
-use super::BinanceExecutor;
+use super::{
+    BinanceExecutor, FaultInjector, MarginModel, MultiVenueSimulationExecutor, SimulatedRateLimit, SimulationExecutor,
+};
 
 pub struct ExecutorFactory {}
 
 impl ExecutorFactory {
+    fn simulation(
+        config: &SimulationConfig,
+        pubsub: Arc<PubSub>,
+        venue: Option<String>,
+        balances: Arc<DashMap<Arc<Asset>, Decimal>>,
+    ) -> SimulationExecutor {
+        SimulationExecutor::builder()
+            .pubsub(pubsub)
+            .venue(venue)
+            .taker_commission(config.commission_taker)
+            .maker_commission(config.commission_maker)
+            .partial_fills(config.partial_fills)
+            .queue_position(config.queue_position)
+            .depth_fills(config.depth_fills)
+            .latency(Duration::from_millis(config.latency))
+            .margin(config.margin.as_ref().map(MarginModel::from_config))
+            .min_notional(config.min_order_size_notional)
+            .faults(config.faults.as_ref().map(FaultInjector::from_config))
+            .rate_limit(config.rate_limit.as_ref().map(SimulatedRateLimit::from_config))
+            .balances(balances)
+            .build()
+    }
+
     pub fn from_config(
         config: &ExecutorConfig,
         pubsub: Arc<PubSub>,
         persistence: Arc<PersistenceService>,
+        adapters: &VenueAdapters,
+        leadership: Arc<Leadership>,
     ) -> Arc<dyn Executor> {
         let executor: Arc<dyn Executor> = match &config.executor {
-            // ExecutorTypeConfig::Simulation(_c) => Arc::new(SimulationExecutor::builder().pubsub(pubsub).build()),
-            ExecutorTypeConfig::Binance(c) => Arc::new(
-                BinanceExecutor::builder()
-                    .pubsub(pubsub)
-                    .persistence(persistence)
-                    .client(Arc::new(
-                        BinanceHttpClient::builder()
-                            .base_url(Url::from_str(&c.base_url).expect("Invalid URL for binance http client"))
-                            .credentials(Some(Credentials::from_hmac(c.api_key.clone(), c.api_secret.clone())))
-                            .build(),
-                    ))
-                    .api_key(c.api_key.clone())
-                    .no_trade(c.no_trade)
-                    .build(),
-            ),
-            _ => unimplemented!(),
+            ExecutorTypeConfig::Simulation(c) => Arc::new(Self::simulation(c, pubsub, None, Arc::default())),
+            ExecutorTypeConfig::MultiVenueSimulation(c) => {
+                // The venues share the margin wallet
+                let balances = Arc::new(DashMap::new());
+                let venues = c
+                    .venues
+                    .iter()
+                    .map(|(venue, c)| {
+                        let executor = Self::simulation(c, pubsub.clone(), Some(venue.clone()), balances.clone());
+                        (venue.clone(), Arc::new(executor))
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                Arc::new(MultiVenueSimulationExecutor::builder().venues(venues).build())
+            }
+            ExecutorTypeConfig::Binance(c) => {
+                let client = Arc::new(
+                    BinanceHttpClient::builder()
+                        .base_url(Url::from_str(&c.base_url).expect("Invalid URL for binance http client"))
+                        .credentials(Some(Credentials::from_hmac(c.api_key.clone(), c.api_secret.clone())))
+                        .build(),
+                );
+                // Share the adapter with the ingestors, register one on top of our client if there is none yet
+                let adapter = adapters.get(BinanceAdapter::VENUE).unwrap_or_else(|| {
+                    let adapter: Arc<dyn VenueAdapter> =
+                        Arc::new(BinanceAdapter::builder().client(client.clone()).build());
+                    adapters.insert(adapter.clone());
+                    adapter
+                });
+                Arc::new(
+                    BinanceExecutor::builder()
+                        .pubsub(pubsub)
+                        .persistence(persistence)
+                        .client(client)
+                        .adapter(adapter)
+                        .api_key(c.api_key.clone())
+                        .ws_url(c.ws_url.clone())
+                        .no_trade(c.no_trade)
+                        .dry_run(c.dry_run)
+                        .spot_client(Some(Arc::new(
+                            BinanceHttpClient::builder()
+                                .base_url(
+                                    Url::from_str(&c.account_check.spot_base_url)
+                                        .expect("Invalid URL for binance spot http client"),
+                                )
+                                .credentials(Some(Credentials::from_hmac(c.api_key.clone(), c.api_secret.clone())))
+                                .build(),
+                        )))
+                        .account_check(c.account_check.clone())
+                        .leadership(leadership)
+                        .build(),
+                )
+            }
         };
 
         executor
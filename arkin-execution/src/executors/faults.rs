@@ -0,0 +1,151 @@
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use time::{macros::format_description, Duration, OffsetDateTime, PrimitiveDateTime};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::SimulationFaultsConfig;
+
+/// Venue misbehavior injected into a simulation to test that the order manager and the execution strategies
+/// recover from it. Orders are rejected at random as if the venue was overloaded, cancels take effect without their
+/// acknowledgement, fills of some orders are held back and during downtime windows orders are rejected, cancels fail
+/// and nothing is matched. The faults are drawn from a seeded generator, so a replay injects the same faults on every
+/// run.
+#[derive(Debug, TypedBuilder)]
+pub struct FaultInjector {
+    /// Share of the orders rejected on arrival
+    #[builder(default)]
+    reject_rate: f64,
+    /// Share of the cancels that take effect without publishing the cancelled order
+    #[builder(default)]
+    drop_cancel_ack_rate: f64,
+    /// Share of the orders that fill `fill_delay` after they could first fill
+    #[builder(default)]
+    delay_fill_rate: f64,
+    #[builder(default)]
+    fill_delay: Duration,
+    #[builder(default)]
+    downtime: Vec<(OffsetDateTime, OffsetDateTime)>,
+    #[builder(default)]
+    seed: u64,
+    /// Generator and the number of values drawn from it since it was seeded
+    #[builder(setter(skip), default = Mutex::new((StdRng::seed_from_u64(seed), 0)))]
+    rng: Mutex<(StdRng, u64)>,
+}
+
+impl FaultInjector {
+    pub fn from_config(config: &SimulationFaultsConfig) -> Self {
+        let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+        let parse = |time: &str| {
+            PrimitiveDateTime::parse(time, &format)
+                .expect("Invalid downtime, use YYYY-MM-DD HH:MM")
+                .assume_utc()
+        };
+        Self::builder()
+            .reject_rate(config.reject_rate)
+            .drop_cancel_ack_rate(config.drop_cancel_ack_rate)
+            .delay_fill_rate(config.delay_fill_rate)
+            .fill_delay(Duration::milliseconds(config.fill_delay_ms as i64))
+            .downtime(config.downtime.iter().map(|d| (parse(&d.start), parse(&d.end))).collect())
+            .seed(config.seed)
+            .build()
+    }
+
+    fn chance(&self, rate: f64) -> bool {
+        if rate <= 0. {
+            return false;
+        }
+        let (rng, draws) = &mut *self.rng.lock();
+        *draws += 1;
+        rng.gen::<f64>() < rate
+    }
+
+    /// Position of the generator, so a resumed simulation draws the same faults as the run it resumes.
+    pub fn rng_state(&self, venue: Option<String>) -> RngState {
+        RngState {
+            venue,
+            seed: self.seed,
+            draws: self.rng.lock().1,
+        }
+    }
+
+    /// Seed the generator again and skip the values drawn before the checkpoint, every draw takes one value.
+    pub fn restore_rng(&self, state: &RngState) {
+        let mut rng = StdRng::seed_from_u64(state.seed);
+        for _ in 0..state.draws {
+            rng.next_u64();
+        }
+        *self.rng.lock() = (rng, state.draws);
+    }
+
+    pub fn reject(&self) -> bool {
+        self.chance(self.reject_rate)
+    }
+
+    pub fn drop_cancel_ack(&self) -> bool {
+        self.chance(self.drop_cancel_ack_rate)
+    }
+
+    /// Time the fills of an order that could first fill now are held back until.
+    pub fn fill_after(&self, event_time: OffsetDateTime) -> OffsetDateTime {
+        match self.chance(self.delay_fill_rate) {
+            true => event_time + self.fill_delay,
+            false => event_time,
+        }
+    }
+
+    pub fn is_down(&self, event_time: OffsetDateTime) -> bool {
+        self.downtime
+            .iter()
+            .any(|(start, end)| *start <= event_time && event_time < *end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::SimulationDowntimeConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_faults() {
+        let config = SimulationFaultsConfig {
+            reject_rate: 0.5,
+            drop_cancel_ack_rate: 0.,
+            delay_fill_rate: 1.,
+            fill_delay_ms: 2000,
+            downtime: vec![SimulationDowntimeConfig {
+                start: "2025-01-01 12:00".to_string(),
+                end: "2025-01-01 12:30".to_string(),
+            }],
+            seed: 7,
+        };
+        let faults = FaultInjector::from_config(&config);
+        assert!(!faults.is_down(datetime!(2025-01-01 11:59 UTC)));
+        assert!(faults.is_down(datetime!(2025-01-01 12:00 UTC)));
+        assert!(!faults.is_down(datetime!(2025-01-01 12:30 UTC)));
+        assert!(!faults.drop_cancel_ack());
+        assert_eq!(
+            faults.fill_after(datetime!(2025-01-01 00:00 UTC)),
+            datetime!(2025-01-01 00:00:02 UTC)
+        );
+
+        // The same seed rejects the same orders
+        let rejects = (0..100).map(|_| faults.reject()).collect::<Vec<_>>();
+        let again = FaultInjector::from_config(&config);
+        again.fill_after(datetime!(2025-01-01 00:00 UTC));
+        assert_eq!((0..100).map(|_| again.reject()).collect::<Vec<_>>(), rejects);
+        assert!(rejects.iter().any(|r| *r) && rejects.iter().any(|r| !*r));
+
+        // A generator restored from a checkpoint draws what the original draws next
+        let state = faults.rng_state(None);
+        assert_eq!(state.draws, 101);
+        let restored = FaultInjector::from_config(&config);
+        restored.restore_rng(&state);
+        let next = (0..100).map(|_| faults.reject()).collect::<Vec<_>>();
+        assert_eq!((0..100).map(|_| restored.reject()).collect::<Vec<_>>(), next);
+    }
+}
@@ -0,0 +1,39 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use typed_builder::TypedBuilder;
+
+use crate::SimulationMarginConfig;
+
+/// Margin requirements of a simulated futures account in cross margin mode, like Binance USD-M futures with a single
+/// maintenance tier. Orders need the initial margin of the notional they open, the account is liquidated when its
+/// equity at the mark prices falls to the maintenance margin of the positions.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct MarginModel {
+    #[builder(default = dec!(10))]
+    pub leverage: Decimal,
+    /// Share of the notional the equity has to cover to keep the positions open
+    #[builder(default = dec!(0.004))]
+    pub maintenance_rate: Decimal,
+}
+
+impl MarginModel {
+    pub fn from_config(config: &SimulationMarginConfig) -> Self {
+        Self::builder()
+            .leverage(config.leverage)
+            .maintenance_rate(config.maintenance_rate)
+            .build()
+    }
+
+    pub fn initial_margin(&self, notional: Decimal) -> Decimal {
+        notional.abs() / self.leverage
+    }
+
+    pub fn maintenance_margin(&self, notional: Decimal) -> Decimal {
+        notional.abs() * self.maintenance_rate
+    }
+
+    /// True once the equity no longer covers the maintenance margin.
+    pub fn is_liquidated(&self, equity: Decimal, maintenance_margin: Decimal) -> bool {
+        maintenance_margin > Decimal::ZERO && equity <= maintenance_margin
+    }
+}
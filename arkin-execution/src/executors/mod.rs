@@ -1,7 +1,17 @@
 mod binance;
 mod factory;
-// mod simulation;
+mod faults;
+mod margin;
+mod multi_venue;
+mod order_book;
+mod rate_limit;
+mod simulation;
 
 pub use binance::*;
 pub use factory::ExecutorFactory;
-// pub use simulation::*;
+pub use faults::*;
+pub use margin::*;
+pub use multi_venue::*;
+pub use order_book::*;
+pub use rate_limit::*;
+pub use simulation::*;
@@ -0,0 +1,218 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_trait::async_trait;
+use futures_util::future::try_join_all;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{Executor, ExecutorError};
+
+use super::SimulationExecutor;
+
+/// Simulates several venues in one backtest, one [`SimulationExecutor`] per venue with its own fees, latency and
+/// order book, so cross-exchange strategies like basis trades can be tested. Every executor takes the orders and
+/// market data of the instruments on its venue, the venues share the margin wallet.
+#[derive(Debug, TypedBuilder)]
+pub struct MultiVenueSimulationExecutor {
+    /// Executors by venue name
+    venues: BTreeMap<String, Arc<SimulationExecutor>>,
+}
+
+impl MultiVenueSimulationExecutor {
+    pub fn venue(&self, instrument: &Instrument) -> Result<&Arc<SimulationExecutor>, ExecutorError> {
+        self.venues
+            .values()
+            .find(|e| e.on_venue(instrument))
+            .ok_or_else(|| ExecutorError::InvalidOrder(format!("no simulated venue for {}", instrument.venue)))
+    }
+
+    fn executors(&self) -> impl Iterator<Item = &Arc<SimulationExecutor>> {
+        self.venues.values()
+    }
+}
+
+#[async_trait]
+impl Executor for MultiVenueSimulationExecutor {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), ExecutorError> {
+        info!(
+            "Starting multi-venue simulation for {}...",
+            self.venues.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+        try_join_all(self.executors().map(|e| e.start(shutdown.clone()))).await?;
+        Ok(())
+    }
+
+    async fn get_account(&self) -> Result<(), ExecutorError> {
+        self.get_balances().await?;
+        self.get_positions().await
+    }
+
+    /// The venues share the wallet, any of them publishes it.
+    async fn get_balances(&self) -> Result<(), ExecutorError> {
+        match self.executors().next() {
+            Some(executor) => executor.get_balances().await,
+            None => Ok(()),
+        }
+    }
+
+    async fn get_positions(&self) -> Result<(), ExecutorError> {
+        for executor in self.executors() {
+            executor.get_positions().await?;
+        }
+        Ok(())
+    }
+
+    async fn place_order(&self, order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
+        self.venue(&order.instrument)?.place_order(order).await
+    }
+
+    async fn place_orders(&self, orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
+        for order in orders {
+            self.place_order(order).await?;
+        }
+        Ok(())
+    }
+
+    async fn modify_order(&self, order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
+        self.venue(&order.instrument)?.modify_order(order).await
+    }
+
+    async fn modify_orders(&self, orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
+        for order in orders {
+            self.modify_order(order).await?;
+        }
+        Ok(())
+    }
+
+    async fn cancel_order(&self, id: VenueOrderId) -> Result<(), ExecutorError> {
+        match self.executors().find(|e| e.has_order(&id)) {
+            Some(executor) => executor.cancel_order(id).await,
+            None => Err(ExecutorError::InvalidOrder(id.to_string())),
+        }
+    }
+
+    async fn cancel_orders(&self, ids: Vec<VenueOrderId>) -> Result<(), ExecutorError> {
+        for id in ids {
+            self.cancel_order(id).await?;
+        }
+        Ok(())
+    }
+
+    async fn cancel_orders_by_instrument(&self, instrument: Arc<Instrument>) -> Result<(), ExecutorError> {
+        self.venue(&instrument)?.cancel_orders_by_instrument(instrument).await
+    }
+
+    async fn cancel_all_orders(&self) -> Result<(), ExecutorError> {
+        for executor in self.executors() {
+            executor.cancel_all_orders().await?;
+        }
+        Ok(())
+    }
+
+    /// The venues share the futures wallet, the transfer is booked on it once.
+    async fn transfer(&self, transfer: Arc<AccountTransfer>) -> Result<String, ExecutorError> {
+        match self.executors().next() {
+            Some(executor) => executor.transfer(transfer).await,
+            None => Err(ExecutorError::InvalidTransfer("no simulated venue".into())),
+        }
+    }
+
+    fn checkpoint(&self, checkpoint: &mut SimulationCheckpoint) {
+        for executor in self.executors() {
+            executor.checkpoint(checkpoint);
+        }
+    }
+
+    /// Every venue restores the positions and orders of its instruments.
+    fn restore(
+        &self,
+        checkpoint: &SimulationCheckpoint,
+        portfolio: &Arc<Portfolio>,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<(), ExecutorError> {
+        for executor in self.executors() {
+            executor.restore(checkpoint, portfolio, instruments)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+    use rust_decimal_macros::dec;
+    use test_log::test;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn okx_btc_usdt_perp() -> Arc<Instrument> {
+        let venue = Venue::builder()
+            .id(Uuid::new_v4())
+            .name("okx".into())
+            .venue_type(VenueType::Cex)
+            .build();
+        let mut instrument = (*test_inst_binance_btc_usdt_perp()).clone();
+        instrument.id = Uuid::new_v4();
+        instrument.venue = Arc::new(venue);
+        instrument.symbol = "perp-btc-usdt@okx".into();
+        Arc::new(instrument)
+    }
+
+    fn order(instrument: Arc<Instrument>) -> Arc<VenueOrder> {
+        VenueOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(instrument)
+            .order_type(VenueOrderType::Market)
+            .side(MarketSide::Buy)
+            .price(dec!(0))
+            .quantity(dec!(1))
+            .build()
+            .into()
+    }
+
+    #[test(tokio::test)]
+    async fn test_route_by_venue() {
+        let pubsub = Arc::new(PubSub::new());
+        let wallet = Arc::new(DashMap::from_iter([(test_usdt_asset(), dec!(10000))]));
+        let venue = |name: &str, commission| {
+            Arc::new(
+                SimulationExecutor::builder()
+                    .pubsub(pubsub.clone())
+                    .venue(Some(name.to_string()))
+                    .taker_commission(commission)
+                    .balances(wallet.clone())
+                    .build(),
+            )
+        };
+        let executor = MultiVenueSimulationExecutor::builder()
+            .venues(BTreeMap::from([
+                ("binance".to_string(), venue("binance", dec!(0.0005))),
+                ("okx".to_string(), venue("okx", dec!(0.001))),
+            ]))
+            .build();
+
+        // Every venue fills from its own book and charges its own fees
+        let binance = test_inst_binance_btc_usdt_perp();
+        let okx = okx_btc_usdt_perp();
+        executor.venues["binance"].tick_update(test_tick(binance.clone(), dec!(99), dec!(1), dec!(100), dec!(1)));
+        executor.venues["okx"].tick_update(test_tick(okx.clone(), dec!(101), dec!(1), dec!(102), dec!(1)));
+        executor.place_order(order(binance.clone())).await.unwrap();
+        executor.place_order(order(okx.clone())).await.unwrap();
+
+        let binance_position = executor.venues["binance"].get_position(&binance).unwrap();
+        assert_eq!(binance_position.entry_price, dec!(100));
+        assert!(executor.venues["binance"].get_position(&okx).is_none());
+        assert_eq!(executor.venues["okx"].get_position(&okx).unwrap().entry_price, dec!(102));
+        // Both fees are paid from the shared wallet
+        assert_eq!(wallet.get(&test_usdt_asset()).map(|b| *b), Some(dec!(9999.848)));
+
+        // Orders on venues that are not simulated are refused
+        let mut other = (*okx).clone();
+        other.venue = Arc::new(Venue::builder().name("bybit".into()).venue_type(VenueType::Cex).build());
+        assert!(executor.place_order(order(Arc::new(other))).await.is_err());
+    }
+}
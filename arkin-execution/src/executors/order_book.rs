@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
+
+/// Order book of an instrument rebuilt from the replayed depth updates. Every update sets the quantity of its price
+/// levels and a zero quantity removes the level, like the diff depth stream of Binance.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    pub event_time: Option<OffsetDateTime>,
+    bids: BTreeMap<Price, Quantity>,
+    asks: BTreeMap<Price, Quantity>,
+}
+
+impl OrderBook {
+    pub fn update(&mut self, book: &Book) {
+        let apply = |levels: &mut BTreeMap<Price, Quantity>, updates: &[BookUpdateSide]| {
+            for level in updates {
+                match level.quantity.is_zero() {
+                    true => levels.remove(&level.price),
+                    false => levels.insert(level.price, level.quantity),
+                };
+            }
+        };
+        apply(&mut self.bids, &book.bids);
+        apply(&mut self.asks, &book.asks);
+        self.event_time = Some(book.event_time);
+    }
+
+    /// Book with the given levels, like one taken with [`OrderBook::levels`].
+    pub fn from_levels(bids: &[(Price, Quantity)], asks: &[(Price, Quantity)], event_time: OffsetDateTime) -> Self {
+        Self {
+            event_time: Some(event_time),
+            bids: bids.iter().copied().collect(),
+            asks: asks.iter().copied().collect(),
+        }
+    }
+
+    /// Bid and ask levels, best price first.
+    pub fn levels(&self) -> (Vec<(Price, Quantity)>, Vec<(Price, Quantity)>) {
+        (
+            self.bids.iter().rev().map(|(p, q)| (*p, *q)).collect(),
+            self.asks.iter().map(|(p, q)| (*p, *q)).collect(),
+        )
+    }
+
+    pub fn best_bid(&self) -> Option<(Price, Quantity)> {
+        self.bids.iter().next_back().map(|(p, q)| (*p, *q))
+    }
+
+    pub fn best_ask(&self) -> Option<(Price, Quantity)> {
+        self.asks.iter().next().map(|(p, q)| (*p, *q))
+    }
+
+    /// Levels an order on the given side takes liquidity from, best price first, with the quantity it takes from each
+    /// level. The walk stops once the quantity is reached or the next level is past the limit price.
+    pub fn walk(&self, side: MarketSide, quantity: Quantity, limit: Option<Price>) -> Vec<(Price, Quantity)> {
+        let levels: Box<dyn Iterator<Item = (&Price, &Quantity)>> = match side {
+            MarketSide::Buy => Box::new(self.asks.iter()),
+            MarketSide::Sell => Box::new(self.bids.iter().rev()),
+        };
+        let mut left = quantity;
+        let mut taken = Vec::new();
+        for (price, size) in levels {
+            let within = match (side, limit) {
+                (_, None) => true,
+                (MarketSide::Buy, Some(limit)) => *price <= limit,
+                (MarketSide::Sell, Some(limit)) => *price >= limit,
+            };
+            if left <= Decimal::ZERO || !within {
+                break;
+            }
+            let quantity = left.min(*size);
+            taken.push((*price, quantity));
+            left -= quantity;
+        }
+        taken
+    }
+
+    /// Take the filled quantity off the level an order on the given side traded against, until the next update
+    /// sets the level again.
+    pub fn consume(&mut self, side: MarketSide, price: Price, quantity: Quantity) {
+        let levels = match side {
+            MarketSide::Buy => &mut self.asks,
+            MarketSide::Sell => &mut self.bids,
+        };
+        if let Some(size) = levels.get_mut(&price) {
+            *size -= quantity;
+            if *size <= Decimal::ZERO {
+                levels.remove(&price);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_walk_book() {
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let mut book = OrderBook::default();
+        book.update(&Book::new(
+            OffsetDateTime::now_utc(),
+            instrument.clone(),
+            vec![BookUpdateSide::new(dec!(99), dec!(1)), BookUpdateSide::new(dec!(98), dec!(2))],
+            vec![
+                BookUpdateSide::new(dec!(101), dec!(1)),
+                BookUpdateSide::new(dec!(102), dec!(2)),
+                BookUpdateSide::new(dec!(103), dec!(5)),
+            ],
+        ));
+        assert_eq!(book.best_bid(), Some((dec!(99), dec!(1))));
+        assert_eq!(book.best_ask(), Some((dec!(101), dec!(1))));
+
+        // A buy walks up the asks, a limit stops it at its price
+        assert_eq!(
+            book.walk(MarketSide::Buy, dec!(2.5), None),
+            vec![(dec!(101), dec!(1)), (dec!(102), dec!(1.5))]
+        );
+        assert_eq!(
+            book.walk(MarketSide::Buy, dec!(10), Some(dec!(102))),
+            vec![(dec!(101), dec!(1)), (dec!(102), dec!(2))]
+        );
+        assert_eq!(
+            book.walk(MarketSide::Sell, dec!(2), None),
+            vec![(dec!(99), dec!(1)), (dec!(98), dec!(1))]
+        );
+
+        // Zero quantities remove levels, consumed size is gone until the level is updated again
+        book.update(&Book::new(
+            OffsetDateTime::now_utc(),
+            instrument,
+            vec![BookUpdateSide::new(dec!(99), dec!(0))],
+            vec![BookUpdateSide::new(dec!(102), dec!(4))],
+        ));
+        assert_eq!(book.best_bid(), Some((dec!(98), dec!(2))));
+        book.consume(MarketSide::Buy, dec!(101), dec!(1));
+        assert_eq!(book.best_ask(), Some((dec!(102), dec!(4))));
+    }
+}
@@ -0,0 +1,206 @@
+use parking_lot::Mutex;
+use time::{Duration, OffsetDateTime};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{SimulationRateLimitConfig, SimulationRateLimitMode};
+
+/// Limit of the venue over a fixed window, the windows start on the clock like the minute windows of Binance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimit {
+    pub limit_type: RateLimitType,
+    pub interval: Duration,
+    pub limit: u32,
+}
+
+/// Limit a request ran into and the time the venue has budget for it again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitBreach {
+    pub limit: RequestLimit,
+    /// With delays the time the request goes through, otherwise the time the exceeded window resets
+    pub retry_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Accepted,
+    Delayed(RateLimitBreach),
+    Rejected(RateLimitBreach),
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    window: Option<OffsetDateTime>,
+    used: u32,
+}
+
+/// Request weight and order count limits of the venue counted on the replayed clock. Orders take their weight and
+/// count as an order, cancels only take their weight. A request over a limit is rejected, or with delays held back
+/// until the windows have budget for it again.
+#[derive(Debug, TypedBuilder)]
+pub struct SimulatedRateLimit {
+    limits: Vec<RequestLimit>,
+    #[builder(default = 1)]
+    order_weight: u32,
+    #[builder(default = 1)]
+    cancel_weight: u32,
+    /// Hold requests back until the limits reset instead of rejecting them
+    #[builder(default)]
+    delay: bool,
+    #[builder(default)]
+    usage: Mutex<Vec<Usage>>,
+}
+
+impl SimulatedRateLimit {
+    pub fn from_config(config: &SimulationRateLimitConfig) -> Self {
+        let limits = vec![
+            RequestLimit {
+                limit_type: RateLimitType::RequestWeight,
+                interval: Duration::minutes(1),
+                limit: config.weight_per_minute,
+            },
+            RequestLimit {
+                limit_type: RateLimitType::Orders,
+                interval: Duration::seconds(10),
+                limit: config.orders_per_10s,
+            },
+            RequestLimit {
+                limit_type: RateLimitType::Orders,
+                interval: Duration::minutes(1),
+                limit: config.orders_per_minute,
+            },
+        ];
+        Self::builder()
+            .limits(limits)
+            .order_weight(config.order_weight)
+            .cancel_weight(config.cancel_weight)
+            .delay(config.mode == SimulationRateLimitMode::Delay)
+            .build()
+    }
+
+    fn window_start(event_time: OffsetDateTime, interval: Duration) -> OffsetDateTime {
+        let interval = interval.whole_nanoseconds();
+        let nanos = event_time.unix_timestamp_nanos();
+        OffsetDateTime::from_unix_timestamp_nanos(nanos - nanos.rem_euclid(interval)).expect("Valid window start")
+    }
+
+    /// Budget an order or cancel takes from the limit.
+    fn cost(&self, limit: &RequestLimit, order: bool) -> u32 {
+        match (limit.limit_type, order) {
+            (RateLimitType::RequestWeight, true) => self.order_weight,
+            (RateLimitType::RequestWeight, false) => self.cancel_weight,
+            (RateLimitType::Orders, true) => 1,
+            (RateLimitType::Orders, false) => 0,
+        }
+    }
+
+    /// Take the budget of a request at the given time if every limit has room for it.
+    fn try_acquire(&self, event_time: OffsetDateTime, order: bool) -> Result<(), RateLimitBreach> {
+        let mut usage = self.usage.lock();
+        usage.resize(self.limits.len(), Usage::default());
+        let mut costs = Vec::with_capacity(self.limits.len());
+        for (limit, usage) in self.limits.iter().zip(usage.iter_mut()) {
+            let window = Self::window_start(event_time, limit.interval);
+            if usage.window != Some(window) {
+                *usage = Usage {
+                    window: Some(window),
+                    used: 0,
+                };
+            }
+            let cost = self.cost(limit, order);
+            if usage.used + cost > limit.limit {
+                return Err(RateLimitBreach {
+                    limit: *limit,
+                    retry_at: window + limit.interval,
+                });
+            }
+            costs.push(cost);
+        }
+        for (usage, cost) in usage.iter_mut().zip(costs) {
+            usage.used += cost;
+        }
+        Ok(())
+    }
+
+    /// Whether an order or cancel arriving at the given time is accepted, delayed or rejected.
+    pub fn admit(&self, event_time: OffsetDateTime, order: bool) -> Admission {
+        let mut breach = match self.try_acquire(event_time, order) {
+            Ok(()) => return Admission::Accepted,
+            Err(breach) if !self.delay => return Admission::Rejected(breach),
+            Err(breach) => breach,
+        };
+        // A request the limits never have room for can't be delayed
+        if self.limits.iter().any(|l| self.cost(l, order) > l.limit) {
+            return Admission::Rejected(breach);
+        }
+        while let Err(next) = self.try_acquire(breach.retry_at, order) {
+            breach.retry_at = next.retry_at;
+        }
+        Admission::Delayed(breach)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn rate_limit(delay: bool) -> SimulatedRateLimit {
+        SimulatedRateLimit::builder()
+            .limits(vec![
+                RequestLimit {
+                    limit_type: RateLimitType::RequestWeight,
+                    interval: Duration::minutes(1),
+                    limit: 4,
+                },
+                RequestLimit {
+                    limit_type: RateLimitType::Orders,
+                    interval: Duration::seconds(10),
+                    limit: 2,
+                },
+            ])
+            .delay(delay)
+            .build()
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limiter = rate_limit(false);
+        let now = datetime!(2025-01-01 12:00:01 UTC);
+        assert_eq!(limiter.admit(now, true), Admission::Accepted);
+        assert_eq!(limiter.admit(now, true), Admission::Accepted);
+
+        // The third order in ten seconds is over the order limit, a cancel still has weight left
+        let Admission::Rejected(breach) = limiter.admit(now, true) else {
+            panic!("expected the order to be rejected");
+        };
+        assert_eq!(breach.limit.limit_type, RateLimitType::Orders);
+        assert_eq!(breach.retry_at, datetime!(2025-01-01 12:00:10 UTC));
+        assert_eq!(limiter.admit(now, false), Admission::Accepted);
+        assert_eq!(limiter.admit(now, false), Admission::Accepted);
+        assert!(matches!(
+            limiter.admit(datetime!(2025-01-01 12:00:12 UTC), true),
+            Admission::Rejected(RateLimitBreach {
+                limit: RequestLimit {
+                    limit_type: RateLimitType::RequestWeight,
+                    ..
+                },
+                ..
+            })
+        ));
+        assert_eq!(limiter.admit(datetime!(2025-01-01 12:01:00 UTC), true), Admission::Accepted);
+
+        // With delays the request goes through once every limit has room
+        let limiter = rate_limit(true);
+        for _ in 0..4 {
+            limiter.admit(now, false);
+        }
+        let Admission::Delayed(breach) = limiter.admit(now, true) else {
+            panic!("expected the order to be delayed");
+        };
+        assert_eq!(breach.limit.limit_type, RateLimitType::RequestWeight);
+        assert_eq!(breach.retry_at, datetime!(2025-01-01 12:01:00 UTC));
+    }
+}
@@ -1,152 +1,970 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use time::OffsetDateTime;
 use tokio::select;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use typed_builder::TypedBuilder;
+use uuid::Uuid;
 
 use arkin_core::prelude::*;
-use uuid::Uuid;
 
 use crate::{Executor, ExecutorError};
 
-#[derive(Debug, TypedBuilder)]
+use super::{Admission, FaultInjector, MarginModel, OrderBook, RateLimitBreach, SimulatedRateLimit};
+
+const BPS: Decimal = Decimal::from_parts(10000, 0, 0, false, 0);
+
+/// Execution of an order since it reached the venue.
+#[derive(Debug, Clone)]
+struct OrderExecution {
+    arrived_at: OffsetDateTime,
+    /// Mid price on arrival
+    arrival_price: Option<Price>,
+    /// Quantity of the fills with a mid price to compare them to
+    marked_quantity: Quantity,
+    /// Sum of the quantity times the distance of the fill from the mid, as a fraction of the mid
+    marked_spread: Decimal,
+    /// Sum of the quantity times the commission rate of the fill
+    commission: Decimal,
+    /// Quantity of the fills that added liquidity
+    maker_quantity: Quantity,
+    last_fill_at: Option<OffsetDateTime>,
+}
 
+// Error codes of Binance USD-M futures for the orders the simulation rejects
+const REJECT_DISCONNECTED: i64 = -1001;
+const REJECT_TOO_MANY_REQUESTS: i64 = -1003;
+const REJECT_OVERLOADED: i64 = -1008;
+const REJECT_TOO_MANY_ORDERS: i64 = -1015;
+const REJECT_INSUFFICIENT_MARGIN: i64 = -2019;
+const REJECT_REDUCE_ONLY: i64 = -2022;
+const REJECT_TICK_SIZE: i64 = -4014;
+const REJECT_LOT_SIZE: i64 = -4023;
+const REJECT_MIN_NOTIONAL: i64 = -4164;
+const REJECT_POST_ONLY: i64 = -5022;
+
+/// Executor that matches orders against the replayed market data and keeps the account state like the venue would.
+/// Market orders fill immediately at the touch, limit orders rest until the book or, with partial fills, the replayed
+/// trades cross their price. The optional venue models (latency, margin, depth, rate limits and faults) are off by
+/// default and documented on the methods applying them.
+#[derive(Debug, TypedBuilder)]
 pub struct SimulationExecutor {
     pubsub: Arc<PubSub>,
+    /// Venue the executor simulates, it takes the instruments of all venues without one
+    #[builder(default)]
+    venue: Option<String>,
+    #[builder(default)]
+    partial_fills: bool,
+    /// Fill resting limit orders from the trades once the volume queued ahead of them traded, implies partial fills
+    #[builder(default)]
+    queue_position: bool,
+    /// Fill orders taking liquidity from the replayed depth, orders fill at the touch without a book
+    #[builder(default)]
+    depth_fills: bool,
+    /// Time an order takes to reach the venue
     #[builder(default)]
-    orders: DashMap<VenueOrderId, Arc<VenueOrder>>,
+    latency: Duration,
+    #[builder(default)]
+    margin: Option<MarginModel>,
+    /// Smallest notional of an order, reduce-only orders are exempt like on the venue
+    #[builder(default)]
+    min_notional: Decimal,
+    #[builder(default)]
+    faults: Option<FaultInjector>,
+    #[builder(default)]
+    rate_limit: Option<SimulatedRateLimit>,
     #[builder(default = dec!(0.0005))]
     taker_commission: Decimal,
     #[builder(default = dec!(0.0002))]
     maker_commission: Decimal,
-    #[builder(default = DashMap::new())]
-    balances: DashMap<Arc<Asset>, Balance>,
+    #[builder(default = test_usdt_asset())]
+    margin_asset: Arc<Asset>,
+    #[builder(default = dec!(10000))]
+    initial_balance: Decimal,
+    #[builder(default)]
+    orders: DashMap<VenueOrderId, (i64, VenueOrder)>,
+    /// Volume estimated ahead of the resting limit orders in the queue of their price level
+    #[builder(default)]
+    queues: DashMap<VenueOrderId, Quantity>,
+    /// Time the fault injector holds back the fills of the open orders until
+    #[builder(default)]
+    fills_held: DashMap<VenueOrderId, OffsetDateTime>,
+    /// Execution of the open orders, published as metrics once they leave the book
+    #[builder(default)]
+    executions: DashMap<VenueOrderId, OrderExecution>,
+    #[builder(default)]
+    last_ticks: DashMap<Arc<Instrument>, Arc<Tick>>,
+    #[builder(default)]
+    books: DashMap<Arc<Instrument>, OrderBook>,
+    #[builder(default)]
+    positions: DashMap<Arc<Instrument>, Arc<PositionUpdate>>,
+    /// Margin wallet, shared by the executors of a multi-venue simulation
+    #[builder(default)]
+    balances: Arc<DashMap<Arc<Asset>, Decimal>>,
+    #[builder(default)]
+    order_counter: AtomicI64,
 }
 
 impl SimulationExecutor {
-    pub fn list_open_orders(&self) -> Vec<Arc<VenueOrder>> {
-        self.orders
+    /// Whether the instrument trades on the simulated venue, several executors can simulate one venue each (see
+    /// [`MultiVenueSimulationExecutor`]).
+    pub fn on_venue(&self, instrument: &Instrument) -> bool {
+        match &self.venue {
+            Some(venue) => instrument.venue.name.eq_ignore_ascii_case(venue),
+            None => true,
+        }
+    }
+
+    pub fn has_order(&self, id: &VenueOrderId) -> bool {
+        self.orders.contains_key(id)
+    }
+
+    /// Open orders sorted by arrival, so fills are deterministic.
+    pub fn list_open_orders(&self) -> Vec<(i64, VenueOrder)> {
+        let mut orders = self
+            .orders
+            .iter()
+            .filter(|o| o.value().1.is_active())
+            .map(|o| o.value().clone())
+            .collect::<Vec<_>>();
+        orders.sort_by_key(|(venue_id, _)| *venue_id);
+        orders
+    }
+
+    pub fn get_balance(&self, asset: &Arc<Asset>) -> Decimal {
+        self.balances.get(asset).map(|b| *b.value()).unwrap_or(Decimal::ZERO)
+    }
+
+    pub fn get_position(&self, instrument: &Arc<Instrument>) -> Option<Arc<PositionUpdate>> {
+        self.positions.get(instrument).map(|p| p.value().clone())
+    }
+
+    /// Commission the venue charges for a fill with the given liquidity role.
+    pub fn commission(
+        &self,
+        instrument: &Instrument,
+        price: Price,
+        quantity: Quantity,
+        liquidity: LiquidityRole,
+    ) -> Decimal {
+        let rate = match liquidity {
+            LiquidityRole::Maker => self.maker_commission,
+            LiquidityRole::Taker => self.taker_commission,
+        };
+        (price * quantity * instrument.contract_size * rate).round_dp(instrument.quote_precision)
+    }
+
+    /// Touch price an order would trade at against the given tick, None if the order doesn't cross the book.
+    fn marketable_price(order: &VenueOrder, tick: &Tick) -> Option<Price> {
+        let touch = match order.side {
+            MarketSide::Buy => tick.ask_price(),
+            MarketSide::Sell => tick.bid_price(),
+        };
+        match (order.order_type, order.side) {
+            (VenueOrderType::Market, _) => Some(touch),
+            (VenueOrderType::Limit, MarketSide::Buy) if touch <= order.price => Some(touch),
+            (VenueOrderType::Limit, MarketSide::Sell) if touch >= order.price => Some(touch),
+            _ => None,
+        }
+    }
+
+    /// Size shown at the touch an order trades against.
+    fn touch_size(order: &VenueOrder, tick: &Tick) -> Quantity {
+        match order.side {
+            MarketSide::Buy => tick.ask_quantity,
+            MarketSide::Sell => tick.bid_quantity,
+        }
+    }
+
+    /// Resting limit orders are filled by the replayed trades instead of the ticks.
+    fn fills_from_trades(&self) -> bool {
+        self.partial_fills || self.queue_position
+    }
+
+    /// Volume resting ahead of a limit order at its price, the size shown at the touch on its side of the book. An
+    /// order improving the touch is first in the queue. Levels behind the touch are not replayed, their size is
+    /// estimated with the size at the touch.
+    fn queue_ahead(order: &VenueOrder, tick: &Tick) -> Quantity {
+        let (touch, size) = match order.side {
+            MarketSide::Buy => (tick.bid_price(), tick.bid_quantity),
+            MarketSide::Sell => (tick.ask_price(), tick.ask_quantity),
+        };
+        let improves = match order.side {
+            MarketSide::Buy => order.price > touch,
+            MarketSide::Sell => order.price < touch,
+        };
+        match improves {
+            true => Quantity::ZERO,
+            false => size,
+        }
+    }
+
+    /// Volume left ahead of the order after a trade at or through its price. A trade through the price took the
+    /// whole level, so nothing is left ahead.
+    fn queue_trade(&self, order: &VenueOrder, trade: &Trade) -> Quantity {
+        let through = match order.side {
+            MarketSide::Buy => trade.price < order.price,
+            MarketSide::Sell => trade.price > order.price,
+        };
+        let mut ahead = self.queues.entry(order.id).or_insert(Quantity::ZERO);
+        let passed = match through {
+            true => *ahead,
+            false => (*ahead).min(trade.quantity),
+        };
+        *ahead -= passed;
+        (trade.quantity - passed).max(Quantity::ZERO)
+    }
+
+    /// Levels of the rebuilt book an order taking liquidity trades against, limit orders only up to their price. None
+    /// without depth fills or without depth on the side the order takes from.
+    fn depth(&self, order: &VenueOrder) -> Option<Vec<(Price, Quantity)>> {
+        if !self.depth_fills {
+            return None;
+        }
+        let limit = match order.order_type {
+            VenueOrderType::Limit => Some(order.price),
+            _ => None,
+        };
+        let levels = self
+            .books
+            .get(&order.instrument)?
+            .walk(order.side, order.remaining_quantity(), limit);
+        (!levels.is_empty()).then_some(levels)
+    }
+
+    fn publish_order_update(
+        &self,
+        venue_id: i64,
+        order: &VenueOrder,
+        last_price: Price,
+        last_quantity: Quantity,
+        liquidity: LiquidityRole,
+        commission: Decimal,
+        event_time: OffsetDateTime,
+    ) {
+        let update = VenueOrderUpdate::builder()
+            .event_time(event_time)
+            .portfolio(order.portfolio.clone())
+            .instrument(order.instrument.clone())
+            .order_id(order.id.to_string())
+            .venue_order_id(venue_id)
+            .side(order.side)
+            .order_type(order.order_type)
+            .time_in_force(order.time_in_force)
+            .price(order.price)
+            .quantity(order.quantity)
+            .fill_price(order.fill_price)
+            .fill_quantity(order.filled_quantity)
+            .last_fill_price(last_price)
+            .last_fill_quantity(last_quantity)
+            .status(order.status)
+            .liquidity(liquidity)
+            .commission_asset(Some(self.margin_asset.clone()))
+            .commission(commission)
+            .exchange_time(Some(event_time))
+            .received_at(event_time)
+            .build();
+        self.pubsub.publish::<VenueOrderUpdate>(update.into());
+        if order.status.is_finalized() {
+            self.publish_metrics(order, event_time);
+        }
+    }
+
+    /// Start measuring the execution of an order that reached the venue.
+    fn arrive(&self, order: &VenueOrder, tick: Option<&Tick>, event_time: OffsetDateTime) {
+        let execution = OrderExecution {
+            arrived_at: event_time,
+            arrival_price: tick.map(|t| t.mid_price()),
+            marked_quantity: Quantity::ZERO,
+            marked_spread: Decimal::ZERO,
+            commission: Decimal::ZERO,
+            maker_quantity: Quantity::ZERO,
+            last_fill_at: None,
+        };
+        self.executions.insert(order.id, execution);
+    }
+
+    /// Book a fill of an order against the current mid, with its fee and liquidity role.
+    fn mark_fill(&self, order: &VenueOrder, fill: &VenueOrderFill) {
+        let Some(mut execution) = self.executions.get_mut(&order.id) else {
+            return;
+        };
+        execution.last_fill_at = Some(fill.event_time);
+        execution.commission += fill.quantity * fill.commission_rate();
+        if fill.liquidity == LiquidityRole::Maker {
+            execution.maker_quantity += fill.quantity;
+        }
+        let mid = self.last_ticks.get(&order.instrument).map(|t| t.mid_price());
+        if let Some(mid) = mid.filter(|m| !m.is_zero()) {
+            execution.marked_quantity += fill.quantity;
+            execution.marked_spread += fill.quantity * Decimal::from(order.side) * (fill.price - mid) / mid;
+        }
+    }
+
+    /// Publish the execution quality of an order that left the book.
+    fn publish_metrics(&self, order: &VenueOrder, event_time: OffsetDateTime) {
+        let Some((_, execution)) = self.executions.remove(&order.id) else {
+            return;
+        };
+        let filled = !order.filled_quantity.is_zero();
+        let fill_ratio = match order.quantity.is_zero() {
+            true => Decimal::ZERO,
+            false => order.filled_quantity / order.quantity,
+        };
+        let effective_spread = (!execution.marked_quantity.is_zero())
+            .then(|| Decimal::TWO * execution.marked_spread / execution.marked_quantity * BPS)
+            .and_then(|s| s.to_f64());
+        let shortfall = execution
+            .arrival_price
+            .filter(|p| filled && !p.is_zero())
+            .and_then(|p| (Decimal::from(order.side) * (order.fill_price - p) / p * BPS).to_f64());
+        let commission = filled
+            .then(|| execution.commission / order.filled_quantity * BPS)
+            .and_then(|c| c.to_f64());
+        let maker_ratio = filled
+            .then(|| execution.maker_quantity / order.filled_quantity)
+            .and_then(|r| r.to_f64());
+        let metrics = ExecutionMetrics::builder()
+            .event_time(event_time)
+            .order_id(order.id)
+            .instrument(order.instrument.clone())
+            .side(order.side)
+            .order_type(order.order_type)
+            .status(order.status)
+            .quantity(order.quantity)
+            .filled_quantity(order.filled_quantity)
+            .fill_ratio(fill_ratio.to_f64().unwrap_or_default())
+            .time_to_fill(execution.last_fill_at.map(|t| (t - execution.arrived_at).unsigned_abs()))
+            .arrival_price(execution.arrival_price)
+            .avg_fill_price(filled.then_some(order.fill_price))
+            .effective_spread_bps(effective_spread)
+            .implementation_shortfall_bps(shortfall)
+            .commission_bps(commission)
+            .maker_ratio(maker_ratio)
+            .build();
+        debug!("SimulationExecutor execution metrics: {}", metrics);
+        self.pubsub.publish::<ExecutionMetrics>(metrics.into());
+    }
+
+    fn publish_balance(&self, event_time: OffsetDateTime, portfolio: Arc<Portfolio>) {
+        let update = BalanceUpdate::builder()
+            .event_time(event_time)
+            .portfolio(portfolio)
+            .asset(self.margin_asset.clone())
+            .quantity(self.get_balance(&self.margin_asset))
+            .build();
+        self.pubsub.publish::<BalanceUpdate>(update.into());
+    }
+
+    /// Fill the given quantity of an order and book the result on the position and margin balance. Orders with
+    /// quantity left stay in the book.
+    fn fill(
+        &self,
+        venue_id: i64,
+        mut order: VenueOrder,
+        price: Price,
+        quantity: Quantity,
+        liquidity: LiquidityRole,
+        event_time: OffsetDateTime,
+    ) {
+        // Reduce-only orders never fill past the position, the rest expires once it is closed
+        let quantity = match self.reducible(&order) {
+            Some(reducible) => quantity.min(reducible),
+            None => quantity,
+        };
+        if quantity.is_zero() {
+            return self.expire(venue_id, order, event_time);
+        }
+
+        let instrument = order.instrument.clone();
+        let commission = self.commission(&instrument, price, quantity, liquidity);
+
+        let fill = VenueOrderFill::builder()
+            .event_time(event_time)
+            .venue_order(Arc::new(order.clone()))
+            .instrument(instrument.clone())
+            .side(order.side)
+            .price(price)
+            .quantity(quantity)
+            .liquidity(liquidity)
+            .commission_asset(Some(self.margin_asset.clone()))
+            .commission(commission)
+            .exchange_time(Some(event_time))
+            .received_at(event_time)
+            .build();
+        self.mark_fill(&order, &fill);
+        order.add_fill(Arc::new(fill));
+        info!("SimulationExecutor filled order: {}", order);
+
+        // Update the position, realized pnl is booked on the reducing part of the fill
+        let current = self.get_position(&instrument);
+        let held = current.as_ref().map(|p| p.quantity).unwrap_or(Decimal::ZERO);
+        let entry = current.as_ref().map(|p| p.entry_price).unwrap_or(Decimal::ZERO);
+        let mut realized = current.as_ref().map(|p| p.realized_pnl).unwrap_or(Decimal::ZERO);
+        let traded = quantity * Decimal::from(order.side);
+        let new_quantity = held + traded;
+
+        let mut pnl = Decimal::ZERO;
+        let new_entry = if held.is_zero() {
+            price
+        } else if held.is_sign_positive() == traded.is_sign_positive() {
+            (entry * held.abs() + price * quantity) / new_quantity.abs()
+        } else {
+            let closed = quantity.min(held.abs());
+            pnl = (price - entry) * closed * held.signum() * instrument.contract_size;
+            realized += pnl;
+            if new_quantity.is_zero() {
+                Decimal::ZERO
+            } else if new_quantity.is_sign_positive() == held.is_sign_positive() {
+                entry
+            } else {
+                price
+            }
+        };
+
+        let mark = self.last_ticks.get(&instrument).map(|t| t.mid_price()).unwrap_or(price);
+        let position = PositionUpdate::builder()
+            .event_time(event_time)
+            .portfolio(order.portfolio.clone())
+            .instrument(instrument.clone())
+            .entry_price(new_entry)
+            .quantity(new_quantity)
+            .realized_pnl(realized)
+            .unrealized_pnl((mark - new_entry) * new_quantity * instrument.contract_size)
+            .position_side(match new_quantity < Decimal::ZERO {
+                true => PositionSide::Short,
+                false => PositionSide::Long,
+            })
+            .build();
+        let position = Arc::new(position);
+        self.positions.insert(instrument.clone(), position.clone());
+        *self.balances.entry(self.margin_asset.clone()).or_insert(Decimal::ZERO) += pnl - commission;
+
+        self.publish_order_update(venue_id, &order, price, quantity, liquidity, commission, event_time);
+        self.publish_balance(event_time, order.portfolio.clone());
+        self.pubsub.publish::<PositionUpdate>(position);
+        match order.is_active() {
+            true => {
+                self.orders.insert(order.id, (venue_id, order));
+            }
+            false => {
+                self.orders.remove(&order.id);
+                self.queues.remove(&order.id);
+                self.fills_held.remove(&order.id);
+            }
+        }
+    }
+
+    /// Quantity a reduce-only order may still fill, the position on the other side of the order. None for orders
+    /// that are not reduce-only.
+    fn reducible(&self, order: &VenueOrder) -> Option<Quantity> {
+        if !order.reduce_only {
+            return None;
+        }
+        let held = self.get_position(&order.instrument).map(|p| p.quantity).unwrap_or_default();
+        let reducible = match order.side {
+            MarketSide::Buy => -held,
+            MarketSide::Sell => held,
+        };
+        Some(reducible.max(Quantity::ZERO))
+    }
+
+    /// Mid of the last tick, the entry price if the instrument didn't tick yet.
+    fn mark_price(&self, position: &PositionUpdate) -> Price {
+        self.last_ticks
+            .get(&position.instrument)
+            .map(|t| t.mid_price())
+            .unwrap_or(position.entry_price)
+    }
+
+    /// Margin balance and the unrealized pnl of the positions at the mark prices.
+    fn equity(&self) -> Decimal {
+        let unrealized = self
+            .positions
+            .iter()
+            .map(|p| (self.mark_price(&p) - p.entry_price) * p.quantity * p.instrument.contract_size)
+            .sum::<Decimal>();
+        self.get_balance(&self.margin_asset) + unrealized
+    }
+
+    fn position_notional(&self, position: &PositionUpdate) -> Decimal {
+        self.mark_price(position) * position.quantity.abs() * position.instrument.contract_size
+    }
+
+    /// Notional an order adds to the positions, the part closing the position on the other side needs no margin.
+    fn opening_notional(&self, order: &VenueOrder, price: Price) -> Decimal {
+        let held = self.get_position(&order.instrument).map(|p| p.quantity).unwrap_or_default();
+        let closing = match order.side {
+            MarketSide::Buy => -held,
+            MarketSide::Sell => held,
+        };
+        let opening = (order.remaining_quantity() - closing.max(Quantity::ZERO)).max(Quantity::ZERO);
+        price * opening * order.instrument.contract_size
+    }
+
+    /// Equity left after the initial margin of the positions and the open orders.
+    fn available_margin(&self, margin: &MarginModel) -> Decimal {
+        let positions = self
+            .positions
             .iter()
-            .filter(|order| !order.value().is_active())
-            .map(|order| order.value().clone())
-            .collect()
-    }
-
-    pub fn fill_order(&self, _id: VenueOrderId, _fill: Arc<VenueOrderFill>) {
-        unimplemented!("SimulationExecutor::fill_order")
-        // if let Some(mut order) = self.orders.get_mut(&id) {
-        //     order.add_fill(fill.clone());
-        //     info!("SimulationExecutor filled order: {}", fill);
-        // }
-
-        // // Remove the order if it is filled
-        // let is_finalized = self.orders.get(&id).map(|order| order.is_finalized()).unwrap_or(false);
-        // if is_finalized {
-        //     self.orders.remove(&id);
-        // }
-    }
-
-    pub fn update_balance(&self, asset: &Arc<Asset>, quantity: Decimal) {
-        let mut entry = self.balances.entry(asset.clone()).or_insert(
-            Balance::builder()
-                .id(Uuid::new_v4())
-                .asset(asset.clone())
-                .balance(dec!(0))
-                .build(),
+            .map(|p| margin.initial_margin(self.position_notional(&p)))
+            .sum::<Decimal>();
+        let orders = self
+            .list_open_orders()
+            .iter()
+            .map(|(_, o)| margin.initial_margin(self.opening_notional(o, o.price)))
+            .sum::<Decimal>();
+        self.equity() - positions - orders
+    }
+
+    /// Cancel the open orders and close every position at the touch once the equity no longer covers the
+    /// maintenance margin.
+    fn check_liquidation(&self, event_time: OffsetDateTime) {
+        let Some(margin) = &self.margin else {
+            return;
+        };
+        let equity = self.equity();
+        let maintenance = self
+            .positions
+            .iter()
+            .map(|p| margin.maintenance_margin(self.position_notional(&p)))
+            .sum::<Decimal>();
+        if !margin.is_liquidated(equity, maintenance) {
+            return;
+        }
+        warn!(
+            "SimulationExecutor liquidating account, equity {} below maintenance margin {}",
+            equity, maintenance
         );
-        entry.quantity += quantity;
+
+        for (_, order) in self.list_open_orders() {
+            if let Err(e) = self.cancel(order.id) {
+                warn!(
+                    "SimulationExecutor failed to cancel order {} before liquidation: {}",
+                    order.id, e
+                );
+            }
+        }
+
+        let positions = self.positions.iter().map(|p| p.value().clone()).collect::<Vec<_>>();
+        for position in positions.into_iter().filter(|p| !p.quantity.is_zero()) {
+            let mark = self.mark_price(&position);
+            let side = match position.quantity.is_sign_positive() {
+                true => MarketSide::Sell,
+                false => MarketSide::Buy,
+            };
+            let mut order = VenueOrder::builder()
+                .portfolio(position.portfolio.clone())
+                .instrument(position.instrument.clone())
+                .side(side)
+                .order_type(VenueOrderType::Market)
+                .reduce_only(true)
+                .price(Price::ZERO)
+                .quantity(position.quantity.abs())
+                .created_at(event_time)
+                .updated_at(event_time)
+                .build();
+            let price = self
+                .last_ticks
+                .get(&position.instrument)
+                .and_then(|t| Self::marketable_price(&order, &t))
+                .unwrap_or(mark);
+
+            let venue_id = self.order_counter.fetch_add(1, Ordering::Relaxed) + 1;
+            order.update_status(VenueOrderStatus::Placed);
+            self.publish_order_update(
+                venue_id,
+                &order,
+                Decimal::ZERO,
+                Decimal::ZERO,
+                LiquidityRole::Taker,
+                Decimal::ZERO,
+                event_time,
+            );
+            let quantity = order.quantity;
+            self.fill(venue_id, order, price, quantity, LiquidityRole::Taker, event_time);
+
+            let liquidation = Liquidation::builder()
+                .event_time(event_time)
+                .portfolio(position.portfolio.clone())
+                .instrument(position.instrument.clone())
+                .side(side)
+                .quantity(quantity)
+                .mark_price(mark)
+                .fill_price(price)
+                .equity(equity)
+                .maintenance_margin(maintenance)
+                .build();
+            warn!("SimulationExecutor liquidated position: {}", liquidation);
+            self.pubsub.publish::<Liquidation>(liquidation.into());
+        }
     }
 
-    pub fn get_balance(&self, asset: &Arc<Asset>) -> Option<Balance> {
-        self.balances.get(asset).map(|holding| holding.value().clone())
+    /// True while the fault injector keeps an order from filling, because the venue is down or its fills are
+    /// delayed. Whether the fills of an order are delayed is drawn the first time it could fill.
+    fn fill_held(&self, order: &VenueOrder, event_time: OffsetDateTime) -> bool {
+        let Some(faults) = &self.faults else {
+            return false;
+        };
+        if faults.is_down(event_time) {
+            return true;
+        }
+        let until = *self.fills_held.entry(order.id).or_insert_with(|| faults.fill_after(event_time));
+        event_time < until
+    }
+
+    /// Count a request against the rate limits, returns the time it reaches the venue at or the limit it was rejected
+    /// by. Requests delayed or rejected by a limit publish a [`RateLimitExceeded`].
+    fn rate_limit(
+        &self,
+        order: &VenueOrder,
+        new_order: bool,
+        event_time: OffsetDateTime,
+    ) -> Result<OffsetDateTime, RateLimitBreach> {
+        let Some(rate_limit) = &self.rate_limit else {
+            return Ok(event_time);
+        };
+        let (action, breach) = match rate_limit.admit(event_time, new_order) {
+            Admission::Accepted => return Ok(event_time),
+            Admission::Delayed(breach) => (RateLimitAction::Delayed, breach),
+            Admission::Rejected(breach) => (RateLimitAction::Rejected, breach),
+        };
+        let exceeded = RateLimitExceeded::builder()
+            .event_time(event_time)
+            .instrument(Some(order.instrument.clone()))
+            .order_id(order.id)
+            .limit_type(breach.limit.limit_type)
+            .interval(breach.limit.interval)
+            .limit(breach.limit.limit)
+            .action(action)
+            .retry_at(breach.retry_at)
+            .build();
+        warn!("SimulationExecutor rate limit exceeded: {}", exceeded);
+        self.pubsub.publish::<RateLimitExceeded>(exceeded.into());
+        match action {
+            RateLimitAction::Delayed => Ok(breach.retry_at),
+            RateLimitAction::Rejected => Err(breach),
+        }
+    }
+
+    fn cancel(&self, id: VenueOrderId) -> Result<(), ExecutorError> {
+        let Some((venue_id, mut order)) = self.orders.get(&id).map(|o| o.value().clone()) else {
+            return Err(ExecutorError::InvalidOrder(id.to_string()));
+        };
+        let event_time = self
+            .last_ticks
+            .get(&order.instrument)
+            .map(|t| t.event_time)
+            .unwrap_or(order.updated_at);
+        if self.faults.as_ref().is_some_and(|f| f.is_down(event_time)) {
+            return Err(ExecutorError::NetworkError("simulated venue is down".into()));
+        }
+        let event_time = self
+            .rate_limit(&order, false, event_time)
+            .map_err(|_| ExecutorError::ApiLimitExceeded)?;
+
+        self.orders.remove(&id);
+        self.queues.remove(&id);
+        self.fills_held.remove(&id);
+        order.cancel();
+        if self.faults.as_ref().is_some_and(|f| f.drop_cancel_ack()) {
+            warn!("SimulationExecutor cancelled order {} without acknowledgement", order.id);
+            self.publish_metrics(&order, event_time);
+            return Ok(());
+        }
+        info!("SimulationExecutor cancelled order: {}", order);
+        self.publish_order_update(
+            venue_id,
+            &order,
+            Decimal::ZERO,
+            Decimal::ZERO,
+            LiquidityRole::Taker,
+            Decimal::ZERO,
+            event_time,
+        );
+        Ok(())
+    }
+
+    /// Refuse an order on arrival.
+    fn reject(&self, venue_id: i64, mut order: VenueOrder, code: i64, reason: &str, event_time: OffsetDateTime) {
+        order.update_status(VenueOrderStatus::Rejected);
+        order.updated_at = event_time;
+        warn!("SimulationExecutor rejected order {}: {}", order.id, reason);
+        self.publish_order_update(
+            venue_id,
+            &order,
+            Decimal::ZERO,
+            Decimal::ZERO,
+            LiquidityRole::Taker,
+            Decimal::ZERO,
+            event_time,
+        );
+        let rejected = VenueOrderRejected::builder()
+            .event_time(event_time)
+            .order_id(order.id)
+            .instrument(order.instrument.clone())
+            .code(Some(code))
+            .reason(reason.to_string())
+            .build();
+        self.pubsub.publish::<VenueOrderRejected>(rejected.into());
+    }
+
+    /// Expire the rest of an order that can't trade anymore because of its time in force.
+    fn expire(&self, venue_id: i64, mut order: VenueOrder, event_time: OffsetDateTime) {
+        self.orders.remove(&order.id);
+        self.queues.remove(&order.id);
+        self.fills_held.remove(&order.id);
+        let expired_quantity = order.remaining_quantity();
+        order.expire();
+        order.updated_at = event_time;
+        info!("SimulationExecutor expired order: {}", order);
+        self.publish_order_update(
+            venue_id,
+            &order,
+            Decimal::ZERO,
+            Decimal::ZERO,
+            LiquidityRole::Taker,
+            Decimal::ZERO,
+            event_time,
+        );
+        let expired = VenueOrderExpired::builder()
+            .event_time(event_time)
+            .order_id(order.id)
+            .instrument(order.instrument.clone())
+            .time_in_force(order.time_in_force)
+            .filled_quantity(order.filled_quantity)
+            .expired_quantity(expired_quantity)
+            .build();
+        self.pubsub.publish::<VenueOrderExpired>(expired.into());
+    }
+
+    /// Expire the good-till-date orders the replayed clock passed the expiry of.
+    fn expire_orders(&self, now: OffsetDateTime) {
+        for (venue_id, order) in self.list_open_orders() {
+            if order.is_expired(now) {
+                self.expire(venue_id, order, now);
+            }
+        }
+    }
+
+    /// Fill an immediate-or-cancel or fill-or-kill order from the size shown at the touch, or the rebuilt book with
+    /// depth fills, and expire what is left.
+    fn fill_immediate(&self, venue_id: i64, order: VenueOrder, tick: Option<&Tick>, event_time: OffsetDateTime) {
+        let crosses = tick.and_then(|t| Self::marketable_price(&order, t)).is_some();
+        if let Some(levels) = self.depth(&order).filter(|_| crosses) {
+            let available = levels.iter().map(|(_, q)| *q).sum::<Quantity>();
+            if order.time_in_force == VenueOrderTimeInForce::Fok && available < order.remaining_quantity() {
+                return self.expire(venue_id, order, event_time);
+            }
+            if let Some(order) = self.take_levels(venue_id, order, &levels, event_time) {
+                self.expire(venue_id, order, event_time);
+            }
+            return;
+        }
+        let (price, available) = match tick.and_then(|t| Self::marketable_price(&order, t).map(|p| (p, t))) {
+            Some((price, tick)) => (price, Self::touch_size(&order, tick)),
+            None => (Price::ZERO, Quantity::ZERO),
+        };
+        let quantity = match order.time_in_force {
+            VenueOrderTimeInForce::Fok if available < order.remaining_quantity() => Quantity::ZERO,
+            _ => order.remaining_quantity().min(available),
+        };
+        if quantity.is_zero() {
+            return self.expire(venue_id, order, event_time);
+        }
+        let id = order.id;
+        self.fill(venue_id, order, price, quantity, LiquidityRole::Taker, event_time);
+        if let Some((_, (venue_id, order))) = self.orders.remove(&id) {
+            self.expire(venue_id, order, event_time);
+        }
+    }
+
+    /// Fill an order level by level from the rebuilt book and take the filled size off the book. Returns the order if
+    /// quantity is left once the levels are used up.
+    fn take_levels(
+        &self,
+        venue_id: i64,
+        mut order: VenueOrder,
+        levels: &[(Price, Quantity)],
+        event_time: OffsetDateTime,
+    ) -> Option<VenueOrder> {
+        for (price, size) in levels {
+            let quantity = order.remaining_quantity().min(*size);
+            let quantity = self.reducible(&order).map_or(quantity, |r| quantity.min(r));
+            if quantity <= Decimal::ZERO {
+                break;
+            }
+            if let Some(mut book) = self.books.get_mut(&order.instrument) {
+                book.consume(order.side, *price, quantity);
+            }
+            let id = order.id;
+            self.fill(venue_id, order, *price, quantity, LiquidityRole::Taker, event_time);
+            order = self.orders.remove(&id)?.1;
+        }
+        Some(order)
+    }
+
+    /// Fill an order taking liquidity from the rebuilt book. What the replayed depth can't fill rests at the price of
+    /// a limit order, and fills at the deepest level replayed for a market order, the book isn't known past it.
+    fn fill_depth(&self, venue_id: i64, order: VenueOrder, levels: Vec<(Price, Quantity)>, event_time: OffsetDateTime) {
+        let Some((deepest, _)) = levels.last().copied() else {
+            return;
+        };
+        let Some(order) = self.take_levels(venue_id, order, &levels, event_time) else {
+            return;
+        };
+        match order.order_type {
+            VenueOrderType::Market => {
+                let quantity = order.remaining_quantity();
+                self.fill(venue_id, order, deepest, quantity, LiquidityRole::Taker, event_time)
+            }
+            _ => {
+                info!("SimulationExecutor placed rest of order: {}", order);
+                // The levels up to its price are taken, nothing is queued ahead of the rest
+                if self.queue_position {
+                    self.queues.insert(order.id, Quantity::ZERO);
+                }
+                self.orders.insert(order.id, (venue_id, order));
+            }
+        }
+    }
+
+    fn book_update(&self, book: Arc<Book>) {
+        debug!("SimulationExecutor received book: {}", book.instrument);
+        self.books.entry(book.instrument.clone()).or_default().update(&book);
+    }
+
+    pub(crate) fn tick_update(&self, tick: Arc<Tick>) {
+        debug!("SimulationExecutor received tick: {}", tick.instrument);
+        self.last_ticks.insert(tick.instrument.clone(), tick.clone());
+        self.expire_orders(tick.event_time);
+
+        for (venue_id, order) in self.list_open_orders() {
+            if order.instrument != tick.instrument {
+                continue;
+            }
+            // Orders ahead that got cancelled shrink the queue, the estimate never grows again
+            if self.queue_position && order.order_type == VenueOrderType::Limit {
+                if let Some(mut ahead) = self.queues.get_mut(&order.id) {
+                    *ahead = (*ahead).min(Self::queue_ahead(&order, &tick));
+                }
+            }
+            // Resting limit orders are filled by the trades when partial fills or queue positions are enabled
+            if self.fills_from_trades() && order.order_type == VenueOrderType::Limit {
+                continue;
+            }
+            if let Some(touch) = Self::marketable_price(&order, &tick) {
+                if self.fill_held(&order, tick.event_time) {
+                    continue;
+                }
+                // Resting limit orders provide liquidity at their own price
+                let quantity = order.remaining_quantity();
+                match order.order_type {
+                    VenueOrderType::Limit => {
+                        let price = order.price;
+                        self.fill(venue_id, order, price, quantity, LiquidityRole::Maker, tick.event_time)
+                    }
+                    _ => match self.depth(&order) {
+                        Some(levels) => self.fill_depth(venue_id, order, levels, tick.event_time),
+                        None => self.fill(venue_id, order, touch, quantity, LiquidityRole::Taker, tick.event_time),
+                    },
+                }
+            }
+        }
+        self.check_liquidation(tick.event_time);
+    }
+
+    /// Split the size of a trade over the resting limit orders it trades through, in order of arrival. With queue
+    /// positions only the volume past the queue ahead of an order fills it.
+    fn trade_update(&self, trade: Arc<Trade>) {
+        if !self.fills_from_trades() {
+            return;
+        }
+        debug!("SimulationExecutor received trade: {}", trade.instrument);
+        self.expire_orders(trade.event_time);
+
+        let mut available = trade.quantity;
+        for (venue_id, order) in self.list_open_orders() {
+            if available <= Decimal::ZERO {
+                break;
+            }
+            if order.instrument != trade.instrument || order.order_type != VenueOrderType::Limit {
+                continue;
+            }
+            let crossed = match order.side {
+                MarketSide::Buy => trade.price <= order.price,
+                MarketSide::Sell => trade.price >= order.price,
+            };
+            if !crossed || self.fill_held(&order, trade.event_time) {
+                continue;
+            }
+            let reached = match self.queue_position {
+                true => self.queue_trade(&order, &trade),
+                false => trade.quantity,
+            };
+            let quantity = order.remaining_quantity().min(available).min(reached);
+            if quantity <= Decimal::ZERO {
+                continue;
+            }
+            available -= quantity;
+            let price = order.price;
+            self.fill(venue_id, order, price, quantity, LiquidityRole::Maker, trade.event_time);
+        }
     }
 }
 
 #[async_trait]
 impl Executor for SimulationExecutor {
     async fn start(&self, shutdown: CancellationToken) -> Result<(), ExecutorError> {
-        info!("Starting simulation executor...");
-        // TODO: Send current balance
-        let holding = Arc::new(
-            Balance::builder()
-                .id(Uuid::new_v4())
-                .asset(test_usdt_asset())
-                .balance(dec!(10000))
-                .build(),
+        info!(
+            "Starting simulation executor for {}...",
+            self.venue.as_deref().unwrap_or("all venues")
         );
-        self.update_balance(&holding.asset, holding.quantity);
-        info!("Sending initial balance: {}", holding);
-        self.pubsub.publish::<Balance>(holding);
+        // Executors sharing the wallet fund it once
+        self.balances.entry(self.margin_asset.clone()).or_insert(self.initial_balance);
+        info!("Sending initial balance: {} {}", self.initial_balance, self.margin_asset);
+        // Positions restored from a checkpoint are published too
+        self.get_account().await?;
 
         let mut tick_updates = self.pubsub.subscribe::<Tick>();
+        let mut trade_updates = self.pubsub.subscribe::<Trade>();
+        let mut book_updates = self.pubsub.subscribe::<Book>();
         let mut venue_orders = self.pubsub.subscribe::<VenueOrder>();
         loop {
             select! {
                 Ok(order) = venue_orders.recv() => {
+                    if !self.on_venue(&order.instrument) {
+                        continue;
+                    }
                     info!("SimulationExecutor received order: {}", order);
-                    // Check if the order is valid and we have enough balance
-                    // TODO: Check if the order is valid
-
-                    // Notify the order has been received
-                    self.orders.insert(order.id.clone(), order.clone());
-                    let update = VenueOrderState::builder()
-                        .id(order.id.clone())
-                        .status(VenueOrderStatus::Placed)
-                        .build();
-                    info!("SimulationExecutor placed order: {}", order);
-                    self.pubsub.publish::<VenueOrderState>(update.into());
-
+                    if let Err(e) = self.place_order(order).await {
+                        warn!("SimulationExecutor rejected order: {}", e);
+                    }
                 }
                 Ok(tick) = tick_updates.recv() => {
-                    debug!("SimulationExecutor received tick: {}", tick.instrument);
-                    // Fill the order
-                    let open_orders = self.list_open_orders();
-
-                    // check if we got a price for the instrument
-                    for order in open_orders.iter() {
-                        if order.instrument == tick.instrument {
-                            let price = if order.side == MarketSide::Buy {
-                                tick.ask_price()
-                            } else {
-                                tick.bid_price()
-                            };
-                            let quantity = order.quantity;
-
-                            // Calculate commission
-                            let mut commission = match order.order_type {
-                                VenueOrderType::Market => (price * quantity) * self.taker_commission,
-                                VenueOrderType::Limit => (price * quantity) * self.maker_commission,
-                                _ => unimplemented!("Unsupported order type"),
-                            };
-                            commission = commission.round_dp(order.instrument.price_precision);
-
-                            // Create the fill
-                            let fill = VenueOrderFill::builder()
-                                .venue_order(order.clone())
-                                .instrument(order.instrument.clone())
-                                .side(order.side.clone())
-                                .price(price)
-                                .quantity(order.quantity)
-                                .commission(commission)
-                                .build();
-                            let fill = Arc::new(fill);
-
-
-                            // Subtract the value from the balance
-                            // self.update_balance(&order.instrument.base_asset, fill.market_value() + fill.commission);
-                            self.fill_order(order.id.clone(), fill.clone());
-
-                            // Publish
-                            // info!("Publishing new balance: {}", holding);
-                            // self.pubsub.publish::<Holding>(holding);
-                            info!("SimulationExecutor publishing order filled: {}", order);
-                            self.pubsub.publish::<VenueOrderFill>(fill);
-                        }
+                    if self.on_venue(&tick.instrument) {
+                        self.tick_update(tick);
+                    }
+                }
+                Ok(trade) = trade_updates.recv() => {
+                    if self.on_venue(&trade.instrument) {
+                        self.trade_update(trade);
+                    }
+                }
+                Ok(book) = book_updates.recv() => {
+                    if self.on_venue(&book.instrument) {
+                        self.book_update(book);
                     }
                 }
                 _ = shutdown.cancelled() => {
@@ -157,36 +975,203 @@ impl Executor for SimulationExecutor {
         Ok(())
     }
 
+    async fn get_account(&self) -> Result<(), ExecutorError> {
+        self.get_balances().await?;
+        self.get_positions().await
+    }
+
+    async fn get_balances(&self) -> Result<(), ExecutorError> {
+        self.publish_balance(OffsetDateTime::now_utc(), test_portfolio());
+        Ok(())
+    }
+
+    async fn get_positions(&self) -> Result<(), ExecutorError> {
+        for position in self.positions.iter() {
+            self.pubsub.publish::<PositionUpdate>(position.value().clone());
+        }
+        Ok(())
+    }
+
+    /// The order reaches the venue after the latency and once the rate limits let it through. It is rejected with
+    /// the error code of Binance USD-M futures when it is off the lot or tick size, below the minimum notional, post-only
+    /// and crossing the book, reduce-only without a position to reduce, when the available margin can't cover it or
+    /// when the fault injector takes the venue down or overloads it.
     async fn place_order(&self, order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
-        self.orders.insert(order.id, order.clone());
-        info!("SimulationExecution placed order: {}", order);
+        if !matches!(order.order_type, VenueOrderType::Market | VenueOrderType::Limit) {
+            return Err(ExecutorError::InvalidOrder(format!(
+                "order type {} is not supported in simulation",
+                order.order_type
+            )));
+        }
+        if order.quantity <= Decimal::ZERO {
+            return Err(ExecutorError::InvalidOrder(format!("invalid quantity {}", order.quantity)));
+        }
+        if order.time_in_force == VenueOrderTimeInForce::Gtd && order.expires_at.is_none() {
+            return Err(ExecutorError::InvalidOrder("good till date order without expiry".into()));
+        }
+
+        let venue_id = self.order_counter.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut order = order.as_ref().clone();
+        let tick = self.last_ticks.get(&order.instrument).map(|t| t.value().clone());
+        let event_time = tick.as_ref().map(|t| t.event_time).unwrap_or(order.created_at) + self.latency;
+        let event_time = match self.rate_limit(&order, true, event_time) {
+            Ok(event_time) => event_time,
+            Err(breach) => {
+                let (code, reason) = match breach.limit.limit_type {
+                    RateLimitType::RequestWeight => (REJECT_TOO_MANY_REQUESTS, "too many requests"),
+                    RateLimitType::Orders => (REJECT_TOO_MANY_ORDERS, "too many new orders"),
+                };
+                self.reject(venue_id, order, code, reason, event_time);
+                return Ok(());
+            }
+        };
+
+        if let Some(faults) = &self.faults {
+            if faults.is_down(event_time) {
+                self.reject(venue_id, order, REJECT_DISCONNECTED, "simulated venue is down", event_time);
+                return Ok(());
+            }
+            if faults.reject() {
+                self.reject(venue_id, order, REJECT_OVERLOADED, "simulated venue is overloaded", event_time);
+                return Ok(());
+            }
+        }
+
+        // Filters of the instrument, market orders are valued at the touch
+        let instrument = order.instrument.clone();
+        let price = match order.order_type {
+            VenueOrderType::Market => tick.as_ref().and_then(|t| Self::marketable_price(&order, t)),
+            _ => None,
+        }
+        .unwrap_or(order.price);
+        let notional = price * order.quantity * instrument.contract_size;
+        if !instrument.lot_size.is_zero() && !(order.quantity % instrument.lot_size).is_zero() {
+            let reason = format!(
+                "quantity {} is not a multiple of the lot size {}",
+                order.quantity, instrument.lot_size
+            );
+            self.reject(venue_id, order, REJECT_LOT_SIZE, &reason, event_time);
+            return Ok(());
+        }
+        if order.order_type == VenueOrderType::Limit
+            && !instrument.tick_size.is_zero()
+            && !(order.price % instrument.tick_size).is_zero()
+        {
+            let reason = format!(
+                "price {} is not a multiple of the tick size {}",
+                order.price, instrument.tick_size
+            );
+            self.reject(venue_id, order, REJECT_TICK_SIZE, &reason, event_time);
+            return Ok(());
+        }
+        if !order.reduce_only && notional < self.min_notional {
+            let reason = format!("notional {} is below the minimum notional {}", notional, self.min_notional);
+            self.reject(venue_id, order, REJECT_MIN_NOTIONAL, &reason, event_time);
+            return Ok(());
+        }
+
+        // Post-only orders may only add liquidity
+        let crosses = tick.as_ref().and_then(|t| Self::marketable_price(&order, t)).is_some();
+        if order.time_in_force == VenueOrderTimeInForce::Gtx && crosses {
+            self.reject(
+                venue_id,
+                order,
+                REJECT_POST_ONLY,
+                "post-only order would take liquidity",
+                event_time,
+            );
+            return Ok(());
+        }
+        if let Some(reducible) = self.reducible(&order) {
+            if reducible.is_zero() {
+                let reason = "reduce-only order would increase the position";
+                self.reject(venue_id, order, REJECT_REDUCE_ONLY, reason, event_time);
+                return Ok(());
+            }
+            order.quantity = order.quantity.min(reducible);
+        }
+        if let Some(margin) = &self.margin {
+            let required = margin.initial_margin(self.opening_notional(&order, price));
+            let available = self.available_margin(margin);
+            if required > available {
+                let reason = format!("insufficient margin, requires {} with {} available", required, available);
+                self.reject(venue_id, order, REJECT_INSUFFICIENT_MARGIN, &reason, event_time);
+                return Ok(());
+            }
+        }
+
+        order.update_status(VenueOrderStatus::Placed);
+        self.arrive(&order, tick.as_deref(), event_time);
+        self.publish_order_update(
+            venue_id,
+            &order,
+            Decimal::ZERO,
+            Decimal::ZERO,
+            LiquidityRole::Taker,
+            Decimal::ZERO,
+            event_time,
+        );
+
+        // Orders that may not rest trade against the book on arrival and expire the rest
+        if matches!(order.time_in_force, VenueOrderTimeInForce::Ioc | VenueOrderTimeInForce::Fok) {
+            self.fill_immediate(venue_id, order, tick.as_deref(), event_time);
+            return Ok(());
+        }
+        if order.is_expired(event_time) {
+            self.expire(venue_id, order, event_time);
+            return Ok(());
+        }
+
+        // Orders crossing the book on arrival take liquidity
+        match tick
+            .as_ref()
+            .and_then(|t| Self::marketable_price(&order, t))
+            .filter(|_| !self.fill_held(&order, event_time))
+        {
+            Some(price) => match self.depth(&order) {
+                Some(levels) => self.fill_depth(venue_id, order, levels, event_time),
+                None => {
+                    let quantity = order.remaining_quantity();
+                    self.fill(venue_id, order, price, quantity, LiquidityRole::Taker, event_time)
+                }
+            },
+            None => {
+                info!("SimulationExecutor placed order: {}", order);
+                if self.queue_position && order.order_type == VenueOrderType::Limit {
+                    let ahead = tick.as_ref().map(|t| Self::queue_ahead(&order, t)).unwrap_or_default();
+                    debug!("SimulationExecutor queued order {} behind {}", order.id, ahead);
+                    self.queues.insert(order.id, ahead);
+                }
+                self.orders.insert(order.id, (venue_id, order));
+            }
+        }
         Ok(())
     }
 
     async fn place_orders(&self, orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
         for order in orders {
-            self.orders.insert(order.id, order);
+            self.place_order(order).await?;
         }
         Ok(())
     }
 
-    async fn modify_order(&self, _order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
-        unimplemented!("SimulationExecutor::modify_order")
+    /// Orders can't be modified in simulation, they are cancelled and placed again instead.
+    async fn modify_order(&self, order: Arc<VenueOrder>) -> Result<(), ExecutorError> {
+        Err(ExecutorError::InvalidOrder(format!(
+            "modifying order {} is not supported in simulation",
+            order.id
+        )))
     }
 
-    async fn modify_orders(&self, _orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
-        unimplemented!("SimulationExecutor::modify_orders")
+    async fn modify_orders(&self, orders: Vec<Arc<VenueOrder>>) -> Result<(), ExecutorError> {
+        for order in orders {
+            self.modify_order(order).await?;
+        }
+        Ok(())
     }
 
-    async fn cancel_order(&self, _id: VenueOrderId) -> Result<(), ExecutorError> {
-        unimplemented!("SimulationExecutor::cancel_order")
-        // if let Some(mut order) = self.orders.get_mut(&id) {
-        //     order.cancel();
-        //     info!("SimulationExecution cancelled order: {}", *order);
-        //     Ok(())
-        // } else {
-        //     return Err(ExecutorError::InvalidOrder(id.to_string()));
-        // }
+    async fn cancel_order(&self, id: VenueOrderId) -> Result<(), ExecutorError> {
+        self.cancel(id)
     }
 
     async fn cancel_orders(&self, ids: Vec<VenueOrderId>) -> Result<(), ExecutorError> {
@@ -196,17 +1181,200 @@ impl Executor for SimulationExecutor {
         Ok(())
     }
 
-    async fn cancel_orders_by_instrument(&self, _instrument: Arc<Instrument>) -> Result<(), ExecutorError> {
-        unimplemented!("SimulationExecutor::cancel_orders_by_instrument")
+    async fn cancel_orders_by_instrument(&self, instrument: Arc<Instrument>) -> Result<(), ExecutorError> {
+        let ids = self
+            .list_open_orders()
+            .into_iter()
+            .filter(|(_, o)| o.instrument == instrument)
+            .map(|(_, o)| o.id)
+            .collect();
+        self.cancel_orders(ids).await
     }
 
     async fn cancel_all_orders(&self) -> Result<(), ExecutorError> {
-        unimplemented!("SimulationExecutor::cancel_all_orders")
-        // for mut order in self.orders.iter_mut() {
-        //     order.cancel();
-        //     info!("SimulationExecution cancelled order: {}", *order);
-        // }
-        // Ok(())
+        let ids = self.list_open_orders().into_iter().map(|(_, o)| o.id).collect();
+        self.cancel_orders(ids).await
+    }
+
+    /// Only the futures wallet is simulated, transfers into it add to the margin balance and transfers out of it
+    /// can't take more than the available margin. Transfers between other wallets are accepted without effect.
+    async fn transfer(&self, transfer: Arc<AccountTransfer>) -> Result<String, ExecutorError> {
+        let quantity = transfer.futures_quantity();
+        if quantity.is_zero() {
+            return Ok(transfer.id.to_string());
+        }
+        if transfer.asset != self.margin_asset {
+            return Err(ExecutorError::InvalidTransfer(format!(
+                "the futures wallet only holds {}",
+                self.margin_asset
+            )));
+        }
+        let available = match &self.margin {
+            Some(margin) => self.available_margin(margin),
+            None => self.get_balance(&self.margin_asset),
+        };
+        if -quantity > available {
+            return Err(ExecutorError::InvalidTransfer(format!(
+                "{} exceeds the available margin of {}",
+                transfer.quantity, available
+            )));
+        }
+        *self.balances.entry(self.margin_asset.clone()).or_insert(Decimal::ZERO) += quantity;
+        self.publish_balance(transfer.event_time, transfer.portfolio.clone());
+        Ok(transfer.id.to_string())
+    }
+
+    fn checkpoint(&self, checkpoint: &mut SimulationCheckpoint) {
+        let venue = checkpoint.venue.get_or_insert_with(VenueState::default);
+        venue
+            .positions
+            .extend(self.positions.iter().filter(|p| !p.quantity.is_zero()).map(|p| PositionState {
+                instrument_id: p.instrument.id,
+                side: p.position_side.to_string(),
+                open_price: p.entry_price,
+                quantity: p.quantity,
+                realized_pnl: p.realized_pnl,
+            }));
+        venue
+            .open_orders
+            .extend(self.list_open_orders().into_iter().map(|(_, order)| OpenOrderState {
+                id: order.id,
+                instrument_id: order.instrument.id,
+                side: order.side.to_string(),
+                price: order.price,
+                quantity: order.quantity,
+                filled_quantity: order.filled_quantity,
+            }));
+        venue
+            .order_books
+            .extend(self.books.iter().filter(|b| self.on_venue(b.key())).map(|b| {
+                let (bids, asks) = b.value().levels();
+                OrderBookState {
+                    instrument_id: b.key().id,
+                    bids,
+                    asks,
+                }
+            }));
+        if let Some(faults) = &self.faults {
+            venue.rngs.push(faults.rng_state(self.venue.clone()));
+        }
+        // Executors sharing the wallet write it once
+        for balance in self.balances.iter() {
+            if !venue.balances.iter().any(|b| b.asset == balance.key().symbol) {
+                venue.balances.push(BalanceState {
+                    asset: balance.key().symbol.clone(),
+                    balance: *balance.value(),
+                });
+            }
+        }
+    }
+
+    /// Positions, balances, the rebuilt order books and the fault generator are restored as they were, open orders
+    /// rest again as good-till-cancel limit orders with their remaining quantity. Their place in the queue is lost.
+    fn restore(
+        &self,
+        checkpoint: &SimulationCheckpoint,
+        portfolio: &Arc<Portfolio>,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<(), ExecutorError> {
+        let Some(venue) = &checkpoint.venue else {
+            return Ok(());
+        };
+        let event_time = checkpoint.clock.current_timestamp;
+        let instrument = |id: &Uuid| {
+            instruments
+                .iter()
+                .find(|i| i.id == *id)
+                .ok_or_else(|| ExecutorError::InvalidOrder(format!("instrument {} of the checkpoint not loaded", id)))
+        };
+
+        for balance in &venue.balances {
+            match balance.asset == self.margin_asset.symbol {
+                true => {
+                    self.balances.insert(self.margin_asset.clone(), balance.balance);
+                }
+                false => warn!(
+                    "Only the {} wallet is simulated, ignoring {} balance",
+                    self.margin_asset, balance.asset
+                ),
+            }
+        }
+
+        for state in &venue.positions {
+            let instrument = instrument(&state.instrument_id)?;
+            if !self.on_venue(instrument) {
+                continue;
+            }
+            let position = PositionUpdate::builder()
+                .event_time(event_time)
+                .portfolio(portfolio.clone())
+                .instrument(instrument.clone())
+                .entry_price(state.open_price)
+                .quantity(state.quantity)
+                .realized_pnl(state.realized_pnl)
+                .unrealized_pnl(Decimal::ZERO)
+                .position_side(match state.quantity < Decimal::ZERO {
+                    true => PositionSide::Short,
+                    false => PositionSide::Long,
+                })
+                .build();
+            self.positions.insert(instrument.clone(), Arc::new(position));
+        }
+
+        for state in &venue.open_orders {
+            let instrument = instrument(&state.instrument_id)?;
+            if !self.on_venue(instrument) {
+                continue;
+            }
+            let side = match state.side.as_str() {
+                "buy" => MarketSide::Buy,
+                "sell" => MarketSide::Sell,
+                side => return Err(ExecutorError::InvalidOrder(format!("order {} has side {}", state.id, side))),
+            };
+            let order = VenueOrder::builder()
+                .id(state.id)
+                .portfolio(portfolio.clone())
+                .instrument(instrument.clone())
+                .side(side)
+                .order_type(VenueOrderType::Limit)
+                .price(state.price)
+                .quantity(state.quantity)
+                .fill_price(state.price)
+                .filled_quantity(state.filled_quantity)
+                .status(match state.filled_quantity.is_zero() {
+                    true => VenueOrderStatus::Placed,
+                    false => VenueOrderStatus::PartiallyFilled,
+                })
+                .created_at(event_time)
+                .updated_at(event_time)
+                .build();
+            let venue_id = self.order_counter.fetch_add(1, Ordering::Relaxed) + 1;
+            self.orders.insert(order.id, (venue_id, order));
+        }
+
+        for state in &venue.order_books {
+            let instrument = instrument(&state.instrument_id)?;
+            if !self.on_venue(instrument) {
+                continue;
+            }
+            let book = OrderBook::from_levels(&state.bids, &state.asks, event_time);
+            self.books.insert(instrument.clone(), book);
+        }
+
+        if let Some(faults) = &self.faults {
+            match venue.rngs.iter().find(|r| r.venue == self.venue) {
+                Some(state) => faults.restore_rng(state),
+                None => warn!("SimulationExecutor has no fault generator in the checkpoint, it draws from the seed"),
+            }
+        }
+
+        info!(
+            "SimulationExecutor restored {} positions and {} open orders at {}",
+            self.positions.len(),
+            self.orders.len(),
+            event_time
+        );
+        Ok(())
     }
 }
 
@@ -214,72 +1382,783 @@ impl Executor for SimulationExecutor {
 mod tests {
     use super::*;
 
-    use rust_decimal_macros::dec;
     use test_log::test;
+
+    use crate::SimulationRateLimitConfig;
     use tokio_util::task::TaskTracker;
 
+    fn tick(bid: Decimal, ask: Decimal) -> Arc<Tick> {
+        test_tick(test_inst_binance_btc_usdt_perp(), bid, dec!(1), ask, dec!(1))
+    }
+
+    fn order(side: MarketSide, order_type: VenueOrderType, price: Decimal, quantity: Decimal) -> Arc<VenueOrder> {
+        VenueOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_type(order_type)
+            .side(side)
+            .price(price)
+            .quantity(quantity)
+            .build()
+            .into()
+    }
+
     #[test(tokio::test)]
-    async fn test_backtest_executor_place_order() {
-        // Create executor
+    async fn test_market_order_fills_at_touch() {
         let pubsub = Arc::new(PubSub::new());
         let executor = Arc::new(SimulationExecutor::builder().pubsub(pubsub.clone()).build());
 
-        // Start executor
         let tracker = TaskTracker::new();
         let shutdown = CancellationToken::new();
         let shutdown_clone = shutdown.clone();
+        let executor_clone = executor.clone();
         tracker.spawn(async move {
-            executor.start(shutdown_clone).await.unwrap();
+            executor_clone.start(shutdown_clone).await.unwrap();
         });
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-        // Wait for executor to start
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let mut updates = pubsub.subscribe::<VenueOrderUpdate>();
+        let mut positions = pubsub.subscribe::<PositionUpdate>();
+        pubsub.publish::<Tick>(tick(dec!(50000), dec!(50001)));
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        pubsub.publish::<VenueOrder>(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(0.1)));
 
-        // Create a sample VenueOrder
-        let order: Arc<VenueOrder> = VenueOrder::builder()
-            .id(Uuid::new_v4())
-            .portfolio(test_portfolio())
-            .instrument(test_inst_binance_btc_usdt_perp())
-            .order_type(VenueOrderType::Market)
-            .side(MarketSide::Buy)
-            .price(None)
-            .quantity(dec!(0.1))
-            .build()
-            .into();
+        let ack = updates.recv().await.unwrap();
+        assert_eq!(ack.status, VenueOrderStatus::Placed);
+        let fill = updates.recv().await.unwrap();
+        assert_eq!(fill.status, VenueOrderStatus::Filled);
+        assert_eq!(fill.last_fill_price, dec!(50001));
+        assert_eq!(fill.last_fill_quantity, dec!(0.1));
+        assert_eq!(fill.commission, dec!(2.50005));
+        assert_eq!(fill.liquidity, LiquidityRole::Taker);
+        assert_eq!(fill.commission_asset, Some(test_usdt_asset()));
 
-        // Subscribe to fill and updates
-        let mut updates = pubsub.subscribe::<VenueOrderState>();
-        let mut fills = pubsub.subscribe::<VenueOrderFill>();
+        let position = positions.recv().await.unwrap();
+        assert_eq!(position.quantity, dec!(0.1));
+        assert_eq!(position.entry_price, dec!(50001));
+        assert_eq!(executor.get_balance(&test_usdt_asset()), dec!(10000) - dec!(2.50005));
 
-        // Publish the order
-        info!("Publishing order: {:?}", order);
-        pubsub.publish::<VenueOrder>(order.clone());
+        shutdown.cancel();
+        tracker.close();
+        tracker.wait().await;
+    }
 
-        // Check for ack
-        let ack = updates.recv().await.unwrap();
-        assert_eq!(ack.status, VenueOrderStatus::Placed);
+    #[test(tokio::test)]
+    async fn test_limit_order_rests_until_crossed() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder().pubsub(pubsub.clone()).build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+
+        executor.tick_update(tick(dec!(50000), dec!(50001)));
+        executor
+            .place_order(order(MarketSide::Sell, VenueOrderType::Limit, dec!(50100), dec!(0.2)))
+            .await
+            .unwrap();
+        assert_eq!(executor.list_open_orders().len(), 1);
+        assert!(executor.get_position(&instrument).is_none());
+
+        // Modifying is refused, the order keeps resting as it was
+        let (_, resting) = executor.list_open_orders().remove(0);
+        let res = executor.modify_order(resting.into()).await;
+        assert!(matches!(res, Err(ExecutorError::InvalidOrder(_))));
+        assert_eq!(executor.list_open_orders().len(), 1);
+
+        executor.tick_update(tick(dec!(50150), dec!(50151)));
+        assert!(executor.list_open_orders().is_empty());
+        let position = executor.get_position(&instrument).unwrap();
+        assert_eq!(position.quantity, dec!(-0.2));
+        assert_eq!(position.entry_price, dec!(50100));
+        assert_eq!(position.position_side, PositionSide::Short);
+    }
+
+    #[test(tokio::test)]
+    async fn test_partial_fills_from_trades() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .partial_fills(true)
+            .maker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let trade = |price: Decimal, quantity: Decimal| {
+            Arc::new(Trade::new(
+                OffsetDateTime::now_utc(),
+                instrument.clone(),
+                1,
+                MarketSide::Sell,
+                price,
+                quantity,
+            ))
+        };
+
+        executor.tick_update(tick(dec!(100), dec!(101)));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(99), dec!(1)))
+            .await
+            .unwrap();
+
+        // Trades above the limit and crossing ticks don't fill
+        executor.trade_update(trade(dec!(100), dec!(5)));
+        executor.tick_update(tick(dec!(98), dec!(99)));
+        assert_eq!(executor.list_open_orders()[0].1.filled_quantity, Decimal::ZERO);
+
+        executor.trade_update(trade(dec!(99), dec!(0.4)));
+        let (_, open) = &executor.list_open_orders()[0];
+        assert_eq!(open.status, VenueOrderStatus::PartiallyFilled);
+        assert_eq!(open.remaining_quantity(), dec!(0.6));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(0.4));
 
-        // Send price update
-        let tick = Arc::new(
+        executor.trade_update(trade(dec!(98), dec!(2)));
+        assert!(executor.list_open_orders().is_empty());
+        let position = executor.get_position(&instrument).unwrap();
+        assert_eq!(position.quantity, dec!(1));
+        assert_eq!(position.entry_price, dec!(99));
+
+        // Cancelling a partially filled order keeps the filled part
+        let sell = order(MarketSide::Sell, VenueOrderType::Limit, dec!(105), dec!(1));
+        executor.place_order(sell.clone()).await.unwrap();
+        executor.trade_update(trade(dec!(105), dec!(0.25)));
+        let mut updates = pubsub.subscribe::<VenueOrderUpdate>();
+        executor.cancel_order(sell.id).await.unwrap();
+        let cancelled = updates.recv().await.unwrap();
+        assert_eq!(cancelled.status, VenueOrderStatus::PartiallyFilledCanceled);
+        assert_eq!(cancelled.fill_quantity, dec!(0.25));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(0.75));
+    }
+
+    #[test(tokio::test)]
+    async fn test_queue_position() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .queue_position(true)
+            .maker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let trade = |price: Decimal, quantity: Decimal| {
+            Arc::new(Trade::new(
+                OffsetDateTime::now_utc(),
+                instrument.clone(),
+                1,
+                MarketSide::Sell,
+                price,
+                quantity,
+            ))
+        };
+
+        // Joining the bid queues behind the 3 shown at the level
+        executor.tick_update(test_tick(instrument.clone(), dec!(100), dec!(3), dec!(101), dec!(2)));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(100), dec!(1)))
+            .await
+            .unwrap();
+        executor.trade_update(trade(dec!(100), dec!(2)));
+        assert_eq!(executor.list_open_orders()[0].1.filled_quantity, Decimal::ZERO);
+
+        // Cancels ahead shrink the queue to the size shown, the volume past it fills the order
+        executor.tick_update(test_tick(instrument.clone(), dec!(100), dec!(0.5), dec!(101), dec!(2)));
+        executor.trade_update(trade(dec!(100), dec!(0.75)));
+        let (_, open) = &executor.list_open_orders()[0];
+        assert_eq!(open.filled_quantity, dec!(0.25));
+
+        // A trade through the price took the whole level
+        executor.trade_update(trade(dec!(99), dec!(5)));
+        assert!(executor.list_open_orders().is_empty());
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(1));
+
+        // Improving the touch puts the order first in the queue
+        executor
+            .place_order(order(MarketSide::Sell, VenueOrderType::Limit, dec!(100.5), dec!(1)))
+            .await
+            .unwrap();
+        executor.trade_update(trade(dec!(100.5), dec!(1)));
+        assert!(executor.list_open_orders().is_empty());
+        assert!(executor.queues.is_empty());
+    }
+
+    #[test]
+    fn test_commission_by_liquidity_role() {
+        let executor = SimulationExecutor::builder().pubsub(Arc::new(PubSub::new())).build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+
+        let maker = executor.commission(&instrument, dec!(50000), dec!(1), LiquidityRole::Maker);
+        let taker = executor.commission(&instrument, dec!(50000), dec!(1), LiquidityRole::Taker);
+        assert_eq!(maker, dec!(10));
+        assert_eq!(taker, dec!(25));
+    }
+
+    #[test(tokio::test)]
+    async fn test_closing_fill_realizes_pnl() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .taker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+
+        executor.tick_update(tick(dec!(100), dec!(101)));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(2)))
+            .await
+            .unwrap();
+        executor.tick_update(tick(dec!(110), dec!(111)));
+        executor
+            .place_order(order(MarketSide::Sell, VenueOrderType::Market, Decimal::ZERO, dec!(3)))
+            .await
+            .unwrap();
+
+        let position = executor.get_position(&instrument).unwrap();
+        assert_eq!(position.quantity, dec!(-1));
+        assert_eq!(position.entry_price, dec!(110));
+        assert_eq!(position.realized_pnl, dec!(18));
+        assert_eq!(executor.get_balance(&test_usdt_asset()), dec!(18));
+    }
+
+    #[test(tokio::test)]
+    async fn test_time_in_force() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .taker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let with_tif = |order: Arc<VenueOrder>, tif: VenueOrderTimeInForce| {
+            let mut order = order.as_ref().clone();
+            order.time_in_force = tif;
+            Arc::new(order)
+        };
+        let mut updates = pubsub.subscribe::<VenueOrderUpdate>();
+        let mut expired = pubsub.subscribe::<VenueOrderExpired>();
+
+        // Immediate or cancel fills the size at the touch and expires the rest
+        executor.tick_update(tick(dec!(100), dec!(101)));
+        let ioc = with_tif(
+            order(MarketSide::Buy, VenueOrderType::Limit, dec!(101), dec!(3)),
+            VenueOrderTimeInForce::Ioc,
+        );
+        executor.place_order(ioc).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Placed);
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::PartiallyFilled);
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::PartiallyFilledExpired);
+        let event = expired.recv().await.unwrap();
+        assert_eq!(event.filled_quantity, dec!(1));
+        assert_eq!(event.expired_quantity, dec!(2));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(1));
+        assert!(executor.list_open_orders().is_empty());
+
+        // Fill or kill doesn't fill when the touch is too small
+        let fok = with_tif(
+            order(MarketSide::Sell, VenueOrderType::Market, Decimal::ZERO, dec!(2)),
+            VenueOrderTimeInForce::Fok,
+        );
+        executor.place_order(fok).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Placed);
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Expired);
+        assert_eq!(expired.recv().await.unwrap().expired_quantity, dec!(2));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(1));
+
+        // Good till date rests until the replayed clock passes the expiry
+        let now = OffsetDateTime::now_utc();
+        let mut gtd = order(MarketSide::Sell, VenueOrderType::Limit, dec!(110), dec!(1))
+            .as_ref()
+            .clone();
+        gtd.time_in_force = VenueOrderTimeInForce::Gtd;
+        gtd.expires_at = Some(now + time::Duration::minutes(5));
+        executor.place_order(Arc::new(gtd)).await.unwrap();
+        assert_eq!(executor.list_open_orders().len(), 1);
+        let later = |minutes: i64| {
             Tick::builder()
-                .instrument(test_inst_binance_btc_usdt_perp())
-                .tick_id(0 as u64)
-                .bid_price(dec!(50000))
+                .event_time(now + time::Duration::minutes(minutes))
+                .instrument(instrument.clone())
+                .tick_id(1)
+                .bid_price(dec!(100))
                 .bid_quantity(dec!(1))
-                .ask_price(dec!(50001))
+                .ask_price(dec!(101))
                 .ask_quantity(dec!(1))
-                .build(),
+                .build()
+        };
+        executor.tick_update(Arc::new(later(4)));
+        assert_eq!(executor.list_open_orders().len(), 1);
+        executor.tick_update(Arc::new(later(5)));
+        assert!(executor.list_open_orders().is_empty());
+        let event = expired.recv().await.unwrap();
+        assert_eq!(event.time_in_force, VenueOrderTimeInForce::Gtd);
+        assert_eq!(event.expired_quantity, dec!(1));
+
+        // Good till date needs an expiry
+        let gtd = with_tif(
+            order(MarketSide::Sell, VenueOrderType::Limit, dec!(110), dec!(1)),
+            VenueOrderTimeInForce::Gtd,
         );
-        pubsub.publish::<Tick>(tick);
+        assert!(executor.place_order(gtd).await.is_err());
+    }
+
+    #[test(tokio::test)]
+    async fn test_post_only_and_reduce_only() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .taker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let mut updates = pubsub.subscribe::<VenueOrderUpdate>();
+        let mut rejected = pubsub.subscribe::<VenueOrderRejected>();
+        executor.tick_update(tick(dec!(100), dec!(101)));
 
-        // Check for fill
-        let fill = fills.recv().await.unwrap();
-        assert_eq!(fill.price, Decimal::from_f64(50001.).unwrap());
-        assert_eq!(fill.quantity, Decimal::from_f64(0.1).unwrap());
+        // Post-only crossing the spread is rejected, resting on the book it is accepted
+        let mut post_only = order(MarketSide::Buy, VenueOrderType::Limit, dec!(101), dec!(1))
+            .as_ref()
+            .clone();
+        post_only.time_in_force = VenueOrderTimeInForce::Gtx;
+        executor.place_order(Arc::new(post_only.clone())).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Rejected);
+        assert_eq!(rejected.recv().await.unwrap().order_id, post_only.id);
+        assert!(executor.get_position(&instrument).is_none());
+        post_only.id = VenueOrderId::new_v4();
+        post_only.price = dec!(100);
+        executor.place_order(Arc::new(post_only)).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Placed);
+        assert_eq!(executor.list_open_orders().len(), 1);
+        executor.cancel_all_orders().await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Canceled);
 
-        // Shutdown
-        shutdown.cancel();
-        tracker.close();
-        tracker.wait().await;
+        // Reduce-only without a position is rejected
+        let reduce_only = |side: MarketSide, quantity: Decimal| {
+            let mut order = order(side, VenueOrderType::Market, Decimal::ZERO, quantity).as_ref().clone();
+            order.reduce_only = true;
+            Arc::new(order)
+        };
+        executor.place_order(reduce_only(MarketSide::Sell, dec!(1))).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Rejected);
+        rejected.recv().await.unwrap();
+
+        // Reduce-only is clamped to the position and never flips it
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(1)))
+            .await
+            .unwrap();
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(1));
+        executor.place_order(reduce_only(MarketSide::Sell, dec!(3))).await.unwrap();
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, Decimal::ZERO);
+        executor.place_order(reduce_only(MarketSide::Buy, dec!(1))).await.unwrap();
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, Decimal::ZERO);
+    }
+
+    #[test(tokio::test)]
+    async fn test_margin_and_liquidation() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .taker_commission(Decimal::ZERO)
+            .margin(Some(MarginModel::builder().build()))
+            .build();
+        executor.balances.insert(test_usdt_asset(), dec!(100));
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let mut rejected = pubsub.subscribe::<VenueOrderRejected>();
+        let mut liquidations = pubsub.subscribe::<Liquidation>();
+        executor.tick_update(tick(dec!(100), dec!(101)));
+
+        // At 10x leverage 100 USDT margin can't open 1010 USDT
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(10)))
+            .await
+            .unwrap();
+        assert!(rejected.recv().await.unwrap().reason.starts_with("insufficient margin"));
+        assert!(executor.get_position(&instrument).is_none());
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(9)))
+            .await
+            .unwrap();
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(9));
+
+        // Resting orders hold margin too
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(80), dec!(1)))
+            .await
+            .unwrap();
+        rejected.recv().await.unwrap();
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(80), dec!(0.5)))
+            .await
+            .unwrap();
+        assert_eq!(executor.list_open_orders().len(), 1);
+
+        // Equity above the maintenance margin keeps the position open
+        executor.tick_update(tick(dec!(95), dec!(96)));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(9));
+
+        // Below it the open orders are cancelled and the position is closed at the bid
+        executor.tick_update(tick(dec!(89), dec!(90)));
+        let liquidation = liquidations.recv().await.unwrap();
+        assert_eq!(liquidation.side, MarketSide::Sell);
+        assert_eq!(liquidation.quantity, dec!(9));
+        assert_eq!(liquidation.fill_price, dec!(89));
+        assert!(liquidation.equity <= liquidation.maintenance_margin);
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, Decimal::ZERO);
+        assert!(executor.list_open_orders().is_empty());
+        assert_eq!(executor.get_balance(&test_usdt_asset()), dec!(-8));
+    }
+
+    #[test(tokio::test)]
+    async fn test_transfers() {
+        let executor = SimulationExecutor::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .margin(Some(MarginModel::builder().build()))
+            .build();
+        executor.balances.insert(test_usdt_asset(), dec!(100));
+        let transfer = |from: AccountType, to: AccountType, quantity: Decimal| {
+            Arc::new(
+                AccountTransfer::builder()
+                    .event_time(OffsetDateTime::now_utc())
+                    .portfolio(test_portfolio())
+                    .asset(test_usdt_asset())
+                    .quantity(quantity)
+                    .from(from)
+                    .to(to)
+                    .requested_by("test".to_string())
+                    .build(),
+            )
+        };
+
+        executor
+            .transfer(transfer(AccountType::Spot, AccountType::UsdmFutures, dec!(50)))
+            .await
+            .unwrap();
+        assert_eq!(executor.get_balance(&test_usdt_asset()), dec!(150));
+
+        // The margin held by a position can't be transferred out
+        executor.tick_update(tick(dec!(100), dec!(101)));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(10)))
+            .await
+            .unwrap();
+        let res = executor
+            .transfer(transfer(AccountType::UsdmFutures, AccountType::Spot, dec!(100)))
+            .await;
+        assert!(matches!(res, Err(ExecutorError::InvalidTransfer(_))));
+        executor
+            .transfer(transfer(AccountType::UsdmFutures, AccountType::Spot, dec!(20)))
+            .await
+            .unwrap();
+        assert!(executor.get_balance(&test_usdt_asset()) < dec!(130));
+    }
+
+    #[test(tokio::test)]
+    async fn test_order_filters() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .min_notional(dec!(5))
+            .build();
+        let mut rejected = pubsub.subscribe::<VenueOrderRejected>();
+        executor.tick_update(tick(dec!(100), dec!(101)));
+
+        // Lot size of 0.001 and tick size of 0.1
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(99), dec!(0.0505)))
+            .await
+            .unwrap();
+        assert_eq!(rejected.recv().await.unwrap().code, Some(-4023));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(99.05), dec!(0.1)))
+            .await
+            .unwrap();
+        assert_eq!(rejected.recv().await.unwrap().code, Some(-4014));
+
+        // Market orders are valued at the touch, 0.04 at 101 is below 5
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(0.04)))
+            .await
+            .unwrap();
+        let event = rejected.recv().await.unwrap();
+        assert_eq!(event.code, Some(-4164));
+        assert!(event.reason.contains("below the minimum notional 5"));
+
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(99.1), dec!(0.051)))
+            .await
+            .unwrap();
+        assert_eq!(executor.list_open_orders().len(), 1);
+        assert!(rejected.try_recv().is_err());
+    }
+
+    #[test(tokio::test)]
+    async fn test_rate_limit() {
+        let pubsub = Arc::new(PubSub::new());
+        let config = SimulationRateLimitConfig {
+            weight_per_minute: 3,
+            orders_per_10s: 2,
+            ..Default::default()
+        };
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .rate_limit(Some(SimulatedRateLimit::from_config(&config)))
+            .build();
+        let mut rejected = pubsub.subscribe::<VenueOrderRejected>();
+        let mut exceeded = pubsub.subscribe::<RateLimitExceeded>();
+        executor.tick_update(tick(dec!(100), dec!(101)));
+
+        // The third order within ten seconds is over the order limit
+        let orders = (0..3)
+            .map(|_| order(MarketSide::Buy, VenueOrderType::Limit, dec!(99), dec!(1)))
+            .collect::<Vec<_>>();
+        executor.place_orders(orders.clone()).await.unwrap();
+        assert_eq!(executor.list_open_orders().len(), 2);
+        assert_eq!(rejected.recv().await.unwrap().code, Some(REJECT_TOO_MANY_ORDERS));
+        let event = exceeded.recv().await.unwrap();
+        assert_eq!(event.limit_type, RateLimitType::Orders);
+        assert_eq!(event.action, RateLimitAction::Rejected);
+
+        // Cancels take request weight, the second one is over the weight limit and the order stays open
+        executor.cancel_order(orders[0].id).await.unwrap();
+        assert!(matches!(
+            executor.cancel_order(orders[1].id).await,
+            Err(ExecutorError::ApiLimitExceeded)
+        ));
+        assert_eq!(exceeded.recv().await.unwrap().limit_type, RateLimitType::RequestWeight);
+        assert_eq!(executor.list_open_orders().len(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn test_fault_injection() {
+        let pubsub = Arc::new(PubSub::new());
+        let start = time::macros::datetime!(2025-01-01 00:00 UTC);
+        let faults = FaultInjector::builder()
+            .drop_cancel_ack_rate(1.)
+            .delay_fill_rate(1.)
+            .fill_delay(time::Duration::seconds(5))
+            .downtime(vec![(start + time::Duration::minutes(1), start + time::Duration::minutes(2))])
+            .build();
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .faults(Some(faults))
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let mut updates = pubsub.subscribe::<VenueOrderUpdate>();
+        let mut rejected = pubsub.subscribe::<VenueOrderRejected>();
+        let tick_at = |secs: i64| {
+            let mut tick = tick(dec!(100), dec!(101)).as_ref().clone();
+            tick.event_time = start + time::Duration::seconds(secs);
+            Arc::new(tick)
+        };
+        executor.tick_update(tick_at(0));
+
+        // The fill of a market order comes 5 seconds late
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(1)))
+            .await
+            .unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Placed);
+        executor.tick_update(tick_at(2));
+        assert!(executor.get_position(&instrument).is_none());
+        executor.tick_update(tick_at(5));
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Filled);
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(1));
+
+        // Cancels take effect without acknowledgement
+        let resting = order(MarketSide::Buy, VenueOrderType::Limit, dec!(99), dec!(1));
+        executor.place_order(resting.clone()).await.unwrap();
+        assert_eq!(updates.recv().await.unwrap().status, VenueOrderStatus::Placed);
+        executor.cancel_order(resting.id).await.unwrap();
+        assert!(executor.list_open_orders().is_empty());
+        assert!(updates.try_recv().is_err());
+
+        // While the venue is down orders are rejected and cancels fail
+        let resting = order(MarketSide::Buy, VenueOrderType::Limit, dec!(99), dec!(1));
+        executor.place_order(resting.clone()).await.unwrap();
+        updates.recv().await.unwrap();
+        executor.tick_update(tick_at(90));
+        assert!(matches!(
+            executor.cancel_order(resting.id).await,
+            Err(ExecutorError::NetworkError(_))
+        ));
+        executor
+            .place_order(order(MarketSide::Sell, VenueOrderType::Market, Decimal::ZERO, dec!(1)))
+            .await
+            .unwrap();
+        assert_eq!(rejected.recv().await.unwrap().code, Some(-1001));
+        assert_eq!(executor.list_open_orders().len(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn test_depth_fills() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .depth_fills(true)
+            .taker_commission(Decimal::ZERO)
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let level = |price: Decimal, quantity: Decimal| BookUpdateSide::new(price, quantity);
+        let mut expired = pubsub.subscribe::<VenueOrderExpired>();
+        executor.tick_update(tick(dec!(100), dec!(101)));
+        executor.book_update(Arc::new(Book::new(
+            OffsetDateTime::now_utc(),
+            instrument.clone(),
+            vec![level(dec!(100), dec!(1)), level(dec!(99), dec!(3))],
+            vec![level(dec!(101), dec!(1)), level(dec!(102), dec!(2)), level(dec!(103), dec!(1))],
+        )));
+
+        // A large market order walks up the asks and pays the average of the levels
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(2.5)))
+            .await
+            .unwrap();
+        let position = executor.get_position(&instrument).unwrap();
+        assert_eq!(position.quantity, dec!(2.5));
+        assert_eq!(position.entry_price, dec!(101.6));
+
+        // The taken size is gone from the book, a limit order rests what is left past its price
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(102), dec!(1)))
+            .await
+            .unwrap();
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(3));
+        let open = executor.list_open_orders();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].1.remaining_quantity(), dec!(0.5));
+
+        // Immediate or cancel takes the levels up to its price and expires the rest
+        let mut ioc = order(MarketSide::Sell, VenueOrderType::Limit, dec!(99), dec!(5))
+            .as_ref()
+            .clone();
+        ioc.time_in_force = VenueOrderTimeInForce::Ioc;
+        executor.place_order(Arc::new(ioc)).await.unwrap();
+        let event = expired.recv().await.unwrap();
+        assert_eq!(event.filled_quantity, dec!(4));
+        assert_eq!(event.expired_quantity, dec!(1));
+        assert_eq!(executor.get_position(&instrument).unwrap().quantity, dec!(-1));
+    }
+
+    #[test(tokio::test)]
+    async fn test_execution_metrics() {
+        let pubsub = Arc::new(PubSub::new());
+        let executor = SimulationExecutor::builder().pubsub(pubsub.clone()).build();
+        let mut metrics = pubsub.subscribe::<ExecutionMetrics>();
+        let now = OffsetDateTime::now_utc();
+        let tick_at = |bid: Decimal, ask: Decimal, event_time: OffsetDateTime| {
+            let mut tick = tick(bid, ask).as_ref().clone();
+            tick.event_time = event_time;
+            Arc::new(tick)
+        };
+
+        // A bid arriving at a mid of 100 fills 5 seconds later once the asks drop through it
+        executor.tick_update(tick_at(dec!(99), dec!(101), now));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(99.5), dec!(2)))
+            .await
+            .unwrap();
+        executor.tick_update(tick_at(dec!(99), dec!(99.4), now + Duration::from_secs(5)));
+        let m = metrics.recv().await.unwrap();
+        assert_eq!(m.status, VenueOrderStatus::Filled);
+        assert_eq!(m.fill_ratio, 1.);
+        assert_eq!(m.time_to_fill, Some(Duration::from_secs(5)));
+        assert_eq!(m.arrival_price, Some(dec!(100)));
+        assert_eq!(m.avg_fill_price, Some(dec!(99.5)));
+        assert!((m.implementation_shortfall_bps.unwrap() + 50.).abs() < 1e-9);
+        // Filled 0.3 above the mid of 99.2 at the time of the fill
+        assert!((m.effective_spread_bps.unwrap() - 2. * 0.3 / 99.2 * 1e4).abs() < 1e-6);
+        // The resting bid added liquidity and paid the maker fee
+        assert!((m.commission_bps.unwrap() - 2.).abs() < 1e-9);
+        assert_eq!(m.maker_ratio, Some(1.));
+
+        // A market order pays half the spread right away
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, Decimal::ZERO, dec!(1)))
+            .await
+            .unwrap();
+        let m = metrics.recv().await.unwrap();
+        assert_eq!(m.time_to_fill, Some(Duration::ZERO));
+        assert!((m.effective_spread_bps.unwrap() - 0.4 / 99.2 * 1e4).abs() < 1e-6);
+        assert!((m.implementation_shortfall_bps.unwrap() - 0.2 / 99.2 * 1e4).abs() < 1e-6);
+        assert!((m.commission_bps.unwrap() - 5.).abs() < 1e-9);
+        assert_eq!(m.maker_ratio, Some(0.));
+
+        // Cancelled without a fill
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Limit, dec!(90), dec!(1)))
+            .await
+            .unwrap();
+        let (_, open) = executor.list_open_orders().remove(0);
+        executor.cancel_order(open.id).await.unwrap();
+        let m = metrics.recv().await.unwrap();
+        assert_eq!(m.status, VenueOrderStatus::Canceled);
+        assert_eq!(m.fill_ratio, 0.);
+        assert!(m.time_to_fill.is_none() && m.effective_spread_bps.is_none());
+        assert!(m.implementation_shortfall_bps.is_none());
+        assert!(m.commission_bps.is_none() && m.maker_ratio.is_none());
+        assert!(executor.executions.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn test_checkpoint_restore() {
+        let pubsub = Arc::new(PubSub::new());
+        let wallet = || Arc::new(DashMap::from_iter([(test_usdt_asset(), dec!(10000))]));
+        let faults = || Some(FaultInjector::builder().seed(7).build());
+        let executor = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .balances(wallet())
+            .faults(faults())
+            .build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let level = |price: Decimal, quantity: Decimal| BookUpdateSide::new(price, quantity);
+
+        executor.tick_update(tick(dec!(50000), dec!(50001)));
+        executor.book_update(Arc::new(Book::new(
+            OffsetDateTime::now_utc(),
+            instrument.clone(),
+            vec![level(dec!(50000), dec!(1)), level(dec!(49999), dec!(2))],
+            vec![level(dec!(50001), dec!(1))],
+        )));
+        executor
+            .place_order(order(MarketSide::Buy, VenueOrderType::Market, dec!(0), dec!(0.1)))
+            .await
+            .unwrap();
+        executor
+            .place_order(order(MarketSide::Sell, VenueOrderType::Limit, dec!(50100), dec!(0.1)))
+            .await
+            .unwrap();
+
+        let mut checkpoint = SimulationCheckpoint::builder()
+            .clock(ClockState {
+                start: OffsetDateTime::UNIX_EPOCH,
+                end: OffsetDateTime::UNIX_EPOCH + Duration::from_secs(86400),
+                frequency_secs: 60,
+                current_timestamp: OffsetDateTime::UNIX_EPOCH + Duration::from_secs(3600),
+            })
+            .build();
+        executor.checkpoint(&mut checkpoint);
+        let venue = checkpoint.venue.as_ref().unwrap();
+        assert_eq!(venue.positions.len(), 1);
+        assert_eq!(venue.open_orders.len(), 1);
+        assert_eq!(venue.balances.len(), 1);
+        assert_eq!(venue.order_books.len(), 1);
+        assert_eq!(venue.rngs[0].seed, 7);
+
+        let restored = SimulationExecutor::builder()
+            .pubsub(pubsub.clone())
+            .balances(wallet())
+            .faults(faults())
+            .build();
+        restored.restore(&checkpoint, &test_portfolio(), &[instrument.clone()]).unwrap();
+        assert_eq!(
+            restored.books.get(&instrument).unwrap().levels(),
+            executor.books.get(&instrument).unwrap().levels()
+        );
+        assert_eq!(
+            restored.get_balance(&test_usdt_asset()),
+            executor.get_balance(&test_usdt_asset())
+        );
+        let position = restored.get_position(&instrument).unwrap();
+        assert_eq!(position.quantity, dec!(0.1));
+        assert_eq!(position.entry_price, dec!(50001));
+
+        // The restored order rests and fills once the book crosses it, closing the position
+        let open = restored.list_open_orders();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].1.id, executor.list_open_orders()[0].1.id);
+        restored.tick_update(tick(dec!(50150), dec!(50151)));
+        assert!(restored.list_open_orders().is_empty());
+        assert_eq!(restored.get_position(&instrument).unwrap().quantity, dec!(0));
     }
 }
@@ -1,15 +1,43 @@
 use std::sync::Arc;
 
-use arkin_core::PubSub;
+use arkin_core::{PubSub, TradingSwitch, Watchdog};
 
-use crate::{OrderManager, OrderManagerConfig, OrderManagerType, SimpleOrderManager};
+use crate::{
+    ExecutionExperiment, OrderManager, OrderManagerConfig, OrderManagerType, OrderThrottle, QuotingPause,
+    SimpleOrderManager, TradingWindows,
+};
 
 pub struct ExecutionFactory {}
 
 impl ExecutionFactory {
-    pub fn from_config(config: &OrderManagerConfig, pubsub: Arc<PubSub>) -> Arc<dyn OrderManager> {
+    pub fn from_config(
+        config: &OrderManagerConfig,
+        pubsub: Arc<PubSub>,
+        watchdog: Arc<Watchdog>,
+        switch: Arc<TradingSwitch>,
+    ) -> Arc<dyn OrderManager> {
+        let throttle = config.throttle.as_ref().map(|c| Arc::new(OrderThrottle::from_config(c)));
+        let pause = config.quoting_pause.as_ref().map(|c| Arc::new(QuotingPause::from_config(c)));
+        let experiment = config
+            .experiment
+            .as_ref()
+            .map(|c| Arc::new(ExecutionExperiment::from_config(c)));
+        let windows = config
+            .trading_windows
+            .as_ref()
+            .map(|c| Arc::new(TradingWindows::from_config(c)));
         let order_manager: Arc<dyn OrderManager> = match &config.order_manager {
-            OrderManagerType::SimpleExecutor => Arc::new(SimpleOrderManager::builder().pubsub(pubsub).build()),
+            OrderManagerType::SimpleExecutor => Arc::new(
+                SimpleOrderManager::builder()
+                    .pubsub(pubsub)
+                    .watchdog(watchdog)
+                    .throttle(throttle)
+                    .pause(pause)
+                    .experiment(experiment)
+                    .windows(windows)
+                    .switch(switch)
+                    .build(),
+            ),
         };
 
         order_manager
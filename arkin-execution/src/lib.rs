@@ -5,6 +5,7 @@ mod factory;
 mod order_managers;
 // mod strategies;
 mod traits;
+mod transfers;
 
 pub use config::*;
 pub use errors::*;
@@ -13,6 +14,7 @@ pub use factory::*;
 pub use order_managers::*;
 // pub use strategies::*;
 pub use traits::*;
+pub use transfers::*;
 
 pub mod prelude {
     pub use crate::config::*;
@@ -21,4 +23,5 @@ pub mod prelude {
     pub use crate::order_managers::*;
     // pub use crate::strategies::*;
     pub use crate::traits::*;
+    pub use crate::transfers::*;
 }
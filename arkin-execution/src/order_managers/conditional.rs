@@ -0,0 +1,224 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+const BPS: Decimal = Decimal::from_parts(10000, 0, 0, false, 0);
+
+#[derive(Debug, Default)]
+struct ConditionalState {
+    /// Orders waiting for their trigger, in order of arrival
+    waiting: Vec<Arc<ExecutionOrder>>,
+    ticks: HashMap<Arc<Instrument>, Arc<Tick>>,
+    features: HashMap<Arc<Instrument>, HashMap<FeatureId, Decimal>>,
+    /// Features of insights without an instrument, they apply to all instruments
+    global_features: HashMap<FeatureId, Decimal>,
+}
+
+impl ConditionalState {
+    fn value(&self, instrument: &Arc<Instrument>, operand: &TriggerOperand) -> Option<Decimal> {
+        let tick = self.ticks.get(instrument);
+        match operand {
+            TriggerOperand::Price => tick.map(|t| t.mid_price()),
+            TriggerOperand::Bid => tick.map(|t| t.bid_price()),
+            TriggerOperand::Ask => tick.map(|t| t.ask_price()),
+            TriggerOperand::SpreadBps => tick
+                .filter(|t| !t.mid_price().is_zero())
+                .map(|t| t.spread() / t.mid_price() * BPS),
+            TriggerOperand::Feature(feature_id) => self
+                .features
+                .get(instrument)
+                .and_then(|f| f.get(feature_id))
+                .or_else(|| self.global_features.get(feature_id))
+                .copied(),
+        }
+    }
+
+    fn triggered(&self, order: &ExecutionOrder) -> bool {
+        order
+            .trigger
+            .as_ref()
+            .is_none_or(|t| t.holds(|operand| self.value(&order.instrument, operand)))
+    }
+
+    /// Take the waiting orders of the instruments that triggered, all instruments without one.
+    fn release(&mut self, instrument: Option<&Arc<Instrument>>, now: OffsetDateTime) -> Vec<Arc<ExecutionOrder>> {
+        let waiting = std::mem::take(&mut self.waiting);
+        let (triggered, waiting) = waiting
+            .into_iter()
+            .partition::<Vec<_>, _>(|o| instrument.is_none_or(|i| o.instrument == *i) && self.triggered(o));
+        self.waiting = waiting;
+        triggered
+            .into_iter()
+            .map(|order| {
+                info!("Order {} triggered at {}", order.id, now);
+                let mut order = (*order).clone();
+                order.updated_at = now;
+                Arc::new(order)
+            })
+            .collect()
+    }
+}
+
+/// Holds back orders carrying a trigger in the order manager until its conditions hold on the last tick and
+/// insights of their instrument, the conditions are evaluated again on every tick and insight tick. Orders whose
+/// trigger expires before it fires are dropped.
+#[derive(Debug, Default, TypedBuilder)]
+pub struct ConditionalOrders {
+    #[builder(default)]
+    state: Mutex<ConditionalState>,
+}
+
+impl ConditionalOrders {
+    /// Pass the order on if it has no trigger or its trigger holds already, otherwise it waits for it.
+    pub fn hold(&self, order: Arc<ExecutionOrder>) -> Option<Arc<ExecutionOrder>> {
+        let Some(trigger) = &order.trigger else {
+            return Some(order);
+        };
+        if trigger.is_expired(order.updated_at) {
+            info!("Dropped order {}, trigger {} expired", order.id, trigger);
+            return None;
+        }
+        let mut state = self.state.lock();
+        if state.triggered(&order) {
+            return Some(order);
+        }
+        debug!("Order {} waiting for trigger {}", order.id, trigger);
+        state.waiting.push(order);
+        None
+    }
+
+    /// Orders the tick triggered.
+    pub fn tick(&self, tick: Arc<Tick>) -> Vec<Arc<ExecutionOrder>> {
+        let mut state = self.state.lock();
+        let (instrument, now) = (tick.instrument.clone(), tick.event_time);
+        state.ticks.insert(instrument.clone(), tick);
+        if state.waiting.is_empty() {
+            return Vec::new();
+        }
+        state.release(Some(&instrument), now)
+    }
+
+    /// Orders the insights triggered.
+    pub fn insights(&self, tick: &InsightTick) -> Vec<Arc<ExecutionOrder>> {
+        let mut state = self.state.lock();
+        for insight in &tick.insights {
+            match &insight.instrument {
+                Some(instrument) => {
+                    state
+                        .features
+                        .entry(instrument.clone())
+                        .or_default()
+                        .insert(insight.feature_id.clone(), insight.value);
+                }
+                None => {
+                    state.global_features.insert(insight.feature_id.clone(), insight.value);
+                }
+            }
+        }
+        if state.waiting.is_empty() {
+            return Vec::new();
+        }
+        state.release(None, tick.event_time)
+    }
+
+    /// Drop the waiting orders whose trigger expired.
+    pub fn expire(&self, now: OffsetDateTime) -> Vec<Arc<ExecutionOrder>> {
+        self.discard(|order| order.trigger.as_ref().is_some_and(|t| t.is_expired(now)))
+    }
+
+    /// Drop the waiting orders matching the predicate.
+    pub fn discard(&self, predicate: impl Fn(&ExecutionOrder) -> bool) -> Vec<Arc<ExecutionOrder>> {
+        let mut state = self.state.lock();
+        let waiting = std::mem::take(&mut state.waiting);
+        let (dropped, waiting) = waiting.into_iter().partition::<Vec<_>, _>(|o| predicate(o));
+        state.waiting = waiting;
+        dropped
+    }
+
+    pub fn waiting(&self) -> usize {
+        self.state.lock().waiting.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn order(trigger: Option<OrderTrigger>) -> Arc<ExecutionOrder> {
+        Arc::new(
+            ExecutionOrder::builder()
+                .portfolio(test_portfolio())
+                .instrument(test_inst_binance_btc_usdt_perp())
+                .order_type(ExecutionOrderType::Maker)
+                .side(MarketSide::Buy)
+                .price(dec!(100))
+                .quantity(dec!(1))
+                .trigger(trigger)
+                .updated_at(datetime!(2025-01-01 12:00 UTC))
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_conditional_orders() {
+        let conditional = ConditionalOrders::default();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let now = datetime!(2025-01-01 12:00 UTC);
+        let tick = |bid: Decimal, ask: Decimal, event_time: OffsetDateTime| {
+            let mut tick = (*test_tick(instrument.clone(), bid, dec!(1), ask, dec!(1))).clone();
+            tick.event_time = event_time;
+            Arc::new(tick)
+        };
+        let insights = |value: Decimal, event_time: OffsetDateTime| InsightTick {
+            event_time,
+            instruments: vec![instrument.clone()],
+            insights: vec![Arc::new(
+                Insight::builder()
+                    .event_time(event_time)
+                    .pipeline(test_pipeline())
+                    .instrument(Some(instrument.clone()))
+                    .feature_id(Arc::new("vol_30m".to_string()))
+                    .value(value)
+                    .build(),
+            )],
+        };
+
+        // Orders without a trigger pass right away
+        assert!(conditional.hold(order(None)).is_some());
+
+        // Wide spread and no volatility known yet
+        let trigger = "spread_bps < 5 and vol_30m < 0.02".parse::<OrderTrigger>().unwrap();
+        let waiting = order(Some(trigger.clone()));
+        assert!(conditional.tick(tick(dec!(99), dec!(101), now)).is_empty());
+        assert!(conditional.hold(waiting.clone()).is_none());
+        assert_eq!(conditional.waiting(), 1);
+
+        // The spread narrows to 2bps but the volatility is still too high
+        assert!(conditional.tick(tick(dec!(99.99), dec!(100.01), now)).is_empty());
+        assert!(conditional.insights(&insights(dec!(0.05), now)).is_empty());
+        let later = now + time::Duration::minutes(1);
+        let triggered = conditional.insights(&insights(dec!(0.01), later));
+        assert_eq!(triggered.len(), 1);
+        assert_eq!(triggered[0].id, waiting.id);
+        assert_eq!(triggered[0].updated_at, later);
+        assert_eq!(conditional.waiting(), 0);
+
+        // A trigger that holds already passes, expired triggers are dropped
+        assert!(conditional.hold(order(Some(trigger.clone()))).is_some());
+        let expiring = order(Some("spread_bps > 10".parse::<OrderTrigger>().unwrap().until(later)));
+        assert!(conditional.hold(expiring.clone()).is_none());
+        assert!(conditional.expire(now).is_empty());
+        assert_eq!(conditional.expire(later)[0].id, expiring.id);
+        assert!(conditional.hold(order(Some(trigger.until(now)))).is_none());
+        assert_eq!(conditional.waiting(), 0);
+    }
+}
@@ -0,0 +1,319 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_decimal::prelude::*;
+use statrs::distribution::{ContinuousCDF, StudentsT};
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{ExecutionExperimentConfig, OrderThrottle};
+
+const BPS: Decimal = Decimal::from_parts(10000, 0, 0, false, 0);
+
+/// Execution of an order routed by the experiment.
+#[derive(Debug)]
+struct Routed {
+    treatment: bool,
+    side: MarketSide,
+    decision_price: Price,
+    filled_quantity: Quantity,
+    /// Price times quantity of the fills
+    filled_notional: Decimal,
+    commission: Commission,
+}
+
+impl Routed {
+    /// Shortfall of the fills against the decision price including commission, positive when the fills cost.
+    fn cost_bps(&self) -> Option<f64> {
+        if self.filled_quantity.is_zero() || self.decision_price.is_zero() {
+            return None;
+        }
+        let fill_price = self.filled_notional / self.filled_quantity;
+        let slippage = Decimal::from(self.side) * (fill_price - self.decision_price) / self.decision_price;
+        let commission = self.commission / self.filled_notional;
+        ((slippage + commission) * BPS).to_f64()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ExperimentState {
+    open: HashMap<Uuid, Routed>,
+    orders: [u64; 2],
+    costs: [Vec<f64>; 2],
+    last_report: Option<OffsetDateTime>,
+}
+
+/// Splits comparable execution orders at random between the order type they were sent with and an alternative, and
+/// measures the cost of both arms as the shortfall of the fills against the decision price plus commission. The
+/// difference is reported with a Welch t-test, so a cheaper execution can be rolled out once it is significant.
+/// Orders are comparable when they have the control order type, a decision price and a strategy in the experiment.
+#[derive(Debug, TypedBuilder)]
+pub struct ExecutionExperiment {
+    name: String,
+    control: ExecutionOrderType,
+    treatment: ExecutionOrderType,
+    /// Share of the comparable orders routed through the treatment
+    fraction: f64,
+    /// Strategies whose orders take part, all strategies when empty
+    #[builder(default)]
+    strategies: Vec<String>,
+    #[builder(default = Duration::from_secs(3600))]
+    report_every: Duration,
+    #[builder(default = 0.05)]
+    significance: f64,
+    #[builder(default = Mutex::new(StdRng::seed_from_u64(0)))]
+    rng: Mutex<StdRng>,
+    #[builder(default)]
+    state: Mutex<ExperimentState>,
+}
+
+impl ExecutionExperiment {
+    pub fn from_config(config: &ExecutionExperimentConfig) -> Self {
+        Self::builder()
+            .name(config.name.clone())
+            .control(config.control.into())
+            .treatment(config.treatment.into())
+            .fraction(config.fraction)
+            .strategies(config.strategies.clone())
+            .report_every(Duration::from_secs(config.report_secs))
+            .significance(config.significance)
+            .rng(Mutex::new(StdRng::seed_from_u64(config.seed)))
+            .build()
+    }
+
+    fn comparable(&self, order: &ExecutionOrder) -> bool {
+        order.order_type == self.control
+            && !order.price.is_zero()
+            && (self.strategies.is_empty() || self.strategies.contains(&OrderThrottle::strategy_of(order)))
+    }
+
+    /// Pick the arm of a comparable order, the orders routed through the treatment are returned with its order type.
+    pub fn route(&self, order: Arc<ExecutionOrder>) -> Arc<ExecutionOrder> {
+        if !self.comparable(&order) {
+            return order;
+        }
+        let treatment = self.rng.lock().gen::<f64>() < self.fraction;
+        let mut state = self.state.lock();
+        state.orders[treatment as usize] += 1;
+        state.open.insert(
+            order.id,
+            Routed {
+                treatment,
+                side: order.side,
+                decision_price: order.price,
+                filled_quantity: Quantity::ZERO,
+                filled_notional: Decimal::ZERO,
+                commission: Commission::ZERO,
+            },
+        );
+        if !treatment {
+            return order;
+        }
+        debug!("Experiment {} routing order {} as {}", self.name, order.id, self.treatment);
+        let mut routed = (*order).clone();
+        routed.order_type = self.treatment;
+        Arc::new(routed)
+    }
+
+    /// Book the last fill of an order in the experiment, its cost is measured once it is finalized.
+    pub fn update(&self, update: &VenueOrderUpdate) {
+        let Ok(id) = Uuid::parse_str(&update.order_id) else {
+            return;
+        };
+        let mut state = self.state.lock();
+        let Some(routed) = state.open.get_mut(&id) else {
+            return;
+        };
+        routed.filled_quantity += update.last_fill_quantity.abs();
+        routed.filled_notional += update.last_fill_price * update.last_fill_quantity.abs();
+        routed.commission += update.commission;
+        if !update.status.is_finalized() {
+            return;
+        }
+        if let Some(routed) = state.open.remove(&id) {
+            if let Some(cost) = routed.cost_bps() {
+                state.costs[routed.treatment as usize].push(cost);
+            }
+        }
+    }
+
+    fn arm(&self, state: &ExperimentState, treatment: bool) -> ExperimentArm {
+        let costs = &state.costs[treatment as usize];
+        let (mean, variance) = mean_variance(costs);
+        ExperimentArm {
+            order_type: if treatment {
+                self.treatment
+            } else {
+                self.control
+            },
+            orders: state.orders[treatment as usize],
+            filled: costs.len() as u64,
+            mean_cost_bps: mean,
+            std_cost_bps: variance.sqrt(),
+        }
+    }
+
+    /// Cost difference of the arms so far.
+    pub fn report(&self, event_time: OffsetDateTime) -> ExecutionExperimentReport {
+        let state = self.state.lock();
+        let control = self.arm(&state, false);
+        let treatment = self.arm(&state, true);
+        let test = welch_t_test(&state.costs[1], &state.costs[0]);
+        ExecutionExperimentReport::builder()
+            .event_time(event_time)
+            .experiment(self.name.clone())
+            .control(control)
+            .treatment(treatment)
+            .difference_bps(treatment.mean_cost_bps - control.mean_cost_bps)
+            .t_stat(test.map(|(t, _)| t))
+            .p_value(test.map(|(_, p)| p))
+            .significant(test.is_some_and(|(_, p)| p < self.significance))
+            .build()
+    }
+
+    /// Report the cost difference if the report interval passed since the last one.
+    pub fn report_due(&self, now: OffsetDateTime) -> Option<ExecutionExperimentReport> {
+        {
+            let mut state = self.state.lock();
+            match state.last_report {
+                Some(last) if now - last < self.report_every => return None,
+                Some(_) => state.last_report = Some(now),
+                None => {
+                    state.last_report = Some(now);
+                    return None;
+                }
+            }
+        }
+        Some(self.report(now))
+    }
+}
+
+fn mean_variance(samples: &[f64]) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0., 0.);
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.);
+    }
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.);
+    (mean, variance)
+}
+
+/// Welch t-test of the difference in means of two samples, returns the t statistic and the two-sided p-value. None
+/// while a sample has fewer than two values or both have no variance.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (mean_a, var_a) = mean_variance(a);
+    let (mean_b, var_b) = mean_variance(b);
+    let (se_a, se_b) = (var_a / a.len() as f64, var_b / b.len() as f64);
+    let se = se_a + se_b;
+    if se <= 0. {
+        return None;
+    }
+    let t = (mean_a - mean_b) / se.sqrt();
+    let dof = se.powi(2) / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+    match StudentsT::new(0., 1., dof) {
+        Ok(dist) => Some((t, 2. * (1. - dist.cdf(t.abs())))),
+        Err(e) => {
+            warn!("No t distribution with {} degrees of freedom: {}", dof, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn order(order_type: ExecutionOrderType) -> Arc<ExecutionOrder> {
+        Arc::new(
+            ExecutionOrder::builder()
+                .portfolio(test_portfolio())
+                .instrument(test_inst_binance_btc_usdt_perp())
+                .order_type(order_type)
+                .side(MarketSide::Buy)
+                .price(dec!(100))
+                .quantity(dec!(1))
+                .build(),
+        )
+    }
+
+    fn filled(order: &ExecutionOrder, price: Price) -> VenueOrderUpdate {
+        VenueOrderUpdate::builder()
+            .event_time(order.updated_at)
+            .portfolio(test_portfolio())
+            .instrument(order.instrument.clone())
+            .order_id(order.id.to_string())
+            .venue_order_id(1)
+            .side(order.side)
+            .order_type(order.order_type.into())
+            .time_in_force(VenueOrderTimeInForce::Gtc)
+            .price(order.price)
+            .quantity(order.quantity)
+            .fill_price(price)
+            .fill_quantity(order.quantity)
+            .last_fill_price(price)
+            .last_fill_quantity(order.quantity)
+            .status(VenueOrderStatus::Filled)
+            .commission_asset(None)
+            .commission(dec!(0.01))
+            .build()
+    }
+
+    #[test]
+    fn test_experiment() {
+        let experiment = ExecutionExperiment::builder()
+            .name("maker_vs_taker".to_string())
+            .control(ExecutionOrderType::Taker)
+            .treatment(ExecutionOrderType::Maker)
+            .fraction(0.5)
+            .build();
+
+        // Orders of other types are left alone
+        let maker = order(ExecutionOrderType::Maker);
+        assert_eq!(experiment.route(maker.clone()), maker);
+
+        // Takers pay 10bps of slippage, the routed makers fill at the decision price and both pay about 1bp commission
+        for _ in 0..40 {
+            let routed = experiment.route(order(ExecutionOrderType::Taker));
+            let price = match routed.order_type {
+                ExecutionOrderType::Maker => dec!(100),
+                _ => dec!(100.1),
+            };
+            experiment.update(&filled(&routed, price));
+        }
+        let report = experiment.report(OffsetDateTime::now_utc());
+        assert_eq!(report.control.orders + report.treatment.orders, 40);
+        assert!(report.treatment.orders > 0 && report.control.orders > 0);
+        assert!((report.control.mean_cost_bps - 11.).abs() < 0.01);
+        assert!((report.treatment.mean_cost_bps - 1.).abs() < 0.01);
+        assert!((report.difference_bps + 10.).abs() < 0.01);
+        // Without any variance there is nothing to test
+        assert!(report.p_value.is_none());
+        assert!(experiment.state.lock().open.is_empty());
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        assert!(welch_t_test(&[1.], &[1., 2.]).is_none());
+        let (t, p) = welch_t_test(&[1., 2., 3., 4., 5.], &[1., 2., 3., 4., 5.]).unwrap();
+        assert_eq!(t, 0.);
+        assert!((p - 1.).abs() < 1e-9);
+
+        // Means 5 apart with a standard error of sqrt(2/3)
+        let (t, p) = welch_t_test(&[9., 10., 11.], &[4., 5., 6.]).unwrap();
+        assert!((t - 6.123724356957945).abs() < 1e-9);
+        assert!(p < 0.01);
+    }
+}
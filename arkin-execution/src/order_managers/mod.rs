@@ -1,4 +1,16 @@
+mod conditional;
+mod experiment;
+mod pause;
+mod quote_guard;
 mod simple;
+mod throttle;
+mod trading_window;
 
+pub use conditional::*;
+pub use experiment::*;
+pub use pause::*;
+pub use quote_guard::*;
 pub use simple::SimpleOrderManager;
 pub use simple::SimpleOrderManagerBuilder;
+pub use throttle::*;
+pub use trading_window::*;
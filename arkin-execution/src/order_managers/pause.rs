@@ -0,0 +1,177 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tracing::debug;
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{QuotingPauseConfig, QuotingPausePolicyConfig};
+
+/// How long quoting pauses around the venue events of an instrument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotingPausePolicy {
+    pub before: Duration,
+    pub after: Duration,
+    pub mode: QuotingPauseMode,
+}
+
+impl From<&QuotingPausePolicyConfig> for QuotingPausePolicy {
+    fn from(config: &QuotingPausePolicyConfig) -> Self {
+        Self {
+            before: Duration::from_secs(config.before_secs),
+            after: Duration::from_secs(config.after_secs),
+            mode: config.mode,
+        }
+    }
+}
+
+/// Pause in effect for an instrument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivePause {
+    pub mode: QuotingPauseMode,
+    pub event_type: VenueCalendarEventType,
+    pub until: OffsetDateTime,
+}
+
+/// Keeps the announced maintenance and funding events of the venues and tells whether quoting in an instrument is
+/// paused at a given time, from the configured time before the start of an event until the time after its end.
+#[derive(Debug, TypedBuilder)]
+pub struct QuotingPause {
+    /// Pause of instruments without their own policy
+    #[builder(default)]
+    default_policy: Option<QuotingPausePolicy>,
+    #[builder(default)]
+    policies: HashMap<String, QuotingPausePolicy>,
+    #[builder(default)]
+    events: Mutex<Vec<Arc<VenueCalendarEvent>>>,
+}
+
+impl QuotingPause {
+    pub fn from_config(config: &QuotingPauseConfig) -> Self {
+        Self::builder()
+            .default_policy(config.default.as_ref().map(|p| p.into()))
+            .policies(
+                config
+                    .instruments
+                    .iter()
+                    .map(|(symbol, p)| (symbol.clone(), p.into()))
+                    .collect(),
+            )
+            .build()
+    }
+
+    pub fn policy(&self, instrument: &Instrument) -> Option<QuotingPausePolicy> {
+        self.policies.get(&instrument.symbol).copied().or(self.default_policy)
+    }
+
+    pub fn schedule(&self, event: Arc<VenueCalendarEvent>) {
+        let mut events = self.events.lock();
+        events.retain(|e| e.id != event.id);
+        events.push(event);
+    }
+
+    /// Pause of the instrument at the given time, the one lasting longest when events overlap.
+    pub fn active(&self, instrument: &Instrument, now: OffsetDateTime) -> Option<ActivePause> {
+        let policy = self.policy(instrument)?;
+        self.events
+            .lock()
+            .iter()
+            .filter(|e| e.applies_to(instrument))
+            .filter(|e| e.start - policy.before <= now && now < e.end + policy.after)
+            .map(|e| ActivePause {
+                mode: policy.mode,
+                event_type: e.event_type,
+                until: e.end + policy.after,
+            })
+            .max_by_key(|p| p.until)
+    }
+
+    /// Forget the events no instrument is paused for anymore.
+    pub fn expire(&self, now: OffsetDateTime) {
+        let longest_after = self
+            .policies
+            .values()
+            .chain(self.default_policy.iter())
+            .map(|p| p.after)
+            .max()
+            .unwrap_or_default();
+        let mut events = self.events.lock();
+        let before = events.len();
+        events.retain(|e| e.end + longest_after > now);
+        if events.len() < before {
+            debug!("Expired {} venue events", before - events.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn event(
+        instrument: Option<Arc<Instrument>>,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Arc<VenueCalendarEvent> {
+        VenueCalendarEvent::builder()
+            .event_time(start)
+            .venue(test_binance_venue())
+            .instrument(instrument)
+            .event_type(VenueCalendarEventType::Maintenance)
+            .start(start)
+            .end(end)
+            .build()
+            .into()
+    }
+
+    fn policy(before: u64, after: u64, mode: QuotingPauseMode) -> QuotingPausePolicy {
+        QuotingPausePolicy {
+            before: Duration::from_secs(before),
+            after: Duration::from_secs(after),
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_pause_window() {
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let pause = QuotingPause::builder()
+            .policies(HashMap::from([(
+                btc.symbol.clone(),
+                policy(30, 60, QuotingPauseMode::PullQuotes),
+            )]))
+            .build();
+        let start = datetime!(2025-01-01 08:00 UTC);
+        pause.schedule(event(None, start, start + Duration::from_secs(600)));
+
+        assert!(pause.active(&btc, start - Duration::from_secs(31)).is_none());
+        let active = pause.active(&btc, start - Duration::from_secs(30)).unwrap();
+        assert_eq!(active.mode, QuotingPauseMode::PullQuotes);
+        assert_eq!(active.until, start + Duration::from_secs(660));
+        assert!(pause.active(&btc, start + Duration::from_secs(660)).is_none());
+        // Instruments without a policy keep quoting
+        assert!(pause.active(&eth, start).is_none());
+
+        pause.expire(start + Duration::from_secs(660));
+        assert!(pause.events.lock().is_empty());
+    }
+
+    #[test]
+    fn test_instrument_event() {
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let pause = QuotingPause::builder()
+            .default_policy(Some(policy(10, 10, QuotingPauseMode::TakerOnly)))
+            .build();
+        let funding = datetime!(2025-01-01 16:00 UTC);
+        pause.schedule(event(Some(btc.clone()), funding, funding));
+
+        assert_eq!(pause.active(&btc, funding).unwrap().mode, QuotingPauseMode::TakerOnly);
+        assert!(pause.active(&eth, funding).is_none());
+    }
+}
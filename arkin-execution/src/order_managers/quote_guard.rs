@@ -0,0 +1,261 @@
+use std::{collections::VecDeque, time::Duration};
+
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{PickOffConfig, QuoteGuardConfig};
+
+const BPS: Decimal = Decimal::from_parts(10000, 0, 0, false, 0);
+const REQUOTE_WINDOW: Duration = Duration::from_secs(1);
+
+/// When a fill counts as picked off and how the spread widens after repeated pick-offs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickOffPolicy {
+    /// Time after a fill the mid is compared to the fill price
+    pub window: Duration,
+    /// Move of the mid against the fill that makes it a pick-off
+    pub adverse_move_bps: Decimal,
+    /// Pick-offs within the lookback that widen the spread
+    pub max_pick_offs: usize,
+    pub lookback: Duration,
+    /// Factor the spread is multiplied with on every widening, up to the max
+    pub widen_factor: Decimal,
+    pub max_widen: Decimal,
+    /// Time without widening before the spread returns to normal
+    pub cooldown: Duration,
+}
+
+impl From<&PickOffConfig> for PickOffPolicy {
+    fn from(config: &PickOffConfig) -> Self {
+        Self {
+            window: Duration::from_millis(config.window_ms),
+            adverse_move_bps: config.adverse_move_bps,
+            max_pick_offs: config.max_pick_offs,
+            lookback: Duration::from_secs(config.lookback_secs),
+            widen_factor: config.widen_factor,
+            max_widen: config.max_widen,
+            cooldown: Duration::from_secs(config.cooldown_secs),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QuoteGuardState {
+    requotes: VecDeque<OffsetDateTime>,
+    /// Fills waiting for the end of their window
+    fills: VecDeque<(OffsetDateTime, MarketSide, Price)>,
+    pick_offs: VecDeque<OffsetDateTime>,
+    widen: Decimal,
+    widened_at: Option<OffsetDateTime>,
+}
+
+impl Default for QuoteGuardState {
+    fn default() -> Self {
+        Self {
+            requotes: VecDeque::new(),
+            fills: VecDeque::new(),
+            pick_offs: VecDeque::new(),
+            widen: Decimal::ONE,
+            widened_at: None,
+        }
+    }
+}
+
+/// Protects a quoter from being gamed. Requotes are limited per second so a jumpy book can't make the quoter stuff
+/// the venue with orders, the distance of the quotes from the mid is drawn at random within a band so other
+/// participants can't read the quoting rule off the book, and fills followed by a move of the mid against them
+/// within a short window count as pick-offs. Once the quoter got picked off repeatedly the spread widens, until no
+/// pick-off happened for the cooldown.
+#[derive(Debug, TypedBuilder)]
+pub struct QuoteGuard {
+    #[builder(default = u32::MAX)]
+    max_requotes_per_sec: u32,
+    /// Half width of the band the spread is drawn from, as a fraction of the spread
+    #[builder(default)]
+    offset_jitter: Decimal,
+    /// Spreads never widen without it
+    #[builder(default)]
+    pick_off: Option<PickOffPolicy>,
+    #[builder(default = Mutex::new(StdRng::seed_from_u64(0)))]
+    rng: Mutex<StdRng>,
+    #[builder(default)]
+    state: Mutex<QuoteGuardState>,
+}
+
+/// Guard without protections, quotes are never held back, moved or widened.
+impl Default for QuoteGuard {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl QuoteGuard {
+    pub fn from_config(config: &QuoteGuardConfig) -> Self {
+        Self::builder()
+            .max_requotes_per_sec(config.max_requotes_per_sec)
+            .offset_jitter(config.offset_jitter)
+            .pick_off(config.pick_off.as_ref().map(|p| p.into()))
+            .rng(Mutex::new(StdRng::seed_from_u64(config.seed)))
+            .build()
+    }
+
+    /// Take a requote out of the budget of the last second, false if the budget is used up and the quote has to stay.
+    pub fn requote(&self, now: OffsetDateTime) -> bool {
+        let mut state = self.state.lock();
+        while state.requotes.front().is_some_and(|t| now - *t >= REQUOTE_WINDOW) {
+            state.requotes.pop_front();
+        }
+        if state.requotes.len() >= self.max_requotes_per_sec as usize {
+            debug!(
+                "Requote at {} held back, {} requotes in the last second",
+                now,
+                state.requotes.len()
+            );
+            return false;
+        }
+        state.requotes.push_back(now);
+        true
+    }
+
+    /// Factor the spread is widened with at the given time.
+    pub fn widen(&self, now: OffsetDateTime) -> Decimal {
+        let mut state = self.state.lock();
+        if let (Some(policy), Some(widened_at)) = (&self.pick_off, state.widened_at) {
+            if now - widened_at >= policy.cooldown {
+                info!("Quote spread back to normal after {:?} without pick-offs", policy.cooldown);
+                state.widen = Decimal::ONE;
+                state.widened_at = None;
+            }
+        }
+        state.widen
+    }
+
+    /// Spread to quote at, the given spread widened after pick-offs and moved at random within the jitter band.
+    pub fn spread(&self, spread: Decimal, now: OffsetDateTime) -> Decimal {
+        let spread = spread * self.widen(now);
+        if self.offset_jitter.is_zero() {
+            return spread;
+        }
+        let draw = Decimal::from_f64(self.rng.lock().gen_range(-1.0..=1.0)).unwrap_or_default();
+        spread * (Decimal::ONE + self.offset_jitter * draw)
+    }
+
+    /// Price to quote a side at around the mid, the spread being a fraction of the mid on each side.
+    pub fn quote_price(&self, side: MarketSide, mid: Price, spread: Decimal, now: OffsetDateTime) -> Price {
+        let offset = mid * self.spread(spread, now);
+        match side {
+            MarketSide::Buy => mid - offset,
+            MarketSide::Sell => mid + offset,
+        }
+    }
+
+    /// Remember a fill of a quote, it is judged once the mid after its window is known.
+    pub fn fill(&self, event_time: OffsetDateTime, side: MarketSide, price: Price) {
+        if self.pick_off.is_some() {
+            self.state.lock().fills.push_back((event_time, side, price));
+        }
+    }
+
+    /// Judge the fills whose window passed against the mid, returns true if the spread widened.
+    pub fn mark(&self, now: OffsetDateTime, mid: Price) -> bool {
+        let Some(policy) = &self.pick_off else {
+            return false;
+        };
+        let mut state = self.state.lock();
+        while let Some((filled_at, side, price)) = state.fills.front().copied() {
+            if now - filled_at < policy.window {
+                break;
+            }
+            state.fills.pop_front();
+            let moved = Decimal::from(side) * (mid - price) / price * BPS;
+            if moved <= -policy.adverse_move_bps {
+                debug!(
+                    "Quote filled at {} picked off, mid moved {}bps against it",
+                    price,
+                    moved.round_dp(2)
+                );
+                state.pick_offs.push_back(now);
+            }
+        }
+        while state.pick_offs.front().is_some_and(|t| now - *t > policy.lookback) {
+            state.pick_offs.pop_front();
+        }
+        if state.pick_offs.len() < policy.max_pick_offs {
+            return false;
+        }
+        state.pick_offs.clear();
+        state.widen = (state.widen * policy.widen_factor).min(policy.max_widen);
+        state.widened_at = Some(now);
+        info!("Quote spread widened to {}x after repeated pick-offs", state.widen);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_requote_rate() {
+        let guard = QuoteGuard::builder().max_requotes_per_sec(2).build();
+        let now = datetime!(2025-01-01 00:00:00 UTC);
+        assert!(guard.requote(now));
+        assert!(guard.requote(now + Duration::from_millis(200)));
+        assert!(!guard.requote(now + Duration::from_millis(400)));
+        assert!(guard.requote(now + Duration::from_millis(1000)));
+    }
+
+    #[test]
+    fn test_offset_jitter() {
+        let guard = QuoteGuard::builder().offset_jitter(dec!(0.2)).build();
+        let now = datetime!(2025-01-01 00:00:00 UTC);
+        let spreads = (0..50).map(|_| guard.spread(dec!(0.001), now)).collect::<Vec<_>>();
+        assert!(spreads.iter().all(|s| *s >= dec!(0.0008) && *s <= dec!(0.0012)));
+        assert!(spreads.iter().any(|s| *s != spreads[0]));
+        assert!(guard.quote_price(MarketSide::Buy, dec!(100), dec!(0.001), now) < dec!(100));
+    }
+
+    #[test]
+    fn test_pick_off_widens() {
+        let policy = PickOffPolicy {
+            window: Duration::from_secs(1),
+            adverse_move_bps: dec!(5),
+            max_pick_offs: 2,
+            lookback: Duration::from_secs(60),
+            widen_factor: dec!(2),
+            max_widen: dec!(3),
+            cooldown: Duration::from_secs(300),
+        };
+        let guard = QuoteGuard::builder().pick_off(Some(policy)).build();
+        let now = datetime!(2025-01-01 00:00:00 UTC);
+
+        // Bought at 100, the mid drops 10bps right after
+        guard.fill(now, MarketSide::Buy, dec!(100));
+        assert!(!guard.mark(now + Duration::from_millis(500), dec!(99.9)));
+        assert!(!guard.mark(now + Duration::from_secs(1), dec!(99.9)));
+        // A sell followed by a drop is not picked off
+        guard.fill(now + Duration::from_secs(2), MarketSide::Sell, dec!(100));
+        assert!(!guard.mark(now + Duration::from_secs(3), dec!(99.9)));
+        // The second pick-off widens the spread
+        guard.fill(now + Duration::from_secs(4), MarketSide::Sell, dec!(100));
+        assert!(guard.mark(now + Duration::from_secs(5), dec!(100.1)));
+        assert_eq!(guard.widen(now + Duration::from_secs(5)), dec!(2));
+        assert_eq!(guard.spread(dec!(0.001), now + Duration::from_secs(5)), dec!(0.002));
+
+        // Widening is capped and goes away after the cooldown
+        guard.fill(now + Duration::from_secs(6), MarketSide::Buy, dec!(100));
+        guard.fill(now + Duration::from_secs(6), MarketSide::Buy, dec!(100));
+        assert!(guard.mark(now + Duration::from_secs(7), dec!(99)));
+        assert_eq!(guard.widen(now + Duration::from_secs(7)), dec!(3));
+        assert_eq!(guard.widen(now + Duration::from_secs(307)), Decimal::ONE);
+    }
+}
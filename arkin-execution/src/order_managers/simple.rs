@@ -1,17 +1,204 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
+use parking_lot::Mutex;
+use time::OffsetDateTime;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{info, warn};
 use typed_builder::TypedBuilder;
+use uuid::Uuid;
 
 use arkin_core::prelude::*;
 
-use crate::{OrderManager, OrderManagerError};
+use crate::{
+    ConditionalOrders, ExecutionExperiment, OrderManager, OrderManagerError, OrderThrottle, QuotingPause,
+    TradingWindows,
+};
 
 #[derive(Debug, TypedBuilder)]
 pub struct SimpleOrderManager {
     pubsub: Arc<PubSub>,
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+    /// Orders are passed through to the venue right away without a throttle
+    #[builder(default)]
+    throttle: Option<Arc<OrderThrottle>>,
+    /// Instruments and strategies trading is disabled for, new orders in them are rejected
+    #[builder(default)]
+    switch: Arc<TradingSwitch>,
+    /// Maker orders are never paused without it
+    #[builder(default)]
+    pause: Option<Arc<QuotingPause>>,
+    /// Orders keep their order type without it
+    #[builder(default)]
+    experiment: Option<Arc<ExecutionExperiment>>,
+    /// Strategies trade around the clock without it
+    #[builder(default)]
+    windows: Option<Arc<TradingWindows>>,
+    /// Orders with a trigger wait here until it fires
+    #[builder(default)]
+    conditional: ConditionalOrders,
+    #[builder(default)]
+    open: Mutex<HashMap<ExecutionOrderId, Arc<ExecutionOrder>>>,
+    /// Open maker orders already handed out to be cancelled for a pause
+    #[builder(default)]
+    pulled: Mutex<HashSet<ExecutionOrderId>>,
+}
+
+impl SimpleOrderManager {
+    /// Hand the order to the executor, stamped with the time it leaves the order manager.
+    fn send(&self, order: &Arc<ExecutionOrder>, event_time: OffsetDateTime) {
+        self.open.lock().insert(order.id, order.clone());
+        let venue_order = VenueOrder::builder()
+            .id(order.id)
+            .portfolio(test_portfolio())
+            .instrument(order.instrument.to_owned())
+            .side(order.side)
+            .order_type(order.order_type.into())
+            .price(order.price)
+            .quantity(order.quantity)
+            .created_at(event_time)
+            .updated_at(event_time)
+            .build();
+
+        self.pubsub.publish::<VenueOrder>(venue_order.into());
+    }
+
+    /// Route an order through the trading windows, the pause, the experiment and the throttle to the executor.
+    fn submit(&self, order: Arc<ExecutionOrder>) {
+        let order = match &self.windows {
+            Some(windows) => match windows.admit(order) {
+                Some(order) => order,
+                None => return,
+            },
+            None => order,
+        };
+        let Some(order) = self.paused(order) else {
+            return;
+        };
+        let order = match &self.experiment {
+            Some(experiment) => experiment.route(order),
+            None => order,
+        };
+        match &self.throttle {
+            Some(throttle) => {
+                throttle.enqueue(order.clone());
+                self.release(throttle, order.updated_at);
+            }
+            None => self.send(&order, order.updated_at),
+        }
+    }
+
+    fn release(&self, throttle: &OrderThrottle, now: OffsetDateTime) {
+        for order in throttle.release(now) {
+            self.send(&order, now);
+        }
+    }
+
+    /// Reject or convert a maker order while quoting in its instrument is paused.
+    fn paused(&self, order: Arc<ExecutionOrder>) -> Option<Arc<ExecutionOrder>> {
+        let Some(pause) = &self.pause else {
+            return Some(order);
+        };
+        if order.order_type != ExecutionOrderType::Maker {
+            return Some(order);
+        }
+        let Some(active) = pause.active(&order.instrument, order.updated_at) else {
+            return Some(order);
+        };
+        match active.mode {
+            QuotingPauseMode::PullQuotes => {
+                warn!(
+                    "Rejected maker order {}, quoting in {} is paused for {} until {}",
+                    order.id, order.instrument, active.event_type, active.until
+                );
+                None
+            }
+            QuotingPauseMode::TakerOnly => {
+                info!(
+                    "Sending maker order {} as taker, {} is taker only for {} until {}",
+                    order.id, order.instrument, active.event_type, active.until
+                );
+                let mut taker = (*order).clone();
+                taker.order_type = ExecutionOrderType::Taker;
+                Some(Arc::new(taker))
+            }
+        }
+    }
+
+    /// Hand out the open maker orders in instruments that entered a pause pulling the quotes.
+    fn pull_quotes(&self, pause: &QuotingPause, now: OffsetDateTime) {
+        pause.expire(now);
+        let mut pulled = self.pulled.lock();
+        let mut pulls: HashMap<Arc<Instrument>, (ActivePause, Vec<ExecutionOrderId>)> = HashMap::new();
+        for order in self.open.lock().values() {
+            if order.order_type != ExecutionOrderType::Maker || pulled.contains(&order.id) {
+                continue;
+            }
+            match pause.active(&order.instrument, now) {
+                Some(active) if active.mode == QuotingPauseMode::PullQuotes => {
+                    pulls
+                        .entry(order.instrument.clone())
+                        .or_insert((active, Vec::new()))
+                        .1
+                        .push(order.id);
+                }
+                _ => {}
+            }
+        }
+        for (instrument, (active, orders)) in pulls {
+            pulled.extend(orders.iter().copied());
+            let event = QuotesPulled::builder()
+                .event_time(now)
+                .instrument(instrument)
+                .event_type(active.event_type)
+                .until(active.until)
+                .orders(orders)
+                .build();
+            info!("SimpleOrderManager pausing quotes: {}", event);
+            self.pubsub.publish::<QuotesPulled>(event.into());
+        }
+    }
+
+    /// Publish the cost difference of the experiment, every report interval and when a simulation finishes.
+    fn report_experiment(&self, report: Option<ExecutionExperimentReport>) {
+        if let Some(report) = report {
+            info!("SimpleOrderManager execution experiment: {}", report);
+            self.pubsub.publish::<ExecutionExperimentReport>(report.into());
+        }
+    }
+
+    /// Apply the control to the switch and drop the queued orders it disables, open orders are cancelled by the
+    /// engine.
+    fn control(&self, control: &TradingControl) {
+        self.switch.apply(control);
+        if control.enabled {
+            return;
+        }
+        let dropped = self.conditional.discard(|order| control.scope.contains(order));
+        if !dropped.is_empty() {
+            info!("Dropped {} conditional orders of disabled {}", dropped.len(), control.scope);
+        }
+        if let Some(windows) = &self.windows {
+            let dropped = windows.discard(|order| control.scope.contains(order));
+            if !dropped.is_empty() {
+                info!(
+                    "Dropped {} orders waiting for the trading window of disabled {}",
+                    dropped.len(),
+                    control.scope
+                );
+            }
+        }
+        if let Some(throttle) = &self.throttle {
+            let dropped = throttle.discard(|order| control.scope.contains(order));
+            if !dropped.is_empty() {
+                info!("Dropped {} queued orders of disabled {}", dropped.len(), control.scope);
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -20,27 +207,100 @@ impl OrderManager for SimpleOrderManager {
         info!("Starting order manager...");
         let mut execution_orders = self.pubsub.subscribe::<ExecutionOrder>();
         let mut venue_order_updates = self.pubsub.subscribe::<VenueOrderUpdate>();
+        let mut interval_tick = self.pubsub.subscribe::<IntervalTick>();
+        let mut ticks = self.pubsub.subscribe::<Tick>();
+        let mut insight_ticks = self.pubsub.subscribe::<InsightTick>();
+        let mut trading_controls = self.pubsub.subscribe::<TradingControl>();
+        let mut venue_events = self.pubsub.subscribe::<VenueCalendarEvent>();
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
         loop {
             tokio::select! {
                 Ok(order) = execution_orders.recv() => {
                     info!("SimpleOrderManager received order: {}", order);
-                    let venue_order = VenueOrder::builder()
-                        .id(order.id)
-                        .portfolio(test_portfolio())
-                        .instrument(order.instrument.to_owned())
-                        .side(order.side)
-                        .order_type(order.order_type.into())
-                        .price(order.price)
-                        .quantity(order.quantity)
-                        .build();
-
-                    self.pubsub.publish::<VenueOrder>(venue_order.into());
+                    let _guard = self.watchdog.track("order_manager", "execution_order");
+                    if let Some(scope) = self.switch.blocked_by(&order) {
+                        warn!("Rejected order {}, trading is disabled for {}", order.id, scope);
+                        continue;
+                    }
+                    if let Some(order) = self.conditional.hold(order) {
+                        self.submit(order);
+                    }
+                }
+                Ok(tick) = ticks.recv() => {
+                    for order in self.conditional.tick(tick) {
+                        self.submit(order);
+                    }
+                }
+                Ok(tick) = insight_ticks.recv() => {
+                    for order in self.conditional.insights(&tick) {
+                        self.submit(order);
+                    }
                 }
                 Ok(order) = venue_order_updates.recv() => {
                     info!("SimpleOrderManager received order update: {}", order);
-                    // if let Err(e) = self.order_update(fill.clone()).await {
-                    //     error!("Failed to process fill: {}", e);
-                    // }
+                    if let Some(experiment) = &self.experiment {
+                        experiment.update(&order);
+                    }
+                    let finalized = matches!(
+                        order.status,
+                        VenueOrderStatus::Filled
+                            | VenueOrderStatus::Canceled
+                            | VenueOrderStatus::Rejected
+                            | VenueOrderStatus::Expired
+                            | VenueOrderStatus::PartiallyFilledCanceled
+                            | VenueOrderStatus::PartiallyFilledExpired
+                    );
+                    if finalized {
+                        let id = match Uuid::parse_str(&order.order_id) {
+                            Ok(id) => id,
+                            Err(e) => {
+                                warn!("Order update with unknown order id {}: {}", order.order_id, e);
+                                continue;
+                            }
+                        };
+                        self.open.lock().remove(&id);
+                        self.pulled.lock().remove(&id);
+                        if let Some(throttle) = &self.throttle {
+                            throttle.order_closed(id);
+                            self.release(throttle, order.event_time);
+                        }
+                    }
+                }
+                Ok(tick) = interval_tick.recv() => {
+                    let _guard = self.watchdog.track("order_manager", "interval_tick");
+                    for order in self.conditional.expire(tick.event_time) {
+                        info!("Dropped order {}, trigger expired at {}", order.id, tick.event_time);
+                    }
+                    if let Some(windows) = &self.windows {
+                        for order in windows.release(tick.event_time) {
+                            self.submit(order);
+                        }
+                    }
+                    // Orders waiting for the rate window to pass are released on the next tick
+                    if let Some(throttle) = &self.throttle {
+                        self.release(throttle, tick.event_time);
+                    }
+                    if let Some(pause) = &self.pause {
+                        self.pull_quotes(pause, tick.event_time);
+                    }
+                    if let Some(experiment) = &self.experiment {
+                        self.report_experiment(experiment.report_due(tick.event_time));
+                    }
+                }
+                Ok(finished) = simulation_finished.recv() => {
+                    if let Some(experiment) = &self.experiment {
+                        self.report_experiment(Some(experiment.report(finished.event_time)));
+                    }
+                }
+                Ok(event) = venue_events.recv() => {
+                    if let Some(pause) = &self.pause {
+                        info!("SimpleOrderManager scheduled venue event: {}", event);
+                        pause.schedule(event);
+                    }
+                }
+                Ok(control) = trading_controls.recv() => {
+                    info!("SimpleOrderManager received trading control: {}", control);
+                    self.control(&control);
                 }
                 _ = shutdown.cancelled() => {
                     break;
@@ -49,4 +309,85 @@ impl OrderManager for SimpleOrderManager {
         }
         Ok(())
     }
+
+    fn open_orders(&self, scope: &TradingScope) -> Vec<ExecutionOrderId> {
+        self.open
+            .lock()
+            .values()
+            .filter(|order| scope.contains(order))
+            .map(|order| order.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rust_decimal_macros::dec;
+    use test_log::test;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use crate::StrategyBudget;
+
+    use super::*;
+
+    fn order(strategy: &Arc<Strategy>) -> Arc<ExecutionOrder> {
+        ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .strategy(Some(strategy.clone()))
+            .order_type(ExecutionOrderType::Taker)
+            .side(MarketSide::Buy)
+            .price(dec!(60000))
+            .quantity(dec!(0.01))
+            .build()
+            .into()
+    }
+
+    #[test(tokio::test)]
+    async fn test_throttles_strategies_apart() {
+        let pubsub = Arc::new(PubSub::new());
+        let throttle = OrderThrottle::builder()
+            .default_budget(StrategyBudget {
+                max_orders_per_minute: 1,
+                max_open_orders: u32::MAX,
+            })
+            .build();
+        let order_manager = Arc::new(
+            SimpleOrderManager::builder()
+                .pubsub(pubsub.clone())
+                .throttle(Some(Arc::new(throttle)))
+                .build(),
+        );
+        let mut venue_orders = pubsub.subscribe::<VenueOrder>();
+        let shutdown = CancellationToken::new();
+        let manager = order_manager.clone();
+        let token = shutdown.clone();
+        let handle = tokio::spawn(async move { manager.start(token).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The allocation used up its budget, the orders of the hedge still go out
+        let allocation = Arc::new(Strategy::builder().name("allocation".into()).description(None).build());
+        let hedge = Arc::new(Strategy::builder().name("delta_hedge".into()).description(None).build());
+        let orders = [order(&allocation), order(&allocation), order(&hedge)];
+        for order in &orders {
+            pubsub.publish::<ExecutionOrder>(order.clone());
+        }
+
+        let mut sent = Vec::new();
+        for _ in 0..2 {
+            let order = tokio::time::timeout(Duration::from_secs(1), venue_orders.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            sent.push(order.id);
+        }
+        assert_eq!(sent, vec![orders[0].id, orders[2].id]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(matches!(venue_orders.try_recv(), Err(TryRecvError::Empty)));
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+    }
 }
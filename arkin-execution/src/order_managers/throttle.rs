@@ -0,0 +1,308 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tracing::debug;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{OrderThrottleConfig, StrategyBudgetConfig};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Budget for the orders of a single strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrategyBudget {
+    pub max_orders_per_minute: u32,
+    pub max_open_orders: u32,
+}
+
+impl Default for StrategyBudget {
+    fn default() -> Self {
+        Self {
+            max_orders_per_minute: u32::MAX,
+            max_open_orders: u32::MAX,
+        }
+    }
+}
+
+impl From<&StrategyBudgetConfig> for StrategyBudget {
+    fn from(config: &StrategyBudgetConfig) -> Self {
+        Self {
+            max_orders_per_minute: config.max_orders_per_minute,
+            max_open_orders: config.max_open_orders,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct StrategyState {
+    queue: VecDeque<Arc<ExecutionOrder>>,
+    sent: VecDeque<OffsetDateTime>,
+    open: HashSet<Uuid>,
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    strategies: HashMap<String, StrategyState>,
+    /// Strategies with queued orders in the order they are served
+    turns: VecDeque<String>,
+    /// Orders sent by the whole account within the window
+    sent: VecDeque<OffsetDateTime>,
+    /// Strategy of every open order
+    open: HashMap<Uuid, String>,
+}
+
+/// Queues execution orders per strategy and releases them round robin within the budget of each strategy and the
+/// account, so a runaway strategy can't starve the others or trip the rate limits of the exchange.
+#[derive(Debug, TypedBuilder)]
+pub struct OrderThrottle {
+    /// Orders per minute for the whole account
+    #[builder(default = u32::MAX)]
+    max_orders_per_minute: u32,
+    #[builder(default)]
+    default_budget: StrategyBudget,
+    #[builder(default)]
+    budgets: HashMap<String, StrategyBudget>,
+    #[builder(default)]
+    state: Mutex<ThrottleState>,
+}
+
+impl OrderThrottle {
+    pub const DEFAULT_STRATEGY: &'static str = "default";
+
+    pub fn from_config(config: &OrderThrottleConfig) -> Self {
+        Self::builder()
+            .max_orders_per_minute(config.max_orders_per_minute)
+            .default_budget((&config.default).into())
+            .budgets(config.strategies.iter().map(|(name, b)| (name.clone(), b.into())).collect())
+            .build()
+    }
+
+    /// Strategy an order is accounted to, orders without a strategy share the default budget.
+    pub fn strategy_of(order: &ExecutionOrder) -> String {
+        match &order.strategy {
+            Some(strategy) => strategy.name.clone(),
+            None => Self::DEFAULT_STRATEGY.to_string(),
+        }
+    }
+
+    pub fn budget(&self, strategy: &str) -> StrategyBudget {
+        self.budgets.get(strategy).copied().unwrap_or(self.default_budget)
+    }
+
+    pub fn enqueue(&self, order: Arc<ExecutionOrder>) {
+        let strategy = Self::strategy_of(&order);
+        let mut state = self.state.lock();
+        let entry = state.strategies.entry(strategy.clone()).or_default();
+        entry.queue.push_back(order);
+        if entry.queue.len() == 1 {
+            state.turns.push_back(strategy);
+        }
+    }
+
+    /// Release the queued orders that fit in the budgets at the given time. Every strategy with budget left gets one
+    /// order per round until the account budget is used up.
+    pub fn release(&self, now: OffsetDateTime) -> Vec<Arc<ExecutionOrder>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        expire(&mut state.sent, now);
+
+        let mut released = Vec::new();
+        let mut skipped = 0;
+        while skipped < state.turns.len() {
+            if state.sent.len() >= self.max_orders_per_minute as usize {
+                debug!("Account order budget used up, {} strategies waiting", state.turns.len());
+                break;
+            }
+            let Some(strategy) = state.turns.pop_front() else {
+                break;
+            };
+            let budget = self.budget(&strategy);
+            let entry = state.strategies.entry(strategy.clone()).or_default();
+            expire(&mut entry.sent, now);
+
+            let within_budget = entry.sent.len() < budget.max_orders_per_minute as usize
+                && entry.open.len() < budget.max_open_orders as usize;
+            if !within_budget {
+                state.turns.push_back(strategy);
+                skipped += 1;
+                continue;
+            }
+
+            let Some(order) = entry.queue.pop_front() else {
+                continue;
+            };
+            entry.sent.push_back(now);
+            entry.open.insert(order.id);
+            state.sent.push_back(now);
+            state.open.insert(order.id, strategy.clone());
+            if !entry.queue.is_empty() {
+                state.turns.push_back(strategy);
+            }
+            skipped = 0;
+            released.push(order);
+        }
+        released
+    }
+
+    /// Free the open order slot of a finished order.
+    pub fn order_closed(&self, order_id: Uuid) {
+        let mut state = self.state.lock();
+        let Some(strategy) = state.open.remove(&order_id) else {
+            return;
+        };
+        if let Some(entry) = state.strategies.get_mut(&strategy) {
+            entry.open.remove(&order_id);
+        }
+    }
+
+    /// Remove the queued orders matching the predicate before they are released.
+    pub fn discard(&self, predicate: impl Fn(&ExecutionOrder) -> bool) -> Vec<Arc<ExecutionOrder>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let mut discarded = Vec::new();
+        for entry in state.strategies.values_mut() {
+            let (matching, rest): (Vec<_>, VecDeque<_>) = entry.queue.drain(..).partition(|order| predicate(order));
+            entry.queue = rest;
+            discarded.extend(matching);
+        }
+        let strategies = &state.strategies;
+        state.turns.retain(|s| strategies.get(s).is_some_and(|e| !e.queue.is_empty()));
+        discarded
+    }
+
+    pub fn queued(&self, strategy: &str) -> usize {
+        self.state.lock().strategies.get(strategy).map(|s| s.queue.len()).unwrap_or(0)
+    }
+
+    pub fn open_orders(&self, strategy: &str) -> usize {
+        self.state.lock().strategies.get(strategy).map(|s| s.open.len()).unwrap_or(0)
+    }
+}
+
+fn expire(sent: &mut VecDeque<OffsetDateTime>, now: OffsetDateTime) {
+    while sent.front().is_some_and(|t| now - *t >= WINDOW) {
+        sent.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn order(strategy: Option<&str>) -> Arc<ExecutionOrder> {
+        ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .strategy(
+                strategy.map(|name| Arc::new(Strategy::builder().name(name.to_string()).description(None).build())),
+            )
+            .order_type(ExecutionOrderType::Taker)
+            .side(MarketSide::Buy)
+            .price(dec!(60000))
+            .quantity(dec!(0.01))
+            .build()
+            .into()
+    }
+
+    fn budget(max_orders_per_minute: u32, max_open_orders: u32) -> StrategyBudget {
+        StrategyBudget {
+            max_orders_per_minute,
+            max_open_orders,
+        }
+    }
+
+    #[test]
+    fn test_orders_per_minute() {
+        let throttle = OrderThrottle::builder().default_budget(budget(2, 10)).build();
+        let now = datetime!(2024-06-01 00:00 UTC);
+        for _ in 0..3 {
+            throttle.enqueue(order(None));
+        }
+
+        assert_eq!(throttle.release(now).len(), 2);
+        assert_eq!(throttle.release(now + Duration::from_secs(59)).len(), 0);
+        assert_eq!(throttle.queued(OrderThrottle::DEFAULT_STRATEGY), 1);
+        assert_eq!(throttle.release(now + Duration::from_secs(60)).len(), 1);
+    }
+
+    #[test]
+    fn test_max_open_orders() {
+        let throttle = OrderThrottle::builder().default_budget(budget(100, 1)).build();
+        let now = datetime!(2024-06-01 00:00 UTC);
+        throttle.enqueue(order(Some("momentum")));
+        throttle.enqueue(order(Some("momentum")));
+
+        let released = throttle.release(now);
+        assert_eq!(released.len(), 1);
+        assert!(throttle.release(now).is_empty());
+
+        throttle.order_closed(released[0].id);
+        assert_eq!(throttle.open_orders("momentum"), 0);
+        assert_eq!(throttle.release(now).len(), 1);
+    }
+
+    #[test]
+    fn test_runaway_strategy_does_not_starve_others() {
+        let throttle = OrderThrottle::builder().max_orders_per_minute(10).build();
+        let now = datetime!(2024-06-01 00:00 UTC);
+        for _ in 0..100 {
+            throttle.enqueue(order(Some("runaway")));
+        }
+        throttle.enqueue(order(Some("mean_reversion")));
+        throttle.enqueue(order(Some("mean_reversion")));
+
+        let released = throttle.release(now);
+        assert_eq!(released.len(), 10);
+        let others = released
+            .iter()
+            .filter(|o| OrderThrottle::strategy_of(o) == "mean_reversion")
+            .count();
+        assert_eq!(others, 2);
+        assert_eq!(throttle.queued("runaway"), 92);
+    }
+
+    #[test]
+    fn test_strategy_budget_overrides_default() {
+        let throttle = OrderThrottle::builder()
+            .default_budget(budget(1, 10))
+            .budgets(HashMap::from([("market_maker".to_string(), budget(5, 10))]))
+            .build();
+        let now = datetime!(2024-06-01 00:00 UTC);
+        for _ in 0..5 {
+            throttle.enqueue(order(Some("market_maker")));
+            throttle.enqueue(order(None));
+        }
+
+        let released = throttle.release(now);
+        assert_eq!(released.len(), 6);
+        assert_eq!(throttle.queued(OrderThrottle::DEFAULT_STRATEGY), 4);
+        assert_eq!(throttle.queued("market_maker"), 0);
+    }
+
+    #[test]
+    fn test_discard() {
+        let throttle = OrderThrottle::builder().default_budget(budget(1, 10)).build();
+        let now = datetime!(2024-06-01 00:00 UTC);
+        throttle.enqueue(order(Some("momentum")));
+        throttle.enqueue(order(Some("momentum")));
+        throttle.enqueue(order(None));
+
+        let discarded = throttle.discard(|o| o.strategy.as_ref().is_some_and(|s| s.name == "momentum"));
+        assert_eq!(discarded.len(), 2);
+        assert_eq!(throttle.queued("momentum"), 0);
+        let released = throttle.release(now);
+        assert_eq!(released.len(), 1);
+        assert_eq!(OrderThrottle::strategy_of(&released[0]), OrderThrottle::DEFAULT_STRATEGY);
+    }
+}
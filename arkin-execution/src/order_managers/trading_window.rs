@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use parking_lot::Mutex;
+use time::{macros::format_description, OffsetDateTime, Time, UtcOffset, Weekday};
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{TradingWindowConfig, TradingWindowPolicy, TradingWindowsConfig};
+
+/// Time a strategy trades the instruments of the window, in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradingWindow {
+    /// Start and end of the hours of the day trading is open
+    pub hours: Option<(Time, Time)>,
+    pub skip_weekends: bool,
+    /// Instrument symbols the window covers, all when empty
+    pub instruments: Vec<String>,
+    pub policy: TradingWindowPolicy,
+}
+
+impl From<&TradingWindowConfig> for TradingWindow {
+    fn from(config: &TradingWindowConfig) -> Self {
+        let format = format_description!("[hour]:[minute]");
+        let hours = config.hours.as_ref().map(|hours| {
+            let (start, end) = hours.split_once('-').expect("Hours of trading window should be HH:MM-HH:MM");
+            (
+                Time::parse(start.trim(), &format).expect("Failed to parse start of trading window"),
+                Time::parse(end.trim(), &format).expect("Failed to parse end of trading window"),
+            )
+        });
+        Self {
+            hours,
+            skip_weekends: config.skip_weekends,
+            instruments: config.instruments.clone(),
+            policy: config.policy,
+        }
+    }
+}
+
+impl TradingWindow {
+    pub fn applies_to(&self, instrument: &Instrument) -> bool {
+        self.instruments.is_empty() || self.instruments.contains(&instrument.symbol)
+    }
+
+    /// True if trading is open at the given time, a window with the same start and end is open all day.
+    pub fn is_open(&self, now: OffsetDateTime) -> bool {
+        let now = now.to_offset(UtcOffset::UTC);
+        if self.skip_weekends && matches!(now.weekday(), Weekday::Saturday | Weekday::Sunday) {
+            return false;
+        }
+        match self.hours {
+            Some((start, end)) if start < end => start <= now.time() && now.time() < end,
+            Some((start, end)) => now.time() >= start || now.time() < end,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for TradingWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.hours {
+            Some((start, end)) => write!(
+                f,
+                "{:02}:{:02}-{:02}:{:02}",
+                start.hour(),
+                start.minute(),
+                end.hour(),
+                end.minute()
+            )?,
+            None => write!(f, "all day")?,
+        }
+        if self.skip_weekends {
+            write!(f, " on weekdays")?;
+        }
+        Ok(())
+    }
+}
+
+/// Restricts the strategies to their trading windows in the order manager. An order outside a window of its strategy
+/// covering its instrument is rejected or queued until all of these windows are open again, as the policy of the
+/// closed window says. Orders without a strategy or of strategies without windows trade at any time.
+#[derive(Debug, Default, TypedBuilder)]
+pub struct TradingWindows {
+    #[builder(default)]
+    strategies: HashMap<String, Vec<TradingWindow>>,
+    /// Orders waiting for their windows to open, in order of arrival
+    #[builder(default)]
+    queued: Mutex<Vec<Arc<ExecutionOrder>>>,
+}
+
+impl TradingWindows {
+    pub fn from_config(config: &TradingWindowsConfig) -> Self {
+        Self::builder()
+            .strategies(
+                config
+                    .strategies
+                    .iter()
+                    .map(|(name, windows)| (name.clone(), windows.iter().map(|w| w.into()).collect()))
+                    .collect(),
+            )
+            .build()
+    }
+
+    /// Window of the strategy of the order that is closed at the given time, none if the order can trade.
+    pub fn closed(&self, order: &ExecutionOrder, now: OffsetDateTime) -> Option<&TradingWindow> {
+        let strategy = order.strategy.as_ref()?;
+        self.strategies
+            .get(&strategy.name)?
+            .iter()
+            .filter(|w| w.applies_to(&order.instrument))
+            .find(|w| !w.is_open(now))
+    }
+
+    /// Pass the order on if the windows of its strategy are open, otherwise it is rejected or queued.
+    pub fn admit(&self, order: Arc<ExecutionOrder>) -> Option<Arc<ExecutionOrder>> {
+        let Some(window) = self.closed(&order, order.updated_at) else {
+            return Some(order);
+        };
+        let strategy = order.strategy.as_ref().map(|s| s.name.as_str()).unwrap_or_default();
+        match window.policy {
+            TradingWindowPolicy::Reject => {
+                warn!("Rejected order {}, strategy {} trades {} only", order.id, strategy, window);
+            }
+            TradingWindowPolicy::Queue => {
+                info!(
+                    "Queued order {} until strategy {} trades again ({})",
+                    order.id, strategy, window
+                );
+                self.queued.lock().push(order);
+            }
+        }
+        None
+    }
+
+    /// Take the queued orders whose windows opened.
+    pub fn release(&self, now: OffsetDateTime) -> Vec<Arc<ExecutionOrder>> {
+        let mut queued = self.queued.lock();
+        if queued.is_empty() {
+            return Vec::new();
+        }
+        let (open, closed) = std::mem::take(&mut *queued)
+            .into_iter()
+            .partition::<Vec<_>, _>(|o| self.closed(o, now).is_none());
+        *queued = closed;
+        open.into_iter()
+            .map(|order| {
+                info!("Released order {}, trading window opened at {}", order.id, now);
+                let mut order = (*order).clone();
+                order.updated_at = now;
+                Arc::new(order)
+            })
+            .collect()
+    }
+
+    /// Drop the queued orders matching the predicate.
+    pub fn discard(&self, predicate: impl Fn(&ExecutionOrder) -> bool) -> Vec<Arc<ExecutionOrder>> {
+        let mut queued = self.queued.lock();
+        let (dropped, kept) = std::mem::take(&mut *queued)
+            .into_iter()
+            .partition::<Vec<_>, _>(|o| predicate(o));
+        *queued = kept;
+        dropped
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.lock().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn order(instrument: Arc<Instrument>, updated_at: OffsetDateTime) -> Arc<ExecutionOrder> {
+        Arc::new(
+            ExecutionOrder::builder()
+                .portfolio(test_portfolio())
+                .instrument(instrument)
+                .strategy(Some(test_strategy()))
+                .order_type(ExecutionOrderType::Taker)
+                .side(MarketSide::Buy)
+                .price(dec!(100))
+                .quantity(dec!(1))
+                .updated_at(updated_at)
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_trading_windows() {
+        let btc = test_inst_binance_btc_usdt_perp();
+        let eth = test_inst_binance_eth_usdt_perp();
+        let config = TradingWindowsConfig {
+            strategies: HashMap::from([(
+                test_strategy().name.clone(),
+                vec![
+                    TradingWindowConfig {
+                        hours: Some("22:00-08:00".to_string()),
+                        skip_weekends: false,
+                        instruments: vec![],
+                        policy: TradingWindowPolicy::Queue,
+                    },
+                    TradingWindowConfig {
+                        hours: None,
+                        skip_weekends: true,
+                        instruments: vec![eth.symbol.clone()],
+                        policy: TradingWindowPolicy::Reject,
+                    },
+                ],
+            )]),
+        };
+        let windows = TradingWindows::from_config(&config);
+
+        // Friday night, the window runs over midnight
+        let night = datetime!(2025-01-03 23:00 UTC);
+        assert!(windows.admit(order(btc.clone(), night)).is_some());
+        assert!(windows.admit(order(eth.clone(), night)).is_some());
+
+        // Outside the hours the order waits for the window to open
+        let noon = datetime!(2025-01-03 12:00 UTC);
+        let queued = order(btc.clone(), noon);
+        assert!(windows.admit(queued.clone()).is_none());
+        assert_eq!(windows.queued(), 1);
+        assert!(windows.release(datetime!(2025-01-03 21:59 UTC)).is_empty());
+        let released = windows.release(datetime!(2025-01-03 22:00 UTC));
+        assert_eq!(released[0].id, queued.id);
+        assert_eq!(released[0].updated_at, datetime!(2025-01-03 22:00 UTC));
+        assert_eq!(windows.queued(), 0);
+
+        // Weekends are closed for ETH only and its orders are rejected
+        let saturday = datetime!(2025-01-04 02:00 UTC);
+        assert!(windows.admit(order(btc.clone(), saturday)).is_some());
+        assert!(windows.admit(order(eth.clone(), saturday)).is_none());
+        assert_eq!(windows.queued(), 0);
+
+        // Orders without a strategy trade at any time
+        let mut manual = (*order(btc.clone(), noon)).clone();
+        manual.strategy = None;
+        assert!(windows.admit(Arc::new(manual)).is_some());
+
+        assert!(windows.admit(order(btc, noon)).is_none());
+        assert_eq!(windows.discard(|_| true).len(), 1);
+    }
+}
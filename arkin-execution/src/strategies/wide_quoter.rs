@@ -8,8 +8,10 @@ use tracing::info;
 
 use arkin_core::prelude::*;
 
-use crate::{ExecutionStrategy, OrderManager, StrategyError};
+use crate::{ExecutionStrategy, OrderManager, QuoteGuard, StrategyError};
 
+/// Quotes both sides wide of the mid and requotes once the price moved. The quote guard limits the requotes,
+/// randomizes the distance of the quotes from the mid and widens the spread after repeated pick-offs.
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct WideQuoter {
     pubsub: PubSub,
@@ -17,6 +19,8 @@ pub struct WideQuoter {
     order_manager: Arc<dyn OrderManager>,
     spread_from_mid: Decimal,
     requote_price_move_pct: Decimal,
+    #[builder(default)]
+    guard: Arc<QuoteGuard>,
     shutdown: CancellationToken,
 }
 
@@ -25,7 +29,8 @@ impl ExecutionStrategy for WideQuoter {
     async fn start(&self) -> Result<(), StrategyError> {
         info!("Starting WideQuoter for order {}", self.execution_order_id);
         // let order = VenueOrder::builder().execution_order_id(self.execution_order_id)..build().unwrap();
-        // TODO: Place the order
+        // TODO: Place the order at self.guard.quote_price(side, mid, self.spread_from_mid, now), requote only when
+        // self.guard.requote(now) allows it and feed the fills and mids to self.guard.fill and self.guard.mark
         tokio::select! {
             _ = self.shutdown.cancelled() => {
                 info!("Order {} is cancelled", self.execution_order_id);
@@ -13,6 +13,9 @@ use crate::{ExecutorError, OrderManagerError, StrategyError};
 pub trait OrderManager: std::fmt::Debug + Send + Sync {
     async fn start(&self, shutdown: CancellationToken) -> Result<(), OrderManagerError>;
 
+    /// Orders sent to the venue that are not finalized yet and fall in the scope.
+    fn open_orders(&self, scope: &TradingScope) -> Vec<ExecutionOrderId>;
+
     // async fn order_by_id(&self, id: ExecutionOrderId) -> Option<Arc<ExecutionOrder>>;
 
     // async fn list_new_orders(&self) -> Vec<Arc<ExecutionOrder>>;
@@ -72,4 +75,29 @@ pub trait Executor: std::fmt::Debug + Send + Sync {
     async fn cancel_orders(&self, ids: Vec<VenueOrderId>) -> Result<(), ExecutorError>;
     async fn cancel_orders_by_instrument(&self, instrument: Arc<Instrument>) -> Result<(), ExecutorError>;
     async fn cancel_all_orders(&self) -> Result<(), ExecutorError>;
+
+    /// Move funds between the wallets of the account or its sub-accounts, returns the id the venue gave the
+    /// transfer.
+    async fn transfer(&self, transfer: Arc<AccountTransfer>) -> Result<String, ExecutorError> {
+        Err(ExecutorError::InvalidTransfer(format!(
+            "{} not supported by this executor",
+            transfer.id
+        )))
+    }
+
+    /// Write the positions, open orders and balances of a simulated venue into a checkpoint, live venues keep their
+    /// own state.
+    fn checkpoint(&self, _checkpoint: &mut SimulationCheckpoint) {}
+
+    /// Restore a simulated venue from a checkpoint before it starts, the positions and orders belong to the portfolio.
+    fn restore(
+        &self,
+        _checkpoint: &SimulationCheckpoint,
+        _portfolio: &Arc<Portfolio>,
+        _instruments: &[Arc<Instrument>],
+    ) -> Result<(), ExecutorError> {
+        Err(ExecutorError::Unknown(
+            "restoring from a checkpoint not supported by this executor".into(),
+        ))
+    }
 }
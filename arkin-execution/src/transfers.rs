@@ -0,0 +1,254 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use time::{Date, OffsetDateTime};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{AssetTransferLimits, Executor, ExecutorError, TransferLimitsConfig};
+
+/// Executes the [`AccountTransfer`] requests of the operator and the allocation on the venue once they pass the
+/// limits: a maximum per transfer and per UTC day of every listed asset, and only the listed sub-accounts. Every
+/// request ends in an [`AccountTransferUpdate`], executed, rejected by the limits or failed on the venue, which is the
+/// audit trail of the funds moved. The transfers executed earlier in the day are reloaded from that trail on start.
+#[derive(Debug, TypedBuilder)]
+pub struct TransferManager {
+    pubsub: Arc<PubSub>,
+    executor: Arc<dyn Executor>,
+    /// Reloads the transfers of the day on start, none in tests
+    #[builder(default)]
+    persistence: Option<Arc<PersistenceService>>,
+    /// Limits of the assets that can be transferred by symbol
+    limits: HashMap<String, AssetTransferLimits>,
+    /// Emails of the sub-accounts funds can be moved to and from
+    #[builder(default)]
+    sub_accounts: Vec<String>,
+    /// Quantity of every asset transferred by UTC day of the manager's clock
+    #[builder(default)]
+    transferred: Mutex<HashMap<Date, HashMap<String, Decimal>>>,
+}
+
+impl TransferManager {
+    pub fn from_config(
+        config: &TransferLimitsConfig,
+        pubsub: Arc<PubSub>,
+        executor: Arc<dyn Executor>,
+        persistence: Arc<PersistenceService>,
+    ) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .executor(executor)
+            .persistence(Some(persistence))
+            .limits(config.assets.clone())
+            .sub_accounts(config.sub_accounts.clone())
+            .build()
+    }
+
+    fn transferred_on(&self, day: Date, asset: &str) -> Decimal {
+        self.transferred
+            .lock()
+            .get(&day)
+            .and_then(|transferred| transferred.get(asset).copied())
+            .unwrap_or_default()
+    }
+
+    /// Add the quantity to the total of the day, the totals of the days before are no longer needed.
+    fn book(&self, day: Date, asset: &str, quantity: Decimal) {
+        let mut transferred = self.transferred.lock();
+        transferred.retain(|d, _| *d >= day);
+        *transferred.entry(day).or_default().entry(asset.to_string()).or_default() += quantity;
+    }
+
+    /// Take the transfers executed earlier on the day of `now` from the history, so a restart doesn't reset the daily
+    /// limits.
+    pub async fn reload(&self, now: OffsetDateTime) -> Result<(), ExecutorError> {
+        let Some(persistence) = &self.persistence else {
+            return Ok(());
+        };
+        let day = now.date();
+        let transferred = persistence
+            .account_transfer_store
+            .executed_since(day.midnight().assume_utc())
+            .await?;
+        for (asset, quantity) in &transferred {
+            info!("Transferred {} {} earlier today", quantity, asset);
+        }
+        self.transferred.lock().insert(day, transferred);
+        Ok(())
+    }
+
+    /// Why the transfer breaks the limits at `now`, None if it can be executed. The daily limit counts the transfers of
+    /// the UTC day of `now`, never of the event time set by the requester.
+    pub fn check(&self, transfer: &AccountTransfer, now: OffsetDateTime) -> Option<String> {
+        if transfer.quantity <= Decimal::ZERO {
+            return Some(format!("invalid quantity {}", transfer.quantity));
+        }
+        if transfer.from == transfer.to && transfer.from_sub_account == transfer.to_sub_account {
+            return Some("the funds are already in the wallet".to_string());
+        }
+        let asset = &transfer.asset.symbol;
+        let Some(limits) = self.limits.get(asset) else {
+            return Some(format!("asset {} can't be transferred", asset));
+        };
+        let mut sub_accounts = transfer.from_sub_account.iter().chain(transfer.to_sub_account.iter());
+        if let Some(sub_account) = sub_accounts.find(|s| !self.sub_accounts.contains(s)) {
+            return Some(format!("sub-account {} is not allowed", sub_account));
+        }
+        if transfer.quantity > limits.max_quantity {
+            return Some(format!(
+                "{} {} exceeds the transfer limit of {}",
+                transfer.quantity, asset, limits.max_quantity
+            ));
+        }
+        let transferred = self.transferred_on(now.date(), asset);
+        if transferred + transfer.quantity > limits.max_daily_quantity {
+            return Some(format!(
+                "{} {} exceeds the daily limit of {}, {} transferred today",
+                transfer.quantity, asset, limits.max_daily_quantity, transferred
+            ));
+        }
+        None
+    }
+
+    /// Check the transfer against the limits at `now` and execute it on the venue, publishes and returns the outcome.
+    pub async fn handle(&self, transfer: Arc<AccountTransfer>, now: OffsetDateTime) -> Arc<AccountTransferUpdate> {
+        info!("Transfer requested: {}", transfer);
+        let (status, venue_transfer_id, reason) = match self.check(&transfer, now) {
+            Some(reason) => (AccountTransferStatus::Rejected, None, reason),
+            None => match self.executor.transfer(transfer.clone()).await {
+                Ok(venue_id) => {
+                    self.book(now.date(), &transfer.asset.symbol, transfer.quantity);
+                    (AccountTransferStatus::Executed, Some(venue_id), String::new())
+                }
+                Err(e) => (AccountTransferStatus::Failed, None, e.to_string()),
+            },
+        };
+        let update: Arc<AccountTransferUpdate> = AccountTransferUpdate::builder()
+            .event_time(now)
+            .transfer(transfer)
+            .status(status)
+            .venue_transfer_id(venue_transfer_id)
+            .reason(reason)
+            .build()
+            .into();
+        match update.status {
+            AccountTransferStatus::Executed => info!("Transfer update: {}", update),
+            AccountTransferStatus::Rejected => warn!("Transfer update: {}", update),
+            AccountTransferStatus::Failed => error!("Transfer update: {}", update),
+        }
+        self.pubsub.publish::<AccountTransferUpdate>(update.clone());
+        update
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), ExecutorError> {
+        info!("Starting transfer manager...");
+        let mut transfers = self.pubsub.subscribe::<AccountTransfer>();
+        self.reload(OffsetDateTime::now_utc()).await?;
+        loop {
+            tokio::select! {
+                Ok(transfer) = transfers.recv() => {
+                    self.handle(transfer, OffsetDateTime::now_utc()).await;
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::MockExecutor;
+
+    fn transfer(quantity: Decimal, to_sub_account: Option<&str>) -> Arc<AccountTransfer> {
+        asset_transfer(test_usdt_asset(), quantity, to_sub_account)
+    }
+
+    fn asset_transfer(asset: Arc<Asset>, quantity: Decimal, to_sub_account: Option<&str>) -> Arc<AccountTransfer> {
+        AccountTransfer::builder()
+            .event_time(datetime!(2025-01-01 12:00 UTC))
+            .portfolio(test_portfolio())
+            .asset(asset)
+            .quantity(quantity)
+            .from(AccountType::Spot)
+            .to(AccountType::UsdmFutures)
+            .to_sub_account(to_sub_account.map(|s| s.to_string()))
+            .requested_by("operator".to_string())
+            .build()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn test_transfer_limits() {
+        let mut executor = MockExecutor::new();
+        executor.expect_transfer().times(4).returning(|t| Ok(t.id.to_string()));
+        let limits = |max_quantity: Decimal, max_daily_quantity: Decimal| AssetTransferLimits {
+            max_quantity,
+            max_daily_quantity,
+        };
+        let manager = TransferManager::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .executor(Arc::new(executor))
+            .limits(HashMap::from([
+                (test_usdt_asset().symbol.clone(), limits(dec!(1000), dec!(1500))),
+                (test_btc_asset().symbol.clone(), limits(dec!(1), dec!(2))),
+            ]))
+            .sub_accounts(vec!["mm@example.com".to_string()])
+            .build();
+        let now = datetime!(2025-01-01 12:00 UTC);
+
+        // Within the limits the transfer is executed on the venue
+        let update = manager.handle(transfer(dec!(800), None), now).await;
+        assert_eq!(update.status, AccountTransferStatus::Executed);
+        assert!(update.venue_transfer_id.is_some());
+
+        // Over the single and daily limits or to an unknown sub-account it never reaches the venue
+        let update = manager.handle(transfer(dec!(1200), None), now).await;
+        assert_eq!(update.status, AccountTransferStatus::Rejected);
+        assert!(update.reason.contains("transfer limit"));
+        let update = manager.handle(transfer(dec!(800), None), now).await;
+        assert!(update.reason.contains("daily limit"));
+        let update = manager.handle(transfer(dec!(100), Some("other@example.com")), now).await;
+        assert!(update.reason.contains("sub-account"));
+        let update = manager.handle(transfer(dec!(-1), None), now).await;
+        assert_eq!(update.status, AccountTransferStatus::Rejected);
+
+        let update = manager.handle(transfer(dec!(700), Some("mm@example.com")), now).await;
+        assert_eq!(update.status, AccountTransferStatus::Executed);
+        let day = now.date();
+        assert_eq!(manager.transferred_on(day, &test_usdt_asset().symbol), dec!(1500));
+
+        // Every asset has its own limits, in its own quantities
+        let update = manager.handle(asset_transfer(test_btc_asset(), dec!(1.5), None), now).await;
+        assert!(update.reason.contains("transfer limit"));
+        let update = manager.handle(asset_transfer(test_btc_asset(), dec!(0.5), None), now).await;
+        assert_eq!(update.status, AccountTransferStatus::Executed);
+        assert_eq!(manager.transferred_on(day, &test_btc_asset().symbol), dec!(0.5));
+        let update = manager.handle(asset_transfer(test_eth_asset(), dec!(1), None), now).await;
+        assert!(update.reason.contains("can't be transferred"));
+
+        // A request dated on another day doesn't reset the limit of the day of the manager's clock
+        let mut backdated = (*transfer(dec!(100), None)).clone();
+        backdated.event_time = datetime!(2024-12-31 12:00 UTC);
+        let update = manager.handle(backdated.into(), now).await;
+        assert!(update.reason.contains("daily limit"));
+        assert_eq!(manager.transferred_on(day, &test_usdt_asset().symbol), dec!(1500));
+
+        // The limit starts over on the next day
+        let update = manager.handle(transfer(dec!(100), None), now + time::Duration::days(1)).await;
+        assert_eq!(update.status, AccountTransferStatus::Executed);
+        assert_eq!(
+            manager.transferred_on(day.next_day().unwrap(), &test_usdt_asset().symbol),
+            dec!(100)
+        );
+    }
+}
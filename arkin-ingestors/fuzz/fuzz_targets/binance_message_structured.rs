@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use arkin_ingestors::fuzzing::{parse_binance_message, BINANCE_SEED_MESSAGES};
+use arkin_ingestors_fuzz::MutatedMessage;
+
+// Mutate real messages field by field, this reaches the typed parsing much faster than raw bytes
+fuzz_target!(|input: MutatedMessage| {
+    let data = input.apply(BINANCE_SEED_MESSAGES);
+    let _ = parse_binance_message(data.as_bytes());
+});
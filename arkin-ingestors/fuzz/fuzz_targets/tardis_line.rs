@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use arkin_ingestors::fuzzing::parse_tardis_line;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_tardis_line(data);
+});
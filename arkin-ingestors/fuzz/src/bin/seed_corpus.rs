@@ -0,0 +1,69 @@
+//! Seed the fuzz corpus with recorded real messages.
+//!
+//! Usage: `cargo run --bin seed_corpus -- <archive directory> [corpus directory]`
+//!
+//! Every distinct message in the raw feed archives of the Binance ingestor becomes a corpus entry of the
+//! `binance_message` target, the built in seed messages are always added.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use arkin_ingestors::{
+    fuzzing::{BINANCE_SEED_MESSAGES, TARDIS_SEED_LINES},
+    prelude::{list_archives, ArchiveReader, BinanceIngestor},
+};
+
+fn write_entry(dir: &Path, data: &str) -> io::Result<bool> {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let path = dir.join(format!("{:016x}", hasher.finish()));
+    if path.exists() {
+        return Ok(false);
+    }
+    fs::write(path, data)?;
+    Ok(true)
+}
+
+fn run(archives: &Path, corpus: &Path) -> io::Result<()> {
+    let binance_dir = corpus.join("binance_message");
+    let tardis_dir = corpus.join("tardis_line");
+    fs::create_dir_all(&binance_dir)?;
+    fs::create_dir_all(&tardis_dir)?;
+
+    for message in BINANCE_SEED_MESSAGES {
+        write_entry(&binance_dir, message)?;
+    }
+    for line in TARDIS_SEED_LINES {
+        write_entry(&tardis_dir, line)?;
+    }
+
+    let mut added = 0;
+    for archive in list_archives(archives, BinanceIngestor::SOURCE)? {
+        for record in ArchiveReader::open(&archive)? {
+            if write_entry(&binance_dir, &record?.data)? {
+                added += 1;
+            }
+        }
+    }
+    println!("Added {} recorded messages to {}", added, binance_dir.display());
+    Ok(())
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(archives) = args.next().map(PathBuf::from) else {
+        eprintln!("Usage: seed_corpus <archive directory> [corpus directory]");
+        process::exit(2);
+    };
+    let corpus = args.next().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("corpus"));
+    if let Err(e) = run(&archives, &corpus) {
+        eprintln!("Failed to seed corpus: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,101 @@
+use arbitrary::Arbitrary;
+use serde_json::{Map, Number, Value};
+
+/// A JSON value the structured targets put into messages. Numbers as strings are what Binance sends for prices and
+/// quantities, so they get their own variant.
+#[derive(Debug, Arbitrary)]
+pub enum FuzzValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    NumericStr(i64, u8),
+    Array(Vec<i64>),
+}
+
+impl From<&FuzzValue> for Value {
+    fn from(value: &FuzzValue) -> Self {
+        match value {
+            FuzzValue::Null => Value::Null,
+            FuzzValue::Bool(b) => Value::Bool(*b),
+            FuzzValue::Int(i) => Value::Number((*i).into()),
+            FuzzValue::Float(f) => Number::from_f64(*f).map(Value::Number).unwrap_or(Value::Null),
+            FuzzValue::Str(s) => Value::String(s.clone()),
+            FuzzValue::NumericStr(mantissa, scale) => {
+                let digits = mantissa.unsigned_abs().to_string();
+                let scale = (*scale as usize).min(digits.len());
+                let (int, frac) = digits.split_at(digits.len() - scale);
+                let sign = if *mantissa < 0 { "-" } else { "" };
+                Value::String(format!("{}{}.{}", sign, if int.is_empty() { "0" } else { int }, frac))
+            }
+            FuzzValue::Array(values) => Value::Array(values.iter().map(|v| Value::Number((*v).into())).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum Mutation {
+    /// Remove the n-th field
+    Remove(u8),
+    /// Replace the value of the n-th field
+    Replace(u8, FuzzValue),
+    /// Add a field, short keys collide with the real ones
+    Insert(String, FuzzValue),
+    /// Cut the serialized message after n bytes
+    Truncate(u16),
+}
+
+/// A real message with a list of mutations applied to it.
+#[derive(Debug, Arbitrary)]
+pub struct MutatedMessage {
+    pub seed: u8,
+    pub mutations: Vec<Mutation>,
+}
+
+impl MutatedMessage {
+    pub fn apply(&self, seeds: &[&str]) -> String {
+        let seed = seeds[self.seed as usize % seeds.len()];
+        let mut fields = match serde_json::from_str::<Value>(seed) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+
+        let mut truncate = None;
+        for mutation in &self.mutations {
+            match mutation {
+                Mutation::Remove(n) => {
+                    if let Some(key) = nth_key(&fields, *n) {
+                        fields.remove(&key);
+                    }
+                }
+                Mutation::Replace(n, value) => {
+                    if let Some(key) = nth_key(&fields, *n) {
+                        fields.insert(key, value.into());
+                    }
+                }
+                Mutation::Insert(key, value) => {
+                    fields.insert(key.clone(), value.into());
+                }
+                Mutation::Truncate(n) => truncate = Some(*n as usize),
+            }
+        }
+
+        let mut message = Value::Object(fields).to_string();
+        if let Some(n) = truncate {
+            let mut end = n.min(message.len());
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+        message
+    }
+}
+
+fn nth_key(fields: &Map<String, Value>, n: u8) -> Option<String> {
+    match fields.is_empty() {
+        true => None,
+        false => fields.keys().nth(n as usize % fields.len()).cloned(),
+    }
+}
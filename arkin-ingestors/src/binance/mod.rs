@@ -1,5 +1,5 @@
 mod provider;
-mod swaps;
+pub(crate) mod swaps;
 
 pub use provider::BinanceIngestor;
 pub use provider::BinanceIngestorBuilder;
@@ -1,19 +1,22 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use async_tungstenite::tungstenite::Message;
 use serde::Serialize;
+use time::OffsetDateTime;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 use tracing::{debug, error, info, warn};
 use typed_builder::TypedBuilder;
 use url::Url;
 
+use arkin_binance::BinanceAdapter;
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 
 use crate::binance::swaps::BinanceSwapEvent;
+use crate::recorder::{ArchiveReader, FeedRecorder};
 use crate::traits::Ingestor;
 use crate::ws::WebSocketManager;
 use crate::IngestorError;
@@ -23,6 +26,8 @@ use crate::IngestorError;
 pub struct BinanceIngestor {
     pubsub: Arc<PubSub>,
     persistence: Arc<PersistenceService>,
+    /// Shared with the executor, resolves the symbols of the feed to instruments
+    adapter: Arc<dyn VenueAdapter>,
     url: Url,
     channels: Vec<String>,
     #[builder(default)]
@@ -31,14 +36,64 @@ pub struct BinanceIngestor {
     api_secret: Option<String>,
     connections_per_manager: usize,
     duplicate_lookback: usize,
+    #[builder(default)]
+    recorder: Option<Arc<FeedRecorder>>,
 }
 
 impl BinanceIngestor {
-    async fn process_event(pubsub: Arc<PubSub>, persistence: Arc<PersistenceService>, data: String) {
+    /// Name of the raw feed archives written by this ingestor
+    pub const SOURCE: &'static str = "binance";
+
+    /// Feed the messages of a raw feed archive through the parser, optionally limited to the messages received in
+    /// [start, end). Returns the number of replayed messages.
+    pub async fn replay(
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+        archive: &Path,
+        start: Option<OffsetDateTime>,
+        end: Option<OffsetDateTime>,
+    ) -> Result<u64, IngestorError> {
+        info!("Replaying raw feed archive {}", archive.display());
+        let symbols = SymbolMap::default();
+        let mut replayed = 0;
+        for record in ArchiveReader::open(archive)? {
+            let record = record?;
+            if start.is_some_and(|s| record.received_at < s) || end.is_some_and(|e| record.received_at >= e) {
+                continue;
+            }
+            Self::process_event(pubsub.clone(), persistence.clone(), &symbols, record.data).await;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    /// Instrument of a venue symbol, looked up in the symbol registry the first time the symbol shows up in the feed.
+    async fn instrument(
+        persistence: &PersistenceService,
+        symbols: &SymbolMap,
+        venue_symbol: &str,
+    ) -> Result<Arc<Instrument>, PersistenceError> {
+        if let Some(instrument) = symbols.instrument(venue_symbol) {
+            return Ok(instrument);
+        }
+        let instrument = persistence
+            .symbol_registry
+            .instrument(BinanceAdapter::VENUE, venue_symbol)
+            .await?;
+        symbols.insert(instrument.clone());
+        Ok(instrument)
+    }
+
+    async fn process_event(
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+        symbols: &SymbolMap,
+        data: String,
+    ) {
         match serde_json::from_str::<BinanceSwapEvent>(&data) {
             Ok(e) => {
                 debug!("BinanceSwapEvent: {}", e);
-                if let Ok(instrument) = persistence.instrument_store.read_by_venue_symbol(&e.venue_symbol()).await {
+                if let Ok(instrument) = Self::instrument(&persistence, symbols, &e.venue_symbol()).await {
                     debug!("Instrument found: {}", instrument.symbol);
                     match e {
                         BinanceSwapEvent::AggTrade(trade) => {
@@ -112,12 +167,24 @@ impl Ingestor for BinanceIngestor {
             ws_manager.run(tx, subscription, ws_manager_shutdown).await.unwrap();
         });
 
+        if let Some(recorder) = self.recorder.clone() {
+            let recorder_shutdown = shutdown.clone();
+            ws_manager_tracker.spawn(async move {
+                if let Err(e) = recorder.run(recorder_shutdown).await {
+                    error!("Raw feed recorder failed: {}", e);
+                }
+            });
+        }
+
         loop {
             tokio::select! {
                 res = rx.recv_async() => {
                     match res {
                         Ok(data) => {
-                            Self::process_event(self.pubsub.clone(), self.persistence.clone(), data).await;
+                            if let Some(recorder) = &self.recorder {
+                                recorder.record(&data);
+                            }
+                            Self::process_event(self.pubsub.clone(), self.persistence.clone(), self.adapter.symbols(), data).await;
                         }
                         Err(e) => {
                             error!("{}", e);
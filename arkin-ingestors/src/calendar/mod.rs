@@ -0,0 +1,5 @@
+mod parser;
+mod service;
+
+pub use parser::{parse_ics, parse_json, CalendarEntry};
+pub use service::CalendarIngestor;
@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime, PrimitiveDateTime,
+};
+
+use arkin_core::prelude::*;
+
+/// Event as read from a calendar, before its symbol is resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarEntry {
+    pub uid: String,
+    pub title: String,
+    pub importance: CalendarImportance,
+    /// Venue symbol the event is about, like an announced delisting
+    pub symbol: Option<String>,
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonEntry {
+    id: String,
+    title: String,
+    /// RFC 3339 timestamp
+    start: String,
+    #[serde(default)]
+    end: Option<String>,
+    #[serde(default)]
+    importance: Option<CalendarImportance>,
+    #[serde(default)]
+    symbol: Option<String>,
+}
+
+/// Parse a JSON array of events like
+/// `[{"id": "fomc-2025-01", "title": "FOMC", "start": "2025-01-29T19:00:00Z", "importance": "high"}]`.
+pub fn parse_json(data: &str, default_importance: CalendarImportance) -> Result<Vec<CalendarEntry>> {
+    let entries = serde_json::from_str::<Vec<JsonEntry>>(data)?;
+    entries
+        .into_iter()
+        .map(|e| {
+            let start = OffsetDateTime::parse(&e.start, &Rfc3339)?;
+            let end = match &e.end {
+                Some(end) => OffsetDateTime::parse(end, &Rfc3339)?,
+                None => start,
+            };
+            Ok(CalendarEntry {
+                uid: e.id,
+                title: e.title,
+                importance: e.importance.unwrap_or(default_importance),
+                symbol: e.symbol,
+                start,
+                end,
+            })
+        })
+        .collect()
+}
+
+/// Parse the events of an iCalendar (RFC 5545). Times without a zone are taken as UTC, all-day events span the day.
+/// The importance comes from the priority of the event, 1 to 4 is high, 5 medium and 6 to 9 low.
+pub fn parse_ics(data: &str, default_importance: CalendarImportance) -> Result<Vec<CalendarEntry>> {
+    // Long lines are folded onto lines starting with a space or tab
+    let mut lines = Vec::<String>::new();
+    for line in data.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(folded) if !lines.is_empty() => lines.last_mut().expect("Checked non-empty").push_str(folded),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut event: Option<Vec<(String, String, String)>> = None;
+    for line in lines {
+        match line.as_str() {
+            "BEGIN:VEVENT" => event = Some(Vec::new()),
+            "END:VEVENT" => {
+                if let Some(properties) = event.take() {
+                    entries.push(ics_entry(&properties, default_importance)?);
+                }
+            }
+            _ => {
+                if let (Some(properties), Some((name, value))) = (event.as_mut(), line.split_once(':')) {
+                    let (name, params) = name.split_once(';').unwrap_or((name, ""));
+                    properties.push((name.to_uppercase(), params.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn ics_entry(properties: &[(String, String, String)], default_importance: CalendarImportance) -> Result<CalendarEntry> {
+    let get = |name: &str| properties.iter().find(|(n, _, _)| n == name);
+    let (_, _, uid) = get("UID").ok_or_else(|| anyhow!("calendar event without UID"))?;
+    let (_, params, value) = get("DTSTART").ok_or_else(|| anyhow!("calendar event {} without DTSTART", uid))?;
+    let start = ics_time(params, value)?;
+    let all_day = params.contains("VALUE=DATE");
+    let end = match get("DTEND") {
+        Some((_, params, value)) => ics_time(params, value)?,
+        None if all_day => start + time::Duration::DAY,
+        None => start,
+    };
+    let importance = match get("PRIORITY").and_then(|(_, _, p)| p.trim().parse::<u8>().ok()) {
+        Some(1..=4) => CalendarImportance::High,
+        Some(5) => CalendarImportance::Medium,
+        Some(6..=9) => CalendarImportance::Low,
+        _ => default_importance,
+    };
+    Ok(CalendarEntry {
+        uid: uid.clone(),
+        title: get("SUMMARY").map(|(_, _, s)| unescape(s)).unwrap_or_default(),
+        importance,
+        symbol: None,
+        start,
+        end,
+    })
+}
+
+fn ics_time(params: &str, value: &str) -> Result<OffsetDateTime> {
+    let value = value.trim();
+    if params.contains("VALUE=DATE") {
+        let date = Date::parse(value, &format_description!("[year][month][day]"))?;
+        return Ok(date.midnight().assume_utc());
+    }
+    let format = format_description!("[year][month][day]T[hour][minute][second]");
+    Ok(PrimitiveDateTime::parse(value.trim_end_matches('Z'), &format)?.assume_utc())
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let data = r#"[
+            {"id": "fomc", "title": "FOMC rate decision", "start": "2025-01-29T19:00:00Z", "importance": "high"},
+            {"id": "delist", "title": "Delisting", "start": "2025-02-01T08:00:00Z", "end": "2025-02-01T09:00:00Z",
+             "symbol": "XYZUSDT"}
+        ]"#;
+        let entries = parse_json(data, CalendarImportance::Low).unwrap();
+        assert_eq!(entries[0].importance, CalendarImportance::High);
+        assert_eq!(entries[0].start, datetime!(2025-01-29 19:00 UTC));
+        assert_eq!(entries[0].end, entries[0].start);
+        assert_eq!(entries[1].importance, CalendarImportance::Low);
+        assert_eq!(entries[1].symbol.as_deref(), Some("XYZUSDT"));
+        assert_eq!(entries[1].end, datetime!(2025-02-01 09:00 UTC));
+        assert!(parse_json(r#"[{"id": "x", "title": "x", "start": "tomorrow"}]"#, CalendarImportance::Low).is_err());
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let data = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:cpi-2025-02\r\n\
+            SUMMARY:US CPI\\, January\r\n\
+            DTSTART:20250212T133000Z\r\n\
+            PRIORITY:1\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:holiday\r\n\
+            SUMMARY:Market holiday with a long\r\n  description\r\n\
+            DTSTART;VALUE=DATE:20250217\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let entries = parse_ics(data, CalendarImportance::Medium).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "US CPI, January");
+        assert_eq!(entries[0].importance, CalendarImportance::High);
+        assert_eq!(entries[0].start, datetime!(2025-02-12 13:30 UTC));
+        assert_eq!(entries[1].title, "Market holiday with a long description");
+        assert_eq!(entries[1].importance, CalendarImportance::Medium);
+        assert_eq!(entries[1].end, datetime!(2025-02-18 00:00 UTC));
+    }
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{
+    config::{CalendarFormat, CalendarIngestorConfig},
+    traits::Ingestor,
+    IngestorError,
+};
+
+use super::{parse_ics, parse_json, CalendarEntry};
+
+/// Reads scheduled macro events and exchange announcements from a JSON or iCalendar source and publishes each upcoming
+/// event as a [`CalendarEvent`]. The source is read again every refresh, events are only published again if their
+/// time or importance changed.
+#[derive(Debug, TypedBuilder)]
+pub struct CalendarIngestor {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    name: String,
+    /// Path or http url of the calendar
+    source: String,
+    format: CalendarFormat,
+    refresh: Duration,
+    default_importance: CalendarImportance,
+    /// Venue the symbols of the events are resolved on
+    venue: String,
+    #[builder(default)]
+    published: Mutex<HashMap<String, CalendarEntry>>,
+}
+
+impl CalendarIngestor {
+    pub fn from_config(
+        config: &CalendarIngestorConfig,
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+    ) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .persistence(persistence)
+            .name(config.name.clone())
+            .source(config.source.clone())
+            .format(config.format)
+            .refresh(Duration::from_secs(config.refresh_secs))
+            .default_importance(config.default_importance)
+            .venue(config.venue.clone())
+            .build()
+    }
+
+    async fn fetch(&self) -> Result<String> {
+        match self.source.starts_with("http://") || self.source.starts_with("https://") {
+            true => Ok(reqwest::get(&self.source).await?.error_for_status()?.text().await?),
+            false => Ok(tokio::fs::read_to_string(&self.source).await?),
+        }
+    }
+
+    /// Entries that are new or changed since they were last published and did not end yet.
+    fn changed(&self, entries: Vec<CalendarEntry>, now: OffsetDateTime) -> Vec<CalendarEntry> {
+        let mut published = self.published.lock();
+        published.retain(|_, e| e.end >= now);
+        let mut changed = Vec::new();
+        for entry in entries {
+            if entry.end >= now && published.get(&entry.uid) != Some(&entry) {
+                published.insert(entry.uid.clone(), entry.clone());
+                changed.push(entry);
+            }
+        }
+        changed
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        let data = self.fetch().await?;
+        let entries = match self.format {
+            CalendarFormat::Json => parse_json(&data, self.default_importance)?,
+            CalendarFormat::Ics => parse_ics(&data, self.default_importance)?,
+        };
+        let now = OffsetDateTime::now_utc();
+        for entry in self.changed(entries, now) {
+            let instrument = match &entry.symbol {
+                Some(symbol) => match self.persistence.symbol_registry.instrument(&self.venue, symbol).await {
+                    Ok(instrument) => Some(instrument),
+                    Err(e) => {
+                        warn!("Skipping calendar event {} for unknown symbol {}: {}", entry.uid, symbol, e);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let event = CalendarEvent::builder()
+                .event_time(now)
+                .source(self.name.clone())
+                .uid(entry.uid)
+                .title(entry.title)
+                .importance(entry.importance)
+                .instrument(instrument)
+                .start(entry.start)
+                .end(entry.end)
+                .build();
+            info!("Calendar event: {}", event);
+            self.pubsub.publish::<CalendarEvent>(event.into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Ingestor for CalendarIngestor {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        info!("Starting calendar ingestor {} from {}...", self.name, self.source);
+        let mut interval = tokio::time::interval(self.refresh);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.refresh().await {
+                        warn!("Failed to read calendar {}: {}", self.name, e);
+                    }
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
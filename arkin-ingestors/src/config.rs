@@ -1,16 +1,66 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use time::{macros::format_description, OffsetDateTime};
+
+use arkin_core::{CalendarImportance, ObjectStorageConfig, OperatorActionType};
+
+use crate::recorder::ArchivePartition;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IngestorsConfig {
     pub ingestors: Vec<IngestorConfig>,
 }
 
+impl IngestorsConfig {
+    /// Start the replays of the sim ingestors at the given minute instead, to resume a simulation from a checkpoint.
+    /// The operator actions before it already happened and are dropped.
+    pub fn resume_at(&mut self, at: OffsetDateTime) {
+        let start = at
+            .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+            .expect("Failed to format resume time");
+        for ingestor in &mut self.ingestors {
+            if let IngestorConfig::Sim(config) = ingestor {
+                config.actions.retain(|a| a.at >= start);
+                config.start = start.clone();
+            }
+        }
+    }
+
+    /// Pace the replays of the sim ingestors as given instead.
+    pub fn pacing(&mut self, pacing: ReplayPacing) {
+        for ingestor in &mut self.ingestors {
+            if let IngestorConfig::Sim(config) = ingestor {
+                config.pacing = pacing;
+            }
+        }
+    }
+
+    /// Load the replays of the sim ingestors in windows of the given size instead, the bounds of an adaptive window
+    /// still apply.
+    pub fn replay_window(&mut self, secs: u64) {
+        for ingestor in &mut self.ingestors {
+            if let IngestorConfig::Sim(config) = ingestor {
+                config.chunk_secs = secs;
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum IngestorConfig {
     #[serde(rename = "binance")]
     Binance(BinanceIngestorConfig),
     #[serde(rename = "tardis")]
     Tardis(TardisIngestorConfig),
+    #[serde(rename = "sim")]
+    Sim(SimIngestorConfig),
+    #[serde(rename = "calendar")]
+    Calendar(CalendarIngestorConfig),
+    #[serde(rename = "metrics")]
+    Metrics(MetricIngestorConfig),
+    #[serde(rename = "onchain")]
+    OnChain(OnChainIngestorConfig),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -21,6 +71,22 @@ pub struct BinanceIngestorConfig {
     pub api_secret: Option<String>,
     pub connections_per_manager: usize,
     pub duplicate_lookback: usize,
+    /// Record the raw websocket messages before they are parsed
+    #[serde(default)]
+    pub recorder: Option<FeedRecorderConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeedRecorderConfig {
+    pub directory: String,
+    #[serde(default)]
+    pub partition: ArchivePartition,
+    /// Upload the archive of a partition once the recorder moved past it
+    #[serde(default)]
+    pub upload: Option<ObjectStorageConfig>,
+    /// Keep uploaded archives in the directory as well
+    #[serde(default)]
+    pub keep_local: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,3 +100,224 @@ pub struct TardisIngestorConfig {
     pub start: String,
     pub end: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimIngestorConfig {
+    pub channels: Vec<String>,
+    /// Venue the instruments are replayed from
+    #[serde(default = "default_sim_venue")]
+    pub venue: String,
+    /// Venue or canonical symbols, like `BTCUSDT` or `perp-btc-usdt`
+    pub instruments: Vec<String>,
+    /// Instruments replayed per channel out of the instruments, channels without an entry replay all of them
+    #[serde(default)]
+    pub channel_instruments: HashMap<String, Vec<String>>,
+    pub start: String,
+    pub end: String,
+    /// Hours of every day replayed as HH:MM-HH:MM in UTC, like `08:00-16:00`, the whole day without them
+    #[serde(default)]
+    pub sessions: Vec<String>,
+    /// Window of the replay loaded at once
+    pub chunk_secs: u64,
+    /// Adapt the window to the orders of the simulation
+    #[serde(default)]
+    pub adaptive_window: Option<AdaptiveWindowConfig>,
+    /// Speed of the replay against the wall clock
+    #[serde(default)]
+    pub pacing: ReplayPacing,
+    /// Look for gaps in the replayed data, gaps go unnoticed without it
+    #[serde(default)]
+    pub gaps: Option<DataGapConfig>,
+    /// Operator actions injected into the replay, to rehearse a runbook against historical data
+    #[serde(default)]
+    pub actions: Vec<ScheduledActionConfig>,
+    /// Wall clock time between the progress updates of the replay
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
+}
+
+/// Speed the sim ingestor publishes the replayed events at.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayPacing {
+    /// As fast as the simulation keeps up
+    #[default]
+    Fast,
+    /// The replayed time passes this many times faster than the wall clock
+    Multiplier(f64),
+    /// At the speed the events happened
+    RealTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DataGapConfig {
+    /// Seconds without any data on a channel that count as a gap
+    pub max_gap_secs: u64,
+    #[serde(default)]
+    pub policy: DataGapPolicy,
+}
+
+/// What the sim ingestor does about a gap in the replayed data.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DataGapPolicy {
+    /// Log the gap and replay through it
+    #[default]
+    Warn,
+    /// Publish a `DataGap` event before replaying through it
+    Publish,
+    /// Publish a `DataGap` event and move the simulation past the gap, the other channels are not replayed within it
+    Skip,
+    /// Stop the replay at the start of the gap and fail the sim ingestor
+    Abort,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdaptiveWindowConfig {
+    #[serde(default = "default_min_window_secs")]
+    pub min_secs: u64,
+    #[serde(default = "default_max_window_secs")]
+    pub max_secs: u64,
+    /// Halve the window while the orders per minute over the last window reach it
+    pub busy_orders_per_min: f64,
+    /// Double the window while the orders per minute over the last window stay at or below it
+    pub quiet_orders_per_min: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledActionConfig {
+    /// Time of the action in "YYYY-MM-DD HH:MM" format
+    pub at: String,
+    #[serde(flatten)]
+    pub action: OperatorActionType,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarIngestorConfig {
+    /// Name of the calendar on its events, like `macro` or `binance_announcements`
+    pub name: String,
+    /// Path or http url of the calendar
+    pub source: String,
+    pub format: CalendarFormat,
+    #[serde(default = "default_calendar_refresh_secs")]
+    pub refresh_secs: u64,
+    /// Importance of the events that don't carry one
+    #[serde(default)]
+    pub default_importance: CalendarImportance,
+    /// Venue the symbols of the events are resolved on
+    #[serde(default = "default_sim_venue")]
+    pub venue: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "ics")]
+    Ics,
+}
+
+fn default_calendar_refresh_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetricIngestorConfig {
+    /// Name of the provider on its metrics, like `santiment`
+    pub source: String,
+    pub provider: MetricProviderConfig,
+    /// Venue the symbols of the metrics are resolved on
+    #[serde(default = "default_sim_venue")]
+    pub venue: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum MetricProviderConfig {
+    /// Receive the metrics pushed to `POST /metrics`
+    #[serde(rename = "webhook")]
+    Webhook(MetricWebhookConfig),
+    /// Read the metrics from an url on an interval
+    #[serde(rename = "poll")]
+    Poll(MetricPollConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetricWebhookConfig {
+    /// Address the webhook listens on (e.g., 0.0.0.0:8092)
+    pub address: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetricPollConfig {
+    pub url: String,
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OnChainIngestorConfig {
+    /// Chain the wallets are on, like `ethereum`
+    pub chain: String,
+    /// Etherscan compatible api of the chain, like `https://api.etherscan.io/api`
+    pub api_url: String,
+    pub api_key: String,
+    #[serde(default = "default_onchain_poll_secs")]
+    pub poll_secs: u64,
+    /// Blocks on top of a transfer before it is booked
+    #[serde(default = "default_onchain_confirmations")]
+    pub confirmations: u64,
+    /// Block to book the transfers from, without one only the transfers after the first poll are booked
+    #[serde(default)]
+    pub start_block: Option<u64>,
+    pub wallets: Vec<OnChainWalletConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OnChainWalletConfig {
+    /// Funding wallet the capital is sent from and back to
+    pub address: String,
+    /// Asset the transfers are booked in, like `USDT`
+    pub asset: String,
+    /// Contract of the token of the asset on the chain
+    pub token_contract: String,
+    /// Deposit address and withdrawal wallets of the exchange, transfers with other addresses are skipped
+    pub exchange_addresses: Vec<String>,
+    /// Strategy the deposits are given to and the withdrawals taken from
+    #[serde(default = "default_onchain_strategy")]
+    pub strategy: String,
+}
+
+impl OnChainWalletConfig {
+    pub fn is_exchange(&self, address: &str) -> bool {
+        self.exchange_addresses.iter().any(|a| a.eq_ignore_ascii_case(address))
+    }
+}
+
+fn default_onchain_poll_secs() -> u64 {
+    60
+}
+
+fn default_onchain_confirmations() -> u64 {
+    12
+}
+
+fn default_onchain_strategy() -> String {
+    "default".to_string()
+}
+
+fn default_min_window_secs() -> u64 {
+    60
+}
+
+fn default_max_window_secs() -> u64 {
+    86400
+}
+
+fn default_sim_venue() -> String {
+    "binance".to_string()
+}
+
+fn default_progress_interval_ms() -> u64 {
+    1000
+}
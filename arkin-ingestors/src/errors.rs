@@ -1,5 +1,9 @@
+use std::sync::Arc;
+
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum IngestorError {
     #[error("Channel send error: {0}")]
@@ -14,12 +18,34 @@ pub enum IngestorError {
     #[error("Acuireing the lock failed: {0}")]
     LockError(#[from] tokio::sync::AcquireError),
 
+    #[error("Archive error: {0}")]
+    ArchiveError(#[from] std::io::Error),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 
+    #[error("Gap in the replayed data: {0}")]
+    DataGap(Arc<DataGap>),
+
     #[error(transparent)]
     PersistenceError(#[from] arkin_persistence::PersistenceError),
 
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
+
+impl ClassifyError for IngestorError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            IngestorError::ChannelSendError(_)
+            | IngestorError::ChannelReceiveError(_)
+            | IngestorError::WebSocketError(_)
+            | IngestorError::ArchiveError(_) => ErrorClass::Transient,
+            IngestorError::PersistenceError(e) => e.class(),
+            IngestorError::LockError(_)
+            | IngestorError::UnexpectedError(_)
+            | IngestorError::DataGap(_)
+            | IngestorError::Anyhow(_) => ErrorClass::Fatal,
+        }
+    }
+}
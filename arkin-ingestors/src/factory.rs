@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
+use arkin_binance::{BinanceAdapter, BinanceAdapterConfig};
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 
 use crate::{
     config::{IngestorConfig, IngestorsConfig},
     traits::Ingestor,
-    BinanceIngestor, TardisIngestor,
+    BinanceIngestor, CalendarIngestor, FeedRecorder, MetricIngestor, OnChainIngestor, SimIngestor, TardisIngestor,
 };
 
 pub struct IngestorFactory {}
@@ -16,27 +17,63 @@ impl IngestorFactory {
         config: &IngestorsConfig,
         pubsub: Arc<PubSub>,
         persistence: Arc<PersistenceService>,
+        adapters: &VenueAdapters,
+        debugger: Option<Arc<SimDebugger>>,
+        barrier: Option<Arc<ReplayBarrier>>,
     ) -> Vec<Arc<dyn Ingestor>> {
         config
             .ingestors
             .iter()
             .map(|config| {
                 let ingestor: Arc<dyn Ingestor> = match config {
-                    IngestorConfig::Binance(c) => Arc::new(
-                        BinanceIngestor::builder()
-                            .pubsub(pubsub.clone())
-                            .persistence(persistence.clone())
-                            .url(c.ws_url.parse().expect("Failed to parse ws binance URL"))
-                            .channels(c.ws_channels.to_owned())
-                            .api_key(c.api_key.to_owned())
-                            .api_secret(c.api_secret.to_owned())
-                            .connections_per_manager(c.connections_per_manager)
-                            .duplicate_lookback(c.duplicate_lookback)
-                            .build(),
-                    ),
+                    IngestorConfig::Binance(c) => {
+                        // Market data needs no credentials, register a public adapter if there is none yet
+                        let adapter = adapters.get(BinanceAdapter::VENUE).unwrap_or_else(|| {
+                            let adapter: Arc<dyn VenueAdapter> =
+                                Arc::new(BinanceAdapter::from_config(&BinanceAdapterConfig {
+                                    market_data_url: c.ws_url.clone(),
+                                    ..Default::default()
+                                }));
+                            adapters.insert(adapter.clone());
+                            adapter
+                        });
+                        Arc::new(
+                            BinanceIngestor::builder()
+                                .pubsub(pubsub.clone())
+                                .persistence(persistence.clone())
+                                .adapter(adapter)
+                                .url(c.ws_url.parse().expect("Failed to parse ws binance URL"))
+                                .channels(c.ws_channels.to_owned())
+                                .api_key(c.api_key.to_owned())
+                                .api_secret(c.api_secret.to_owned())
+                                .connections_per_manager(c.connections_per_manager)
+                                .duplicate_lookback(c.duplicate_lookback)
+                                .recorder(
+                                    c.recorder
+                                        .as_ref()
+                                        .map(|r| Arc::new(FeedRecorder::from_config(r, BinanceIngestor::SOURCE))),
+                                )
+                                .build(),
+                        )
+                    }
                     IngestorConfig::Tardis(c) => {
                         Arc::new(TardisIngestor::from_config(c, pubsub.clone(), persistence.clone()))
                     }
+                    IngestorConfig::Sim(c) => Arc::new(
+                        SimIngestor::from_config(c, pubsub.clone(), persistence.clone())
+                            .with_debugger(debugger.clone())
+                            .with_barrier(barrier.clone()),
+                    ),
+                    IngestorConfig::Calendar(c) => {
+                        Arc::new(CalendarIngestor::from_config(c, pubsub.clone(), persistence.clone()))
+                    }
+                    // The webhook shares the ingestor with its handlers
+                    IngestorConfig::Metrics(c) => {
+                        Arc::new(Arc::new(MetricIngestor::from_config(c, pubsub.clone(), persistence.clone())))
+                    }
+                    IngestorConfig::OnChain(c) => {
+                        Arc::new(OnChainIngestor::from_config(c, pubsub.clone(), persistence.clone()))
+                    }
                 };
                 ingestor
             })
@@ -0,0 +1,59 @@
+//! Entry points for the fuzz targets in `arkin-ingestors/fuzz`. They run the same parsing the ingestors do on an
+//! untrusted payload, including formatting the parsed event, and must never panic.
+
+use crate::{binance::swaps::BinanceSwapEvent, tardis::binance_swap::BinanceSwapsEvent};
+
+/// Real Binance websocket messages the fuzz targets start mutating from when no recorded corpus is available.
+pub const BINANCE_SEED_MESSAGES: &[&str] = &[
+    r#"{"e":"aggTrade","E":1698796800043,"a":3863267,"s":"GASUSDT","p":"6.279000","q":"141.2","f":15146241,"l":15146244,"T":1698796799890,"m":false}"#,
+    r#"{"e":"bookTicker","u":2487455691211,"s":"BTCUSDT","b":"21840.40","B":"21.292","a":"21840.50","A":"11.169","T":1676026461537,"E":1676026461542}"#,
+];
+
+/// Real Tardis replay lines the fuzz targets start mutating from when no recorded corpus is available.
+pub const TARDIS_SEED_LINES: &[&str] = &[
+    r#"2023-11-01T00:00:00.043Z {"stream":"gasusdt@aggTrade","data":{"e":"aggTrade","E":1698796800043,"a":3863267,"s":"GASUSDT","p":"6.279000","q":"141.2","f":15146241,"l":15146244,"T":1698796799890,"m":false}}"#,
+    r#"2023-02-10T09:34:21.542Z {"stream":"btcusdt@bookTicker","data":{"e":"bookTicker","u":2487455691211,"s":"BTCUSDT","b":"21840.40","B":"21.292","a":"21840.50","A":"11.169","T":1676026461537,"E":1676026461542}}"#,
+];
+
+/// Parse a raw Binance websocket message like the Binance ingestor does.
+pub fn parse_binance_message(data: &[u8]) -> Option<String> {
+    let event = serde_json::from_slice::<BinanceSwapEvent>(data).ok()?;
+    Some(format!("{} {}", event.venue_symbol(), event))
+}
+
+/// Parse a line of a Tardis replay like the Tardis ingestor does.
+pub fn parse_tardis_line(data: &[u8]) -> Option<String> {
+    let line = std::str::from_utf8(data).ok()?;
+    let (_, json) = crate::tardis::service::parse_line(line.trim()).ok()?;
+    let event = serde_json::from_str::<BinanceSwapsEvent>(&json).ok()?;
+    Some(format!("{} {}", event.venue_symbol(), event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_messages_parse() {
+        for message in BINANCE_SEED_MESSAGES {
+            assert!(parse_binance_message(message.as_bytes()).is_some());
+        }
+        for line in TARDIS_SEED_LINES {
+            assert!(parse_tardis_line(line.as_bytes()).is_some());
+        }
+    }
+
+    #[test]
+    fn test_truncated_messages_do_not_panic() {
+        for message in BINANCE_SEED_MESSAGES {
+            for end in 0..message.len() {
+                assert!(parse_binance_message(&message.as_bytes()[..end]).is_none());
+            }
+        }
+        for line in TARDIS_SEED_LINES {
+            for end in 0..line.len() {
+                assert!(parse_tardis_line(&line.as_bytes()[..end]).is_none());
+            }
+        }
+    }
+}
@@ -1,21 +1,41 @@
 mod binance;
+mod calendar;
 mod config;
 mod errors;
 mod factory;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
+mod metrics;
+mod onchain;
+mod recorder;
+mod sim;
 mod tardis;
 mod traits;
 mod ws;
 
 pub use binance::BinanceIngestor;
+pub use calendar::CalendarIngestor;
 pub use errors::IngestorError;
 pub use factory::IngestorFactory;
+pub use metrics::MetricIngestor;
+pub use onchain::OnChainIngestor;
+pub use recorder::FeedRecorder;
+pub use sim::SimIngestor;
 pub use tardis::TardisIngestor;
 pub use traits::Ingestor;
 
 pub mod prelude {
-    pub use crate::binance::BinanceIngestorBuilder;
+    pub use crate::binance::{BinanceIngestor, BinanceIngestorBuilder};
+    pub use crate::calendar::{CalendarEntry, CalendarIngestor};
     pub use crate::config::*;
     pub use crate::errors::IngestorError;
     pub use crate::factory::IngestorFactory;
+    pub use crate::metrics::{MetricIngestor, MetricPayload};
+    pub use crate::onchain::{parse_token_transfers, OnChainIngestor, TokenTransfer, WalletState};
+    pub use crate::recorder::{list_archives, ArchivePartition, ArchiveReader, ArchiveWriter, FeedRecorder, RawRecord};
+    pub use crate::sim::{
+        GapDetector, ReplayMerger, ReplayPacer, ReplaySession, ReplayTask, ReplayWindow, SimChannel, SimEvent,
+        SimIngestor,
+    };
     pub use crate::traits::Ingestor;
 }
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{
+    config::{MetricIngestorConfig, MetricProviderConfig},
+    traits::Ingestor,
+    IngestorError,
+};
+
+/// Metric as pushed to the webhook or served by a polled url.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricPayload {
+    /// Venue symbol the metric is about, market-wide metrics have none
+    #[serde(default)]
+    pub symbol: Option<String>,
+    pub metric_type: MetricType,
+    pub name: String,
+    pub value: Decimal,
+    /// RFC 3339 time of the metric, the time it was received without one
+    #[serde(default)]
+    pub timestamp: Option<String>,
+}
+
+/// Takes third-party signals like sentiment scores or on-chain metrics from a webhook or a polled url and publishes
+/// them as [`Metric`], which the insights service adds to its pipeline under their name. Metrics that are not newer
+/// than the last one of the same name and instrument are dropped, so a poll returning the same values again
+/// publishes nothing.
+#[derive(Debug, TypedBuilder)]
+pub struct MetricIngestor {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    source: String,
+    provider: MetricProviderConfig,
+    /// Venue the symbols of the metrics are resolved on
+    venue: String,
+    #[builder(default)]
+    last: Mutex<HashMap<(Option<String>, String), OffsetDateTime>>,
+}
+
+impl MetricIngestor {
+    pub fn from_config(
+        config: &MetricIngestorConfig,
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+    ) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .persistence(persistence)
+            .source(config.source.clone())
+            .provider(config.provider.clone())
+            .venue(config.venue.clone())
+            .build()
+    }
+
+    /// Publish the payloads newer than the last metric of their name and instrument, returns how many were
+    /// published.
+    pub async fn ingest(&self, payloads: Vec<MetricPayload>) -> Result<usize> {
+        let now = OffsetDateTime::now_utc();
+        let mut published = 0;
+        for payload in payloads {
+            let event_time = match &payload.timestamp {
+                Some(timestamp) => OffsetDateTime::parse(timestamp, &Rfc3339)?,
+                None => now,
+            };
+            let key = (payload.symbol.clone(), payload.name.clone());
+            if self.last.lock().get(&key).is_some_and(|last| event_time <= *last) {
+                debug!("Skipping metric {} at {}, already ingested", payload.name, event_time);
+                continue;
+            }
+
+            let instrument = match &payload.symbol {
+                Some(symbol) => match self.persistence.symbol_registry.instrument(&self.venue, symbol).await {
+                    Ok(instrument) => Some(instrument),
+                    Err(e) => {
+                        warn!("Skipping metric {} for unknown symbol {}: {}", payload.name, symbol, e);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            self.last.lock().insert(key, event_time);
+            let metric = Metric::builder()
+                .event_time(event_time)
+                .instrument(instrument)
+                .metric_type(payload.metric_type)
+                .name(payload.name)
+                .value(payload.value)
+                .source(self.source.clone())
+                .build();
+            debug!("Metric: {}", metric);
+            self.pubsub.publish::<Metric>(metric.into());
+            published += 1;
+        }
+        Ok(published)
+    }
+
+    async fn poll(&self, url: &str) -> Result<usize> {
+        let payloads = reqwest::get(url)
+            .await?
+            .error_for_status()?
+            .json::<Vec<MetricPayload>>()
+            .await?;
+        self.ingest(payloads).await
+    }
+
+    pub fn router(self: &Arc<Self>) -> Router {
+        Router::new().route("/metrics", post(metrics)).with_state(self.clone())
+    }
+}
+
+async fn metrics(State(ingestor): State<Arc<MetricIngestor>>, Json(payloads): Json<Vec<MetricPayload>>) -> StatusCode {
+    match ingestor.ingest(payloads).await {
+        Ok(_) => StatusCode::ACCEPTED,
+        Err(e) => {
+            warn!("Rejected metrics from {}: {}", ingestor.source, e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
+
+#[async_trait]
+impl Ingestor for Arc<MetricIngestor> {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        info!("Starting metric ingestor {}...", self.source);
+        match &self.provider {
+            MetricProviderConfig::Webhook(c) => {
+                let listener = TcpListener::bind(&c.address)
+                    .await
+                    .map_err(|e| IngestorError::UnexpectedError(format!("metric webhook on {}: {}", c.address, e)))?;
+                info!("Metric webhook listening on {}", c.address);
+                axum::serve(listener, self.router())
+                    .with_graceful_shutdown(async move { shutdown.cancelled().await })
+                    .await
+                    .map_err(|e| IngestorError::UnexpectedError(format!("metric webhook stopped: {}", e)))?;
+            }
+            MetricProviderConfig::Poll(c) => {
+                let mut interval = tokio::time::interval(Duration::from_secs(c.interval_secs));
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => match self.poll(&c.url).await {
+                            Ok(published) => debug!("Polled {} metrics from {}", published, self.source),
+                            Err(e) => warn!("Failed to poll metrics from {}: {}", self.source, e),
+                        },
+                        _ = shutdown.cancelled() => break,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let data = r#"[
+            {"symbol": "BTCUSDT", "metric_type": "sentiment", "name": "sentiment_score", "value": 0.42,
+             "timestamp": "2025-01-01T00:00:00Z"},
+            {"metric_type": "on_chain", "name": "exchange_netflow", "value": "-1250.5"}
+        ]"#;
+        let payloads = serde_json::from_str::<Vec<MetricPayload>>(data).unwrap();
+        assert_eq!(payloads[0].metric_type, MetricType::Sentiment);
+        assert_eq!(payloads[0].value, dec!(0.42));
+        assert_eq!(payloads[1].symbol, None);
+        assert_eq!(payloads[1].metric_type, MetricType::OnChain);
+        assert_eq!(payloads[1].value, dec!(-1250.5));
+        assert!(payloads[1].timestamp.is_none());
+    }
+}
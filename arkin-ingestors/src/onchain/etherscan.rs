@@ -0,0 +1,89 @@
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+/// Token transfer as listed by the `tokentx` action of an Etherscan compatible api.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenTransfer {
+    pub block_number: String,
+    pub time_stamp: String,
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    /// Amount in the smallest unit of the token
+    pub value: String,
+    pub token_decimal: String,
+    pub confirmations: String,
+}
+
+impl TokenTransfer {
+    pub fn block(&self) -> Result<u64> {
+        Ok(self.block_number.parse()?)
+    }
+
+    pub fn time(&self) -> Result<OffsetDateTime> {
+        Ok(OffsetDateTime::from_unix_timestamp(self.time_stamp.parse()?)?)
+    }
+
+    pub fn quantity(&self) -> Result<Decimal> {
+        let decimals = self.token_decimal.parse::<u32>()?;
+        Decimal::try_from_i128_with_scale(self.value.parse()?, decimals)
+            .map(|q| q.normalize())
+            .map_err(|e| anyhow!("token amount {} with {} decimals: {}", self.value, decimals, e))
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations.parse().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    status: String,
+    message: String,
+    result: serde_json::Value,
+}
+
+/// Parse the response of the `tokentx` action. An address without transfers comes back as an error status with an
+/// empty result.
+pub fn parse_token_transfers(data: &str) -> Result<Vec<TokenTransfer>> {
+    let response = serde_json::from_str::<Response>(data)?;
+    if response.status != "1" && response.message != "No transactions found" {
+        bail!("{}: {}", response.message, response.result);
+    }
+    match response.result {
+        serde_json::Value::Array(_) => Ok(serde_json::from_value(response.result)?),
+        result => bail!("unexpected result: {}", result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_token_transfers() {
+        let data = r#"{"status": "1", "message": "OK", "result": [{
+            "blockNumber": "21525000", "timeStamp": "1735732800", "hash": "0xabc", "nonce": "7",
+            "from": "0x1111", "contractAddress": "0xdac17f958d2ee523a2206206994597c13d831ec7", "to": "0x2222",
+            "value": "25000500000", "tokenName": "Tether USD", "tokenSymbol": "USDT", "tokenDecimal": "6",
+            "confirmations": "40"
+        }]}"#;
+        let transfers = parse_token_transfers(data).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].block().unwrap(), 21525000);
+        assert_eq!(transfers[0].time().unwrap(), datetime!(2025-01-01 12:00 UTC));
+        assert_eq!(transfers[0].quantity().unwrap(), dec!(25000.5));
+        assert_eq!(transfers[0].confirmations(), 40);
+
+        let empty = r#"{"status": "0", "message": "No transactions found", "result": []}"#;
+        assert!(parse_token_transfers(empty).unwrap().is_empty());
+        let error = r#"{"status": "0", "message": "NOTOK", "result": "Invalid API Key"}"#;
+        assert!(parse_token_transfers(error).is_err());
+    }
+}
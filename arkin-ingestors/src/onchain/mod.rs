@@ -0,0 +1,5 @@
+mod etherscan;
+mod service;
+
+pub use etherscan::{parse_token_transfers, TokenTransfer};
+pub use service::{OnChainIngestor, WalletState};
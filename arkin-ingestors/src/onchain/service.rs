@@ -0,0 +1,247 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::{
+    config::{OnChainIngestorConfig, OnChainWalletConfig},
+    traits::Ingestor,
+    IngestorError,
+};
+
+use super::{parse_token_transfers, TokenTransfer};
+
+/// Transfers of a tracked wallet seen so far.
+#[derive(Debug, Default)]
+pub struct WalletState {
+    /// Block the next poll starts from, None until the first poll synced the wallet
+    cursor: Option<u64>,
+    seen: HashSet<String>,
+}
+
+impl WalletState {
+    /// Start booking the transfers from the given block, without one the transfers before the first poll are only
+    /// marked as seen.
+    pub fn new(start_block: Option<u64>) -> Self {
+        Self {
+            cursor: start_block,
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn start_block(&self) -> u64 {
+        self.cursor.unwrap_or_default()
+    }
+
+    /// Transfers to book out of a poll, the ones not seen before with enough confirmations. The cursor stops before
+    /// the first transfer still waiting for confirmations, so the next poll lists it again.
+    pub fn update(&mut self, mut transfers: Vec<TokenTransfer>, confirmations: u64) -> Result<Vec<TokenTransfer>> {
+        transfers.sort_by_key(|t| t.block().unwrap_or_default());
+        let Some(mut cursor) = self.cursor else {
+            self.cursor = Some(transfers.iter().map(|t| t.block()).max().transpose()?.unwrap_or_default());
+            self.seen.extend(transfers.into_iter().map(|t| t.hash));
+            return Ok(Vec::new());
+        };
+
+        let mut pending = false;
+        let mut confirmed = Vec::new();
+        for transfer in transfers {
+            if self.seen.contains(&transfer.hash) {
+                continue;
+            }
+            if transfer.confirmations() < confirmations {
+                pending = true;
+                continue;
+            }
+            if !pending {
+                cursor = cursor.max(transfer.block()?);
+            }
+            self.seen.insert(transfer.hash.clone());
+            confirmed.push(transfer);
+        }
+        self.cursor = Some(cursor);
+        Ok(confirmed)
+    }
+}
+
+/// Tracks funding wallets on an Etherscan compatible api and publishes the token transfers between them and the
+/// exchange as [`WalletTransfer`], which are booked on the ledger as deposits and withdrawals. Transfers with other
+/// addresses don't move capital on or off the exchange and are skipped.
+#[derive(Debug, TypedBuilder)]
+pub struct OnChainIngestor {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    chain: String,
+    api_url: String,
+    api_key: String,
+    poll: Duration,
+    /// Blocks on top of a transfer before it is booked
+    confirmations: u64,
+    wallets: Vec<OnChainWalletConfig>,
+    #[builder(default)]
+    state: Mutex<HashMap<String, WalletState>>,
+}
+
+impl OnChainIngestor {
+    pub fn from_config(
+        config: &OnChainIngestorConfig,
+        pubsub: Arc<PubSub>,
+        persistence: Arc<PersistenceService>,
+    ) -> Self {
+        let state = config
+            .wallets
+            .iter()
+            .map(|w| (w.address.to_lowercase(), WalletState::new(config.start_block)))
+            .collect();
+        Self::builder()
+            .pubsub(pubsub)
+            .persistence(persistence)
+            .chain(config.chain.clone())
+            .api_url(config.api_url.clone())
+            .api_key(config.api_key.clone())
+            .poll(Duration::from_secs(config.poll_secs))
+            .confirmations(config.confirmations)
+            .wallets(config.wallets.clone())
+            .state(Mutex::new(state))
+            .build()
+    }
+
+    async fn fetch(&self, wallet: &OnChainWalletConfig, start_block: u64) -> Result<Vec<TokenTransfer>> {
+        let start_block = start_block.to_string();
+        let data = reqwest::Client::new()
+            .get(&self.api_url)
+            .query(&[
+                ("module", "account"),
+                ("action", "tokentx"),
+                ("address", wallet.address.as_str()),
+                ("contractaddress", wallet.token_contract.as_str()),
+                ("startblock", start_block.as_str()),
+                ("sort", "asc"),
+                ("apikey", self.api_key.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        parse_token_transfers(&data)
+    }
+
+    async fn poll_wallet(&self, wallet: &OnChainWalletConfig) -> Result<()> {
+        let address = wallet.address.to_lowercase();
+        let start_block = self.state.lock().entry(address.clone()).or_default().start_block();
+        let transfers = self.fetch(wallet, start_block).await?;
+        let transfers = self
+            .state
+            .lock()
+            .entry(address.clone())
+            .or_default()
+            .update(transfers, self.confirmations)?;
+
+        for transfer in transfers {
+            let (direction, counterparty) = match (transfer.from.to_lowercase(), transfer.to.to_lowercase()) {
+                (from, to) if from == address && wallet.is_exchange(&to) => (WalletTransferDirection::Deposit, to),
+                (from, to) if to == address && wallet.is_exchange(&from) => (WalletTransferDirection::Withdrawal, from),
+                _ => {
+                    debug!(
+                        "Skipping transfer {} of {}, not with the exchange",
+                        transfer.hash, wallet.address
+                    );
+                    continue;
+                }
+            };
+            let asset = self.persistence.asset_store.read_by_symbol(&wallet.asset).await?;
+            let transfer = WalletTransfer::builder()
+                .event_time(transfer.time()?)
+                .portfolio(test_portfolio())
+                .strategy(wallet.strategy.clone())
+                .asset(asset)
+                .direction(direction)
+                .quantity(transfer.quantity()?)
+                .chain(self.chain.clone())
+                .wallet(address.clone())
+                .counterparty(counterparty)
+                .tx_hash(transfer.hash)
+                .build();
+            info!("Wallet transfer: {}", transfer);
+            self.pubsub.publish::<WalletTransfer>(transfer.into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Ingestor for OnChainIngestor {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        info!(
+            "Starting on-chain ingestor for {} wallets on {}...",
+            self.wallets.len(),
+            self.chain
+        );
+        let mut interval = tokio::time::interval(self.poll);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    for wallet in &self.wallets {
+                        if let Err(e) = self.poll_wallet(wallet).await {
+                            warn!("Failed to poll wallet {} on {}: {}", wallet.address, self.chain, e);
+                        }
+                    }
+                }
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(hash: &str, block: u64, confirmations: u64) -> TokenTransfer {
+        TokenTransfer {
+            block_number: block.to_string(),
+            time_stamp: "1735732800".to_string(),
+            hash: hash.to_string(),
+            from: "0x1111".to_string(),
+            to: "0x2222".to_string(),
+            value: "1000000".to_string(),
+            token_decimal: "6".to_string(),
+            confirmations: confirmations.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_wallet_state() {
+        // Without a start block the history is only marked as seen
+        let mut state = WalletState::new(None);
+        assert!(state.update(vec![transfer("a", 100, 50)], 12).unwrap().is_empty());
+        assert_eq!(state.start_block(), 100);
+
+        // New transfers are booked once they have enough confirmations
+        let booked = state
+            .update(vec![transfer("a", 100, 60), transfer("b", 105, 20), transfer("c", 110, 3)], 12)
+            .unwrap();
+        assert_eq!(booked, vec![transfer("b", 105, 20)]);
+        assert_eq!(state.start_block(), 105);
+        let booked = state.update(vec![transfer("b", 105, 30), transfer("c", 110, 13)], 12).unwrap();
+        assert_eq!(booked, vec![transfer("c", 110, 13)]);
+        assert_eq!(state.start_block(), 110);
+
+        // With a start block the history from there is booked
+        let mut state = WalletState::new(Some(100));
+        assert_eq!(state.update(vec![transfer("a", 100, 50)], 12).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use time::{macros::format_description, OffsetDateTime, Time};
+
+const EXTENSION: &str = "raw.gz";
+
+/// A message exactly as it was received from the venue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawRecord {
+    pub received_at: OffsetDateTime,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchivePartition {
+    #[default]
+    Hourly,
+    Daily,
+}
+
+impl ArchivePartition {
+    /// Start of the partition the given time falls into.
+    pub fn start(&self, time: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            ArchivePartition::Hourly => time.replace_time(Time::from_hms(time.hour(), 0, 0).expect("Invalid hour")),
+            ArchivePartition::Daily => time.replace_time(Time::MIDNIGHT),
+        }
+    }
+}
+
+/// Path of the archive file holding the given partition: `<directory>/<source>/<date>/<source>-<date>-<hour>.raw.gz`.
+/// File names sort in time order.
+pub fn archive_path(directory: &Path, source: &str, partition: OffsetDateTime) -> PathBuf {
+    let date = partition
+        .format(format_description!("[year]-[month]-[day]"))
+        .expect("Failed to format partition date");
+    directory
+        .join(source)
+        .join(&date)
+        .join(format!("{}-{}-{:02}.{}", source, date, partition.hour(), EXTENSION))
+}
+
+/// All archive files of a source sorted by time.
+pub fn list_archives(directory: &Path, source: &str) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for day in fs::read_dir(directory.join(source))? {
+        let day = day?.path();
+        if !day.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&day)? {
+            let file = file?.path();
+            if file.to_string_lossy().ends_with(EXTENSION) {
+                files.push(file);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Writes raw records to gzip compressed archive files, one file per partition. A record is stored as the receive
+/// time in nanoseconds (i128 LE), the length of the message (u32 LE) and the message bytes.
+///
+/// Files are opened in append mode, every writer adds a new gzip member so restarts within a partition are safe.
+#[derive(Debug)]
+pub struct ArchiveWriter {
+    directory: PathBuf,
+    source: String,
+    partition: ArchivePartition,
+    current: Option<(OffsetDateTime, GzEncoder<BufWriter<File>>)>,
+    completed: Vec<PathBuf>,
+}
+
+impl ArchiveWriter {
+    pub fn new(directory: impl Into<PathBuf>, source: &str, partition: ArchivePartition) -> Self {
+        Self {
+            directory: directory.into(),
+            source: source.to_owned(),
+            partition,
+            current: None,
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn write(&mut self, record: &RawRecord) -> io::Result<()> {
+        let partition = self.partition.start(record.received_at);
+        if let Some(previous) = self.current.as_ref().map(|(p, _)| *p).filter(|p| *p != partition) {
+            self.finish()?;
+            self.completed.push(archive_path(&self.directory, &self.source, previous));
+        }
+        if self.current.is_none() {
+            let path = archive_path(&self.directory, &self.source, partition);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            self.current = Some((partition, GzEncoder::new(BufWriter::new(file), Compression::default())));
+        }
+
+        let (_, encoder) = self.current.as_mut().expect("Archive file should be open");
+        let data = record.data.as_bytes();
+        let len = u32::try_from(data.len()).map_err(|_| io::Error::new(ErrorKind::InvalidInput, "record too large"))?;
+        encoder.write_all(&record.received_at.unix_timestamp_nanos().to_le_bytes())?;
+        encoder.write_all(&len.to_le_bytes())?;
+        encoder.write_all(data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.current.as_mut() {
+            Some((_, encoder)) => encoder.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Files of the partitions the writer moved past since the last call. They are not written to again unless
+    /// records arrive out of order.
+    pub fn take_completed(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.completed)
+    }
+
+    /// Close the current archive file.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, encoder)) = self.current.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ArchiveWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Reads the records of a single archive file in the order they were written.
+pub struct ArchiveReader<R: Read> {
+    decoder: MultiGzDecoder<BufReader<R>>,
+}
+
+impl ArchiveReader<File> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> ArchiveReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            decoder: MultiGzDecoder::new(BufReader::new(reader)),
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<RawRecord>> {
+        let mut nanos = [0u8; 16];
+        match self.decoder.read_exact(&mut nanos) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut len = [0u8; 4];
+        self.decoder.read_exact(&mut len)?;
+        let mut data = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.decoder.read_exact(&mut data)?;
+
+        let received_at = OffsetDateTime::from_unix_timestamp_nanos(i128::from_le_bytes(nanos))
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        let data = String::from_utf8(data).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        Ok(Some(RawRecord { received_at, data }))
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = io::Result<RawRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("arkin-archive-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn record(received_at: OffsetDateTime, data: &str) -> RawRecord {
+        RawRecord {
+            received_at,
+            data: data.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_partition_start() {
+        let time = datetime!(2024-06-01 13:45:12.5 UTC);
+        assert_eq!(ArchivePartition::Hourly.start(time), datetime!(2024-06-01 13:00 UTC));
+        assert_eq!(ArchivePartition::Daily.start(time), datetime!(2024-06-01 00:00 UTC));
+        assert_eq!(
+            archive_path(Path::new("/data"), "binance", datetime!(2024-06-01 09:00 UTC)),
+            PathBuf::from("/data/binance/2024-06-01/binance-2024-06-01-09.raw.gz")
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_partitions() {
+        let dir = test_dir("partitions");
+        let start = datetime!(2024-06-01 23:59:59 UTC);
+        let records = vec![
+            record(start, r#"{"e":"aggTrade","s":"BTCUSDT"}"#),
+            record(start + Duration::from_millis(500), r#"{"e":"bookTicker","s":"BTCUSDT"}"#),
+            record(start + Duration::from_secs(2), "not even json"),
+        ];
+
+        let mut writer = ArchiveWriter::new(&dir, "binance", ArchivePartition::Hourly);
+        for r in &records {
+            writer.write(r).unwrap();
+        }
+        // Only the partition before midnight is done, the current one is still written to
+        assert_eq!(
+            writer.take_completed(),
+            vec![archive_path(&dir, "binance", datetime!(2024-06-01 23:00 UTC))]
+        );
+        writer.finish().unwrap();
+
+        let files = list_archives(&dir, "binance").unwrap();
+        assert_eq!(files.len(), 2);
+        let read = files
+            .iter()
+            .flat_map(|f| ArchiveReader::open(f).unwrap())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, records);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_after_restart() {
+        let dir = test_dir("append");
+        let time = datetime!(2024-06-01 10:15 UTC);
+        for data in ["first", "second"] {
+            let mut writer = ArchiveWriter::new(&dir, "binance", ArchivePartition::Daily);
+            writer.write(&record(time, data)).unwrap();
+        }
+
+        let files = list_archives(&dir, "binance").unwrap();
+        assert_eq!(files.len(), 1);
+        let read = ArchiveReader::open(&files[0])
+            .unwrap()
+            .map(|r| r.unwrap().data)
+            .collect::<Vec<_>>();
+        assert_eq!(read, vec!["first", "second"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+mod archive;
+mod service;
+
+pub use archive::*;
+pub use service::ArchiveStore;
+pub use service::FeedRecorder;
+pub use service::FeedRecorderBuilder;
@@ -0,0 +1,181 @@
+use std::{path::PathBuf, time::Duration};
+
+use flume::{Receiver, Sender};
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use typed_builder::TypedBuilder;
+
+#[cfg(feature = "object-storage")]
+use arkin_core::prelude::ArtifactStore;
+
+use crate::{config::FeedRecorderConfig, IngestorError};
+
+use super::archive::{ArchivePartition, ArchiveWriter, RawRecord};
+
+/// Store the recorder uploads completed archives to.
+#[cfg(feature = "object-storage")]
+pub type ArchiveStore = ArtifactStore;
+
+/// Store the recorder uploads completed archives to, there is none without the object-storage feature.
+#[cfg(not(feature = "object-storage"))]
+#[derive(Debug, Clone)]
+pub enum ArchiveStore {}
+
+#[cfg(not(feature = "object-storage"))]
+impl ArchiveStore {
+    async fn upload(&self, _path: &std::path::Path, _key: &str) -> anyhow::Result<()> {
+        match *self {}
+    }
+
+    fn url(&self) -> &str {
+        match *self {}
+    }
+}
+
+/// Records the raw messages of a feed before they are parsed, so parser bugs and feed incidents can be reproduced
+/// exactly by replaying the archive. Ingestors hand messages over with [`FeedRecorder::record`], the writing happens
+/// in [`FeedRecorder::run`] so the feed is never blocked on disk.
+#[derive(Debug, TypedBuilder)]
+pub struct FeedRecorder {
+    directory: PathBuf,
+    source: String,
+    #[builder(default)]
+    partition: ArchivePartition,
+    #[builder(default = Duration::from_secs(1))]
+    flush_interval: Duration,
+    /// Completed archives are uploaded here
+    #[builder(default)]
+    upload: Option<ArchiveStore>,
+    #[builder(default)]
+    keep_local: bool,
+    #[builder(default = flume::unbounded())]
+    channel: (Sender<RawRecord>, Receiver<RawRecord>),
+}
+
+impl FeedRecorder {
+    pub fn from_config(config: &FeedRecorderConfig, source: &str) -> Self {
+        // The feed is more important than the upload, without a store the archives stay on disk
+        #[cfg(feature = "object-storage")]
+        let upload = config.upload.as_ref().and_then(|c| match ArtifactStore::from_config(c) {
+            Ok(store) => Some(store),
+            Err(e) => {
+                error!("Failed to set up archive upload to {}: {}", c.url, e);
+                None
+            }
+        });
+        #[cfg(not(feature = "object-storage"))]
+        let upload = config.upload.as_ref().and_then(|c| {
+            warn!(
+                "Built without the object-storage feature, archives are not uploaded to {}",
+                c.url
+            );
+            None
+        });
+        Self::builder()
+            .directory(PathBuf::from(&config.directory))
+            .source(source.to_owned())
+            .partition(config.partition)
+            .upload(upload)
+            .keep_local(config.keep_local)
+            .build()
+    }
+
+    pub fn record(&self, data: &str) {
+        let record = RawRecord {
+            received_at: OffsetDateTime::now_utc(),
+            data: data.to_owned(),
+        };
+        if let Err(e) = self.channel.0.send(record) {
+            error!("Failed to queue raw message for recording: {}", e);
+        }
+    }
+
+    pub async fn run(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        info!("Recording raw {} feed to {}", self.source, self.directory.display());
+        let mut writer = ArchiveWriter::new(&self.directory, &self.source, self.partition);
+        let mut flush = tokio::time::interval(self.flush_interval);
+        loop {
+            tokio::select! {
+                Ok(record) = self.channel.1.recv_async() => {
+                    writer.write(&record)?;
+                    self.upload_completed(&mut writer).await;
+                }
+                _ = flush.tick() => {
+                    writer.flush()?;
+                }
+                _ = shutdown.cancelled() => {
+                    // Write what is still queued before closing the archive
+                    for record in self.channel.1.drain() {
+                        writer.write(&record)?;
+                    }
+                    writer.finish()?;
+                    info!("Raw {} feed recorder stopped", self.source);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Upload the archives the writer moved past. Failed uploads stay on disk and are not retried.
+    async fn upload_completed(&self, writer: &mut ArchiveWriter) {
+        let Some(store) = &self.upload else {
+            return;
+        };
+        for path in writer.take_completed() {
+            let Ok(relative) = path.strip_prefix(&self.directory) else {
+                continue;
+            };
+            let key = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            match store.upload(&path, &key).await {
+                Ok(()) if !self.keep_local => {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        warn!("Failed to remove uploaded archive {}: {}", path.display(), e);
+                    }
+                }
+                Ok(()) => {}
+                Err(e) => error!("Failed to upload archive {} to {}: {}", path.display(), store.url(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::recorder::{list_archives, ArchiveReader};
+
+    #[test(tokio::test)]
+    async fn test_records_until_shutdown() {
+        let dir = std::env::temp_dir().join(format!("arkin-recorder-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let recorder = FeedRecorder::builder()
+            .directory(dir.clone())
+            .source("binance".to_owned())
+            .build();
+
+        let shutdown = CancellationToken::new();
+        recorder.record(r#"{"e":"aggTrade"}"#);
+        recorder.record(r#"{"e":"bookTicker"}"#);
+        shutdown.cancel();
+        recorder.run(shutdown).await.unwrap();
+
+        let files = list_archives(&dir, "binance").unwrap();
+        let data = files
+            .iter()
+            .flat_map(|f| ArchiveReader::open(f).unwrap())
+            .map(|r| r.unwrap().data)
+            .collect::<Vec<_>>();
+        assert_eq!(data, vec![r#"{"e":"aggTrade"}"#, r#"{"e":"bookTicker"}"#]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use std::{sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use tracing::warn;
+
+use arkin_core::prelude::*;
+
+use crate::config::{DataGapConfig, DataGapPolicy};
+
+use super::SimEvent;
+
+/// Finds the stretches without any data on a replayed channel that last longer than the configured gap. A gap ends
+/// at the first event after it, it is marked in the stream of the channel right before that event with the time of
+/// its start, so the merge sees it before anything within it. Gaps are found per channel, an instrument of the channel
+/// going quiet while the others trade on is not one.
+#[derive(Debug)]
+pub struct GapDetector {
+    channel: String,
+    max_gap: Duration,
+    policy: DataGapPolicy,
+    /// Time of the last event of the channel, or of the start of the replay or the session
+    last: OffsetDateTime,
+    aborted: bool,
+}
+
+impl GapDetector {
+    pub fn new(config: &DataGapConfig, channel: String, start: OffsetDateTime) -> Self {
+        Self {
+            channel,
+            max_gap: Duration::from_secs(config.max_gap_secs),
+            policy: config.policy,
+            last: start,
+            aborted: false,
+        }
+    }
+
+    /// Mark the gaps before the events, which are sorted by time. When aborting the events end at the first gap.
+    pub fn scan(&mut self, events: Vec<SimEvent>) -> Vec<SimEvent> {
+        let mut scanned = Vec::with_capacity(events.len());
+        for event in events {
+            if let Some(gap) = self.advance(event.event_time()) {
+                scanned.push(gap);
+            }
+            if self.aborted {
+                break;
+            }
+            scanned.push(event);
+        }
+        scanned
+    }
+
+    /// Move on to a time the channel has no more data until, like the end of a session or of the replay.
+    pub fn advance(&mut self, at: OffsetDateTime) -> Option<SimEvent> {
+        let start = self.last;
+        self.last = self.last.max(at);
+        if self.aborted || at - start <= self.max_gap {
+            return None;
+        }
+
+        let gap = DataGap::builder()
+            .event_time(start)
+            .channel(self.channel.clone())
+            .start(start)
+            .end(at)
+            .skipped(self.policy == DataGapPolicy::Skip)
+            .build();
+        warn!("Gap in the replayed data: {}", gap);
+        match self.policy {
+            DataGapPolicy::Warn => None,
+            DataGapPolicy::Publish | DataGapPolicy::Skip => Some(SimEvent::Gap(Arc::new(gap))),
+            DataGapPolicy::Abort => {
+                self.aborted = true;
+                Some(SimEvent::Abort(Arc::new(gap)))
+            }
+        }
+    }
+
+    /// True once a gap aborted the replay, the channel has nothing more to replay.
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Start watching again at the given time, like the start of a session.
+    pub fn reset(&mut self, at: OffsetDateTime) {
+        self.last = at;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_gap_detector() {
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let start = datetime!(2025-01-01 00:00 UTC);
+        let trade = |minutes: i64| {
+            SimEvent::Trade(Arc::new(
+                Trade::builder()
+                    .event_time(start + time::Duration::minutes(minutes))
+                    .instrument(instrument.clone())
+                    .trade_id(minutes as u64)
+                    .side(MarketSide::Buy)
+                    .price(dec!(100))
+                    .quantity(dec!(1))
+                    .build(),
+            ))
+        };
+        let config = |policy: DataGapPolicy| DataGapConfig {
+            max_gap_secs: 600,
+            policy,
+        };
+
+        // Two hours of trades missing after the first ten minutes
+        let events = || vec![trade(5), trade(10), trade(130), trade(135)];
+        let mut detector = GapDetector::new(&config(DataGapPolicy::Publish), "trades".into(), start);
+        let scanned = detector.scan(events());
+        assert_eq!(scanned.len(), 5);
+        let SimEvent::Gap(gap) = &scanned[2] else {
+            panic!("Expected a gap before the trades resume");
+        };
+        assert_eq!(gap.event_time, start + time::Duration::minutes(10));
+        assert_eq!(gap.end, start + time::Duration::minutes(130));
+        assert!(!gap.skipped);
+        // The end of the replay closes a gap as well
+        assert!(detector.advance(start + time::Duration::minutes(140)).is_none());
+        assert!(detector.advance(start + time::Duration::minutes(150)).is_some());
+
+        let mut detector = GapDetector::new(&config(DataGapPolicy::Warn), "trades".into(), start);
+        assert_eq!(detector.scan(events()).len(), 4);
+
+        let mut detector = GapDetector::new(&config(DataGapPolicy::Skip), "trades".into(), start);
+        let scanned = detector.scan(events());
+        assert!(matches!(&scanned[2], SimEvent::Gap(gap) if gap.skipped));
+
+        // Aborting ends the replay of the channel at the gap
+        let mut detector = GapDetector::new(&config(DataGapPolicy::Abort), "trades".into(), start);
+        let scanned = detector.scan(events());
+        assert_eq!(scanned.len(), 3);
+        assert!(matches!(&scanned[2], SimEvent::Abort(_)));
+        assert!(detector.aborted());
+        assert!(detector.advance(start + time::Duration::minutes(300)).is_none());
+    }
+}
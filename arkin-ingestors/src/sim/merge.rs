@@ -0,0 +1,298 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use time::OffsetDateTime;
+use tokio::{sync::mpsc, time::Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use arkin_core::prelude::*;
+
+use crate::config::ReplayPacing;
+
+use super::{ReplayPacer, ReplayWindow, SimEvent};
+
+/// Batches a replay stream may load ahead of the merge.
+pub const STREAM_BUFFER: usize = 2;
+
+/// One replayed stream, batches of events sorted by event time with every batch starting at or after the end of
+/// the previous one.
+#[derive(Debug)]
+struct MergeStream {
+    receiver: mpsc::Receiver<Vec<SimEvent>>,
+    buffer: VecDeque<SimEvent>,
+}
+
+impl MergeStream {
+    /// Next event of the stream, waiting for the next batch once the buffer ran empty. None once the stream ended.
+    async fn next(&mut self) -> Option<SimEvent> {
+        loop {
+            if let Some(event) = self.buffer.pop_front() {
+                return Some(event);
+            }
+            self.buffer = self.receiver.recv().await?.into();
+        }
+    }
+}
+
+/// Merges the replay streams into one by event time before publishing, so downstream sees strictly ordered
+/// timestamps no matter how the streams interleave their loading. An event is only published once every stream
+/// still running has shown its next event, the one with the earliest event time goes first. Events with the same
+/// time go in the order of the streams. A skipped gap drops the market data within it from every stream, a gap
+/// aborting the replay stops the merge. With a progress tracker the merge publishes how far the replay got, with a
+/// replay barrier it stops at the barriers until the simulation is checkpointed.
+#[derive(Debug)]
+pub struct ReplayMerger {
+    pubsub: Arc<PubSub>,
+    streams: Vec<MergeStream>,
+    /// Holds the events while a debugged simulation is paused
+    debugger: Option<Arc<SimDebugger>>,
+    /// Holds the events while a checkpoint of the simulation is taken
+    barrier: Option<Arc<ReplayBarrier>>,
+    /// Follows the progress of the replay to size its windows
+    window: Option<Arc<ReplayWindow>>,
+    pacer: ReplayPacer,
+    /// End of the skipped gaps, market data before it is dropped
+    skip_until: Option<OffsetDateTime>,
+    /// Follows the published events through the replayed range
+    progress: Option<ProgressTracker>,
+    /// Wall clock time between the progress updates
+    progress_interval: Duration,
+}
+
+impl ReplayMerger {
+    pub fn new(pubsub: Arc<PubSub>, debugger: Option<Arc<SimDebugger>>) -> Self {
+        Self {
+            pubsub,
+            streams: Vec::new(),
+            debugger,
+            barrier: None,
+            window: None,
+            pacer: ReplayPacer::new(ReplayPacing::Fast),
+            skip_until: None,
+            progress: None,
+            progress_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Publish the events at the given speed against the wall clock.
+    pub fn with_pacing(mut self, pacing: ReplayPacing) -> Self {
+        self.pacer = ReplayPacer::new(pacing);
+        self
+    }
+
+    /// Publish the progress of the replay at the given wall clock interval and once it finished.
+    pub fn with_progress(mut self, tracker: ProgressTracker, interval: Duration) -> Self {
+        self.progress = Some(tracker);
+        self.progress_interval = interval;
+        self
+    }
+
+    pub fn with_barrier(mut self, barrier: Option<Arc<ReplayBarrier>>) -> Self {
+        self.barrier = barrier;
+        self
+    }
+
+    pub fn with_window(mut self, window: Arc<ReplayWindow>) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Add a stream, the replay sends its batches to the returned sender and drops it when it is done.
+    pub fn stream(&mut self) -> mpsc::Sender<Vec<SimEvent>> {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        self.streams.push(MergeStream {
+            receiver,
+            buffer: VecDeque::new(),
+        });
+        sender
+    }
+
+    async fn next(&mut self, stream: usize, shutdown: &CancellationToken) -> Option<SimEvent> {
+        tokio::select! {
+            event = self.streams[stream].next() => event,
+            _ = shutdown.cancelled() => None,
+        }
+    }
+
+    /// True for market data within a skipped gap.
+    fn skipped(&self, event: &SimEvent) -> bool {
+        let market_data = matches!(event, SimEvent::Trade(_) | SimEvent::Tick(_) | SimEvent::Book(_));
+        market_data && self.skip_until.is_some_and(|until| event.event_time() < until)
+    }
+
+    /// Skip the market data within the gap on every stream.
+    fn skip(&mut self, gap: &DataGap) {
+        let from = self.skip_until.unwrap_or(gap.start).max(gap.start);
+        if gap.end > from {
+            self.pacer.skip(gap.end - from);
+            self.skip_until = Some(gap.end);
+        }
+    }
+
+    /// Publish the events of all streams in order of event time until every stream ended. Returns the gap that
+    /// aborted the replay, if any.
+    pub async fn run(mut self, shutdown: CancellationToken) -> Option<Arc<DataGap>> {
+        let mut heads = (0..self.streams.len()).map(|_| None).collect::<Vec<Option<SimEvent>>>();
+        let mut heap = BinaryHeap::new();
+        for stream in 0..self.streams.len() {
+            if let Some(event) = self.next(stream, &shutdown).await {
+                heap.push(Reverse((event.event_time(), stream)));
+                heads[stream] = Some(event);
+            }
+        }
+
+        let mut published = 0u64;
+        let mut skipped = 0u64;
+        let mut last = OffsetDateTime::UNIX_EPOCH;
+        let mut reported = Instant::now();
+        while let Some(Reverse((event_time, stream))) = heap.pop() {
+            if shutdown.is_cancelled() {
+                return None;
+            }
+            let Some(event) = heads[stream].take() else {
+                continue;
+            };
+            if event_time < last {
+                warn!("Replay stream {} went back in time from {} to {}", stream, last, event_time);
+            }
+            last = last.max(event_time);
+            if self.skipped(&event) {
+                skipped += 1;
+            } else {
+                tokio::select! {
+                    _ = self.pacer.wait(event_time) => {},
+                    _ = shutdown.cancelled() => return None,
+                }
+                if let Some(debugger) = self.debugger.as_ref().filter(|_| !matches!(event, SimEvent::StreamEnded(_))) {
+                    let paused = Instant::now();
+                    tokio::select! {
+                        _ = debugger.gate(&event.to_event()) => {},
+                        _ = shutdown.cancelled() => return None,
+                    }
+                    self.pacer.hold(paused.elapsed());
+                }
+                if let Some(barrier) = &self.barrier {
+                    let held = Instant::now();
+                    tokio::select! {
+                        _ = barrier.gate(event_time) => {},
+                        _ = shutdown.cancelled() => return None,
+                    }
+                    self.pacer.hold(held.elapsed());
+                }
+                let aborted = match &event {
+                    SimEvent::Gap(gap) if gap.skipped => {
+                        self.skip(gap);
+                        None
+                    }
+                    SimEvent::Abort(gap) => Some(gap.clone()),
+                    _ => None,
+                };
+                event.publish(&self.pubsub);
+                published += 1;
+                if let Some(progress) = self.progress.as_mut() {
+                    progress.add_events(1);
+                    if aborted.is_some() || reported.elapsed() >= self.progress_interval {
+                        self.pubsub.publish::<SimulationProgress>(progress.update(event_time).into());
+                        reported = Instant::now();
+                    }
+                }
+                if let Some(gap) = aborted {
+                    warn!("Replay aborted at the gap in {} from {} to {}", gap.channel, gap.start, gap.end);
+                    return Some(gap);
+                }
+            }
+            if let Some(window) = &self.window {
+                window.advance(event_time);
+            }
+
+            if let Some(next) = self.next(stream, &shutdown).await {
+                heap.push(Reverse((next.event_time(), stream)));
+                heads[stream] = Some(next);
+            }
+        }
+        if let Some(progress) = &self.progress {
+            self.pubsub.publish::<SimulationProgress>(progress.finish().into());
+        }
+        debug!("Replay merger published {} events, skipped {} in gaps", published, skipped);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use test_log::test;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_merge_by_event_time() {
+        let pubsub = Arc::new(PubSub::new());
+        let mut events = pubsub.subscribe_events(EventFilter::default());
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let start = datetime!(2025-01-01 00:00:00 UTC);
+        let tick = |secs: i64| {
+            let mut tick = (*test_tick(instrument.clone(), dec!(99), dec!(1), dec!(101), dec!(1))).clone();
+            tick.event_time = start + time::Duration::seconds(secs);
+            SimEvent::Tick(Arc::new(tick))
+        };
+        let trade = |secs: i64| {
+            SimEvent::Trade(Arc::new(
+                Trade::builder()
+                    .event_time(start + time::Duration::seconds(secs))
+                    .instrument(instrument.clone())
+                    .trade_id(secs as u64)
+                    .side(MarketSide::Buy)
+                    .price(dec!(100))
+                    .quantity(dec!(1))
+                    .build(),
+            ))
+        };
+
+        let tracker = ProgressTracker::new(start, start + time::Duration::seconds(9));
+        let mut merger = ReplayMerger::new(pubsub.clone(), None).with_progress(tracker, Duration::from_secs(3600));
+        let ticks = merger.stream();
+        let trades = merger.stream();
+        let shutdown = CancellationToken::new();
+        let merge = tokio::spawn(merger.run(shutdown.clone()));
+
+        // The trades deliver their batches first and both streams overlap in time
+        trades.send(vec![trade(1), trade(4)]).await.unwrap();
+        trades.send(vec![trade(5), trade(9)]).await.unwrap();
+        drop(trades);
+        ticks.send(vec![tick(0), tick(4)]).await.unwrap();
+        ticks.send(vec![tick(6)]).await.unwrap();
+        drop(ticks);
+        merge.await.unwrap();
+
+        let mut published = Vec::new();
+        let mut progress = None;
+        while let Ok(event) = events.try_recv() {
+            let event_time = match &event {
+                Event::Tick(t) => t.event_time,
+                Event::Trade(t) => t.event_time,
+                Event::SimulationProgress(p) => {
+                    progress = Some(p.clone());
+                    continue;
+                }
+                _ => continue,
+            };
+            published.push((event_time, event.event_type()));
+        }
+        let secs = published.iter().map(|(t, _)| (*t - start).whole_seconds()).collect::<Vec<_>>();
+        assert_eq!(secs, vec![0, 1, 4, 4, 5, 6, 9]);
+        // Ties go in the order of the streams
+        assert_eq!(published[2].1, EventType::Tick);
+        assert_eq!(published[3].1, EventType::Trade);
+        // The finished replay reports all of its events through the whole range
+        let progress = progress.expect("No progress published");
+        assert_eq!(progress.events, 7);
+        assert!((progress.fraction - 1.).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,13 @@
+mod gaps;
+mod merge;
+mod pacing;
+mod service;
+mod session;
+mod window;
+
+pub use gaps::GapDetector;
+pub use merge::{ReplayMerger, STREAM_BUFFER};
+pub use pacing::ReplayPacer;
+pub use service::{ReplayTask, SimChannel, SimEvent, SimIngestor};
+pub use session::{next_session, ReplaySession};
+pub use window::ReplayWindow;
@@ -0,0 +1,129 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{bail, Error};
+use time::OffsetDateTime;
+use tokio::time::Instant;
+
+use crate::config::ReplayPacing;
+
+/// Parses `fast`, `realtime` or a multiplier like `10x`.
+impl FromStr for ReplayPacing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "fast" => Ok(ReplayPacing::Fast),
+            "realtime" | "real_time" => Ok(ReplayPacing::RealTime),
+            speed => {
+                let Some(Ok(multiplier)) = speed.strip_suffix('x').map(|m| m.parse::<f64>()) else {
+                    bail!("invalid replay pacing: {}, use fast, realtime or a multiplier like 10x", s);
+                };
+                if !multiplier.is_finite() || multiplier <= 0. {
+                    bail!("multiplier of replay pacing has to be positive: {}", s);
+                }
+                Ok(ReplayPacing::Multiplier(multiplier))
+            }
+        }
+    }
+}
+
+/// Holds the replayed events back until they are due on the wall clock. The first event is published right away and
+/// anchors the replay, every later one is due once its distance in event time from the anchor, divided by the speed,
+/// passed on the wall clock.
+#[derive(Debug)]
+pub struct ReplayPacer {
+    /// Replayed seconds per second of wall clock, none to replay as fast as possible
+    speed: Option<f64>,
+    anchor: Option<(OffsetDateTime, Instant)>,
+}
+
+impl ReplayPacer {
+    pub fn new(pacing: ReplayPacing) -> Self {
+        let speed = match pacing {
+            ReplayPacing::Fast => None,
+            ReplayPacing::Multiplier(multiplier) => Some(multiplier),
+            ReplayPacing::RealTime => Some(1.),
+        };
+        Self {
+            speed,
+            anchor: None,
+        }
+    }
+
+    /// Wall clock time until the event is due, zero when it is due already.
+    pub fn delay(&mut self, event_time: OffsetDateTime, now: Instant) -> Duration {
+        let Some(speed) = self.speed else {
+            return Duration::ZERO;
+        };
+        let (anchor_time, anchor) = *self.anchor.get_or_insert((event_time, now));
+        let replayed = (event_time - anchor_time).as_seconds_f64().max(0.);
+        let due = anchor + Duration::from_secs_f64(replayed / speed);
+        due.saturating_duration_since(now)
+    }
+
+    /// Move the anchor forward by a time the replay stood still, like a pause in the debugger, so the replay doesn't
+    /// rush to catch up afterwards.
+    pub fn hold(&mut self, paused: Duration) {
+        if let Some((_, anchor)) = &mut self.anchor {
+            *anchor += paused;
+        }
+    }
+
+    /// Move the anchor forward by a span of event time the replay skipped, so the events after it are due right away.
+    pub fn skip(&mut self, span: time::Duration) {
+        if let Some((anchor_time, _)) = &mut self.anchor {
+            *anchor_time += span;
+        }
+    }
+
+    /// Wait until the event is due.
+    pub async fn wait(&mut self, event_time: OffsetDateTime) {
+        let delay = self.delay(event_time, Instant::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_replay_pacing() {
+        assert_eq!("fast".parse::<ReplayPacing>().unwrap(), ReplayPacing::Fast);
+        assert_eq!("realtime".parse::<ReplayPacing>().unwrap(), ReplayPacing::RealTime);
+        assert_eq!("10x".parse::<ReplayPacing>().unwrap(), ReplayPacing::Multiplier(10.));
+        assert!("0x".parse::<ReplayPacing>().is_err());
+        assert!("slow".parse::<ReplayPacing>().is_err());
+
+        let start = datetime!(2025-01-01 12:00 UTC);
+        let now = Instant::now();
+        let mut fast = ReplayPacer::new(ReplayPacing::Fast);
+        assert_eq!(fast.delay(start, now), Duration::ZERO);
+        assert_eq!(fast.delay(start + time::Duration::hours(1), now), Duration::ZERO);
+
+        // Ten minutes of replay take a minute at 10x
+        let mut pacer = ReplayPacer::new(ReplayPacing::Multiplier(10.));
+        assert_eq!(pacer.delay(start, now), Duration::ZERO);
+        let event_time = start + time::Duration::minutes(10);
+        assert_eq!(pacer.delay(event_time, now), Duration::from_secs(60));
+        assert_eq!(pacer.delay(event_time, now + Duration::from_secs(45)), Duration::from_secs(15));
+        assert_eq!(pacer.delay(event_time, now + Duration::from_secs(90)), Duration::ZERO);
+
+        // A pause in the debugger pushes the later events back
+        pacer.hold(Duration::from_secs(30));
+        assert_eq!(pacer.delay(event_time, now + Duration::from_secs(60)), Duration::from_secs(30));
+
+        // Skipping a gap of an hour makes the events after it due as if it was never there
+        pacer.skip(time::Duration::hours(1));
+        let after_gap = event_time + time::Duration::hours(1) + time::Duration::minutes(10);
+        assert_eq!(pacer.delay(after_gap, now + Duration::from_secs(60)), Duration::from_secs(90));
+
+        let mut real_time = ReplayPacer::new(ReplayPacing::RealTime);
+        real_time.delay(start, now);
+        assert_eq!(real_time.delay(start + time::Duration::seconds(5), now), Duration::from_secs(5));
+    }
+}
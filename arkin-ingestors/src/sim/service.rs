@@ -0,0 +1,452 @@
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{bail, Error, Result};
+use async_trait::async_trait;
+use time::{macros::format_description, OffsetDateTime, PrimitiveDateTime};
+use tokio::sync::mpsc;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{debug, error, info, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+use arkin_persistence::prelude::*;
+
+use crate::config::{DataGapConfig, ReplayPacing, SimIngestorConfig};
+use crate::traits::Ingestor;
+use crate::IngestorError;
+
+use super::{next_session, GapDetector, ReplayMerger, ReplaySession, ReplayWindow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimChannel {
+    Trades,
+    Ticks,
+    /// Order book updates, lets the simulation executor fill orders taking liquidity from the rebuilt book
+    Depth,
+}
+
+impl fmt::Display for SimChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SimChannel::Trades => "trades",
+                SimChannel::Ticks => "ticks",
+                SimChannel::Depth => "depth",
+            }
+        )
+    }
+}
+
+impl FromStr for SimChannel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "trades" => Ok(SimChannel::Trades),
+            "ticks" => Ok(SimChannel::Ticks),
+            "depth" => Ok(SimChannel::Depth),
+            _ => bail!("invalid sim channel: {}", s),
+        }
+    }
+}
+
+/// A single replayed event.
+#[derive(Debug, Clone)]
+pub enum SimEvent {
+    Trade(Arc<Trade>),
+    Tick(Arc<Tick>),
+    Book(Arc<Book>),
+    Action(Arc<OperatorAction>),
+    StreamEnded(Arc<StreamEnded>),
+    /// Gap in the data of a stream, published before the stream resumes
+    Gap(Arc<DataGap>),
+    /// Gap in the data of a stream that ends the replay
+    Abort(Arc<DataGap>),
+}
+
+impl SimEvent {
+    pub fn event_time(&self) -> OffsetDateTime {
+        match self {
+            SimEvent::Trade(t) => t.event_time,
+            SimEvent::Tick(t) => t.event_time,
+            SimEvent::Book(b) => b.event_time,
+            SimEvent::Action(a) => a.event_time,
+            SimEvent::StreamEnded(e) => e.event_time,
+            SimEvent::Gap(g) | SimEvent::Abort(g) => g.event_time,
+        }
+    }
+
+    pub fn to_event(&self) -> Event {
+        match self {
+            SimEvent::Trade(t) => Event::Trade(t.clone()),
+            SimEvent::Tick(t) => Event::Tick(t.clone()),
+            SimEvent::Book(b) => Event::Book(b.clone()),
+            SimEvent::Action(a) => Event::OperatorAction(a.clone()),
+            SimEvent::StreamEnded(e) => Event::StreamEnded(e.clone()),
+            SimEvent::Gap(g) | SimEvent::Abort(g) => Event::DataGap(g.clone()),
+        }
+    }
+
+    pub fn publish(self, pubsub: &PubSub) {
+        match self {
+            SimEvent::Trade(t) => pubsub.publish::<Trade>(t),
+            SimEvent::Tick(t) => pubsub.publish::<Tick>(t),
+            SimEvent::Book(b) => pubsub.publish::<Book>(b),
+            SimEvent::Action(a) => {
+                info!("Injecting operator action: {}", a);
+                pubsub.publish::<OperatorAction>(a)
+            }
+            SimEvent::StreamEnded(e) => pubsub.publish::<StreamEnded>(e),
+            SimEvent::Gap(g) | SimEvent::Abort(g) => pubsub.publish::<DataGap>(g),
+        }
+    }
+}
+
+/// Replays one channel from persistence in chunks, handing every chunk to the merge of the replayed streams. With
+/// sessions only the hours of the day within them are replayed. Gaps in the data are marked in the stream, a gap
+/// aborting the replay ends the stream.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ReplayTask {
+    persistence: Arc<PersistenceService>,
+    /// Stream of the channel in the merge
+    sender: mpsc::Sender<Vec<SimEvent>>,
+    channel: SimChannel,
+    instruments: Vec<Arc<Instrument>>,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    /// Window loaded at once, shared by the channels
+    window: Arc<ReplayWindow>,
+    /// Hours of the day replayed, the whole day without them
+    #[builder(default)]
+    sessions: Vec<ReplaySession>,
+    #[builder(default)]
+    gaps: Option<DataGapConfig>,
+}
+
+impl ReplayTask {
+    async fn load(&self, from: OffsetDateTime, to: OffsetDateTime) -> Result<Vec<SimEvent>, IngestorError> {
+        let mut events = match self.channel {
+            SimChannel::Trades => self
+                .persistence
+                .trade_store
+                .read_range(&self.instruments, from, to)
+                .await?
+                .into_iter()
+                .map(SimEvent::Trade)
+                .collect::<Vec<_>>(),
+            SimChannel::Ticks => {
+                let ids = self.instruments.iter().map(|i| i.id).collect::<Vec<_>>();
+                self.persistence
+                    .tick_store
+                    .read_range(&ids, from, to)
+                    .await?
+                    .into_iter()
+                    .map(SimEvent::Tick)
+                    .collect::<Vec<_>>()
+            }
+            SimChannel::Depth => {
+                let ids = self.instruments.iter().map(|i| i.id).collect::<Vec<_>>();
+                self.persistence
+                    .book_store
+                    .read_range(&ids, from, to)
+                    .await?
+                    .into_iter()
+                    .map(SimEvent::Book)
+                    .collect::<Vec<_>>()
+            }
+        };
+        events.sort_by_key(|e| e.event_time());
+        Ok(events)
+    }
+
+    /// Replay the channel, the stream ends in the merge once the task returns, also on failure, so it never holds
+    /// back the other channels.
+    pub async fn run(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        let name = self.channel.to_string();
+        let mut from = self.start;
+        let mut gaps = self.gaps.as_ref().map(|c| GapDetector::new(c, name.clone(), self.start));
+        // Gap markers waiting for the next batch of the stream
+        let mut pending = Vec::new();
+
+        while from < self.end {
+            let (open, close) = next_session(&self.sessions, from).unwrap_or((from, self.end));
+            if open > from {
+                debug!("Replay {} skips from {} to the session at {}", name, from, open);
+                // The time between the sessions is no gap, only the end of the session before
+                if let Some(gaps) = &mut gaps {
+                    pending.extend(gaps.advance(from));
+                    gaps.reset(open);
+                }
+            }
+            if open >= self.end {
+                break;
+            }
+            from = open;
+            let to = (from + self.window.next()).min(close).min(self.end);
+            let mut events = self.load(from, to).await?;
+            debug!("Replay {} loaded {} events from {} to {}", name, events.len(), from, to);
+            if let Some(gaps) = &mut gaps {
+                events = std::mem::take(&mut pending).into_iter().chain(gaps.scan(events)).collect();
+                if gaps.aborted() {
+                    self.send(events, &shutdown).await;
+                    return Ok(());
+                }
+            }
+            if !events.is_empty() && !self.send(events, &shutdown).await {
+                return Ok(());
+            }
+            from = to;
+        }
+        if let Some(gaps) = &mut gaps {
+            pending.extend(gaps.advance(self.end));
+            if gaps.aborted() {
+                self.send(pending, &shutdown).await;
+                return Ok(());
+            }
+        }
+
+        info!("Replay of {} finished at {}", name, self.end);
+        let mut venues = self.instruments.iter().map(|i| i.venue.clone()).collect::<Vec<_>>();
+        venues.dedup_by_key(|v| v.id);
+        pending.extend(venues.into_iter().map(|venue| {
+            let ended = StreamEnded::builder()
+                .event_time(self.end)
+                .venue(venue)
+                .channel(name.clone())
+                .build();
+            SimEvent::StreamEnded(Arc::new(ended))
+        }));
+        self.send(pending, &shutdown).await;
+        Ok(())
+    }
+
+    /// Hand a chunk to the merge, waiting while it is behind. False once the replay is shut down.
+    async fn send(&self, events: Vec<SimEvent>, shutdown: &CancellationToken) -> bool {
+        tokio::select! {
+            res = self.sender.send(events) => res.is_ok(),
+            _ = shutdown.cancelled() => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SimIngestor {
+    pubsub: Arc<PubSub>,
+    persistence: Arc<PersistenceService>,
+    channels: Vec<SimChannel>,
+    venue: String,
+    instruments: Vec<String>,
+    /// Instruments replayed on a channel, all instruments on channels without an entry
+    channel_instruments: HashMap<SimChannel, Vec<String>>,
+    sessions: Vec<ReplaySession>,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    window: Arc<ReplayWindow>,
+    pacing: ReplayPacing,
+    gaps: Option<DataGapConfig>,
+    /// Operator actions in order of time
+    actions: Vec<Arc<OperatorAction>>,
+    /// Wall clock time between the published progress updates
+    progress_interval: Duration,
+    debugger: Option<Arc<SimDebugger>>,
+    /// Holds the replay while the simulation is checkpointed
+    barrier: Option<Arc<ReplayBarrier>>,
+}
+
+impl SimIngestor {
+    pub fn from_config(config: &SimIngestorConfig, pubsub: Arc<PubSub>, persistence: Arc<PersistenceService>) -> Self {
+        let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+        let start = PrimitiveDateTime::parse(&config.start, &format)
+            .expect("Failed to parse start date")
+            .assume_utc();
+        let end = PrimitiveDateTime::parse(&config.end, &format)
+            .expect("Failed to parse end date")
+            .assume_utc();
+        let mut actions = config
+            .actions
+            .iter()
+            .map(|a| {
+                let event_time = PrimitiveDateTime::parse(&a.at, &format)
+                    .expect("Failed to parse time of operator action")
+                    .assume_utc();
+                Arc::new(
+                    OperatorAction::builder()
+                        .event_time(event_time)
+                        .action(a.action.clone())
+                        .reason(a.reason.clone())
+                        .build(),
+                )
+            })
+            .collect::<Vec<_>>();
+        actions.sort_by_key(|a| a.event_time);
+
+        Self {
+            pubsub,
+            persistence,
+            channels: config
+                .channels
+                .iter()
+                .map(|c| SimChannel::from_str(c).expect("Invalid channel for sim ingestor"))
+                .collect(),
+            venue: config.venue.to_owned(),
+            instruments: config.instruments.to_owned(),
+            channel_instruments: config
+                .channel_instruments
+                .iter()
+                .map(|(c, symbols)| {
+                    let channel = SimChannel::from_str(c).expect("Invalid channel for sim ingestor");
+                    (channel, symbols.to_owned())
+                })
+                .collect(),
+            sessions: config
+                .sessions
+                .iter()
+                .map(|s| s.parse().expect("Invalid session for sim ingestor"))
+                .collect(),
+            start,
+            end,
+            window: Arc::new(ReplayWindow::new(
+                Duration::from_secs(config.chunk_secs),
+                config.adaptive_window.clone(),
+            )),
+            pacing: config.pacing,
+            gaps: config.gaps.clone(),
+            actions,
+            progress_interval: Duration::from_millis(config.progress_interval_ms),
+            debugger: None,
+            barrier: None,
+        }
+    }
+
+    /// Pause the replay at the breakpoints of the debugger.
+    pub fn with_debugger(mut self, debugger: Option<Arc<SimDebugger>>) -> Self {
+        self.debugger = debugger;
+        self
+    }
+
+    /// Hold the replay at the barriers of the checkpoints.
+    pub fn with_barrier(mut self, barrier: Option<Arc<ReplayBarrier>>) -> Self {
+        self.barrier = barrier;
+        self
+    }
+}
+
+#[async_trait]
+impl Ingestor for SimIngestor {
+    async fn start(&self, shutdown: CancellationToken) -> Result<(), IngestorError> {
+        info!("Starting sim ingestor from {} till {}...", self.start, self.end);
+
+        let mut instruments = vec![];
+        for symbol in &self.instruments {
+            let instrument = self.persistence.symbol_registry.instrument(&self.venue, symbol).await?;
+            instruments.push((symbol, instrument));
+        }
+        for (channel, symbols) in &self.channel_instruments {
+            for symbol in symbols.iter().filter(|s| !self.instruments.contains(s)) {
+                warn!(
+                    "Instrument {} of channel {} is not replayed by the sim ingestor",
+                    symbol, channel
+                );
+            }
+        }
+        if !self.sessions.is_empty() {
+            let sessions = self.sessions.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            info!("Replaying the sessions {} of every day", sessions.join(", "));
+        }
+
+        // Every channel and the operator actions are a stream of the merge, it publishes them in order of time so the
+        // actions land between the same events in every run
+        let mut merger = ReplayMerger::new(self.pubsub.clone(), self.debugger.clone())
+            .with_pacing(self.pacing)
+            .with_barrier(self.barrier.clone())
+            .with_progress(ProgressTracker::new(self.start, self.end), self.progress_interval);
+        let tracker = TaskTracker::new();
+        // A gap aborting the replay stops the channels still loading
+        let replay = shutdown.child_token();
+        // An adaptive window follows the replay and counts the orders of the simulation until the replay ended
+        let recording = shutdown.child_token();
+        if self.window.is_adaptive() {
+            merger = merger.with_window(self.window.clone());
+            let mut orders = self.pubsub.subscribe::<VenueOrder>();
+            let window = self.window.clone();
+            let recording = recording.clone();
+            tracker.spawn(async move {
+                loop {
+                    tokio::select! {
+                        Ok(order) = orders.recv() => {
+                            if order.status == VenueOrderStatus::New {
+                                window.record_order(order.created_at);
+                            }
+                        }
+                        _ = recording.cancelled() => break,
+                    }
+                }
+            });
+        }
+        for channel in &self.channels {
+            let instruments = instruments
+                .iter()
+                .filter(|(symbol, _)| self.channel_instruments.get(channel).is_none_or(|s| s.contains(*symbol)))
+                .map(|(_, instrument)| instrument.clone())
+                .collect::<Vec<_>>();
+            if instruments.is_empty() {
+                warn!("No instruments to replay on channel {}", channel);
+                continue;
+            }
+            let task = ReplayTask::builder()
+                .persistence(self.persistence.clone())
+                .sender(merger.stream())
+                .channel(*channel)
+                .instruments(instruments)
+                .start(self.start)
+                .end(self.end)
+                .window(self.window.clone())
+                .sessions(self.sessions.clone())
+                .gaps(self.gaps.clone())
+                .build();
+            let shutdown = replay.clone();
+            tracker.spawn(async move {
+                if let Err(e) = task.run(shutdown).await {
+                    error!("Replay task {} failed: {}", task.channel, e);
+                }
+            });
+        }
+        if !self.actions.is_empty() {
+            let actions = self.actions.iter().cloned().map(SimEvent::Action).collect::<Vec<_>>();
+            let sender = merger.stream();
+            tracker.spawn(async move {
+                let _ = sender.send(actions).await;
+            });
+        }
+        let merged = merger.run(shutdown.clone());
+        let aborted = tracker.spawn(async move {
+            let aborted = merged.await;
+            recording.cancel();
+            if aborted.is_some() {
+                replay.cancel();
+            }
+            aborted
+        });
+        tracker.close();
+        tracker.wait().await;
+
+        if let Ok(Some(gap)) = aborted.await {
+            // The simulation ends at the gap, failing the ingestor halts trading
+            error!(
+                "Sim ingestor aborted at the gap in {} from {} to {}",
+                gap.channel, gap.start, gap.end
+            );
+            let finished = SimulationFinished::builder().event_time(gap.start).build();
+            self.pubsub.publish::<SimulationFinished>(finished.into());
+            return Err(IngestorError::DataGap(gap));
+        }
+        if !shutdown.is_cancelled() {
+            let finished = SimulationFinished::builder().event_time(self.end).build();
+            self.pubsub.publish::<SimulationFinished>(finished.into());
+        }
+        info!("Sim ingestor finished");
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Error};
+use time::{macros::format_description, Date, OffsetDateTime, Time};
+
+/// Hours of the day a replay covers, in UTC. A session ending at or before its start runs over midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaySession {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl ReplaySession {
+    /// Range of the session starting on the given day.
+    pub fn on(&self, date: Date) -> (OffsetDateTime, OffsetDateTime) {
+        let start = date.with_time(self.start).assume_utc();
+        let end = if self.end > self.start {
+            date.with_time(self.end).assume_utc()
+        } else {
+            date.next_day().expect("Date out of range").with_time(self.end).assume_utc()
+        };
+        (start, end)
+    }
+}
+
+/// Parses `HH:MM-HH:MM`, like `08:00-16:00`.
+impl FromStr for ReplaySession {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = format_description!("[hour]:[minute]");
+        let Some((start, end)) = s.split_once('-') else {
+            bail!("invalid replay session '{}', use HH:MM-HH:MM", s);
+        };
+        Ok(Self {
+            start: Time::parse(start.trim(), &format)?,
+            end: Time::parse(end.trim(), &format)?,
+        })
+    }
+}
+
+impl fmt::Display for ReplaySession {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start.hour(),
+            self.start.minute(),
+            self.end.hour(),
+            self.end.minute()
+        )
+    }
+}
+
+/// Range of the sessions containing the given time, starting at it, or else the next session after it. None without
+/// sessions.
+pub fn next_session(sessions: &[ReplaySession], at: OffsetDateTime) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    let date = at.date();
+    // A session running over midnight may have started the day before
+    let days = [date.previous_day(), Some(date), date.next_day()];
+    days.into_iter()
+        .flatten()
+        .flat_map(|day| sessions.iter().map(move |s| s.on(day)))
+        .filter(|(_, end)| *end > at)
+        .map(|(start, end)| (start.max(at), end))
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_next_session() {
+        let day = "08:00-16:00".parse::<ReplaySession>().unwrap();
+        let night = "22:00-02:00".parse::<ReplaySession>().unwrap();
+        assert_eq!(night.to_string(), "22:00-02:00");
+        assert!("08:00".parse::<ReplaySession>().is_err());
+        assert!("8-16".parse::<ReplaySession>().is_err());
+
+        assert_eq!(next_session(&[], datetime!(2025-01-01 12:00 UTC)), None);
+        let sessions = [day, night];
+        // Within a session the replay goes on from where it is
+        assert_eq!(
+            next_session(&sessions, datetime!(2025-01-01 12:00 UTC)),
+            Some((datetime!(2025-01-01 12:00 UTC), datetime!(2025-01-01 16:00 UTC)))
+        );
+        // Between sessions it skips to the next one
+        assert_eq!(
+            next_session(&sessions, datetime!(2025-01-01 16:00 UTC)),
+            Some((datetime!(2025-01-01 22:00 UTC), datetime!(2025-01-02 02:00 UTC)))
+        );
+        // The night session started the day before
+        assert_eq!(
+            next_session(&sessions, datetime!(2025-01-02 01:00 UTC)),
+            Some((datetime!(2025-01-02 01:00 UTC), datetime!(2025-01-02 02:00 UTC)))
+        );
+        assert_eq!(
+            next_session(&sessions, datetime!(2025-01-02 02:00 UTC)),
+            Some((datetime!(2025-01-02 08:00 UTC), datetime!(2025-01-02 16:00 UTC)))
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::config::AdaptiveWindowConfig;
+
+#[derive(Debug)]
+struct WindowState {
+    size: Duration,
+    /// Time of the last event the replay published
+    now: OffsetDateTime,
+    resized_at: OffsetDateTime,
+    /// Times of the orders placed within the last window
+    orders: Vec<OffsetDateTime>,
+}
+
+/// Size of the windows the replay tasks load from persistence. The window is fixed unless it adapts to the orders of
+/// the simulation, then it is halved while the order rate over the last window of the replay is busy and doubled
+/// while it is quiet, within its bounds. The merge of the replay streams keeps the order of the events the same for
+/// every window size.
+#[derive(Debug)]
+pub struct ReplayWindow {
+    adaptive: Option<AdaptiveWindowConfig>,
+    state: Mutex<WindowState>,
+}
+
+impl ReplayWindow {
+    pub fn new(size: Duration, adaptive: Option<AdaptiveWindowConfig>) -> Self {
+        let size = match &adaptive {
+            Some(config) => size.clamp(Duration::from_secs(config.min_secs), Duration::from_secs(config.max_secs)),
+            None => size,
+        };
+        Self {
+            adaptive,
+            state: Mutex::new(WindowState {
+                size,
+                now: OffsetDateTime::UNIX_EPOCH,
+                resized_at: OffsetDateTime::UNIX_EPOCH,
+                orders: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive.is_some()
+    }
+
+    /// Move the replay forward to the time of a published event.
+    pub fn advance(&self, event_time: OffsetDateTime) {
+        let mut state = self.state.lock();
+        state.now = state.now.max(event_time);
+    }
+
+    /// Count an order placed in the simulation.
+    pub fn record_order(&self, created_at: OffsetDateTime) {
+        if self.is_adaptive() {
+            self.state.lock().orders.push(created_at);
+        }
+    }
+
+    /// Size of the next window to load, an adaptive window is resized at most once per window of the replay.
+    pub fn next(&self) -> Duration {
+        let mut state = self.state.lock();
+        let Some(config) = &self.adaptive else {
+            return state.size;
+        };
+        if state.now < state.resized_at + state.size {
+            return state.size;
+        }
+
+        let since = state.now - state.size;
+        state.orders.retain(|t| *t >= since);
+        let rate = state.orders.len() as f64 * 60. / state.size.as_secs_f64();
+        let size = if rate >= config.busy_orders_per_min {
+            (state.size / 2).max(Duration::from_secs(config.min_secs))
+        } else if rate <= config.quiet_orders_per_min {
+            (state.size * 2).min(Duration::from_secs(config.max_secs))
+        } else {
+            state.size
+        };
+        if size != state.size {
+            info!(
+                "Replay window resized from {:?} to {:?} at {} with {:.1} orders per minute",
+                state.size, size, state.now, rate
+            );
+        }
+        state.size = size;
+        state.resized_at = state.now;
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_adaptive_window() {
+        let config = AdaptiveWindowConfig {
+            min_secs: 60,
+            max_secs: 3600,
+            busy_orders_per_min: 10.,
+            quiet_orders_per_min: 1.,
+        };
+        let fixed = ReplayWindow::new(Duration::from_secs(600), None);
+        fixed.advance(datetime!(2025-01-01 12:00 UTC));
+        assert_eq!(fixed.next(), Duration::from_secs(600));
+
+        // The configured window is kept within the bounds
+        let window = ReplayWindow::new(Duration::from_secs(7200), Some(config.clone()));
+        assert_eq!(window.next(), Duration::from_secs(3600));
+        let window = ReplayWindow::new(Duration::from_secs(600), Some(config));
+        let start = datetime!(2025-01-01 12:00 UTC);
+
+        // Without orders the window widens, but only once per window
+        window.advance(start);
+        assert_eq!(window.next(), Duration::from_secs(1200));
+        window.advance(start + time::Duration::minutes(10));
+        assert_eq!(window.next(), Duration::from_secs(1200));
+
+        // 20 orders per minute over the last window halve it each window
+        let now = start + time::Duration::minutes(20);
+        for i in 0..400 {
+            window.record_order(now - time::Duration::seconds(i * 3));
+        }
+        window.advance(now);
+        assert_eq!(window.next(), Duration::from_secs(600));
+        let now = now + time::Duration::minutes(10);
+        for i in 0..200 {
+            window.record_order(now - time::Duration::seconds(i * 3));
+        }
+        window.advance(now);
+        assert_eq!(window.next(), Duration::from_secs(300));
+
+        // 5 orders per minute neither widen nor shrink it
+        let now = now + time::Duration::minutes(5);
+        for i in 0..25 {
+            window.record_order(now - time::Duration::seconds(i * 12));
+        }
+        window.advance(now);
+        assert_eq!(window.next(), Duration::from_secs(300));
+    }
+}
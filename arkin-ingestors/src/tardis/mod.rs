@@ -1,5 +1,5 @@
-mod binance_swap;
+pub(crate) mod binance_swap;
 mod http;
-mod service;
+pub(crate) mod service;
 
 pub use service::TardisIngestor;
@@ -4,6 +4,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use anyhow::{bail, Error, Result};
+use arkin_binance::BinanceAdapter;
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 use async_trait::async_trait;
@@ -327,7 +328,7 @@ impl TardisIngestor {
     }
 }
 
-fn parse_line(line: &str) -> Result<(OffsetDateTime, String)> {
+pub(crate) fn parse_line(line: &str) -> Result<(OffsetDateTime, String)> {
     let mut parts = line.splitn(2, ' ');
 
     // Timestamp part
@@ -393,9 +394,13 @@ impl Ingestor for TardisIngestor {
                     };
 
                     let instrument = persistence_service
-                        .instrument_store
-                        .read_by_venue_symbol(&event.venue_symbol())
-                        .await?;
+                        .symbol_registry
+                        .instrument(BinanceAdapter::VENUE, &event.venue_symbol())
+                        .await
+                        .map_err(IngestorError::from);
+                    let Some(instrument) = skip_failed_event("tardis ingestor", instrument)? else {
+                        continue;
+                    };
 
                     match event {
                         BinanceSwapsEvent::AggTradeStream(stream) => {
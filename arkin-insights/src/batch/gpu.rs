@@ -0,0 +1,298 @@
+use bytemuck::{Pod, Zeroable};
+use tracing::{debug, warn};
+use wgpu::util::DeviceExt;
+
+use crate::InsightsError;
+
+use super::{BatchKernels, CpuKernels};
+
+const WORKGROUP_SIZE: u32 = 64;
+const MAX_WORKGROUPS: u32 = 65_535;
+
+/// Rank of every value in its window by counting, so every window is independent and gets its own invocation.
+const ROLLING_QUANTILE_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    window: u32,
+    stride: u32,
+    q: f32,
+}
+
+@group(0) @binding(0) var<storage, read> data: array<f32>;
+@group(0) @binding(1) var<storage, read_write> result: array<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let start = id.y * params.stride + id.x;
+    if (start + params.window > params.len) {
+        return;
+    }
+    let rank = params.q * f32(params.window - 1u);
+    let lower = u32(floor(rank));
+    let upper = min(lower + 1u, params.window - 1u);
+    var lower_value = 0.0;
+    var upper_value = 0.0;
+    for (var i = 0u; i < params.window; i++) {
+        let value = data[start + i];
+        var below = 0u;
+        var equal = 0u;
+        for (var j = 0u; j < params.window; j++) {
+            let other = data[start + j];
+            if (other < value) {
+                below++;
+            } else if (other == value) {
+                equal++;
+            }
+        }
+        // The value takes the sorted positions [below, below + equal)
+        if (lower >= below && lower < below + equal) {
+            lower_value = value;
+        }
+        if (upper >= below && upper < below + equal) {
+            upper_value = value;
+        }
+    }
+    result[start] = lower_value + (upper_value - lower_value) * (rank - f32(lower));
+}
+"#;
+
+/// One invocation per pair of series, the series are laid out one after the other.
+const CORRELATION_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    count: u32,
+}
+
+@group(0) @binding(0) var<storage, read> data: array<f32>;
+@group(0) @binding(1) var<storage, read_write> result: array<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = id.x;
+    let y = id.y;
+    if (x >= params.count || y >= params.count) {
+        return;
+    }
+    let n = f32(params.len);
+    var sum_x = 0.0;
+    var sum_y = 0.0;
+    for (var k = 0u; k < params.len; k++) {
+        sum_x += data[x * params.len + k];
+        sum_y += data[y * params.len + k];
+    }
+    let mean_x = sum_x / n;
+    let mean_y = sum_y / n;
+    var cov = 0.0;
+    var var_x = 0.0;
+    var var_y = 0.0;
+    for (var k = 0u; k < params.len; k++) {
+        let dx = data[x * params.len + k] - mean_x;
+        let dy = data[y * params.len + k] - mean_y;
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+    result[x * params.count + y] = cov / sqrt(var_x * var_y);
+}
+"#;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct QuantileParams {
+    len: u32,
+    window: u32,
+    stride: u32,
+    q: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct CorrelationParams {
+    len: u32,
+    count: u32,
+    // Uniform buffers are at least 16 bytes
+    _padding: [u32; 2],
+}
+
+/// Batch kernels as wgpu compute shaders. The GPU computes in `f32`, inputs too large for a storage buffer of the
+/// adapter are computed on the CPU instead.
+#[derive(Debug)]
+pub struct GpuKernels {
+    adapter: String,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+impl GpuKernels {
+    pub fn new() -> Result<Self, InsightsError> {
+        pollster::block_on(async {
+            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    ..Default::default()
+                })
+                .await
+                .ok_or_else(|| InsightsError::BackendUnavailable("no GPU adapter found".into()))?;
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor::default(), None)
+                .await
+                .map_err(|e| InsightsError::BackendUnavailable(e.to_string()))?;
+            Ok(Self {
+                adapter: adapter.get_info().name,
+                device,
+                queue,
+            })
+        })
+    }
+
+    pub fn adapter(&self) -> &str {
+        &self.adapter
+    }
+
+    fn fits(&self, values: usize) -> bool {
+        let bytes = (values * std::mem::size_of::<f32>()) as u64;
+        bytes <= self.device.limits().max_storage_buffer_binding_size as u64
+    }
+
+    /// Run a shader with the input, output and uniform bindings of the kernels above and read back the output.
+    fn run(&self, shader: &str, input: &[f32], params: &[u8], output_len: usize, workgroups: (u32, u32)) -> Vec<f32> {
+        let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        });
+        let pipeline = self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let input = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("input"),
+            contents: bytemuck::cast_slice(input),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let size = (output_len * std::mem::size_of::<f32>()) as u64;
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("output"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: input.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: output.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(workgroups.0, workgroups.1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |res| {
+            if let Err(e) = res {
+                warn!("Failed to map GPU result: {}", e);
+            }
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        let result = bytemuck::cast_slice::<u8, f32>(&slice.get_mapped_range()).to_vec();
+        staging.unmap();
+        result
+    }
+}
+
+impl BatchKernels for GpuKernels {
+    fn name(&self) -> &'static str {
+        "gpu"
+    }
+
+    fn rolling_quantile(&self, data: &[f64], window: usize, q: f64) -> Vec<f64> {
+        if window == 0 || data.len() < window {
+            return Vec::new();
+        }
+        if !self.fits(data.len()) {
+            debug!("Series of {} values too large for the GPU, computing on the CPU", data.len());
+            return CpuKernels.rolling_quantile(data, window, q);
+        }
+        let outputs = (data.len() - window + 1) as u32;
+        let groups = outputs.div_ceil(WORKGROUP_SIZE);
+        let groups_x = groups.min(MAX_WORKGROUPS);
+        let params = QuantileParams {
+            len: data.len() as u32,
+            window: window as u32,
+            stride: groups_x * WORKGROUP_SIZE,
+            q: q.clamp(0., 1.) as f32,
+        };
+        let input = data.iter().map(|v| *v as f32).collect::<Vec<_>>();
+        let result = self.run(
+            ROLLING_QUANTILE_SHADER,
+            &input,
+            bytemuck::bytes_of(&params),
+            data.len(),
+            (groups_x, groups.div_ceil(groups_x)),
+        );
+        result.into_iter().take(outputs as usize).map(f64::from).collect()
+    }
+
+    fn correlation_matrix(&self, series: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let count = series.len();
+        let len = series.first().map(|s| s.len()).unwrap_or_default();
+        if count == 0 || len == 0 || series.iter().any(|s| s.len() != len) || !self.fits(count * len.max(count)) {
+            return CpuKernels.correlation_matrix(series);
+        }
+        let params = CorrelationParams {
+            len: len as u32,
+            count: count as u32,
+            _padding: [0; 2],
+        };
+        let input = series.iter().flatten().map(|v| *v as f32).collect::<Vec<_>>();
+        let groups = (count as u32).div_ceil(8);
+        let result = self.run(
+            CORRELATION_SHADER,
+            &input,
+            bytemuck::bytes_of(&params),
+            count * count,
+            (groups, groups),
+        );
+        result
+            .chunks(count)
+            .map(|row| row.iter().map(|v| f64::from(*v)).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fmt::Debug, sync::Arc};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::math;
+
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuKernels;
+
+/// Kernels for the heaviest transforms of a backfill, computed over whole series at once.
+pub trait BatchKernels: Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Quantile of every full window of the series, see [`math::rolling_quantile`].
+    fn rolling_quantile(&self, data: &[f64], window: usize, q: f64) -> Vec<f64>;
+
+    /// Pearson correlation between every pair of series of equal length, row major.
+    fn correlation_matrix(&self, series: &[Vec<f64>]) -> Vec<Vec<f64>>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchBackend {
+    #[default]
+    Cpu,
+    /// Requires the `gpu` feature, falls back to the CPU when no adapter is available
+    Gpu,
+}
+
+/// Kernels of the requested backend, the CPU kernels if the GPU is not compiled in or not available.
+pub fn batch_kernels(backend: BatchBackend) -> Arc<dyn BatchKernels> {
+    match backend {
+        BatchBackend::Cpu => Arc::new(CpuKernels),
+        #[cfg(feature = "gpu")]
+        BatchBackend::Gpu => match GpuKernels::new() {
+            Ok(kernels) => {
+                info!("Computing batch kernels on {}", kernels.adapter());
+                Arc::new(kernels)
+            }
+            Err(e) => {
+                warn!("{}, falling back to the CPU", e);
+                Arc::new(CpuKernels)
+            }
+        },
+        #[cfg(not(feature = "gpu"))]
+        BatchBackend::Gpu => {
+            warn!("Built without the gpu feature, falling back to the CPU");
+            Arc::new(CpuKernels)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuKernels;
+
+impl BatchKernels for CpuKernels {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn rolling_quantile(&self, data: &[f64], window: usize, q: f64) -> Vec<f64> {
+        math::rolling_quantile(data, window, q)
+    }
+
+    fn correlation_matrix(&self, series: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let centered = series
+            .par_iter()
+            .map(|s| {
+                let mean = math::mean(s).unwrap_or_default();
+                let centered = s.iter().map(|v| v - mean).collect::<Vec<_>>();
+                let norm = math::sum(&centered.iter().map(|v| v * v).collect::<Vec<_>>()).sqrt();
+                (centered, norm)
+            })
+            .collect::<Vec<_>>();
+        centered
+            .par_iter()
+            .map(|(x, x_norm)| {
+                centered
+                    .iter()
+                    .map(|(y, y_norm)| {
+                        let products = x.iter().zip(y).map(|(a, b)| a * b).collect::<Vec<_>>();
+                        math::sum(&products) / (x_norm * y_norm)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_correlation_matrix() {
+        let x = vec![1., 2., 3., 4., 5.];
+        let y = vec![2., 4., 6., 8., 10.];
+        let z = vec![5., 4., 3., 2., 1.];
+        let matrix = CpuKernels.correlation_matrix(&[x, y, z]);
+        let expected = [[1., 1., -1.], [1., 1., -1.], [-1., -1., 1.]];
+        for (row, expected) in matrix.iter().zip(expected) {
+            for (value, expected) in row.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-12, "{} != {}", value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback() {
+        let kernels = batch_kernels(BatchBackend::Gpu);
+        let quantiles = kernels.rolling_quantile(&[1., 2., 3., 4.], 2, 0.5);
+        assert_eq!(quantiles.len(), 3);
+        assert!((quantiles[0] - 1.5).abs() < 1e-6);
+    }
+}
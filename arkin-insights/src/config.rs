@@ -1,3 +1,5 @@
+use std::fmt;
+
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -14,12 +16,141 @@ pub struct InsightsServiceConfig {
     pub state_lookback: u64,
     pub frequency_secs: u64,
     pub scale_periods: usize,
+    /// Initialize the state from the persisted insights on startup instead of replaying the trades
+    #[serde(default)]
+    pub warm_start: Option<WarmStartConfig>,
+    /// Record the compute time of every feature and report the slowest after a run
+    #[serde(default)]
+    pub profiling: Option<ProfilingConfig>,
+    /// Supervised targets computed alongside the features in backfills
+    #[serde(default)]
+    pub labels: Option<LabelerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LabelerConfig {
+    /// Price the labels are computed from, such as the trade price or the close of a bar
+    pub input: FeatureId,
+    #[serde(default)]
+    pub forward_returns: Vec<ForwardReturnConfig>,
+    #[serde(default)]
+    pub triple_barriers: Vec<TripleBarrierConfig>,
+    #[serde(default)]
+    pub meta_labels: Vec<MetaLabelConfig>,
+    /// Outcome labels of fills for execution agents
+    #[serde(default)]
+    pub execution: Option<ExecutionLabelerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ForwardReturnConfig {
+    pub output: FeatureId,
+    pub horizon_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TripleBarrierConfig {
+    pub output: FeatureId,
+    /// Vertical barrier, the label is 0 when neither price barrier is touched within it
+    pub horizon_secs: u64,
+    /// Upper barrier as a return from the price at the tick
+    pub take_profit: f64,
+    /// Lower barrier as a (positive) return from the price at the tick
+    pub stop_loss: f64,
+    /// Scale both barriers with this feature, such as a rolling volatility
+    #[serde(default)]
+    pub volatility: Option<FeatureId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionLabelerConfig {
+    /// Horizons after the fill the markouts are measured at
+    pub markout_horizons_secs: Vec<u64>,
+    #[serde(default)]
+    pub reward: RewardWeights,
+}
+
+/// Weights of the execution labels in the reward, latency is penalized per second.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RewardWeights {
+    pub price_improvement: f64,
+    pub latency: f64,
+    pub adverse_selection: f64,
+}
+
+impl Default for RewardWeights {
+    fn default() -> Self {
+        Self {
+            price_improvement: 1.,
+            latency: 0.,
+            adverse_selection: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetaLabelConfig {
+    pub output: FeatureId,
+    /// Side of the primary model, only its sign is used
+    pub input_signal: FeatureId,
+    /// Output of a triple barrier label
+    pub input_barrier: FeatureId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProfilingConfig {
+    /// Number of features in the report
+    pub top_n: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarmStartConfig {
+    /// Number of periods of persisted insights loaded into the state
+    pub periods: u32,
+    /// Largest difference between a recomputed and a persisted insight that is accepted
+    pub tolerance: Decimal,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PipelineConfig {
     pub name: String,
     pub features: Vec<FeatureConfig>,
+    /// When the features are computed, on the interval ticks or on the close of bars built from the trades
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub enum SamplingConfig {
+    #[default]
+    #[serde(rename = "time")]
+    Time,
+    #[serde(rename = "volume")]
+    Volume(VolumeBarConfig),
+    #[serde(rename = "dollar")]
+    Dollar(DollarBarConfig),
+    #[serde(rename = "imbalance")]
+    Imbalance(ImbalanceBarConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VolumeBarConfig {
+    /// Quantity traded per bar
+    pub threshold: Quantity,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DollarBarConfig {
+    /// Notional traded per bar
+    pub threshold: Notional,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImbalanceBarConfig {
+    /// Expected number of trades of the first bar, until the bar lengths are estimated from the closed bars
+    pub expected_trades: f64,
+    /// Weight of the last bar in the moving averages of the bar length and the trade imbalance
+    pub alpha: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +159,8 @@ pub enum FeatureConfig {
     OHLCV(OHLCVConfig),
     #[serde(rename = "time")]
     Time(TimeConfig),
+    #[serde(rename = "anchored_vwap")]
+    AnchoredVWAP(AnchoredVWAPConfig),
 
     // Mathematical
     #[serde(rename = "log_return")]
@@ -58,6 +191,169 @@ pub enum FeatureConfig {
     // Portfolio Optimization
     #[serde(rename = "mean_variance")]
     MeanVariance(MeanVarianceConfig),
+
+    // Options
+    #[serde(rename = "option_greeks")]
+    OptionGreeks(OptionGreeksConfig),
+}
+
+impl FeatureConfig {
+    /// Config names of every feature the factory can build.
+    pub const KINDS: [&'static str; 15] = [
+        "ohlcv",
+        "time",
+        "anchored_vwap",
+        "log_return",
+        "std_dev",
+        "sum",
+        "signal",
+        "ma",
+        "rsi",
+        "adx",
+        "cmf",
+        "co",
+        "catboost",
+        "mean_variance",
+        "option_greeks",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FeatureConfig::OHLCV(_) => "ohlcv",
+            FeatureConfig::Time(_) => "time",
+            FeatureConfig::AnchoredVWAP(_) => "anchored_vwap",
+            FeatureConfig::LogReturn(_) => "log_return",
+            FeatureConfig::StdDev(_) => "std_dev",
+            FeatureConfig::Sum(_) => "sum",
+            FeatureConfig::SignalStrength(_) => "signal",
+            FeatureConfig::MA(_) => "ma",
+            FeatureConfig::RSI(_) => "rsi",
+            FeatureConfig::ADX(_) => "adx",
+            FeatureConfig::CMF(_) => "cmf",
+            FeatureConfig::CO(_) => "co",
+            FeatureConfig::CatBoost(_) => "catboost",
+            FeatureConfig::MeanVariance(_) => "mean_variance",
+            FeatureConfig::OptionGreeks(_) => "option_greeks",
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<FeatureId> {
+        match self {
+            FeatureConfig::OHLCV(c) => vec![c.input_price.clone(), c.input_quantity.clone()],
+            FeatureConfig::Time(c) => vec![c.input.clone()],
+            FeatureConfig::AnchoredVWAP(c) => {
+                let mut inputs = vec![c.input_price.clone(), c.input_quantity.clone()];
+                if let AnchorConfig::Feature(anchor) = &c.anchor {
+                    inputs.push(anchor.input.clone());
+                }
+                inputs
+            }
+            FeatureConfig::LogReturn(c) => vec![c.input.clone()],
+            FeatureConfig::StdDev(c) => vec![c.input.clone()],
+            FeatureConfig::Sum(c) => vec![c.input.clone()],
+            FeatureConfig::SignalStrength(c) => vec![c.input_first.clone(), c.input_second.clone()],
+            FeatureConfig::MA(c) => vec![c.input.clone()],
+            FeatureConfig::RSI(c) => vec![c.input.clone()],
+            FeatureConfig::ADX(c) => vec![c.input.clone()],
+            FeatureConfig::CMF(c) => vec![c.input.clone()],
+            FeatureConfig::CO(c) => vec![c.input.clone()],
+            FeatureConfig::CatBoost(c) => c.input_numerical.iter().chain(&c.input_categorical).cloned().collect(),
+            FeatureConfig::MeanVariance(c) => vec![c.input_expected_returns.clone(), c.input_returns.clone()],
+            FeatureConfig::OptionGreeks(c) => vec![c.input_option_price.clone(), c.input_underlying_price.clone()],
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<FeatureId> {
+        match self {
+            FeatureConfig::OHLCV(c) => vec![
+                c.output_open.clone(),
+                c.output_high.clone(),
+                c.output_low.clone(),
+                c.output_close.clone(),
+                c.output_typical_price.clone(),
+                c.output_vwap.clone(),
+                c.output_volume.clone(),
+                c.output_buy_volume.clone(),
+                c.output_sell_volume.clone(),
+                c.output_notional_volume.clone(),
+                c.output_buy_notional_volume.clone(),
+                c.output_sell_notional_volume.clone(),
+                c.output_trade_count.clone(),
+                c.output_buy_trade_count.clone(),
+                c.output_sell_trade_count.clone(),
+            ],
+            FeatureConfig::Time(c) => vec![
+                c.output_day_of_week.clone(),
+                c.output_hour_of_day.clone(),
+                c.output_minute_of_day.clone(),
+                c.output_minute_of_hour.clone(),
+            ],
+            FeatureConfig::AnchoredVWAP(c) => vec![c.output_vwap.clone(), c.output_twap.clone()],
+            FeatureConfig::LogReturn(c) => vec![c.output.clone()],
+            FeatureConfig::StdDev(c) => vec![c.output.clone()],
+            FeatureConfig::Sum(c) => vec![c.output.clone()],
+            FeatureConfig::SignalStrength(c) => vec![c.output.clone()],
+            FeatureConfig::MA(c) => vec![c.output.clone()],
+            FeatureConfig::RSI(c) => vec![c.output.clone()],
+            FeatureConfig::ADX(c) => vec![c.output.clone()],
+            FeatureConfig::CMF(c) => vec![c.output.clone()],
+            FeatureConfig::CO(c) => vec![c.output.clone()],
+            FeatureConfig::CatBoost(c) => vec![c.output.clone()],
+            FeatureConfig::MeanVariance(c) => vec![c.output.clone()],
+            FeatureConfig::OptionGreeks(c) => vec![
+                c.output_iv.clone(),
+                c.output_delta.clone(),
+                c.output_gamma.clone(),
+                c.output_vega.clone(),
+                c.output_theta.clone(),
+            ],
+        }
+    }
+
+    pub fn persist(&self) -> bool {
+        match self {
+            FeatureConfig::OHLCV(c) => c.persist,
+            FeatureConfig::Time(c) => c.persist,
+            FeatureConfig::AnchoredVWAP(c) => c.persist,
+            FeatureConfig::LogReturn(c) => c.persist,
+            FeatureConfig::StdDev(c) => c.persist,
+            FeatureConfig::Sum(c) => c.persist,
+            FeatureConfig::SignalStrength(c) => c.persist,
+            FeatureConfig::MA(c) => c.persist,
+            FeatureConfig::RSI(c) => c.persist,
+            FeatureConfig::ADX(c) => c.persist,
+            FeatureConfig::CMF(c) => c.persist,
+            FeatureConfig::CO(c) => c.persist,
+            FeatureConfig::CatBoost(c) => c.persist,
+            FeatureConfig::MeanVariance(c) => c.persist,
+            FeatureConfig::OptionGreeks(c) => c.persist,
+        }
+    }
+
+    /// Lookback of the feature as the factory builds it, periods are scaled by `scale_periods`.
+    pub fn window(&self, scale_periods: usize) -> Option<String> {
+        match self {
+            FeatureConfig::OHLCV(c) => Some(format!("{}s", c.window)),
+            FeatureConfig::LogReturn(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::StdDev(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::Sum(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::MA(c) => Some(format!("{} {} periods", c.periods * scale_periods, c.ma_type)),
+            FeatureConfig::RSI(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::ADX(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::CMF(c) => Some(format!("{} periods", c.periods * scale_periods)),
+            FeatureConfig::CO(c) => Some(format!(
+                "{}/{} periods",
+                c.periods_fast * scale_periods,
+                c.periods_slow * scale_periods
+            )),
+            FeatureConfig::MeanVariance(c) => Some(format!("{} periods", c.periods_returns * scale_periods)),
+            FeatureConfig::AnchoredVWAP(c) => Some(format!("since {}", c.anchor)),
+            FeatureConfig::Time(_)
+            | FeatureConfig::SignalStrength(_)
+            | FeatureConfig::CatBoost(_)
+            | FeatureConfig::OptionGreeks(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -95,6 +391,64 @@ pub struct TimeConfig {
     pub persist: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnchoredVWAPConfig {
+    pub input_price: FeatureId,
+    pub input_quantity: FeatureId,
+    pub anchor: AnchorConfig,
+    pub output_vwap: FeatureId,
+    pub output_twap: FeatureId,
+    #[serde(default)]
+    pub persist: bool,
+}
+
+/// Point the anchored features accumulate from. The inputs since the anchor have to be within the state lookback.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum AnchorConfig {
+    #[serde(rename = "session")]
+    Session(SessionAnchorConfig),
+    #[serde(rename = "timestamp")]
+    Timestamp(TimestampAnchorConfig),
+    #[serde(rename = "feature")]
+    Feature(FeatureAnchorConfig),
+}
+
+impl fmt::Display for AnchorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnchorConfig::Session(c) => write!(f, "session open +{}s every {}s", c.open_secs, c.length_secs),
+            AnchorConfig::Timestamp(c) => write!(f, "{}", c.at),
+            AnchorConfig::Feature(c) => write!(f, "last {}", c.input),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionAnchorConfig {
+    /// Open of the first session of the day in seconds after midnight UTC
+    #[serde(default)]
+    pub open_secs: u64,
+    /// Length of a session, a day for daily sessions
+    #[serde(default = "default_session_length_secs")]
+    pub length_secs: u64,
+}
+
+fn default_session_length_secs() -> u64 {
+    86400
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimestampAnchorConfig {
+    /// Time of the anchor in "YYYY-MM-DD HH:MM" format, UTC
+    pub at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeatureAnchorConfig {
+    /// Marker feature, like a swing point, anchored at the last time it was not zero
+    pub input: FeatureId,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VWAPConfig {
     pub input_price: FeatureId,
@@ -254,3 +608,49 @@ pub struct MeanVarianceConfig {
     #[serde(default)]
     pub persist: bool,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptionGreeksConfig {
+    pub input_option_price: FeatureId,
+    pub input_underlying_price: FeatureId,
+    pub output_iv: FeatureId,
+    pub output_delta: FeatureId,
+    pub output_gamma: FeatureId,
+    pub output_vega: FeatureId,
+    pub output_theta: FeatureId,
+    #[serde(default)]
+    pub risk_free_rate: f64,
+    #[serde(default)]
+    pub persist: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationConfig {
+    pub feature_validation: FeatureValidationConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeatureValidationConfig {
+    /// Directory containing the reference CSV fixtures
+    pub fixtures_dir: String,
+    /// Fail the validation if a feature kind from the factory has no case
+    #[serde(default)]
+    pub require_full_coverage: bool,
+    pub cases: Vec<FeatureValidationCaseConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeatureValidationCaseConfig {
+    pub name: String,
+    /// Fixture file relative to the fixtures directory
+    pub fixture: String,
+    pub feature: FeatureConfig,
+    /// Maximum absolute difference to the reference value
+    pub tolerance: Decimal,
+    /// Maximum relative difference to the reference value, checked when the absolute tolerance is exceeded
+    #[serde(default)]
+    pub relative_tolerance: Decimal,
+    /// Number of leading rows that are fed but not compared
+    #[serde(default)]
+    pub warmup: usize,
+}
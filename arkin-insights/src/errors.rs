@@ -1,10 +1,34 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum InsightsError {
     #[error(transparent)]
     Persistence(#[from] arkin_persistence::PersistenceError),
 
+    #[error("Invalid validation fixture {0}: {1}")]
+    InvalidFixture(String, String),
+
+    #[error("Invalid pipeline {0}: {1}")]
+    InvalidPipeline(String, String),
+
+    #[error("Batch backend unavailable: {0}")]
+    BackendUnavailable(String),
+
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
+
+impl ClassifyError for InsightsError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            InsightsError::Persistence(e) => e.class(),
+            InsightsError::BackendUnavailable(_) => ErrorClass::Fatal,
+            // Feature computations fail on the data of a single interval, the next one can succeed again
+            InsightsError::InvalidFixture(_, _) | InsightsError::InvalidPipeline(_, _) | InsightsError::Anyhow(_) => {
+                ErrorClass::InvalidInput
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use serde::Serialize;
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    config::ForwardReturnConfig,
+    labeler::forward_return,
+    vectorized::{Frame, Series},
+};
+
+/// Scores persisted predictions against the realized return over the horizon after each of them, without running a
+/// simulation. A prediction above the threshold is a call for the price to go up, below for it to go down.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PredictionEvaluator {
+    /// Feature holding the predictions
+    prediction: FeatureId,
+    /// Price the outcome is measured on
+    price: FeatureId,
+    horizon_secs: u64,
+    /// 0.5 for probabilities, 0 for signed scores
+    #[builder(default = 0.)]
+    threshold: f64,
+    /// Cost of a round trip of the naive rule
+    #[builder(default = 0.)]
+    cost_bps: f64,
+    #[builder(default = 10)]
+    calibration_buckets: usize,
+}
+
+impl PredictionEvaluator {
+    /// Score the predictions of all instruments together, `end` is the last second covered by the prices.
+    pub fn evaluate(&self, frames: &HashMap<Arc<Instrument>, Frame>, end: i64) -> ModelScorecard {
+        let mut outcomes = Vec::new();
+        for frame in frames.values() {
+            let (Some(predictions), Some(prices)) = (frame.get(&self.prediction), frame.get(&self.price)) else {
+                continue;
+            };
+            outcomes.extend(self.outcomes(predictions, prices, end));
+        }
+        self.score(outcomes)
+    }
+
+    /// Pair every prediction with the log return that followed it.
+    fn outcomes(&self, predictions: &Series, prices: &Series, end: i64) -> Vec<(f64, f64)> {
+        let config = ForwardReturnConfig {
+            output: self.prediction.clone(),
+            horizon_secs: self.horizon_secs,
+        };
+        let returns = forward_return(&config, prices, &predictions.timestamps, end);
+        let returns = returns.timestamps.iter().zip(&returns.values).collect::<HashMap<_, _>>();
+        predictions
+            .timestamps
+            .iter()
+            .zip(&predictions.values)
+            .filter_map(|(ts, prediction)| returns.get(ts).map(|r| (*prediction, **r)))
+            .filter(|(prediction, r)| prediction.is_finite() && r.is_finite())
+            .collect()
+    }
+
+    fn score(&self, mut outcomes: Vec<(f64, f64)>) -> ModelScorecard {
+        let mut scorecard = ModelScorecard {
+            feature: self.prediction.to_string(),
+            horizon_secs: self.horizon_secs,
+            predictions: outcomes.len(),
+            ..Default::default()
+        };
+        if outcomes.is_empty() {
+            return scorecard;
+        }
+
+        // Predictions right at the threshold and flat outcomes have no direction
+        let directional = outcomes
+            .iter()
+            .filter(|(p, r)| *p != self.threshold && *r != 0.)
+            .collect::<Vec<_>>();
+        if !directional.is_empty() {
+            let hits = directional.iter().filter(|(p, r)| (*p > self.threshold) == (*r > 0.)).count();
+            scorecard.hit_rate = Some(hits as f64 / directional.len() as f64);
+        }
+        scorecard.auc = auc(&outcomes);
+
+        // Every prediction is an independent round trip over the horizon, they overlap when the predictions are
+        // more frequent than the horizon
+        for (prediction, r) in &outcomes {
+            let position = match prediction.partial_cmp(&self.threshold) {
+                Some(std::cmp::Ordering::Greater) => 1.,
+                Some(std::cmp::Ordering::Less) => -1.,
+                _ => continue,
+            };
+            scorecard.naive_trades += 1;
+            scorecard.naive_pnl_bps += position * r.exp_m1() * 1e4 - self.cost_bps;
+        }
+
+        outcomes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let size = outcomes.len().div_ceil(self.calibration_buckets.max(1));
+        scorecard.calibration = outcomes
+            .chunks(size)
+            .map(|bucket| {
+                let count = bucket.len() as f64;
+                CalibrationBucket {
+                    predictions: bucket.len(),
+                    mean_prediction: bucket.iter().map(|(p, _)| p).sum::<f64>() / count,
+                    up_rate: bucket.iter().filter(|(_, r)| *r > 0.).count() as f64 / count,
+                    mean_return_bps: bucket.iter().map(|(_, r)| r.exp_m1() * 1e4).sum::<f64>() / count,
+                }
+            })
+            .collect();
+        scorecard
+    }
+}
+
+/// Probability that an up move got a higher prediction than a down move, ties count half.
+fn auc(outcomes: &[(f64, f64)]) -> Option<f64> {
+    let mut ranked = outcomes.iter().filter(|(_, r)| *r != 0.).collect::<Vec<_>>();
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let positives = ranked.iter().filter(|(_, r)| *r > 0.).count();
+    let negatives = ranked.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+
+    // Mann-Whitney U with the average rank for tied predictions
+    let mut rank_sum = 0.;
+    let mut i = 0;
+    while i < ranked.len() {
+        let mut j = i;
+        while j < ranked.len() && ranked[j].0 == ranked[i].0 {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.;
+        rank_sum += rank * ranked[i..j].iter().filter(|(_, r)| *r > 0.).count() as f64;
+        i = j;
+    }
+    let u = rank_sum - (positives * (positives + 1)) as f64 / 2.;
+    Some(u / (positives * negatives) as f64)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CalibrationBucket {
+    pub predictions: usize,
+    pub mean_prediction: f64,
+    /// Share of the predictions followed by an up move
+    pub up_rate: f64,
+    pub mean_return_bps: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModelScorecard {
+    pub feature: String,
+    pub horizon_secs: u64,
+    /// Predictions with a realized outcome
+    pub predictions: usize,
+    pub hit_rate: Option<f64>,
+    pub auc: Option<f64>,
+    /// Buckets of equally many predictions, from the lowest to the highest
+    pub calibration: Vec<CalibrationBucket>,
+    pub naive_trades: usize,
+    /// Pnl of trading the side of every prediction over the horizon, after costs
+    pub naive_pnl_bps: f64,
+}
+
+impl fmt::Display for ModelScorecard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = |value: Option<f64>| value.map(|v| format!("{:.4}", v)).unwrap_or_else(|| "n/a".into());
+        let per_trade = match self.naive_trades {
+            0 => 0.,
+            n => self.naive_pnl_bps / n as f64,
+        };
+        write!(
+            f,
+            "Scorecard of {} over {}s: predictions={} hit_rate={} auc={} naive_trades={} naive_pnl_bps={:.2} per_trade_bps={:.2}",
+            self.feature,
+            self.horizon_secs,
+            self.predictions,
+            format(self.hit_rate),
+            format(self.auc),
+            self.naive_trades,
+            self.naive_pnl_bps,
+            per_trade
+        )?;
+        write!(f, "\nCalibration:")?;
+        for bucket in &self.calibration {
+            write!(
+                f,
+                "\n  predictions={} mean_prediction={:.4} up_rate={:.4} mean_return_bps={:.2}",
+                bucket.predictions, bucket.mean_prediction, bucket.up_rate, bucket.mean_return_bps
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> FeatureId {
+        FeatureId::new(name.into())
+    }
+
+    fn evaluator() -> PredictionEvaluator {
+        PredictionEvaluator::builder()
+            .prediction(id("prediction"))
+            .price(id("price"))
+            .horizon_secs(60)
+            .threshold(0.5)
+            .calibration_buckets(2)
+            .build()
+    }
+
+    #[test]
+    fn test_auc() {
+        assert_eq!(auc(&[(0.9, 1.), (0.8, 1.), (0.2, -1.), (0.1, -1.)]), Some(1.));
+        assert_eq!(auc(&[(0.1, 1.), (0.9, -1.)]), Some(0.));
+        assert_eq!(auc(&[(0.5, 1.), (0.5, -1.)]), Some(0.5));
+        assert_eq!(auc(&[(0.5, 1.)]), None);
+    }
+
+    #[test]
+    fn test_scorecard() {
+        let mut frame = Frame::new();
+        let mut prices = Series::default();
+        for (ts, price) in [(0, 100.), (60, 101.), (120, 100.), (180, 100.5), (240, 101.)] {
+            prices.push(ts, price);
+        }
+        let mut predictions = Series::default();
+        // Right on the first two calls, wrong on the third, the last one has no outcome yet
+        for (ts, prediction) in [(0, 0.7), (60, 0.2), (120, 0.3), (240, 0.9)] {
+            predictions.push(ts, prediction);
+        }
+        frame.insert(id("price"), prices);
+        frame.insert(id("prediction"), predictions);
+        let frames = HashMap::from([(test_inst_binance_btc_usdt_perp(), frame)]);
+
+        let scorecard = evaluator().evaluate(&frames, 240);
+        assert_eq!(scorecard.predictions, 3);
+        assert_eq!(scorecard.hit_rate, Some(2. / 3.));
+        assert_eq!(scorecard.auc, Some(1.));
+        assert_eq!(scorecard.naive_trades, 3);
+        let expected = 100. + (1. - 100. / 101.) * 1e4 - 50.;
+        assert!((scorecard.naive_pnl_bps - expected).abs() < 1e-6);
+        assert_eq!(scorecard.calibration.len(), 2);
+        assert_eq!(scorecard.calibration[0].predictions, 2);
+        assert_eq!(scorecard.calibration[1].up_rate, 1.);
+    }
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
+
+use crate::config::{ExecutionLabelerConfig, RewardWeights};
+
+/// Outcome of one fill. Prices are in basis points of the reference price and signed so that positive is good for
+/// the order, they can be used as reward components for execution agents as they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionLabel {
+    pub event_time: OffsetDateTime,
+    pub instrument: Arc<Instrument>,
+    pub venue_order_id: VenueOrderId,
+    pub side: MarketSide,
+    pub quantity: f64,
+    /// Fill price against the mid price when the order was created
+    pub price_improvement_bps: f64,
+    /// Time from creating the order to the fill
+    pub fill_latency: Duration,
+    /// Move of the mid price after the fill per horizon, negative when the price moved against the fill. Horizons the
+    /// tick data does not cover are missing.
+    pub markouts_bps: Vec<(Duration, f64)>,
+}
+
+impl ExecutionLabel {
+    /// Adverse selection at the longest horizon, 0 without markouts.
+    pub fn adverse_selection_bps(&self) -> f64 {
+        self.markouts_bps.last().map(|(_, m)| (-m).max(0.)).unwrap_or_default()
+    }
+
+    pub fn reward(&self, weights: &RewardWeights) -> f64 {
+        weights.price_improvement * self.price_improvement_bps
+            - weights.latency * self.fill_latency.as_secs_f64()
+            - weights.adverse_selection * self.adverse_selection_bps()
+    }
+}
+
+impl fmt::Display for ExecutionLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instrument={} side={} quantity={} price_improvement_bps={:.2} fill_latency={:?} adverse_selection_bps={:.2}",
+            self.instrument.symbol,
+            self.side,
+            self.quantity,
+            self.price_improvement_bps,
+            self.fill_latency,
+            self.adverse_selection_bps()
+        )
+    }
+}
+
+/// Quantity weighted averages of the labels, to evaluate an execution agent over a period.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionLabelSummary {
+    pub fills: usize,
+    pub quantity: f64,
+    pub price_improvement_bps: f64,
+    pub fill_latency: Duration,
+    pub adverse_selection_bps: f64,
+    pub reward: f64,
+}
+
+impl ExecutionLabelSummary {
+    pub fn from_labels(labels: &[ExecutionLabel], weights: &RewardWeights) -> Self {
+        let quantity = labels.iter().map(|l| l.quantity).sum::<f64>();
+        if labels.is_empty() || quantity <= 0. {
+            return Self::default();
+        }
+        let weighted =
+            |value: fn(&ExecutionLabel) -> f64| labels.iter().map(|l| value(l) * l.quantity).sum::<f64>() / quantity;
+        Self {
+            fills: labels.len(),
+            quantity,
+            price_improvement_bps: weighted(|l| l.price_improvement_bps),
+            fill_latency: Duration::from_secs_f64(weighted(|l| l.fill_latency.as_secs_f64())),
+            adverse_selection_bps: weighted(|l| l.adverse_selection_bps()),
+            reward: labels.iter().map(|l| l.reward(weights) * l.quantity).sum::<f64>() / quantity,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionLabelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fills={} quantity={} price_improvement_bps={:.2} fill_latency={:?} adverse_selection_bps={:.2} reward={:.4}",
+            self.fills,
+            self.quantity,
+            self.price_improvement_bps,
+            self.fill_latency,
+            self.adverse_selection_bps,
+            self.reward
+        )
+    }
+}
+
+/// Labels fills with their execution outcome from the ticks around them. The arrival price is the last mid at or
+/// before the order was created, markouts use the last mid at or before the fill plus the horizon and are only
+/// produced when there is a tick after that point.
+#[derive(Debug, Clone)]
+pub struct ExecutionLabeler {
+    markout_horizons: Vec<Duration>,
+}
+
+impl ExecutionLabeler {
+    pub fn from_config(config: &ExecutionLabelerConfig) -> Self {
+        let mut markout_horizons = config
+            .markout_horizons_secs
+            .iter()
+            .map(|s| Duration::from_secs(*s))
+            .collect::<Vec<_>>();
+        markout_horizons.sort();
+        markout_horizons.dedup();
+        Self { markout_horizons }
+    }
+
+    /// Label the fills, the ticks have to be sorted by event time. Fills without a tick before their order was
+    /// created have no arrival price and are skipped.
+    pub fn label(&self, fills: &[Arc<VenueOrderFill>], ticks: &[Arc<Tick>]) -> Vec<ExecutionLabel> {
+        let mut mids: HashMap<Arc<Instrument>, Vec<(OffsetDateTime, f64)>> = HashMap::new();
+        for tick in ticks {
+            if let Some(mid) = tick.mid_price().to_f64() {
+                mids.entry(tick.instrument.clone()).or_default().push((tick.event_time, mid));
+            }
+        }
+
+        let mut labels = Vec::with_capacity(fills.len());
+        for fill in fills {
+            let Some(mids) = mids.get(&fill.instrument) else {
+                continue;
+            };
+            let (Some(arrival), Some(price), Some(quantity)) = (
+                mid_at(mids, fill.venue_order.created_at),
+                fill.price.to_f64(),
+                fill.quantity.to_f64(),
+            ) else {
+                continue;
+            };
+            if arrival <= 0. || price <= 0. {
+                continue;
+            }
+            let sign = match fill.side {
+                MarketSide::Buy => 1.,
+                MarketSide::Sell => -1.,
+            };
+
+            let last_tick = mids.last().map(|(t, _)| *t);
+            let markouts_bps = self
+                .markout_horizons
+                .iter()
+                .filter(|h| last_tick.is_some_and(|t| t > fill.event_time + **h))
+                .filter_map(|h| mid_at(mids, fill.event_time + *h).map(|mid| (*h, sign * (mid - price) / price * 1e4)))
+                .collect();
+
+            labels.push(ExecutionLabel {
+                event_time: fill.event_time,
+                instrument: fill.instrument.clone(),
+                venue_order_id: fill.venue_order.id,
+                side: fill.side,
+                quantity,
+                price_improvement_bps: sign * (arrival - price) / arrival * 1e4,
+                fill_latency: (fill.event_time - fill.venue_order.created_at).try_into().unwrap_or_default(),
+                markouts_bps,
+            });
+        }
+        labels
+    }
+}
+
+/// Last mid price at or before the time.
+fn mid_at(mids: &[(OffsetDateTime, f64)], time: OffsetDateTime) -> Option<f64> {
+    match mids.partition_point(|(t, _)| *t <= time) {
+        0 => None,
+        i => Some(mids[i - 1].1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn tick(time: OffsetDateTime, bid: Decimal, ask: Decimal) -> Arc<Tick> {
+        let tick = Tick::builder()
+            .event_time(time)
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .tick_id(0)
+            .bid_price(bid)
+            .bid_quantity(dec!(1))
+            .ask_price(ask)
+            .ask_quantity(dec!(1))
+            .build();
+        Arc::new(tick)
+    }
+
+    fn fill(created_at: OffsetDateTime, time: OffsetDateTime, side: MarketSide, price: Decimal) -> Arc<VenueOrderFill> {
+        let order = VenueOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_type(VenueOrderType::Limit)
+            .side(side)
+            .price(price)
+            .quantity(dec!(2))
+            .created_at(created_at)
+            .build();
+        let fill = VenueOrderFill::builder()
+            .event_time(time)
+            .venue_order(Arc::new(order))
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .side(side)
+            .price(price)
+            .quantity(dec!(2))
+            .commission(dec!(0))
+            .build();
+        Arc::new(fill)
+    }
+
+    #[test]
+    fn test_execution_labels() {
+        let t0 = datetime!(2025-01-01 00:00 UTC);
+        let ticks = vec![
+            tick(t0, dec!(99), dec!(101)),
+            tick(t0 + Duration::from_secs(5), dec!(98), dec!(100)),
+            tick(t0 + Duration::from_secs(20), dec!(97), dec!(99)),
+            tick(t0 + Duration::from_secs(70), dec!(96), dec!(98)),
+        ];
+        let labeler = ExecutionLabeler::from_config(&ExecutionLabelerConfig {
+            markout_horizons_secs: vec![60, 10],
+            reward: RewardWeights::default(),
+        });
+
+        // Bought below the arrival mid of 100, then the price kept falling
+        let fills = vec![fill(
+            t0 + Duration::from_secs(1),
+            t0 + Duration::from_secs(6),
+            MarketSide::Buy,
+            dec!(99.5),
+        )];
+        let labels = labeler.label(&fills, &ticks);
+        assert_eq!(labels.len(), 1);
+        let label = &labels[0];
+        assert!((label.price_improvement_bps - 50.).abs() < 1e-9);
+        assert_eq!(label.fill_latency, Duration::from_secs(5));
+        assert_eq!(label.markouts_bps.len(), 2);
+        assert_eq!(label.markouts_bps[0].0, Duration::from_secs(10));
+        assert!((label.markouts_bps[0].1 - (99. - 99.5) / 99.5 * 1e4).abs() < 1e-9);
+        assert!((label.adverse_selection_bps() - (99.5 - 98.) / 99.5 * 1e4).abs() < 1e-9);
+        assert!(label.reward(&RewardWeights::default()) < 0.);
+
+        // The tick data ends before the longest horizon
+        let fills = vec![fill(
+            t0 + Duration::from_secs(1),
+            t0 + Duration::from_secs(30),
+            MarketSide::Sell,
+            dec!(98),
+        )];
+        let labels = labeler.label(&fills, &ticks);
+        assert_eq!(labels[0].markouts_bps.len(), 1);
+        assert!((labels[0].price_improvement_bps - (-200.)).abs() < 1e-9);
+
+        let summary = ExecutionLabelSummary::from_labels(&labels, &RewardWeights::default());
+        assert_eq!(summary.fills, 1);
+        assert_eq!(summary.quantity, 2.);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+use serde::Serialize;
+
+use arkin_core::prelude::*;
+
+use crate::{config::InsightsServiceConfig, InsightsError};
+
+/// Bytes a value takes in the insight state: the `Decimal`, its timestamp key and the b-tree overhead.
+const STATE_BYTES_PER_VALUE: u64 = 48;
+
+/// Feature node of a resolved pipeline.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainedFeature {
+    /// Position of the feature in the pipeline config
+    pub index: usize,
+    pub kind: &'static str,
+    pub inputs: Vec<FeatureId>,
+    pub outputs: Vec<FeatureId>,
+    pub window: Option<String>,
+    /// Config positions of the features producing the inputs
+    pub depends_on: Vec<usize>,
+    pub persist: bool,
+    /// Estimated size of the output series in the state
+    pub state_bytes: u64,
+}
+
+/// Resolved DAG of a pipeline config in the order the features are calculated, built without constructing or running
+/// any feature.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineExplain {
+    pub name: String,
+    pub instruments: usize,
+    /// Values kept per series, the state lookback over the frequency
+    pub values_per_series: u64,
+    pub raw_inputs: Vec<FeatureId>,
+    pub features: Vec<ExplainedFeature>,
+}
+
+impl PipelineExplain {
+    pub fn from_config(config: &InsightsServiceConfig, instruments: usize) -> Result<Self, InsightsError> {
+        let name = config.pipeline.name.clone();
+        let invalid = |reason: String| InsightsError::InvalidPipeline(name.clone(), reason);
+
+        let mut producers = HashMap::new();
+        for (index, feature) in config.pipeline.features.iter().enumerate() {
+            for output in feature.outputs() {
+                if let Some(other) = producers.insert(output.clone(), index) {
+                    return Err(invalid(format!("{} is an output of feature {} and {}", output, other, index)));
+                }
+            }
+        }
+
+        let values_per_series = config.state_lookback / config.frequency_secs.max(1);
+        let mut raw_inputs: Vec<FeatureId> = Vec::new();
+        let mut features = Vec::with_capacity(config.pipeline.features.len());
+        for (index, feature) in config.pipeline.features.iter().enumerate() {
+            let inputs = feature.inputs();
+            let mut depends_on = Vec::new();
+            for input in &inputs {
+                if RAW_FEATURE_IDS.contains(input) {
+                    if !raw_inputs.contains(input) {
+                        raw_inputs.push(input.clone());
+                    }
+                    continue;
+                }
+                match producers.get(input) {
+                    Some(producer) if !depends_on.contains(producer) => depends_on.push(*producer),
+                    Some(_) => {}
+                    None => return Err(invalid(format!("input {} of feature {} is not produced", input, index))),
+                }
+            }
+            let outputs = feature.outputs();
+            let state_bytes = outputs.len() as u64 * instruments as u64 * values_per_series * STATE_BYTES_PER_VALUE;
+            features.push(ExplainedFeature {
+                index,
+                kind: feature.kind(),
+                inputs,
+                outputs,
+                window: feature.window(config.scale_periods),
+                depends_on,
+                persist: feature.persist(),
+                state_bytes,
+            });
+        }
+
+        let features = topological_order(features).map_err(invalid)?;
+        Ok(Self {
+            name,
+            instruments,
+            values_per_series,
+            raw_inputs,
+            features,
+        })
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        self.features.iter().map(|f| f.state_bytes).sum()
+    }
+}
+
+/// Sort the features so every feature comes after the features it depends on (Kahn's algorithm).
+fn topological_order(features: Vec<ExplainedFeature>) -> Result<Vec<ExplainedFeature>, String> {
+    let mut in_degrees = features.iter().map(|f| f.depends_on.len()).collect::<Vec<_>>();
+    let mut ready = (0..features.len()).filter(|i| in_degrees[*i] == 0).collect::<VecDeque<_>>();
+    let mut order = Vec::with_capacity(features.len());
+    while let Some(index) = ready.pop_front() {
+        order.push(index);
+        for (dependent, feature) in features.iter().enumerate() {
+            if feature.depends_on.contains(&index) {
+                in_degrees[dependent] -= 1;
+                if in_degrees[dependent] == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+        }
+    }
+    if order.len() < features.len() {
+        let cycle = (0..features.len())
+            .filter(|i| in_degrees[*i] > 0)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        return Err(format!("cycle between features {}", cycle.join(", ")));
+    }
+
+    let mut features = features.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order.into_iter().filter_map(|i| features[i].take()).collect())
+}
+
+impl fmt::Display for PipelineExplain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "pipeline={} features={} instruments={} values_per_series={} estimated_state={}",
+            self.name,
+            self.features.len(),
+            self.instruments,
+            self.values_per_series,
+            format_bytes(self.state_bytes())
+        )?;
+        writeln!(f, "raw inputs: {}", join(&self.raw_inputs))?;
+        for feature in &self.features {
+            writeln!(
+                f,
+                "[{}] {} window={} persist={} state={}",
+                feature.index,
+                feature.kind,
+                feature.window.as_deref().unwrap_or("-"),
+                feature.persist,
+                format_bytes(feature.state_bytes)
+            )?;
+            writeln!(f, "    inputs: {}", join(&feature.inputs))?;
+            writeln!(f, "    outputs: {}", join(&feature.outputs))?;
+            if !feature.depends_on.is_empty() {
+                let depends_on = feature.depends_on.iter().map(|i| format!("[{}]", i)).collect::<Vec<_>>();
+                writeln!(f, "    depends on: {}", depends_on.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn join(ids: &[FeatureId]) -> String {
+    ids.iter().map(|id| id.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KiB", b as f64 / (1u64 << 10) as f64),
+        b => format!("{} B", b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{FeatureConfig, LogReturnConfig, PipelineConfig, SamplingConfig, StdDevConfig};
+
+    use super::*;
+
+    fn config(features: Vec<FeatureConfig>) -> InsightsServiceConfig {
+        InsightsServiceConfig {
+            pipeline: PipelineConfig {
+                name: "test".into(),
+                features,
+                sampling: SamplingConfig::default(),
+            },
+            state_lookback: 3600,
+            frequency_secs: 60,
+            scale_periods: 2,
+            warm_start: None,
+            profiling: None,
+            labels: None,
+        }
+    }
+
+    fn log_return(input: &str, output: &str) -> FeatureConfig {
+        FeatureConfig::LogReturn(LogReturnConfig {
+            input: FeatureId::new(input.into()),
+            output: FeatureId::new(output.into()),
+            periods: 1,
+            persist: false,
+        })
+    }
+
+    fn std_dev(input: &str, output: &str) -> FeatureConfig {
+        FeatureConfig::StdDev(StdDevConfig {
+            input: FeatureId::new(input.into()),
+            output: FeatureId::new(output.into()),
+            periods: 10,
+            persist: true,
+        })
+    }
+
+    #[test]
+    fn test_explain() {
+        let explain = PipelineExplain::from_config(
+            &config(vec![
+                std_dev("log_return", "volatility"),
+                log_return("trade_price", "log_return"),
+            ]),
+            3,
+        )
+        .unwrap();
+        assert_eq!(explain.raw_inputs, vec![FeatureId::new("trade_price".into())]);
+        assert_eq!(explain.features[0].kind, "log_return");
+        assert_eq!(explain.features[1].depends_on, vec![1]);
+        assert_eq!(explain.features[1].window.as_deref(), Some("20 periods"));
+        assert_eq!(explain.state_bytes(), 2 * 3 * 60 * STATE_BYTES_PER_VALUE);
+        assert!(explain.to_string().contains("[0] std_dev window=20 periods persist=true"));
+    }
+
+    #[test]
+    fn test_explain_invalid() {
+        let missing = PipelineExplain::from_config(&config(vec![std_dev("log_return", "volatility")]), 1);
+        assert!(missing
+            .unwrap_err()
+            .to_string()
+            .contains("input log_return of feature 0 is not produced"));
+
+        let cycle = PipelineExplain::from_config(&config(vec![std_dev("a", "b"), std_dev("b", "a")]), 1);
+        assert!(cycle.unwrap_err().to_string().contains("cycle between features 0, 1"));
+    }
+}
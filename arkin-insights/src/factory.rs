@@ -1,12 +1,18 @@
 use std::{sync::Arc, time::Duration};
 
+use time::{macros::format_description, PrimitiveDateTime};
+
 use arkin_core::prelude::*;
 
 use crate::{
     allocation::MeanVarianceFeature,
-    config::FeatureConfig,
+    config::{AnchorConfig, FeatureConfig},
     forecast::CatBoostFeature,
-    simple::{LogReturnFeature, OHLCVFeature, SignalStrengthFeature, StdDevFeature, SumFeature, TimeFeature},
+    options::OptionGreeksFeature,
+    simple::{
+        Anchor, AnchoredVWAPFeature, LogReturnFeature, OHLCVFeature, SignalStrengthFeature, StdDevFeature, SumFeature,
+        TimeFeature,
+    },
     state::InsightsState,
     ta::{
         AverageDirectionalIndexFeature, ChaikinMoneyFlowFeature, ChaikinOscillatorFeature, MovingAverageFeature,
@@ -23,6 +29,7 @@ impl FeatureFactory {
         pipeline: Arc<Pipeline>,
         state: Arc<InsightsState>,
         scale_periods: usize,
+        predictions: Option<Arc<PubSub>>,
     ) -> Vec<Box<dyn Computation>> {
         // Create nodes
         configs
@@ -66,6 +73,18 @@ impl FeatureFactory {
                             .persist(c.persist)
                             .build(),
                     ),
+                    FeatureConfig::AnchoredVWAP(c) => Box::new(
+                        AnchoredVWAPFeature::builder()
+                            .pipeline(pipeline.clone())
+                            .insight_state(state.clone())
+                            .input_price(c.input_price.clone())
+                            .input_quantity(c.input_quantity.clone())
+                            .anchor(Self::anchor(&c.anchor))
+                            .output_vwap(c.output_vwap.clone())
+                            .output_twap(c.output_twap.clone())
+                            .persist(c.persist)
+                            .build(),
+                    ),
                     FeatureConfig::LogReturn(c) => Box::new(
                         LogReturnFeature::builder()
                             .pipeline(pipeline.clone())
@@ -169,6 +188,7 @@ impl FeatureFactory {
                             .input_categorical(c.input_categorical.clone())
                             .output(c.output.clone())
                             .persist(c.persist)
+                            .predictions(predictions.clone())
                             .build(),
                     ),
                     FeatureConfig::MeanVariance(c) => Box::new(
@@ -189,9 +209,39 @@ impl FeatureFactory {
                             .persist(c.persist)
                             .build(),
                     ),
+                    FeatureConfig::OptionGreeks(c) => Box::new(
+                        OptionGreeksFeature::builder()
+                            .pipeline(pipeline.clone())
+                            .insight_state(state.clone())
+                            .input_option_price(c.input_option_price.clone())
+                            .input_underlying_price(c.input_underlying_price.clone())
+                            .output_iv(c.output_iv.clone())
+                            .output_delta(c.output_delta.clone())
+                            .output_gamma(c.output_gamma.clone())
+                            .output_vega(c.output_vega.clone())
+                            .output_theta(c.output_theta.clone())
+                            .risk_free_rate(c.risk_free_rate)
+                            .persist(c.persist)
+                            .build(),
+                    ),
                 };
                 feature
             })
             .collect()
     }
+
+    fn anchor(config: &AnchorConfig) -> Anchor {
+        match config {
+            AnchorConfig::Session(c) => Anchor::Session {
+                open: time::Duration::seconds(c.open_secs as i64),
+                length: time::Duration::seconds(c.length_secs as i64),
+            },
+            AnchorConfig::Timestamp(c) => {
+                let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+                let time = PrimitiveDateTime::parse(&c.at, &format).expect("Invalid anchor time, use YYYY-MM-DD HH:MM");
+                Anchor::Timestamp(time.assume_utc())
+            }
+            AnchorConfig::Feature(c) => Anchor::Feature(c.input.clone()),
+        }
+    }
 }
@@ -1,10 +1,11 @@
-use std::{fmt, sync::Arc};
+use std::{fmt, sync::Arc, time::Instant};
 
 use anyhow::Result;
 use catboost_rs::Model;
 use dashmap::DashMap;
 use rayon::prelude::*;
 use rust_decimal::prelude::*;
+use sha2::{Digest, Sha256};
 use time::OffsetDateTime;
 use tracing::{debug, info, warn};
 use typed_builder::TypedBuilder;
@@ -26,6 +27,26 @@ pub struct CatBoostFeature {
     input_categorical: Vec<FeatureId>,
     output: FeatureId,
     persist: bool,
+    /// Publish every prediction with the model metadata
+    #[builder(default)]
+    predictions: Option<Arc<PubSub>>,
+}
+
+impl CatBoostFeature {
+    /// Stable hash of the model inputs, so the predictions of different model versions can be matched on the
+    /// features they saw.
+    fn feature_hash(&self, numerical: &[f32], categorical: &[String]) -> String {
+        let mut hasher = Sha256::new();
+        for (id, value) in self.input_numerical.iter().zip(numerical) {
+            hasher.update(id.as_bytes());
+            hasher.update(value.to_le_bytes());
+        }
+        for (id, value) in self.input_categorical.iter().zip(categorical) {
+            hasher.update(id.as_bytes());
+            hasher.update(value.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
 }
 
 impl fmt::Debug for CatBoostFeature {
@@ -105,9 +126,15 @@ impl Computation for CatBoostFeature {
                 }
 
                 // Apply the model
+                let feature_hash = self
+                    .predictions
+                    .as_ref()
+                    .map(|_| self.feature_hash(&numerical_data, &categorical_data));
+                let started = Instant::now();
                 let prediction = model
                     .calc_model_prediction(vec![numerical_data], vec![categorical_data])
                     .expect("Failed to calculate model prediction");
+                let latency = started.elapsed();
 
                 // info!("Prediction: {:?}", prediction);
                 // None
@@ -116,6 +143,21 @@ impl Computation for CatBoostFeature {
 
                 debug!("Prediction for {} is {}", instrument, prediction);
 
+                if let (Some(pubsub), Some(feature_hash)) = (&self.predictions, feature_hash) {
+                    let prediction = Prediction::builder()
+                        .event_time(event_time)
+                        .pipeline(self.pipeline.clone())
+                        .instrument(instrument.clone())
+                        .feature_id(self.output.clone())
+                        .model_name(self.model_name.clone())
+                        .model_version(self.model_version.clone())
+                        .feature_hash(feature_hash)
+                        .value(prediction)
+                        .latency(latency)
+                        .build();
+                    pubsub.publish::<Prediction>(Arc::new(prediction));
+                }
+
                 // Return insight
                 Some(
                     Insight::builder()
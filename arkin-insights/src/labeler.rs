@@ -0,0 +1,250 @@
+use std::{collections::HashMap, sync::Arc};
+
+use rayon::prelude::*;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    config::{ForwardReturnConfig, LabelerConfig, MetaLabelConfig, TripleBarrierConfig},
+    vectorized::{Frame, Series},
+};
+
+/// Computes supervised targets from the prices after every tick. A label at tick `t` is stamped with `t` so it lines
+/// up with the features of that tick, but it only looks at prices in `(t, t + horizon]` and is only produced when the
+/// data covers the whole horizon. Everything it reads from before the tick (the entry price, the volatility and the
+/// signal) is the last value at or before `t`, like the features see it.
+#[derive(Debug, Clone)]
+pub struct Labeler {
+    config: LabelerConfig,
+}
+
+impl Labeler {
+    pub fn from_config(config: &LabelerConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<FeatureId> {
+        self.config
+            .forward_returns
+            .iter()
+            .map(|c| c.output.clone())
+            .chain(self.config.triple_barriers.iter().map(|c| c.output.clone()))
+            .chain(self.config.meta_labels.iter().map(|c| c.output.clone()))
+            .collect()
+    }
+
+    /// Longest look ahead of the labels. Training and test sets need at least this gap between them, otherwise the
+    /// labels at the end of the training set overlap the test set.
+    pub fn max_horizon(&self) -> u64 {
+        self.config
+            .forward_returns
+            .iter()
+            .map(|c| c.horizon_secs)
+            .chain(self.config.triple_barriers.iter().map(|c| c.horizon_secs))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Label the ticks of one instrument. The frame holds the price and the features the labels depend on, `end` is
+    /// the last second covered by the data.
+    pub fn run(&self, frame: &Frame, ticks: &[i64], end: i64) -> Frame {
+        let mut labels = Frame::new();
+        let Some(prices) = frame.get(&self.config.input) else {
+            return labels;
+        };
+        for config in &self.config.forward_returns {
+            labels.insert(config.output.clone(), forward_return(config, prices, ticks, end));
+        }
+        for config in &self.config.triple_barriers {
+            let volatility = config.volatility.as_ref().and_then(|id| frame.get(id));
+            labels.insert(config.output.clone(), triple_barrier(config, prices, volatility, ticks, end));
+        }
+        for config in &self.config.meta_labels {
+            let (Some(signal), Some(barrier)) = (frame.get(&config.input_signal), labels.get(&config.input_barrier))
+            else {
+                continue;
+            };
+            let series = meta_label(signal, barrier);
+            labels.insert(config.output.clone(), series);
+        }
+        labels
+    }
+
+    /// Label all instruments in parallel.
+    pub fn run_all(
+        &self,
+        frames: &HashMap<Arc<Instrument>, Frame>,
+        ticks: &[i64],
+        end: i64,
+    ) -> HashMap<Arc<Instrument>, Frame> {
+        frames
+            .par_iter()
+            .map(|(instrument, frame)| (instrument.clone(), self.run(frame, ticks, end)))
+            .collect()
+    }
+}
+
+/// Last value at or before the timestamp.
+fn value_at(series: &Series, timestamp: i64) -> Option<f64> {
+    match series.until(timestamp) {
+        0 => None,
+        i => Some(series.values[i - 1]),
+    }
+}
+
+/// Log return from the price at the tick to the price at the end of the horizon.
+pub(crate) fn forward_return(config: &ForwardReturnConfig, prices: &Series, ticks: &[i64], end: i64) -> Series {
+    let horizon = config.horizon_secs as i64;
+    let mut series = Series::default();
+    for tick in ticks.iter().filter(|t| **t + horizon <= end) {
+        let (Some(entry), Some(exit)) = (value_at(prices, *tick), value_at(prices, tick + horizon)) else {
+            continue;
+        };
+        if entry > 0. && exit > 0. {
+            series.push(*tick, (exit / entry).ln());
+        }
+    }
+    series
+}
+
+/// 1 when the upper barrier is touched first, -1 for the lower barrier and 0 when the horizon ends before either.
+fn triple_barrier(
+    config: &TripleBarrierConfig,
+    prices: &Series,
+    volatility: Option<&Series>,
+    ticks: &[i64],
+    end: i64,
+) -> Series {
+    let horizon = config.horizon_secs as i64;
+    let mut series = Series::default();
+    for tick in ticks.iter().filter(|t| **t + horizon <= end) {
+        let Some(entry) = value_at(prices, *tick).filter(|p| *p > 0.) else {
+            continue;
+        };
+        let scale = match (&config.volatility, volatility) {
+            (None, _) => 1.,
+            (Some(_), Some(volatility)) => match value_at(volatility, *tick) {
+                Some(v) if v > 0. => v,
+                _ => continue,
+            },
+            // The volatility is configured but was not computed, so there is nothing to scale with
+            (Some(_), None) => continue,
+        };
+        let upper = config.take_profit * scale;
+        let lower = -config.stop_loss * scale;
+
+        let path = &prices.values[prices.until(*tick)..prices.until(tick + horizon)];
+        let label = path
+            .iter()
+            .map(|p| p / entry - 1.)
+            .find_map(|r| {
+                if r >= upper {
+                    Some(1.)
+                } else if r <= lower {
+                    Some(-1.)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(0.);
+        series.push(*tick, label);
+    }
+    series
+}
+
+/// 1 when acting on the side of the primary signal would have hit the take profit, 0 otherwise. Ticks without a
+/// side are not labelled.
+fn meta_label(signal: &Series, barrier: &Series) -> Series {
+    let mut series = Series::default();
+    for (tick, label) in barrier.timestamps.iter().zip(&barrier.values) {
+        let Some(side) = value_at(signal, *tick).filter(|s| *s != 0.) else {
+            continue;
+        };
+        let hit = *label != 0. && side.signum() == label.signum();
+        series.push(*tick, if hit { 1. } else { 0. });
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> FeatureId {
+        FeatureId::new(name.into())
+    }
+
+    fn series(values: &[(i64, f64)]) -> Series {
+        let mut series = Series::default();
+        for (ts, value) in values {
+            series.push(*ts, *value);
+        }
+        series
+    }
+
+    fn labeler() -> Labeler {
+        Labeler::from_config(&LabelerConfig {
+            input: id("price"),
+            forward_returns: vec![ForwardReturnConfig {
+                output: id("fwd_return"),
+                horizon_secs: 60,
+            }],
+            triple_barriers: vec![TripleBarrierConfig {
+                output: id("barrier"),
+                horizon_secs: 60,
+                take_profit: 0.02,
+                stop_loss: 0.01,
+                volatility: None,
+            }],
+            meta_labels: vec![MetaLabelConfig {
+                output: id("meta"),
+                input_signal: id("signal"),
+                input_barrier: id("barrier"),
+            }],
+            execution: None,
+        })
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut frame = Frame::new();
+        // Up 3% within the first minute, down 1.5% in the second and flat in the third
+        frame.insert(
+            id("price"),
+            series(&[
+                (0, 100.),
+                (30, 103.),
+                (60, 102.),
+                (90, 100.5),
+                (120, 100.47),
+                (150, 100.6),
+                (180, 100.4),
+            ]),
+        );
+        frame.insert(id("signal"), series(&[(0, 1.), (60, 1.), (120, -1.)]));
+
+        let labels = labeler().run(&frame, &[0, 60, 120, 180], 180);
+
+        let fwd = &labels[&id("fwd_return")];
+        assert_eq!(fwd.timestamps, vec![0, 60, 120]);
+        assert!((fwd.values[0] - (102f64 / 100.).ln()).abs() < 1e-12);
+
+        assert_eq!(labels[&id("barrier")], series(&[(0, 1.), (60, -1.), (120, 0.)]));
+        assert_eq!(labels[&id("meta")], series(&[(0, 1.), (60, 0.), (120, 0.)]));
+    }
+
+    #[test]
+    fn test_point_in_time() {
+        let mut frame = Frame::new();
+        frame.insert(id("price"), series(&[(0, 100.), (59, 110.), (61, 90.)]));
+
+        // The horizon of the tick at 30 ends after the data, so it has no label yet
+        let labels = labeler().run(&frame, &[0, 30], 60);
+        assert_eq!(labels[&id("fwd_return")].timestamps, vec![0]);
+        // The trade one second after the horizon is not part of the label
+        assert_eq!(labels[&id("barrier")], series(&[(0, 1.)]));
+        assert_eq!(labeler().max_horizon(), 60);
+    }
+}
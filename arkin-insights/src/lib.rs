@@ -1,23 +1,52 @@
 mod allocation;
+mod batch;
 mod config;
 mod errors;
+mod evaluation;
+mod execution_labels;
+mod explain;
 mod factory;
 mod forecast;
+mod labeler;
+pub mod math;
+mod options;
 mod pipeline;
+mod profiler;
+mod sampling;
 mod service;
 mod simple;
 mod state;
 mod ta;
 mod traits;
+mod validation;
+mod vectorized;
 
+pub use batch::*;
 pub use errors::*;
+pub use evaluation::*;
+pub use execution_labels::*;
+pub use explain::*;
+pub use labeler::*;
+pub use profiler::*;
+pub use sampling::*;
 pub use service::InsightsService;
 pub use traits::*;
+pub use validation::*;
+pub use vectorized::*;
 
 pub mod prelude {
     // pub use crate::base::*;
+    pub use crate::batch::*;
     pub use crate::config::*;
     pub use crate::errors::*;
+    pub use crate::evaluation::*;
+    pub use crate::execution_labels::*;
+    pub use crate::explain::*;
+    pub use crate::labeler::*;
+    pub use crate::profiler::*;
+    pub use crate::sampling::*;
     pub use crate::service::InsightsService;
     pub use crate::traits::*;
+    pub use crate::validation::*;
+    pub use crate::vectorized::*;
 }
@@ -0,0 +1,448 @@
+//! Kernels for rolling window statistics over `f64` series.
+//!
+//! Reductions are split in chunks of [`LANES`] independent accumulators so the compiler can vectorize the loops, the
+//! rolling versions aggregate the window with a two-stack queue of partial results instead of recomputing every
+//! window from scratch. Both only need the aggregation to be a [`Monoid`].
+
+/// Number of independent accumulators in the chunked reductions.
+pub const LANES: usize = 8;
+
+/// Associative aggregation with an identity, so a window can be aggregated from the partial results of its parts.
+pub trait Monoid {
+    type Value: Copy + std::fmt::Debug;
+
+    fn identity() -> Self::Value;
+    fn lift(value: f64) -> Self::Value;
+    fn combine(a: Self::Value, b: Self::Value) -> Self::Value;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+impl Monoid for Sum {
+    type Value = f64;
+
+    fn identity() -> f64 {
+        0.
+    }
+
+    fn lift(value: f64) -> f64 {
+        value
+    }
+
+    fn combine(a: f64, b: f64) -> f64 {
+        a + b
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Min;
+
+impl Monoid for Min {
+    type Value = f64;
+
+    fn identity() -> f64 {
+        f64::INFINITY
+    }
+
+    fn lift(value: f64) -> f64 {
+        value
+    }
+
+    fn combine(a: f64, b: f64) -> f64 {
+        a.min(b)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Max;
+
+impl Monoid for Max {
+    type Value = f64;
+
+    fn identity() -> f64 {
+        f64::NEG_INFINITY
+    }
+
+    fn lift(value: f64) -> f64 {
+        value
+    }
+
+    fn combine(a: f64, b: f64) -> f64 {
+        a.max(b)
+    }
+}
+
+/// Count, mean and sum of squared deviations, combined with the parallel algorithm of Chan et al. which stays stable
+/// where the naive sum of squares cancels out.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Moments {
+    pub count: f64,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl Moments {
+    /// Sample variance, `None` with fewer than two values.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count >= 2.).then(|| self.m2 / (self.count - 1.))
+    }
+}
+
+impl Monoid for Moments {
+    type Value = Moments;
+
+    fn identity() -> Moments {
+        Moments::default()
+    }
+
+    fn lift(value: f64) -> Moments {
+        Moments {
+            count: 1.,
+            mean: value,
+            m2: 0.,
+        }
+    }
+
+    fn combine(a: Moments, b: Moments) -> Moments {
+        if a.count == 0. {
+            return b;
+        }
+        if b.count == 0. {
+            return a;
+        }
+        let count = a.count + b.count;
+        let delta = b.mean - a.mean;
+        Moments {
+            count,
+            mean: a.mean + delta * b.count / count,
+            m2: a.m2 + b.m2 + delta * delta * a.count * b.count / count,
+        }
+    }
+}
+
+/// Fixed size window over a stream of values answering the aggregate in amortized constant time. New values go on
+/// the back stack, when the front stack runs empty the back stack is moved over and its suffix aggregates computed.
+#[derive(Debug, Clone)]
+pub struct SlidingWindow<M: Monoid> {
+    capacity: usize,
+    /// Oldest value on top, every entry holds the aggregate of itself and the newer entries below it
+    front: Vec<M::Value>,
+    front_aggregates: Vec<M::Value>,
+    back: Vec<M::Value>,
+    back_aggregate: M::Value,
+}
+
+impl<M: Monoid> SlidingWindow<M> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Window capacity must be positive");
+        Self {
+            capacity,
+            front: Vec::with_capacity(capacity),
+            front_aggregates: Vec::with_capacity(capacity),
+            back: Vec::with_capacity(capacity),
+            back_aggregate: M::identity(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+
+    /// Add a value, dropping the oldest one if the window is full.
+    pub fn push(&mut self, value: f64) {
+        if self.is_full() {
+            self.pop();
+        }
+        let value = M::lift(value);
+        self.back.push(value);
+        self.back_aggregate = M::combine(self.back_aggregate, value);
+    }
+
+    fn pop(&mut self) {
+        if self.front.is_empty() {
+            while let Some(value) = self.back.pop() {
+                let newer = self.front_aggregates.last().copied().unwrap_or_else(M::identity);
+                self.front.push(value);
+                self.front_aggregates.push(M::combine(value, newer));
+            }
+            self.back_aggregate = M::identity();
+        }
+        self.front.pop();
+        self.front_aggregates.pop();
+    }
+
+    pub fn aggregate(&self) -> M::Value {
+        let front = self.front_aggregates.last().copied().unwrap_or_else(M::identity);
+        M::combine(front, self.back_aggregate)
+    }
+}
+
+/// Aggregate every full window of the series, the result has `data.len() - window + 1` values.
+pub fn rolling<M: Monoid>(data: &[f64], window: usize) -> Vec<M::Value> {
+    if window == 0 || data.len() < window {
+        return Vec::new();
+    }
+    let mut sliding = SlidingWindow::<M>::new(window);
+    let mut result = Vec::with_capacity(data.len() - window + 1);
+    for value in data {
+        sliding.push(*value);
+        if sliding.is_full() {
+            result.push(sliding.aggregate());
+        }
+    }
+    result
+}
+
+pub fn sum(data: &[f64]) -> f64 {
+    let chunks = data.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut lanes = [0.; LANES];
+    for chunk in chunks {
+        for (lane, value) in lanes.iter_mut().zip(chunk) {
+            *lane += value;
+        }
+    }
+    lanes.iter().sum::<f64>() + remainder.iter().sum::<f64>()
+}
+
+pub fn mean(data: &[f64]) -> Option<f64> {
+    (!data.is_empty()).then(|| sum(data) / data.len() as f64)
+}
+
+/// Sample variance, `None` with fewer than two values.
+pub fn variance(data: &[f64]) -> Option<f64> {
+    if data.len() < 2 {
+        return None;
+    }
+    let mean = mean(data)?;
+    let chunks = data.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut lanes = [0.; LANES];
+    for chunk in chunks {
+        for (lane, value) in lanes.iter_mut().zip(chunk) {
+            *lane += (value - mean) * (value - mean);
+        }
+    }
+    let squares = lanes.iter().sum::<f64>() + remainder.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>();
+    Some(squares / (data.len() - 1) as f64)
+}
+
+pub fn std_dev(data: &[f64]) -> Option<f64> {
+    variance(data).map(f64::sqrt)
+}
+
+pub fn min(data: &[f64]) -> Option<f64> {
+    reduce::<Min>(data)
+}
+
+pub fn max(data: &[f64]) -> Option<f64> {
+    reduce::<Max>(data)
+}
+
+fn reduce<M: Monoid<Value = f64>>(data: &[f64]) -> Option<f64> {
+    if data.is_empty() {
+        return None;
+    }
+    let chunks = data.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut lanes = [M::identity(); LANES];
+    for chunk in chunks {
+        for (lane, value) in lanes.iter_mut().zip(chunk) {
+            *lane = M::combine(*lane, *value);
+        }
+    }
+    Some(
+        lanes
+            .into_iter()
+            .chain(remainder.iter().copied())
+            .fold(M::identity(), M::combine),
+    )
+}
+
+/// Quantile with linear interpolation between the closest ranks, `q` is clamped to [0, 1].
+pub fn quantile(data: &[f64], q: f64) -> Option<f64> {
+    if data.is_empty() {
+        return None;
+    }
+    let mut values = data.to_vec();
+    let rank = q.clamp(0., 1.) * (values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let (_, value, upper) = values.select_nth_unstable_by(lower, f64::total_cmp);
+    let value = *value;
+    let next = upper.iter().copied().min_by(f64::total_cmp).unwrap_or(value);
+    Some(value + (next - value) * (rank - lower as f64))
+}
+
+pub fn rolling_sum(data: &[f64], window: usize) -> Vec<f64> {
+    rolling::<Sum>(data, window)
+}
+
+pub fn rolling_mean(data: &[f64], window: usize) -> Vec<f64> {
+    rolling::<Sum>(data, window).into_iter().map(|s| s / window as f64).collect()
+}
+
+/// Rolling sample standard deviation, empty for windows smaller than two.
+pub fn rolling_std_dev(data: &[f64], window: usize) -> Vec<f64> {
+    rolling::<Moments>(data, window)
+        .into_iter()
+        .filter_map(|m| m.variance().map(f64::sqrt))
+        .collect()
+}
+
+pub fn rolling_min(data: &[f64], window: usize) -> Vec<f64> {
+    rolling::<Min>(data, window)
+}
+
+pub fn rolling_max(data: &[f64], window: usize) -> Vec<f64> {
+    rolling::<Max>(data, window)
+}
+
+/// Rolling quantile, the window is kept sorted so every step is a binary search and a shift instead of a selection.
+pub fn rolling_quantile(data: &[f64], window: usize, q: f64) -> Vec<f64> {
+    if window == 0 || data.len() < window {
+        return Vec::new();
+    }
+    let rank = q.clamp(0., 1.) * (window - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = (lower + 1).min(window - 1);
+    let fraction = rank - lower as f64;
+
+    let mut sorted = data[..window].to_vec();
+    sorted.sort_unstable_by(f64::total_cmp);
+    let mut result = Vec::with_capacity(data.len() - window + 1);
+    result.push(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction);
+    for (old, new) in data.iter().zip(&data[window..]) {
+        let position = sorted.partition_point(|v| v.total_cmp(old).is_lt());
+        sorted.remove(position);
+        let position = sorted.partition_point(|v| v.total_cmp(new).is_lt());
+        sorted.insert(position, *new);
+        result.push(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use test_log::test;
+    use tracing::info;
+
+    use super::*;
+
+    fn series(len: usize) -> Vec<f64> {
+        // Deterministic random walk around 100
+        let mut state = 42u64;
+        let mut price = 100.;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                price += ((state >> 33) as f64 / (1u64 << 31) as f64) - 0.5;
+                price
+            })
+            .collect()
+    }
+
+    fn scalar_std_dev(data: &[f64]) -> f64 {
+        let mean = data.iter().sum::<f64>() / data.len() as f64;
+        (data.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (data.len() - 1) as f64).sqrt()
+    }
+
+    fn scalar_quantile(data: &[f64], q: f64) -> f64 {
+        let mut sorted = data.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let rank = q * (sorted.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_reductions() {
+        let data = series(101);
+        assert!((sum(&data) - data.iter().sum::<f64>()).abs() < 1e-9);
+        assert!((std_dev(&data).unwrap() - scalar_std_dev(&data)).abs() < 1e-9);
+        assert_eq!(min(&data), data.iter().copied().reduce(f64::min));
+        assert_eq!(max(&data), data.iter().copied().reduce(f64::max));
+        assert_eq!(quantile(&[4., 1., 3., 2.], 0.5), Some(2.5));
+        assert_eq!(quantile(&[4., 1., 3., 2.], 1.), Some(4.));
+        assert_eq!(mean(&[]), None);
+        assert_eq!(variance(&[1.]), None);
+        assert_eq!(min(&[]), None);
+    }
+
+    #[test]
+    fn test_rolling() {
+        let data = series(250);
+        let window = 20;
+        let windows = data.windows(window).collect::<Vec<_>>();
+
+        let expected = windows
+            .iter()
+            .map(|w| w.iter().sum::<f64>() / window as f64)
+            .collect::<Vec<_>>();
+        assert_close(&rolling_mean(&data, window), &expected);
+        let expected = windows.iter().map(|w| scalar_std_dev(w)).collect::<Vec<_>>();
+        assert_close(&rolling_std_dev(&data, window), &expected);
+        let expected = windows
+            .iter()
+            .map(|w| w.iter().copied().fold(f64::INFINITY, f64::min))
+            .collect::<Vec<_>>();
+        assert_close(&rolling_min(&data, window), &expected);
+        let expected = windows
+            .iter()
+            .map(|w| w.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+            .collect::<Vec<_>>();
+        assert_close(&rolling_max(&data, window), &expected);
+        let expected = windows.iter().map(|w| scalar_quantile(w, 0.9)).collect::<Vec<_>>();
+        assert_close(&rolling_quantile(&data, window, 0.9), &expected);
+
+        assert!(rolling_sum(&data[..5], window).is_empty());
+    }
+
+    /// Compare the kernels against the scalar loops on a multi-million row series:
+    /// `RUST_LOG=info cargo test --release -p arkin-insights bench_rolling -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_rolling() {
+        let data = series(5_000_000);
+        let window = 300;
+
+        let timed = |name: &str, f: &dyn Fn() -> Vec<f64>| {
+            let start = Instant::now();
+            let result = f();
+            info!("{:<24} {:>10.1?} ({} values)", name, start.elapsed(), result.len());
+            result
+        };
+
+        let scalar = timed("scalar std_dev", &|| data.windows(window).map(scalar_std_dev).collect());
+        let kernel = timed("rolling_std_dev", &|| rolling_std_dev(&data, window));
+        assert_close(&scalar, &kernel);
+        timed("scalar min", &|| {
+            data.windows(window)
+                .map(|w| w.iter().copied().fold(f64::INFINITY, f64::min))
+                .collect()
+        });
+        timed("rolling_min", &|| rolling_min(&data, window));
+        timed("scalar quantile", &|| {
+            data.windows(window).map(|w| scalar_quantile(w, 0.9)).collect()
+        });
+        timed("rolling_quantile", &|| rolling_quantile(&data, window, 0.9));
+    }
+}
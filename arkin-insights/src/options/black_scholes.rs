@@ -0,0 +1,169 @@
+use std::f64::consts::{PI, SQRT_2};
+
+use statrs::function::erf::erf;
+
+use arkin_core::prelude::*;
+
+/// Bounds of the implied volatility search.
+const MIN_VOLATILITY: f64 = 1e-6;
+const MAX_VOLATILITY: f64 = 10.0;
+const IV_TOLERANCE: f64 = 1e-8;
+const IV_MAX_ITERATIONS: usize = 200;
+
+pub fn norm_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / SQRT_2))
+}
+
+pub fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+/// Sensitivities of the option price. Vega is per volatility point (0.01) and theta per calendar day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub vega: f64,
+    pub theta: f64,
+}
+
+/// European option priced with Black-Scholes, time to expiry in years and a continuously compounded rate.
+#[derive(Debug, Clone)]
+pub struct BlackScholes {
+    pub option_type: InstrumentOptionType,
+    pub spot: f64,
+    pub strike: f64,
+    pub time: f64,
+    pub rate: f64,
+}
+
+impl BlackScholes {
+    fn d1_d2(&self, volatility: f64) -> (f64, f64) {
+        let sqrt_time = self.time.sqrt();
+        let d1 = ((self.spot / self.strike).ln() + (self.rate + 0.5 * volatility * volatility) * self.time)
+            / (volatility * sqrt_time);
+        (d1, d1 - volatility * sqrt_time)
+    }
+
+    fn discount(&self) -> f64 {
+        (-self.rate * self.time).exp()
+    }
+
+    /// Value of the option at expiry, the lower bound of the price.
+    fn intrinsic(&self) -> f64 {
+        let forward_strike = self.strike * self.discount();
+        match self.option_type {
+            InstrumentOptionType::Call => (self.spot - forward_strike).max(0.0),
+            InstrumentOptionType::Put => (forward_strike - self.spot).max(0.0),
+        }
+    }
+
+    pub fn price(&self, volatility: f64) -> f64 {
+        if self.time <= 0.0 || volatility <= 0.0 {
+            return self.intrinsic();
+        }
+        let (d1, d2) = self.d1_d2(volatility);
+        let forward_strike = self.strike * self.discount();
+        match self.option_type {
+            InstrumentOptionType::Call => self.spot * norm_cdf(d1) - forward_strike * norm_cdf(d2),
+            InstrumentOptionType::Put => forward_strike * norm_cdf(-d2) - self.spot * norm_cdf(-d1),
+        }
+    }
+
+    pub fn greeks(&self, volatility: f64) -> Greeks {
+        let (d1, d2) = self.d1_d2(volatility);
+        let sqrt_time = self.time.sqrt();
+        let forward_strike = self.strike * self.discount();
+        let decay = -self.spot * norm_pdf(d1) * volatility / (2.0 * sqrt_time);
+        let (delta, theta) = match self.option_type {
+            InstrumentOptionType::Call => (norm_cdf(d1), decay - self.rate * forward_strike * norm_cdf(d2)),
+            InstrumentOptionType::Put => (norm_cdf(d1) - 1.0, decay + self.rate * forward_strike * norm_cdf(-d2)),
+        };
+        Greeks {
+            delta,
+            gamma: norm_pdf(d1) / (self.spot * volatility * sqrt_time),
+            vega: self.spot * norm_pdf(d1) * sqrt_time / 100.0,
+            theta: theta / 365.0,
+        }
+    }
+
+    /// Volatility at which the model price matches the given price, found by bisection as the price is monotonic in
+    /// the volatility. `None` when the price is outside the no-arbitrage bounds or the option has expired.
+    pub fn implied_volatility(&self, price: f64) -> Option<f64> {
+        if self.time <= 0.0 || !price.is_finite() || price < self.intrinsic() {
+            return None;
+        }
+        let (mut low, mut high) = (MIN_VOLATILITY, MAX_VOLATILITY);
+        if self.price(high) < price {
+            return None;
+        }
+        for _ in 0..IV_MAX_ITERATIONS {
+            let mid = 0.5 * (low + high);
+            let diff = self.price(mid) - price;
+            if diff.abs() < IV_TOLERANCE || high - low < IV_TOLERANCE {
+                return Some(mid);
+            }
+            if diff > 0.0 {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Some(0.5 * (low + high))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(option_type: InstrumentOptionType) -> BlackScholes {
+        BlackScholes {
+            option_type,
+            spot: 100.0,
+            strike: 100.0,
+            time: 1.0,
+            rate: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_price() {
+        // Hull, at the money with 20% volatility
+        let call = option(InstrumentOptionType::Call).price(0.2);
+        let put = option(InstrumentOptionType::Put).price(0.2);
+        assert!((call - 10.4506).abs() < 1e-4);
+        assert!((put - 5.5735).abs() < 1e-4);
+
+        // Put call parity
+        assert!((call - put - (100.0 - 100.0 * (-0.05f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_greeks() {
+        let call = option(InstrumentOptionType::Call).greeks(0.2);
+        assert!((call.delta - 0.6368).abs() < 1e-4);
+        assert!((call.gamma - 0.01876).abs() < 1e-5);
+        assert!((call.vega - 0.3752).abs() < 1e-4);
+        assert!((call.theta - -6.4140 / 365.0).abs() < 1e-5);
+
+        let put = option(InstrumentOptionType::Put).greeks(0.2);
+        assert!((put.delta - (call.delta - 1.0)).abs() < 1e-12);
+        assert_eq!(put.gamma, call.gamma);
+    }
+
+    #[test]
+    fn test_implied_volatility() {
+        for option_type in [InstrumentOptionType::Call, InstrumentOptionType::Put] {
+            let contract = option(option_type);
+            for vol in [0.05, 0.2, 0.8, 2.5] {
+                let iv = contract.implied_volatility(contract.price(vol)).unwrap();
+                assert!((iv - vol).abs() < 1e-6, "expected {} got {}", vol, iv);
+            }
+        }
+
+        let call = option(InstrumentOptionType::Call);
+        assert!(call.implied_volatility(1.0).is_none());
+        assert!(call.implied_volatility(150.0).is_none());
+    }
+}
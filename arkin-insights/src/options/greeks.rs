@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rayon::prelude::*;
+use rust_decimal::prelude::*;
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{state::InsightsState, Computation};
+
+use super::BlackScholes;
+
+/// Implied volatility and Black-Scholes greeks of options from the mark price of the option and the price of its
+/// underlying. Instruments that are not options or miss a price are skipped.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct OptionGreeksFeature {
+    pipeline: Arc<Pipeline>,
+    insight_state: Arc<InsightsState>,
+    input_option_price: FeatureId,
+    input_underlying_price: FeatureId,
+    output_iv: FeatureId,
+    output_delta: FeatureId,
+    output_gamma: FeatureId,
+    output_vega: FeatureId,
+    output_theta: FeatureId,
+    risk_free_rate: f64,
+    persist: bool,
+}
+
+impl OptionGreeksFeature {
+    fn contract(&self, instrument: &Arc<Instrument>, event_time: OffsetDateTime) -> Option<(BlackScholes, f64)> {
+        let (Some(option_type), Some(strike), Some(underlying)) =
+            (&instrument.option_type, instrument.strike, &instrument.underlying)
+        else {
+            warn!("Option {} is missing its strike, type or underlying", instrument);
+            return None;
+        };
+        let time = instrument.time_to_expiry(event_time).filter(|t| *t > 0.0)?;
+
+        let option_price =
+            self.insight_state
+                .last(Some(instrument.clone()), self.input_option_price.clone(), event_time)?;
+        let spot =
+            self.insight_state
+                .last(Some(underlying.clone()), self.input_underlying_price.clone(), event_time)?;
+
+        let contract = BlackScholes {
+            option_type: option_type.clone(),
+            spot: spot.to_f64()?,
+            strike: strike.to_f64()?,
+            time,
+            rate: self.risk_free_rate,
+        };
+        Some((contract, option_price.to_f64()?))
+    }
+
+    fn insight(
+        &self,
+        instrument: &Arc<Instrument>,
+        event_time: OffsetDateTime,
+        feature_id: &FeatureId,
+        value: f64,
+    ) -> Option<Arc<Insight>> {
+        Some(
+            Insight::builder()
+                .event_time(event_time)
+                .pipeline(self.pipeline.clone())
+                .instrument(Some(instrument.clone()))
+                .feature_id(feature_id.clone())
+                .value(Decimal::from_f64(value)?)
+                .persist(self.persist)
+                .build()
+                .into(),
+        )
+    }
+}
+
+impl Computation for OptionGreeksFeature {
+    fn inputs(&self) -> Vec<FeatureId> {
+        vec![self.input_option_price.clone(), self.input_underlying_price.clone()]
+    }
+
+    fn outputs(&self) -> Vec<FeatureId> {
+        vec![
+            self.output_iv.clone(),
+            self.output_delta.clone(),
+            self.output_gamma.clone(),
+            self.output_vega.clone(),
+            self.output_theta.clone(),
+        ]
+    }
+
+    fn calculate(&self, instruments: &[Arc<Instrument>], event_time: OffsetDateTime) -> Result<Vec<Arc<Insight>>> {
+        debug!("Calculating Option Greeks...");
+
+        let insights = instruments
+            .par_iter()
+            .filter(|instrument| instrument.is_option())
+            .filter_map(|instrument| {
+                let (contract, option_price) = self.contract(instrument, event_time)?;
+                let Some(iv) = contract.implied_volatility(option_price) else {
+                    warn!("No implied volatility for {} at price {}", instrument, option_price);
+                    return None;
+                };
+                let greeks = contract.greeks(iv);
+
+                Some(
+                    [
+                        (&self.output_iv, iv),
+                        (&self.output_delta, greeks.delta),
+                        (&self.output_gamma, greeks.gamma),
+                        (&self.output_vega, greeks.vega),
+                        (&self.output_theta, greeks.theta),
+                    ]
+                    .into_iter()
+                    .filter_map(|(feature_id, value)| self.insight(instrument, event_time, feature_id, value))
+                    .collect::<Vec<_>>(),
+                )
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // Insert the insights into the state
+        self.insight_state.insert_batch(&insights);
+
+        Ok(insights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn feature(state: Arc<InsightsState>) -> OptionGreeksFeature {
+        OptionGreeksFeature::builder()
+            .pipeline(test_pipeline())
+            .insight_state(state)
+            .input_option_price(Arc::new("option_price".to_string()))
+            .input_underlying_price(Arc::new("underlying_price".to_string()))
+            .output_iv(Arc::new("iv".to_string()))
+            .output_delta(Arc::new("delta".to_string()))
+            .output_gamma(Arc::new("gamma".to_string()))
+            .output_vega(Arc::new("vega".to_string()))
+            .output_theta(Arc::new("theta".to_string()))
+            .risk_free_rate(0.0)
+            .persist(false)
+            .build()
+    }
+
+    fn price(
+        instrument: Arc<Instrument>,
+        feature_id: &str,
+        event_time: OffsetDateTime,
+        value: Decimal,
+    ) -> Arc<Insight> {
+        Insight::builder()
+            .event_time(event_time)
+            .pipeline(test_pipeline())
+            .instrument(Some(instrument))
+            .feature_id(Arc::new(feature_id.to_string()))
+            .value(value)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn test_option_greeks() {
+        let state = Arc::new(InsightsState::default());
+        let feature = feature(state.clone());
+        let option = test_inst_binance_btc_usdt_call();
+        let perp = test_inst_binance_btc_usdt_perp();
+        let event_time = datetime!(2025-01-27 08:00 UTC);
+
+        // Underlying at the strike, 60 days to expiry
+        let contract = BlackScholes {
+            option_type: InstrumentOptionType::Call,
+            spot: 100000.0,
+            strike: 100000.0,
+            time: option.time_to_expiry(event_time).unwrap(),
+            rate: 0.0,
+        };
+        let mark = Decimal::from_f64(contract.price(0.6)).unwrap().round_dp(2);
+        state.insert_batch(&[
+            price(option.clone(), "option_price", event_time, mark),
+            price(perp.clone(), "underlying_price", event_time, dec!(100000)),
+        ]);
+
+        let insights = feature.calculate(&[option.clone(), perp], event_time).unwrap();
+        assert_eq!(insights.len(), 5);
+        assert!(insights.iter().all(|i| i.instrument == Some(option.clone())));
+
+        let value = |id: &str| insights.iter().find(|i| i.feature_id.as_str() == id).unwrap().value;
+        assert_eq!(value("iv").round_dp(3), dec!(0.600));
+        assert_eq!(value("delta").round_dp(2), dec!(0.54));
+        assert!(value("gamma") > Decimal::ZERO);
+        assert!(value("vega") > Decimal::ZERO);
+        assert!(value("theta") < Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_skips_options_without_prices() {
+        let state = Arc::new(InsightsState::default());
+        let feature = feature(state.clone());
+        let insights = feature
+            .calculate(&[test_inst_binance_btc_usdt_call()], datetime!(2025-01-27 08:00 UTC))
+            .unwrap();
+        assert!(insights.is_empty());
+    }
+}
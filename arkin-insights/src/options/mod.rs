@@ -0,0 +1,5 @@
+mod black_scholes;
+mod greeks;
+
+pub use black_scholes::*;
+pub use greeks::OptionGreeksFeature;
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use arkin_core::prelude::*;
 use parking_lot::Mutex;
@@ -13,12 +14,13 @@ use rayon::ThreadPoolBuilder;
 use time::OffsetDateTime;
 use tracing::{debug, error, info};
 
-use crate::Computation;
+use crate::{profiler::FeatureProfiler, Computation};
 
 #[derive(Debug)]
 pub struct PipelineGraph {
     graph: Arc<DiGraph<Box<dyn Computation>, ()>>,
     order: Vec<NodeIndex>,
+    profiler: Option<Arc<FeatureProfiler>>,
 }
 
 impl PipelineGraph {
@@ -73,9 +75,16 @@ impl PipelineGraph {
         PipelineGraph {
             graph: Arc::new(graph),
             order,
+            profiler: None,
         }
     }
 
+    /// Record the compute time of every feature node in the profiler.
+    pub fn with_profiler(mut self, profiler: Arc<FeatureProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
     // Topological Sorting in parallel, which can be efficiently implemented using Kahn's algorithm
     pub fn calculate(&self, instruments: &[Arc<Instrument>], timestamp: OffsetDateTime) -> Vec<Arc<Insight>> {
         // Step 1: Calculate in-degrees
@@ -104,13 +113,18 @@ impl PipelineGraph {
                 let in_degrees = Arc::clone(&in_degrees);
                 let queue_tx = queue_tx.clone();
                 let pipeline_result = Arc::clone(&pipeline_result);
+                let profiler = self.profiler.as_ref();
 
                 s.spawn(move |_| {
                     // Process the node
                     let feature = &graph[node];
 
                     // Calculate the feature
+                    let start = Instant::now();
                     let res = feature.calculate(instruments, timestamp);
+                    if let Some(profiler) = profiler {
+                        profiler.record(node.index(), || feature_name(feature.as_ref()), start.elapsed());
+                    }
                     match res {
                         Ok(data) => pipeline_result.lock().extend(data),
                         Err(e) => error!("Failed to calculate: {:?}", e),
@@ -140,6 +154,16 @@ impl PipelineGraph {
     }
 }
 
+/// Name a feature node after its first output.
+fn feature_name(feature: &dyn Computation) -> String {
+    let outputs = feature.outputs();
+    match outputs.as_slice() {
+        [] => "unnamed".into(),
+        [output] => output.to_string(),
+        [output, rest @ ..] => format!("{} (+{} outputs)", output, rest.len()),
+    }
+}
+
 // COULD BE USED IN THE FUTURE IF WE HAVE ASYNC FEATURES
 // pub async fn calculate_async(&self) {
 //     // Step 1: Calculate in-degrees
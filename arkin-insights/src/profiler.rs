@@ -0,0 +1,108 @@
+use std::{collections::HashMap, fmt, time::Duration};
+
+use parking_lot::Mutex;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureProfile {
+    /// Outputs of the feature node, the first output names the node
+    pub feature: String,
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl FeatureProfile {
+    pub fn mean(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => self.total / calls as u32,
+        }
+    }
+}
+
+impl fmt::Display for FeatureProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} calls={} total={:?} mean={:?} max={:?}",
+            self.feature,
+            self.calls,
+            self.total,
+            self.mean(),
+            self.max
+        )
+    }
+}
+
+/// Records the compute time of every feature node of the pipeline.
+#[derive(Debug, Default)]
+pub struct FeatureProfiler {
+    profiles: Mutex<HashMap<usize, FeatureProfile>>,
+}
+
+impl FeatureProfiler {
+    pub fn record(&self, node: usize, feature: impl FnOnce() -> String, elapsed: Duration) {
+        let mut profiles = self.profiles.lock();
+        let profile = profiles.entry(node).or_insert_with(|| FeatureProfile {
+            feature: feature(),
+            ..Default::default()
+        });
+        profile.calls += 1;
+        profile.total += elapsed;
+        profile.max = profile.max.max(elapsed);
+    }
+
+    /// The `top_n` features that took the most time in total, slowest first.
+    pub fn report(&self, top_n: usize) -> FeatureProfileReport {
+        let mut profiles = self.profiles.lock().values().cloned().collect::<Vec<_>>();
+        profiles.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.feature.cmp(&b.feature)));
+        let total = profiles.iter().map(|p| p.total).sum();
+        profiles.truncate(top_n);
+        FeatureProfileReport { total, profiles }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeatureProfileReport {
+    /// Compute time of all features
+    pub total: Duration,
+    pub profiles: Vec<FeatureProfile>,
+}
+
+impl fmt::Display for FeatureProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Feature compute time {:?}, slowest features:", self.total)?;
+        for (rank, profile) in self.profiles.iter().enumerate() {
+            let share = match self.total.is_zero() {
+                true => 0.,
+                false => profile.total.as_secs_f64() / self.total.as_secs_f64() * 100.,
+            };
+            write!(f, "\n{:>3}. {} share={:.1}%", rank + 1, profile, share)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let profiler = FeatureProfiler::default();
+        profiler.record(0, || "close".into(), Duration::from_millis(1));
+        profiler.record(1, || "rsi".into(), Duration::from_millis(5));
+        profiler.record(1, || "rsi".into(), Duration::from_millis(3));
+        profiler.record(2, || "volatility".into(), Duration::from_millis(2));
+
+        let report = profiler.report(2);
+        assert_eq!(report.total, Duration::from_millis(11));
+        assert_eq!(report.profiles.len(), 2);
+        assert_eq!(report.profiles[0].feature, "rsi");
+        assert_eq!(report.profiles[0].calls, 2);
+        assert_eq!(report.profiles[0].mean(), Duration::from_millis(4));
+        assert_eq!(report.profiles[0].max, Duration::from_millis(5));
+        assert_eq!(report.profiles[1].feature, "volatility");
+        assert!(report.to_string().contains("1. rsi calls=2"));
+    }
+}
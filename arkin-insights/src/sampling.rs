@@ -0,0 +1,226 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
+
+use crate::config::{ImbalanceBarConfig, SamplingConfig};
+
+/// Bar of one instrument that is still collecting trades.
+#[derive(Debug, Clone)]
+struct OpenBar {
+    start: OffsetDateTime,
+    last: OffsetDateTime,
+    open: Price,
+    high: Price,
+    low: Price,
+    close: Price,
+    volume: Quantity,
+    notional_volume: Notional,
+    trade_count: u64,
+    /// Buy trades minus sell trades
+    imbalance: f64,
+}
+
+impl OpenBar {
+    fn new(trade: &Trade) -> Self {
+        Self {
+            start: trade.event_time,
+            last: trade.event_time,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: Decimal::ZERO,
+            notional_volume: Decimal::ZERO,
+            trade_count: 0,
+            imbalance: 0.,
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.last = trade.event_time;
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+        self.notional_volume += trade.price * trade.quantity;
+        self.trade_count += 1;
+        self.imbalance += match trade.side {
+            MarketSide::Buy => 1.,
+            MarketSide::Sell => -1.,
+        };
+    }
+
+    fn to_bar(&self, instrument: Arc<Instrument>) -> Bar {
+        Bar::builder()
+            .event_time(self.start)
+            .instrument(instrument)
+            .frequency((self.last - self.start).unsigned_abs())
+            .open(self.open)
+            .high(self.high)
+            .low(self.low)
+            .close(self.close)
+            .volume(self.volume)
+            .notional_volume(self.notional_volume)
+            .trade_count(self.trade_count)
+            .build()
+    }
+}
+
+/// Open bar of an instrument and the expectations of its imbalance bars.
+#[derive(Debug, Default)]
+struct SamplerState {
+    bar: Option<OpenBar>,
+    /// Moving average of the trades per bar, unknown until the first bar closed
+    expected_trades: Option<f64>,
+    /// Moving average of the imbalance per trade
+    expected_imbalance: f64,
+}
+
+impl SamplerState {
+    fn imbalance_closed(&mut self, bar: &OpenBar, config: &ImbalanceBarConfig) -> bool {
+        let trades = bar.trade_count as f64;
+        let closed = match self.expected_trades {
+            Some(expected) => bar.imbalance.abs() >= (expected * self.expected_imbalance.abs()).max(1.),
+            None => trades >= config.expected_trades,
+        };
+        if closed {
+            let imbalance = bar.imbalance / trades;
+            (self.expected_trades, self.expected_imbalance) = match self.expected_trades {
+                Some(expected) => (
+                    Some(config.alpha * trades + (1. - config.alpha) * expected),
+                    config.alpha * imbalance + (1. - config.alpha) * self.expected_imbalance,
+                ),
+                None => (Some(trades), imbalance),
+            };
+        }
+        closed
+    }
+}
+
+/// Samples the trades into bars of equal information instead of equal time. Volume bars close once a quantity
+/// traded, dollar bars once a notional traded, the trade closing a bar is part of it even if it trades past the
+/// threshold. Imbalance bars close once the buy trades minus the sell trades outgrow the imbalance expected from the
+/// previous bars.
+#[derive(Debug)]
+pub struct BarSampler {
+    sampling: SamplingConfig,
+    states: Mutex<HashMap<Arc<Instrument>, SamplerState>>,
+}
+
+impl BarSampler {
+    /// None for time sampling, the features are computed on the interval ticks then.
+    pub fn from_config(config: &SamplingConfig) -> Option<Self> {
+        match config {
+            SamplingConfig::Time => None,
+            sampling => Some(Self {
+                sampling: sampling.clone(),
+                states: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Add a trade to the open bar of its instrument, returns the bar if the trade closed it.
+    pub fn update(&self, trade: &Trade) -> Option<Bar> {
+        let mut states = self.states.lock();
+        let state = states.entry(trade.instrument.clone()).or_default();
+        let mut bar = state.bar.take().unwrap_or_else(|| OpenBar::new(trade));
+        bar.add(trade);
+
+        let closed = match &self.sampling {
+            SamplingConfig::Time => false,
+            SamplingConfig::Volume(c) => bar.volume >= c.threshold,
+            SamplingConfig::Dollar(c) => bar.notional_volume >= c.threshold,
+            SamplingConfig::Imbalance(c) => state.imbalance_closed(&bar, c),
+        };
+        match closed {
+            true => Some(bar.to_bar(trade.instrument.clone())),
+            false => {
+                state.bar = Some(bar);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use crate::config::{DollarBarConfig, VolumeBarConfig};
+
+    use super::*;
+
+    fn trade(second: i64, side: MarketSide, price: Price, quantity: Quantity) -> Trade {
+        Trade::new(
+            datetime!(2025-01-01 00:00 UTC) + time::Duration::seconds(second),
+            test_inst_binance_btc_usdt_perp(),
+            second as u64,
+            side,
+            price,
+            quantity,
+        )
+    }
+
+    #[test]
+    fn test_volume_bars() {
+        let sampler = BarSampler::from_config(&SamplingConfig::Volume(VolumeBarConfig { threshold: dec!(2) })).unwrap();
+        assert!(sampler.update(&trade(0, MarketSide::Buy, dec!(100), dec!(1))).is_none());
+        let bar = sampler.update(&trade(5, MarketSide::Sell, dec!(102), dec!(1.5))).unwrap();
+        assert_eq!(bar.open, dec!(100));
+        assert_eq!(bar.high, dec!(102));
+        assert_eq!(bar.close, dec!(102));
+        assert_eq!(bar.volume, dec!(2.5));
+        assert_eq!(bar.trade_count, 2);
+        assert_eq!(bar.end_time(), datetime!(2025-01-01 00:00:05 UTC));
+
+        // The next bar starts with the next trade
+        let bar = sampler.update(&trade(9, MarketSide::Buy, dec!(99), dec!(3))).unwrap();
+        assert_eq!(bar.event_time, datetime!(2025-01-01 00:00:09 UTC));
+        assert_eq!(bar.open, dec!(99));
+        assert_eq!(bar.volume, dec!(3));
+    }
+
+    #[test]
+    fn test_dollar_bars() {
+        let config = SamplingConfig::Dollar(DollarBarConfig {
+            threshold: dec!(1000),
+        });
+        let sampler = BarSampler::from_config(&config).unwrap();
+        assert!(sampler.update(&trade(0, MarketSide::Buy, dec!(100), dec!(5))).is_none());
+        assert!(sampler.update(&trade(1, MarketSide::Buy, dec!(100), dec!(4))).is_none());
+        let bar = sampler.update(&trade(2, MarketSide::Buy, dec!(200), dec!(1))).unwrap();
+        assert_eq!(bar.notional_volume, dec!(1100));
+        assert_eq!(bar.vwap(), dec!(110));
+        assert!(BarSampler::from_config(&SamplingConfig::Time).is_none());
+    }
+
+    #[test]
+    fn test_imbalance_bars() {
+        let config = SamplingConfig::Imbalance(ImbalanceBarConfig {
+            expected_trades: 4.,
+            alpha: 0.5,
+        });
+        let sampler = BarSampler::from_config(&config).unwrap();
+
+        // The first bar closes after the expected trades, 3 buys and 1 sell expect an imbalance of 0.5 per trade
+        let sides = [MarketSide::Buy, MarketSide::Buy, MarketSide::Sell, MarketSide::Buy];
+        let bars = sides
+            .iter()
+            .enumerate()
+            .filter_map(|(i, side)| sampler.update(&trade(i as i64, *side, dec!(100), dec!(1))))
+            .collect::<Vec<_>>();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].trade_count, 4);
+
+        // Balanced flow keeps the bar open, a one-sided run of 4 * 0.5 trades closes it
+        assert!(sampler.update(&trade(4, MarketSide::Buy, dec!(100), dec!(1))).is_none());
+        assert!(sampler.update(&trade(5, MarketSide::Sell, dec!(100), dec!(1))).is_none());
+        assert!(sampler.update(&trade(6, MarketSide::Sell, dec!(100), dec!(1))).is_none());
+        assert!(sampler.update(&trade(7, MarketSide::Sell, dec!(100), dec!(1))).is_some());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
@@ -7,16 +8,22 @@ use anyhow::Result;
 use arkin_core::prelude::*;
 use arkin_persistence::prelude::*;
 use async_trait::async_trait;
+use rust_decimal::Decimal;
 use time::OffsetDateTime;
 use tokio::select;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info};
+use tracing::{debug, info, warn};
 
 use crate::errors::InsightsError;
 use crate::factory::FeatureFactory;
 use crate::pipeline::PipelineGraph;
+use crate::profiler::{FeatureProfileReport, FeatureProfiler};
+use crate::sampling::BarSampler;
 use crate::traits::Insights;
-use crate::{config::InsightsServiceConfig, state::InsightsState};
+use crate::{
+    config::{InsightsServiceConfig, WarmStartConfig},
+    state::InsightsState,
+};
 
 #[derive(Debug)]
 pub struct InsightsService {
@@ -26,6 +33,12 @@ pub struct InsightsService {
     pipeline: Arc<Pipeline>,
     graph: PipelineGraph,
     state_lookback: Duration,
+    frequency: Duration,
+    /// Computes the features on the close of the sampled bars instead of the interval ticks
+    sampler: Option<BarSampler>,
+    warm_start: Option<WarmStartConfig>,
+    profiling: Option<(Arc<FeatureProfiler>, usize)>,
+    watchdog: Arc<Watchdog>,
 }
 
 impl InsightsService {
@@ -45,17 +58,79 @@ impl InsightsService {
             pipeline.clone(),
             state.clone(),
             config.scale_periods,
+            Some(pubsub.clone()),
         );
 
+        let mut graph = PipelineGraph::from_config(features);
+        let profiling = config
+            .profiling
+            .as_ref()
+            .map(|c| (Arc::new(FeatureProfiler::default()), c.top_n));
+        if let Some((profiler, _)) = &profiling {
+            graph = graph.with_profiler(profiler.clone());
+        }
+
         Self {
             state,
             pubsub,
             persistence_service,
             pipeline,
-            graph: PipelineGraph::from_config(features),
+            graph,
             state_lookback: Duration::from_secs(config.state_lookback),
+            frequency: Duration::from_secs(config.frequency_secs),
+            sampler: BarSampler::from_config(&config.pipeline.sampling),
+            warm_start: config.warm_start.clone(),
+            profiling,
+            watchdog: Arc::new(Watchdog::default()),
         }
     }
+
+    /// Report the handler durations to the watchdog of the engine.
+    pub fn with_watchdog(mut self, watchdog: Arc<Watchdog>) -> Self {
+        self.watchdog = watchdog;
+        self
+    }
+}
+
+impl InsightsService {
+    /// Report of the slowest features since the start, only available when profiling is configured.
+    pub fn profile_report(&self) -> Option<FeatureProfileReport> {
+        self.profiling.as_ref().map(|(profiler, top_n)| profiler.report(*top_n))
+    }
+
+    /// Export the current feature state for a simulation checkpoint.
+    pub fn checkpoint(&self) -> Vec<FeatureState> {
+        self.state.snapshot()
+    }
+
+    /// Restore the feature state from a simulation checkpoint.
+    pub fn restore(&self, features: &[FeatureState], instruments: &[Arc<Instrument>]) {
+        self.state.restore(features, instruments);
+        info!("Restored {} feature series from checkpoint", features.len());
+    }
+}
+
+/// Compare the recomputed insights against the persisted ones, every persisted insight has to be recomputed within
+/// the tolerance. Returns a description of every mismatch.
+fn verify_insights(persisted: &[Arc<Insight>], recomputed: &[Arc<Insight>], tolerance: Decimal) -> Vec<String> {
+    let recomputed = recomputed
+        .iter()
+        .map(|i| ((i.instrument.clone(), i.feature_id.clone()), i.value))
+        .collect::<HashMap<_, _>>();
+    persisted
+        .iter()
+        .filter_map(|insight| {
+            let symbol = insight.instrument.as_ref().map(|i| i.symbol.as_str()).unwrap_or("global");
+            match recomputed.get(&(insight.instrument.clone(), insight.feature_id.clone())) {
+                Some(value) if (value - insight.value).abs() <= tolerance => None,
+                Some(value) => Some(format!(
+                    "{} on {} recomputed as {} but persisted as {}",
+                    insight.feature_id, symbol, value, insight.value
+                )),
+                None => Some(format!("{} on {} was not recomputed", insight.feature_id, symbol)),
+            }
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -64,26 +139,52 @@ impl Insights for InsightsService {
         info!("Starting insights service...");
         let mut interval_tick = self.pubsub.subscribe::<IntervalTick>();
         let mut trades = self.pubsub.subscribe::<Trade>();
+        let mut metrics = self.pubsub.subscribe::<Metric>();
+        let mut stream_ended = self.pubsub.subscribe::<StreamEnded>();
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
         loop {
             select! {
                 Ok(time_tick) = interval_tick.recv() => {
-                    debug!("InsightsService received interval tick: {}", time_tick.event_time);
-                    if let Err(e) = self.process(time_tick.event_time, &time_tick.instruments, true).await {
-                        error!("Error processing interval tick: {}", e);
+                    if self.sampler.is_some() {
+                        continue;
                     }
+                    debug!("InsightsService received interval tick: {}", time_tick.event_time);
+                    let _guard = self.watchdog.track("insights", "interval_tick");
+                    skip_failed_event("insights", self.process(time_tick.event_time, &time_tick.instruments, true).await)?;
                 }
                 Ok(trade) = trades.recv() => {
                     debug!("InsightsService received trade: {}", trade.event_time);
+                    let _guard = self.watchdog.track("insights", "trade");
                     let insights = trade.as_ref().clone().to_insights(self.pipeline.clone());
-                    if let Err(e) = self.insert_batch(insights.as_slice()).await {
-                        error!("Error inserting trade: {}", e);
+                    skip_failed_event("insights", self.insert_batch(insights.as_slice()).await)?;
+                    if let Some(bar) = self.sampler.as_ref().and_then(|s| s.update(&trade)) {
+                        debug!("InsightsService sampled bar: {}", bar);
+                        let instruments = [bar.instrument.clone()];
+                        let end_time = bar.end_time();
+                        skip_failed_event("insights", self.insert_batch(&bar.to_insights(self.pipeline.clone())).await)?;
+                        skip_failed_event("insights", self.process(end_time, &instruments, true).await)?;
                     }
                 }
+                Ok(metric) = metrics.recv() => {
+                    debug!("InsightsService received metric: {}", metric);
+                    let insights = metric.as_ref().clone().to_insights(self.pipeline.clone());
+                    skip_failed_event("insights", self.insert_batch(insights.as_slice()).await)?;
+                }
+                Ok(ended) = stream_ended.recv() => {
+                    info!("Stream {} on {} ended at {}", ended.channel, ended.venue, ended.event_time);
+                }
+                Ok(finished) = simulation_finished.recv() => {
+                    info!("Simulation finished at {}, stopping insights service", finished.event_time);
+                    break;
+                }
                 _ = shutdown.cancelled() => {
                     break;
                 }
             }
         }
+        if let Some(report) = self.profile_report() {
+            info!("{}", report);
+        }
         Ok(())
     }
 
@@ -113,6 +214,88 @@ impl Insights for InsightsService {
         Ok(())
     }
 
+    async fn load_bars(
+        &self,
+        event_time: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+        lookback: Duration,
+        frequency: Duration,
+    ) -> Result<(), InsightsError> {
+        let start = event_time - lookback;
+        info!("Loading {:?} bars from {} to {}", frequency, start, event_time);
+
+        let bars = self
+            .persistence_service
+            .trade_store
+            .read_bars(instruments, start, event_time, frequency)
+            .await?;
+
+        // Skip the last bar if it is still open at event_time
+        let insights = bars
+            .into_iter()
+            .filter(|b| b.end_time() <= event_time)
+            .flat_map(|b| b.as_ref().clone().to_insights(self.pipeline.clone()))
+            .collect::<Vec<_>>();
+        debug!("Adding {} bar insights to state", insights.len());
+        self.state.insert_batch(insights.as_slice());
+        Ok(())
+    }
+
+    async fn warm_start(
+        &self,
+        event_time: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<Option<OffsetDateTime>, InsightsError> {
+        let Some(config) = &self.warm_start else {
+            return Ok(None);
+        };
+        let lookback = self.frequency * config.periods;
+        let start = event_time - lookback;
+        info!(
+            "Warm starting insights from persisted insights from {} to {}",
+            start, event_time
+        );
+
+        let insights = self
+            .persistence_service
+            .insights_store
+            .read_range(&self.pipeline, instruments, start, event_time)
+            .await?;
+        let Some(last_time) = insights.last().map(|i| i.event_time) else {
+            warn!(
+                "No persisted insights from {} to {}, falling back to a replay",
+                start, event_time
+            );
+            return Ok(None);
+        };
+
+        // The last period is recomputed from the trades on top of the history to verify the state
+        let (persisted, history): (Vec<_>, Vec<_>) = insights.into_iter().partition(|i| i.event_time == last_time);
+        self.state.insert_batch(history.as_slice());
+        self.load(last_time, instruments, lookback).await?;
+        let recomputed = self.graph.calculate(instruments, last_time);
+
+        let mismatches = verify_insights(&persisted, &recomputed, config.tolerance);
+        if !mismatches.is_empty() {
+            warn!(
+                "Warm start failed verification at {} with {} of {} insights off: {}",
+                last_time,
+                mismatches.len(),
+                persisted.len(),
+                mismatches.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
+            );
+            self.state.clear();
+            return Ok(None);
+        }
+        info!(
+            "Warm started insights with {} persisted insights, verified {} at {}",
+            history.len(),
+            persisted.len(),
+            last_time
+        );
+        Ok(Some(last_time))
+    }
+
     async fn insert(&self, insight: Arc<Insight>) -> Result<(), InsightsError> {
         self.state.insert(insight);
         Ok(())
@@ -157,3 +340,41 @@ impl Insights for InsightsService {
         Ok(insights)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn insight(feature_id: &str, value: Decimal) -> Arc<Insight> {
+        let insight = Insight::builder()
+            .event_time(datetime!(2025-01-01 00:00 UTC))
+            .pipeline(test_pipeline())
+            .instrument(Some(test_inst_binance_btc_usdt_perp()))
+            .feature_id(FeatureId::new(feature_id.into()))
+            .value(value)
+            .build();
+        Arc::new(insight)
+    }
+
+    #[test]
+    fn test_verify_insights() {
+        let persisted = vec![insight("close", dec!(100)), insight("rsi", dec!(55.5)), insight("ma", dec!(99))];
+        let recomputed = vec![
+            insight("close", dec!(100)),
+            insight("rsi", dec!(55.50001)),
+            insight("ma", dec!(98)),
+            insight("volume", dec!(10)),
+        ];
+        assert!(verify_insights(&persisted[..2], &recomputed, dec!(0.0001)).is_empty());
+
+        let mismatches = verify_insights(&persisted, &recomputed, dec!(0.0001));
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("ma on perp-btc-usdt@binance recomputed as 98 but persisted as 99"));
+
+        let mismatches = verify_insights(&[insight("adx", dec!(20))], &recomputed, dec!(0.0001));
+        assert!(mismatches[0].contains("was not recomputed"));
+    }
+}
@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rayon::prelude::*;
+use rust_decimal::prelude::*;
+use time::{Duration, OffsetDateTime, Time};
+use tracing::{debug, warn};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{state::InsightsState, Computation};
+
+/// Point the anchored features accumulate from.
+#[derive(Debug, Clone)]
+pub enum Anchor {
+    /// Open of the current session, sessions of `length` start `open` after midnight UTC
+    Session {
+        open: Duration,
+        length: Duration,
+    },
+    Timestamp(OffsetDateTime),
+    /// Last time the marker feature was not zero
+    Feature(FeatureId),
+}
+
+impl Anchor {
+    fn start(
+        &self,
+        state: &InsightsState,
+        instrument: &Arc<Instrument>,
+        event_time: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        match self {
+            Anchor::Session { open, length } => {
+                let mut first = event_time.replace_time(Time::MIDNIGHT) + *open;
+                if first > event_time {
+                    first -= Duration::DAY;
+                }
+                let sessions = (event_time - first).whole_seconds() / length.whole_seconds().max(1);
+                Some(first + *length * sessions as i32)
+            }
+            Anchor::Timestamp(time) => (*time <= event_time).then_some(*time),
+            Anchor::Feature(input) => state.last_nonzero(Some(instrument.clone()), input.clone(), event_time),
+        }
+    }
+}
+
+/// Volume and time weighted average price since an anchor, like the open of the session, a fixed time or the last
+/// swing point. The time weighted price holds every price until the next one or the event time.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct AnchoredVWAPFeature {
+    pipeline: Arc<Pipeline>,
+    insight_state: Arc<InsightsState>,
+    input_price: FeatureId,
+    input_quantity: FeatureId,
+    anchor: Anchor,
+    output_vwap: FeatureId,
+    output_twap: FeatureId,
+    persist: bool,
+}
+
+impl AnchoredVWAPFeature {
+    fn insight(
+        &self,
+        instrument: &Arc<Instrument>,
+        event_time: OffsetDateTime,
+        feature_id: &FeatureId,
+        value: Decimal,
+    ) -> Arc<Insight> {
+        Insight::builder()
+            .event_time(event_time)
+            .pipeline(self.pipeline.clone())
+            .instrument(Some(instrument.clone()))
+            .feature_id(feature_id.clone())
+            .value(value)
+            .persist(self.persist)
+            .build()
+            .into()
+    }
+}
+
+impl Computation for AnchoredVWAPFeature {
+    fn inputs(&self) -> Vec<FeatureId> {
+        let mut inputs = vec![self.input_price.clone(), self.input_quantity.clone()];
+        if let Anchor::Feature(input) = &self.anchor {
+            inputs.push(input.clone());
+        }
+        inputs
+    }
+
+    fn outputs(&self) -> Vec<FeatureId> {
+        vec![self.output_vwap.clone(), self.output_twap.clone()]
+    }
+
+    fn calculate(&self, instruments: &[Arc<Instrument>], event_time: OffsetDateTime) -> Result<Vec<Arc<Insight>>> {
+        debug!("Calculating anchored VWAP");
+
+        let insights = instruments
+            .par_iter()
+            .filter_map(|instrument| {
+                let start = self.anchor.start(&self.insight_state, instrument, event_time)?;
+                let prices =
+                    self.insight_state
+                        .range(Some(instrument.clone()), self.input_price.clone(), start, event_time);
+                let quantities =
+                    self.insight_state
+                        .range(Some(instrument.clone()), self.input_quantity.clone(), start, event_time);
+                if prices.is_empty() || prices.len() != quantities.len() {
+                    warn!("Not enough data for anchored VWAP of {} since {}", instrument, start);
+                    return None;
+                }
+
+                // Sold quantities are negative
+                let (notional, volume) = prices
+                    .iter()
+                    .zip(quantities.iter())
+                    .fold((Decimal::ZERO, Decimal::ZERO), |(notional, volume), ((_, p), (_, q))| {
+                        (notional + p * q.abs(), volume + q.abs())
+                    });
+                let last = prices.last().expect("Should have at least one price").1;
+                let vwap = if volume.is_zero() {
+                    last
+                } else {
+                    notional / volume
+                };
+
+                let ends = prices.iter().skip(1).map(|(t, _)| *t).chain([event_time.unix_timestamp()]);
+                let (weighted, elapsed) = prices.iter().zip(ends).fold(
+                    (Decimal::ZERO, Decimal::ZERO),
+                    |(weighted, elapsed), ((t, p), end)| {
+                        let held = Decimal::from(end - t);
+                        (weighted + p * held, elapsed + held)
+                    },
+                );
+                let twap = if elapsed.is_zero() {
+                    last
+                } else {
+                    weighted / elapsed
+                };
+
+                Some(vec![
+                    self.insight(instrument, event_time, &self.output_vwap, vwap),
+                    self.insight(instrument, event_time, &self.output_twap, twap),
+                ])
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        self.insight_state.insert_batch(&insights);
+        Ok(insights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn feature(state: Arc<InsightsState>, anchor: Anchor) -> AnchoredVWAPFeature {
+        AnchoredVWAPFeature::builder()
+            .pipeline(test_pipeline())
+            .insight_state(state)
+            .input_price(Arc::new("trade_price".to_string()))
+            .input_quantity(Arc::new("trade_quantity".to_string()))
+            .anchor(anchor)
+            .output_vwap(Arc::new("avwap".to_string()))
+            .output_twap(Arc::new("atwap".to_string()))
+            .persist(false)
+            .build()
+    }
+
+    fn insight(feature_id: &str, event_time: OffsetDateTime, value: Decimal) -> Arc<Insight> {
+        Insight::builder()
+            .event_time(event_time)
+            .pipeline(test_pipeline())
+            .instrument(Some(test_inst_binance_btc_usdt_perp()))
+            .feature_id(Arc::new(feature_id.to_string()))
+            .value(value)
+            .build()
+            .into()
+    }
+
+    fn trades(state: &InsightsState, trades: &[(OffsetDateTime, Decimal, Decimal)]) {
+        for (event_time, price, quantity) in trades {
+            state.insert_batch(&[
+                insight("trade_price", *event_time, *price),
+                insight("trade_quantity", *event_time, *quantity),
+            ]);
+        }
+    }
+
+    fn value(insights: &[Arc<Insight>], feature_id: &str) -> Decimal {
+        insights.iter().find(|i| i.feature_id.as_str() == feature_id).unwrap().value
+    }
+
+    #[test]
+    fn test_session_anchor() {
+        let state = Arc::new(InsightsState::default());
+        // The trade before the 08:00 open belongs to the previous session
+        trades(
+            &state,
+            &[
+                (datetime!(2025-01-01 07:59 UTC), dec!(50), dec!(10)),
+                (datetime!(2025-01-01 08:00 UTC), dec!(100), dec!(1)),
+                (datetime!(2025-01-01 08:30 UTC), dec!(110), dec!(-3)),
+            ],
+        );
+        let anchor = Anchor::Session {
+            open: Duration::hours(8),
+            length: Duration::DAY,
+        };
+        let feature = feature(state, anchor);
+        let insights = feature
+            .calculate(&[test_inst_binance_btc_usdt_perp()], datetime!(2025-01-01 09:00 UTC))
+            .unwrap();
+        assert_eq!(value(&insights, "avwap"), dec!(107.5));
+        assert_eq!(value(&insights, "atwap"), dec!(105));
+    }
+
+    #[test]
+    fn test_timestamp_and_feature_anchors() {
+        let state = Arc::new(InsightsState::default());
+        trades(
+            &state,
+            &[
+                (datetime!(2025-01-01 10:00 UTC), dec!(100), dec!(1)),
+                (datetime!(2025-01-01 11:00 UTC), dec!(120), dec!(1)),
+                (datetime!(2025-01-01 12:00 UTC), dec!(130), dec!(3)),
+            ],
+        );
+        let instruments = [test_inst_binance_btc_usdt_perp()];
+        let event_time = datetime!(2025-01-01 12:00 UTC);
+
+        let fixed = feature(state.clone(), Anchor::Timestamp(datetime!(2025-01-01 11:00 UTC)));
+        assert_eq!(value(&fixed.calculate(&instruments, event_time).unwrap(), "avwap"), dec!(127.5));
+        let later = feature(state.clone(), Anchor::Timestamp(datetime!(2025-01-02 00:00 UTC)));
+        assert!(later.calculate(&instruments, event_time).unwrap().is_empty());
+
+        // Anchored at the last swing, zeros in between don't move the anchor
+        state.insert_batch(&[
+            insight("swing", datetime!(2025-01-01 10:00 UTC), dec!(1)),
+            insight("swing", datetime!(2025-01-01 11:00 UTC), Decimal::ZERO),
+        ]);
+        let swing = feature(state, Anchor::Feature(Arc::new("swing".to_string())));
+        assert_eq!(value(&swing.calculate(&instruments, event_time).unwrap(), "avwap"), dec!(122));
+    }
+}
@@ -1,3 +1,4 @@
+mod anchored_vwap;
 mod log_return;
 mod ohlcv;
 mod signal_strength;
@@ -5,6 +6,7 @@ mod std_dev;
 mod sum;
 mod time;
 
+pub use anchored_vwap::{Anchor, AnchoredVWAPFeature};
 pub use log_return::LogReturnFeature;
 pub use ohlcv::OHLCVFeature;
 pub use signal_strength::SignalStrengthFeature;
@@ -11,7 +11,7 @@ use rust_decimal::prelude::*;
 use time::OffsetDateTime;
 
 use arkin_core::prelude::*;
-use tracing::debug;
+use tracing::{debug, warn};
 use typed_builder::TypedBuilder;
 use yata::core::Candle;
 
@@ -51,6 +51,46 @@ impl InsightsState {
         debug!("Remove from insight state took {:?}", start.elapsed());
     }
 
+    pub fn clear(&self) {
+        self.features.clear();
+    }
+
+    /// Export all feature series so they can be written to a checkpoint.
+    pub fn snapshot(&self) -> Vec<FeatureState> {
+        self.features
+            .iter()
+            .map(|entry| {
+                let (instrument, feature_id) = entry.key();
+                FeatureState {
+                    instrument_id: instrument.as_ref().map(|i| i.id),
+                    feature_id: feature_id.to_string(),
+                    values: entry.value().iter().map(|(k, v)| (*k, *v)).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Restore feature series from a checkpoint. Series for unknown instruments are skipped.
+    pub fn restore(&self, features: &[FeatureState], instruments: &[Arc<Instrument>]) {
+        let start = Instant::now();
+        for feature in features {
+            let instrument = match feature.instrument_id {
+                Some(id) => match instruments.iter().find(|i| i.id == id) {
+                    Some(i) => Some(i.clone()),
+                    None => {
+                        warn!("Skipping feature {} for unknown instrument {}", feature.feature_id, id);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let key = (instrument, FeatureId::new(feature.feature_id.clone()));
+            let mut entry = self.features.entry(key).or_default();
+            entry.extend(feature.values.iter().cloned());
+        }
+        debug!("Restore insight state took {:?}", start.elapsed());
+    }
+
     pub fn last_candle(&self, instrument: Arc<Instrument>, timestamp: OffsetDateTime) -> Option<Candle> {
         let start = Instant::now();
         let open = self
@@ -114,6 +154,35 @@ impl InsightsState {
         }
     }
 
+    /// Values with their unix timestamp from start up to and including timestamp.
+    pub fn range(
+        &self,
+        instrument: Option<Arc<Instrument>>,
+        feature_id: FeatureId,
+        start: OffsetDateTime,
+        timestamp: OffsetDateTime,
+    ) -> Vec<(i64, Decimal)> {
+        if let Some(tree) = self.features.get(&(instrument, feature_id)) {
+            tree.range(start.unix_timestamp()..=timestamp.unix_timestamp())
+                .map(|(k, v)| (*k, *v))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Time of the last value that is not zero, like the last mark of a swing point feature.
+    pub fn last_nonzero(
+        &self,
+        instrument: Option<Arc<Instrument>>,
+        feature_id: FeatureId,
+        timestamp: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        let tree = self.features.get(&(instrument, feature_id))?;
+        let (time, _) = tree.range(..=timestamp.unix_timestamp()).rev().find(|(_, v)| !v.is_zero())?;
+        OffsetDateTime::from_unix_timestamp(*time).ok()
+    }
+
     pub fn periods(
         &self,
         instrument: Option<Arc<Instrument>>,
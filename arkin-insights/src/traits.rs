@@ -26,6 +26,24 @@ pub trait Insights: std::fmt::Debug + Send + Sync {
         lookback: Duration,
     ) -> Result<(), InsightsError>;
 
+    /// Warm up the state from pre-aggregated bars instead of replaying raw trades
+    async fn load_bars(
+        &self,
+        event_time: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+        lookback: Duration,
+        frequency: Duration,
+    ) -> Result<(), InsightsError>;
+
+    /// Initialize the state from the persisted insights of the last periods before event_time instead of replaying
+    /// the trades. Returns the time of the last period if the recomputed insights match the persisted ones, otherwise
+    /// the state is left empty and the caller has to replay.
+    async fn warm_start(
+        &self,
+        event_time: OffsetDateTime,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<Option<OffsetDateTime>, InsightsError>;
+
     async fn process(
         &self,
         event_time: OffsetDateTime,
@@ -0,0 +1,329 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    config::{FeatureConfig, FeatureValidationCaseConfig, FeatureValidationConfig},
+    factory::FeatureFactory,
+    state::InsightsState,
+    InsightsError,
+};
+
+/// Feeds reference fixtures through the features built by the factory and compares the outputs against the values
+/// of a reference implementation (e.g. ta-lib or pandas).
+///
+/// A fixture is a CSV file with a `timestamp` column in unix seconds followed by one column per feature id. Columns
+/// matching an output of the feature hold the reference values, all other columns are inserted as inputs. Empty
+/// cells mean no value for that row and lines starting with `#` are ignored.
+#[derive(Debug, TypedBuilder)]
+pub struct FeatureValidator {
+    fixtures_dir: PathBuf,
+    cases: Vec<FeatureValidationCaseConfig>,
+    #[builder(default)]
+    require_full_coverage: bool,
+    #[builder(default = test_inst_binance_btc_usdt_perp())]
+    instrument: Arc<Instrument>,
+}
+
+impl FeatureValidator {
+    pub fn from_config(config: &FeatureValidationConfig) -> Self {
+        Self::builder()
+            .fixtures_dir(PathBuf::from(&config.fixtures_dir))
+            .cases(config.cases.clone())
+            .require_full_coverage(config.require_full_coverage)
+            .build()
+    }
+
+    /// Feature kinds from the factory that have no validation case.
+    pub fn uncovered(&self) -> Vec<&'static str> {
+        FeatureConfig::KINDS
+            .into_iter()
+            .filter(|kind| !self.cases.iter().any(|c| c.feature.kind() == *kind))
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<FeatureValidationSummary, InsightsError> {
+        let reports = self
+            .cases
+            .iter()
+            .map(|case| self.validate_case(case))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(FeatureValidationSummary {
+            reports,
+            uncovered: self.uncovered(),
+            require_full_coverage: self.require_full_coverage,
+        })
+    }
+
+    pub fn validate_case(&self, case: &FeatureValidationCaseConfig) -> Result<FeatureValidationReport, InsightsError> {
+        info!("Validating feature {} ({})", case.name, case.feature.kind());
+        let fixture = Fixture::load(&self.fixtures_dir.join(&case.fixture))?;
+
+        let now = OffsetDateTime::now_utc();
+        let pipeline = Arc::new(
+            Pipeline::builder()
+                .name(format!("validation_{}", case.name))
+                .description("Feature validation against reference fixtures".into())
+                .created_at(now)
+                .updated_at(now)
+                .build(),
+        );
+        let state = Arc::new(InsightsState::default());
+        let feature =
+            FeatureFactory::from_config(std::slice::from_ref(&case.feature), pipeline.clone(), state.clone(), 1, None)
+                .pop()
+                .expect("Factory should build exactly one feature");
+
+        let outputs = feature.outputs();
+        let missing = outputs
+            .iter()
+            .filter(|o| !fixture.columns.contains(o))
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(InsightsError::InvalidFixture(
+                case.fixture.clone(),
+                format!("missing reference columns {}", missing.join(", ")),
+            ));
+        }
+
+        let mut report = FeatureValidationReport::new(case.name.clone(), case.feature.kind());
+        let instruments = [self.instrument.clone()];
+        for (idx, (event_time, values)) in fixture.rows.iter().enumerate() {
+            let inputs = fixture
+                .columns
+                .iter()
+                .zip(values)
+                .filter(|(id, _)| !outputs.contains(id))
+                .filter_map(|(id, value)| {
+                    let insight = Insight::builder()
+                        .event_time(*event_time)
+                        .pipeline(pipeline.clone())
+                        .instrument(Some(self.instrument.clone()))
+                        .feature_id(id.clone())
+                        .value((*value)?)
+                        .build();
+                    Some(Arc::new(insight))
+                })
+                .collect::<Vec<_>>();
+            state.insert_batch(&inputs);
+
+            let computed = feature.calculate(&instruments, *event_time)?;
+            if idx < case.warmup {
+                continue;
+            }
+
+            for (id, expected) in fixture.columns.iter().zip(values) {
+                let Some(expected) = expected else {
+                    continue;
+                };
+                if !outputs.contains(id) {
+                    continue;
+                }
+                let actual = computed.iter().find(|i| i.feature_id == *id).map(|i| i.value);
+                report.compare(
+                    *event_time,
+                    id.clone(),
+                    *expected,
+                    actual,
+                    case.tolerance,
+                    case.relative_tolerance,
+                );
+            }
+        }
+        debug!("Validated feature {}: {}", case.name, report);
+        Ok(report)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureMismatch {
+    pub event_time: OffsetDateTime,
+    pub feature_id: FeatureId,
+    pub expected: Decimal,
+    pub actual: Option<Decimal>,
+}
+
+impl fmt::Display for FeatureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.actual {
+            Some(actual) => write!(
+                f,
+                "{} {} expected={} actual={} diff={}",
+                self.event_time,
+                self.feature_id,
+                self.expected,
+                actual,
+                (actual - self.expected).abs()
+            ),
+            None => write!(
+                f,
+                "{} {} expected={} actual=none",
+                self.event_time, self.feature_id, self.expected
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureValidationReport {
+    pub name: String,
+    pub kind: &'static str,
+    pub compared: usize,
+    pub max_abs_error: Decimal,
+    pub max_rel_error: Decimal,
+    pub mismatches: Vec<FeatureMismatch>,
+}
+
+impl FeatureValidationReport {
+    fn new(name: String, kind: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            compared: 0,
+            max_abs_error: Decimal::ZERO,
+            max_rel_error: Decimal::ZERO,
+            mismatches: Vec::new(),
+        }
+    }
+
+    fn compare(
+        &mut self,
+        event_time: OffsetDateTime,
+        feature_id: FeatureId,
+        expected: Decimal,
+        actual: Option<Decimal>,
+        tolerance: Decimal,
+        relative_tolerance: Decimal,
+    ) {
+        self.compared += 1;
+        let Some(value) = actual else {
+            self.mismatches.push(FeatureMismatch {
+                event_time,
+                feature_id,
+                expected,
+                actual,
+            });
+            return;
+        };
+
+        let abs_error = (value - expected).abs();
+        let rel_error = match expected.is_zero() {
+            true => Decimal::ZERO,
+            false => abs_error / expected.abs(),
+        };
+        self.max_abs_error = self.max_abs_error.max(abs_error);
+        self.max_rel_error = self.max_rel_error.max(rel_error);
+
+        // The relative tolerance only applies to non zero references
+        let within_relative = !relative_tolerance.is_zero() && !expected.is_zero() && rel_error <= relative_tolerance;
+        if abs_error > tolerance && !within_relative {
+            self.mismatches.push(FeatureMismatch {
+                event_time,
+                feature_id,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.compared > 0 && self.mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for FeatureValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}) compared={} mismatches={} max_abs_error={} max_rel_error={}",
+            if self.passed() { "PASS" } else { "FAIL" },
+            self.name,
+            self.kind,
+            self.compared,
+            self.mismatches.len(),
+            self.max_abs_error.normalize(),
+            self.max_rel_error.round_dp(12).normalize()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureValidationSummary {
+    pub reports: Vec<FeatureValidationReport>,
+    pub uncovered: Vec<&'static str>,
+    pub require_full_coverage: bool,
+}
+
+impl FeatureValidationSummary {
+    pub fn failed(&self) -> Vec<&FeatureValidationReport> {
+        self.reports.iter().filter(|r| !r.passed()).collect()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed().is_empty() && (!self.require_full_coverage || self.uncovered.is_empty())
+    }
+}
+
+/// Parsed reference fixture, rows are kept in file order.
+struct Fixture {
+    columns: Vec<FeatureId>,
+    rows: Vec<(OffsetDateTime, Vec<Option<Decimal>>)>,
+}
+
+impl Fixture {
+    fn load(path: &Path) -> Result<Self, InsightsError> {
+        let name = path.display().to_string();
+        let content =
+            fs::read_to_string(path).map_err(|e| InsightsError::InvalidFixture(name.clone(), e.to_string()))?;
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty() && !l.starts_with('#'));
+
+        let Some((_, header)) = lines.next() else {
+            return Err(InsightsError::InvalidFixture(name, "empty file".into()));
+        };
+        let mut header = header.split(',').map(|c| c.trim());
+        if header.next() != Some("timestamp") {
+            return Err(InsightsError::InvalidFixture(name, "first column should be timestamp".into()));
+        }
+        let columns = header.map(|c| FeatureId::new(c.to_string())).collect::<Vec<_>>();
+
+        let mut rows = Vec::new();
+        for (line_nr, line) in lines {
+            let invalid =
+                |msg: String| InsightsError::InvalidFixture(name.clone(), format!("line {}: {}", line_nr + 1, msg));
+            let fields = line.split(',').map(|f| f.trim()).collect::<Vec<_>>();
+            if fields.len() != columns.len() + 1 {
+                return Err(invalid(format!("expected {} fields got {}", columns.len() + 1, fields.len())));
+            }
+            let timestamp = fields[0].parse::<i64>().map_err(|e| invalid(e.to_string()))?;
+            let event_time = OffsetDateTime::from_unix_timestamp(timestamp).map_err(|e| invalid(e.to_string()))?;
+            let values = fields[1..]
+                .iter()
+                .map(|f| match f.is_empty() {
+                    true => Ok(None),
+                    false => Decimal::from_str(f)
+                        .or_else(|_| Decimal::from_scientific(f))
+                        .map(Some)
+                        .map_err(|e| invalid(format!("{}: {}", f, e))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push((event_time, values));
+        }
+        Ok(Self { columns, rows })
+    }
+}
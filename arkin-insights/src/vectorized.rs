@@ -0,0 +1,551 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use rayon::prelude::*;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+use arkin_core::prelude::*;
+
+use crate::{
+    config::{FeatureConfig, InsightsServiceConfig, OHLCVConfig},
+    explain::PipelineExplain,
+    factory::FeatureFactory,
+    math,
+    pipeline::PipelineGraph,
+    state::InsightsState,
+    InsightsError,
+};
+
+#[cfg(feature = "polars")]
+mod polars_engine;
+
+/// Values of a feature for one instrument as parallel columns sorted by time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    /// Unix seconds, the insight state keeps one value per second
+    pub timestamps: Vec<i64>,
+    pub values: Vec<f64>,
+}
+
+impl Series {
+    pub fn push(&mut self, timestamp: i64, value: f64) {
+        // A later value in the same second replaces the earlier one, like in the insight state
+        if self.timestamps.last() == Some(&timestamp) {
+            if let Some(last) = self.values.last_mut() {
+                *last = value;
+            }
+            return;
+        }
+        self.timestamps.push(timestamp);
+        self.values.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Number of values at or before the timestamp.
+    pub(crate) fn until(&self, timestamp: i64) -> usize {
+        self.timestamps.partition_point(|t| *t <= timestamp)
+    }
+}
+
+/// Feature series of one instrument.
+pub type Frame = HashMap<FeatureId, Series>;
+
+/// Computes a pipeline over whole historical ranges at once: every feature is evaluated for all ticks column by
+/// column with the kernels of [`math`] instead of tick by tick through the insight state. Meant for backfills, the
+/// streaming engine stays in charge of live. Only features with a vectorized implementation are supported, the
+/// others are rejected when the pipeline is loaded.
+#[derive(Debug, Clone)]
+pub struct VectorizedBackfill {
+    /// Features in the order they are calculated
+    features: Vec<FeatureConfig>,
+    scale_periods: usize,
+    engine: VectorizedEngine,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorizedEngine {
+    /// Plain columns fed to the kernels of the streaming features
+    #[default]
+    Columns,
+    /// Polars data frames, requires the `polars` feature and falls back to the columns when it is not compiled in
+    Polars,
+}
+
+impl VectorizedBackfill {
+    pub const SUPPORTED: [&'static str; 4] = ["ohlcv", "log_return", "std_dev", "sum"];
+
+    pub fn from_config(config: &InsightsServiceConfig) -> Result<Self, InsightsError> {
+        let explain = PipelineExplain::from_config(config, 1)?;
+        let features = explain
+            .features
+            .iter()
+            .map(|f| config.pipeline.features[f.index].clone())
+            .collect::<Vec<_>>();
+        if let Some(feature) = features.iter().find(|f| !Self::SUPPORTED.contains(&f.kind())) {
+            return Err(InsightsError::InvalidPipeline(
+                config.pipeline.name.clone(),
+                format!("{} has no vectorized implementation", feature.kind()),
+            ));
+        }
+        Ok(Self {
+            features,
+            scale_periods: config.scale_periods,
+            engine: VectorizedEngine::default(),
+        })
+    }
+
+    /// Compute the features with the given engine.
+    pub fn with_engine(mut self, engine: VectorizedEngine) -> Self {
+        if engine == VectorizedEngine::Polars && cfg!(not(feature = "polars")) {
+            warn!("Built without the polars feature, falling back to the columns engine");
+            return self;
+        }
+        self.engine = engine;
+        self
+    }
+
+    /// Compute every feature at the ticks from the raw inputs of one instrument.
+    pub fn run(&self, inputs: &Frame, ticks: &[i64]) -> Result<Frame, InsightsError> {
+        match self.engine {
+            VectorizedEngine::Columns => Ok(self.run_columns(inputs, ticks)),
+            #[cfg(feature = "polars")]
+            VectorizedEngine::Polars => polars_engine::run(&self.features, self.scale_periods, inputs, ticks),
+            #[cfg(not(feature = "polars"))]
+            VectorizedEngine::Polars => unreachable!("The polars engine is only selected when it is compiled in"),
+        }
+    }
+
+    fn run_columns(&self, inputs: &Frame, ticks: &[i64]) -> Frame {
+        let mut frame = inputs.clone();
+        for feature in &self.features {
+            let outputs = match feature {
+                FeatureConfig::OHLCV(c) => ohlcv(c, feature.outputs(), &frame, ticks),
+                FeatureConfig::LogReturn(c) => {
+                    let periods = c.periods * self.scale_periods;
+                    let values = periods_kernel(&frame, &c.input, ticks, periods + 1, |window| {
+                        let (first, last) = (window[0], window[window.len() - 1]);
+                        (first != 0.).then(|| (last / first).ln())
+                    });
+                    vec![(c.output.clone(), values)]
+                }
+                FeatureConfig::StdDev(c) => {
+                    let periods = c.periods * self.scale_periods;
+                    let values = periods_kernel(&frame, &c.input, ticks, periods, math::std_dev);
+                    vec![(c.output.clone(), values)]
+                }
+                FeatureConfig::Sum(c) => {
+                    let periods = c.periods * self.scale_periods;
+                    let values = periods_kernel(&frame, &c.input, ticks, periods, |window| Some(math::sum(window)));
+                    vec![(c.output.clone(), values)]
+                }
+                _ => unreachable!("Unsupported features are rejected in from_config"),
+            };
+            frame.extend(outputs);
+        }
+        for input in inputs.keys() {
+            frame.remove(input);
+        }
+        frame
+    }
+
+    /// Compute the pipeline for all instruments in parallel.
+    pub fn run_all(
+        &self,
+        inputs: &HashMap<Arc<Instrument>, Frame>,
+        ticks: &[i64],
+    ) -> Result<HashMap<Arc<Instrument>, Frame>, InsightsError> {
+        inputs
+            .par_iter()
+            .map(|(instrument, frame)| self.run(frame, ticks).map(|outputs| (instrument.clone(), outputs)))
+            .collect()
+    }
+}
+
+/// Turn the given features of the frames into persisted insights. Non finite values, such as a vwap without volume,
+/// have no decimal representation and are skipped.
+pub fn insights_from_frames(
+    frames: &HashMap<Arc<Instrument>, Frame>,
+    pipeline: Arc<Pipeline>,
+    features: &HashSet<FeatureId>,
+) -> Result<Vec<Arc<Insight>>, InsightsError> {
+    let mut insights = Vec::new();
+    for (instrument, frame) in frames {
+        for (feature_id, series) in frame.iter().filter(|(id, _)| features.contains(*id)) {
+            for (ts, value) in series.timestamps.iter().zip(&series.values) {
+                let Some(value) = Decimal::from_f64(*value) else {
+                    continue;
+                };
+                let event_time = OffsetDateTime::from_unix_timestamp(*ts).map_err(anyhow::Error::from)?;
+                let insight = Insight::builder()
+                    .event_time(event_time)
+                    .pipeline(pipeline.clone())
+                    .instrument(Some(instrument.clone()))
+                    .feature_id(feature_id.clone())
+                    .value(value)
+                    .persist(true)
+                    .build();
+                insights.push(Arc::new(insight));
+            }
+        }
+    }
+    Ok(insights)
+}
+
+/// Build the raw input frames from insights sorted by event time, such as the ones of [`Trade::to_insights`].
+pub fn frames_from_insights(insights: &[Arc<Insight>]) -> HashMap<Arc<Instrument>, Frame> {
+    let mut frames: HashMap<Arc<Instrument>, Frame> = HashMap::new();
+    for insight in insights {
+        let (Some(instrument), Some(value)) = (&insight.instrument, insight.value.to_f64()) else {
+            continue;
+        };
+        frames
+            .entry(instrument.clone())
+            .or_default()
+            .entry(insight.feature_id.clone())
+            .or_default()
+            .push(insight.event_time.unix_timestamp(), value);
+    }
+    frames
+}
+
+/// Apply the kernel to the last `periods` values at or before every tick, like [`InsightsState::periods`].
+fn periods_kernel(
+    frame: &Frame,
+    input: &FeatureId,
+    ticks: &[i64],
+    periods: usize,
+    kernel: impl Fn(&[f64]) -> Option<f64>,
+) -> Series {
+    let mut series = Series::default();
+    let Some(input) = frame.get(input) else {
+        return series;
+    };
+    for tick in ticks {
+        let end = input.until(*tick);
+        if periods == 0 || end < periods {
+            continue;
+        }
+        if let Some(value) = kernel(&input.values[end - periods..end]) {
+            series.push(*tick, value);
+        }
+    }
+    series
+}
+
+/// OHLCV over the trades in [tick - window, tick], like [`InsightsState::window`].
+fn ohlcv(config: &OHLCVConfig, outputs: Vec<FeatureId>, frame: &Frame, ticks: &[i64]) -> Vec<(FeatureId, Series)> {
+    let mut outputs = outputs.into_iter().map(|id| (id, Series::default())).collect::<Vec<_>>();
+    let (Some(prices), Some(quantities)) = (frame.get(&config.input_price), frame.get(&config.input_quantity)) else {
+        return outputs;
+    };
+    let window = config.window as i64;
+    for tick in ticks {
+        let start = prices.until(tick - window - 1);
+        let end = prices.until(*tick);
+        let quantity_start = quantities.until(tick - window - 1);
+        let quantity_end = quantities.until(*tick);
+        if start == end || end - start != quantity_end - quantity_start {
+            continue;
+        }
+        let prices = &prices.values[start..end];
+        let quantities = &quantities.values[quantity_start..quantity_end];
+
+        let high = math::max(prices).unwrap_or_default();
+        let low = math::min(prices).unwrap_or_default();
+        let close = prices[prices.len() - 1];
+        let buys = quantities.iter().filter(|q| **q > 0.).copied().collect::<Vec<_>>();
+        let sells = quantities.iter().filter(|q| **q <= 0.).map(|q| q.abs()).collect::<Vec<_>>();
+        let notionals = prices.iter().zip(quantities).map(|(p, q)| p * q).collect::<Vec<_>>();
+        let buy_notional = math::sum(&notionals.iter().filter(|n| **n > 0.).copied().collect::<Vec<_>>());
+        let sell_notional = -math::sum(&notionals.iter().filter(|n| **n <= 0.).copied().collect::<Vec<_>>());
+        let (buy_volume, sell_volume) = (math::sum(&buys), math::sum(&sells));
+        let volume = buy_volume + sell_volume;
+        let notional = buy_notional + sell_notional;
+
+        let values = [
+            prices[0],
+            high,
+            low,
+            close,
+            (high + low + close) / 3.,
+            notional / volume,
+            volume,
+            buy_volume,
+            sell_volume,
+            notional,
+            buy_notional,
+            sell_notional,
+            quantities.len() as f64,
+            buys.len() as f64,
+            sells.len() as f64,
+        ];
+        for ((_, series), value) in outputs.iter_mut().zip(values) {
+            series.push(*tick, value);
+        }
+    }
+    outputs
+}
+
+/// Run the streaming engine over the same inputs and ticks and compare its outputs to the vectorized ones. Returns a
+/// description of every value that is missing on one side or differs by more than the relative tolerance.
+pub fn cross_check(
+    config: &InsightsServiceConfig,
+    engine: VectorizedEngine,
+    pipeline: Arc<Pipeline>,
+    insights: &[Arc<Insight>],
+    ticks: &[OffsetDateTime],
+    tolerance: f64,
+) -> Result<Vec<String>, InsightsError> {
+    let backfill = VectorizedBackfill::from_config(config)?.with_engine(engine);
+    let inputs = frames_from_insights(insights);
+    let instruments = inputs.keys().cloned().collect::<Vec<_>>();
+    let vectorized = backfill.run_all(&inputs, &ticks.iter().map(|t| t.unix_timestamp()).collect::<Vec<_>>())?;
+
+    // Insert one by one so the last value of a second wins like in the frames
+    let state = Arc::new(InsightsState::default());
+    for insight in insights {
+        state.insert(insight.clone());
+    }
+    let features = FeatureFactory::from_config(&config.pipeline.features, pipeline, state, config.scale_periods, None);
+    let graph = PipelineGraph::from_config(features);
+    let mut streaming: HashMap<Arc<Instrument>, Frame> = HashMap::new();
+    for tick in ticks {
+        for insight in graph.calculate(&instruments, *tick) {
+            let (Some(instrument), Some(value)) = (&insight.instrument, insight.value.to_f64()) else {
+                continue;
+            };
+            streaming
+                .entry(instrument.clone())
+                .or_default()
+                .entry(insight.feature_id.clone())
+                .or_default()
+                .push(tick.unix_timestamp(), value);
+        }
+    }
+
+    let mut mismatches = Vec::new();
+    for instrument in &instruments {
+        let empty = Frame::new();
+        let expected = streaming.get(instrument).unwrap_or(&empty);
+        let actual = vectorized.get(instrument).unwrap_or(&empty);
+        let mut features = expected.keys().chain(actual.keys()).cloned().collect::<Vec<_>>();
+        features.sort();
+        features.dedup();
+        for feature in features {
+            let expected = expected.get(&feature).cloned().unwrap_or_default();
+            let actual = actual.get(&feature).cloned().unwrap_or_default();
+            mismatches.extend(compare_series(&instrument.symbol, &feature, &expected, &actual, tolerance));
+        }
+    }
+    info!(
+        "Cross-checked {} ticks of {} instruments, {} mismatches",
+        ticks.len(),
+        instruments.len(),
+        mismatches.len()
+    );
+    Ok(mismatches)
+}
+
+fn compare_series(
+    symbol: &str,
+    feature: &FeatureId,
+    expected: &Series,
+    actual: &Series,
+    tolerance: f64,
+) -> Vec<String> {
+    let expected = expected.timestamps.iter().zip(&expected.values).collect::<HashMap<_, _>>();
+    let actual = actual.timestamps.iter().zip(&actual.values).collect::<HashMap<_, _>>();
+    let mut timestamps = expected.keys().chain(actual.keys()).copied().collect::<Vec<_>>();
+    timestamps.sort();
+    timestamps.dedup();
+    timestamps
+        .into_iter()
+        .filter_map(|ts| match (expected.get(ts), actual.get(ts)) {
+            (Some(e), Some(a)) if (*e - *a).abs() <= tolerance * e.abs().max(1.) => None,
+            (Some(e), Some(a)) => Some(format!("{} {} at {}: streaming {} vectorized {}", symbol, feature, ts, e, a)),
+            (Some(e), None) => Some(format!("{} {} at {}: streaming {} vectorized missing", symbol, feature, ts, e)),
+            (None, Some(a)) => Some(format!("{} {} at {}: streaming missing vectorized {}", symbol, feature, ts, a)),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// Ticks every frequency in (start, end], the event times the streaming engine calculates at.
+pub fn backfill_ticks(start: OffsetDateTime, end: OffsetDateTime, frequency: Duration) -> Vec<OffsetDateTime> {
+    let mut clock = Clock::new(start, end, frequency);
+    let mut ticks = Vec::new();
+    while let Some((_, tick)) = clock.next() {
+        ticks.push(tick);
+    }
+    debug!("Backfill from {} to {} has {} ticks", start, end, ticks.len());
+    ticks
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use crate::config::{LogReturnConfig, PipelineConfig, SamplingConfig, StdDevConfig, SumConfig};
+
+    use super::*;
+
+    fn id(name: &str) -> FeatureId {
+        FeatureId::new(name.into())
+    }
+
+    fn config() -> InsightsServiceConfig {
+        let ohlcv = OHLCVConfig {
+            input_price: TRADE_PRICE_FEATURE_ID.clone(),
+            input_quantity: TRADE_QUANTITY_FEATURE_ID.clone(),
+            output_open: id("open"),
+            output_high: id("high"),
+            output_low: id("low"),
+            output_close: id("close"),
+            output_typical_price: id("typical_price"),
+            output_vwap: id("vwap"),
+            output_volume: id("volume"),
+            output_buy_volume: id("buy_volume"),
+            output_sell_volume: id("sell_volume"),
+            output_notional_volume: id("notional_volume"),
+            output_buy_notional_volume: id("buy_notional_volume"),
+            output_sell_notional_volume: id("sell_notional_volume"),
+            output_trade_count: id("trade_count"),
+            output_buy_trade_count: id("buy_trade_count"),
+            output_sell_trade_count: id("sell_trade_count"),
+            window: 60,
+            persist: false,
+        };
+        InsightsServiceConfig {
+            pipeline: PipelineConfig {
+                name: "test".into(),
+                features: vec![
+                    FeatureConfig::StdDev(StdDevConfig {
+                        input: id("log_return"),
+                        output: id("volatility"),
+                        periods: 3,
+                        persist: false,
+                    }),
+                    FeatureConfig::OHLCV(ohlcv),
+                    FeatureConfig::LogReturn(LogReturnConfig {
+                        input: id("close"),
+                        output: id("log_return"),
+                        periods: 1,
+                        persist: false,
+                    }),
+                    FeatureConfig::Sum(SumConfig {
+                        input: id("volume"),
+                        output: id("volume_5"),
+                        periods: 5,
+                        persist: false,
+                    }),
+                ],
+                sampling: SamplingConfig::default(),
+            },
+            state_lookback: 86400,
+            frequency_secs: 60,
+            scale_periods: 1,
+            warm_start: None,
+            profiling: None,
+            labels: None,
+        }
+    }
+
+    fn trades(start: OffsetDateTime, count: usize) -> Vec<Arc<Insight>> {
+        (0..count)
+            .flat_map(|i| {
+                let price = Decimal::from(100) + Decimal::from((i * 7) % 13) - Decimal::from((i * 3) % 5);
+                let side = if i % 3 == 0 {
+                    MarketSide::Sell
+                } else {
+                    MarketSide::Buy
+                };
+                let trade = Trade::builder()
+                    .event_time(start + Duration::from_secs(i as u64 * 7))
+                    .instrument(test_inst_binance_btc_usdt_perp())
+                    .trade_id(i as u64)
+                    .side(side)
+                    .price(price)
+                    .quantity(dec!(0.5) + Decimal::from(i % 4))
+                    .build();
+                trade.to_insights(test_pipeline())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unsupported_feature() {
+        let mut config = config();
+        config.pipeline.features.push(FeatureConfig::Time(crate::config::TimeConfig {
+            input: TRADE_PRICE_FEATURE_ID.clone(),
+            output_day_of_week: id("day_of_week"),
+            output_hour_of_day: id("hour_of_day"),
+            output_minute_of_day: id("minute_of_day"),
+            output_minute_of_hour: id("minute_of_hour"),
+            persist: false,
+        }));
+        let err = VectorizedBackfill::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("time has no vectorized implementation"));
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let start = datetime!(2025-01-01 00:00 UTC);
+        let end = start + Duration::from_secs(3600);
+        let insights = trades(start, 500);
+        let ticks = backfill_ticks(start, end, Duration::from_secs(60));
+
+        let backfill = VectorizedBackfill::from_config(&config()).unwrap();
+        let frames = frames_from_insights(&insights);
+        let ticks_secs = ticks.iter().map(|t| t.unix_timestamp()).collect::<Vec<_>>();
+        let outputs = backfill.run_all(&frames, &ticks_secs).unwrap();
+        let frame = &outputs[&test_inst_binance_btc_usdt_perp()];
+        assert_eq!(frame[&id("close")].len(), 59);
+        assert!(!frame[&id("volatility")].is_empty());
+        assert!(!frame.contains_key(&*TRADE_PRICE_FEATURE_ID));
+
+        let mismatches =
+            cross_check(&config(), VectorizedEngine::Columns, test_pipeline(), &insights, &ticks, 1e-6).unwrap();
+        assert!(mismatches.is_empty(), "{:?}", mismatches);
+    }
+
+    #[cfg(feature = "polars")]
+    #[test]
+    fn test_cross_check_polars() {
+        let start = datetime!(2025-01-01 00:00 UTC);
+        let end = start + Duration::from_secs(3600);
+        let insights = trades(start, 500);
+        let ticks = backfill_ticks(start, end, Duration::from_secs(60));
+
+        let frames = frames_from_insights(&insights);
+        let ticks_secs = ticks.iter().map(|t| t.unix_timestamp()).collect::<Vec<_>>();
+        let columns = VectorizedBackfill::from_config(&config()).unwrap();
+        let polars = columns.clone().with_engine(VectorizedEngine::Polars);
+        let expected = columns.run_all(&frames, &ticks_secs).unwrap();
+        let actual = polars.run_all(&frames, &ticks_secs).unwrap();
+        let (expected, actual) = (
+            &expected[&test_inst_binance_btc_usdt_perp()],
+            &actual[&test_inst_binance_btc_usdt_perp()],
+        );
+        assert_eq!(expected[&id("trade_count")], actual[&id("trade_count")]);
+        assert_eq!(expected[&id("volatility")].timestamps, actual[&id("volatility")].timestamps);
+
+        let mismatches =
+            cross_check(&config(), VectorizedEngine::Polars, test_pipeline(), &insights, &ticks, 1e-6).unwrap();
+        assert!(mismatches.is_empty(), "{:?}", mismatches);
+    }
+}
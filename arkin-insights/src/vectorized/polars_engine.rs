@@ -0,0 +1,201 @@
+use polars::prelude::*;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    config::{FeatureConfig, OHLCVConfig},
+    InsightsError,
+};
+
+use super::{Frame, Series as FeatureSeries};
+
+/// Compute every feature at the ticks from the raw inputs of one instrument with Polars expressions. The rolling
+/// windows run over the same rows as the columns engine, the sums and variances are Polars' own so the outputs agree
+/// up to rounding.
+pub(super) fn run(
+    features: &[FeatureConfig],
+    scale_periods: usize,
+    inputs: &Frame,
+    ticks: &[i64],
+) -> Result<Frame, InsightsError> {
+    let mut frame = inputs.clone();
+    for feature in features {
+        let outputs = match feature {
+            FeatureConfig::OHLCV(c) => ohlcv(c, feature.outputs(), &frame, ticks)?,
+            FeatureConfig::LogReturn(c) => {
+                let periods = c.periods * scale_periods;
+                let previous = col("value").shift(lit(periods as i64));
+                let expr = when(previous.clone().neq(lit(0.)))
+                    .then((col("value") / previous).log(std::f64::consts::E))
+                    .otherwise(lit(NULL));
+                vec![(c.output.clone(), rolling(&frame, &c.input, ticks, periods + 1, expr)?)]
+            }
+            FeatureConfig::StdDev(c) => {
+                let periods = c.periods * scale_periods;
+                let expr = col("value").rolling_std(window(periods));
+                // The sample deviation of a single value is undefined like in math::std_dev
+                let periods = if periods < 2 { 0 } else { periods };
+                vec![(c.output.clone(), rolling(&frame, &c.input, ticks, periods, expr)?)]
+            }
+            FeatureConfig::Sum(c) => {
+                let periods = c.periods * scale_periods;
+                let expr = col("value").rolling_sum(window(periods));
+                vec![(c.output.clone(), rolling(&frame, &c.input, ticks, periods, expr)?)]
+            }
+            _ => unreachable!("Unsupported features are rejected in from_config"),
+        };
+        frame.extend(outputs);
+    }
+    for input in inputs.keys() {
+        frame.remove(input);
+    }
+    Ok(frame)
+}
+
+fn window(periods: usize) -> RollingOptionsFixedWindow {
+    RollingOptionsFixedWindow {
+        window_size: periods,
+        min_periods: periods,
+        ..Default::default()
+    }
+}
+
+/// Evaluate the expression over the whole input column and take its value at the last row at or before every tick
+/// with at least `periods` rows.
+fn rolling(
+    frame: &Frame,
+    input: &FeatureId,
+    ticks: &[i64],
+    periods: usize,
+    expr: Expr,
+) -> Result<FeatureSeries, InsightsError> {
+    let mut series = FeatureSeries::default();
+    let Some(input) = frame.get(input) else {
+        return Ok(series);
+    };
+    if periods == 0 || input.is_empty() {
+        return Ok(series);
+    }
+    let outputs = df!("value" => input.values.as_slice())
+        .and_then(|df| df.lazy().select([expr.alias("output")]).collect())
+        .map_err(anyhow::Error::from)?;
+    let outputs = outputs.column("output").and_then(|c| c.f64()).map_err(anyhow::Error::from)?;
+    for tick in ticks {
+        let end = input.until(*tick);
+        if end < periods {
+            continue;
+        }
+        if let Some(value) = outputs.get(end - 1) {
+            series.push(*tick, value);
+        }
+    }
+    Ok(series)
+}
+
+/// OHLCV over the trades in [tick - window, tick], the volumes and counts are differences of cumulative sums.
+fn ohlcv(
+    config: &OHLCVConfig,
+    outputs: Vec<FeatureId>,
+    frame: &Frame,
+    ticks: &[i64],
+) -> Result<Vec<(FeatureId, FeatureSeries)>, InsightsError> {
+    let mut outputs = outputs.into_iter().map(|id| (id, FeatureSeries::default())).collect::<Vec<_>>();
+    let (Some(prices), Some(quantities)) = (frame.get(&config.input_price), frame.get(&config.input_quantity)) else {
+        return Ok(outputs);
+    };
+    // Every trade has a price and a quantity, the rows line up
+    if prices.is_empty() || prices.timestamps != quantities.timestamps {
+        return Ok(outputs);
+    }
+
+    let buy = col("quantity").gt(lit(0.));
+    let notional = col("price") * col("quantity");
+    let sums = df!("price" => prices.values.as_slice(), "quantity" => quantities.values.as_slice())
+        .and_then(|df| {
+            df.lazy()
+                .select([
+                    col("price"),
+                    when(buy.clone())
+                        .then(col("quantity"))
+                        .otherwise(lit(0.))
+                        .cum_sum(false)
+                        .alias("buy_volume"),
+                    when(buy.clone())
+                        .then(lit(0.))
+                        .otherwise(lit(0.) - col("quantity"))
+                        .cum_sum(false)
+                        .alias("sell_volume"),
+                    when(notional.clone().gt(lit(0.)))
+                        .then(notional.clone())
+                        .otherwise(lit(0.))
+                        .cum_sum(false)
+                        .alias("buy_notional"),
+                    when(notional.clone().gt(lit(0.)))
+                        .then(lit(0.))
+                        .otherwise(lit(0.) - notional)
+                        .cum_sum(false)
+                        .alias("sell_notional"),
+                    buy.cast(DataType::Float64).cum_sum(false).alias("buys"),
+                ])
+                .collect()
+        })
+        .map_err(anyhow::Error::from)?;
+    let column = |name: &str| sums.column(name).and_then(|c| c.f64()).map_err(anyhow::Error::from);
+    let price = column("price")?;
+    let buy_volumes = column("buy_volume")?;
+    let sell_volumes = column("sell_volume")?;
+    let buy_notionals = column("buy_notional")?;
+    let sell_notionals = column("sell_notional")?;
+    let buy_counts = column("buys")?;
+    let between = |sums: &Float64Chunked, start: usize, end: usize| {
+        let before = if start == 0 {
+            0.
+        } else {
+            sums.get(start - 1).unwrap_or_default()
+        };
+        sums.get(end - 1).unwrap_or_default() - before
+    };
+
+    let window = config.window as i64;
+    for tick in ticks {
+        let start = prices.until(tick - window - 1);
+        let end = prices.until(*tick);
+        if start == end {
+            continue;
+        }
+        let window_prices = price.slice(start as i64, end - start);
+        let high = window_prices.max().unwrap_or_default();
+        let low = window_prices.min().unwrap_or_default();
+        let (open, close) = (prices.values[start], prices.values[end - 1]);
+        let buy_volume = between(buy_volumes, start, end);
+        let sell_volume = between(sell_volumes, start, end);
+        let buy_notional = between(buy_notionals, start, end);
+        let sell_notional = between(sell_notionals, start, end);
+        let buys = between(buy_counts, start, end).round();
+        let count = (end - start) as f64;
+        let volume = buy_volume + sell_volume;
+        let notional = buy_notional + sell_notional;
+
+        let values = [
+            open,
+            high,
+            low,
+            close,
+            (high + low + close) / 3.,
+            notional / volume,
+            volume,
+            buy_volume,
+            sell_volume,
+            notional,
+            buy_notional,
+            sell_notional,
+            count,
+            buys,
+            count - buys,
+        ];
+        for ((_, series), value) in outputs.iter_mut().zip(values) {
+            series.push(*tick, value);
+        }
+    }
+    Ok(outputs)
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use test_log::test;
+
+use arkin_core::prelude::*;
+use arkin_insights::prelude::*;
+
+fn id(name: &str) -> FeatureId {
+    Arc::new(name.to_string())
+}
+
+fn case(name: &str, fixture: &str, feature: FeatureConfig, tolerance: Decimal) -> FeatureValidationCaseConfig {
+    FeatureValidationCaseConfig {
+        name: name.to_string(),
+        fixture: fixture.to_string(),
+        feature,
+        tolerance,
+        relative_tolerance: Decimal::ZERO,
+        warmup: 0,
+    }
+}
+
+fn validator(cases: Vec<FeatureValidationCaseConfig>) -> FeatureValidator {
+    FeatureValidator::builder()
+        .fixtures_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/features").into())
+        .cases(cases)
+        .build()
+}
+
+fn reference_cases() -> Vec<FeatureValidationCaseConfig> {
+    vec![
+        case(
+            "time",
+            "time.csv",
+            FeatureConfig::Time(TimeConfig {
+                input: id("close"),
+                output_day_of_week: id("day_of_week"),
+                output_hour_of_day: id("hour_of_day"),
+                output_minute_of_day: id("minute_of_day"),
+                output_minute_of_hour: id("minute_of_hour"),
+                persist: false,
+            }),
+            Decimal::ZERO,
+        ),
+        case(
+            "sum_5",
+            "sum_5.csv",
+            FeatureConfig::Sum(SumConfig {
+                input: id("volume"),
+                output: id("volume_sum_5"),
+                periods: 5,
+                persist: false,
+            }),
+            Decimal::ZERO,
+        ),
+        case(
+            "std_dev_10",
+            "std_dev_10.csv",
+            FeatureConfig::StdDev(StdDevConfig {
+                input: id("close"),
+                output: id("close_std_dev_10"),
+                periods: 10,
+                persist: false,
+            }),
+            dec!(0.00000001),
+        ),
+        case(
+            "log_return_3",
+            "log_return_3.csv",
+            FeatureConfig::LogReturn(LogReturnConfig {
+                input: id("close"),
+                output: id("close_log_return_3"),
+                periods: 3,
+                persist: false,
+            }),
+            dec!(0.000000001),
+        ),
+        case(
+            "signal_volume",
+            "signal_volume.csv",
+            FeatureConfig::SignalStrength(SignalStrengthConfig {
+                input_first: id("buy_volume"),
+                input_second: id("sell_volume"),
+                output: id("volume_signal"),
+                persist: false,
+            }),
+            dec!(0.000000001),
+        ),
+        case(
+            "sma_8",
+            "ma_sma_8.csv",
+            FeatureConfig::MA(MovingAverageConfig {
+                ma_type: "SMA".to_string(),
+                input: id("close"),
+                output: id("close_sma_8"),
+                periods: 8,
+                persist: false,
+            }),
+            dec!(0.00000001),
+        ),
+        // ta-lib seeds the EMA with the SMA of the first periods, skip rows until both have converged
+        FeatureValidationCaseConfig {
+            warmup: 60,
+            ..case(
+                "ema_5",
+                "ma_ema_5.csv",
+                FeatureConfig::MA(MovingAverageConfig {
+                    ma_type: "EMA".to_string(),
+                    input: id("close"),
+                    output: id("close_ema_5"),
+                    periods: 5,
+                    persist: false,
+                }),
+                dec!(0.000001),
+            )
+        },
+    ]
+}
+
+#[test]
+fn test_features_match_reference() {
+    let summary = validator(reference_cases()).validate().unwrap();
+
+    for report in &summary.reports {
+        for mismatch in report.mismatches.iter().take(5) {
+            tracing::error!("{}: {}", report.name, mismatch);
+        }
+        assert!(report.passed(), "{}", report);
+    }
+    assert!(summary.passed());
+}
+
+#[test]
+fn test_detects_mismatching_feature() {
+    // Summing over the wrong number of periods should be caught
+    let cases = vec![case(
+        "sum_4",
+        "sum_5.csv",
+        FeatureConfig::Sum(SumConfig {
+            input: id("volume"),
+            output: id("volume_sum_5"),
+            periods: 4,
+            persist: false,
+        }),
+        dec!(0.000000001),
+    )];
+    let summary = validator(cases).validate().unwrap();
+
+    assert!(!summary.passed());
+    assert_eq!(summary.failed().len(), 1);
+    assert!(summary.reports[0].compared > 0);
+    assert!(summary.reports[0].max_abs_error > Decimal::ZERO);
+}
+
+#[test]
+fn test_reports_uncovered_kinds() {
+    let validator = validator(reference_cases());
+    let uncovered = validator.uncovered();
+
+    assert!(uncovered.contains(&"rsi"));
+    assert!(uncovered.contains(&"catboost"));
+    assert!(!uncovered.contains(&"ma"));
+
+    let strict = FeatureValidator::builder()
+        .fixtures_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/features").into())
+        .cases(reference_cases())
+        .require_full_coverage(true)
+        .build();
+    assert!(!strict.validate().unwrap().passed());
+}
+
+#[test]
+fn test_missing_reference_column_is_an_error() {
+    let cases = vec![case(
+        "sum_wrong_output",
+        "sum_5.csv",
+        FeatureConfig::Sum(SumConfig {
+            input: id("volume"),
+            output: id("volume_sum_10"),
+            periods: 10,
+            persist: false,
+        }),
+        Decimal::ZERO,
+    )];
+
+    assert!(matches!(validator(cases).validate(), Err(InsightsError::InvalidFixture(_, _))));
+}
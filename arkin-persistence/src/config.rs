@@ -1,10 +1,24 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PersistenceConfig {
     pub database: DatabaseConfig,
+    /// Optional read replica for heavy analytical reads (range scans for replays and backfills)
+    #[serde(default)]
+    pub replica: Option<DatabaseConfig>,
     pub auto_commit_interval: u64,
     pub batch_size: usize,
+    #[serde(default)]
+    pub writer: WriterConfig,
+    /// Queries slower than this are logged together with their parameters
+    #[serde(default = "default_slow_query_threshold_ms")]
+    pub slow_query_threshold_ms: u64,
+}
+
+fn default_slow_query_threshold_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,3 +34,50 @@ pub struct DatabaseConfig {
     pub acquire_timeout: u64,
     pub max_lifetime: u64,
 }
+
+/// What a batch writer does with new items while its queue is full.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WriterOverflow {
+    /// Drop and count them, so a slow database never holds up live trading
+    #[default]
+    Drop,
+    /// Wait for room in the queue, so a replay is slowed down to the database instead of losing data
+    Wait,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WriterConfig {
+    /// Maximum number of queued items per table before the overflow policy applies
+    pub queue_capacity: usize,
+    #[serde(default)]
+    pub overflow: WriterOverflow,
+    /// Default flush interval in milliseconds
+    pub flush_interval_ms: u64,
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Flush interval overrides in milliseconds per table (e.g. trades, ticks)
+    #[serde(default)]
+    pub tables: HashMap<String, u64>,
+}
+
+impl WriterConfig {
+    pub fn flush_interval_ms(&self, table: &str) -> u64 {
+        self.tables.get(table).cloned().unwrap_or(self.flush_interval_ms)
+    }
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 100_000,
+            overflow: WriterOverflow::Drop,
+            flush_interval_ms: 1000,
+            max_retries: 5,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 10_000,
+            tables: HashMap::new(),
+        }
+    }
+}
@@ -1,10 +1,38 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum PersistenceError {
     #[error(transparent)]
     SqlxError(#[from] sqlx::Error),
 
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
     #[error("Entity not found")]
     NotFound,
 }
+
+impl PersistenceError {
+    /// Errors that are likely to go away when retrying, like connection or pool problems.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            PersistenceError::SqlxError(e) => matches!(
+                e,
+                sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed
+            ),
+            PersistenceError::SerdeJsonError(_) | PersistenceError::NotFound => false,
+        }
+    }
+}
+
+impl ClassifyError for PersistenceError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            e if e.is_transient() => ErrorClass::Transient,
+            PersistenceError::SerdeJsonError(_) | PersistenceError::NotFound => ErrorClass::InvalidInput,
+            PersistenceError::SqlxError(_) => ErrorClass::Fatal,
+        }
+    }
+}
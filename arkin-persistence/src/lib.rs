@@ -1,24 +1,30 @@
 mod config;
 mod errors;
+mod query_stats;
 mod repos;
 mod service;
 mod services;
 mod stores;
 mod test_utils;
 mod traits;
+mod writer;
 
 pub use config::*;
 pub use errors::*;
+pub use query_stats::*;
 pub use service::*;
 pub use services::*;
 pub use traits::*;
+pub use writer::*;
 
 pub mod prelude {
     pub use crate::config::*;
     pub use crate::errors::*;
+    pub use crate::query_stats::*;
     pub use crate::service::*;
     pub use crate::services::*;
     pub use crate::traits::*;
+    pub use crate::writer::*;
 }
 
 pub const BIND_LIMIT: usize = 65535;
@@ -0,0 +1,338 @@
+use std::{
+    fmt::{self, Write},
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use tracing::{info, warn};
+
+use arkin_core::prelude::render_labels;
+
+/// Process wide query statistics, shared by all repositories.
+pub static QUERY_STATS: LazyLock<QueryStats> = LazyLock::new(QueryStats::default);
+
+/// Upper bounds of the latency buckets in microseconds, the last bucket catches everything above.
+const BUCKETS_US: [u64; 16] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    5_000_000, 10_000_000,
+];
+
+/// Latency histogram of a single named query.
+#[derive(Debug, Default)]
+pub struct QueryHistogram {
+    buckets: [AtomicU64; BUCKETS_US.len() + 1],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl QueryHistogram {
+    pub fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let idx = BUCKETS_US.iter().position(|b| us <= *b).unwrap_or(BUCKETS_US.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn mean(&self) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_micros(self.sum_us.load(Ordering::Relaxed) / count)
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_micros(self.sum_us.load(Ordering::Relaxed))
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_us.load(Ordering::Relaxed))
+    }
+
+    /// Approximate quantile, returns the upper bound of the bucket containing the quantile.
+    pub fn quantile(&self, q: f64) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        let target = ((count as f64) * q.clamp(0., 1.)).ceil().max(1.) as u64;
+        let mut seen = 0;
+        for (idx, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= target {
+                return match BUCKETS_US.get(idx) {
+                    Some(bound) => Duration::from_micros(*bound).min(self.max()),
+                    None => self.max(),
+                };
+            }
+        }
+        self.max()
+    }
+
+    /// Cumulative bucket counts as (upper bound, count) pairs, None being the overflow bucket.
+    pub fn buckets(&self) -> Vec<(Option<Duration>, u64)> {
+        let mut cumulative = 0;
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, bucket)| {
+                cumulative += bucket.load(Ordering::Relaxed);
+                (BUCKETS_US.get(idx).map(|b| Duration::from_micros(*b)), cumulative)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryStatsSnapshot {
+    pub name: &'static str,
+    pub count: u64,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl fmt::Display for QueryStatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} count={} mean={:?} p50={:?} p99={:?} max={:?}",
+            self.name, self.count, self.mean, self.p50, self.p99, self.max
+        )
+    }
+}
+
+/// Latency statistics per query name plus the slow query log.
+#[derive(Debug)]
+pub struct QueryStats {
+    queries: DashMap<&'static str, QueryHistogram>,
+    slow_threshold_us: AtomicU64,
+}
+
+impl Default for QueryStats {
+    fn default() -> Self {
+        Self {
+            queries: DashMap::new(),
+            slow_threshold_us: AtomicU64::new(1_000_000),
+        }
+    }
+}
+
+impl QueryStats {
+    pub fn set_slow_threshold(&self, threshold: Duration) {
+        self.slow_threshold_us
+            .store(threshold.as_micros().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+    }
+
+    pub fn slow_threshold(&self) -> Duration {
+        Duration::from_micros(self.slow_threshold_us.load(Ordering::Relaxed))
+    }
+
+    /// Record a query execution, returns true if the query was slower than the threshold.
+    pub fn record(&self, name: &'static str, elapsed: Duration) -> bool {
+        self.queries.entry(name).or_default().record(elapsed);
+        elapsed > self.slow_threshold()
+    }
+
+    pub fn get(&self, name: &str) -> Option<QueryStatsSnapshot> {
+        self.queries.get(name).map(|h| Self::snapshot_of(h.key(), h.value()))
+    }
+
+    /// Snapshot of all queries, sorted by total time spent descending.
+    pub fn snapshot(&self) -> Vec<QueryStatsSnapshot> {
+        let mut stats = self
+            .queries
+            .iter()
+            .map(|h| Self::snapshot_of(h.key(), h.value()))
+            .collect::<Vec<_>>();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.mean.as_micros() * s.count as u128));
+        stats
+    }
+
+    /// Log a summary of all query latencies.
+    pub fn report(&self) {
+        for stats in self.snapshot() {
+            info!("Query stats: {}", stats);
+        }
+    }
+
+    pub fn reset(&self) {
+        self.queries.clear();
+    }
+
+    /// Write the latency histograms of all queries in the Prometheus text format.
+    pub fn render(&self, out: &mut String) {
+        let mut queries = self.queries.iter().collect::<Vec<_>>();
+        if queries.is_empty() {
+            return;
+        }
+        queries.sort_by_key(|h| *h.key());
+
+        out.push_str("# HELP arkin_query_duration_seconds Latency of the database queries\n");
+        out.push_str("# TYPE arkin_query_duration_seconds histogram\n");
+        for histogram in queries {
+            let query = histogram.key();
+            for (bound, count) in histogram.buckets() {
+                let le = bound.map_or("+Inf".to_string(), |b| b.as_secs_f64().to_string());
+                let labels = render_labels(&[("query", query), ("le", &le)]);
+                let _ = writeln!(out, "arkin_query_duration_seconds_bucket{} {}", labels, count);
+            }
+            let labels = render_labels(&[("query", query)]);
+            let sum = histogram.sum().as_secs_f64();
+            let _ = writeln!(out, "arkin_query_duration_seconds_sum{} {}", labels, sum);
+            let _ = writeln!(out, "arkin_query_duration_seconds_count{} {}", labels, histogram.count());
+        }
+    }
+
+    fn snapshot_of(name: &'static str, histogram: &QueryHistogram) -> QueryStatsSnapshot {
+        QueryStatsSnapshot {
+            name,
+            count: histogram.count(),
+            mean: histogram.mean(),
+            p50: histogram.quantile(0.5),
+            p99: histogram.quantile(0.99),
+            max: histogram.max(),
+        }
+    }
+}
+
+/// Future wrapper that records the latency of a query in [`QUERY_STATS`].
+pub struct Timed<'a, F: Future> {
+    name: &'static str,
+    params: Option<Box<dyn FnOnce() -> String + Send + 'a>>,
+    start: Option<Instant>,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Timed<'_, F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        match self.inner.as_mut().poll(cx) {
+            Poll::Ready(output) => {
+                let elapsed = start.elapsed();
+                if QUERY_STATS.record(self.name, elapsed) {
+                    let params = self.params.take().map(|p| p()).unwrap_or_default();
+                    warn!("Slow query {} took {:?} {}", self.name, elapsed, params);
+                }
+                Poll::Ready(output)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub trait TimedQuery: Future + Sized {
+    /// Time this query under the given name.
+    fn timed<'a>(self, name: &'static str) -> Timed<'a, Self> {
+        Timed {
+            name,
+            params: None,
+            start: None,
+            inner: Box::pin(self),
+        }
+    }
+
+    /// Time this query under the given name, logging the formatted parameters if the query is slow.
+    fn timed_with<'a>(self, name: &'static str, params: impl FnOnce() -> String + Send + 'a) -> Timed<'a, Self> {
+        Timed {
+            name,
+            params: Some(Box::new(params)),
+            start: None,
+            inner: Box::pin(self),
+        }
+    }
+}
+
+impl<F: Future> TimedQuery for F {}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let histogram = QueryHistogram::default();
+        for _ in 0..98 {
+            histogram.record(Duration::from_micros(200));
+        }
+        histogram.record(Duration::from_millis(40));
+        histogram.record(Duration::from_millis(700));
+
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.quantile(0.5), Duration::from_micros(250));
+        assert_eq!(histogram.quantile(0.99), Duration::from_millis(50));
+        assert_eq!(histogram.quantile(1.), Duration::from_millis(700));
+        assert_eq!(histogram.max(), Duration::from_millis(700));
+        assert_eq!(histogram.buckets().last().unwrap(), &(None, 100));
+    }
+
+    #[test]
+    fn test_slow_threshold() {
+        let stats = QueryStats::default();
+        stats.set_slow_threshold(Duration::from_millis(10));
+        assert!(!stats.record("test.fast", Duration::from_millis(1)));
+        assert!(stats.record("test.slow", Duration::from_millis(20)));
+        assert_eq!(stats.snapshot().len(), 2);
+        assert_eq!(stats.get("test.slow").unwrap().count, 1);
+    }
+
+    #[test]
+    fn test_render_histograms() {
+        let stats = QueryStats::default();
+        stats.record("trades.read_range", Duration::from_millis(500));
+        stats.record("trades.read_range", Duration::from_secs(20));
+
+        let mut out = String::new();
+        stats.render(&mut out);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "# TYPE arkin_query_duration_seconds histogram");
+        assert_eq!(
+            lines[2],
+            "arkin_query_duration_seconds_bucket{query=\"trades.read_range\",le=\"0.0001\"} 0"
+        );
+        assert_eq!(
+            lines[13],
+            "arkin_query_duration_seconds_bucket{query=\"trades.read_range\",le=\"0.5\"} 1"
+        );
+        assert_eq!(
+            lines[18],
+            "arkin_query_duration_seconds_bucket{query=\"trades.read_range\",le=\"+Inf\"} 2"
+        );
+        assert_eq!(lines[19], "arkin_query_duration_seconds_sum{query=\"trades.read_range\"} 20.5");
+        assert_eq!(lines[20], "arkin_query_duration_seconds_count{query=\"trades.read_range\"} 2");
+    }
+
+    #[test(tokio::test)]
+    async fn test_timed_future_records_latency() {
+        let res = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            42
+        }
+        .timed_with("test.timed", || "params".to_string())
+        .await;
+
+        assert_eq!(res, 42);
+        let stats = QUERY_STATS.get("test.timed").unwrap();
+        assert_eq!(stats.count, 1);
+        assert!(stats.max >= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone)]
+pub struct AccountTransferDTO {
+    /// Id of the transfer request, every request has one outcome
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub requested_at: OffsetDateTime,
+    pub portfolio_id: Uuid,
+    pub asset_id: Uuid,
+    pub quantity: Decimal,
+    pub from_account: String,
+    pub to_account: String,
+    pub from_sub_account: Option<String>,
+    pub to_sub_account: Option<String>,
+    pub requested_by: String,
+    pub status: String,
+    pub venue_transfer_id: Option<String>,
+    pub reason: String,
+}
+
+impl From<Arc<AccountTransferUpdate>> for AccountTransferDTO {
+    fn from(update: Arc<AccountTransferUpdate>) -> Self {
+        let transfer = &update.transfer;
+        Self {
+            id: transfer.id,
+            event_time: update.event_time,
+            requested_at: transfer.event_time,
+            portfolio_id: transfer.portfolio.id,
+            asset_id: transfer.asset.id,
+            quantity: transfer.quantity,
+            from_account: transfer.from.to_string(),
+            to_account: transfer.to.to_string(),
+            from_sub_account: transfer.from_sub_account.clone(),
+            to_sub_account: transfer.to_sub_account.clone(),
+            requested_by: transfer.requested_by.clone(),
+            status: update.status.to_string(),
+            venue_transfer_id: update.venue_transfer_id.clone(),
+            reason: update.reason.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct AccountTransferRepo {
+    pool: PgPool,
+}
+
+impl AccountTransferRepo {
+    pub async fn insert(&self, transfer: AccountTransferDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO account_transfers
+            (
+                id,
+                event_time,
+                requested_at,
+                portfolio_id,
+                asset_id,
+                quantity,
+                from_account,
+                to_account,
+                from_sub_account,
+                to_sub_account,
+                requested_by,
+                status,
+                venue_transfer_id,
+                reason
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            transfer.id,
+            transfer.event_time,
+            transfer.requested_at,
+            transfer.portfolio_id,
+            transfer.asset_id,
+            transfer.quantity,
+            transfer.from_account,
+            transfer.to_account,
+            transfer.from_sub_account,
+            transfer.to_sub_account,
+            transfer.requested_by,
+            transfer.status,
+            transfer.venue_transfer_id,
+            transfer.reason,
+        )
+        .execute(&self.pool)
+        .timed("account_transfers.insert")
+        .await?;
+        Ok(())
+    }
+
+    /// Quantity of every asset executed at or after the given time, by asset symbol.
+    pub async fn read_executed_since(&self, from: OffsetDateTime) -> Result<Vec<(String, Decimal)>, PersistenceError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                a.symbol,
+                SUM(t.quantity) AS "quantity!"
+            FROM account_transfers t
+            JOIN assets a ON a.id = t.asset_id
+            WHERE t.status = 'executed' AND t.event_time >= $1
+            GROUP BY a.symbol
+            "#,
+            from,
+        )
+        .fetch_all(&self.pool)
+        .timed("account_transfers.read_executed_since")
+        .await?;
+        Ok(rows.into_iter().map(|r| (r.symbol, r.quantity)).collect())
+    }
+}
@@ -7,7 +7,7 @@ use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 8;
 
@@ -70,6 +70,7 @@ impl AllocationRepo {
             allocation.weight
         )
         .execute(&self.pool)
+        .timed("allocation.insert")
         .await?;
         Ok(())
     }
@@ -110,7 +111,7 @@ impl AllocationRepo {
             // query_builder.push("ON CONFLICT (instrument_id, tick_id, event_time) DO NOTHING");
             let query = query_builder.build();
 
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("allocation.insert_batch").await?;
         }
         Ok(())
     }
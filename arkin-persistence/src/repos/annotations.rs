@@ -0,0 +1,177 @@
+use arkin_core::{Annotation, AnnotationTarget};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone)]
+pub struct AnnotationDTO {
+    pub id: Uuid,
+    pub target: AnnotationTarget,
+    pub target_id: Option<Uuid>,
+    pub instrument: Option<String>,
+    pub start_time: OffsetDateTime,
+    pub end_time: OffsetDateTime,
+    pub tag: String,
+    pub note: String,
+    pub exclude: bool,
+    pub author: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl From<&Annotation> for AnnotationDTO {
+    fn from(annotation: &Annotation) -> Self {
+        Self {
+            id: annotation.id,
+            target: annotation.target,
+            target_id: annotation.target_id,
+            instrument: annotation.instrument.clone(),
+            start_time: annotation.start,
+            end_time: annotation.end,
+            tag: annotation.tag.clone(),
+            note: annotation.note.clone(),
+            exclude: annotation.exclude,
+            author: annotation.author.clone(),
+            created_at: annotation.created_at,
+        }
+    }
+}
+
+impl From<AnnotationDTO> for Annotation {
+    fn from(annotation: AnnotationDTO) -> Self {
+        Annotation {
+            id: annotation.id,
+            target: annotation.target,
+            target_id: annotation.target_id,
+            instrument: annotation.instrument,
+            start: annotation.start_time,
+            end: annotation.end_time,
+            tag: annotation.tag,
+            note: annotation.note,
+            exclude: annotation.exclude,
+            author: annotation.author,
+            created_at: annotation.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct AnnotationRepo {
+    pool: PgPool,
+}
+
+impl AnnotationRepo {
+    pub async fn insert(&self, annotation: AnnotationDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO annotations
+            (
+                id,
+                target,
+                target_id,
+                instrument,
+                start_time,
+                end_time,
+                tag,
+                note,
+                exclude,
+                author,
+                created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+            annotation.id,
+            annotation.target as AnnotationTarget,
+            annotation.target_id,
+            annotation.instrument,
+            annotation.start_time,
+            annotation.end_time,
+            annotation.tag,
+            annotation.note,
+            annotation.exclude,
+            annotation.author,
+            annotation.created_at,
+        )
+        .execute(&self.pool)
+        .timed("annotations.insert")
+        .await?;
+        Ok(())
+    }
+
+    /// Annotations covering part of the range, oldest first.
+    pub async fn read_range(
+        &self,
+        from: OffsetDateTime,
+        till: OffsetDateTime,
+    ) -> Result<Vec<AnnotationDTO>, PersistenceError> {
+        let annotations = sqlx::query_as!(
+            AnnotationDTO,
+            r#"
+            SELECT
+                id,
+                target AS "target:AnnotationTarget",
+                target_id,
+                instrument,
+                start_time,
+                end_time,
+                tag,
+                note,
+                exclude,
+                author,
+                created_at
+            FROM annotations
+            WHERE start_time <= $2 AND end_time >= $1
+            ORDER BY start_time, created_at
+            "#,
+            from,
+            till,
+        )
+        .fetch_all(&self.pool)
+        .timed("annotations.read_range")
+        .await?;
+        Ok(annotations)
+    }
+
+    pub async fn read_by_target(&self, target_id: &Uuid) -> Result<Vec<AnnotationDTO>, PersistenceError> {
+        let annotations = sqlx::query_as!(
+            AnnotationDTO,
+            r#"
+            SELECT
+                id,
+                target AS "target:AnnotationTarget",
+                target_id,
+                instrument,
+                start_time,
+                end_time,
+                tag,
+                note,
+                exclude,
+                author,
+                created_at
+            FROM annotations
+            WHERE target_id = $1
+            ORDER BY created_at
+            "#,
+            target_id,
+        )
+        .fetch_all(&self.pool)
+        .timed("annotations.read_by_target")
+        .await?;
+        Ok(annotations)
+    }
+
+    pub async fn delete(&self, id: &Uuid) -> Result<bool, PersistenceError> {
+        let res = sqlx::query!(
+            r#"
+            DELETE FROM annotations
+            WHERE id = $1
+            "#,
+            id,
+        )
+        .execute(&self.pool)
+        .timed("annotations.delete")
+        .await?;
+        Ok(res.rows_affected() == 1)
+    }
+}
@@ -5,7 +5,7 @@ use sqlx::{prelude::*, PgPool};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(FromRow)]
 pub struct AssetDTO {
@@ -62,6 +62,7 @@ impl AssetRepo {
             asset.asset_type as AssetType,
         )
         .execute(&self.pool)
+        .timed("assets.insert")
         .await?;
         Ok(())
     }
@@ -81,6 +82,7 @@ impl AssetRepo {
             id,
         )
         .fetch_optional(&self.pool)
+        .timed("assets.read_by_id")
         .await?;
 
         match asset {
@@ -104,6 +106,7 @@ impl AssetRepo {
             symbol,
         )
         .fetch_optional(&self.pool)
+        .timed("assets.read_by_symbol")
         .await?;
 
         match asset {
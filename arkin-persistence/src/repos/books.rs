@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use sqlx::{FromRow, PgPool};
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
+
+const FIELD_COUNT: usize = 5;
+
+/// One price level of a book update, bids are stored on the buy side and asks on the sell side.
+#[derive(Debug, FromRow)]
+pub struct BookLevelDTO {
+    pub event_time: OffsetDateTime,
+    pub instrument_id: Uuid,
+    pub side: MarketSide,
+    pub price: Price,
+    pub quantity: Quantity,
+}
+
+impl BookLevelDTO {
+    pub fn from_book(book: &Arc<Book>) -> Vec<Self> {
+        let level = |side: MarketSide, level: &BookUpdateSide| Self {
+            event_time: book.event_time,
+            instrument_id: book.instrument.id,
+            side,
+            price: level.price,
+            quantity: level.quantity,
+        };
+        book.bids
+            .iter()
+            .map(|l| level(MarketSide::Buy, l))
+            .chain(book.asks.iter().map(|l| level(MarketSide::Sell, l)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct BookRepo {
+    pool: PgPool,
+    /// Pool used for heavy reads, falls back to the primary pool
+    #[builder(default)]
+    read_pool: Option<PgPool>,
+}
+
+impl BookRepo {
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    pub async fn insert_batch(&self, levels: Vec<BookLevelDTO>) -> Result<(), PersistenceError> {
+        for batch in levels.chunks(BIND_LIMIT / FIELD_COUNT) {
+            let mut query_builder =
+                sqlx::QueryBuilder::new("INSERT INTO book_updates (event_time, instrument_id, side, price, quantity) ");
+
+            query_builder.push_values(batch, |mut b, level| {
+                b.push_bind(level.event_time)
+                    .push_bind(level.instrument_id)
+                    .push_bind(level.side)
+                    .push_bind(level.price)
+                    .push_bind(level.quantity);
+            });
+
+            query_builder.push("ON CONFLICT (instrument_id, event_time, side, price) DO NOTHING");
+            let query = query_builder.build();
+
+            query.execute(&self.pool).timed("book_updates.insert_batch").await?;
+        }
+        Ok(())
+    }
+
+    pub async fn read_range(
+        &self,
+        instrument_ids: &[Uuid],
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> Result<Vec<BookLevelDTO>, PersistenceError> {
+        let levels = sqlx::query_as!(
+            BookLevelDTO,
+            r#"
+            SELECT
+                event_time,
+                instrument_id,
+                side as "side:MarketSide",
+                price,
+                quantity
+            FROM book_updates
+            WHERE instrument_id = ANY($3) AND event_time >= $1 AND event_time < $2
+            ORDER BY event_time ASC, instrument_id ASC
+            "#,
+            start,
+            end,
+            instrument_ids,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("book_updates.read_range", || {
+            format!("instruments={:?} from={} to={}", instrument_ids, start, end)
+        })
+        .await?;
+
+        Ok(levels)
+    }
+}
@@ -0,0 +1,87 @@
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone)]
+pub struct CheckpointDTO {
+    pub id: Uuid,
+    pub run: String,
+    pub clock_time: OffsetDateTime,
+    /// Checkpoint encoded as json
+    pub state: String,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CheckpointRepo {
+    pool: PgPool,
+}
+
+impl CheckpointRepo {
+    pub async fn insert(&self, checkpoint: CheckpointDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO simulation_checkpoints
+            (
+                id,
+                run,
+                clock_time,
+                state,
+                created_at
+            ) VALUES ($1, $2, $3, $4, $5)
+            "#,
+            checkpoint.id,
+            checkpoint.run,
+            checkpoint.clock_time,
+            checkpoint.state,
+            checkpoint.created_at,
+        )
+        .execute(&self.pool)
+        .timed("simulation_checkpoints.insert")
+        .await?;
+        Ok(())
+    }
+
+    /// Checkpoint of the run that got the furthest in simulated time.
+    pub async fn read_latest(&self, run: &str) -> Result<Option<CheckpointDTO>, PersistenceError> {
+        let checkpoint = sqlx::query_as!(
+            CheckpointDTO,
+            r#"
+            SELECT
+                id,
+                run,
+                clock_time,
+                state,
+                created_at
+            FROM simulation_checkpoints
+            WHERE run = $1
+            ORDER BY clock_time DESC
+            LIMIT 1
+            "#,
+            run,
+        )
+        .fetch_optional(&self.pool)
+        .timed("simulation_checkpoints.read_latest")
+        .await?;
+        Ok(checkpoint)
+    }
+
+    /// Remove the checkpoints of the run before the given simulated time, returns how many were removed.
+    pub async fn delete_before(&self, run: &str, clock_time: OffsetDateTime) -> Result<u64, PersistenceError> {
+        let res = sqlx::query!(
+            r#"
+            DELETE FROM simulation_checkpoints
+            WHERE run = $1 AND clock_time < $2
+            "#,
+            run,
+            clock_time,
+        )
+        .execute(&self.pool)
+        .timed("simulation_checkpoints.delete_before")
+        .await?;
+        Ok(res.rows_affected())
+    }
+}
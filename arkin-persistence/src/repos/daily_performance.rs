@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use sqlx::{prelude::*, PgPool};
+use time::Date;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::DailyPerformance;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(FromRow)]
+pub struct DailyPerformanceDTO {
+    pub id: Uuid,
+    pub date: Date,
+    pub portfolio_id: Uuid,
+    pub strategy: String,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub signal_pnl: Decimal,
+    pub execution_pnl: Decimal,
+    pub commission: Decimal,
+    pub funding: Decimal,
+    pub turnover: Decimal,
+    pub trades: i64,
+    pub nav: Decimal,
+    pub high_water_mark: Decimal,
+    pub management_fee: Decimal,
+    pub performance_fee: Decimal,
+}
+
+impl From<Arc<DailyPerformance>> for DailyPerformanceDTO {
+    fn from(performance: Arc<DailyPerformance>) -> Self {
+        Self {
+            id: performance.id,
+            date: performance.date,
+            portfolio_id: performance.portfolio.id,
+            strategy: performance.strategy.clone(),
+            realized_pnl: performance.realized_pnl,
+            unrealized_pnl: performance.unrealized_pnl,
+            signal_pnl: performance.signal_pnl,
+            execution_pnl: performance.execution_pnl,
+            commission: performance.commission,
+            funding: performance.funding,
+            turnover: performance.turnover,
+            trades: performance.trades as i64,
+            nav: performance.nav,
+            high_water_mark: performance.high_water_mark,
+            management_fee: performance.management_fee,
+            performance_fee: performance.performance_fee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct DailyPerformanceRepo {
+    pool: PgPool,
+}
+
+impl DailyPerformanceRepo {
+    /// Insert the performance of a day, a day frozen again (e.g. a rerun simulation) replaces the previous one.
+    pub async fn upsert(&self, performance: DailyPerformanceDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO daily_performance
+            (
+                id,
+                date,
+                portfolio_id,
+                strategy,
+                realized_pnl,
+                unrealized_pnl,
+                signal_pnl,
+                execution_pnl,
+                commission,
+                funding,
+                turnover,
+                trades,
+                nav,
+                high_water_mark,
+                management_fee,
+                performance_fee
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            ON CONFLICT (date, portfolio_id, strategy) DO UPDATE SET
+                realized_pnl = EXCLUDED.realized_pnl,
+                unrealized_pnl = EXCLUDED.unrealized_pnl,
+                signal_pnl = EXCLUDED.signal_pnl,
+                execution_pnl = EXCLUDED.execution_pnl,
+                commission = EXCLUDED.commission,
+                funding = EXCLUDED.funding,
+                turnover = EXCLUDED.turnover,
+                trades = EXCLUDED.trades,
+                nav = EXCLUDED.nav,
+                high_water_mark = EXCLUDED.high_water_mark,
+                management_fee = EXCLUDED.management_fee,
+                performance_fee = EXCLUDED.performance_fee
+            "#,
+            performance.id,
+            performance.date,
+            performance.portfolio_id,
+            performance.strategy,
+            performance.realized_pnl,
+            performance.unrealized_pnl,
+            performance.signal_pnl,
+            performance.execution_pnl,
+            performance.commission,
+            performance.funding,
+            performance.turnover,
+            performance.trades,
+            performance.nav,
+            performance.high_water_mark,
+            performance.management_fee,
+            performance.performance_fee
+        )
+        .execute(&self.pool)
+        .timed("daily_performance.upsert")
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone)]
+pub struct ExecutionMetricsDTO {
+    pub order_id: VenueOrderId,
+    pub event_time: OffsetDateTime,
+    pub instrument_id: Uuid,
+    pub side: MarketSide,
+    pub order_type: VenueOrderType,
+    pub status: VenueOrderStatus,
+    pub quantity: Decimal,
+    pub filled_quantity: Decimal,
+    pub fill_ratio: f64,
+    pub time_to_fill_us: Option<i64>,
+    pub arrival_price: Option<Decimal>,
+    pub avg_fill_price: Option<Decimal>,
+    pub effective_spread_bps: Option<f64>,
+    pub implementation_shortfall_bps: Option<f64>,
+    pub commission_bps: Option<f64>,
+    pub maker_ratio: Option<f64>,
+}
+
+impl From<Arc<ExecutionMetrics>> for ExecutionMetricsDTO {
+    fn from(metrics: Arc<ExecutionMetrics>) -> Self {
+        Self {
+            order_id: metrics.order_id,
+            event_time: metrics.event_time,
+            instrument_id: metrics.instrument.id,
+            side: metrics.side,
+            order_type: metrics.order_type,
+            status: metrics.status,
+            quantity: metrics.quantity,
+            filled_quantity: metrics.filled_quantity,
+            fill_ratio: metrics.fill_ratio,
+            time_to_fill_us: metrics.time_to_fill.map(|t| t.as_micros() as i64),
+            arrival_price: metrics.arrival_price,
+            avg_fill_price: metrics.avg_fill_price,
+            effective_spread_bps: metrics.effective_spread_bps,
+            implementation_shortfall_bps: metrics.implementation_shortfall_bps,
+            commission_bps: metrics.commission_bps,
+            maker_ratio: metrics.maker_ratio,
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ExecutionMetricsRepo {
+    pool: PgPool,
+}
+
+impl ExecutionMetricsRepo {
+    pub async fn insert(&self, metrics: ExecutionMetricsDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO execution_metrics
+            (
+                order_id,
+                event_time,
+                instrument_id,
+                side,
+                order_type,
+                status,
+                quantity,
+                filled_quantity,
+                fill_ratio,
+                time_to_fill_us,
+                arrival_price,
+                avg_fill_price,
+                effective_spread_bps,
+                implementation_shortfall_bps,
+                commission_bps,
+                maker_ratio
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            ON CONFLICT (order_id) DO NOTHING
+            "#,
+            metrics.order_id,
+            metrics.event_time,
+            metrics.instrument_id,
+            metrics.side as MarketSide,
+            metrics.order_type as VenueOrderType,
+            metrics.status as VenueOrderStatus,
+            metrics.quantity,
+            metrics.filled_quantity,
+            metrics.fill_ratio,
+            metrics.time_to_fill_us,
+            metrics.arrival_price,
+            metrics.avg_fill_price,
+            metrics.effective_spread_bps,
+            metrics.implementation_shortfall_bps,
+            metrics.commission_bps,
+            metrics.maker_ratio,
+        )
+        .execute(&self.pool)
+        .timed("execution_metrics.insert")
+        .await?;
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use arkin_core::prelude::*;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(Debug, Clone)]
 pub struct ExecutionOrderDTO {
@@ -109,6 +109,7 @@ impl ExecutionOrderRepo {
             order.updated_at,
         )
         .execute(&self.pool)
+        .timed("execution_orders.insert")
         .await?;
         Ok(())
     }
@@ -133,6 +134,7 @@ impl ExecutionOrderRepo {
             order.updated_at,
         )
         .execute(&self.pool)
+        .timed("execution_orders.update")
         .await?;
         Ok(())
     }
@@ -146,6 +148,7 @@ impl ExecutionOrderRepo {
             id
         )
         .execute(&self.pool)
+        .timed("execution_orders.delete")
         .await?;
         Ok(())
     }
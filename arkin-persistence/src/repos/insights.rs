@@ -10,7 +10,7 @@ use typed_builder::TypedBuilder;
 use arkin_core::Insight;
 use uuid::Uuid;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 5;
 
@@ -39,9 +39,16 @@ impl From<Arc<Insight>> for InsightDTO {
 
 pub struct InsightsRepo {
     pool: PgPool,
+    /// Pool used for heavy reads, falls back to the primary pool
+    #[builder(default)]
+    read_pool: Option<PgPool>,
 }
 
 impl InsightsRepo {
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
     pub async fn insert(&self, insight: InsightDTO) -> Result<(), PersistenceError> {
         sqlx::query!(
             r#"
@@ -63,6 +70,7 @@ impl InsightsRepo {
             insight.value,
         )
         .execute(&self.pool)
+        .timed("insights.insert")
         .await?;
         Ok(())
     }
@@ -101,9 +109,46 @@ impl InsightsRepo {
             );
             let query = query_builder.build();
 
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("insights.insert_batch").await?;
         }
         debug!("Saved {} insights", insights.len());
         Ok(())
     }
+
+    /// Read the insights of a pipeline in [from, to), insights without an instrument are always included.
+    pub async fn read_range(
+        &self,
+        pipeline_id: Uuid,
+        instrument_ids: &[Uuid],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<InsightDTO>, PersistenceError> {
+        let insights = sqlx::query_as!(
+            InsightDTO,
+            r#"
+            SELECT
+                event_time,
+                pipeline_id,
+                instrument_id,
+                feature_id,
+                value
+            FROM insights
+            WHERE pipeline_id = $1
+                AND (instrument_id = ANY($2) OR instrument_id IS NULL)
+                AND event_time >= $3 AND event_time < $4
+            ORDER BY event_time ASC
+            "#,
+            pipeline_id,
+            instrument_ids,
+            from,
+            to,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("insights.read_range", || {
+            format!("pipeline={} from={} to={}", pipeline_id, from, to)
+        })
+        .await?;
+
+        Ok(insights)
+    }
 }
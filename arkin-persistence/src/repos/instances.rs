@@ -7,7 +7,7 @@ use typed_builder::TypedBuilder;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(Debug, Clone)]
 pub struct InstanceDTO {
@@ -87,6 +87,7 @@ impl InstanceRepo {
             instance.status as InstanceStatus,
         )
         .execute(&self.pool)
+        .timed("instances.insert")
         .await?;
         Ok(())
     }
@@ -108,6 +109,7 @@ impl InstanceRepo {
             id
         )
         .fetch_optional(&self.pool) // -> Vec<Country>
+        .timed("instances.read_by_id")
         .await?;
         match instance {
             Some(instance) => Ok(instance),
@@ -132,6 +134,7 @@ impl InstanceRepo {
             name
         )
         .fetch_optional(&self.pool) // -> Vec<Country>
+        .timed("instances.read_by_name")
         .await?;
         match instance {
             Some(instance) => Ok(instance),
@@ -159,6 +162,7 @@ impl InstanceRepo {
             instance.status as InstanceStatus,
         )
         .execute(&self.pool)
+        .timed("instances.update")
         .await?;
         Ok(())
     }
@@ -172,6 +176,7 @@ impl InstanceRepo {
             id
         )
         .execute(&self.pool)
+        .timed("instances.delete")
         .await?;
         Ok(())
     }
@@ -9,7 +9,7 @@ use uuid::Uuid;
 
 use arkin_core::{Instrument, InstrumentOptionType, InstrumentStatus, InstrumentType, Price};
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(FromRow)]
 pub struct InstrumentDTO {
@@ -24,6 +24,7 @@ pub struct InstrumentDTO {
     pub strike: Option<Decimal>,
     pub maturity: Option<OffsetDateTime>,
     pub option_type: Option<InstrumentOptionType>,
+    pub underlying_id: Option<Uuid>,
     pub contract_size: Decimal,
     pub price_precision: i32,
     pub quantity_precision: i32,
@@ -48,6 +49,7 @@ impl From<Arc<Instrument>> for InstrumentDTO {
             strike: instrument.strike,
             maturity: instrument.maturity,
             option_type: instrument.option_type.as_ref().map(|v| v.clone()),
+            underlying_id: instrument.underlying.as_ref().map(|u| u.id),
             contract_size: instrument.contract_size,
             price_precision: instrument.price_precision as i32,
             quantity_precision: instrument.quantity_precision as i32,
@@ -60,6 +62,13 @@ impl From<Arc<Instrument>> for InstrumentDTO {
     }
 }
 
+/// Symbols of an instrument, for listings and shell completion.
+#[derive(FromRow)]
+pub struct InstrumentSymbolDTO {
+    pub symbol: String,
+    pub venue_symbol: String,
+}
+
 #[derive(Debug, Clone, TypedBuilder)]
 
 pub struct InstrumentRepo {
@@ -73,9 +82,9 @@ impl InstrumentRepo {
             r#"
             INSERT INTO instruments (
                 id, secondary_id, venue_id, symbol, venue_symbol, instrument_type, base_asset_id, quote_asset_id, strike, maturity, option_type,
-                contract_size, price_precision, quantity_precision, base_precision, quote_precision, lot_size, tick_size, status
+                underlying_id, contract_size, price_precision, quantity_precision, base_precision, quote_precision, lot_size, tick_size, status
             ) VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,$11, $12, $13, $14, $15, $16, $17, $18, $19
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,$11, $12, $13, $14, $15, $16, $17, $18, $19, $20
             )
             "#,
             instrument.id,
@@ -89,6 +98,7 @@ impl InstrumentRepo {
             instrument.strike,
             instrument.maturity,
             instrument.option_type as Option<InstrumentOptionType>,
+            instrument.underlying_id,
             instrument.contract_size,
             instrument.price_precision,
             instrument.quantity_precision,
@@ -99,6 +109,21 @@ impl InstrumentRepo {
             instrument.status as InstrumentStatus
         )
         .execute(&self.pool)
+        .timed("instruments.insert")
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_status(&self, id: &Uuid, status: InstrumentStatus) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            UPDATE instruments SET status = $2 WHERE id = $1
+            "#,
+            id,
+            status as InstrumentStatus
+        )
+        .execute(&self.pool)
+        .timed("instruments.update_status")
         .await?;
         Ok(())
     }
@@ -119,6 +144,7 @@ impl InstrumentRepo {
                 strike,
                 maturity,
                 option_type AS "option_type:InstrumentOptionType",
+                underlying_id,
                 contract_size,
                 price_precision,
                 quantity_precision,
@@ -133,6 +159,7 @@ impl InstrumentRepo {
             id,
         )
         .fetch_optional(&self.pool)
+        .timed("instruments.read_by_id")
         .await?;
 
         match instrument {
@@ -158,6 +185,7 @@ impl InstrumentRepo {
                 strike,
                 maturity,
                 option_type AS "option_type:InstrumentOptionType",
+                underlying_id,
                 contract_size,
                 price_precision,
                 quantity_precision,
@@ -172,6 +200,48 @@ impl InstrumentRepo {
             symbol,
         )
         .fetch_optional(&self.pool)
+        .timed("instruments.read_by_venue_symbol")
+        .await?;
+
+        match instrument {
+            Some(instrument) => Ok(instrument),
+            None => Err(PersistenceError::NotFound),
+        }
+    }
+
+    pub async fn read_by_symbol(&self, symbol: &str) -> Result<InstrumentDTO, PersistenceError> {
+        debug!("Instrument repo reading instrument by symbol: {}", symbol);
+        let instrument = sqlx::query_as!(
+            InstrumentDTO,
+            r#"
+            SELECT
+                id,
+                secondary_id,
+                venue_id,
+                symbol,
+                venue_symbol,
+                instrument_type AS "instrument_type:InstrumentType",
+                base_asset_id,
+                quote_asset_id,
+                strike,
+                maturity,
+                option_type AS "option_type:InstrumentOptionType",
+                underlying_id,
+                contract_size,
+                price_precision,
+                quantity_precision,
+                base_precision,
+                quote_precision,
+                lot_size,
+                tick_size,
+                status AS "status:InstrumentStatus"
+            FROM instruments
+            WHERE symbol = $1
+            "#,
+            symbol,
+        )
+        .fetch_optional(&self.pool)
+        .timed("instruments.read_by_symbol")
         .await?;
 
         match instrument {
@@ -179,4 +249,23 @@ impl InstrumentRepo {
             None => Err(PersistenceError::NotFound),
         }
     }
+
+    /// Symbols of all instruments that are not delisted.
+    pub async fn read_symbols(&self) -> Result<Vec<InstrumentSymbolDTO>, PersistenceError> {
+        let symbols = sqlx::query_as!(
+            InstrumentSymbolDTO,
+            r#"
+            SELECT
+                symbol,
+                venue_symbol
+            FROM instruments
+            WHERE status <> 'delisted'
+            ORDER BY symbol
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .timed("instruments.read_symbols")
+        .await?;
+        Ok(symbols)
+    }
 }
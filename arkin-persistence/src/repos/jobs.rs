@@ -0,0 +1,321 @@
+use std::time::Duration;
+
+use arkin_core::{Job, JobStatus};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone)]
+pub struct JobDTO {
+    pub id: Uuid,
+    pub name: String,
+    pub args: Vec<String>,
+    pub group_id: Option<Uuid>,
+    pub instruments: Vec<String>,
+    pub start_time: OffsetDateTime,
+    pub end_time: OffsetDateTime,
+    pub cursor_time: OffsetDateTime,
+    pub chunk_secs: i64,
+    pub status: JobStatus,
+    pub error: Option<String>,
+    pub worker: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl From<&Job> for JobDTO {
+    fn from(job: &Job) -> Self {
+        Self {
+            id: job.id,
+            name: job.name.clone(),
+            args: job.args.clone(),
+            group_id: job.group_id,
+            instruments: job.instruments.clone(),
+            start_time: job.start,
+            end_time: job.end,
+            cursor_time: job.cursor,
+            chunk_secs: job.chunk.as_secs() as i64,
+            status: job.status,
+            error: job.error.clone(),
+            worker: job.worker.clone(),
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+impl From<JobDTO> for Job {
+    fn from(job: JobDTO) -> Self {
+        Job {
+            id: job.id,
+            name: job.name,
+            args: job.args,
+            group_id: job.group_id,
+            instruments: job.instruments,
+            start: job.start_time,
+            end: job.end_time,
+            cursor: job.cursor_time,
+            chunk: Duration::from_secs(job.chunk_secs.max(0) as u64),
+            status: job.status,
+            error: job.error,
+            worker: job.worker,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct JobRepo {
+    pool: PgPool,
+}
+
+impl JobRepo {
+    pub async fn insert(&self, job: JobDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO jobs
+            (
+                id,
+                name,
+                args,
+                group_id,
+                instruments,
+                start_time,
+                end_time,
+                cursor_time,
+                chunk_secs,
+                status,
+                error,
+                worker,
+                created_at,
+                updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+            job.id,
+            job.name,
+            &job.args,
+            job.group_id,
+            &job.instruments,
+            job.start_time,
+            job.end_time,
+            job.cursor_time,
+            job.chunk_secs,
+            job.status as JobStatus,
+            job.error,
+            job.worker,
+            job.created_at,
+            job.updated_at,
+        )
+        .execute(&self.pool)
+        .timed("jobs.insert")
+        .await?;
+        Ok(())
+    }
+
+    pub async fn read_by_id(&self, id: &Uuid) -> Result<JobDTO, PersistenceError> {
+        let job = sqlx::query_as!(
+            JobDTO,
+            r#"
+            SELECT
+                id,
+                name,
+                args,
+                group_id,
+                instruments,
+                start_time,
+                end_time,
+                cursor_time,
+                chunk_secs,
+                status AS "status:JobStatus",
+                error,
+                worker,
+                created_at,
+                updated_at
+            FROM jobs
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .timed("jobs.read_by_id")
+        .await?;
+        match job {
+            Some(job) => Ok(job),
+            None => Err(PersistenceError::NotFound),
+        }
+    }
+
+    pub async fn read_all(&self) -> Result<Vec<JobDTO>, PersistenceError> {
+        let jobs = sqlx::query_as!(
+            JobDTO,
+            r#"
+            SELECT
+                id,
+                name,
+                args,
+                group_id,
+                instruments,
+                start_time,
+                end_time,
+                cursor_time,
+                chunk_secs,
+                status AS "status:JobStatus",
+                error,
+                worker,
+                created_at,
+                updated_at
+            FROM jobs
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .timed("jobs.read_all")
+        .await?;
+        Ok(jobs)
+    }
+
+    pub async fn read_by_group(&self, group_id: &Uuid) -> Result<Vec<JobDTO>, PersistenceError> {
+        let jobs = sqlx::query_as!(
+            JobDTO,
+            r#"
+            SELECT
+                id,
+                name,
+                args,
+                group_id,
+                instruments,
+                start_time,
+                end_time,
+                cursor_time,
+                chunk_secs,
+                status AS "status:JobStatus",
+                error,
+                worker,
+                created_at,
+                updated_at
+            FROM jobs
+            WHERE group_id = $1
+            ORDER BY created_at
+            "#,
+            group_id
+        )
+        .fetch_all(&self.pool)
+        .timed("jobs.read_by_group")
+        .await?;
+        Ok(jobs)
+    }
+
+    /// Mark the oldest queued job as running and return it. Skips jobs locked by other workers, so several workers
+    /// never claim the same job.
+    pub async fn claim_next(&self, worker: &str) -> Result<Option<JobDTO>, PersistenceError> {
+        let job = sqlx::query_as!(
+            JobDTO,
+            r#"
+            UPDATE jobs
+            SET status = 'running', worker = $1, updated_at = now()
+            WHERE id = (
+                SELECT id
+                FROM jobs
+                WHERE status = 'queued'
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING
+                id,
+                name,
+                args,
+                group_id,
+                instruments,
+                start_time,
+                end_time,
+                cursor_time,
+                chunk_secs,
+                status AS "status:JobStatus",
+                error,
+                worker,
+                created_at,
+                updated_at
+            "#,
+            worker,
+        )
+        .fetch_optional(&self.pool)
+        .timed("jobs.claim_next")
+        .await?;
+        Ok(job)
+    }
+
+    pub async fn update_status(
+        &self,
+        id: &Uuid,
+        status: JobStatus,
+        error: Option<String>,
+    ) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = $2, error = $3, updated_at = now()
+            WHERE id = $1
+            "#,
+            id,
+            status as JobStatus,
+            error,
+        )
+        .execute(&self.pool)
+        .timed("jobs.update_status")
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_cursor(&self, id: &Uuid, cursor: OffsetDateTime) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET cursor_time = $2, updated_at = now()
+            WHERE id = $1
+            "#,
+            id,
+            cursor,
+        )
+        .execute(&self.pool)
+        .timed("jobs.update_cursor")
+        .await?;
+        Ok(())
+    }
+
+    /// Tell other workers the job is still worked on.
+    pub async fn heartbeat(&self, id: &Uuid) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET updated_at = now()
+            WHERE id = $1 AND status = 'running'
+            "#,
+            id,
+        )
+        .execute(&self.pool)
+        .timed("jobs.heartbeat")
+        .await?;
+        Ok(())
+    }
+
+    /// Put running jobs that were not updated since `before` back in the queue, their worker is gone.
+    pub async fn requeue_stale(&self, before: OffsetDateTime) -> Result<u64, PersistenceError> {
+        let res = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'queued', updated_at = now()
+            WHERE status = 'running' AND updated_at < $1
+            "#,
+            before,
+        )
+        .execute(&self.pool)
+        .timed("jobs.requeue_stale")
+        .await?;
+        Ok(res.rows_affected())
+    }
+}
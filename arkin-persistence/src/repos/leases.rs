@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use sqlx::{postgres::types::PgInterval, PgPool};
+use typed_builder::TypedBuilder;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct LeaseRepo {
+    pool: PgPool,
+}
+
+impl LeaseRepo {
+    /// Take the lease if it is free or expired, or extend it if the holder already has it. The expiry is set with the
+    /// clock of the database, so the clocks of the instances don't matter.
+    pub async fn acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool, PersistenceError> {
+        let ttl = PgInterval::try_from(ttl).map_err(sqlx::Error::Encode)?;
+        let res = sqlx::query!(
+            r#"
+            INSERT INTO leases (name, holder, expires_at)
+            VALUES ($1, $2, now() + $3::interval)
+            ON CONFLICT (name) DO UPDATE
+            SET holder = EXCLUDED.holder, expires_at = EXCLUDED.expires_at
+            WHERE leases.holder = EXCLUDED.holder OR leases.expires_at < now()
+            "#,
+            name,
+            holder,
+            ttl,
+        )
+        .execute(&self.pool)
+        .timed("leases.acquire")
+        .await?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    /// Give up the lease, so a standby takes over right away instead of after the expiry.
+    pub async fn release(&self, name: &str, holder: &str) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM leases
+            WHERE name = $1 AND holder = $2
+            "#,
+            name,
+            holder,
+        )
+        .execute(&self.pool)
+        .timed("leases.release")
+        .await?;
+        Ok(())
+    }
+}
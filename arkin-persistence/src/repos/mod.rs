@@ -1,15 +1,26 @@
 mod insights_parquet;
 // mod trades_parquet;
+mod account_transfers;
 mod allocation;
+mod annotations;
 mod assets;
+mod books;
+mod checkpoints;
+mod daily_performance;
+mod execution_metrics;
 mod execution_orders;
 mod insights;
 mod instances;
 mod instruments;
+mod jobs;
+mod leases;
 mod pipelines;
 mod portfolio;
+mod predictions;
+mod rebalances;
 mod signals;
 mod strategies;
+mod symbol_overrides;
 mod ticks;
 mod trades;
 mod transactions;
@@ -18,16 +29,27 @@ mod venues;
 
 pub use insights_parquet::*;
 // pub use trades_parquet::*;
+pub use account_transfers::*;
 pub use allocation::*;
+pub use annotations::*;
 pub use assets::*;
+pub use books::*;
+pub use checkpoints::*;
+pub use daily_performance::*;
+pub use execution_metrics::*;
 pub use execution_orders::*;
 pub use insights::*;
 pub use instances::*;
 pub use instruments::*;
+pub use jobs::*;
+pub use leases::*;
 pub use pipelines::*;
 pub use portfolio::*;
+pub use predictions::*;
+pub use rebalances::*;
 pub use signals::*;
 pub use strategies::*;
+pub use symbol_overrides::*;
 pub use ticks::*;
 pub use trades::*;
 pub use transactions::*;
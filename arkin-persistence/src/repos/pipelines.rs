@@ -6,7 +6,7 @@ use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(FromRow)]
 pub struct PipelineDTO {
@@ -68,6 +68,7 @@ impl PipelineRepo {
             asset.updated_at,
         )
         .execute(&self.pool)
+        .timed("pipelines.insert")
         .await?;
         Ok(())
     }
@@ -88,6 +89,7 @@ impl PipelineRepo {
             id,
         )
         .fetch_optional(&self.pool)
+        .timed("pipelines.read_by_id")
         .await?;
 
         match pipeline {
@@ -112,6 +114,7 @@ impl PipelineRepo {
             name,
         )
         .fetch_optional(&self.pool)
+        .timed("pipelines.read_by_name")
         .await?;
 
         match pipeline {
@@ -119,4 +122,18 @@ impl PipelineRepo {
             None => Err(PersistenceError::NotFound),
         }
     }
+
+    pub async fn read_names(&self) -> Result<Vec<String>, PersistenceError> {
+        let names = sqlx::query_scalar!(
+            r#"
+            SELECT name
+            FROM pipelines
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .timed("pipelines.read_names")
+        .await?;
+        Ok(names)
+    }
 }
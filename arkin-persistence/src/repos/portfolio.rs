@@ -6,7 +6,7 @@ use time::OffsetDateTime;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(FromRow)]
 pub struct PortfolioDTO {
@@ -68,6 +68,7 @@ impl PortfolioRepo {
             portfolio.updated_at,
         )
         .execute(&self.pool)
+        .timed("portfolio.insert")
         .await?;
         Ok(())
     }
@@ -88,6 +89,7 @@ impl PortfolioRepo {
             id,
         )
         .fetch_optional(&self.pool)
+        .timed("portfolio.read_by_id")
         .await?;
 
         match portfolio {
@@ -112,6 +114,7 @@ impl PortfolioRepo {
             name,
         )
         .fetch_optional(&self.pool)
+        .timed("portfolio.read_by_name")
         .await?;
 
         match portfolio {
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{prelude::*, PgPool};
+use time::OffsetDateTime;
+use tracing::debug;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::Prediction;
+
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
+
+const FIELD_COUNT: usize = 10;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PredictionDTO {
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub pipeline_id: Uuid,
+    pub instrument_id: Uuid,
+    pub feature_id: String,
+    pub model_name: String,
+    pub model_version: String,
+    pub feature_hash: String,
+    pub value: Decimal,
+    pub latency_us: i64,
+}
+
+impl From<Arc<Prediction>> for PredictionDTO {
+    fn from(prediction: Arc<Prediction>) -> Self {
+        Self {
+            id: prediction.id,
+            event_time: prediction.event_time,
+            pipeline_id: prediction.pipeline.id,
+            instrument_id: prediction.instrument.id,
+            feature_id: prediction.feature_id.to_string(),
+            model_name: prediction.model_name.clone(),
+            model_version: prediction.model_version.clone(),
+            feature_hash: prediction.feature_hash.clone(),
+            value: prediction.value,
+            latency_us: prediction.latency.as_micros() as i64,
+        }
+    }
+}
+
+/// Distribution of the predictions of one model version.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PredictionDistributionDTO {
+    pub model_version: String,
+    pub count: i64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub p05: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub mean_latency_us: f64,
+}
+
+/// Predictions of two versions for the same instrument and time.
+#[derive(Debug, Clone, FromRow)]
+pub struct PredictionPairDTO {
+    pub event_time: OffsetDateTime,
+    pub instrument_id: Uuid,
+    pub baseline: Decimal,
+    pub candidate: Decimal,
+    pub same_features: bool,
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PredictionRepo {
+    pool: PgPool,
+    /// Pool used for heavy reads, falls back to the primary pool
+    #[builder(default)]
+    read_pool: Option<PgPool>,
+}
+
+impl PredictionRepo {
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    pub async fn insert_batch(&self, predictions: &[PredictionDTO]) -> Result<(), PersistenceError> {
+        for batch in predictions.chunks(BIND_LIMIT / FIELD_COUNT) {
+            let mut query_builder = sqlx::QueryBuilder::new(
+                r#"
+                INSERT INTO predictions
+                (
+                    id,
+                    event_time,
+                    pipeline_id,
+                    instrument_id,
+                    feature_id,
+                    model_name,
+                    model_version,
+                    feature_hash,
+                    value,
+                    latency_us
+                )
+                "#,
+            );
+            query_builder.push_values(batch, |mut b, prediction| {
+                b.push_bind(prediction.id)
+                    .push_bind(prediction.event_time)
+                    .push_bind(prediction.pipeline_id)
+                    .push_bind(prediction.instrument_id)
+                    .push_bind(prediction.feature_id.clone())
+                    .push_bind(prediction.model_name.clone())
+                    .push_bind(prediction.model_version.clone())
+                    .push_bind(prediction.feature_hash.clone())
+                    .push_bind(prediction.value)
+                    .push_bind(prediction.latency_us);
+            });
+            query_builder.push(
+                "ON CONFLICT (model_name, model_version, instrument_id, event_time) DO UPDATE SET value = EXCLUDED.value, feature_hash = EXCLUDED.feature_hash, latency_us = EXCLUDED.latency_us",
+            );
+            query_builder
+                .build()
+                .execute(&self.pool)
+                .timed("predictions.insert_batch")
+                .await?;
+        }
+        debug!("Saved {} predictions", predictions.len());
+        Ok(())
+    }
+
+    /// Distribution of the predictions per version of a model in [from, to).
+    pub async fn read_distributions(
+        &self,
+        model_name: &str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<PredictionDistributionDTO>, PersistenceError> {
+        let distributions = sqlx::query_as!(
+            PredictionDistributionDTO,
+            r#"
+            SELECT
+                model_version,
+                COUNT(*) AS "count!",
+                AVG(value)::float8 AS "mean!",
+                COALESCE(STDDEV_SAMP(value)::float8, 0) AS "std_dev!",
+                percentile_cont(0.05) WITHIN GROUP (ORDER BY value::float8) AS "p05!",
+                percentile_cont(0.25) WITHIN GROUP (ORDER BY value::float8) AS "p25!",
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY value::float8) AS "p50!",
+                percentile_cont(0.75) WITHIN GROUP (ORDER BY value::float8) AS "p75!",
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY value::float8) AS "p95!",
+                AVG(latency_us)::float8 AS "mean_latency_us!"
+            FROM predictions
+            WHERE model_name = $1 AND event_time >= $2 AND event_time < $3
+            GROUP BY model_version
+            ORDER BY model_version
+            "#,
+            model_name,
+            from,
+            to,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("predictions.read_distributions", || {
+            format!("model={} from={} to={}", model_name, from, to)
+        })
+        .await?;
+        Ok(distributions)
+    }
+
+    /// Predictions of two versions of a model made for the same instrument at the same time in [from, to).
+    pub async fn read_pairs(
+        &self,
+        model_name: &str,
+        baseline: &str,
+        candidate: &str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<PredictionPairDTO>, PersistenceError> {
+        let pairs = sqlx::query_as!(
+            PredictionPairDTO,
+            r#"
+            SELECT
+                b.event_time,
+                b.instrument_id,
+                b.value AS baseline,
+                c.value AS candidate,
+                b.feature_hash = c.feature_hash AS "same_features!"
+            FROM predictions b
+            JOIN predictions c
+                ON c.model_name = b.model_name
+                AND c.instrument_id = b.instrument_id
+                AND c.event_time = b.event_time
+            WHERE b.model_name = $1 AND b.model_version = $2 AND c.model_version = $3
+                AND b.event_time >= $4 AND b.event_time < $5
+            ORDER BY b.event_time ASC
+            "#,
+            model_name,
+            baseline,
+            candidate,
+            from,
+            to,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("predictions.read_pairs", || {
+            format!("model={} baseline={} candidate={}", model_name, baseline, candidate)
+        })
+        .await?;
+        Ok(pairs)
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use sqlx::{prelude::*, PgPool};
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::{Rebalance, RebalanceTrigger};
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(FromRow)]
+pub struct RebalanceDTO {
+    pub id: Uuid,
+    pub event_time: OffsetDateTime,
+    pub portfolio_id: Uuid,
+    pub trigger: RebalanceTrigger,
+    pub detail: String,
+}
+
+impl From<Arc<Rebalance>> for RebalanceDTO {
+    fn from(rebalance: Arc<Rebalance>) -> Self {
+        Self {
+            id: rebalance.id,
+            event_time: rebalance.event_time,
+            portfolio_id: rebalance.portfolio.id,
+            trigger: rebalance.trigger,
+            detail: rebalance.detail.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RebalanceRepo {
+    pool: PgPool,
+}
+
+impl RebalanceRepo {
+    pub async fn insert(&self, rebalance: RebalanceDTO) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO rebalances
+            (
+                id,
+                event_time,
+                portfolio_id,
+                trigger,
+                detail
+            ) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            rebalance.id,
+            rebalance.event_time,
+            rebalance.portfolio_id,
+            rebalance.trigger as RebalanceTrigger,
+            rebalance.detail
+        )
+        .execute(&self.pool)
+        .timed("rebalances.insert")
+        .await?;
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@ use typed_builder::TypedBuilder;
 use arkin_core::prelude::*;
 use uuid::Uuid;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 5;
 
@@ -56,6 +56,7 @@ impl SignalRepo {
             signal.weight,
         )
         .execute(&self.pool)
+        .timed("signals.insert")
         .await?;
         Ok(())
     }
@@ -89,7 +90,7 @@ impl SignalRepo {
 
             let query = query_builder.build();
 
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("signals.insert_batch").await?;
         }
         debug!("Saved {} venue signals", signals.len());
         Ok(())
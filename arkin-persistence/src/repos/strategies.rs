@@ -6,7 +6,7 @@ use typed_builder::TypedBuilder;
 use arkin_core::prelude::*;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(Debug, Clone)]
 pub struct StrategyDTO {
@@ -67,6 +67,7 @@ impl StrategyRepo {
             strategy.description,
         )
         .execute(&self.pool)
+        .timed("strategies.insert")
         .await?;
         Ok(())
     }
@@ -85,6 +86,7 @@ impl StrategyRepo {
             id
         )
         .fetch_optional(&self.pool) // -> Vec<Country>
+        .timed("strategies.read_by_id")
         .await?;
         match strategy {
             Some(strategy) => Ok(strategy),
@@ -106,6 +108,7 @@ impl StrategyRepo {
             name
         )
         .fetch_optional(&self.pool) // -> Vec<Country>
+        .timed("strategies.read_by_name")
         .await?;
         match strategy {
             Some(strategy) => Ok(strategy),
@@ -127,6 +130,7 @@ impl StrategyRepo {
             strategy.description
         )
         .execute(&self.pool)
+        .timed("strategies.update")
         .await?;
         Ok(())
     }
@@ -140,6 +144,7 @@ impl StrategyRepo {
             id
         )
         .execute(&self.pool)
+        .timed("strategies.delete")
         .await?;
         Ok(())
     }
@@ -181,4 +186,18 @@ pub mod tests {
         let result = repo.delete(&strategy.id).await;
         assert!(result.is_ok());
     }
+
+    pub async fn read_names(&self) -> Result<Vec<String>, PersistenceError> {
+        let names = sqlx::query_scalar!(
+            r#"
+            SELECT name
+            FROM strategies
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .timed("strategies.read_names")
+        .await?;
+        Ok(names)
+    }
 }
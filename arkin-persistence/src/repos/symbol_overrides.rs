@@ -0,0 +1,76 @@
+use sqlx::{prelude::*, PgPool};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::SymbolOverride;
+
+use crate::{PersistenceError, TimedQuery};
+
+#[derive(FromRow)]
+pub struct SymbolOverrideDTO {
+    pub venue: String,
+    pub venue_symbol: String,
+    pub canonical_symbol: String,
+}
+
+impl From<SymbolOverrideDTO> for SymbolOverride {
+    fn from(symbol_override: SymbolOverrideDTO) -> Self {
+        Self {
+            venue: symbol_override.venue,
+            venue_symbol: symbol_override.venue_symbol,
+            canonical_symbol: symbol_override.canonical_symbol,
+        }
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SymbolOverrideRepo {
+    pool: PgPool,
+}
+
+impl SymbolOverrideRepo {
+    /// Insert or replace the override of a venue symbol.
+    pub async fn upsert(
+        &self,
+        venue_id: &Uuid,
+        venue_symbol: &str,
+        canonical_symbol: &str,
+    ) -> Result<(), PersistenceError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO symbol_overrides
+            (
+                venue_id,
+                venue_symbol,
+                canonical_symbol
+            ) VALUES ($1, $2, $3)
+            ON CONFLICT (venue_id, venue_symbol) DO UPDATE SET canonical_symbol = EXCLUDED.canonical_symbol
+            "#,
+            venue_id,
+            venue_symbol,
+            canonical_symbol,
+        )
+        .execute(&self.pool)
+        .timed("symbol_overrides.upsert")
+        .await?;
+        Ok(())
+    }
+
+    pub async fn read_all(&self) -> Result<Vec<SymbolOverrideDTO>, PersistenceError> {
+        let overrides = sqlx::query_as!(
+            SymbolOverrideDTO,
+            r#"
+            SELECT
+                venues.name AS venue,
+                symbol_overrides.venue_symbol,
+                symbol_overrides.canonical_symbol
+            FROM symbol_overrides
+            JOIN venues ON venues.id = symbol_overrides.venue_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .timed("symbol_overrides.read_all")
+        .await?;
+        Ok(overrides)
+    }
+}
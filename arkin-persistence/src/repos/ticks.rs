@@ -7,7 +7,7 @@ use uuid::Uuid;
 
 use arkin_core::prelude::*;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 7;
 
@@ -40,9 +40,16 @@ impl From<Arc<Tick>> for TickDTO {
 
 pub struct TickRepo {
     pool: PgPool,
+    /// Pool used for heavy reads, falls back to the primary pool
+    #[builder(default)]
+    read_pool: Option<PgPool>,
 }
 
 impl TickRepo {
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
     pub async fn insert(&self, tick: TickDTO) -> Result<(), PersistenceError> {
         sqlx::query!(
             r#"
@@ -67,6 +74,7 @@ impl TickRepo {
             tick.ask_quantity,
         )
         .execute(&self.pool)
+        .timed("ticks.insert")
         .await?;
         Ok(())
     }
@@ -94,7 +102,7 @@ impl TickRepo {
             query_builder.push("ON CONFLICT (instrument_id, tick_id, event_time) DO NOTHING");
             let query = query_builder.build();
 
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("ticks.insert_batch").await?;
         }
         Ok(())
     }
@@ -123,6 +131,7 @@ impl TickRepo {
             instrument_id,
         )
         .fetch_optional(&self.pool)
+        .timed("ticks.read_tick")
         .await?;
 
         Ok(tick)
@@ -153,7 +162,10 @@ impl TickRepo {
             end,
             instrument_ids,
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
+        .timed_with("ticks.read_range", || {
+            format!("instruments={:?} from={} to={}", instrument_ids, start, end)
+        })
         .await?;
 
         Ok(ticks)
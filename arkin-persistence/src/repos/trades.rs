@@ -1,14 +1,15 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use rust_decimal::Decimal;
-use sqlx::{FromRow, PgPool};
+use sqlx::{postgres::types::PgInterval, FromRow, PgPool};
 use time::OffsetDateTime;
+use tracing::debug;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
 use arkin_core::prelude::*;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 6;
 
@@ -35,13 +36,34 @@ impl From<Arc<Trade>> for TradeDTO {
     }
 }
 
+/// OHLCV bar, either from the `trades_1m` continuous aggregate or aggregated from raw trades.
+#[derive(Debug, FromRow)]
+pub struct BarDTO {
+    pub event_time: OffsetDateTime,
+    pub instrument_id: Uuid,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub notional_volume: Decimal,
+    pub trade_count: i64,
+}
+
 #[derive(Debug, Clone, TypedBuilder)]
 
 pub struct TradeRepo {
     pool: PgPool,
+    /// Pool used for heavy reads, falls back to the primary pool
+    #[builder(default)]
+    read_pool: Option<PgPool>,
 }
 
 impl TradeRepo {
+    fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
     pub async fn insert(&self, trade: TradeDTO) -> Result<(), PersistenceError> {
         sqlx::query!(
             r#"
@@ -64,6 +86,7 @@ impl TradeRepo {
             trade.quantity,
         )
         .execute(&self.pool)
+        .timed("trades.insert")
         .await?;
         Ok(())
     }
@@ -92,7 +115,7 @@ impl TradeRepo {
             query_builder.push(" ON CONFLICT (instrument_id, trade_id, event_time) DO NOTHING");
 
             let query = query_builder.build();
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("trades.insert_batch").await?;
         }
         Ok(())
     }
@@ -121,9 +144,156 @@ impl TradeRepo {
             from,
             to,
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.read_pool())
+        .timed_with("trades.read_range", || {
+            format!("instruments={:?} from={} to={}", instrument_ids, from, to)
+        })
         .await?;
 
         Ok(trades)
     }
+
+    /// Read one minute bars from the `trades_1m` continuous aggregate.
+    pub async fn read_bars_1m(
+        &self,
+        instrument_ids: &[Uuid],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<BarDTO>, PersistenceError> {
+        let bars = sqlx::query_as!(
+            BarDTO,
+            r#"
+            SELECT
+                event_time as "event_time!",
+                instrument_id as "instrument_id!",
+                open as "open!",
+                high as "high!",
+                low as "low!",
+                close as "close!",
+                volume as "volume!",
+                notional_volume as "notional_volume!",
+                trade_count as "trade_count!"
+            FROM trades_1m
+            WHERE instrument_id = ANY($1) AND event_time >= $2 AND event_time < $3
+            ORDER BY event_time ASC
+            "#,
+            instrument_ids,
+            from,
+            to,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("trades.read_bars_1m", || {
+            format!("instruments={:?} from={} to={}", instrument_ids, from, to)
+        })
+        .await?;
+
+        Ok(bars)
+    }
+
+    /// Materialize the `trades_1m` continuous aggregate for [from, to). Its policy only refreshes the last days, so
+    /// backfilled history has to be refreshed after it is written.
+    pub async fn refresh_bars(&self, from: OffsetDateTime, to: OffsetDateTime) -> Result<(), PersistenceError> {
+        // Only whole buckets within the window are refreshed, so widen it to the minutes around it
+        let minute = |t: OffsetDateTime| t - Duration::new(t.second() as u64, t.nanosecond());
+        let from = minute(from);
+        let to = if minute(to) < to {
+            minute(to) + Duration::from_secs(60)
+        } else {
+            to
+        };
+        sqlx::query("CALL refresh_continuous_aggregate('trades_1m', $1, $2)")
+            .bind(from)
+            .bind(to)
+            .execute(&self.pool)
+            .timed_with("trades.refresh_bars", || format!("from={} to={}", from, to))
+            .await?;
+        Ok(())
+    }
+
+    /// Read OHLCV bars of an arbitrary frequency, aggregated server side.
+    ///
+    /// Frequencies that are a whole number of minutes are rolled up from the `trades_1m` continuous
+    /// aggregate, other frequencies are bucketed from the raw trades. So are ranges the aggregate has no bars for,
+    /// like history backfilled without refreshing it.
+    pub async fn read_bars(
+        &self,
+        instrument_ids: &[Uuid],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        frequency: Duration,
+    ) -> Result<Vec<BarDTO>, PersistenceError> {
+        let interval = PgInterval::try_from(frequency).map_err(sqlx::Error::Encode)?;
+
+        if frequency.as_secs() % 60 == 0 && frequency.subsec_nanos() == 0 {
+            let bars = sqlx::query_as!(
+                BarDTO,
+                r#"
+                SELECT
+                    time_bucket($4::interval, event_time) as "event_time!",
+                    instrument_id as "instrument_id!",
+                    first(open, event_time) as "open!",
+                    max(high) as "high!",
+                    min(low) as "low!",
+                    last(close, event_time) as "close!",
+                    sum(volume) as "volume!",
+                    sum(notional_volume) as "notional_volume!",
+                    sum(trade_count)::bigint as "trade_count!"
+                FROM trades_1m
+                WHERE instrument_id = ANY($1) AND event_time >= $2 AND event_time < $3
+                GROUP BY 1, 2
+                ORDER BY 1 ASC
+                "#,
+                instrument_ids,
+                from,
+                to,
+                interval,
+            )
+            .fetch_all(self.read_pool())
+            .timed_with("trades.read_bars", || {
+                format!(
+                    "instruments={:?} from={} to={} frequency={:?}",
+                    instrument_ids, from, to, frequency
+                )
+            })
+            .await?;
+            if !bars.is_empty() {
+                return Ok(bars);
+            }
+            debug!("No bars materialized from {} to {}, aggregating the raw trades", from, to);
+        }
+
+        let bars = sqlx::query_as!(
+            BarDTO,
+            r#"
+            SELECT
+                time_bucket($4::interval, event_time) as "event_time!",
+                instrument_id as "instrument_id!",
+                first(price, event_time) as "open!",
+                max(price) as "high!",
+                min(price) as "low!",
+                last(price, event_time) as "close!",
+                sum(quantity) as "volume!",
+                sum(price * quantity) as "notional_volume!",
+                count(*) as "trade_count!"
+            FROM trades
+            WHERE instrument_id = ANY($1) AND event_time >= $2 AND event_time < $3
+            GROUP BY 1, 2
+            ORDER BY 1 ASC
+            "#,
+            instrument_ids,
+            from,
+            to,
+            interval,
+        )
+        .fetch_all(self.read_pool())
+        .timed_with("trades.read_bars", || {
+            format!(
+                "instruments={:?} from={} to={} frequency={:?}",
+                instrument_ids, from, to, frequency
+            )
+        })
+        .await?;
+
+        Ok(bars)
+    }
 }
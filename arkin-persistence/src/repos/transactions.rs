@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use arkin_core::prelude::*;
 
-use crate::{PersistenceError, BIND_LIMIT};
+use crate::{PersistenceError, TimedQuery, BIND_LIMIT};
 
 const FIELD_COUNT: usize = 9;
 
@@ -75,6 +75,7 @@ impl TransactionRepo {
             transaction.total_value,
         )
         .execute(&self.pool)
+        .timed("transactions.insert")
         .await?;
         Ok(())
     }
@@ -117,7 +118,7 @@ impl TransactionRepo {
             // query_builder.push("ON CONFLICT (instrument_id, tick_id, event_time) DO NOTHING");
             let query = query_builder.build();
 
-            query.execute(&self.pool).await?;
+            query.execute(&self.pool).timed("transactions.insert_batch").await?;
         }
         Ok(())
     }
@@ -8,7 +8,7 @@ use arkin_core::prelude::*;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(Debug, Clone)]
 pub struct VenueOrderDTO {
@@ -114,6 +114,7 @@ impl VenueOrderRepo {
             order.updated_at,
         )
         .execute(&self.pool)
+        .timed("venue_orders.insert")
         .await?;
         Ok(())
     }
@@ -138,6 +139,7 @@ impl VenueOrderRepo {
             order.updated_at,
         )
         .execute(&self.pool)
+        .timed("venue_orders.update")
         .await?;
         Ok(())
     }
@@ -151,6 +153,7 @@ impl VenueOrderRepo {
             id
         )
         .execute(&self.pool)
+        .timed("venue_orders.delete")
         .await?;
         Ok(())
     }
@@ -6,7 +6,7 @@ use uuid::Uuid;
 
 use arkin_core::{Venue, VenueType};
 
-use crate::PersistenceError;
+use crate::{PersistenceError, TimedQuery};
 
 #[derive(FromRow)]
 pub struct VenueDTO {
@@ -57,6 +57,7 @@ impl VenueRepo {
             venue.venue_type as VenueType,
         )
         .execute(&self.pool)
+        .timed("venues.insert")
         .await?;
         Ok(())
     }
@@ -75,6 +76,7 @@ impl VenueRepo {
             id,
         )
         .fetch_optional(&self.pool)
+        .timed("venues.read_by_id")
         .await?;
 
         match id {
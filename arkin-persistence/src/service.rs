@@ -1,9 +1,9 @@
-use std::{sync::Arc, time::Duration};
+use std::{future::Future, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions, PgSslMode};
 use sqlx::ConnectOptions;
-use tokio_util::sync::CancellationToken;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tracing::{debug, error, info};
 
 use arkin_core::prelude::*;
@@ -11,7 +11,8 @@ use arkin_core::prelude::*;
 use crate::repos::*;
 use crate::stores::*;
 use crate::traits::Persistor;
-use crate::{PersistenceConfig, PersistenceError};
+use crate::writer::BatchWriter;
+use crate::{DatabaseConfig, PersistenceConfig, PersistenceError, QueryStatsSnapshot, QUERY_STATS};
 
 #[derive(Debug)]
 pub struct PersistenceService {
@@ -20,23 +21,41 @@ pub struct PersistenceService {
     pub instance_store: Arc<InstanceStore>,
     pub portfolio_store: Arc<PortfolioStore>,
     pub transaction_store: Arc<TransactionStore>,
+    pub account_transfer_store: Arc<AccountTransferStore>,
     pub venue_store: Arc<VenueStore>,
     pub asset_store: Arc<AssetStore>,
     pub instrument_store: Arc<InstrumentStore>,
+    pub symbol_registry: Arc<SymbolRegistryStore>,
     pub pipeline_store: Arc<PipelineStore>,
     pub insights_store: Arc<InsightsStore>,
+    pub prediction_store: Arc<PredictionStore>,
     pub strategy_store: Arc<StrategyStore>,
+    pub job_store: Arc<JobStore>,
+    pub lease_store: Arc<LeaseStore>,
+    pub annotation_store: Arc<AnnotationStore>,
+    pub checkpoint_store: Arc<CheckpointStore>,
     pub signal_store: Arc<SignalStore>,
     pub allocation_store: Arc<AllocationStore>,
+    pub rebalance_store: Arc<RebalanceStore>,
+    pub daily_performance_store: Arc<DailyPerformanceStore>,
     pub execution_order_store: Arc<ExecutionOrderStore>,
+    pub execution_metrics_store: Arc<ExecutionMetricsStore>,
     pub venue_order_store: Arc<VenueOrderStore>,
     pub tick_store: Arc<TickStore>,
     pub trade_store: Arc<TradeStore>,
+    pub book_store: Arc<BookStore>,
+    pub trade_writer: Arc<BatchWriter<Arc<Trade>>>,
+    pub tick_writer: Arc<BatchWriter<Arc<Tick>>>,
+    pub book_writer: Arc<BatchWriter<Arc<Book>>>,
+    pub insight_writer: Arc<BatchWriter<Arc<Insight>>>,
+    pub prediction_writer: Arc<BatchWriter<Arc<Prediction>>>,
+    pub rebalance_writer: Arc<BatchWriter<Arc<Rebalance>>>,
+    pub daily_performance_writer: Arc<BatchWriter<Arc<DailyPerformance>>>,
+    pub execution_metrics_writer: Arc<BatchWriter<Arc<ExecutionMetrics>>>,
 }
 
 impl PersistenceService {
-    pub async fn from_config(config: &PersistenceConfig, pubsub: Arc<PubSub>) -> Self {
-        let db_config = config.database.clone();
+    fn create_pool(db_config: &DatabaseConfig) -> PgPool {
         let conn_options = PgConnectOptions::new()
             .host(&db_config.host)
             .port(db_config.port)
@@ -47,31 +66,56 @@ impl PersistenceService {
             .log_statements("DEBUG".parse().unwrap())
             .log_slow_statements("DEBUG".parse().unwrap(), Duration::from_secs(300));
 
-        let pool = PgPoolOptions::new()
+        PgPoolOptions::new()
             .min_connections(db_config.min_connections)
             .max_connections(db_config.max_connections)
             .idle_timeout(Duration::from_secs(db_config.idle_timeout))
             .acquire_timeout(Duration::from_secs(db_config.acquire_timeout))
             .max_lifetime(Duration::from_secs(db_config.max_lifetime))
-            .connect_lazy_with(conn_options);
+            .connect_lazy_with(conn_options)
+    }
+
+    pub async fn from_config(config: &PersistenceConfig, pubsub: Arc<PubSub>) -> Self {
+        QUERY_STATS.set_slow_threshold(Duration::from_millis(config.slow_query_threshold_ms));
+        METRICS.register("query_stats", |out| QUERY_STATS.render(out));
+
+        let pool = Self::create_pool(&config.database);
+        let read_pool = config.replica.as_ref().map(|replica| {
+            info!("Routing heavy reads to replica {}:{}", replica.host, replica.port);
+            Self::create_pool(replica)
+        });
 
         // Initialize repositories
         let instance_repo = InstanceRepo::builder().pool(pool.clone()).build();
         let portfolio = PortfolioRepo::builder().pool(pool.clone()).build();
         let transactions_repo = TransactionRepo::builder().pool(pool.clone()).build();
+        let account_transfer_repo = AccountTransferRepo::builder().pool(pool.clone()).build();
         let venue_repo = VenueRepo::builder().pool(pool.clone()).build();
         let asset_repo = AssetRepo::builder().pool(pool.clone()).build();
         let instrument_repo = InstrumentRepo::builder().pool(pool.clone()).build();
         let pipeline_repo = PipelineRepo::builder().pool(pool.clone()).build();
         let insights_repo = InsightsParquetRepo::new("insights_latest.parquet").await.unwrap();
-        // let insights_repo = InsightsRepo::builder().pool(pool.clone()).build();
+        let insights_db_repo = InsightsRepo::builder().pool(pool.clone()).read_pool(read_pool.clone()).build();
+        let prediction_repo = PredictionRepo::builder()
+            .pool(pool.clone())
+            .read_pool(read_pool.clone())
+            .build();
+        let job_repo = JobRepo::builder().pool(pool.clone()).build();
+        let lease_repo = LeaseRepo::builder().pool(pool.clone()).build();
+        let annotation_repo = AnnotationRepo::builder().pool(pool.clone()).build();
+        let checkpoint_repo = CheckpointRepo::builder().pool(pool.clone()).build();
         let strategy_repo = StrategyRepo::builder().pool(pool.clone()).build();
+        let symbol_override_repo = SymbolOverrideRepo::builder().pool(pool.clone()).build();
         let signal_repo = SignalRepo::builder().pool(pool.clone()).build();
         let allocation_repo = AllocationRepo::builder().pool(pool.clone()).build();
+        let rebalance_repo = RebalanceRepo::builder().pool(pool.clone()).build();
+        let daily_performance_repo = DailyPerformanceRepo::builder().pool(pool.clone()).build();
         let execution_order_repo = ExecutionOrderRepo::builder().pool(pool.clone()).build();
+        let execution_metrics_repo = ExecutionMetricsRepo::builder().pool(pool.clone()).build();
         let venue_order_repo = VenueOrderRepo::builder().pool(pool.clone()).build();
-        let tick_repo = TickRepo::builder().pool(pool.clone()).build();
-        let trade_repo = TradeRepo::builder().pool(pool.clone()).build();
+        let tick_repo = TickRepo::builder().pool(pool.clone()).read_pool(read_pool.clone()).build();
+        let trade_repo = TradeRepo::builder().pool(pool.clone()).read_pool(read_pool.clone()).build();
+        let book_repo = BookRepo::builder().pool(pool.clone()).read_pool(read_pool.clone()).build();
         // let trade_repo = TradeParquetRepo::new().await.unwrap();
 
         // Initialize stores
@@ -83,6 +127,11 @@ impl PersistenceService {
                 .buffer_size(config.batch_size)
                 .build(),
         );
+        let account_transfer_store = Arc::new(
+            AccountTransferStore::builder()
+                .account_transfer_repo(account_transfer_repo)
+                .build(),
+        );
         let venue_store = Arc::new(VenueStore::builder().venue_repo(venue_repo).build());
         let asset_store = Arc::new(AssetStore::builder().asset_repo(asset_repo.to_owned()).build());
         let instrument_store = Arc::new(
@@ -92,21 +141,49 @@ impl PersistenceService {
                 .venue_store(venue_store.to_owned())
                 .build(),
         );
+        let symbol_registry = Arc::new(
+            SymbolRegistryStore::builder()
+                .symbol_override_repo(symbol_override_repo)
+                .instrument_store(instrument_store.to_owned())
+                .build(),
+        );
         let pipeline_store = Arc::new(PipelineStore::builder().pipeline_repo(pipeline_repo.to_owned()).build());
         let insights_store = Arc::new(
             InsightsStore::builder()
                 .insights_repo(insights_repo.to_owned())
+                .insights_db_repo(insights_db_repo)
+                .buffer_size(config.batch_size)
+                .build(),
+        );
+        let prediction_store = Arc::new(
+            PredictionStore::builder()
+                .prediction_repo(prediction_repo)
                 .buffer_size(config.batch_size)
                 .build(),
         );
         let strategy_store = Arc::new(StrategyStore::builder().strategy_repo(strategy_repo.to_owned()).build());
+        let job_store = Arc::new(JobStore::builder().job_repo(job_repo).build());
+        let lease_store = Arc::new(LeaseStore::builder().lease_repo(lease_repo).build());
+        let annotation_store = Arc::new(AnnotationStore::builder().annotation_repo(annotation_repo).build());
+        let checkpoint_store = Arc::new(CheckpointStore::builder().checkpoint_repo(checkpoint_repo).build());
         let signal_store = Arc::new(SignalStore::builder().signal_repo(signal_repo.to_owned()).build());
         let allocation_store = Arc::new(AllocationStore::builder().allocation_repo(allocation_repo.to_owned()).build());
+        let rebalance_store = Arc::new(RebalanceStore::builder().rebalance_repo(rebalance_repo).build());
+        let daily_performance_store = Arc::new(
+            DailyPerformanceStore::builder()
+                .daily_performance_repo(daily_performance_repo)
+                .build(),
+        );
         let execution_order_store = Arc::new(
             ExecutionOrderStore::builder()
                 .execution_order_repo(execution_order_repo.to_owned())
                 .build(),
         );
+        let execution_metrics_store = Arc::new(
+            ExecutionMetricsStore::builder()
+                .execution_metrics_repo(execution_metrics_repo)
+                .build(),
+        );
         let venue_order_store =
             Arc::new(VenueOrderStore::builder().venue_order_repo(venue_order_repo.to_owned()).build());
         let tick_store = Arc::new(
@@ -123,6 +200,12 @@ impl PersistenceService {
                 .buffer_size(config.batch_size)
                 .build(),
         );
+        let book_store = Arc::new(
+            BookStore::builder()
+                .book_repo(book_repo)
+                .instrument_store(instrument_store.to_owned())
+                .build(),
+        );
 
         Self {
             pubsub,
@@ -130,20 +213,86 @@ impl PersistenceService {
             instance_store,
             portfolio_store,
             transaction_store,
+            account_transfer_store,
             venue_store,
             asset_store,
             instrument_store,
+            symbol_registry,
             pipeline_store,
             insights_store,
+            prediction_store,
             strategy_store,
+            job_store,
+            lease_store,
+            annotation_store,
+            checkpoint_store,
             signal_store,
             allocation_store,
+            rebalance_store,
+            daily_performance_store,
             execution_order_store,
+            execution_metrics_store,
             venue_order_store,
             tick_store,
             trade_store,
+            book_store,
+            trade_writer: Arc::new(BatchWriter::new("trades", &config.writer, config.batch_size)),
+            tick_writer: Arc::new(BatchWriter::new("ticks", &config.writer, config.batch_size)),
+            book_writer: Arc::new(BatchWriter::new("book_updates", &config.writer, config.batch_size)),
+            insight_writer: Arc::new(BatchWriter::new("insights", &config.writer, config.batch_size)),
+            prediction_writer: Arc::new(BatchWriter::new("predictions", &config.writer, config.batch_size)),
+            rebalance_writer: Arc::new(BatchWriter::new("rebalances", &config.writer, config.batch_size)),
+            daily_performance_writer: Arc::new(BatchWriter::new(
+                "daily_performance",
+                &config.writer,
+                config.batch_size,
+            )),
+            execution_metrics_writer: Arc::new(BatchWriter::new(
+                "execution_metrics",
+                &config.writer,
+                config.batch_size,
+            )),
         }
     }
+
+    /// Wait until everything queued in the batch writers is written.
+    async fn drain_writers(&self) {
+        self.trade_writer.drain().await;
+        self.tick_writer.drain().await;
+        self.book_writer.drain().await;
+        self.insight_writer.drain().await;
+        self.prediction_writer.drain().await;
+        self.rebalance_writer.drain().await;
+        self.daily_performance_writer.drain().await;
+        self.execution_metrics_writer.drain().await;
+    }
+
+    fn export_writer_metrics(&self) {
+        self.trade_writer.export_metrics();
+        self.tick_writer.export_metrics();
+        self.book_writer.export_metrics();
+        self.insight_writer.export_metrics();
+        self.prediction_writer.export_metrics();
+        self.rebalance_writer.export_metrics();
+        self.daily_performance_writer.export_metrics();
+        self.execution_metrics_writer.export_metrics();
+    }
+
+    /// Latency statistics of all repository queries, sorted by total time spent.
+    pub fn query_stats(&self) -> Vec<QueryStatsSnapshot> {
+        QUERY_STATS.snapshot()
+    }
+}
+
+/// Run a batch writer on the tracker until shutdown, writing its batches with `flush`.
+fn spawn_writer<T, F, Fut>(tracker: &TaskTracker, writer: &Arc<BatchWriter<T>>, shutdown: &CancellationToken, flush: F)
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(Vec<T>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), PersistenceError>> + Send,
+{
+    let (writer, shutdown) = (writer.clone(), shutdown.clone());
+    tracker.spawn(async move { writer.run(flush, shutdown).await });
 }
 
 #[async_trait]
@@ -151,48 +300,180 @@ impl Persistor for PersistenceService {
     async fn start(&self, shutdown: CancellationToken) -> Result<(), PersistenceError> {
         info!("Starting persistence service...");
 
+        if let Err(e) = self.symbol_registry.load_overrides().await {
+            error!("Failed to load symbol overrides: {}", e);
+        }
+
         let mut interval = tokio::time::interval(self.auto_commit_interval);
 
+        // Market data, insights and the other event records go through the batch writers so slow database writes
+        // never block this loop
+        let writer_tracker = TaskTracker::new();
+        let writer_shutdown = CancellationToken::new();
+        let store = self.trade_store.clone();
+        spawn_writer(&writer_tracker, &self.trade_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move { store.insert_batch(batch).await }
+        });
+        let store = self.tick_store.clone();
+        spawn_writer(&writer_tracker, &self.tick_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move { store.insert_batch(batch).await }
+        });
+        let store = self.book_store.clone();
+        spawn_writer(&writer_tracker, &self.book_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move { store.insert_batch(batch).await }
+        });
+        let store = self.insights_store.clone();
+        spawn_writer(&writer_tracker, &self.insight_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move { store.insert_batch(batch).await }
+        });
+        let store = self.prediction_store.clone();
+        spawn_writer(&writer_tracker, &self.prediction_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move { store.insert_batch(batch).await }
+        });
+        // Writing these is idempotent, so retrying a batch that failed halfway doesn't duplicate its first records
+        let store = self.rebalance_store.clone();
+        spawn_writer(&writer_tracker, &self.rebalance_writer, &writer_shutdown, move |batch| {
+            let store = store.clone();
+            async move {
+                for rebalance in batch {
+                    store.insert(rebalance).await?;
+                }
+                Ok(())
+            }
+        });
+        let store = self.daily_performance_store.clone();
+        spawn_writer(
+            &writer_tracker,
+            &self.daily_performance_writer,
+            &writer_shutdown,
+            move |batch| {
+                let store = store.clone();
+                async move {
+                    for performance in batch {
+                        store.upsert(performance).await?;
+                    }
+                    Ok(())
+                }
+            },
+        );
+        let store = self.execution_metrics_store.clone();
+        spawn_writer(
+            &writer_tracker,
+            &self.execution_metrics_writer,
+            &writer_shutdown,
+            move |batch| {
+                let store = store.clone();
+                async move {
+                    for metrics in batch {
+                        store.insert(metrics).await?;
+                    }
+                    Ok(())
+                }
+            },
+        );
+
         let mut trades = self.pubsub.subscribe::<Trade>();
         let mut ticks = self.pubsub.subscribe::<Tick>();
+        let mut books = self.pubsub.subscribe::<Book>();
         let mut insight = self.pubsub.subscribe::<Insight>();
         let mut insight_tick = self.pubsub.subscribe::<InsightTick>();
+        let mut predictions = self.pubsub.subscribe::<Prediction>();
+        let mut rebalances = self.pubsub.subscribe::<Rebalance>();
+        let mut daily_performances = self.pubsub.subscribe::<DailyPerformance>();
+        let mut execution_metrics = self.pubsub.subscribe::<ExecutionMetrics>();
+        let mut wallet_transfers = self.pubsub.subscribe::<WalletTransfer>();
+        let mut account_transfers = self.pubsub.subscribe::<AccountTransferUpdate>();
+        let mut instrument_statuses = self.pubsub.subscribe::<InstrumentStatusUpdate>();
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
 
         loop {
             tokio::select! {
                     Ok(trade) = trades.recv() => {
-                        if let Err(e) = self.trade_store.insert_buffered(trade).await {
-                            error!("Failed to insert trade: {}", e);
-                        }
+                        self.trade_store.update_trade_cache(trade.clone()).await;
+                        self.trade_writer.write(trade).await;
                     }
                     Ok(tick) = ticks.recv() => {
-                        if let Err(e) = self.tick_store.insert_buffered(tick).await {
-                            error!("Failed to insert tick: {}", e);
-                        }
+                        self.tick_store.update_tick_cache(tick.clone()).await;
+                        self.tick_writer.write(tick).await;
+                    }
+                    Ok(book) = books.recv() => {
+                        self.book_writer.write(book).await;
                     }
                     Ok(insight) = insight.recv() => {
-                        if let Err(e) = self.insights_store.insert_buffered(insight).await {
-                            error!("Failed to insert insight: {}", e);
+                        if insight.persist {
+                            self.insight_writer.write(insight).await;
                         }
                     }
                     Ok(tick) = insight_tick.recv() => {
-                        if let Err(e) = self.insights_store.insert_buffered_vec(tick.insights.clone()).await {
-                            error!("Failed to insert insight tick: {}", e);
+                        for insight in tick.insights.iter().filter(|i| i.persist) {
+                            self.insight_writer.write(insight.clone()).await;
+                        }
+                    }
+                    Ok(prediction) = predictions.recv() => {
+                        self.prediction_writer.write(prediction).await;
+                    }
+                    Ok(rebalance) = rebalances.recv() => {
+                        self.rebalance_writer.write(rebalance).await;
+                    }
+                    Ok(performance) = daily_performances.recv() => {
+                        self.daily_performance_writer.write(performance).await;
+                    }
+                    Ok(metrics) = execution_metrics.recv() => {
+                        self.execution_metrics_writer.write(metrics).await;
+                    }
+                    Ok(transfer) = wallet_transfers.recv() => {
+                        if let Err(e) = self.transaction_store.insert(Arc::new(transfer.to_transaction())).await {
+                            error!("Failed to book wallet transfer {}: {}", transfer.tx_hash, e);
+                        }
+                    }
+                    Ok(update) = account_transfers.recv() => {
+                        if let Err(e) = self.account_transfer_store.insert(update.clone()).await {
+                            error!("Failed to store account transfer {}: {}", update.transfer.id, e);
+                        }
+                        let Some(transaction) = update.to_transaction() else {
+                            continue;
+                        };
+                        if let Err(e) = self.transaction_store.insert(Arc::new(transaction)).await {
+                            error!("Failed to book account transfer {}: {}", update.transfer.id, e);
+                        }
+                    }
+                    Ok(update) = instrument_statuses.recv() => {
+                        // New listings are only reported, they need to be set up before they can be stored
+                        if let Some(instrument) = &update.instrument {
+                            if let Err(e) = self.instrument_store.update_status(instrument, update.status.clone()).await {
+                                error!("Failed to update status of {}: {}", instrument, e);
+                            }
+                        }
+                    }
+                    Ok(finished) = simulation_finished.recv() => {
+                        info!("Simulation finished at {}, flushing persistence service...", finished.event_time);
+                        if let Err(e) = self.flush().await {
+                            error!("Failed to flush persistence service at simulation end: {}", e);
                         }
                     }
                     _ = interval.tick() => {
                         debug!("Auto commit persistence service...");
+                        self.export_writer_metrics();
                         if let Err(e) = self.flush().await {
                             error!("Failed to auto commit persistence service: {}", e);
                         }
                     }
                     _ = shutdown.cancelled() => {
+                        writer_shutdown.cancel();
+                        writer_tracker.close();
+                        writer_tracker.wait().await;
                         if let Err(e) = self.flush().await {
                             error!("Failed to commit persistence service on shutdown: {}", e);
                         }
                         if let Err(e) = self.close().await {
                             error!("Failed to close persistence service on shutdown: {}", e);
                         }
+                        QUERY_STATS.report();
                         break;
                     }
             }
@@ -202,10 +483,13 @@ impl Persistor for PersistenceService {
         Ok(())
     }
 
+    /// Write everything queued in the batch writers and buffered in the stores.
     async fn flush(&self) -> Result<(), PersistenceError> {
+        self.drain_writers().await;
         self.tick_store.flush().await?;
         self.trade_store.flush().await?;
         self.insights_store.flush().await?;
+        self.prediction_store.flush().await?;
         Ok(())
     }
 
@@ -0,0 +1,31 @@
+use std::{collections::HashMap, sync::Arc};
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+
+use arkin_core::AccountTransferUpdate;
+
+use crate::{repos::AccountTransferRepo, PersistenceError};
+
+/// Outcomes of the account transfers, written right away as they move funds.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct AccountTransferStore {
+    account_transfer_repo: AccountTransferRepo,
+}
+
+impl AccountTransferStore {
+    pub async fn insert(&self, update: Arc<AccountTransferUpdate>) -> Result<(), PersistenceError> {
+        self.account_transfer_repo.insert(update.into()).await
+    }
+
+    /// Quantity transferred per asset symbol at or after the given time.
+    pub async fn executed_since(&self, from: OffsetDateTime) -> Result<HashMap<String, Decimal>, PersistenceError> {
+        Ok(self
+            .account_transfer_repo
+            .read_executed_since(from)
+            .await?
+            .into_iter()
+            .collect())
+    }
+}
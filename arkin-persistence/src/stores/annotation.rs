@@ -0,0 +1,48 @@
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::Annotation;
+
+use crate::{repos::AnnotationRepo, PersistenceError};
+
+/// Annotations are written right away, they come from operators and not from the event stream.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct AnnotationStore {
+    annotation_repo: AnnotationRepo,
+}
+
+impl AnnotationStore {
+    pub async fn insert(&self, annotation: &Annotation) -> Result<(), PersistenceError> {
+        self.annotation_repo.insert(annotation.into()).await
+    }
+
+    pub async fn read_range(
+        &self,
+        from: OffsetDateTime,
+        till: OffsetDateTime,
+    ) -> Result<Vec<Annotation>, PersistenceError> {
+        Ok(self
+            .annotation_repo
+            .read_range(from, till)
+            .await?
+            .into_iter()
+            .map(Annotation::from)
+            .collect())
+    }
+
+    pub async fn read_by_target(&self, target_id: &Uuid) -> Result<Vec<Annotation>, PersistenceError> {
+        Ok(self
+            .annotation_repo
+            .read_by_target(target_id)
+            .await?
+            .into_iter()
+            .map(Annotation::from)
+            .collect())
+    }
+
+    /// Remove an annotation, false if there was none with the id.
+    pub async fn delete(&self, id: &Uuid) -> Result<bool, PersistenceError> {
+        self.annotation_repo.delete(id).await
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    repos::{BookLevelDTO, BookRepo},
+    PersistenceError,
+};
+
+use super::instrument::InstrumentStore;
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct BookStore {
+    instrument_store: Arc<InstrumentStore>,
+    book_repo: BookRepo,
+}
+
+impl BookStore {
+    /// Write a batch of book updates straight to the database.
+    pub async fn insert_batch(&self, books: Vec<Arc<Book>>) -> Result<(), PersistenceError> {
+        let levels = books.iter().flat_map(BookLevelDTO::from_book).collect::<Vec<_>>();
+        self.book_repo.insert_batch(levels).await
+    }
+
+    /// Read the book updates of the instruments in order of time, the levels an instrument updated at the same time
+    /// form one update.
+    pub async fn read_range(
+        &self,
+        instrument_ids: &[Uuid],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<Arc<Book>>, PersistenceError> {
+        let levels = self.book_repo.read_range(instrument_ids, from, to).await?;
+        let mut books = Vec::<Book>::new();
+        for dto in levels {
+            let level = BookUpdateSide::new(dto.price, dto.quantity);
+            let book = match books.last_mut() {
+                Some(book) if book.event_time == dto.event_time && book.instrument.id == dto.instrument_id => book,
+                _ => {
+                    let instrument = self.instrument_store.read_by_id(&dto.instrument_id).await?;
+                    books.push(Book::new(dto.event_time, instrument, Vec::new(), Vec::new()));
+                    books.last_mut().expect("Book was just pushed")
+                }
+            };
+            match dto.side {
+                MarketSide::Buy => book.bids.push(level),
+                MarketSide::Sell => book.asks.push(level),
+            }
+        }
+        Ok(books.into_iter().map(Arc::new).collect())
+    }
+}
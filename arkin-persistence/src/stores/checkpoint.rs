@@ -0,0 +1,45 @@
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::SimulationCheckpoint;
+
+use crate::{
+    repos::{CheckpointDTO, CheckpointRepo},
+    PersistenceError,
+};
+
+/// Checkpoints of simulation runs, stored by run name so a crashed backtest can continue from its last one.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CheckpointStore {
+    checkpoint_repo: CheckpointRepo,
+}
+
+impl CheckpointStore {
+    /// Store a checkpoint of the run, the older checkpoints of the run are removed once it is written.
+    pub async fn insert(&self, run: &str, checkpoint: &SimulationCheckpoint) -> Result<(), PersistenceError> {
+        let clock_time = checkpoint.clock.current_timestamp;
+        self.checkpoint_repo
+            .insert(CheckpointDTO {
+                id: Uuid::new_v4(),
+                run: run.to_owned(),
+                clock_time,
+                state: serde_json::to_string(checkpoint)?,
+                created_at: checkpoint.created_at,
+            })
+            .await?;
+        self.checkpoint_repo.delete_before(run, clock_time).await?;
+        Ok(())
+    }
+
+    pub async fn read_latest(&self, run: &str) -> Result<Option<SimulationCheckpoint>, PersistenceError> {
+        match self.checkpoint_repo.read_latest(run).await? {
+            Some(checkpoint) => Ok(Some(serde_json::from_str(&checkpoint.state)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn delete_before(&self, run: &str, clock_time: OffsetDateTime) -> Result<u64, PersistenceError> {
+        self.checkpoint_repo.delete_before(run, clock_time).await
+    }
+}
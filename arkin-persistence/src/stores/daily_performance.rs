@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use arkin_core::DailyPerformance;
+use typed_builder::TypedBuilder;
+
+use crate::{repos::DailyPerformanceRepo, PersistenceError};
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct DailyPerformanceStore {
+    daily_performance_repo: DailyPerformanceRepo,
+}
+
+impl DailyPerformanceStore {
+    pub async fn upsert(&self, performance: Arc<DailyPerformance>) -> Result<(), PersistenceError> {
+        self.daily_performance_repo.upsert(performance.into()).await
+    }
+}
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use arkin_core::ExecutionMetrics;
+use typed_builder::TypedBuilder;
+
+use crate::{repos::ExecutionMetricsRepo, PersistenceError};
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct ExecutionMetricsStore {
+    execution_metrics_repo: ExecutionMetricsRepo,
+}
+
+impl ExecutionMetricsStore {
+    pub async fn insert(&self, metrics: Arc<ExecutionMetrics>) -> Result<(), PersistenceError> {
+        self.execution_metrics_repo.insert(metrics.into()).await
+    }
+}
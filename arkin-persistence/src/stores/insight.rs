@@ -1,17 +1,23 @@
 use std::sync::Arc;
 
+use time::OffsetDateTime;
 use tokio::sync::Mutex;
 use tracing::{error, info};
 use typed_builder::TypedBuilder;
 
 use arkin_core::prelude::*;
 
-use crate::{repos::InsightsParquetRepo, PersistenceError};
+use crate::{
+    repos::{InsightsParquetRepo, InsightsRepo},
+    PersistenceError,
+};
 
 #[derive(Debug, Clone, TypedBuilder)]
 
 pub struct InsightsStore {
     insights_repo: InsightsParquetRepo,
+    /// Insights table the persisted insights are read back from
+    insights_db_repo: InsightsRepo,
     #[builder(default)]
     insights_buffer: Arc<Mutex<Vec<Arc<Insight>>>>,
     buffer_size: usize,
@@ -70,6 +76,15 @@ impl InsightsStore {
         self.insights_repo.close().await
     }
 
+    /// Write a batch of insights right away, the ones not to be persisted are left out.
+    pub async fn insert_batch(&self, insights: Vec<Arc<Insight>>) -> Result<(), PersistenceError> {
+        let insights = insights.into_iter().filter(|i| i.persist).map(|i| i.into()).collect::<Vec<_>>();
+        if insights.is_empty() {
+            return Ok(());
+        }
+        self.insights_repo.insert_batch(&insights).await
+    }
+
     pub async fn insert_buffered(&self, insight: Arc<Insight>) -> Result<(), PersistenceError> {
         if !insight.persist {
             return Ok(());
@@ -91,4 +106,35 @@ impl InsightsStore {
         lock.extend(insights);
         Ok(())
     }
+
+    /// Read the persisted insights of a pipeline in [from, to). Insights of instruments not in the list are skipped.
+    pub async fn read_range(
+        &self,
+        pipeline: &Arc<Pipeline>,
+        instruments: &[Arc<Instrument>],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<Arc<Insight>>, PersistenceError> {
+        let ids = instruments.iter().map(|i| i.id).collect::<Vec<_>>();
+        let dto = self.insights_db_repo.read_range(pipeline.id, &ids, from, to).await?;
+
+        let insights = dto
+            .into_iter()
+            .filter_map(|insight| {
+                let instrument = match insight.instrument_id {
+                    Some(id) => Some(instruments.iter().find(|i| i.id == id)?.clone()),
+                    None => None,
+                };
+                let insight = Insight::builder()
+                    .event_time(insight.event_time)
+                    .pipeline(pipeline.clone())
+                    .instrument(instrument)
+                    .feature_id(FeatureId::new(insight.feature_id))
+                    .value(insight.value)
+                    .build();
+                Some(Arc::new(insight))
+            })
+            .collect();
+        Ok(insights)
+    }
 }
@@ -5,9 +5,12 @@ use tracing::debug;
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use arkin_core::Instrument;
+use arkin_core::{Instrument, InstrumentStatus};
 
-use crate::{repos::InstrumentRepo, PersistenceError};
+use crate::{
+    repos::{InstrumentDTO, InstrumentRepo, InstrumentSymbolDTO},
+    PersistenceError,
+};
 
 use super::{asset::AssetStore, venue::VenueStore};
 
@@ -44,6 +47,57 @@ impl InstrumentStore {
         self.instrument_repo.insert(instrument.into()).await
     }
 
+    pub async fn update_status(
+        &self,
+        instrument: &Instrument,
+        status: InstrumentStatus,
+    ) -> Result<(), PersistenceError> {
+        self.instrument_repo.update_status(&instrument.id, status.clone()).await?;
+        let mut updated = instrument.clone();
+        updated.status = status;
+        self.update_instrument_cache(Arc::new(updated)).await;
+        Ok(())
+    }
+
+    async fn load(&self, instrument_dto: InstrumentDTO) -> Result<Arc<Instrument>, PersistenceError> {
+        let venue = self.venue_store.read_by_id(&instrument_dto.venue_id).await?;
+
+        let base_asset = self.asset_store.read_by_id(&instrument_dto.base_asset_id).await?;
+        let quote_asset = self.asset_store.read_by_id(&instrument_dto.quote_asset_id).await?;
+        let underlying = match instrument_dto.underlying_id {
+            Some(id) => Some(Box::pin(self.read_by_id(&id)).await?),
+            None => None,
+        };
+
+        let instrument = Instrument {
+            id: instrument_dto.id,
+            secondary_id: instrument_dto.secondary_id,
+            symbol: instrument_dto.symbol,
+            venue_symbol: instrument_dto.venue_symbol,
+            venue,
+            instrument_type: instrument_dto.instrument_type.into(),
+            base_asset,
+            quote_asset,
+            maturity: instrument_dto.maturity,
+            strike: instrument_dto.strike,
+            option_type: instrument_dto.option_type.map(|v| v.into()),
+            underlying,
+            contract_size: instrument_dto.contract_size,
+            price_precision: instrument_dto.price_precision as u32,
+            quantity_precision: instrument_dto.quantity_precision as u32,
+            base_precision: instrument_dto.base_precision as u32,
+            quote_precision: instrument_dto.quote_precision as u32,
+            tick_size: instrument_dto.tick_size,
+            lot_size: instrument_dto.lot_size,
+            status: instrument_dto.status.into(),
+        };
+
+        // Update cache
+        let instrument = Arc::new(instrument);
+        self.update_instrument_cache(instrument.clone()).await;
+        Ok(instrument)
+    }
+
     pub async fn read_by_id(&self, id: &Uuid) -> Result<Arc<Instrument>, PersistenceError> {
         // Check cache
         match self.read_cache_by_id(id).await {
@@ -51,37 +105,7 @@ impl InstrumentStore {
             None => {
                 debug!("Instrument not found in cache");
                 let instrument_dto = self.instrument_repo.read_by_id(id).await?;
-                let venue = self.venue_store.read_by_id(&instrument_dto.venue_id).await?;
-
-                let base_asset = self.asset_store.read_by_id(&instrument_dto.base_asset_id).await?;
-                let quote_asset = self.asset_store.read_by_id(&instrument_dto.quote_asset_id).await?;
-
-                let instrument = Instrument {
-                    id: instrument_dto.id,
-                    secondary_id: instrument_dto.secondary_id,
-                    symbol: instrument_dto.symbol,
-                    venue_symbol: instrument_dto.venue_symbol,
-                    venue,
-                    instrument_type: instrument_dto.instrument_type.into(),
-                    base_asset,
-                    quote_asset,
-                    maturity: instrument_dto.maturity,
-                    strike: instrument_dto.strike,
-                    option_type: instrument_dto.option_type.map(|v| v.into()),
-                    contract_size: instrument_dto.contract_size,
-                    price_precision: instrument_dto.price_precision as u32,
-                    quantity_precision: instrument_dto.quantity_precision as u32,
-                    base_precision: instrument_dto.base_precision as u32,
-                    quote_precision: instrument_dto.quote_precision as u32,
-                    tick_size: instrument_dto.tick_size,
-                    lot_size: instrument_dto.lot_size,
-                    status: instrument_dto.status.into(),
-                };
-
-                // Update cache
-                let instrument = Arc::new(instrument);
-                self.update_instrument_cache(instrument.clone()).await;
-                Ok(instrument)
+                self.load(instrument_dto).await
             }
         }
     }
@@ -93,38 +117,19 @@ impl InstrumentStore {
             None => {
                 debug!("Instrument not found in cache");
                 let instrument_dto = self.instrument_repo.read_by_venue_symbol(&venue_symbol).await?;
-                let venue = self.venue_store.read_by_id(&instrument_dto.venue_id).await?;
-
-                let base_asset = self.asset_store.read_by_id(&instrument_dto.base_asset_id).await?;
-                let quote_asset = self.asset_store.read_by_id(&instrument_dto.quote_asset_id).await?;
-
-                let instrument = Instrument {
-                    id: instrument_dto.id,
-                    secondary_id: instrument_dto.secondary_id,
-                    symbol: instrument_dto.symbol,
-                    venue_symbol: instrument_dto.venue_symbol,
-                    venue,
-                    instrument_type: instrument_dto.instrument_type.into(),
-                    base_asset,
-                    quote_asset,
-                    maturity: instrument_dto.maturity,
-                    strike: instrument_dto.strike,
-                    option_type: instrument_dto.option_type.map(|v| v.into()),
-                    contract_size: instrument_dto.contract_size,
-                    price_precision: instrument_dto.price_precision as u32,
-                    quantity_precision: instrument_dto.quantity_precision as u32,
-                    base_precision: instrument_dto.base_precision as u32,
-                    quote_precision: instrument_dto.quote_precision as u32,
-                    tick_size: instrument_dto.tick_size,
-                    lot_size: instrument_dto.lot_size,
-                    status: instrument_dto.status.into(),
-                };
-
-                // Update cache
-                let instrument = Arc::new(instrument);
-                self.update_instrument_cache(instrument.clone()).await;
-                Ok(instrument)
+                self.load(instrument_dto).await
             }
         }
     }
+
+    /// Read an instrument by its full symbol, like `perp-btc-usdt@binance`.
+    pub async fn read_by_symbol(&self, symbol: &str) -> Result<Arc<Instrument>, PersistenceError> {
+        let instrument_dto = self.instrument_repo.read_by_symbol(symbol).await?;
+        self.load(instrument_dto).await
+    }
+
+    /// Symbols of the instruments that are not delisted, read from the database without caching.
+    pub async fn read_symbols(&self) -> Result<Vec<InstrumentSymbolDTO>, PersistenceError> {
+        self.instrument_repo.read_symbols().await
+    }
 }
@@ -0,0 +1,62 @@
+use time::OffsetDateTime;
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::{Job, JobStatus};
+
+use crate::{repos::JobRepo, PersistenceError};
+
+/// Jobs are written right away instead of buffered, workers and the CLI coordinate through them.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct JobStore {
+    job_repo: JobRepo,
+}
+
+impl JobStore {
+    pub async fn insert(&self, job: &Job) -> Result<(), PersistenceError> {
+        self.job_repo.insert(job.into()).await
+    }
+
+    pub async fn read_by_id(&self, id: &Uuid) -> Result<Job, PersistenceError> {
+        Ok(self.job_repo.read_by_id(id).await?.into())
+    }
+
+    pub async fn read_all(&self) -> Result<Vec<Job>, PersistenceError> {
+        Ok(self.job_repo.read_all().await?.into_iter().map(Job::from).collect())
+    }
+
+    pub async fn read_by_group(&self, group_id: &Uuid) -> Result<Vec<Job>, PersistenceError> {
+        Ok(self
+            .job_repo
+            .read_by_group(group_id)
+            .await?
+            .into_iter()
+            .map(Job::from)
+            .collect())
+    }
+
+    pub async fn claim_next(&self, worker: &str) -> Result<Option<Job>, PersistenceError> {
+        Ok(self.job_repo.claim_next(worker).await?.map(Job::from))
+    }
+
+    pub async fn update_status(
+        &self,
+        id: &Uuid,
+        status: JobStatus,
+        error: Option<String>,
+    ) -> Result<(), PersistenceError> {
+        self.job_repo.update_status(id, status, error).await
+    }
+
+    pub async fn update_cursor(&self, id: &Uuid, cursor: OffsetDateTime) -> Result<(), PersistenceError> {
+        self.job_repo.update_cursor(id, cursor).await
+    }
+
+    pub async fn heartbeat(&self, id: &Uuid) -> Result<(), PersistenceError> {
+        self.job_repo.heartbeat(id).await
+    }
+
+    pub async fn requeue_stale(&self, before: OffsetDateTime) -> Result<u64, PersistenceError> {
+        self.job_repo.requeue_stale(before).await
+    }
+}
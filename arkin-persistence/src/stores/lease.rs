@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use typed_builder::TypedBuilder;
+
+use crate::{repos::LeaseRepo, PersistenceError};
+
+/// Leases for leader election, written right away like the jobs. They live in the database the instances already
+/// share, so hot/standby needs no lock service like Redis or NATS.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct LeaseStore {
+    lease_repo: LeaseRepo,
+}
+
+impl LeaseStore {
+    pub async fn acquire(&self, name: &str, holder: &str, ttl: Duration) -> Result<bool, PersistenceError> {
+        self.lease_repo.acquire(name, holder, ttl).await
+    }
+
+    pub async fn release(&self, name: &str, holder: &str) -> Result<(), PersistenceError> {
+        self.lease_repo.release(name, holder).await
+    }
+}
@@ -1,29 +1,51 @@
+mod account_transfer;
 mod allocation;
+mod annotation;
 mod asset;
+mod book;
+mod checkpoint;
+mod daily_performance;
+mod execution_metrics;
 mod execution_order;
 mod insight;
 mod instance;
 mod instrument;
+mod job;
+mod lease;
 mod pipeline;
 mod portfolio;
+mod prediction;
+mod rebalance;
 mod signal;
 mod strategy;
+mod symbol_registry;
 mod tick;
 mod trade;
 mod transaction;
 mod venue;
 mod venue_order;
 
+pub use account_transfer::*;
 pub use allocation::*;
+pub use annotation::*;
 pub use asset::*;
+pub use book::*;
+pub use checkpoint::*;
+pub use daily_performance::*;
+pub use execution_metrics::*;
 pub use execution_order::*;
 pub use insight::*;
 pub use instance::*;
 pub use instrument::*;
+pub use job::*;
+pub use lease::*;
 pub use pipeline::*;
 pub use portfolio::*;
+pub use prediction::*;
+pub use rebalance::*;
 pub use signal::*;
 pub use strategy::*;
+pub use symbol_registry::*;
 pub use tick::*;
 pub use trade::*;
 pub use transaction::*;
@@ -28,4 +28,8 @@ impl PipelineStore {
         let pipeline: Arc<Pipeline> = pipeline_dto.into();
         Ok(pipeline)
     }
+
+    pub async fn read_names(&self) -> Result<Vec<String>, PersistenceError> {
+        self.pipeline_repo.read_names().await
+    }
 }
@@ -0,0 +1,192 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::error;
+use typed_builder::TypedBuilder;
+
+use arkin_core::Prediction;
+
+use crate::{
+    repos::{PredictionDistributionDTO, PredictionRepo},
+    PersistenceError,
+};
+
+/// How the predictions of a candidate version differ from the baseline on the same instruments and times.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PredictionComparison {
+    pub baseline: String,
+    pub candidate: String,
+    pub pairs: usize,
+    /// Share of the pairs where both versions saw the same features
+    pub same_features: f64,
+    /// Candidate minus baseline
+    pub mean_diff: f64,
+    pub mean_abs_diff: f64,
+    pub max_abs_diff: f64,
+    pub correlation: Option<f64>,
+}
+
+impl PredictionComparison {
+    fn from_pairs(baseline: &str, candidate: &str, pairs: &[(f64, f64, bool)]) -> Self {
+        let mut comparison = Self {
+            baseline: baseline.to_owned(),
+            candidate: candidate.to_owned(),
+            pairs: pairs.len(),
+            ..Default::default()
+        };
+        if pairs.is_empty() {
+            return comparison;
+        }
+        let n = pairs.len() as f64;
+        comparison.same_features = pairs.iter().filter(|(_, _, same)| *same).count() as f64 / n;
+        comparison.mean_diff = pairs.iter().map(|(b, c, _)| c - b).sum::<f64>() / n;
+        comparison.mean_abs_diff = pairs.iter().map(|(b, c, _)| (c - b).abs()).sum::<f64>() / n;
+        comparison.max_abs_diff = pairs.iter().map(|(b, c, _)| (c - b).abs()).fold(0., f64::max);
+
+        let mean_b = pairs.iter().map(|(b, _, _)| b).sum::<f64>() / n;
+        let mean_c = pairs.iter().map(|(_, c, _)| c).sum::<f64>() / n;
+        let (mut cov, mut var_b, mut var_c) = (0., 0., 0.);
+        for (b, c, _) in pairs {
+            cov += (b - mean_b) * (c - mean_c);
+            var_b += (b - mean_b).powi(2);
+            var_c += (c - mean_c).powi(2);
+        }
+        if var_b > 0. && var_c > 0. {
+            comparison.correlation = Some(cov / (var_b * var_c).sqrt());
+        }
+        comparison
+    }
+}
+
+impl fmt::Display for PredictionComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} vs {}: pairs={} same_features={:.1}% mean_diff={:.6} mean_abs_diff={:.6} max_abs_diff={:.6} correlation={}",
+            self.candidate,
+            self.baseline,
+            self.pairs,
+            self.same_features * 100.,
+            self.mean_diff,
+            self.mean_abs_diff,
+            self.max_abs_diff,
+            self.correlation.map(|c| format!("{:.4}", c)).unwrap_or_else(|| "n/a".into())
+        )
+    }
+}
+
+impl fmt::Display for PredictionDistributionDTO {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "version={} count={} mean={:.6} std_dev={:.6} p05={:.6} p25={:.6} p50={:.6} p75={:.6} p95={:.6} mean_latency_us={:.1}",
+            self.model_version,
+            self.count,
+            self.mean,
+            self.std_dev,
+            self.p05,
+            self.p25,
+            self.p50,
+            self.p75,
+            self.p95,
+            self.mean_latency_us
+        )
+    }
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PredictionStore {
+    prediction_repo: PredictionRepo,
+    #[builder(default)]
+    prediction_buffer: Arc<Mutex<Vec<Arc<Prediction>>>>,
+    buffer_size: usize,
+}
+
+impl PredictionStore {
+    pub async fn flush(&self) -> Result<(), PersistenceError> {
+        let predictions = {
+            let mut lock = self.prediction_buffer.lock().await;
+            std::mem::take(&mut *lock)
+        };
+        if predictions.is_empty() {
+            return Ok(());
+        }
+
+        let predictions = predictions.into_iter().map(|p| p.into()).collect::<Vec<_>>();
+        if let Err(e) = self.prediction_repo.insert_batch(&predictions).await {
+            error!("Failed to flush predictions: {}", e);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub async fn insert_batch(&self, predictions: Vec<Arc<Prediction>>) -> Result<(), PersistenceError> {
+        let predictions = predictions.into_iter().map(|p| p.into()).collect::<Vec<_>>();
+        self.prediction_repo.insert_batch(&predictions).await
+    }
+
+    pub async fn insert_buffered(&self, prediction: Arc<Prediction>) -> Result<(), PersistenceError> {
+        let should_commit = {
+            let mut lock = self.prediction_buffer.lock().await;
+            lock.push(prediction);
+            lock.len() >= self.buffer_size
+        };
+        if should_commit {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Distribution of the predictions per version of a model.
+    pub async fn distributions(
+        &self,
+        model_name: &str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<PredictionDistributionDTO>, PersistenceError> {
+        self.prediction_repo.read_distributions(model_name, from, to).await
+    }
+
+    /// Compare a candidate version to the baseline on the predictions both made, as in a shadow evaluation.
+    pub async fn compare_versions(
+        &self,
+        model_name: &str,
+        baseline: &str,
+        candidate: &str,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<PredictionComparison, PersistenceError> {
+        let pairs = self
+            .prediction_repo
+            .read_pairs(model_name, baseline, candidate, from, to)
+            .await?
+            .into_iter()
+            .filter_map(|p| Some((p.baseline.to_f64()?, p.candidate.to_f64()?, p.same_features)))
+            .collect::<Vec<_>>();
+        Ok(PredictionComparison::from_pairs(baseline, candidate, &pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison() {
+        let pairs = [(0.1, 0.2, true), (0.5, 0.6, true), (0.9, 0.7, false)];
+        let comparison = PredictionComparison::from_pairs("v1", "v2", &pairs);
+        assert_eq!(comparison.pairs, 3);
+        assert!((comparison.same_features - 2. / 3.).abs() < 1e-12);
+        assert!(comparison.mean_diff.abs() < 1e-12);
+        assert!((comparison.mean_abs_diff - 0.4 / 3.).abs() < 1e-12);
+        assert!((comparison.max_abs_diff - 0.2).abs() < 1e-12);
+        assert!(comparison.correlation.unwrap() > 0.9);
+
+        let empty = PredictionComparison::from_pairs("v1", "v2", &[]);
+        assert_eq!(empty.pairs, 0);
+        assert_eq!(empty.correlation, None);
+    }
+}
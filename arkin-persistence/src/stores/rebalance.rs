@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use arkin_core::Rebalance;
+use typed_builder::TypedBuilder;
+
+use crate::{repos::RebalanceRepo, PersistenceError};
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RebalanceStore {
+    rebalance_repo: RebalanceRepo,
+}
+
+impl RebalanceStore {
+    pub async fn insert(&self, rebalance: Arc<Rebalance>) -> Result<(), PersistenceError> {
+        self.rebalance_repo.insert(rebalance.into()).await
+    }
+}
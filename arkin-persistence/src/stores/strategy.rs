@@ -36,4 +36,8 @@ impl StrategyStore {
     pub async fn delete(&self, id: &Uuid) -> Result<(), PersistenceError> {
         self.strategy_repo.delete(id).await
     }
+
+    pub async fn read_names(&self) -> Result<Vec<String>, PersistenceError> {
+        self.strategy_repo.read_names().await
+    }
 }
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use tracing::{debug, info};
+use typed_builder::TypedBuilder;
+
+use arkin_core::{Instrument, SymbolOverride, SymbolRegistry, Venue};
+
+use crate::{repos::SymbolOverrideRepo, PersistenceError};
+
+use super::instrument::InstrumentStore;
+
+/// Backs the [`SymbolRegistry`] with persistence. The overrides are loaded once, instruments are added to the registry
+/// the first time they are looked up.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SymbolRegistryStore {
+    symbol_override_repo: SymbolOverrideRepo,
+    instrument_store: Arc<InstrumentStore>,
+    #[builder(default)]
+    registry: Arc<SymbolRegistry>,
+}
+
+impl SymbolRegistryStore {
+    pub fn registry(&self) -> Arc<SymbolRegistry> {
+        self.registry.clone()
+    }
+
+    /// Load the venue symbol overrides into the registry.
+    pub async fn load_overrides(&self) -> Result<(), PersistenceError> {
+        let overrides = self.symbol_override_repo.read_all().await?;
+        info!("Loaded {} symbol overrides", overrides.len());
+        for symbol_override in overrides {
+            self.registry.add_override(symbol_override.into());
+        }
+        Ok(())
+    }
+
+    pub async fn insert_override(
+        &self,
+        venue: &Venue,
+        venue_symbol: &str,
+        canonical_symbol: &str,
+    ) -> Result<(), PersistenceError> {
+        self.symbol_override_repo
+            .upsert(&venue.id, venue_symbol, canonical_symbol)
+            .await?;
+        self.registry.add_override(SymbolOverride {
+            venue: venue.name.clone(),
+            venue_symbol: venue_symbol.to_owned(),
+            canonical_symbol: canonical_symbol.to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Instrument of a venue or canonical symbol on a venue, see [`SymbolRegistry::instrument`].
+    pub async fn instrument(&self, venue: &str, symbol: &str) -> Result<Arc<Instrument>, PersistenceError> {
+        if let Some(instrument) = self.registry.instrument(venue, symbol) {
+            return Ok(instrument);
+        }
+        debug!("Symbol {} on {} not in the registry", symbol, venue);
+
+        let canonical = self.registry.override_for(venue, symbol);
+        let instrument = match canonical {
+            Some(canonical) => self.read_canonical(venue, &canonical).await?,
+            None => match self.instrument_store.read_by_venue_symbol(symbol).await {
+                Ok(instrument) if instrument.venue.name.eq_ignore_ascii_case(venue) => instrument,
+                Ok(_) | Err(PersistenceError::NotFound) => self.read_canonical(venue, symbol).await?,
+                Err(e) => return Err(e),
+            },
+        };
+        self.registry.insert(instrument.clone());
+        Ok(instrument)
+    }
+
+    /// Instrument of a canonical symbol on a venue, like `perp-btc-usdt` on `binance`.
+    pub async fn resolve(&self, venue: &str, canonical_symbol: &str) -> Result<Arc<Instrument>, PersistenceError> {
+        if let Some(instrument) = self.registry.resolve(venue, canonical_symbol) {
+            return Ok(instrument);
+        }
+        let instrument = self.read_canonical(venue, canonical_symbol).await?;
+        self.registry.insert(instrument.clone());
+        Ok(instrument)
+    }
+
+    /// Symbol to use for the instrument when talking to its venue.
+    pub fn venue_symbol(&self, instrument: &Instrument) -> String {
+        self.registry.venue_symbol(instrument)
+    }
+
+    async fn read_canonical(&self, venue: &str, canonical_symbol: &str) -> Result<Arc<Instrument>, PersistenceError> {
+        let symbol = format!("{}@{}", canonical_symbol, venue.to_lowercase());
+        self.instrument_store.read_by_symbol(&symbol).await
+    }
+}
@@ -55,7 +55,7 @@ impl TickStore {
         Ok(())
     }
 
-    async fn update_tick_cache(&self, tick: Arc<Tick>) {
+    pub async fn update_tick_cache(&self, tick: Arc<Tick>) {
         if let Some(cached_tick) = self.last_tick_cache.get(&tick.instrument).await {
             if cached_tick.event_time < tick.event_time {
                 self.last_tick_cache.insert(tick.instrument.clone(), tick.clone()).await;
@@ -65,6 +65,12 @@ impl TickStore {
         }
     }
 
+    /// Write a batch straight to the database, bypassing the buffer.
+    pub async fn insert_batch(&self, ticks: Vec<Arc<Tick>>) -> Result<(), PersistenceError> {
+        let ticks = ticks.into_iter().map(|t| t.into()).collect::<Vec<_>>();
+        self.tick_repo.insert_batch(ticks).await
+    }
+
     pub async fn insert(&self, tick: Arc<Tick>) -> Result<(), PersistenceError> {
         self.update_tick_cache(tick.clone()).await;
         self.tick_repo.insert(tick.into()).await
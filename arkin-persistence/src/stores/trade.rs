@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use moka2::future::Cache;
 use time::OffsetDateTime;
@@ -7,7 +7,7 @@ use tracing::{debug, error};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
-use arkin_core::{Instrument, Trade};
+use arkin_core::{Bar, Instrument, Trade};
 
 use crate::{repos::TradeRepo, PersistenceError};
 
@@ -55,7 +55,7 @@ impl TradeStore {
         Ok(())
     }
 
-    async fn update_trade_cache(&self, trade: Arc<Trade>) {
+    pub async fn update_trade_cache(&self, trade: Arc<Trade>) {
         if let Some(cached_trade) = self.last_trade_cache.get(&trade.instrument.id).await {
             if cached_trade.event_time < trade.event_time {
                 self.last_trade_cache.insert(trade.instrument.id, trade.clone()).await;
@@ -65,6 +65,12 @@ impl TradeStore {
         }
     }
 
+    /// Write a batch straight to the database, bypassing the buffer.
+    pub async fn insert_batch(&self, trades: Vec<Arc<Trade>>) -> Result<(), PersistenceError> {
+        let trades = trades.into_iter().map(|t| t.into()).collect::<Vec<_>>();
+        self.trade_repo.insert_batch(trades).await
+    }
+
     pub async fn insert(&self, trade: Arc<Trade>) -> Result<(), PersistenceError> {
         self.update_trade_cache(trade.clone()).await;
         self.trade_repo.insert(trade.into()).await
@@ -120,4 +126,40 @@ impl TradeStore {
         }
         Ok(trades)
     }
+
+    /// Materialize the one minute bars of backfilled trades in [from, to).
+    pub async fn refresh_bars(&self, from: OffsetDateTime, to: OffsetDateTime) -> Result<(), PersistenceError> {
+        self.trade_repo.refresh_bars(from, to).await
+    }
+
+    /// Read pre-aggregated OHLCV bars, mainly used to warm up insights without replaying raw trades.
+    pub async fn read_bars(
+        &self,
+        instruments: &[Arc<Instrument>],
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        frequency: Duration,
+    ) -> Result<Vec<Arc<Bar>>, PersistenceError> {
+        let ids = instruments.iter().map(|i| i.id).collect::<Vec<_>>();
+        let dto = self.trade_repo.read_bars(&ids, from, to, frequency).await?;
+
+        let mut bars = Vec::with_capacity(dto.len());
+        for bar in dto {
+            let instrument = self.instrument_store.read_by_id(&bar.instrument_id).await?;
+            let bar = Bar::builder()
+                .event_time(bar.event_time)
+                .instrument(instrument)
+                .frequency(frequency)
+                .open(bar.open)
+                .high(bar.high)
+                .low(bar.low)
+                .close(bar.close)
+                .volume(bar.volume)
+                .notional_volume(bar.notional_volume)
+                .trade_count(bar.trade_count.max(0) as u64)
+                .build();
+            bars.push(Arc::new(bar));
+        }
+        Ok(bars)
+    }
 }
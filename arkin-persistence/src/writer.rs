@@ -0,0 +1,397 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use arkin_core::prelude::*;
+
+use crate::{PersistenceError, WriterConfig, WriterOverflow};
+
+/// Counters of a batch writer, used to monitor the write path.
+#[derive(Debug, Default)]
+pub struct WriterMetrics {
+    pub queued: AtomicU64,
+    pub written: AtomicU64,
+    pub dropped: AtomicU64,
+    pub retries: AtomicU64,
+    pub failed: AtomicU64,
+}
+
+impl WriterMetrics {
+    pub fn queued(&self) -> u64 {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+/// Bounded queue plus batcher for a single table.
+///
+/// Producers push into the queue without ever waiting on the database. A background task drains the queue
+/// into batches that are flushed when full or when the flush interval passes, retrying transient database
+/// errors with exponential backoff. When the queue is full new items are dropped and counted instead of
+/// blocking the event loop, or with [`WriterOverflow::Wait`] the producer waits for room. A drain writes
+/// everything queued before it, so a checkpoint or the end of a simulation never leaves items behind.
+#[derive(Debug)]
+pub struct BatchWriter<T> {
+    name: String,
+    sender: mpsc::Sender<T>,
+    receiver: Mutex<Option<mpsc::Receiver<T>>>,
+    overflow: WriterOverflow,
+    /// Drain requests, answered once everything queued before them is written
+    drains: mpsc::Sender<oneshot::Sender<()>>,
+    drain_receiver: Mutex<Option<mpsc::Receiver<oneshot::Sender<()>>>>,
+    running: AtomicBool,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    metrics: Arc<WriterMetrics>,
+}
+
+impl<T: Clone + Send + 'static> BatchWriter<T> {
+    pub fn new(name: &str, config: &WriterConfig, batch_size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        let (drains, drain_receiver) = mpsc::channel(16);
+        Self {
+            name: name.to_string(),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            overflow: config.overflow,
+            drains,
+            drain_receiver: Mutex::new(Some(drain_receiver)),
+            running: AtomicBool::new(false),
+            batch_size: batch_size.max(1),
+            flush_interval: Duration::from_millis(config.flush_interval_ms(name)),
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            metrics: Arc::new(WriterMetrics::default()),
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<WriterMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Queue an item for writing. Returns false if the queue is full and the item was dropped.
+    pub fn push(&self, item: T) -> bool {
+        match self.sender.try_send(item) {
+            Ok(_) => {
+                self.metrics.queued.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(_) => {
+                let dropped = self.metrics.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped.is_power_of_two() {
+                    warn!("Write queue of {} is full, dropped {} items so far", self.name, dropped);
+                }
+                false
+            }
+        }
+    }
+
+    /// Queue an item for writing following the overflow policy. Returns false if the item was dropped.
+    pub async fn write(&self, item: T) -> bool {
+        match self.overflow {
+            WriterOverflow::Drop => self.push(item),
+            WriterOverflow::Wait => match self.sender.send(item).await {
+                Ok(_) => {
+                    self.metrics.queued.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(_) => false,
+            },
+        }
+    }
+
+    /// Wait until everything queued so far is written, returns right away if the batcher is not running.
+    pub async fn drain(&self) {
+        if !self.running.load(Ordering::Acquire) {
+            return;
+        }
+        let (reply, done) = oneshot::channel();
+        if self.drains.send(reply).await.is_ok() {
+            let _ = done.await;
+        }
+    }
+
+    /// Export the counters as metrics labelled with the table.
+    pub fn export_metrics(&self) {
+        let labels = [("table", self.name.as_str())];
+        let counters = [
+            ("arkin_writer_queued", "Items queued by the batch writer", self.metrics.queued()),
+            (
+                "arkin_writer_written",
+                "Items written by the batch writer",
+                self.metrics.written(),
+            ),
+            (
+                "arkin_writer_dropped",
+                "Items dropped on a full write queue",
+                self.metrics.dropped(),
+            ),
+            ("arkin_writer_retries", "Retried batch writes", self.metrics.retries()),
+            (
+                "arkin_writer_failed",
+                "Items of batches that failed to write",
+                self.metrics.failed(),
+            ),
+        ];
+        for (name, help, value) in counters {
+            METRICS.set_gauge(name, help, &labels, value as f64);
+        }
+    }
+
+    /// Run the batcher until shutdown. Remaining items are flushed before returning.
+    pub async fn run<F, Fut>(&self, flush: F, shutdown: CancellationToken)
+    where
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<(), PersistenceError>>,
+    {
+        let (Some(mut receiver), Some(mut drains)) =
+            (self.receiver.lock().await.take(), self.drain_receiver.lock().await.take())
+        else {
+            error!("Batch writer {} is already running", self.name);
+            return;
+        };
+        self.running.store(true, Ordering::Release);
+        info!(
+            "Starting batch writer {} with flush interval {:?}",
+            self.name, self.flush_interval
+        );
+
+        let mut interval = tokio::time::interval(self.flush_interval);
+        let mut buffer = Vec::with_capacity(self.batch_size);
+        loop {
+            tokio::select! {
+                Some(item) = receiver.recv() => {
+                    buffer.push(item);
+                    if buffer.len() >= self.batch_size {
+                        self.flush_with_retry(&flush, std::mem::take(&mut buffer)).await;
+                    }
+                }
+                _ = interval.tick() => {
+                    if !buffer.is_empty() {
+                        self.flush_with_retry(&flush, std::mem::take(&mut buffer)).await;
+                    }
+                }
+                Some(reply) = drains.recv() => {
+                    self.flush_queued(&flush, &mut receiver, &mut buffer).await;
+                    let _ = reply.send(());
+                }
+                _ = shutdown.cancelled() => {
+                    self.flush_queued(&flush, &mut receiver, &mut buffer).await;
+                    break;
+                }
+            }
+        }
+        self.running.store(false, Ordering::Release);
+        // Answer the drains that came in while stopping, everything is written by now
+        while let Ok(reply) = drains.try_recv() {
+            let _ = reply.send(());
+        }
+        info!(
+            "Batch writer {} stopped (written: {}, dropped: {}, failed: {})",
+            self.name,
+            self.metrics.written(),
+            self.metrics.dropped(),
+            self.metrics.failed()
+        );
+    }
+
+    /// Write the buffer and everything waiting in the queue in batches.
+    async fn flush_queued<F, Fut>(&self, flush: &F, receiver: &mut mpsc::Receiver<T>, buffer: &mut Vec<T>)
+    where
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<(), PersistenceError>>,
+    {
+        while let Ok(item) = receiver.try_recv() {
+            buffer.push(item);
+            if buffer.len() >= self.batch_size {
+                self.flush_with_retry(flush, std::mem::take(buffer)).await;
+            }
+        }
+        if !buffer.is_empty() {
+            self.flush_with_retry(flush, std::mem::take(buffer)).await;
+        }
+    }
+
+    async fn flush_with_retry<F, Fut>(&self, flush: &F, batch: Vec<T>)
+    where
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<(), PersistenceError>>,
+    {
+        let len = batch.len() as u64;
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+
+        loop {
+            debug!("Flushing {} items for {} (attempt {})", len, self.name, attempt + 1);
+            match flush(batch.clone()).await {
+                Ok(_) => {
+                    self.metrics.written.fetch_add(len, Ordering::Relaxed);
+                    return;
+                }
+                Err(e) if e.is_transient() && attempt < self.max_retries => {
+                    attempt += 1;
+                    self.metrics.retries.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "Transient error flushing {}, retrying in {:?} ({}/{}): {}",
+                        self.name, backoff, attempt, self.max_retries, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                }
+                Err(e) => {
+                    self.metrics.failed.fetch_add(len, Ordering::Relaxed);
+                    error!("Failed to flush {} items for {}: {}", len, self.name, e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+
+    use test_log::test;
+
+    use super::*;
+
+    fn config() -> WriterConfig {
+        WriterConfig {
+            queue_capacity: 4,
+            overflow: WriterOverflow::Drop,
+            flush_interval_ms: 10,
+            max_retries: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+            tables: Default::default(),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn test_writer_drops_when_queue_is_full() {
+        let writer = BatchWriter::<u32>::new("test", &config(), 10);
+        for i in 0..6 {
+            writer.push(i);
+        }
+        assert_eq!(writer.metrics().queued(), 4);
+        assert_eq!(writer.metrics().dropped(), 2);
+    }
+
+    #[test(tokio::test)]
+    async fn test_writer_waits_for_room() {
+        let config = WriterConfig {
+            overflow: WriterOverflow::Wait,
+            ..config()
+        };
+        let writer = Arc::new(BatchWriter::<u32>::new("test", &config, 3));
+        let written = Arc::new(AtomicU32::new(0));
+        let shutdown = CancellationToken::new();
+
+        let task = {
+            let writer = writer.clone();
+            let written = written.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                writer
+                    .run(
+                        |batch| {
+                            let written = written.clone();
+                            async move {
+                                written.fetch_add(batch.len() as u32, Ordering::Relaxed);
+                                Ok(())
+                            }
+                        },
+                        shutdown,
+                    )
+                    .await
+            })
+        };
+        while !writer.running.load(Ordering::Acquire) {
+            tokio::task::yield_now().await;
+        }
+
+        // Twice the queue capacity makes it through without drops
+        for i in 0..8 {
+            assert!(writer.write(i).await);
+        }
+        writer.drain().await;
+        assert_eq!(written.load(Ordering::Relaxed), 8);
+        assert_eq!(writer.metrics().written(), 8);
+        assert_eq!(writer.metrics().dropped(), 0);
+
+        shutdown.cancel();
+        task.await.unwrap();
+        // Nothing left to wait for once the writer stopped
+        writer.drain().await;
+    }
+
+    #[test(tokio::test)]
+    async fn test_writer_retries_transient_errors() {
+        let writer = Arc::new(BatchWriter::<u32>::new("test", &config(), 2));
+        let attempts = Arc::new(AtomicU32::new(0));
+        let shutdown = CancellationToken::new();
+
+        let task = {
+            let writer = writer.clone();
+            let attempts = attempts.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                writer
+                    .run(
+                        |_batch| {
+                            let attempts = attempts.clone();
+                            async move {
+                                // Fail the first attempt with a transient error
+                                if attempts.fetch_add(1, Ordering::Relaxed) == 0 {
+                                    Err(PersistenceError::SqlxError(sqlx::Error::PoolTimedOut))
+                                } else {
+                                    Ok(())
+                                }
+                            }
+                        },
+                        shutdown,
+                    )
+                    .await
+            })
+        };
+
+        writer.push(1);
+        writer.push(2);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.cancel();
+        task.await.unwrap();
+
+        assert_eq!(writer.metrics().written(), 2);
+        assert_eq!(writer.metrics().retries(), 1);
+        assert_eq!(writer.metrics().failed(), 0);
+    }
+}
@@ -0,0 +1,66 @@
+use rust_decimal::Decimal;
+
+use arkin_core::prelude::*;
+
+/// Price the strategy decided to trade an order at. Taker orders carry the price of the allocation that sent them,
+/// orders without a price are measured against the mid price when they were sent.
+pub fn decision_price(order: &ExecutionOrder, mark: Option<Price>) -> Option<Price> {
+    match order.price.is_zero() {
+        true => mark,
+        false => Some(order.price),
+    }
+}
+
+/// Pnl of a fill against the decision price of its order, negative when the fill was worse than the decision.
+pub fn execution_pnl(instrument: &Instrument, quantity: Decimal, decision_price: Price, fill_price: Price) -> Decimal {
+    (decision_price - fill_price) * quantity * instrument.contract_size
+}
+
+/// Split of the pnl of a strategy into the part the signal earned and the costs of trading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PnlAttribution {
+    /// Mark to mark pnl of the positions as if every fill happened at its decision price
+    pub signal: Decimal,
+    /// Slippage of the fills against their decision prices
+    pub execution: Decimal,
+    pub fees: Decimal,
+    /// Funding received, negative when paid
+    pub funding: Decimal,
+}
+
+impl PnlAttribution {
+    /// Attribute the trading pnl (realized and change in unrealized) of a period, the signal takes what the
+    /// execution did not explain.
+    pub fn new(trading_pnl: Decimal, execution: Decimal, fees: Decimal, funding: Decimal) -> Self {
+        Self {
+            signal: trading_pnl - execution,
+            execution,
+            fees,
+            funding,
+        }
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.signal + self.execution - self.fees + self.funding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_attribution() {
+        let instrument = test_inst_binance_btc_usdt_perp();
+        // Buying above and selling below the decision price costs the difference
+        assert_eq!(execution_pnl(&instrument, dec!(2), dec!(100), dec!(101)), dec!(-2));
+        assert_eq!(execution_pnl(&instrument, dec!(-1), dec!(100), dec!(99)), dec!(-1));
+        assert_eq!(execution_pnl(&instrument, dec!(1), dec!(100), dec!(99.5)), dec!(0.5));
+
+        let attribution = PnlAttribution::new(dec!(7), dec!(-3), dec!(1), dec!(-0.5));
+        assert_eq!(attribution.signal, dec!(10));
+        assert_eq!(attribution.total(), dec!(5.5));
+    }
+}
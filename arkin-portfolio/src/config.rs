@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,3 +17,48 @@ pub enum PortfolioType {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SingleStrategyPortfolioConfig {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PerformanceFeesConfig {
+    /// Hypothetical fund fees charged in the daily performance, no fees without it
+    #[serde(default)]
+    pub performance_fees: Option<PerformanceFeesSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PerformanceFeesSettings {
+    /// Fees of strategies without their own entry
+    pub default: FeeScheduleConfig,
+    #[serde(default)]
+    pub strategies: HashMap<String, FeeScheduleConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeeScheduleConfig {
+    pub capital: Decimal,
+    /// Yearly fee as a fraction of the net asset value
+    pub management_fee: Decimal,
+    /// Fraction of the gains above the high-water mark
+    pub performance_fee: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SubAccountsConfig {
+    /// Per-strategy capital accounting on the shared venue account, nothing is split without it
+    #[serde(default)]
+    pub sub_accounts: Option<SubAccountsSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubAccountsSettings {
+    /// Fraction of the notional of the open positions held as margin
+    #[serde(default = "default_margin_rate")]
+    pub margin_rate: Decimal,
+    /// Initial capital per strategy
+    #[serde(default)]
+    pub capital: HashMap<String, Decimal>,
+}
+
+fn default_margin_rate() -> Decimal {
+    dec!(0.1)
+}
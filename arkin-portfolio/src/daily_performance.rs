@@ -0,0 +1,477 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use time::{Date, OffsetDateTime};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{
+    decision_price, execution_pnl, FeeSchedule, HighWaterMark, PerformanceFeesSettings, PnlAttribution, PortfolioError,
+};
+
+/// Strategy the fills of orders without a strategy are booked on.
+pub const DEFAULT_STRATEGY: &str = "default";
+
+#[derive(Debug, Default)]
+pub(crate) struct InstrumentBook {
+    /// Signed quantity, negative when short
+    pub quantity: Decimal,
+    pub entry_price: Price,
+}
+
+impl InstrumentBook {
+    /// Book a fill on the average cost of the position and return the realized pnl.
+    pub fn fill(&mut self, instrument: &Instrument, quantity: Decimal, price: Price) -> Decimal {
+        let mut realized = Decimal::ZERO;
+        if self.quantity.is_zero() || self.quantity.is_sign_positive() == quantity.is_sign_positive() {
+            let total = self.quantity.abs() + quantity.abs();
+            self.entry_price = (self.entry_price * self.quantity.abs() + price * quantity.abs()) / total;
+        } else {
+            let closed = quantity.abs().min(self.quantity.abs());
+            let direction = if self.quantity.is_sign_positive() {
+                Decimal::ONE
+            } else {
+                Decimal::NEGATIVE_ONE
+            };
+            realized = (price - self.entry_price) * closed * direction * instrument.contract_size;
+            // A fill larger than the position opens a new one in the other direction at the fill price
+            if quantity.abs() > self.quantity.abs() {
+                self.entry_price = price;
+            }
+        }
+        self.quantity += quantity;
+        realized
+    }
+}
+
+#[derive(Debug)]
+struct StrategyBook {
+    portfolio: Arc<Portfolio>,
+    positions: HashMap<Arc<Instrument>, InstrumentBook>,
+    realized_pnl: Decimal,
+    /// Slippage of the fills against the decision prices of their orders
+    execution_pnl: Decimal,
+    commission: Decimal,
+    funding: Decimal,
+    turnover: Notional,
+    trades: u64,
+    /// Unrealized pnl at the end of the previous day
+    last_unrealized_pnl: Decimal,
+    high_water_mark: HighWaterMark,
+}
+
+impl StrategyBook {
+    fn new(portfolio: Arc<Portfolio>, fees: FeeSchedule) -> Self {
+        Self {
+            portfolio,
+            positions: HashMap::new(),
+            realized_pnl: Decimal::ZERO,
+            execution_pnl: Decimal::ZERO,
+            commission: Decimal::ZERO,
+            funding: Decimal::ZERO,
+            turnover: Decimal::ZERO,
+            trades: 0,
+            last_unrealized_pnl: Decimal::ZERO,
+            high_water_mark: HighWaterMark::new(fees),
+        }
+    }
+
+    /// Book a fill on the average cost of the position and return the realized pnl.
+    fn fill(&mut self, instrument: &Arc<Instrument>, quantity: Decimal, price: Price) -> Decimal {
+        let position = self.positions.entry(instrument.clone()).or_default();
+        let realized = position.fill(instrument, quantity, price);
+        if position.quantity.is_zero() {
+            self.positions.remove(instrument);
+        }
+        realized
+    }
+
+    fn unrealized_pnl(&self, marks: &HashMap<Arc<Instrument>, Price>) -> Decimal {
+        self.positions
+            .iter()
+            .map(|(instrument, position)| {
+                let mark = marks.get(instrument).copied().unwrap_or(position.entry_price);
+                (mark - position.entry_price) * position.quantity * instrument.contract_size
+            })
+            .sum()
+    }
+
+    /// Reset the intraday counters, the open positions carry over to the next day.
+    fn reset(&mut self, unrealized_pnl: Decimal) {
+        self.last_unrealized_pnl = unrealized_pnl;
+        self.realized_pnl = Decimal::ZERO;
+        self.execution_pnl = Decimal::ZERO;
+        self.commission = Decimal::ZERO;
+        self.funding = Decimal::ZERO;
+        self.turnover = Decimal::ZERO;
+        self.trades = 0;
+    }
+}
+
+#[derive(Debug, Default)]
+struct DailyState {
+    /// UTC day being accumulated, set by the first event seen
+    day: Option<Date>,
+    /// Strategy of the execution orders seen
+    strategies: HashMap<Uuid, String>,
+    /// Decision price of the execution orders seen
+    decisions: HashMap<Uuid, Price>,
+    /// Books by portfolio id and strategy
+    books: HashMap<(Uuid, String), StrategyBook>,
+    /// Last mid price per instrument
+    marks: HashMap<Arc<Instrument>, Price>,
+}
+
+/// Accumulates the pnl, fees, funding and turnover of every strategy during the UTC day and freezes them into a
+/// [`DailyPerformance`] at the day boundary. The day rolls over on the event time of the interval ticks and fills, so
+/// a simulation produces the same days as a live run, and the last partial day is frozen when a simulation finishes.
+/// Every strategy also keeps a net asset value after hypothetical fund fees together with its high-water mark, and its
+/// pnl is attributed to the signal, the execution, fees and funding (see [`PnlAttribution`]).
+#[derive(Debug, TypedBuilder)]
+pub struct DailyPerformanceTracker {
+    pubsub: Arc<PubSub>,
+    /// Fees of strategies without their own entry
+    #[builder(default)]
+    default_fees: FeeSchedule,
+    #[builder(default)]
+    fees: HashMap<String, FeeSchedule>,
+    #[builder(default)]
+    state: Mutex<DailyState>,
+}
+
+impl DailyPerformanceTracker {
+    pub fn from_config(config: Option<&PerformanceFeesSettings>, pubsub: Arc<PubSub>) -> Self {
+        match config {
+            Some(config) => Self::builder()
+                .pubsub(pubsub)
+                .default_fees((&config.default).into())
+                .fees(config.strategies.iter().map(|(name, f)| (name.clone(), f.into())).collect())
+                .build(),
+            None => Self::builder().pubsub(pubsub).build(),
+        }
+    }
+
+    pub fn fees(&self, strategy: &str) -> FeeSchedule {
+        self.fees.get(strategy).copied().unwrap_or(self.default_fees)
+    }
+
+    pub fn order(&self, order: &ExecutionOrder) {
+        let strategy = match &order.strategy {
+            Some(strategy) => strategy.name.clone(),
+            None => DEFAULT_STRATEGY.to_string(),
+        };
+        let mut state = self.state.lock();
+        if let Some(price) = decision_price(order, state.marks.get(&order.instrument).copied()) {
+            state.decisions.insert(order.id, price);
+        }
+        state.strategies.insert(order.id, strategy);
+    }
+
+    pub fn mark(&self, tick: &Tick) {
+        self.state.lock().marks.insert(tick.instrument.clone(), tick.mid_price());
+    }
+
+    /// Book the last fill of the update, returns the days frozen before it.
+    pub fn fill(&self, update: &VenueOrderUpdate) -> Vec<Arc<DailyPerformance>> {
+        let mut state = self.state.lock();
+        let frozen = advance(&mut state, update.event_time);
+
+        let (strategy, decision) = match Uuid::parse_str(&update.order_id) {
+            Ok(id) if update.status.is_finalized() => (state.strategies.remove(&id), state.decisions.remove(&id)),
+            Ok(id) => (state.strategies.get(&id).cloned(), state.decisions.get(&id).copied()),
+            Err(e) => {
+                warn!("Order update with unknown order id {}: {}", update.order_id, e);
+                (None, None)
+            }
+        };
+        if update.last_fill_quantity.is_zero() {
+            return frozen;
+        }
+        let strategy = strategy.unwrap_or_else(|| DEFAULT_STRATEGY.to_string());
+
+        let quantity = Decimal::from(update.side) * update.last_fill_quantity.abs();
+        let fees = self.fees(&strategy);
+        let book = state
+            .books
+            .entry((update.portfolio.id, strategy))
+            .or_insert_with(|| StrategyBook::new(update.portfolio.clone(), fees));
+        let realized = book.fill(&update.instrument, quantity, update.last_fill_price);
+        book.realized_pnl += realized;
+        // Fills of orders without a decision price are all attributed to the signal
+        if let Some(decision) = decision {
+            book.execution_pnl += execution_pnl(&update.instrument, quantity, decision, update.last_fill_price);
+        }
+        match update.quote_commission() {
+            Some(commission) => book.commission += commission,
+            None => warn!(
+                "Commission of {} not charged in {}, not booked",
+                update.commission, update.instrument.quote_asset
+            ),
+        }
+        book.turnover += update.last_fill_price * update.last_fill_quantity.abs() * update.instrument.contract_size;
+        book.trades += 1;
+        frozen
+    }
+
+    /// Book a funding payment on a strategy, positive when received.
+    pub fn funding(
+        &self,
+        event_time: OffsetDateTime,
+        portfolio: Arc<Portfolio>,
+        strategy: &str,
+        amount: Decimal,
+    ) -> Vec<Arc<DailyPerformance>> {
+        let mut state = self.state.lock();
+        let frozen = advance(&mut state, event_time);
+        let fees = self.fees(strategy);
+        let book = state
+            .books
+            .entry((portfolio.id, strategy.to_string()))
+            .or_insert_with(|| StrategyBook::new(portfolio, fees));
+        book.funding += amount;
+        frozen
+    }
+
+    /// Move the clock to the given time, returns the days frozen by crossing a day boundary.
+    pub fn advance(&self, now: OffsetDateTime) -> Vec<Arc<DailyPerformance>> {
+        advance(&mut self.state.lock(), now)
+    }
+
+    /// Freeze the day in progress, used when a simulation ends before the day does.
+    pub fn finish(&self) -> Vec<Arc<DailyPerformance>> {
+        let mut state = self.state.lock();
+        match state.day {
+            Some(day) => freeze(&mut state, day),
+            None => Vec::new(),
+        }
+    }
+
+    fn publish(&self, performances: Vec<Arc<DailyPerformance>>) {
+        for performance in performances {
+            info!("Daily performance: {}", performance);
+            self.pubsub.publish::<DailyPerformance>(performance);
+        }
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), PortfolioError> {
+        info!("Starting daily performance tracker...");
+        let mut execution_orders = self.pubsub.subscribe::<ExecutionOrder>();
+        let mut order_updates = self.pubsub.subscribe::<VenueOrderUpdate>();
+        let mut ticks = self.pubsub.subscribe::<Tick>();
+        let mut interval_ticks = self.pubsub.subscribe::<IntervalTick>();
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
+        loop {
+            tokio::select! {
+                Ok(order) = execution_orders.recv() => self.order(&order),
+                Ok(update) = order_updates.recv() => self.publish(self.fill(&update)),
+                Ok(tick) = ticks.recv() => self.mark(&tick),
+                Ok(tick) = interval_ticks.recv() => self.publish(self.advance(tick.event_time)),
+                Ok(_) = simulation_finished.recv() => self.publish(self.finish()),
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn advance(state: &mut DailyState, now: OffsetDateTime) -> Vec<Arc<DailyPerformance>> {
+    let today = now.to_offset(time::UtcOffset::UTC).date();
+    match state.day {
+        Some(day) if today > day => {
+            let frozen = freeze(state, day);
+            state.day = Some(today);
+            frozen
+        }
+        Some(_) => Vec::new(),
+        None => {
+            state.day = Some(today);
+            Vec::new()
+        }
+    }
+}
+
+fn freeze(state: &mut DailyState, date: Date) -> Vec<Arc<DailyPerformance>> {
+    let mut frozen = Vec::new();
+    // Books are kept after the positions are closed, the fees accrue on the net asset value every day
+    for ((_, strategy), book) in state.books.iter_mut() {
+        let unrealized_pnl = book.unrealized_pnl(&state.marks);
+        let attribution = PnlAttribution::new(
+            book.realized_pnl + unrealized_pnl - book.last_unrealized_pnl,
+            book.execution_pnl,
+            book.commission,
+            book.funding,
+        );
+        let accrual = book.high_water_mark.accrue(attribution.total());
+        let performance = DailyPerformance::builder()
+            .date(date)
+            .portfolio(book.portfolio.clone())
+            .strategy(strategy.clone())
+            .realized_pnl(book.realized_pnl)
+            .unrealized_pnl(unrealized_pnl)
+            .signal_pnl(attribution.signal)
+            .execution_pnl(attribution.execution)
+            .commission(book.commission)
+            .funding(book.funding)
+            .turnover(book.turnover)
+            .trades(book.trades)
+            .nav(accrual.nav)
+            .high_water_mark(accrual.high_water_mark)
+            .management_fee(accrual.management_fee)
+            .performance_fee(accrual.performance_fee)
+            .build();
+        frozen.push(Arc::new(performance));
+        book.reset(unrealized_pnl);
+    }
+    frozen.sort_by(|a, b| a.strategy.cmp(&b.strategy));
+    frozen
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    fn update(event_time: OffsetDateTime, order_id: Uuid, side: MarketSide, price: Decimal) -> VenueOrderUpdate {
+        VenueOrderUpdate::builder()
+            .event_time(event_time)
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_id(order_id.to_string())
+            .venue_order_id(1)
+            .side(side)
+            .order_type(VenueOrderType::Market)
+            .time_in_force(VenueOrderTimeInForce::Gtc)
+            .price(price)
+            .quantity(dec!(1))
+            .fill_price(price)
+            .fill_quantity(dec!(1))
+            .last_fill_price(price)
+            .last_fill_quantity(dec!(1))
+            .status(VenueOrderStatus::Filled)
+            .commission_asset(None)
+            .commission(dec!(1))
+            .build()
+    }
+
+    #[test]
+    fn test_rollover() {
+        let fees = FeeSchedule {
+            capital: dec!(1000),
+            management_fee: Decimal::ZERO,
+            performance_fee: dec!(0.1),
+        };
+        let tracker = DailyPerformanceTracker::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .fees(HashMap::from([("momentum".to_string(), fees)]))
+            .build();
+        let order = ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .strategy(Some(Arc::new(
+                Strategy::builder().name("momentum".to_string()).description(None).build(),
+            )))
+            .order_type(ExecutionOrderType::Taker)
+            .side(MarketSide::Buy)
+            .price(dec!(100))
+            .quantity(dec!(1))
+            .build();
+        tracker.order(&order);
+
+        assert!(tracker
+            .fill(&update(datetime!(2025-01-01 10:00 UTC), order.id, MarketSide::Buy, dec!(100)))
+            .is_empty());
+        // Partial close of an order without strategy
+        let other = Uuid::new_v4();
+        tracker.fill(&update(datetime!(2025-01-01 11:00 UTC), other, MarketSide::Sell, dec!(110)));
+        tracker.state.lock().marks.insert(test_inst_binance_btc_usdt_perp(), dec!(120));
+        assert!(tracker.advance(datetime!(2025-01-01 23:59 UTC)).is_empty());
+
+        let frozen = tracker.advance(datetime!(2025-01-02 00:00 UTC));
+        assert_eq!(frozen.len(), 2);
+        let default = &frozen[0];
+        assert_eq!(default.strategy, DEFAULT_STRATEGY);
+        assert_eq!(default.date, date!(2025 - 01 - 01));
+        assert_eq!(default.unrealized_pnl, dec!(-10));
+        assert_eq!(default.nav, dec!(-11));
+        assert_eq!(default.high_water_mark, Decimal::ZERO);
+        let momentum = &frozen[1];
+        assert_eq!(momentum.strategy, "momentum");
+        assert_eq!(momentum.realized_pnl, Decimal::ZERO);
+        assert_eq!(momentum.unrealized_pnl, dec!(20));
+        assert_eq!(momentum.signal_pnl, dec!(20));
+        assert_eq!(momentum.execution_pnl, Decimal::ZERO);
+        assert_eq!(momentum.commission, dec!(1));
+        assert_eq!(momentum.turnover, dec!(100));
+        assert_eq!(momentum.trades, 1);
+        assert_eq!(momentum.performance_fee, dec!(1.9));
+        assert_eq!(momentum.nav, dec!(1017.1));
+
+        // Positions carry over, the counters start from zero
+        let frozen = tracker.finish();
+        assert_eq!(frozen.len(), 2);
+        assert!(frozen.iter().all(|p| p.trades == 0 && p.date == date!(2025 - 01 - 02)));
+        assert_eq!(frozen[1].unrealized_pnl, dec!(20));
+        assert_eq!(frozen[1].performance_fee, Decimal::ZERO);
+        assert_eq!(frozen[1].nav, dec!(1017.1));
+    }
+
+    #[test]
+    fn test_attribution() {
+        let tracker = DailyPerformanceTracker::builder().pubsub(Arc::new(PubSub::new())).build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let order = |side, price| {
+            ExecutionOrder::builder()
+                .portfolio(test_portfolio())
+                .instrument(instrument.clone())
+                .order_type(ExecutionOrderType::Taker)
+                .side(side)
+                .price(price)
+                .quantity(dec!(1))
+                .build()
+        };
+
+        // Bought at 102 on a decision at 100, the order without a price is measured against the mid
+        let buy = order(MarketSide::Buy, dec!(100));
+        tracker.order(&buy);
+        tracker.fill(&update(datetime!(2025-01-01 10:00 UTC), buy.id, MarketSide::Buy, dec!(102)));
+        tracker.state.lock().marks.insert(instrument.clone(), dec!(110));
+        let sell = order(MarketSide::Sell, Decimal::ZERO);
+        tracker.order(&sell);
+        tracker.fill(&update(datetime!(2025-01-01 11:00 UTC), sell.id, MarketSide::Sell, dec!(109)));
+
+        // Both fills close the position on the one book of the strategy
+        let frozen = tracker.finish();
+        assert_eq!(frozen.len(), 1);
+        let performance = &frozen[0];
+        assert_eq!(performance.trades, 2);
+        assert_eq!(performance.unrealized_pnl, Decimal::ZERO);
+        assert_eq!(performance.realized_pnl, dec!(7));
+        assert_eq!(performance.execution_pnl, dec!(-3));
+        assert_eq!(performance.signal_pnl, dec!(10));
+        assert_eq!(performance.commission, dec!(2));
+        assert_eq!(performance.nav, dec!(5));
+    }
+
+    #[test]
+    fn test_realized_pnl() {
+        let mut book = StrategyBook::new(test_portfolio(), FeeSchedule::default());
+        let instrument = test_inst_binance_btc_usdt_perp();
+        assert_eq!(book.fill(&instrument, dec!(2), dec!(100)), Decimal::ZERO);
+        assert_eq!(book.fill(&instrument, dec!(2), dec!(110)), Decimal::ZERO);
+        assert_eq!(book.fill(&instrument, dec!(-1), dec!(120)), dec!(15));
+        // Flip to short, the rest opens at the fill price
+        assert_eq!(book.fill(&instrument, dec!(-5), dec!(100)), dec!(-15));
+        let position = &book.positions[&instrument];
+        assert_eq!(position.quantity, dec!(-2));
+        assert_eq!(position.entry_price, dec!(100));
+        assert_eq!(book.fill(&instrument, dec!(2), dec!(90)), dec!(20));
+        assert!(book.positions.is_empty());
+    }
+}
@@ -1,7 +1,29 @@
 use thiserror::Error;
 
+use arkin_core::prelude::*;
+
 #[derive(Error, Debug)]
 pub enum PortfolioError {
     #[error("Asset not found: {0}")]
     AssetNotFound(String),
+
+    #[error("Instrument not found: {0}")]
+    InstrumentNotFound(String),
+
+    #[error("Invalid capital transfer: {0}")]
+    InvalidTransfer(String),
+
+    #[error("Insufficient capital: {0}")]
+    InsufficientCapital(String),
+}
+
+impl ClassifyError for PortfolioError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            PortfolioError::AssetNotFound(_)
+            | PortfolioError::InstrumentNotFound(_)
+            | PortfolioError::InvalidTransfer(_)
+            | PortfolioError::InsufficientCapital(_) => ErrorClass::InvalidInput,
+        }
+    }
 }
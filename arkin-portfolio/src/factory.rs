@@ -7,11 +7,14 @@ use crate::{Accounting, PortfolioConfig, PortfolioType, SingleStrategyPortfolio}
 pub struct PortfolioFactory {}
 
 impl PortfolioFactory {
-    pub fn from_config(config: &PortfolioConfig, pubsub: Arc<PubSub>) -> Arc<dyn Accounting> {
+    pub fn from_config(config: &PortfolioConfig, pubsub: Arc<PubSub>, watchdog: Arc<Watchdog>) -> Arc<dyn Accounting> {
         let portfolio: Arc<dyn Accounting> = match &config.portfolio {
-            PortfolioType::SingleStrategy(_c) => {
-                Arc::new(SingleStrategyPortfolio::builder().pubsub(pubsub.clone()).build())
-            }
+            PortfolioType::SingleStrategy(_c) => Arc::new(
+                SingleStrategyPortfolio::builder()
+                    .pubsub(pubsub.clone())
+                    .watchdog(watchdog)
+                    .build(),
+            ),
         };
         portfolio
     }
@@ -0,0 +1,116 @@
+use rust_decimal::Decimal;
+
+use crate::FeeScheduleConfig;
+
+const DAYS_PER_YEAR: Decimal = Decimal::from_parts(365, 0, 0, false, 0);
+
+/// Hypothetical fund fees charged on a strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FeeSchedule {
+    /// Capital allocated to the strategy, the starting net asset value and high-water mark
+    pub capital: Decimal,
+    /// Yearly fee as a fraction of the net asset value, accrued daily
+    pub management_fee: Decimal,
+    /// Fraction of the gains above the high-water mark
+    pub performance_fee: Decimal,
+}
+
+impl From<&FeeScheduleConfig> for FeeSchedule {
+    fn from(config: &FeeScheduleConfig) -> Self {
+        Self {
+            capital: config.capital,
+            management_fee: config.management_fee,
+            performance_fee: config.performance_fee,
+        }
+    }
+}
+
+/// Fees charged for a day and the resulting net asset value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeAccrual {
+    pub nav: Decimal,
+    pub high_water_mark: Decimal,
+    pub management_fee: Decimal,
+    pub performance_fee: Decimal,
+}
+
+/// Net asset value of a strategy after fees together with its high-water mark. The performance fee is only charged on
+/// gains that bring the net asset value above the highest value it had after fees, so losses have to be recovered
+/// before fees are charged again.
+#[derive(Debug, Clone)]
+pub struct HighWaterMark {
+    schedule: FeeSchedule,
+    nav: Decimal,
+    high_water_mark: Decimal,
+}
+
+impl HighWaterMark {
+    pub fn new(schedule: FeeSchedule) -> Self {
+        Self {
+            schedule,
+            nav: schedule.capital,
+            high_water_mark: schedule.capital,
+        }
+    }
+
+    /// Book the net pnl of a day and charge the fees of that day.
+    pub fn accrue(&mut self, pnl: Decimal) -> FeeAccrual {
+        self.nav += pnl;
+        let management_fee = (self.nav * self.schedule.management_fee / DAYS_PER_YEAR).max(Decimal::ZERO);
+        self.nav -= management_fee;
+        let performance_fee = if self.nav > self.high_water_mark {
+            (self.nav - self.high_water_mark) * self.schedule.performance_fee
+        } else {
+            Decimal::ZERO
+        };
+        self.nav -= performance_fee;
+        self.high_water_mark = self.high_water_mark.max(self.nav);
+        FeeAccrual {
+            nav: self.nav,
+            high_water_mark: self.high_water_mark,
+            management_fee,
+            performance_fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn test_performance_fee_above_high_water_mark() {
+        let mut hwm = HighWaterMark::new(FeeSchedule {
+            capital: dec!(1000),
+            management_fee: Decimal::ZERO,
+            performance_fee: dec!(0.2),
+        });
+        let accrual = hwm.accrue(dec!(100));
+        assert_eq!(accrual.performance_fee, dec!(20));
+        assert_eq!(accrual.nav, dec!(1080));
+        assert_eq!(accrual.high_water_mark, dec!(1080));
+
+        // No fees until the loss is recovered
+        let accrual = hwm.accrue(dec!(-80));
+        assert_eq!(accrual.performance_fee, Decimal::ZERO);
+        assert_eq!(accrual.high_water_mark, dec!(1080));
+        let accrual = hwm.accrue(dec!(130));
+        assert_eq!(accrual.performance_fee, dec!(10));
+        assert_eq!(accrual.nav, dec!(1120));
+    }
+
+    #[test]
+    fn test_management_fee() {
+        let mut hwm = HighWaterMark::new(FeeSchedule {
+            capital: dec!(365000),
+            management_fee: dec!(0.02),
+            performance_fee: Decimal::ZERO,
+        });
+        let accrual = hwm.accrue(Decimal::ZERO);
+        assert_eq!(accrual.management_fee, dec!(20));
+        assert_eq!(accrual.nav, dec!(364980));
+        assert_eq!(accrual.high_water_mark, dec!(365000));
+    }
+}
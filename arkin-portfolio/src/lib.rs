@@ -1,19 +1,31 @@
+mod attribution;
 mod config;
+mod daily_performance;
 mod errors;
 mod factory;
+mod fees;
 mod portfolios;
+mod sub_accounts;
 mod traits;
 
+pub use attribution::*;
 pub use config::*;
+pub use daily_performance::*;
 pub use errors::*;
 pub use factory::*;
+pub use fees::*;
 pub use portfolios::*;
+pub use sub_accounts::*;
 pub use traits::*;
 
 pub mod prelude {
+    pub use crate::attribution::*;
     pub use crate::config::*;
+    pub use crate::daily_performance::*;
     pub use crate::errors::*;
     pub use crate::factory::*;
+    pub use crate::fees::*;
     pub use crate::portfolios::*;
+    pub use crate::sub_accounts::*;
     pub use crate::traits::*;
 }
@@ -4,8 +4,9 @@ use arkin_core::prelude::*;
 use async_trait::async_trait;
 use dashmap::DashMap;
 use rust_decimal::Decimal;
+use time::OffsetDateTime;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{info, warn};
 use typed_builder::TypedBuilder;
 
 use crate::{Accounting, PortfolioError};
@@ -17,6 +18,16 @@ pub struct SingleStrategyPortfolio {
     positions: DashMap<Arc<Instrument>, Arc<PositionUpdate>>,
     #[builder(default = DashMap::new())]
     balances: DashMap<Arc<Asset>, Arc<BalanceUpdate>>,
+    #[builder(default = DashMap::new())]
+    mark_prices: DashMap<Arc<Instrument>, Price>,
+    #[builder(default)]
+    watchdog: Arc<Watchdog>,
+}
+
+impl SingleStrategyPortfolio {
+    pub fn update_mark_price(&self, tick: &Tick) {
+        self.mark_prices.insert(tick.instrument.clone(), tick.mid_price());
+    }
 }
 
 #[async_trait]
@@ -25,16 +36,25 @@ impl Accounting for SingleStrategyPortfolio {
         info!("Starting portfolio...");
         let mut balance_updates = self.pubsub.subscribe::<BalanceUpdate>();
         let mut position_updates = self.pubsub.subscribe::<PositionUpdate>();
+        let mut ticks = self.pubsub.subscribe::<Tick>();
+        let mut simulation_finished = self.pubsub.subscribe::<SimulationFinished>();
         loop {
             tokio::select! {
                 Ok(balance) = balance_updates.recv() => {
-                    if let Err(e) = self.balance_update(balance).await {
-                        error!("Failed to process balance update: {}", e);
-                    }
+                    let _guard = self.watchdog.track("portfolio", "balance_update");
+                    skip_failed_event("portfolio", self.balance_update(balance).await)?;
                 }
                 Ok(position) = position_updates.recv() => {
-                    if let Err(e) = self.position_update(position).await {
-                        error!("Failed to process position update: {}", e);
+                    let _guard = self.watchdog.track("portfolio", "position_update");
+                    skip_failed_event("portfolio", self.position_update(position).await)?;
+                }
+                Ok(tick) = ticks.recv() => {
+                    self.update_mark_price(&tick);
+                }
+                Ok(finished) = simulation_finished.recv() => {
+                    let _guard = self.watchdog.track("portfolio", "simulation_finished");
+                    if let Some(report) = skip_failed_event("portfolio", self.settle(finished.event_time).await)? {
+                        self.pubsub.publish::<SettlementReport>(report.into());
                     }
                 }
                 // Ok(fill) = fill_updates.recv() => {
@@ -62,6 +82,174 @@ impl Accounting for SingleStrategyPortfolio {
         Ok(())
     }
 
+    async fn settle(&self, event_time: OffsetDateTime) -> Result<SettlementReport, PortfolioError> {
+        info!("Settling open positions at {}", event_time);
+        let mut settled = Vec::new();
+
+        let open_positions = self
+            .positions
+            .iter()
+            .filter(|e| !e.value().quantity.is_zero())
+            .map(|e| e.value().clone())
+            .collect::<Vec<_>>();
+
+        for position in open_positions {
+            let instrument = position.instrument.clone();
+            let mark_price = match self.mark_prices.get(&instrument) {
+                Some(p) => *p.value(),
+                None => {
+                    warn!("No mark price for {}, settling at entry price", instrument);
+                    position.entry_price
+                }
+            };
+
+            let quantity = position.quantity.abs();
+            let carry = match position.position_side {
+                PositionSide::Long => (mark_price - position.entry_price) * quantity * instrument.contract_size,
+                PositionSide::Short => (position.entry_price - mark_price) * quantity * instrument.contract_size,
+            };
+
+            // Book the carry on the quote asset balance
+            let balance = self
+                .balance(&instrument.quote_asset)
+                .await
+                .map(|b| b.quantity)
+                .unwrap_or(Decimal::ZERO);
+            let balance_update = Arc::new(
+                BalanceUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(position.portfolio.clone())
+                    .asset(instrument.quote_asset.clone())
+                    .quantity(balance + carry)
+                    .build(),
+            );
+            self.balance_update(balance_update.clone()).await?;
+            self.pubsub.publish::<BalanceUpdate>(balance_update);
+
+            let position_update = Arc::new(
+                PositionUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(position.portfolio.clone())
+                    .instrument(instrument.clone())
+                    .entry_price(mark_price)
+                    .quantity(Decimal::ZERO)
+                    .realized_pnl(position.realized_pnl + carry)
+                    .unrealized_pnl(Decimal::ZERO)
+                    .position_side(position.position_side)
+                    .build(),
+            );
+            self.position_update(position_update.clone()).await?;
+            self.pubsub.publish::<PositionUpdate>(position_update);
+            settled.push(
+                SettledPosition::builder()
+                    .instrument(instrument)
+                    .side(position.position_side)
+                    .quantity(quantity)
+                    .entry_price(position.entry_price)
+                    .mark_price(mark_price)
+                    .carry(carry)
+                    .build(),
+            );
+        }
+
+        let report = SettlementReport::builder().event_time(event_time).positions(settled).build();
+        info!("{}", report);
+        Ok(report)
+    }
+
+    fn checkpoint(&self, checkpoint: &mut SimulationCheckpoint) {
+        let portfolio = self
+            .positions
+            .iter()
+            .map(|p| p.value().portfolio.id)
+            .chain(self.balances.iter().map(|b| b.value().portfolio.id))
+            .next();
+        let Some(portfolio_id) = portfolio else {
+            return;
+        };
+        checkpoint.portfolio = Some(PortfolioState {
+            portfolio_id,
+            positions: self
+                .positions
+                .iter()
+                .filter(|p| !p.quantity.is_zero())
+                .map(|p| PositionState {
+                    instrument_id: p.instrument.id,
+                    side: p.position_side.to_string(),
+                    open_price: p.entry_price,
+                    quantity: p.quantity,
+                    realized_pnl: p.realized_pnl,
+                })
+                .collect(),
+            balances: self
+                .balances
+                .iter()
+                .map(|b| BalanceState {
+                    asset: b.asset.symbol.clone(),
+                    balance: b.quantity,
+                })
+                .collect(),
+        });
+    }
+
+    /// Balances are restored for the assets of the instruments, the mark prices follow with the first ticks.
+    fn restore(
+        &self,
+        checkpoint: &SimulationCheckpoint,
+        portfolio: &Arc<Portfolio>,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<(), PortfolioError> {
+        let Some(state) = &checkpoint.portfolio else {
+            return Ok(());
+        };
+        let event_time = checkpoint.clock.current_timestamp;
+
+        for balance in &state.balances {
+            let asset = instruments
+                .iter()
+                .flat_map(|i| [&i.quote_asset, &i.base_asset])
+                .find(|a| a.symbol == balance.asset)
+                .ok_or_else(|| PortfolioError::AssetNotFound(balance.asset.clone()))?;
+            let update = BalanceUpdate::builder()
+                .event_time(event_time)
+                .portfolio(portfolio.clone())
+                .asset(asset.clone())
+                .quantity(balance.balance)
+                .build();
+            self.balances.insert(asset.clone(), Arc::new(update));
+        }
+
+        for position in &state.positions {
+            let instrument = instruments
+                .iter()
+                .find(|i| i.id == position.instrument_id)
+                .ok_or_else(|| PortfolioError::InstrumentNotFound(position.instrument_id.to_string()))?;
+            let update = PositionUpdate::builder()
+                .event_time(event_time)
+                .portfolio(portfolio.clone())
+                .instrument(instrument.clone())
+                .entry_price(position.open_price)
+                .quantity(position.quantity)
+                .realized_pnl(position.realized_pnl)
+                .unrealized_pnl(Decimal::ZERO)
+                .position_side(match position.quantity < Decimal::ZERO {
+                    true => PositionSide::Short,
+                    false => PositionSide::Long,
+                })
+                .build();
+            self.positions.insert(instrument.clone(), Arc::new(update));
+        }
+
+        info!(
+            "Portfolio {} restored {} positions and {} balances at {}",
+            portfolio,
+            state.positions.len(),
+            state.balances.len(),
+            event_time
+        );
+        Ok(())
+    }
+
     async fn balance(&self, asset: &Arc<Asset>) -> Option<Arc<BalanceUpdate>> {
         self.balances.get(asset).map(|v| v.value().clone())
     }
@@ -103,3 +291,75 @@ impl Accounting for SingleStrategyPortfolio {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use test_log::test;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_checkpoint_restore() {
+        let pubsub = Arc::new(PubSub::new());
+        let portfolio = SingleStrategyPortfolio::builder().pubsub(pubsub.clone()).build();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let event_time = OffsetDateTime::UNIX_EPOCH + std::time::Duration::from_secs(3600);
+
+        portfolio
+            .balance_update(
+                BalanceUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(test_portfolio())
+                    .asset(test_usdt_asset())
+                    .quantity(dec!(9500))
+                    .build()
+                    .into(),
+            )
+            .await
+            .unwrap();
+        portfolio
+            .position_update(
+                PositionUpdate::builder()
+                    .event_time(event_time)
+                    .portfolio(test_portfolio())
+                    .instrument(instrument.clone())
+                    .entry_price(dec!(50000))
+                    .quantity(dec!(-0.1))
+                    .realized_pnl(dec!(12))
+                    .unrealized_pnl(Decimal::ZERO)
+                    .position_side(PositionSide::Short)
+                    .build()
+                    .into(),
+            )
+            .await
+            .unwrap();
+
+        let mut checkpoint = SimulationCheckpoint::builder()
+            .clock(ClockState {
+                start: OffsetDateTime::UNIX_EPOCH,
+                end: OffsetDateTime::UNIX_EPOCH + std::time::Duration::from_secs(86400),
+                frequency_secs: 60,
+                current_timestamp: event_time,
+            })
+            .build();
+        portfolio.checkpoint(&mut checkpoint);
+        let state = checkpoint.portfolio.as_ref().unwrap();
+        assert_eq!(state.portfolio_id, test_portfolio().id);
+        assert_eq!(state.positions.len(), 1);
+        assert_eq!(state.balances.len(), 1);
+
+        // The restored positions and balances are booked under the portfolio of the resumed run
+        let restored = SingleStrategyPortfolio::builder().pubsub(pubsub).build();
+        let resumed = test_portfolio();
+        restored.restore(&checkpoint, &resumed, &[instrument.clone()]).unwrap();
+        let position = restored.get_position_by_instrument(&instrument).await.unwrap();
+        assert_eq!(position.quantity, dec!(-0.1));
+        assert_eq!(position.position_side, PositionSide::Short);
+        assert_eq!(position.realized_pnl, dec!(12));
+        assert!(Arc::ptr_eq(&position.portfolio, &resumed));
+        let balance = restored.balance(&test_usdt_asset()).await.unwrap();
+        assert_eq!(balance.quantity, dec!(9500));
+        assert!(Arc::ptr_eq(&balance.portfolio, &resumed));
+    }
+}
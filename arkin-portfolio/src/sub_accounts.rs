@@ -0,0 +1,554 @@
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use typed_builder::TypedBuilder;
+use uuid::Uuid;
+
+use arkin_core::prelude::*;
+
+use crate::{InstrumentBook, PortfolioError, SubAccountsSettings, DEFAULT_STRATEGY};
+
+/// State of the sub-account of a strategy at the last mark prices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubAccount {
+    pub portfolio: Arc<Portfolio>,
+    pub strategy: String,
+    /// Initial capital plus the net capital transferred in
+    pub capital: Decimal,
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub commission: Decimal,
+    /// Capital plus the pnl after commission
+    pub equity: Decimal,
+    /// Margin the open positions of the strategy would need on their own
+    pub margin_used: Decimal,
+    /// Pnl after commission relative to the capital, zero without capital
+    pub return_on_capital: Decimal,
+}
+
+impl SubAccount {
+    /// Equity not tied up as margin, the most that can be transferred out.
+    pub fn free_capital(&self) -> Decimal {
+        self.equity - self.margin_used
+    }
+}
+
+#[derive(Debug)]
+struct AccountBook {
+    portfolio: Arc<Portfolio>,
+    capital: Decimal,
+    positions: HashMap<Arc<Instrument>, InstrumentBook>,
+    realized_pnl: Decimal,
+    commission: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct LedgerState {
+    /// Strategy of the execution orders seen
+    strategies: HashMap<Uuid, String>,
+    /// Books by portfolio id and strategy
+    accounts: HashMap<(Uuid, String), AccountBook>,
+    marks: HashMap<Arc<Instrument>, Price>,
+}
+
+/// Virtual sub-accounts splitting one venue account between the strategies trading on it. Every strategy has its own
+/// capital, positions, margin usage and pnl, booked from the fills of its orders, so the return on capital of a
+/// strategy is measured against the capital it actually had. [`CapitalTransfer`] events move capital between the
+/// strategies, a transfer is rejected if it takes more than the free capital of the giving strategy.
+/// [`WalletTransfer`] events add the capital deposited on the exchange to a strategy and take withdrawals off it.
+#[derive(Debug, TypedBuilder)]
+pub struct SubAccountLedger {
+    pubsub: Arc<PubSub>,
+    /// Fraction of the notional of the open positions held as margin
+    #[builder(default = dec!(0.1))]
+    margin_rate: Decimal,
+    /// Initial capital per strategy, strategies without an entry start without capital
+    #[builder(default)]
+    capital: HashMap<String, Decimal>,
+    #[builder(default)]
+    state: Mutex<LedgerState>,
+}
+
+impl SubAccountLedger {
+    pub fn from_config(config: &SubAccountsSettings, pubsub: Arc<PubSub>) -> Self {
+        Self::builder()
+            .pubsub(pubsub)
+            .margin_rate(config.margin_rate)
+            .capital(config.capital.clone())
+            .build()
+    }
+
+    fn account<'a>(
+        &self,
+        accounts: &'a mut HashMap<(Uuid, String), AccountBook>,
+        portfolio: &Arc<Portfolio>,
+        strategy: &str,
+    ) -> &'a mut AccountBook {
+        accounts
+            .entry((portfolio.id, strategy.to_string()))
+            .or_insert_with(|| AccountBook {
+                portfolio: portfolio.clone(),
+                capital: self.capital.get(strategy).copied().unwrap_or_default(),
+                positions: HashMap::new(),
+                realized_pnl: Decimal::ZERO,
+                commission: Decimal::ZERO,
+            })
+    }
+
+    pub fn order(&self, order: &ExecutionOrder) {
+        let strategy = match &order.strategy {
+            Some(strategy) => strategy.name.clone(),
+            None => DEFAULT_STRATEGY.to_string(),
+        };
+        self.state.lock().strategies.insert(order.id, strategy);
+    }
+
+    pub fn mark(&self, tick: &Tick) {
+        self.state.lock().marks.insert(tick.instrument.clone(), tick.mid_price());
+    }
+
+    /// Book the last fill of the update on the sub-account of the strategy the order belongs to.
+    pub fn fill(&self, update: &VenueOrderUpdate) {
+        let mut state = self.state.lock();
+        let strategy = match Uuid::parse_str(&update.order_id) {
+            Ok(id) if update.status.is_finalized() => state.strategies.remove(&id),
+            Ok(id) => state.strategies.get(&id).cloned(),
+            Err(e) => {
+                warn!("Order update with unknown order id {}: {}", update.order_id, e);
+                None
+            }
+        };
+        if update.last_fill_quantity.is_zero() {
+            return;
+        }
+        let strategy = strategy.unwrap_or_else(|| DEFAULT_STRATEGY.to_string());
+
+        let quantity = Decimal::from(update.side) * update.last_fill_quantity.abs();
+        let account = self.account(&mut state.accounts, &update.portfolio, &strategy);
+        let position = account.positions.entry(update.instrument.clone()).or_default();
+        let realized = position.fill(&update.instrument, quantity, update.last_fill_price);
+        if position.quantity.is_zero() {
+            account.positions.remove(&update.instrument);
+        }
+        account.realized_pnl += realized;
+        match update.quote_commission() {
+            Some(commission) => account.commission += commission,
+            None => warn!(
+                "Commission of {} not charged in {}, not booked",
+                update.commission, update.instrument.quote_asset
+            ),
+        }
+    }
+
+    /// Move capital between two strategies, rejected if the giving strategy doesn't have it free.
+    pub fn transfer(&self, transfer: &CapitalTransfer) -> Result<(), PortfolioError> {
+        if transfer.amount <= Decimal::ZERO || transfer.from == transfer.to {
+            return Err(PortfolioError::InvalidTransfer(transfer.to_string()));
+        }
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let from = self.account(&mut state.accounts, &transfer.portfolio, &transfer.from);
+        let free = self.snapshot(&transfer.from, from, &state.marks).free_capital();
+        if free < transfer.amount {
+            return Err(PortfolioError::InsufficientCapital(format!(
+                "{} has {} free for a transfer of {}",
+                transfer.from, free, transfer.amount
+            )));
+        }
+        self.account(&mut state.accounts, &transfer.portfolio, &transfer.from).capital -= transfer.amount;
+        self.account(&mut state.accounts, &transfer.portfolio, &transfer.to).capital += transfer.amount;
+        info!("Transferred capital: {}", transfer);
+        Ok(())
+    }
+
+    /// Book capital moved on or off the exchange on the sub-account of its strategy. A withdrawal is booked even if
+    /// it takes more than the free capital, the capital already left the exchange.
+    pub fn wallet_transfer(&self, transfer: &WalletTransfer) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let account = self.account(&mut state.accounts, &transfer.portfolio, &transfer.strategy);
+        account.capital += transfer.net_quantity();
+        let free = self.snapshot(&transfer.strategy, account, &state.marks).free_capital();
+        if free < Decimal::ZERO {
+            warn!("Sub-account {} is short {} after {}", transfer.strategy, -free, transfer);
+        }
+        info!("Booked wallet transfer: {}", transfer);
+    }
+
+    fn snapshot(&self, strategy: &str, account: &AccountBook, marks: &HashMap<Arc<Instrument>, Price>) -> SubAccount {
+        let (unrealized_pnl, notional) =
+            account
+                .positions
+                .iter()
+                .fold((Decimal::ZERO, Decimal::ZERO), |(pnl, notional), (instrument, position)| {
+                    let mark = marks.get(instrument).copied().unwrap_or(position.entry_price);
+                    (
+                        pnl + (mark - position.entry_price) * position.quantity * instrument.contract_size,
+                        notional + (mark * position.quantity * instrument.contract_size).abs(),
+                    )
+                });
+        let pnl = account.realized_pnl + unrealized_pnl - account.commission;
+        SubAccount {
+            portfolio: account.portfolio.clone(),
+            strategy: strategy.to_string(),
+            capital: account.capital,
+            realized_pnl: account.realized_pnl,
+            unrealized_pnl,
+            commission: account.commission,
+            equity: account.capital + pnl,
+            margin_used: notional * self.margin_rate,
+            return_on_capital: match account.capital.is_zero() {
+                true => Decimal::ZERO,
+                false => pnl / account.capital,
+            },
+        }
+    }
+
+    pub fn sub_account(&self, portfolio: &Arc<Portfolio>, strategy: &str) -> Option<SubAccount> {
+        let state = self.state.lock();
+        state
+            .accounts
+            .get(&(portfolio.id, strategy.to_string()))
+            .map(|account| self.snapshot(strategy, account, &state.marks))
+    }
+
+    /// All sub-accounts sorted by portfolio and strategy.
+    pub fn sub_accounts(&self) -> Vec<SubAccount> {
+        let state = self.state.lock();
+        let mut accounts = state
+            .accounts
+            .iter()
+            .map(|((_, strategy), account)| self.snapshot(strategy, account, &state.marks))
+            .collect::<Vec<_>>();
+        accounts.sort_by(|a, b| (&a.portfolio.name, &a.strategy).cmp(&(&b.portfolio.name, &b.strategy)));
+        accounts
+    }
+
+    /// Write the books of the strategies and the strategy of the open orders into the checkpoint.
+    pub fn checkpoint(&self, checkpoint: &mut SimulationCheckpoint) {
+        let state = self.state.lock();
+        let accounts = state
+            .accounts
+            .iter()
+            .map(|((_, strategy), account)| StrategyState {
+                strategy: strategy.clone(),
+                capital: account.capital,
+                realized_pnl: account.realized_pnl,
+                commission: account.commission,
+                positions: account
+                    .positions
+                    .iter()
+                    .map(|(instrument, position)| PositionState {
+                        instrument_id: instrument.id,
+                        side: match position.quantity < Decimal::ZERO {
+                            true => PositionSide::Short,
+                            false => PositionSide::Long,
+                        }
+                        .to_string(),
+                        open_price: position.entry_price,
+                        quantity: position.quantity,
+                        realized_pnl: Decimal::ZERO,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let orders = state.strategies.iter().map(|(id, strategy)| (*id, strategy.clone())).collect();
+        checkpoint.sub_accounts = Some(SubAccountsState { accounts, orders });
+    }
+
+    /// Books of the strategies are restored under the portfolio the run resumes with, the marks follow with the
+    /// first ticks.
+    pub fn restore(
+        &self,
+        checkpoint: &SimulationCheckpoint,
+        portfolio: &Arc<Portfolio>,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<(), PortfolioError> {
+        let Some(sub_accounts) = &checkpoint.sub_accounts else {
+            return Ok(());
+        };
+        let mut state = self.state.lock();
+        for account in &sub_accounts.accounts {
+            let mut positions = HashMap::new();
+            for position in &account.positions {
+                let instrument = instruments
+                    .iter()
+                    .find(|i| i.id == position.instrument_id)
+                    .ok_or_else(|| PortfolioError::InstrumentNotFound(position.instrument_id.to_string()))?;
+                let book = InstrumentBook {
+                    quantity: position.quantity,
+                    entry_price: position.open_price,
+                };
+                positions.insert(instrument.clone(), book);
+            }
+            let book = AccountBook {
+                portfolio: portfolio.clone(),
+                capital: account.capital,
+                positions,
+                realized_pnl: account.realized_pnl,
+                commission: account.commission,
+            };
+            state.accounts.insert((portfolio.id, account.strategy.clone()), book);
+        }
+        state.strategies.extend(sub_accounts.orders.iter().cloned());
+        info!(
+            "Sub-account ledger restored {} strategies and {} open orders",
+            sub_accounts.accounts.len(),
+            sub_accounts.orders.len()
+        );
+        Ok(())
+    }
+
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<(), PortfolioError> {
+        info!("Starting sub-account ledger...");
+        let mut execution_orders = self.pubsub.subscribe::<ExecutionOrder>();
+        let mut order_updates = self.pubsub.subscribe::<VenueOrderUpdate>();
+        let mut ticks = self.pubsub.subscribe::<Tick>();
+        let mut transfers = self.pubsub.subscribe::<CapitalTransfer>();
+        let mut wallet_transfers = self.pubsub.subscribe::<WalletTransfer>();
+        loop {
+            tokio::select! {
+                Ok(order) = execution_orders.recv() => self.order(&order),
+                Ok(update) = order_updates.recv() => self.fill(&update),
+                Ok(tick) = ticks.recv() => self.mark(&tick),
+                Ok(transfer) = transfers.recv() => {
+                    if let Err(e) = self.transfer(&transfer) {
+                        warn!("Rejected capital transfer: {}", e);
+                    }
+                }
+                Ok(transfer) = wallet_transfers.recv() => self.wallet_transfer(&transfer),
+                _ = shutdown.cancelled() => break,
+            }
+        }
+        for account in self.sub_accounts() {
+            info!(
+                "Sub-account {} {}: capital={} equity={} margin={} roc={}",
+                account.portfolio.name,
+                account.strategy,
+                account.capital,
+                account.equity,
+                account.margin_used,
+                account.return_on_capital
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn order(strategy: &str, side: MarketSide) -> ExecutionOrder {
+        ExecutionOrder::builder()
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .strategy(Some(Arc::new(
+                Strategy::builder().name(strategy.to_string()).description(None).build(),
+            )))
+            .order_type(ExecutionOrderType::Taker)
+            .side(side)
+            .price(dec!(100))
+            .quantity(dec!(1))
+            .build()
+    }
+
+    fn update(order: &ExecutionOrder, price: Decimal, quantity: Decimal) -> VenueOrderUpdate {
+        VenueOrderUpdate::builder()
+            .event_time(OffsetDateTime::now_utc())
+            .portfolio(test_portfolio())
+            .instrument(test_inst_binance_btc_usdt_perp())
+            .order_id(order.id.to_string())
+            .venue_order_id(1)
+            .side(order.side)
+            .order_type(VenueOrderType::Market)
+            .time_in_force(VenueOrderTimeInForce::Gtc)
+            .price(price)
+            .quantity(quantity)
+            .fill_price(price)
+            .fill_quantity(quantity)
+            .last_fill_price(price)
+            .last_fill_quantity(quantity)
+            .status(VenueOrderStatus::Filled)
+            .commission_asset(None)
+            .commission(dec!(1))
+            .build()
+    }
+
+    fn transfer(from: &str, to: &str, amount: Decimal) -> CapitalTransfer {
+        CapitalTransfer::builder()
+            .event_time(OffsetDateTime::now_utc())
+            .portfolio(test_portfolio())
+            .from(from.to_string())
+            .to(to.to_string())
+            .amount(amount)
+            .build()
+    }
+
+    #[test]
+    fn test_sub_accounts() {
+        let ledger = SubAccountLedger::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .capital(HashMap::from([
+                ("momentum".to_string(), dec!(1000)),
+                ("carry".to_string(), dec!(500)),
+            ]))
+            .build();
+        let portfolio = test_portfolio();
+
+        let buy = order("momentum", MarketSide::Buy);
+        ledger.order(&buy);
+        ledger.fill(&update(&buy, dec!(100), dec!(5)));
+        let sell = order("carry", MarketSide::Sell);
+        ledger.order(&sell);
+        ledger.fill(&update(&sell, dec!(100), dec!(2)));
+        ledger.mark(&test_tick(
+            test_inst_binance_btc_usdt_perp(),
+            dec!(109),
+            dec!(1),
+            dec!(111),
+            dec!(1),
+        ));
+
+        // Both trade the same instrument on one account but keep their own positions
+        let momentum = ledger.sub_account(&portfolio, "momentum").unwrap();
+        assert_eq!(momentum.unrealized_pnl, dec!(50));
+        assert_eq!(momentum.equity, dec!(1049));
+        assert_eq!(momentum.margin_used, dec!(55));
+        assert_eq!(momentum.return_on_capital, dec!(0.049));
+        let carry = ledger.sub_account(&portfolio, "carry").unwrap();
+        assert_eq!(carry.unrealized_pnl, dec!(-20));
+        assert_eq!(carry.equity, dec!(479));
+        assert_eq!(carry.free_capital(), dec!(457));
+
+        assert!(matches!(
+            ledger.transfer(&transfer("carry", "momentum", dec!(458))),
+            Err(PortfolioError::InsufficientCapital(_))
+        ));
+        assert!(matches!(
+            ledger.transfer(&transfer("carry", "carry", dec!(1))),
+            Err(PortfolioError::InvalidTransfer(_))
+        ));
+        ledger.transfer(&transfer("carry", "momentum", dec!(250))).unwrap();
+        let accounts = ledger.sub_accounts();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].strategy, "carry");
+        assert_eq!(accounts[0].capital, dec!(250));
+        assert_eq!(accounts[0].return_on_capital, dec!(-0.084));
+        assert_eq!(accounts[1].capital, dec!(1250));
+        assert_eq!(accounts[1].equity, dec!(1299));
+    }
+
+    #[test]
+    fn test_commission_asset() {
+        let ledger = SubAccountLedger::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .capital(HashMap::from([("momentum".to_string(), dec!(1000))]))
+            .build();
+
+        // Commission in the quote asset is booked, the one paid in another asset isn't
+        let buy = order("momentum", MarketSide::Buy);
+        ledger.order(&buy);
+        let mut fill = update(&buy, dec!(100), dec!(1));
+        fill.commission_asset = Some(test_usdt_asset());
+        ledger.fill(&fill);
+        let sell = order("momentum", MarketSide::Sell);
+        ledger.order(&sell);
+        let mut fill = update(&sell, dec!(100), dec!(1));
+        fill.commission_asset = Some(test_btc_asset());
+        ledger.fill(&fill);
+
+        // Both fills land on the one book of the strategy, the position is closed again
+        assert_eq!(ledger.sub_accounts().len(), 1);
+        let momentum = ledger.sub_account(&test_portfolio(), "momentum").unwrap();
+        assert_eq!(momentum.realized_pnl, Decimal::ZERO);
+        assert_eq!(momentum.margin_used, Decimal::ZERO);
+        assert_eq!(momentum.commission, dec!(1));
+        assert_eq!(momentum.equity, dec!(999));
+    }
+
+    #[test]
+    fn test_wallet_transfers() {
+        let ledger = SubAccountLedger::builder()
+            .pubsub(Arc::new(PubSub::new()))
+            .capital(HashMap::from([("momentum".to_string(), dec!(1000))]))
+            .build();
+        let wallet_transfer = |direction: WalletTransferDirection, quantity: Decimal| {
+            WalletTransfer::builder()
+                .event_time(OffsetDateTime::now_utc())
+                .portfolio(test_portfolio())
+                .strategy("momentum".to_string())
+                .asset(test_usdt_asset())
+                .direction(direction)
+                .quantity(quantity)
+                .chain("ethereum".to_string())
+                .wallet("0x1111".to_string())
+                .counterparty("0x2222".to_string())
+                .tx_hash("0xabc".to_string())
+                .build()
+        };
+
+        // Deposits add capital without counting as return, withdrawals take it off
+        ledger.wallet_transfer(&wallet_transfer(WalletTransferDirection::Deposit, dec!(500)));
+        ledger.wallet_transfer(&wallet_transfer(WalletTransferDirection::Withdrawal, dec!(200)));
+        assert_eq!(ledger.sub_accounts().len(), 1);
+        let momentum = ledger.sub_account(&test_portfolio(), "momentum").unwrap();
+        assert_eq!(momentum.capital, dec!(1300));
+        assert_eq!(momentum.equity, dec!(1300));
+        assert_eq!(momentum.return_on_capital, Decimal::ZERO);
+
+        let transaction = wallet_transfer(WalletTransferDirection::Withdrawal, dec!(200)).to_transaction();
+        assert_eq!(transaction.transaction_type, TransactionType::Withdrawal);
+        assert_eq!(transaction.quantity, dec!(-200));
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let ledger = || {
+            SubAccountLedger::builder()
+                .pubsub(Arc::new(PubSub::new()))
+                .capital(HashMap::from([("momentum".to_string(), dec!(1000))]))
+                .build()
+        };
+        let portfolio = test_portfolio();
+        let instrument = test_inst_binance_btc_usdt_perp();
+        let original = ledger();
+        let buy = order("momentum", MarketSide::Buy);
+        original.order(&buy);
+        original.fill(&update(&buy, dec!(100), dec!(5)));
+        let open = order("momentum", MarketSide::Sell);
+        original.order(&open);
+
+        let mut checkpoint = SimulationCheckpoint::builder()
+            .clock(ClockState {
+                start: OffsetDateTime::UNIX_EPOCH,
+                end: OffsetDateTime::UNIX_EPOCH,
+                frequency_secs: 60,
+                current_timestamp: OffsetDateTime::UNIX_EPOCH,
+            })
+            .build();
+        original.checkpoint(&mut checkpoint);
+        let restored = ledger();
+        restored.restore(&checkpoint, &portfolio, &[instrument.clone()]).unwrap();
+        let books = |ledger: &SubAccountLedger| {
+            ledger
+                .sub_accounts()
+                .into_iter()
+                .map(|a| (a.strategy, a.capital, a.realized_pnl, a.commission, a.equity, a.margin_used))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(books(&restored), books(&original));
+
+        // The fill of the order open at the checkpoint is booked on its strategy
+        restored.fill(&update(&open, dec!(110), dec!(5)));
+        let momentum = restored.sub_account(&portfolio, "momentum").unwrap();
+        assert_eq!(momentum.realized_pnl, dec!(50));
+        assert_eq!(momentum.margin_used, Decimal::ZERO);
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use mockall::automock;
 use rust_decimal::prelude::*;
+use time::OffsetDateTime;
 use tokio_util::sync::CancellationToken;
 
 use arkin_core::prelude::*;
@@ -23,6 +24,21 @@ pub trait Accounting: std::fmt::Debug + Send + Sync {
     /// This comes from the exchange and should be reconciled with the portfolio
     async fn position_update(&self, update: Arc<PositionUpdate>) -> Result<(), PortfolioError>;
 
+    /// Close all open positions at the last known mark price and book the resulting pnl on the balances.
+    /// Used at the end of a simulation so results don't hide unrealized exposure, the report lists the carry booked.
+    async fn settle(&self, event_time: OffsetDateTime) -> Result<SettlementReport, PortfolioError>;
+
+    /// Write the booked positions and balances into a checkpoint of the simulation.
+    fn checkpoint(&self, checkpoint: &mut SimulationCheckpoint);
+
+    /// Book the positions and balances of a checkpoint under the portfolio before the simulation resumes.
+    fn restore(
+        &self,
+        checkpoint: &SimulationCheckpoint,
+        portfolio: &Arc<Portfolio>,
+        instruments: &[Arc<Instrument>],
+    ) -> Result<(), PortfolioError>;
+
     /// Provides the current price of a specific assets in the portfolio
     async fn balance(&self, asset: &Arc<Asset>) -> Option<Arc<BalanceUpdate>>;
 
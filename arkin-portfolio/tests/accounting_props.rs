@@ -0,0 +1,221 @@
+use std::{collections::HashMap, sync::Arc};
+
+use proptest::{prelude::*, strategy::Strategy};
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
+use arkin_portfolio::prelude::*;
+
+/// A single accounting event fed into the portfolio.
+#[derive(Debug, Clone)]
+enum Op {
+    Balance(Decimal),
+    Position {
+        instrument: usize,
+        side: PositionSide,
+        entry_price: Decimal,
+        quantity: Decimal,
+        realized_pnl: Decimal,
+    },
+    Mark {
+        instrument: usize,
+        price: Decimal,
+    },
+}
+
+fn instruments() -> Vec<Arc<Instrument>> {
+    vec![test_inst_binance_btc_usdt_perp(), test_inst_binance_eth_usdt_perp()]
+}
+
+fn price() -> impl Strategy<Value = Decimal> {
+    (1i64..10_000_000).prop_map(|c| Decimal::new(c, 2))
+}
+
+fn quantity() -> impl Strategy<Value = Decimal> {
+    (0i64..100_000).prop_map(|q| Decimal::new(q, 3))
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0i64..100_000_000).prop_map(|c| Op::Balance(Decimal::new(c, 2))),
+        (
+            0usize..2,
+            prop_oneof![Just(PositionSide::Long), Just(PositionSide::Short)],
+            price(),
+            quantity(),
+            (-1_000_000i64..1_000_000).prop_map(|c| Decimal::new(c, 2)),
+        )
+            .prop_map(|(instrument, side, entry_price, quantity, realized_pnl)| Op::Position {
+                instrument,
+                side,
+                entry_price,
+                quantity,
+                realized_pnl,
+            }),
+        (0usize..2, price()).prop_map(|(instrument, price)| Op::Mark { instrument, price }),
+    ]
+}
+
+/// Reference model of the expected state, updated with last-write-wins semantics.
+#[derive(Debug, Default)]
+struct Model {
+    balance: Decimal,
+    positions: HashMap<usize, (PositionSide, Decimal, Decimal, Decimal)>,
+    marks: HashMap<usize, Decimal>,
+}
+
+impl Model {
+    fn apply(&mut self, op: &Op) {
+        match op {
+            Op::Balance(b) => self.balance = *b,
+            Op::Position {
+                instrument,
+                side,
+                entry_price,
+                quantity,
+                realized_pnl,
+            } => {
+                self.positions
+                    .insert(*instrument, (*side, *entry_price, *quantity, *realized_pnl));
+            }
+            Op::Mark { instrument, price } => {
+                self.marks.insert(*instrument, *price);
+            }
+        }
+    }
+
+    /// Pnl of closing the position at the mark price (or entry price without a mark).
+    fn carry(&self, instrument: usize, contract_size: Decimal) -> Decimal {
+        match self.positions.get(&instrument) {
+            Some((side, entry, quantity, _)) => {
+                let mark = self.marks.get(&instrument).cloned().unwrap_or(*entry);
+                match side {
+                    PositionSide::Long => (mark - entry) * quantity * contract_size,
+                    PositionSide::Short => (entry - mark) * quantity * contract_size,
+                }
+            }
+            None => Decimal::ZERO,
+        }
+    }
+
+    /// Balance plus unrealized pnl at the mark prices.
+    fn equity(&self, instruments: &[Arc<Instrument>]) -> Decimal {
+        self.balance
+            + instruments
+                .iter()
+                .enumerate()
+                .map(|(idx, i)| self.carry(idx, i.contract_size))
+                .sum::<Decimal>()
+    }
+}
+
+async fn replay(portfolio: &SingleStrategyPortfolio, instruments: &[Arc<Instrument>], ops: &[Op]) {
+    let now = OffsetDateTime::now_utc();
+    let quote = instruments[0].quote_asset.clone();
+    for op in ops {
+        match op {
+            Op::Balance(b) => {
+                let update = BalanceUpdate::builder()
+                    .event_time(now)
+                    .portfolio(test_portfolio())
+                    .asset(quote.clone())
+                    .quantity(*b)
+                    .build();
+                portfolio.balance_update(Arc::new(update)).await.unwrap();
+            }
+            Op::Position {
+                instrument,
+                side,
+                entry_price,
+                quantity,
+                realized_pnl,
+            } => {
+                let update = PositionUpdate::builder()
+                    .event_time(now)
+                    .portfolio(test_portfolio())
+                    .instrument(instruments[*instrument].clone())
+                    .entry_price(*entry_price)
+                    .quantity(*quantity)
+                    .realized_pnl(*realized_pnl)
+                    .unrealized_pnl(Decimal::ZERO)
+                    .position_side(*side)
+                    .build();
+                portfolio.position_update(Arc::new(update)).await.unwrap();
+            }
+            Op::Mark { instrument, price } => {
+                let tick = test_tick(instruments[*instrument].clone(), *price, Decimal::ONE, *price, Decimal::ONE);
+                portfolio.update_mark_price(&tick);
+            }
+        }
+    }
+}
+
+fn run<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(f)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    /// Settling turns unrealized pnl into balance without creating or destroying equity.
+    #[test]
+    fn settlement_conserves_equity(ops in prop::collection::vec(op(), 0..50)) {
+        let instruments = instruments();
+        let mut model = Model::default();
+        ops.iter().for_each(|op| model.apply(op));
+
+        let (balance_before, balance_after) = run(async {
+            let portfolio = SingleStrategyPortfolio::builder().pubsub(Arc::new(PubSub::new())).build();
+            replay(&portfolio, &instruments, &ops).await;
+            let quote = instruments[0].quote_asset.clone();
+            let before = portfolio.balance(&quote).await.map(|b| b.quantity).unwrap_or_default();
+            portfolio.settle(OffsetDateTime::now_utc()).await.unwrap();
+            let after = portfolio.balance(&quote).await.map(|b| b.quantity).unwrap_or_default();
+            (before, after)
+        });
+
+        prop_assert_eq!(balance_before, model.balance);
+        prop_assert_eq!(balance_after, model.equity(&instruments));
+    }
+
+    /// After settlement every position is flat, its realized pnl grew by exactly the carry and a second
+    /// settlement changes nothing.
+    #[test]
+    fn settlement_flattens_and_is_idempotent(ops in prop::collection::vec(op(), 0..50)) {
+        let instruments = instruments();
+        let mut model = Model::default();
+        ops.iter().for_each(|op| model.apply(op));
+
+        let (positions, balance, resettled) = run(async {
+            let portfolio = SingleStrategyPortfolio::builder().pubsub(Arc::new(PubSub::new())).build();
+            replay(&portfolio, &instruments, &ops).await;
+            portfolio.settle(OffsetDateTime::now_utc()).await.unwrap();
+            let positions = portfolio.get_positions().await;
+            let balance = portfolio.available_balance(&instruments[0].quote_asset).await;
+            let resettled = portfolio.settle(OffsetDateTime::now_utc()).await.unwrap();
+            (positions, balance, resettled)
+        });
+
+        prop_assert!(resettled.positions.is_empty());
+        prop_assert!(positions.values().all(|p| p.quantity.is_zero()));
+        // Without open positions the available balance is the plain balance
+        prop_assert_eq!(balance, model.equity(&instruments));
+
+        for (idx, instrument) in instruments.iter().enumerate() {
+            if let Some((_, _, quantity, realized)) = model.positions.get(&idx) {
+                let position = positions.get(instrument).unwrap();
+                let expected = if quantity.is_zero() {
+                    *realized
+                } else {
+                    *realized + model.carry(idx, instrument.contract_size)
+                };
+                prop_assert_eq!(position.realized_pnl, expected);
+            }
+        }
+    }
+}
@@ -300,3 +300,60 @@
 //     let commission = portfolio.commission_instrument(&instrument).await;
 //     assert_eq!(commission, dec!(6));
 // }
+
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use test_log::test;
+use time::OffsetDateTime;
+
+use arkin_core::prelude::*;
+use arkin_portfolio::prelude::*;
+
+#[test(tokio::test)]
+async fn test_single_strategy_settle_at_mark() {
+    let pubsub = Arc::new(PubSub::new());
+    let portfolio = SingleStrategyPortfolio::builder().pubsub(pubsub.clone()).build();
+    let instrument = test_inst_binance_btc_usdt_perp();
+    let now = OffsetDateTime::now_utc();
+
+    let balance = BalanceUpdate::builder()
+        .event_time(now)
+        .portfolio(test_portfolio())
+        .asset(instrument.quote_asset.clone())
+        .quantity(dec!(10000))
+        .build();
+    portfolio.balance_update(Arc::new(balance)).await.unwrap();
+
+    let position = PositionUpdate::builder()
+        .event_time(now)
+        .portfolio(test_portfolio())
+        .instrument(instrument.clone())
+        .entry_price(dec!(100))
+        .quantity(dec!(2))
+        .realized_pnl(dec!(5))
+        .unrealized_pnl(Decimal::ZERO)
+        .position_side(PositionSide::Long)
+        .build();
+    portfolio.position_update(Arc::new(position)).await.unwrap();
+
+    let tick = test_tick(instrument.clone(), dec!(109), dec!(1), dec!(111), dec!(1));
+    portfolio.update_mark_price(&tick);
+
+    let report = portfolio.settle(now).await.unwrap();
+    assert_eq!(report.positions.len(), 1);
+    assert_eq!(report.positions[0].mark_price, dec!(110));
+    assert_eq!(report.positions[0].carry, dec!(20));
+    assert_eq!(report.carry()[&instrument.quote_asset.symbol], dec!(20));
+
+    let position = portfolio.get_position_by_instrument(&instrument).await.unwrap();
+    assert_eq!(position.quantity, Decimal::ZERO);
+    assert_eq!(position.realized_pnl, dec!(25));
+
+    let balance = portfolio.balance(&instrument.quote_asset).await.unwrap();
+    assert_eq!(balance.quantity, dec!(10020));
+
+    // Settling again is a no-op
+    assert!(portfolio.settle(now).await.unwrap().positions.is_empty());
+}
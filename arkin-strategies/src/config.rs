@@ -3,8 +3,9 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct StrategyConfig {
+    #[serde(default)]
     pub strategies: Vec<StrategyAlgorithmConfig>,
 }
 
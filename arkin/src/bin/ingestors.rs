@@ -7,6 +7,7 @@ use tokio_util::task::TaskTracker;
 use tracing::error;
 use tracing::info;
 
+use arkin_binance::VenuesConfig;
 use arkin_core::prelude::*;
 use arkin_ingestors::prelude::*;
 use arkin_persistence::prelude::*;
@@ -24,8 +25,13 @@ async fn main() {
     let config = load::<PersistenceConfig>();
     let persistence_service = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
 
+    let config = load::<VenuesConfig>();
+    let adapters = VenueAdapters::default();
+    config.venues.register(&adapters);
+
     let config = load::<IngestorsConfig>();
-    let ingestors = IngestorFactory::from_config(&config, pubsub.clone(), persistence_service.clone());
+    let ingestors =
+        IngestorFactory::from_config(&config, pubsub.clone(), persistence_service.clone(), &adapters, None, None);
 
     // Start the persistence service
     let persistence_task_tracker = TaskTracker::new();
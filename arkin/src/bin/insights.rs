@@ -45,7 +45,7 @@ async fn main() -> Result<()> {
     let venue_symbols = vec!["BTCUSDT", "ETHUSDT", "SOLUSDT"];
     let mut instruments = vec![];
     for symbol in venue_symbols {
-        match persistence.instrument_store.read_by_venue_symbol(symbol).await {
+        match persistence.symbol_registry.instrument("binance", symbol).await {
             Ok(instr) => instruments.push(instr),
             Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
         }
@@ -5,6 +5,7 @@ use tokio_rustls::rustls::crypto::{aws_lc_rs, CryptoProvider};
 use tracing::{error, info};
 
 use arkin_allocation::prelude::*;
+use arkin_binance::VenuesConfig;
 use arkin_core::prelude::*;
 use arkin_engine::prelude::*;
 use arkin_execution::prelude::*;
@@ -21,40 +22,82 @@ async fn main() {
     let pubsub = Arc::new(PubSub::new());
     info!("PubSub created");
 
+    let watchdog = Arc::new(Watchdog::default());
+
     let config = load::<PersistenceConfig>();
     let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
     info!("Persistence created");
 
     let config = load::<PortfolioConfig>();
-    let portfolio = PortfolioFactory::from_config(&config, pubsub.clone());
+    let portfolio = PortfolioFactory::from_config(&config, pubsub.clone(), watchdog.clone());
     info!("Portfolio created");
 
+    let config = load::<PerformanceFeesConfig>();
+    let daily_performance = Arc::new(DailyPerformanceTracker::from_config(
+        config.performance_fees.as_ref(),
+        pubsub.clone(),
+    ));
+
+    let config = load::<VenuesConfig>();
+    let adapters = VenueAdapters::default();
+    config.venues.register(&adapters);
+
     let config = load::<IngestorsConfig>();
-    let ingestors = IngestorFactory::from_config(&config, pubsub.clone(), persistence.clone());
+    let ingestors = IngestorFactory::from_config(&config, pubsub.clone(), persistence.clone(), &adapters, None, None);
     info!("Ingestors created");
 
     let config = load::<InsightsConfig>();
-    let insights =
-        Arc::new(InsightsService::from_config(&config.insights_service, pubsub.clone(), persistence.clone()).await);
+    let insights = Arc::new(
+        InsightsService::from_config(&config.insights_service, pubsub.clone(), persistence.clone())
+            .await
+            .with_watchdog(watchdog.clone()),
+    );
     info!("Insights created");
 
     let config = load::<AllocationOptimConfig>();
-    let allocation = AllocationFactory::from_config(&config, pubsub.clone(), persistence.clone(), portfolio.clone());
+    let allocation = AllocationFactory::from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        portfolio.clone(),
+        watchdog.clone(),
+    );
+    let hedger = AllocationFactory::hedger_from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        portfolio.clone(),
+        watchdog.clone(),
+    );
+    let overlay = AllocationFactory::overlay_from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        portfolio.clone(),
+        watchdog.clone(),
+    );
     info!("Allocation created");
 
     let config = load::<OrderManagerConfig>();
-    let order_manager = ExecutionFactory::from_config(&config, pubsub.clone());
+    let switch = Arc::new(TradingSwitch::default());
+    let order_manager = ExecutionFactory::from_config(&config, pubsub.clone(), watchdog.clone(), switch);
     info!("Order Manager created");
 
     let config = load::<ExecutorConfig>();
-    let executor = ExecutorFactory::from_config(&config, pubsub.clone(), persistence.clone());
+    let executor = ExecutorFactory::from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        &adapters,
+        Arc::new(Leadership::default()),
+    );
     info!("Executor created");
 
     // Work around for fetching instruments
     let venue_symbols = vec!["BTCUSDT", "ETHUSDT", "SOLUSDT"];
     let mut instruments = vec![];
     for symbol in venue_symbols {
-        match persistence.instrument_store.read_by_venue_symbol(symbol).await {
+        match persistence.symbol_registry.instrument("binance", symbol).await {
             Ok(instr) => instruments.push(instr),
             Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
         }
@@ -66,11 +109,15 @@ async fn main() {
         .instruments(instruments)
         .persistor(persistence)
         .portfolio(portfolio)
+        .daily_performance(Some(daily_performance))
         .ingestors(ingestors)
         .insights(insights)
         .allocation_optim(allocation)
+        .hedger(hedger)
+        .overlay(overlay)
         .order_manager(order_manager)
         .executor(executor)
+        .watchdog(watchdog)
         .build();
 
     engine.start().await.expect("Failed to start engine");
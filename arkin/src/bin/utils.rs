@@ -1,13 +1,23 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, future::Future, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::Result;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{
+    engine::{ArgValueCandidates, CompletionCandidate},
+    CompleteEnv,
+};
+use dialoguer::FuzzySelect;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
 use time::{macros::format_description, OffsetDateTime, PrimitiveDateTime};
 use tokio_rustls::rustls::crypto::{aws_lc_rs, CryptoProvider};
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 use arkin_allocation::prelude::*;
+use arkin_binance::VenuesConfig;
 use arkin_core::prelude::*;
 use arkin_engine::prelude::*;
 use arkin_execution::prelude::*;
@@ -15,6 +25,7 @@ use arkin_ingestors::prelude::*;
 use arkin_insights::prelude::*;
 use arkin_persistence::prelude::*;
 use arkin_portfolio::prelude::*;
+use arkin_strategies::prelude::*;
 
 /// CLI application for X
 #[derive(Parser)]
@@ -25,10 +36,20 @@ use arkin_portfolio::prelude::*;
     about = "Welcome to the world of Arkin!"
 )]
 struct Cli {
+    /// Format of the command output, logs go to stderr with json
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
     #[clap(subcommand)]
     command: Commands,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Perform insights related operations
@@ -40,29 +61,99 @@ enum Commands {
 
     /// Perform engine related operations
     Engine(EngineArgs),
+
+    /// Validate insight features against reference fixtures
+    ValidateFeatures(ValidateFeaturesArgs),
+
+    /// Score persisted predictions against the realized returns
+    EvaluatePredictions(EvaluatePredictionsArgs),
+
+    /// Compare the predictions of model versions, e.g. a shadow model against the live one
+    ComparePredictions(ComparePredictionsArgs),
+
+    /// Print the config layers of the profile and the config they resolve to
+    DumpConfig(DumpConfigArgs),
+
+    /// Enable or disable trading on a running engine
+    #[clap(subcommand)]
+    Trading(TradingCommands),
+
+    /// Place and cancel orders by hand on a running engine, they pass the same checks and audit as any order
+    Trade(TradeArgs),
+
+    /// Queue backfills and downloads as jobs that can be paused and resumed
+    #[clap(subcommand)]
+    Jobs(JobsCommands),
+
+    /// Run the services of a role with all settings from the config, e.g. one role per container
+    Serve(ServeArgs),
+
+    /// Replay the persisted events around a point in time through the pipeline, strategies and allocation in
+    /// isolation and dump every step
+    DebugSlice(DebugSliceArgs),
+
+    /// Attach notes to orders, trades or periods, reports list them and leave out the excluded periods
+    #[clap(subcommand)]
+    Annotations(AnnotationsCommands),
 }
 
 #[derive(Args, Debug)]
 struct InsightsArgs {
+    /// Print the resolved pipeline DAG and its estimated state memory without running it
+    #[arg(long)]
+    explain: bool,
+
     /// Source of data (e.g., db)
-    #[arg(long, short)]
-    source: String,
+    #[arg(long, short, required_unless_present = "explain")]
+    source: Option<String>,
 
     /// Destination format (e.g., parquet)
-    #[arg(long, short)]
-    dest: String,
+    #[arg(long, short, required_unless_present = "explain")]
+    dest: Option<String>,
 
     /// Start date in "YYYY-MM-DD HH:MM" format
-    #[arg(long, short, value_parser = parse_datetime)]
-    from: OffsetDateTime,
+    #[arg(long, short, value_parser = parse_datetime, required_unless_present = "explain")]
+    from: Option<OffsetDateTime>,
 
     /// End date in "YYYY-MM-DD HH:MM" format
-    #[arg(long, short, value_parser = parse_datetime)]
-    till: OffsetDateTime,
+    #[arg(long, short, value_parser = parse_datetime, required_unless_present = "explain")]
+    till: Option<OffsetDateTime>,
 
-    /// Pipeline name (e.g., hft)
-    #[arg(long, short, value_delimiter = ',')]
+    /// Instruments (comma-separated)
+    #[arg(long, short, value_delimiter = ',', add = ArgValueCandidates::new(instrument_candidates))]
     instruments: Vec<String>,
+
+    /// Pick the instruments interactively instead
+    #[arg(long, conflicts_with = "instruments")]
+    select: bool,
+
+    /// Checkpoint the run into persistence under this run name
+    #[arg(long, conflicts_with = "resume_from")]
+    checkpoint_run: Option<String>,
+
+    /// Simulated time between checkpoints (e.g., 30m or 1d)
+    #[arg(long, default_value = "1d", value_parser = parse_window)]
+    checkpoint_every: Duration,
+
+    /// Resume the run from the latest checkpoint of the run, it keeps checkpointing under the run
+    #[arg(long)]
+    resume_from: Option<String>,
+
+    /// Number of clock ticks between progress updates
+    #[arg(long, default_value_t = 60)]
+    progress_interval: u64,
+
+    /// Compute the whole range vectorized per feature instead of tick by tick
+    #[arg(long, conflicts_with_all = ["checkpoint_run", "resume_from"])]
+    vectorized: bool,
+
+    /// Also run the streaming engine and fail if a value differs by more than this relative tolerance
+    #[arg(long, requires = "vectorized")]
+    cross_check: Option<f64>,
+
+    /// Compute the vectorized features with Polars data frames, requires the polars feature
+    #[arg(long, requires = "vectorized")]
+    polars: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -72,6 +163,9 @@ enum IngestorsCommands {
 
     /// Configure and start Tardis ingestor
     Tardis(TardisIngestorArgs),
+
+    /// Replay recorded raw feed archives through the parsers
+    Replay(ReplayArgs),
 }
 
 #[derive(Args, Debug)]
@@ -81,7 +175,7 @@ struct BinanceIngestorArgs {
     channels: Vec<String>,
 
     /// Configure the instruments to subscribe to
-    #[arg(long, short, value_delimiter = ',')]
+    #[arg(long, short, value_delimiter = ',', add = ArgValueCandidates::new(instrument_candidates))]
     instruments: Vec<String>,
 }
 
@@ -96,7 +190,7 @@ struct TardisIngestorArgs {
     channel: String,
 
     /// Instruments (comma-separated)
-    #[arg(long, value_delimiter = ',', value_parser)]
+    #[arg(long, value_delimiter = ',', value_parser, add = ArgValueCandidates::new(instrument_candidates))]
     instruments: Vec<String>,
 
     /// Start datetime in "YYYY-MM-DD HH:MM" format
@@ -108,234 +202,2443 @@ struct TardisIngestorArgs {
     end: OffsetDateTime,
 }
 
+#[derive(Args, Debug)]
+struct ReplayArgs {
+    /// Archive directory the recorder writes to
+    #[arg(long)]
+    dir: PathBuf,
+
+    /// Only replay messages received from this datetime in "YYYY-MM-DD HH:MM" format
+    #[arg(long, value_parser = parse_datetime)]
+    from: Option<OffsetDateTime>,
+
+    /// Only replay messages received before this datetime in "YYYY-MM-DD HH:MM" format
+    #[arg(long, value_parser = parse_datetime)]
+    till: Option<OffsetDateTime>,
+
+    /// Persist the replayed ticks and trades
+    #[arg(long)]
+    persist: bool,
+}
+
 #[derive(Args, Debug)]
 struct EngineArgs {
     /// Instruments (comma-separated)
-    #[arg(long, value_delimiter = ',', value_parser)]
+    #[arg(long, value_delimiter = ',', value_parser, add = ArgValueCandidates::new(instrument_candidates))]
     instruments: Vec<String>,
+
+    /// Pick the instruments interactively instead
+    #[arg(long, conflicts_with = "instruments")]
+    select: bool,
+
+    /// Warn when a service takes longer than this to handle an event (milliseconds)
+    #[arg(long, default_value_t = 500)]
+    soft_deadline_ms: u64,
+
+    /// Report a service as stalled when it takes longer than this to handle an event (milliseconds)
+    #[arg(long, default_value_t = 10000)]
+    hard_deadline_ms: u64,
+
+    /// Halt trading and cancel all open orders when a service stalls
+    #[arg(long)]
+    halt_on_stall: bool,
+
+    /// Serve the runtime trading controls on this address (e.g., 127.0.0.1:8090)
+    #[arg(long)]
+    control_address: Option<String>,
+
+    /// Name of the portfolio the manual orders and transfers of the trading controls are booked under
+    #[arg(long, requires = "control_address")]
+    portfolio: Option<String>,
+
+    /// Pause the simulation at breakpoints and inspect it from an interactive console
+    #[arg(long)]
+    debug: bool,
+
+    /// Breakpoint of the debug console (e.g., `at=2025-01-01 12:00`, `event=trade@BTCUSDT` or `pnl<-100`)
+    #[arg(long = "break", requires = "debug")]
+    breakpoints: Vec<Breakpoint>,
+
+    /// Checkpoint the simulation into persistence under this run name
+    #[arg(long, conflicts_with = "resume_from")]
+    checkpoint_run: Option<String>,
+
+    /// Simulated time between checkpoints in whole minutes (e.g., 30m or 1h)
+    #[arg(long, default_value = "1h", value_parser = parse_window)]
+    checkpoint_every: Duration,
+
+    /// Resume the simulation from the latest checkpoint of the run, it keeps checkpointing under the run
+    #[arg(long)]
+    resume_from: Option<String>,
+
+    /// Window of the replay loaded at once (e.g., 10m or 1h), overrides `chunk_secs` of the sim ingestors
+    #[arg(long, value_parser = parse_window)]
+    replay_window: Option<Duration>,
+
+    /// Speed of the replay of the sim ingestors: fast, realtime or a multiplier like 10x
+    #[arg(long)]
+    pacing: Option<ReplayPacing>,
 }
 
-/// Custom parser to convert string to OffsetDateTime
-fn parse_datetime(s: &str) -> Result<OffsetDateTime, String> {
-    let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
-    let ts = PrimitiveDateTime::parse(&s, &format)
-        .map_err(|e| format!("Failed to parse datetime '{}': {}", s, e))?
-        .assume_utc();
-    Ok(ts)
+#[derive(Args, Debug)]
+struct DebugSliceArgs {
+    /// Instrument (e.g., BTCUSDT)
+    #[arg(long, add = ArgValueCandidates::new(instrument_candidates))]
+    instrument: String,
+
+    /// Point in time in "YYYY-MM-DD HH:MM" format
+    #[arg(long, value_parser = parse_datetime)]
+    at: OffsetDateTime,
+
+    /// Window before and after the point in time (e.g., 30s, 5m or 1h)
+    #[arg(long, default_value = "5m", value_parser = parse_window)]
+    window: Duration,
+
+    /// Pipeline to compute the features with instead of the one of the insights config
+    #[arg(long)]
+    pipeline: Option<String>,
+
+    /// Only compute the features, skip the strategies and the allocation
+    #[arg(long)]
+    features_only: bool,
 }
 
-#[tokio::main(flavor = "multi_thread")]
-async fn main() {
-    init_tracing();
+#[derive(Subcommand, Debug)]
+enum TradingCommands {
+    /// Stop trading an instrument or strategy, its open orders are cancelled
+    Disable(TradingControlArgs),
 
-    // Install the default CryptoProvider
-    CryptoProvider::install_default(aws_lc_rs::default_provider()).expect("Failed to install default CryptoProvider");
+    /// Resume trading an instrument or strategy
+    Enable(TradingControlArgs),
 
-    let cli = Cli::parse();
+    /// List the disabled instruments and strategies
+    List(TradingListArgs),
+}
 
-    match cli.command {
-        Commands::Insights(args) => {
-            info!("Starting Arkin Pipeline 🚀");
-            let res = run_insights(args).await;
-            match res {
-                Ok(_) => info!("Insights completed successfully"),
-                Err(e) => error!("Insights failed: {}", e),
-            }
-        }
-        Commands::Ingestors(args) => {
-            info!("Starting Arkin Ingestors 🚀");
-            let res = run_ingestor(args).await;
-            match res {
-                Ok(_) => info!("Ingestors completed successfully"),
-                Err(e) => error!("Ingestors failed: {}", e),
-            }
-        }
-        Commands::Engine(args) => {
-            info!("Starting Arkin Trading Engine 🚀");
-            let res = run_engine(args).await;
-            match res {
-                Ok(_) => info!("Engine completed successfully"),
-                Err(e) => error!("Engine failed: {}", e),
-            }
-        }
-    }
+#[derive(Args, Debug)]
+struct TradeArgs {
+    /// Control address of the engine
+    #[arg(long, default_value = "127.0.0.1:8090")]
+    address: String,
+
+    /// Who places the orders, defaults to the user
+    #[arg(long)]
+    operator: Option<String>,
+
+    /// API token of the control server, needed once it checks tokens
+    #[arg(long, env = "ARKIN_CONTROL_TOKEN", hide_env_values = true)]
+    token: Option<String>,
 }
 
-async fn run_insights(args: InsightsArgs) -> Result<()> {
-    let mut instruments = vec![];
-    let start = args.from;
-    let end = args.till;
+#[derive(Args, Debug)]
+struct TradingControlArgs {
+    /// Control address of the engine
+    #[arg(long, default_value = "127.0.0.1:8090")]
+    address: String,
 
-    let pubsub = Arc::new(PubSub::new());
+    /// Instrument symbol (e.g., perp-btc-usdt@binance)
+    #[arg(
+        long,
+        conflicts_with = "strategy",
+        required_unless_present = "strategy",
+        add = ArgValueCandidates::new(instrument_symbol_candidates)
+    )]
+    instrument: Option<String>,
 
-    let config = load::<PersistenceConfig>();
-    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+    /// Strategy name
+    #[arg(long, add = ArgValueCandidates::new(strategy_candidates))]
+    strategy: Option<String>,
 
-    // Start the persistence service
-    let persistence_task_tracker = TaskTracker::new();
-    let persistence_shutdown = CancellationToken::new();
-    let shutdown = persistence_shutdown.clone();
-    let persistence_service = persistence.clone();
-    persistence_task_tracker.spawn(async move {
-        if let Err(e) = persistence_service.start(shutdown).await {
-            error!("Failed to start persistence service: {}", e);
-        }
-    });
+    /// Why trading gets enabled or disabled
+    #[arg(long, default_value = "")]
+    reason: String,
 
-    let config = load::<InsightsConfig>().insights_service;
-    let insights_service = Arc::new(InsightsService::from_config(&config, pubsub.clone(), persistence.clone()).await);
+    /// API token of the control server, needed once it checks tokens
+    #[arg(long, env = "ARKIN_CONTROL_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
 
-    // Fetch instruments concurrently
-    for symbol in &args.instruments {
-        match persistence.instrument_store.read_by_venue_symbol(symbol).await {
-            Ok(instr) => instruments.push(instr),
-            Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
-        }
-    }
+#[derive(Args, Debug)]
+struct TradingListArgs {
+    /// Control address of the engine
+    #[arg(long, default_value = "127.0.0.1:8090")]
+    address: String,
 
-    info!("Loaded {} instruments.", instruments.len());
+    /// API token of the control server, needed once it checks tokens
+    #[arg(long, env = "ARKIN_CONTROL_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
 
-    let mut current_day = start.date();
-    let frequency_secs = Duration::from_secs(config.frequency_secs);
+#[derive(Subcommand, Debug)]
+enum JobsCommands {
+    /// Queue a command, e.g. `jobs submit -- insights --from "2025-01-01 00:00" --till ...`
+    Submit(JobSubmitArgs),
 
-    let mut clock = Clock::new(start, end, frequency_secs);
+    /// List the jobs with their progress
+    List,
 
-    while let Some((_tick_start, tick_end)) = clock.next() {
-        if tick_end.date() != current_day {
-            current_day = tick_end.date();
+    /// Pause a job, a running job stops after its current chunk
+    Pause(JobIdArgs),
 
-            // Remove the data
-            insights_service.remove(tick_end).await?;
+    /// Queue a paused or failed job again, it continues after its last finished chunk
+    Resume(JobIdArgs),
 
-            // Load the data
-            let tomorrow = tick_end + Duration::from_secs(86400);
-            insights_service
-                .load(tomorrow, &instruments, Duration::from_secs(86400))
-                .await?;
-        }
-        insights_service.process(tick_end, &instruments, true).await?;
-    }
+    /// Cancel a job, a running job stops after its current chunk
+    Cancel(JobIdArgs),
 
-    persistence.flush().await?;
+    /// Work through the queued jobs, workers on several machines share the queue
+    Run(JobRunArgs),
 
-    persistence_shutdown.cancel();
-    persistence_task_tracker.close();
-    persistence_task_tracker.wait().await;
-    info!("Persistence service has shut down");
-    Ok(())
+    /// Merged state of the shards of a submission
+    Report(JobReportArgs),
 }
 
-async fn run_ingestor(args: IngestorsCommands) -> Result<()> {
-    info!("Args: {:?}", args);
-    let pubsub = Arc::new(PubSub::new());
+#[derive(Args, Debug)]
+struct JobSubmitArgs {
+    /// Hours of the time range per chunk, progress is saved after every chunk
+    #[arg(long, default_value_t = 24)]
+    chunk_hours: u64,
 
-    let config = load::<PersistenceConfig>();
-    let persistence_service = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+    /// Split the instruments of the command over this many jobs, so several workers can run them
+    #[arg(long, default_value_t = 1)]
+    shards: usize,
 
-    let config = load::<IngestorsConfig>();
-    let ingestors = IngestorFactory::from_config(&config, pubsub.clone(), persistence_service.clone());
+    /// Command to run, an insights backfill or a tardis download
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
 
-    // Start the persistence service
-    let persistence_task_tracker = TaskTracker::new();
-    let persistence_shutdown = CancellationToken::new();
-    let shutdown = persistence_shutdown.clone();
-    persistence_task_tracker.spawn(async move {
-        if let Err(e) = persistence_service.start(shutdown).await {
-            error!("Failed to start persistence service: {}", e);
-        }
-    });
-    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+#[derive(Args, Debug)]
+struct JobIdArgs {
+    /// Job id
+    id: Uuid,
+}
 
-    // Start the ingestors
-    let ingestor_task_tracker = TaskTracker::new();
-    let ingestor_shutdown = CancellationToken::new();
-    for ingestor in ingestors {
-        let shutdown = ingestor_shutdown.clone();
-        ingestor_task_tracker.spawn(async move {
-            if let Err(e) = ingestor.start(shutdown).await {
-                error!("Failed to start ingestor: {}", e);
-            }
-        });
-    }
+#[derive(Args, Debug)]
+struct JobRunArgs {
+    /// Name of this worker in the jobs, defaults to the hostname
+    #[arg(long)]
+    worker: Option<String>,
 
-    match tokio::signal::ctrl_c().await {
-        Ok(_) => {
-            info!("Received Ctrl-C signal, shutting down...");
-        }
-        Err(e) => error!("Failed to listen for Ctrl-C signal: {}", e),
-    }
+    /// Exit when the queue is empty instead of waiting for new jobs
+    #[arg(long)]
+    once: bool,
 
-    ingestor_shutdown.cancel();
-    ingestor_task_tracker.close();
-    ingestor_task_tracker.wait().await;
-    info!("All ingestors have shut down");
+    /// Seconds between checks for new jobs
+    #[arg(long, default_value_t = 10)]
+    poll_secs: u64,
 
-    persistence_shutdown.cancel();
-    persistence_task_tracker.close();
-    persistence_task_tracker.wait().await;
-    info!("Persistence service has shut down");
-    Ok(())
+    /// Running jobs without a heartbeat for this many seconds lost their worker and are queued again
+    #[arg(long, default_value_t = 300)]
+    stale_secs: u64,
 }
 
-async fn run_engine(args: EngineArgs) -> Result<()> {
-    let pubsub = Arc::new(PubSub::new());
-    info!("PubSub created");
+#[derive(Args, Debug)]
+struct JobReportArgs {
+    /// Group id printed by `jobs submit --shards`
+    group: Uuid,
 
-    let config = load::<PersistenceConfig>();
-    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
-    info!("Persistence created");
+    /// Wait until no shard is queued or running
+    #[arg(long)]
+    wait: bool,
 
-    let config = load::<PortfolioConfig>();
-    let portfolio = PortfolioFactory::from_config(&config, pubsub.clone());
-    info!("Portfolio created");
+    /// Seconds between checks while waiting
+    #[arg(long, default_value_t = 30)]
+    poll_secs: u64,
+}
 
-    let config = load::<IngestorsConfig>();
-    let ingestors = IngestorFactory::from_config(&config, pubsub.clone(), persistence.clone());
-    info!("Ingestors created");
+#[derive(Subcommand, Debug)]
+enum AnnotationsCommands {
+    /// Annotate an order, a trade or a period, e.g. `annotations add --tag exchange_outage --from ... --till ...`
+    Add(AnnotationAddArgs),
 
-    let config = load::<InsightsConfig>();
-    let insights =
-        Arc::new(InsightsService::from_config(&config.insights_service, pubsub.clone(), persistence.clone()).await);
-    info!("Insights created");
+    /// List the annotations covering part of a period
+    List(AnnotationListArgs),
 
-    let config = load::<AllocationOptimConfig>();
-    let allocation = AllocationFactory::from_config(&config, pubsub.clone(), persistence.clone(), portfolio.clone());
-    info!("Allocation created");
+    /// Remove an annotation
+    Delete(AnnotationIdArgs),
+}
 
-    let config = load::<OrderManagerConfig>();
-    let order_manager = ExecutionFactory::from_config(&config, pubsub.clone());
-    info!("Order Manager created");
+#[derive(Args, Debug)]
+struct AnnotationAddArgs {
+    /// Short label, like manual_intervention or exchange_outage
+    #[arg(long)]
+    tag: String,
 
-    let config = load::<ExecutorConfig>();
-    let executor = ExecutorFactory::from_config(&config, pubsub.clone(), persistence.clone());
-    info!("Executor created");
+    /// Free text note
+    #[arg(long, default_value = "")]
+    note: String,
 
-    // Work around for fetching instruments
-    let mut instruments = vec![];
-    for symbol in &args.instruments {
-        match persistence.instrument_store.read_by_venue_symbol(symbol).await {
-            Ok(instr) => instruments.push(instr),
-            Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
-        }
-    }
-    info!("Loaded {} instruments.", instruments.len());
+    /// Id of the execution or venue order
+    #[arg(long, conflicts_with = "trade")]
+    order: Option<Uuid>,
 
-    let engine = ForecastEngine::builder()
-        .pubsub(pubsub)
-        .instruments(instruments)
-        .persistor(persistence)
-        .portfolio(portfolio)
-        .ingestors(ingestors)
-        .insights(insights)
-        .allocation_optim(allocation)
-        .order_manager(order_manager)
-        .executor(executor)
-        .build();
+    /// Id of the fill
+    #[arg(long)]
+    trade: Option<Uuid>,
+
+    /// Start of the period in "YYYY-MM-DD HH:MM" format, the time of the order or trade, defaults to now
+    #[arg(long, short, value_parser = parse_datetime)]
+    from: Option<OffsetDateTime>,
+
+    /// End of the period in "YYYY-MM-DD HH:MM" format, defaults to the start
+    #[arg(long, short, value_parser = parse_datetime)]
+    till: Option<OffsetDateTime>,
+
+    /// Limit the period to one instrument
+    #[arg(long, add = ArgValueCandidates::new(instrument_candidates))]
+    instrument: Option<String>,
+
+    /// Leave the period out of performance analysis instead of only flagging it
+    #[arg(long)]
+    exclude: bool,
+
+    /// Who made the annotation, defaults to the user
+    #[arg(long)]
+    author: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct AnnotationListArgs {
+    /// Start date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    from: OffsetDateTime,
+
+    /// End date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    till: OffsetDateTime,
+}
+
+#[derive(Args, Debug)]
+struct AnnotationIdArgs {
+    /// Annotation id
+    id: Uuid,
+}
+
+#[derive(Args, Debug)]
+struct ValidateFeaturesArgs {
+    /// Directory with the reference fixtures, overrides the configured one
+    #[arg(long)]
+    fixtures_dir: Option<PathBuf>,
+
+    /// Only run these cases (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    cases: Vec<String>,
+
+    /// Fail if a feature from the factory has no validation case
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Args, Debug)]
+struct EvaluatePredictionsArgs {
+    /// Feature holding the predictions (e.g., catboost_prediction)
+    #[arg(long)]
+    prediction: String,
+
+    /// Start date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    from: OffsetDateTime,
+
+    /// End date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    till: OffsetDateTime,
+
+    /// Instruments (comma-separated)
+    #[arg(long, short, value_delimiter = ',', add = ArgValueCandidates::new(instrument_candidates))]
+    instruments: Vec<String>,
+
+    /// Pipeline the predictions were persisted under, defaults to the configured one
+    #[arg(long, add = ArgValueCandidates::new(pipeline_candidates))]
+    pipeline: Option<String>,
+
+    /// Horizon of the realized return after each prediction
+    #[arg(long, default_value_t = 60)]
+    horizon_secs: u64,
+
+    /// Predictions above are calls for the price to go up (0.5 for probabilities, 0 for signed scores)
+    #[arg(long, default_value_t = 0.)]
+    threshold: f64,
+
+    /// Round trip cost of the naive trading rule in basis points
+    #[arg(long, default_value_t = 0.)]
+    cost_bps: f64,
+
+    /// Number of calibration buckets
+    #[arg(long, default_value_t = 10)]
+    buckets: usize,
+}
+
+#[derive(Args, Debug)]
+struct ComparePredictionsArgs {
+    /// Model name
+    #[arg(long)]
+    model: String,
+
+    /// Version the others are compared to
+    #[arg(long)]
+    baseline: String,
+
+    /// Versions to compare to the baseline (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    candidates: Vec<String>,
+
+    /// Start date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    from: OffsetDateTime,
+
+    /// End date in "YYYY-MM-DD HH:MM" format
+    #[arg(long, short, value_parser = parse_datetime)]
+    till: OffsetDateTime,
+}
+
+#[derive(Args, Debug)]
+struct DumpConfigArgs {
+    /// Print passwords, secrets and keys instead of masking them
+    #[arg(long)]
+    show_secrets: bool,
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Services to run. The settings come from the `serve` section of the config, so a container only needs
+    /// `CONFIG_FILE` and `ARKIN_*` variables, e.g. `ARKIN_SERVE__INSTRUMENTS=BTCUSDT,ETHUSDT`
+    #[arg(long, value_enum, env = "ARKIN_ROLE")]
+    role: ServiceRole,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ServiceRole {
+    /// Ingestors and persistence
+    DataProvider,
+    /// Order manager, executor and portfolio
+    Execution,
+    /// Insights on the trades the data provider persisted
+    Insights,
+    /// The whole trading engine in one process
+    Agent,
+}
+
+/// Settings of `serve` that the other commands take as arguments.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ServeConfig {
+    #[serde(default)]
+    serve: ServeSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct ServeSettings {
+    /// Venue symbols, a list or comma-separated
+    #[serde(deserialize_with = "comma_separated")]
+    instruments: Vec<String>,
+    soft_deadline_ms: u64,
+    hard_deadline_ms: u64,
+    halt_on_stall: bool,
+    control_address: Option<String>,
+    /// Name of the portfolio the trading controls book under
+    portfolio: Option<String>,
+}
+
+impl Default for ServeSettings {
+    fn default() -> Self {
+        Self {
+            instruments: vec![],
+            soft_deadline_ms: 500,
+            hard_deadline_ms: 10000,
+            halt_on_stall: false,
+            control_address: None,
+            portfolio: None,
+        }
+    }
+}
+
+/// Lists can't be written as environment variables, so they are accepted comma-separated as well.
+fn comma_separated<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Values {
+        List(Vec<String>),
+        Text(String),
+    }
+    Ok(match Values::deserialize(deserializer)? {
+        Values::List(values) => values,
+        Values::Text(text) => text.split(',').map(|v| v.trim().to_owned()).filter(|v| !v.is_empty()).collect(),
+    })
+}
+
+/// Custom parser to convert string to OffsetDateTime
+fn parse_datetime(s: &str) -> Result<OffsetDateTime, String> {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+    let ts = PrimitiveDateTime::parse(&s, &format)
+        .map_err(|e| format!("Failed to parse datetime '{}': {}", s, e))?
+        .assume_utc();
+    Ok(ts)
+}
+
+/// Parse a duration like `30s`, `5m`, `1h` or `1d`, plain numbers are seconds.
+fn parse_window(s: &str) -> Result<Duration, String> {
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value = value
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse duration '{}': {}", s, e))?;
+    let secs = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        "d" => value * 86400,
+        _ => return Err(format!("Unknown unit of duration '{}', use s, m, h or d", s)),
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    // Answers completion requests of the shell, e.g. after `source <(COMPLETE=bash arkin)`
+    CompleteEnv::with_factory(Cli::command).complete();
+
+    let cli = Cli::parse();
+    let output = cli.output;
+    match output {
+        OutputFormat::Table => init_tracing(),
+        OutputFormat::Json => init_tracing_stderr(),
+    }
+
+    // Install the default CryptoProvider
+    CryptoProvider::install_default(aws_lc_rs::default_provider()).expect("Failed to install default CryptoProvider");
+
+    match cli.command {
+        Commands::Insights(args) if args.explain => {
+            if let Err(e) = run_explain(args, output) {
+                error!("Explain failed: {}", e);
+                print_status(output, "explain", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Insights(args) => {
+            info!("Starting Arkin Pipeline 🚀");
+            let res = run_insights(args).await;
+            print_status(output, "insights", res.as_ref().err());
+            match res {
+                Ok(_) => info!("Insights completed successfully"),
+                Err(e) => error!("Insights failed: {}", e),
+            }
+        }
+        Commands::Ingestors(args) => {
+            info!("Starting Arkin Ingestors 🚀");
+            let res = run_ingestor(args).await;
+            print_status(output, "ingestors", res.as_ref().err());
+            match res {
+                Ok(_) => info!("Ingestors completed successfully"),
+                Err(e) => error!("Ingestors failed: {}", e),
+            }
+        }
+        Commands::Engine(args) => {
+            info!("Starting Arkin Trading Engine 🚀");
+            let res = run_engine(args).await;
+            print_status(output, "engine", res.as_ref().err());
+            match res {
+                Ok(_) => info!("Engine completed successfully"),
+                Err(e) => error!("Engine failed: {}", e),
+            }
+        }
+        Commands::Trading(args) => {
+            let res = run_trading(args, output).await;
+            match res {
+                Ok(_) => info!("Trading control completed successfully"),
+                Err(e) => {
+                    error!("Trading control failed: {}", e);
+                    print_status(output, "trading", Some(&e));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::ValidateFeatures(args) => {
+            info!("Starting Arkin Feature Validation 🚀");
+            let res = run_validate_features(args, output).await;
+            match res {
+                Ok(_) => info!("Feature validation completed successfully"),
+                Err(e) => {
+                    error!("Feature validation failed: {}", e);
+                    print_status(output, "validate-features", Some(&e));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::DebugSlice(args) => {
+            let res = run_debug_slice(args, output).await;
+            match res {
+                Ok(_) => info!("Debug slice completed successfully"),
+                Err(e) => {
+                    error!("Debug slice failed: {}", e);
+                    print_status(output, "debug-slice", Some(&e));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::DumpConfig(args) => {
+            if let Err(e) = run_dump_config(args, output) {
+                error!("Failed to resolve config: {}", e);
+                print_status(output, "dump-config", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::ComparePredictions(args) => {
+            if let Err(e) = run_compare_predictions(args, output).await {
+                error!("Prediction comparison failed: {}", e);
+                print_status(output, "compare-predictions", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Jobs(command) => {
+            if let Err(e) = run_jobs(command, output).await {
+                error!("Jobs failed: {}", e);
+                print_status(output, "jobs", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Serve(args) => {
+            info!("Starting Arkin {:?} 🚀", args.role);
+            let res = run_serve(args).await;
+            print_status(output, "serve", res.as_ref().err());
+            match res {
+                Ok(_) => info!("Serve completed successfully"),
+                Err(e) => error!("Serve failed: {}", e),
+            }
+        }
+        Commands::Trade(args) => {
+            if let Err(e) = run_trade(args, output).await {
+                error!("Trade console failed: {}", e);
+                print_status(output, "trade", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::Annotations(command) => {
+            if let Err(e) = run_annotations(command, output).await {
+                error!("Annotations failed: {}", e);
+                print_status(output, "annotations", Some(&e));
+                std::process::exit(1);
+            }
+        }
+        Commands::EvaluatePredictions(args) => {
+            if let Err(e) = run_evaluate_predictions(args, output).await {
+                error!("Prediction evaluation failed: {}", e);
+                print_status(output, "evaluate-predictions", Some(&e));
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Candidates for shell completion read from persistence. The shell calls this on every tab, so failures give no
+/// candidates instead of logs.
+fn complete_from_persistence<F, Fut>(read: F) -> Vec<CompletionCandidate>
+where
+    F: FnOnce(PersistenceService) -> Fut,
+    Fut: Future<Output = Result<Vec<CompletionCandidate>, PersistenceError>>,
+{
+    tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(async {
+            let config = load::<PersistenceConfig>();
+            let persistence = PersistenceService::from_config(&config, Arc::new(PubSub::new())).await;
+            read(persistence).await.unwrap_or_default()
+        })
+    })
+}
+
+fn instrument_candidates() -> Vec<CompletionCandidate> {
+    complete_from_persistence(|persistence| async move {
+        let symbols = persistence.instrument_store.read_symbols().await?;
+        Ok(symbols
+            .into_iter()
+            .map(|s| CompletionCandidate::new(s.venue_symbol).help(Some(s.symbol.into())))
+            .collect())
+    })
+}
+
+fn instrument_symbol_candidates() -> Vec<CompletionCandidate> {
+    complete_from_persistence(|persistence| async move {
+        let symbols = persistence.instrument_store.read_symbols().await?;
+        Ok(symbols.into_iter().map(|s| CompletionCandidate::new(s.symbol)).collect())
+    })
+}
+
+fn pipeline_candidates() -> Vec<CompletionCandidate> {
+    complete_from_persistence(|persistence| async move {
+        let names = persistence.pipeline_store.read_names().await?;
+        Ok(names.into_iter().map(CompletionCandidate::new).collect())
+    })
+}
+
+fn strategy_candidates() -> Vec<CompletionCandidate> {
+    complete_from_persistence(|persistence| async move {
+        let names = persistence.strategy_store.read_names().await?;
+        Ok(names.into_iter().map(CompletionCandidate::new).collect())
+    })
+}
+
+/// Pick instruments with a fuzzy search, one after the other until escape is pressed.
+async fn select_instruments(persistence: &PersistenceService) -> Result<Vec<String>> {
+    let mut symbols = persistence
+        .instrument_store
+        .read_symbols()
+        .await?
+        .into_iter()
+        .map(|s| s.venue_symbol)
+        .collect::<Vec<_>>();
+    let mut selected = vec![];
+    while !symbols.is_empty() {
+        let prompt = format!("Instrument ({} selected, esc to finish)", selected.len());
+        let choice =
+            tokio::task::block_in_place(|| FuzzySelect::new().with_prompt(prompt).items(&symbols).interact_opt())?;
+        match choice {
+            Some(i) => selected.push(symbols.remove(i)),
+            None => break,
+        }
+    }
+    Ok(selected)
+}
+
+/// Print a report as json or in its human readable form.
+fn print_report<T: serde::Serialize + std::fmt::Display>(output: OutputFormat, report: &T) -> Result<()> {
+    match output {
+        OutputFormat::Table => println!("{}", report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+    }
+    Ok(())
+}
+
+/// Outcome of a command for scripts in json mode. Commands with a report only print this when they fail, the table
+/// output relies on the logs instead.
+fn print_status(output: OutputFormat, command: &str, error: Option<&anyhow::Error>) {
+    if output != OutputFormat::Json {
+        return;
+    }
+    let status = match error {
+        None => json!({ "command": command, "status": "completed" }),
+        Some(e) => json!({ "command": command, "status": "failed", "error": e.to_string() }),
+    };
+    println!("{}", status);
+}
+
+fn run_explain(args: InsightsArgs, output: OutputFormat) -> Result<()> {
+    let config = load::<InsightsConfig>().insights_service;
+    let explain = PipelineExplain::from_config(&config, args.instruments.len().max(1))?;
+    match output {
+        OutputFormat::Table => print!("{}", explain),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&explain)?),
+    }
+    Ok(())
+}
+
+fn run_dump_config(args: DumpConfigArgs, output: OutputFormat) -> Result<()> {
+    let profile = ConfigProfile::from_env();
+    let config = profile.resolved(args.show_secrets)?;
+    if output == OutputFormat::Json {
+        let dump = json!({ "profile": profile, "layers": profile.layers(), "config": config });
+        println!("{}", serde_json::to_string_pretty(&dump)?);
+        return Ok(());
+    }
+
+    println!(
+        "# profile={} instance={} dir={} file={}",
+        profile.profile,
+        profile.instance.as_deref().unwrap_or("-"),
+        profile.dir,
+        profile.file.as_deref().unwrap_or("-")
+    );
+    for layer in profile.layers() {
+        println!("# {} {}", if layer.found { "+" } else { "-" }, layer.path);
+    }
+    println!("{}", serde_json::to_string_pretty(&config)?);
+    Ok(())
+}
+
+async fn run_insights(args: InsightsArgs) -> Result<()> {
+    let mut instruments = vec![];
+    let (Some(start), Some(end)) = (args.from, args.till) else {
+        anyhow::bail!("--from and --till are required");
+    };
+
+    let pubsub = Arc::new(PubSub::new());
+
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+
+    // Start the persistence service
+    let persistence_task_tracker = TaskTracker::new();
+    let persistence_shutdown = CancellationToken::new();
+    let shutdown = persistence_shutdown.clone();
+    let persistence_service = persistence.clone();
+    persistence_task_tracker.spawn(async move {
+        if let Err(e) = persistence_service.start(shutdown).await {
+            error!("Failed to start persistence service: {}", e);
+        }
+    });
+    if let Some(c) = load::<MetricsExporterConfig>().metrics_exporter {
+        let exporter = Arc::new(MetricsExporter::from_config(&c));
+        let shutdown = persistence_shutdown.clone();
+        persistence_task_tracker.spawn(async move {
+            if let Err(e) = exporter.serve(shutdown).await {
+                error!("Failed to serve metrics: {}", e);
+            }
+        });
+    }
+
+    let config = load::<InsightsConfig>().insights_service;
+    let insights_service = Arc::new(InsightsService::from_config(&config, pubsub.clone(), persistence.clone()).await);
+
+    // Fetch instruments concurrently
+    let symbols = match args.select {
+        true => select_instruments(&persistence).await?,
+        false => args.instruments.clone(),
+    };
+    for symbol in &symbols {
+        match persistence.symbol_registry.instrument("binance", symbol).await {
+            Ok(instr) => instruments.push(instr),
+            Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
+        }
+    }
+
+    info!("Loaded {} instruments.", instruments.len());
+
+    if args.vectorized {
+        run_vectorized_backfill(&args, &config, &persistence, &instruments, start, end).await?;
+        persistence_shutdown.cancel();
+        persistence_task_tracker.close();
+        persistence_task_tracker.wait().await;
+        info!("Persistence service has shut down");
+        return Ok(());
+    }
+
+    let checkpoint_run = args.resume_from.clone().or(args.checkpoint_run.clone());
+    let mut clock = match &args.resume_from {
+        Some(run) => match persistence.checkpoint_store.read_latest(run).await? {
+            Some(checkpoint) => {
+                insights_service.restore(&checkpoint.features, &instruments);
+                info!("Resuming from checkpoint at {}", checkpoint.clock.current_timestamp);
+                Clock::from_state(&checkpoint.clock)
+            }
+            None => anyhow::bail!("No checkpoint of run {} to resume from", run),
+        },
+        None => Clock::new(start, end, Duration::from_secs(config.frequency_secs)),
+    };
+    let mut current_day = clock.current().date();
+    let mut ticks = 0u64;
+    let checkpoint_every = args.checkpoint_every.as_nanos().max(1) as i128;
+
+    let progress_done = CancellationToken::new();
+    let progress_task = render_progress(&pubsub, progress_done.clone());
+    let mut progress = ProgressTracker::new(clock.start(), clock.end());
+
+    while let Some((_tick_start, tick_end)) = clock.next() {
+        if tick_end.date() != current_day {
+            current_day = tick_end.date();
+
+            // Remove the data
+            insights_service.remove(tick_end).await?;
+
+            // Load the data
+            let tomorrow = tick_end + Duration::from_secs(86400);
+            insights_service
+                .load(tomorrow, &instruments, Duration::from_secs(86400))
+                .await?;
+        }
+        let insights = insights_service.process(tick_end, &instruments, true).await?;
+        progress.add_events(insights.len() as u64);
+
+        ticks += 1;
+        if ticks % args.progress_interval.max(1) == 0 {
+            pubsub.publish::<SimulationProgress>(Arc::new(progress.update(tick_end)));
+        }
+        if let Some(run) = &checkpoint_run {
+            if tick_end.unix_timestamp_nanos() % checkpoint_every == 0 {
+                // Make sure everything up to the checkpoint is persisted before we write it
+                persistence.flush().await?;
+                let checkpoint = SimulationCheckpoint::builder()
+                    .clock(clock.state())
+                    .features(insights_service.checkpoint())
+                    .build();
+                persistence.checkpoint_store.insert(run, &checkpoint).await?;
+                info!("Checkpointed run {} at {}", run, tick_end);
+            }
+        }
+    }
+
+    pubsub.publish::<SimulationProgress>(Arc::new(progress.finish()));
+    persistence.flush().await?;
+    progress_done.cancel();
+    progress_task.await?;
+    if let Some(report) = insights_service.profile_report() {
+        info!("{}", report);
+    }
+
+    persistence_shutdown.cancel();
+    persistence_task_tracker.close();
+    persistence_task_tracker.wait().await;
+    info!("Persistence service has shut down");
+    Ok(())
+}
+
+/// Compute the pipeline and the labels over the whole range at once and persist the outputs, optionally checked
+/// against the streaming engine.
+/// Log the progress updates of a simulation as a progress bar, the updates published before `done` are all logged.
+fn render_progress(pubsub: &PubSub, done: CancellationToken) -> tokio::task::JoinHandle<()> {
+    let mut progress_updates = pubsub.subscribe::<SimulationProgress>();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                Ok(progress) = progress_updates.recv() => info!("{}", render_progress_bar(&progress)),
+                _ = done.cancelled() => {
+                    while let Ok(progress) = progress_updates.try_recv() {
+                        info!("{}", render_progress_bar(&progress));
+                    }
+                    break;
+                }
+            }
+        }
+    })
+}
+
+async fn run_vectorized_backfill(
+    args: &InsightsArgs,
+    config: &InsightsServiceConfig,
+    persistence: &Arc<PersistenceService>,
+    instruments: &[Arc<Instrument>],
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<()> {
+    let engine = if args.polars {
+        VectorizedEngine::Polars
+    } else {
+        VectorizedEngine::Columns
+    };
+    let backfill = VectorizedBackfill::from_config(config)?.with_engine(engine);
+    let labeler = config.labels.as_ref().map(Labeler::from_config);
+    let pipeline = persistence.pipeline_store.read_by_name(&config.pipeline.name).await?;
+
+    // Labels look ahead of the last tick, the features never see the extra trades
+    let horizon = Duration::from_secs(labeler.as_ref().map(|l| l.max_horizon()).unwrap_or_default());
+    let trades = persistence.trade_store.read_range(instruments, start, end + horizon).await?;
+    let inputs = trades
+        .iter()
+        .flat_map(|t| t.as_ref().clone().to_insights(pipeline.clone()))
+        .collect::<Vec<_>>();
+    info!("Loaded {} trades", trades.len());
+
+    let ticks = backfill_ticks(start, end, Duration::from_secs(config.frequency_secs));
+    let timestamps = ticks.iter().map(|t| t.unix_timestamp()).collect::<Vec<_>>();
+    let input_frames = frames_from_insights(&inputs);
+    let mut frames = backfill.run_all(&input_frames, &timestamps)?;
+
+    let mut persisted = config
+        .pipeline
+        .features
+        .iter()
+        .filter(|f| f.persist())
+        .flat_map(|f| f.outputs())
+        .collect::<HashSet<_>>();
+    if let Some(labeler) = &labeler {
+        // The labels read the raw price and the features, so they get both in one frame
+        for (instrument, frame) in frames.iter_mut() {
+            if let Some(inputs) = input_frames.get(instrument) {
+                frame.extend(inputs.iter().map(|(id, series)| (id.clone(), series.clone())));
+            }
+        }
+        let data_end = (end + horizon).unix_timestamp();
+        for (instrument, labels) in labeler.run_all(&frames, &timestamps, data_end) {
+            frames.entry(instrument).or_default().extend(labels);
+        }
+        persisted.extend(labeler.outputs());
+    }
+
+    let insights = insights_from_frames(&frames, pipeline.clone(), &persisted)?;
+    info!("Computed {} insights over {} ticks", insights.len(), ticks.len());
+    persistence.insights_store.insert_buffered_vec(insights).await?;
+    persistence.flush().await?;
+
+    if let Some(tolerance) = args.cross_check {
+        let mismatches = cross_check(config, engine, pipeline, &inputs, &ticks, tolerance)?;
+        for mismatch in mismatches.iter().take(20) {
+            error!("Cross-check mismatch: {}", mismatch);
+        }
+        if !mismatches.is_empty() {
+            anyhow::bail!("vectorized and streaming engine differ in {} values", mismatches.len());
+        }
+        info!("Cross-check passed, vectorized and streaming engine agree");
+    }
+    Ok(())
+}
+
+async fn run_ingestor(args: IngestorsCommands) -> Result<()> {
+    info!("Args: {:?}", args);
+    if let IngestorsCommands::Replay(args) = args {
+        return run_replay(args).await;
+    }
+    run_data_provider().await
+}
+
+/// Run the configured ingestors until Ctrl-C.
+async fn run_data_provider() -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+
+    let config = load::<PersistenceConfig>();
+    let persistence_service = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+
+    let config = load::<VenuesConfig>();
+    let adapters = VenueAdapters::default();
+    config.venues.register(&adapters);
+
+    let config = load::<IngestorsConfig>();
+    let ingestors =
+        IngestorFactory::from_config(&config, pubsub.clone(), persistence_service.clone(), &adapters, None, None);
+
+    let config = load::<EventBridgeConfig>();
+    let event_bridge = config
+        .event_bridge
+        .map(|c| EventBridge::from_config(&c, pubsub.clone(), persistence_service.clone()));
+
+    // Start the persistence service
+    let persistence_task_tracker = TaskTracker::new();
+    let persistence_shutdown = CancellationToken::new();
+    let shutdown = persistence_shutdown.clone();
+    persistence_task_tracker.spawn(async move {
+        if let Err(e) = persistence_service.start(shutdown).await {
+            error!("Failed to start persistence service: {}", e);
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    // Start the event bridge, so other instances can follow the market data
+    if let Some(bridge) = event_bridge {
+        let shutdown = persistence_shutdown.clone();
+        persistence_task_tracker.spawn(async move {
+            if let Err(e) = bridge.start(shutdown).await {
+                error!("Failed to start event bridge: {}", e);
+            }
+        });
+    }
+
+    // Start the ingestors
+    let ingestor_task_tracker = TaskTracker::new();
+    let ingestor_shutdown = CancellationToken::new();
+    for ingestor in ingestors {
+        let shutdown = ingestor_shutdown.clone();
+        ingestor_task_tracker.spawn(async move {
+            if let Err(e) = ingestor.start(shutdown).await {
+                error!("Failed to start ingestor: {}", e);
+            }
+        });
+    }
+
+    match tokio::signal::ctrl_c().await {
+        Ok(_) => {
+            info!("Received Ctrl-C signal, shutting down...");
+        }
+        Err(e) => error!("Failed to listen for Ctrl-C signal: {}", e),
+    }
+
+    ingestor_shutdown.cancel();
+    ingestor_task_tracker.close();
+    ingestor_task_tracker.wait().await;
+    info!("All ingestors have shut down");
+
+    persistence_shutdown.cancel();
+    persistence_task_tracker.close();
+    persistence_task_tracker.wait().await;
+    info!("Persistence service has shut down");
+    Ok(())
+}
+
+async fn run_replay(args: ReplayArgs) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+
+    // Only persist when asked, replays are mostly used to reproduce parser issues
+    let persistence_task_tracker = TaskTracker::new();
+    let persistence_shutdown = CancellationToken::new();
+    if args.persist {
+        let shutdown = persistence_shutdown.clone();
+        let persistence_service = persistence.clone();
+        persistence_task_tracker.spawn(async move {
+            if let Err(e) = persistence_service.start(shutdown).await {
+                error!("Failed to start persistence service: {}", e);
+            }
+        });
+    }
+
+    let archives = list_archives(&args.dir, BinanceIngestor::SOURCE)?;
+    info!("Found {} archives in {}", archives.len(), args.dir.display());
+    let mut replayed = 0;
+    for archive in archives {
+        replayed +=
+            BinanceIngestor::replay(pubsub.clone(), persistence.clone(), &archive, args.from, args.till).await?;
+    }
+    info!("Replayed {} raw messages", replayed);
+
+    if args.persist {
+        persistence.flush().await?;
+    }
+    persistence_shutdown.cancel();
+    persistence_task_tracker.close();
+    persistence_task_tracker.wait().await;
+    Ok(())
+}
+
+async fn run_engine(args: EngineArgs) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+    info!("PubSub created");
+
+    let watchdog = Arc::new(
+        Watchdog::builder()
+            .soft_deadline(Duration::from_millis(args.soft_deadline_ms))
+            .hard_deadline(Duration::from_millis(args.hard_deadline_ms))
+            .build(),
+    );
+
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+    info!("Persistence created");
+
+    let checkpoint_run = args.resume_from.clone().or(args.checkpoint_run.clone());
+    if checkpoint_run.is_some() && args.checkpoint_every.as_secs() % 60 != 0 {
+        anyhow::bail!("--checkpoint-every has to be whole minutes, the replay resumes at a minute");
+    }
+    let checkpoint = match &args.resume_from {
+        Some(run) => match persistence.checkpoint_store.read_latest(run).await? {
+            Some(checkpoint) => Some(checkpoint),
+            None => anyhow::bail!("No checkpoint of run {} to resume from", run),
+        },
+        None => None,
+    };
+
+    let config = load::<PortfolioConfig>();
+    let portfolio = PortfolioFactory::from_config(&config, pubsub.clone(), watchdog.clone());
+    info!("Portfolio created");
+
+    let config = load::<PerformanceFeesConfig>();
+    let daily_performance = Arc::new(DailyPerformanceTracker::from_config(
+        config.performance_fees.as_ref(),
+        pubsub.clone(),
+    ));
+
+    let config = load::<SubAccountsConfig>();
+    let sub_accounts = config
+        .sub_accounts
+        .map(|c| Arc::new(SubAccountLedger::from_config(&c, pubsub.clone())));
+
+    let config = load::<VenuesConfig>();
+    let adapters = VenueAdapters::default();
+    config.venues.register(&adapters);
+
+    let debugger = args.debug.then(|| Arc::new(SimDebugger::new(args.breakpoints.clone())));
+
+    let mut config = load::<IngestorsConfig>();
+    // The replay of a resumed simulation continues where the checkpoint left off
+    if let Some(checkpoint) = &checkpoint {
+        config.resume_at(checkpoint.clock.current_timestamp);
+    }
+    if let Some(window) = args.replay_window {
+        config.replay_window(window.as_secs().max(1));
+    }
+    if let Some(pacing) = args.pacing {
+        config.pacing(pacing);
+    }
+    let replay = config.ingestors.iter().find_map(|c| match c {
+        IngestorConfig::Sim(c) => Some((parse_datetime(&c.start), parse_datetime(&c.end))),
+        _ => None,
+    });
+    // The replay of a checkpointed simulation is held at every checkpoint while it is written
+    let barrier = match (&checkpoint_run, &replay) {
+        (Some(_), Some((start, _))) => {
+            let start = start.clone().map_err(anyhow::Error::msg)?;
+            Some(Arc::new(ReplayBarrier::new(start, args.checkpoint_every)))
+        }
+        _ => None,
+    };
+    let ingestors = IngestorFactory::from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        &adapters,
+        debugger.clone(),
+        barrier.clone(),
+    );
+    info!("Ingestors created");
+
+    let config = load::<InsightsConfig>();
+    let frequency = Duration::from_secs(config.insights_service.frequency_secs);
+    let insights = Arc::new(
+        InsightsService::from_config(&config.insights_service, pubsub.clone(), persistence.clone())
+            .await
+            .with_watchdog(watchdog.clone()),
+    );
+    info!("Insights created");
+
+    let config = load::<AllocationOptimConfig>();
+    let allocation = AllocationFactory::from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        portfolio.clone(),
+        watchdog.clone(),
+    );
+    let overlay = AllocationFactory::overlay_from_config(
+        &config,
+        pubsub.clone(),
+        persistence.clone(),
+        portfolio.clone(),
+        watchdog.clone(),
+    );
+    info!("Allocation created");
+
+    let config = load::<CovarianceConfig>();
+    let covariance = config
+        .covariance
+        .map(|c| Arc::new(CovarianceService::from_config(&c, pubsub.clone())));
+
+    let config = load::<OrderManagerConfig>();
+    let switch = Arc::new(TradingSwitch::default());
+    let order_manager = ExecutionFactory::from_config(&config, pubsub.clone(), watchdog.clone(), switch.clone());
+    info!("Order Manager created");
+
+    let config = load::<LeaderElectionConfig>();
+    let leadership = Arc::new(match config.leader_election {
+        Some(_) => Leadership::standby(),
+        None => Leadership::default(),
+    });
+    let leader_election = config
+        .leader_election
+        .map(|c| Arc::new(LeaderElection::from_config(&c, persistence.clone(), leadership.clone())));
+
+    let config = load::<EventBridgeConfig>();
+    let event_bridge = config
+        .event_bridge
+        .map(|c| EventBridge::from_config(&c, pubsub.clone(), persistence.clone()));
+
+    let config = load::<ExecutorConfig>();
+    // Latencies of a simulation are modeled by the executor, live they are measured
+    let simulation = matches!(
+        config.executor,
+        ExecutorTypeConfig::Simulation(_) | ExecutorTypeConfig::MultiVenueSimulation(_)
+    );
+    let executor = ExecutorFactory::from_config(&config, pubsub.clone(), persistence.clone(), &adapters, leadership);
+    info!("Executor created");
+
+    let config = load::<TransferConfig>();
+    let transfers = config.transfers.map(|c| {
+        Arc::new(TransferManager::from_config(
+            &c,
+            pubsub.clone(),
+            executor.clone(),
+            persistence.clone(),
+        ))
+    });
+
+    // Work around for fetching instruments
+    let symbols = match args.select {
+        true => select_instruments(&persistence).await?,
+        false => args.instruments.clone(),
+    };
+    let mut instruments = vec![];
+    for symbol in &symbols {
+        // Canonical symbols with a venue (e.g. perp-btc-usdt@okx) trade there, for multi-venue simulations
+        let (symbol, venue) = symbol.rsplit_once('@').unwrap_or((symbol.as_str(), "binance"));
+        match persistence.symbol_registry.instrument(venue, symbol).await {
+            Ok(instr) => instruments.push(instr),
+            Err(e) => error!("Failed to read instrument {} on {}: {}", symbol, venue, e),
+        }
+    }
+    info!("Loaded {} instruments.", instruments.len());
+
+    if let Some(checkpoint) = &checkpoint {
+        if !simulation {
+            anyhow::bail!("Only simulations can resume from a checkpoint");
+        }
+        // The positions, balances and orders are booked under the portfolio the run was checkpointed with
+        let Some(state) = &checkpoint.portfolio else {
+            anyhow::bail!("The checkpoint holds no portfolio to resume under");
+        };
+        let resumed = persistence.portfolio_store.read_by_id(&state.portfolio_id).await?;
+        insights.restore(&checkpoint.features, &instruments);
+        portfolio.restore(checkpoint, &resumed, &instruments)?;
+        executor.restore(checkpoint, &resumed, &instruments)?;
+        if let Some(sub_accounts) = &sub_accounts {
+            sub_accounts.restore(checkpoint, &resumed, &instruments)?;
+        }
+        info!("Resuming from checkpoint at {}", checkpoint.clock.current_timestamp);
+    }
+    let checkpointer = match (checkpoint_run, replay, barrier) {
+        (Some(run), Some((start, end)), Some(barrier)) => {
+            // A resumed run keeps the range it was started with
+            let (start, end) = match &checkpoint {
+                Some(checkpoint) => (checkpoint.clock.start, checkpoint.clock.end),
+                None => (start.map_err(anyhow::Error::msg)?, end.map_err(anyhow::Error::msg)?),
+            };
+            Some(Arc::new(
+                SimulationCheckpointer::builder()
+                    .pubsub(pubsub.clone())
+                    .persistence(persistence.clone())
+                    .insights(insights.clone())
+                    .portfolio(portfolio.clone())
+                    .executor(executor.clone())
+                    .sub_accounts(sub_accounts.clone())
+                    .barrier(barrier)
+                    .watchdog(watchdog.clone())
+                    .run(run)
+                    .start(start)
+                    .end(end)
+                    .frequency(frequency)
+                    .build(),
+            ))
+        }
+        (Some(_), _, _) => anyhow::bail!("Only simulations replaying with a sim ingestor can be checkpointed"),
+        (None, _, _) => None,
+    };
+
+    let config = load::<AuditConfig>();
+    let audit = config.audit.map(|c| Arc::new(Audit::from_config(&c, pubsub.clone())));
+
+    let config = load::<ExposureConfig>();
+    let exposure = config
+        .exposure
+        .map(|c| Arc::new(ExposureRecorder::from_config(&c, pubsub.clone())));
+
+    let config = load::<LatencyConfig>();
+    let latency = config
+        .latency
+        .map(|c| Arc::new(LatencyTracker::from_config(&c, pubsub.clone(), simulation)));
+
+    let config = load::<MarkoutConfig>();
+    let markouts = config
+        .markouts
+        .map(|c| Arc::new(MarkoutTracker::from_config(&c, pubsub.clone())));
+
+    let config = load::<ConsistencyConfig>();
+    let consistency = config.consistency.map(|c| {
+        Arc::new(ConsistencyChecker::from_config(
+            &c,
+            pubsub.clone(),
+            portfolio.clone(),
+            order_manager.clone(),
+            instruments.clone(),
+        ))
+    });
+
+    let config = load::<LifecycleConfig>();
+    let lifecycle = config.lifecycle.and_then(|c| match adapters.get(&c.venue) {
+        Some(adapter) => Some(Arc::new(InstrumentLifecycle::from_config(
+            &c,
+            pubsub.clone(),
+            adapter,
+            portfolio.clone(),
+            instruments.clone(),
+        ))),
+        None => {
+            warn!("No adapter for venue {}, instrument lifecycle disabled", c.venue);
+            None
+        }
+    });
+
+    let config = load::<UniverseConfig>();
+    let universe = match config.universe {
+        Some(c) => Some(Arc::new(
+            Universe::from_config(&c, pubsub.clone(), persistence.clone(), instruments.clone()).await?,
+        )),
+        None => None,
+    };
+
+    let config = load::<ControlAuthConfig>();
+    let control_auth = config.control_auth.map(|c| Arc::new(ControlAuth::from_config(&c)));
+    let control = match args.control_address {
+        Some(address) => {
+            let Some(name) = &args.portfolio else {
+                anyhow::bail!("The trading controls need the --portfolio they book under");
+            };
+            let portfolio = persistence.portfolio_store.read_by_name(name).await?;
+            Some(Arc::new(
+                TradingControlServer::builder()
+                    .address(address)
+                    .pubsub(pubsub.clone())
+                    .switch(switch)
+                    .portfolio(portfolio)
+                    .ledger(sub_accounts.clone())
+                    .instruments(instruments.clone())
+                    .auth(control_auth)
+                    .build(),
+            ))
+        }
+        None => None,
+    };
+
+    let metrics = load::<MetricsExporterConfig>()
+        .metrics_exporter
+        .map(|c| Arc::new(MetricsExporter::from_config(&c)));
+
+    let warm_up_bars = load::<WarmUpConfig>()
+        .warm_up
+        .map(|c| Duration::from_secs(c.bar_frequency_secs));
+
+    // The sim ingestor reports the progress of the replay
+    let progress_done = CancellationToken::new();
+    let progress = simulation.then(|| render_progress(&pubsub, progress_done.clone()));
+
+    let console = debugger.map(|debugger| {
+        tokio::spawn(run_debug_console(
+            debugger,
+            pubsub.clone(),
+            portfolio.clone(),
+            insights.clone(),
+            instruments.clone(),
+        ))
+    });
+
+    let engine = ForecastEngine::builder()
+        .pubsub(pubsub)
+        .instruments(instruments)
+        .universe(universe)
+        .frequency(frequency)
+        .simulation(simulation)
+        .warm_up_bars(warm_up_bars)
+        .persistor(persistence)
+        .audit(audit)
+        .latency(latency)
+        .markouts(markouts)
+        .leader_election(leader_election)
+        .event_bridge(event_bridge)
+        .metrics(metrics)
+        .portfolio(portfolio)
+        .daily_performance(Some(daily_performance))
+        .exposure(exposure)
+        .sub_accounts(sub_accounts)
+        .ingestors(ingestors)
+        .insights(insights)
+        .checkpointer(checkpointer)
+        .resume_at(checkpoint.map(|c| c.clock.current_timestamp))
+        .allocation_optim(allocation)
+        .overlay(overlay)
+        .covariance(covariance)
+        .order_manager(order_manager)
+        .executor(executor)
+        .watchdog(watchdog)
+        .halt_on_stall(args.halt_on_stall)
+        .consistency(consistency)
+        .lifecycle(lifecycle)
+        .transfers(transfers)
+        .control(control)
+        .build();
 
     engine.start().await.expect("Failed to start engine");
 
-    info!("Waiting for shutdown to complete...");
-    engine.stop().await.expect("Failed to stop engine");
-    info!("Shutdown complete");
+    info!("Waiting for shutdown to complete...");
+    engine.stop().await.expect("Failed to stop engine");
+    if let Some(console) = console {
+        console.abort();
+    }
+    progress_done.cancel();
+    if let Some(progress) = progress {
+        progress.await?;
+    }
+    info!("Shutdown complete");
+    Ok(())
+}
+
+const DEBUG_CONSOLE_HELP: &str = "Commands:
+  continue | c          run until the next breakpoint
+  step [n] | s [n]      publish n events (default 1) and pause again
+  pause | p             pause before the next event
+  portfolio             pnl and open positions
+  books                 last tick of every instrument
+  features <symbol>     latest value of every feature of the instrument
+  break <breakpoint>    add a breakpoint (at=2025-01-01 12:00, event=trade@BTCUSDT, pnl<-100, pnl>250)
+  breakpoints           list the breakpoints
+  delete <n>            remove the nth breakpoint
+  help";
+
+/// Interactive console of a debugged simulation, reads commands from stdin while the engine runs.
+async fn run_debug_console(
+    debugger: Arc<SimDebugger>,
+    pubsub: Arc<PubSub>,
+    portfolio: Arc<dyn Accounting>,
+    insights: Arc<InsightsService>,
+    instruments: Vec<Arc<Instrument>>,
+) {
+    // Track the pnl for the pnl breakpoints
+    let mut position_updates = pubsub.subscribe::<PositionUpdate>();
+    let tracker = debugger.clone();
+    tokio::spawn(async move {
+        while let Ok(update) = position_updates.recv().await {
+            tracker.update_position(&update);
+        }
+    });
+
+    // Stdin blocks, read it on its own thread so it doesn't hold up the shutdown of the runtime
+    let (tx, mut lines) = tokio::sync::mpsc::unbounded_channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            match line {
+                Ok(line) if tx.send(line).is_ok() => {}
+                _ => break,
+            }
+        }
+    });
+
+    println!("Debug console ready, type help for the commands");
+    let mut stops = debugger.subscribe();
+    loop {
+        tokio::select! {
+            res = stops.changed() => {
+                if res.is_err() {
+                    break;
+                }
+                if let Some(stop) = stops.borrow_and_update().clone() {
+                    println!("Paused, {}", stop);
+                }
+            }
+            line = lines.recv() => {
+                let Some(line) = line else {
+                    break;
+                };
+                let (command, arg) = match line.trim().split_once(' ') {
+                    Some((command, arg)) => (command, arg.trim()),
+                    None => (line.trim(), ""),
+                };
+                match command {
+                    "" => {}
+                    "continue" | "c" => debugger.resume(),
+                    "step" | "s" => match arg {
+                        "" => debugger.step(1),
+                        n => match n.parse() {
+                            Ok(n) => debugger.step(n),
+                            Err(_) => println!("Invalid number of events: {}", n),
+                        },
+                    },
+                    "pause" | "p" => debugger.pause(),
+                    "portfolio" => {
+                        println!("pnl={}", debugger.pnl());
+                        for position in portfolio.get_positions().await.values() {
+                            println!("  {}", position);
+                        }
+                    }
+                    "books" => {
+                        for tick in debugger.books() {
+                            println!("  {} {}", tick.event_time, tick);
+                        }
+                    }
+                    "features" => match instruments.iter().find(|i| i.venue_symbol == arg || i.symbol == arg) {
+                        Some(instrument) => {
+                            let mut features = insights
+                                .checkpoint()
+                                .into_iter()
+                                .filter(|f| f.instrument_id == Some(instrument.id))
+                                .filter_map(|f| f.values.last().map(|(ts, value)| (f.feature_id, *ts, *value)))
+                                .collect::<Vec<_>>();
+                            features.sort();
+                            for (feature, ts, value) in features {
+                                println!("  {}={} at {}", feature, value, ts);
+                            }
+                        }
+                        None => println!("Unknown instrument: {}", arg),
+                    },
+                    "break" => match arg.parse::<Breakpoint>() {
+                        Ok(breakpoint) => debugger.add_breakpoint(breakpoint),
+                        Err(e) => println!("{}", e),
+                    },
+                    "breakpoints" => {
+                        for (i, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                            println!("  {} {}", i, breakpoint);
+                        }
+                    }
+                    "delete" => match arg.parse().ok().and_then(|i| debugger.remove_breakpoint(i)) {
+                        Some(breakpoint) => println!("Removed {}", breakpoint),
+                        None => println!("No breakpoint {}", arg),
+                    },
+                    _ => println!("{}", DEBUG_CONSOLE_HELP),
+                }
+            }
+        }
+    }
+}
+
+async fn run_debug_slice(args: DebugSliceArgs, output: OutputFormat) -> Result<()> {
+    // The slice gets its own pubsub, nothing it computes reaches other services
+    let pubsub = Arc::new(PubSub::new());
+
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+    let instrument = persistence.symbol_registry.instrument("binance", &args.instrument).await?;
+
+    let mut config = load::<InsightsConfig>().insights_service;
+    if let Some(pipeline) = args.pipeline {
+        config.pipeline.name = pipeline;
+    }
+    let pipeline = persistence.pipeline_store.read_by_name(&config.pipeline.name).await?;
+    let insights = Arc::new(InsightsService::from_config(&config, pubsub.clone(), persistence.clone()).await);
+
+    let (strategies, allocation) = match args.features_only {
+        true => (vec![], None),
+        false => {
+            let watchdog = Arc::new(Watchdog::default());
+            let strategies = StrategyFactory::from_config(&load::<StrategyConfig>(), pubsub.clone());
+            let portfolio = PortfolioFactory::from_config(&load::<PortfolioConfig>(), pubsub.clone(), watchdog.clone());
+            let allocation = AllocationFactory::from_config(
+                &load::<AllocationOptimConfig>(),
+                pubsub.clone(),
+                persistence.clone(),
+                portfolio,
+                watchdog,
+            );
+            (strategies, Some(allocation))
+        }
+    };
+
+    let slice = DebugSlice::builder()
+        .persistence(persistence)
+        .pipeline(pipeline)
+        .insights(insights)
+        .strategies(strategies)
+        .allocation(allocation)
+        .instrument(instrument)
+        .start(args.at - args.window)
+        .end(args.at + args.window)
+        .frequency(Duration::from_secs(config.frequency_secs))
+        .warmup(Duration::from_secs(config.state_lookback))
+        .build();
+    let report = slice.run().await?;
+    print_report(output, &report)
+}
+
+async fn run_serve(args: ServeArgs) -> Result<()> {
+    let settings = load::<ServeConfig>().serve;
+    info!("Serve settings: {:?}", settings);
+    match args.role {
+        ServiceRole::DataProvider => run_data_provider().await,
+        ServiceRole::Execution => run_execution_role(settings).await,
+        ServiceRole::Insights => run_insights_role(settings).await,
+        ServiceRole::Agent => {
+            let args = EngineArgs {
+                instruments: settings.instruments,
+                select: false,
+                soft_deadline_ms: settings.soft_deadline_ms,
+                hard_deadline_ms: settings.hard_deadline_ms,
+                halt_on_stall: settings.halt_on_stall,
+                control_address: settings.control_address,
+                portfolio: settings.portfolio,
+                debug: false,
+                breakpoints: vec![],
+                checkpoint_run: None,
+                checkpoint_every: Duration::from_secs(3600),
+                resume_from: None,
+                replay_window: None,
+                pacing: None,
+            };
+            run_engine(args).await
+        }
+    }
+}
+
+async fn read_instruments(persistence: &PersistenceService, symbols: &[String]) -> Vec<Arc<Instrument>> {
+    let mut instruments = vec![];
+    for symbol in symbols {
+        match persistence.symbol_registry.instrument("binance", symbol).await {
+            Ok(instr) => instruments.push(instr),
+            Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
+        }
+    }
+    info!("Loaded {} instruments.", instruments.len());
+    instruments
+}
+
+/// Run the order manager and executor with the portfolio they book into, until Ctrl-C. Two of them can run
+/// hot/standby with leader election. The roles don't share their events yet, so orders only reach them from services
+/// in the same process.
+async fn run_execution_role(settings: ServeSettings) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+    let watchdog = Arc::new(
+        Watchdog::builder()
+            .soft_deadline(Duration::from_millis(settings.soft_deadline_ms))
+            .hard_deadline(Duration::from_millis(settings.hard_deadline_ms))
+            .build(),
+    );
+
+    let config = load::<PersistenceConfig>();
+    let persistence_service = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+
+    let config = load::<PortfolioConfig>();
+    let portfolio = PortfolioFactory::from_config(&config, pubsub.clone(), watchdog.clone());
+
+    let config = load::<VenuesConfig>();
+    let adapters = VenueAdapters::default();
+    config.venues.register(&adapters);
+
+    let config = load::<OrderManagerConfig>();
+    let switch = Arc::new(TradingSwitch::default());
+    let order_manager = ExecutionFactory::from_config(&config, pubsub.clone(), watchdog.clone(), switch.clone());
+
+    let config = load::<LeaderElectionConfig>();
+    let leadership = Arc::new(match config.leader_election {
+        Some(_) => Leadership::standby(),
+        None => Leadership::default(),
+    });
+    let leader_election = config
+        .leader_election
+        .map(|c| Arc::new(LeaderElection::from_config(&c, persistence_service.clone(), leadership.clone())));
+
+    let config = load::<ExecutorConfig>();
+    let executor =
+        ExecutorFactory::from_config(&config, pubsub.clone(), persistence_service.clone(), &adapters, leadership);
+
+    let config = load::<ControlAuthConfig>();
+    let control_auth = config.control_auth.map(|c| Arc::new(ControlAuth::from_config(&c)));
+    let control = match settings.control_address {
+        Some(address) => {
+            let Some(name) = &settings.portfolio else {
+                anyhow::bail!("The trading controls need the portfolio they book under");
+            };
+            let portfolio = persistence_service.portfolio_store.read_by_name(name).await?;
+            Some(Arc::new(
+                TradingControlServer::builder()
+                    .address(address)
+                    .pubsub(pubsub.clone())
+                    .switch(switch)
+                    .portfolio(portfolio)
+                    .auth(control_auth)
+                    .build(),
+            ))
+        }
+        None => None,
+    };
+
+    // Start the persistence service and leader election, they stop last so the persistence can flush what the others
+    // wrote and the lease is only released once the executor is down
+    let persistence_task_tracker = TaskTracker::new();
+    let persistence_shutdown = CancellationToken::new();
+    let shutdown = persistence_shutdown.clone();
+    persistence_task_tracker.spawn(async move {
+        if let Err(e) = persistence_service.start(shutdown).await {
+            error!("Failed to start persistence service: {}", e);
+        }
+    });
+    if let Some(election) = leader_election {
+        let shutdown = persistence_shutdown.clone();
+        persistence_task_tracker.spawn(async move {
+            if let Err(e) = election.start(shutdown).await {
+                error!("Failed to start leader election: {}", e);
+            }
+        });
+    }
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+    let task_tracker = TaskTracker::new();
+    let service_shutdown = CancellationToken::new();
+    let shutdown = service_shutdown.clone();
+    task_tracker.spawn(async move {
+        if let Err(e) = portfolio.start(shutdown).await {
+            error!("Failed to start portfolio: {}", e);
+        }
+    });
+    let shutdown = service_shutdown.clone();
+    task_tracker.spawn(async move {
+        if let Err(e) = order_manager.start(shutdown).await {
+            error!("Failed to start order manager: {}", e);
+        }
+    });
+    let shutdown = service_shutdown.clone();
+    task_tracker.spawn(async move {
+        if let Err(e) = executor.start(shutdown).await {
+            error!("Failed to start executor: {}", e);
+        }
+    });
+    if let Some(control) = control {
+        let shutdown = service_shutdown.clone();
+        task_tracker.spawn(async move {
+            if let Err(e) = control.serve(shutdown).await {
+                error!("Failed to start trading control: {}", e);
+            }
+        });
+    }
+
+    match tokio::signal::ctrl_c().await {
+        Ok(_) => {
+            info!("Received Ctrl-C signal, shutting down...");
+        }
+        Err(e) => error!("Failed to listen for Ctrl-C signal: {}", e),
+    }
+
+    service_shutdown.cancel();
+    task_tracker.close();
+    task_tracker.wait().await;
+    info!("All execution services have shut down");
+
+    persistence_shutdown.cancel();
+    persistence_task_tracker.close();
+    persistence_task_tracker.wait().await;
+    info!("Persistence service has shut down");
+    Ok(())
+}
+
+/// Compute the insights on the interval clock until Ctrl-C. The roles don't share their events yet, so the insights
+/// read the trades the data provider persisted since the previous tick instead of receiving them.
+async fn run_insights_role(settings: ServeSettings) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+
+    let persistence_task_tracker = TaskTracker::new();
+    let persistence_shutdown = CancellationToken::new();
+    let shutdown = persistence_shutdown.clone();
+    let persistence_service = persistence.clone();
+    persistence_task_tracker.spawn(async move {
+        if let Err(e) = persistence_service.start(shutdown).await {
+            error!("Failed to start persistence service: {}", e);
+        }
+    });
+
+    let config = load::<InsightsConfig>().insights_service;
+    let insights = InsightsService::from_config(&config, pubsub.clone(), persistence.clone()).await;
+    let instruments = read_instruments(&persistence, &settings.instruments).await;
+
+    let mut loaded = OffsetDateTime::now_utc();
+    insights.load(loaded, &instruments, Duration::from_secs(86400)).await?;
+    let mut current_day = loaded.date();
+    let mut time_helper = TickHelper::new(Duration::from_secs(config.frequency_secs));
+    loop {
+        tokio::select! {
+            (event_time, _) = time_helper.tick() => {
+                if event_time.date() != current_day {
+                    current_day = event_time.date();
+                    insights.remove(event_time).await?;
+                }
+                insights.load(event_time, &instruments, (event_time - loaded).unsigned_abs()).await?;
+                loaded = event_time;
+                insights.process(event_time, &instruments, true).await?;
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Received Ctrl-C signal, shutting down...");
+                break;
+            }
+        }
+    }
+
+    persistence.flush().await?;
+    persistence_shutdown.cancel();
+    persistence_task_tracker.close();
+    persistence_task_tracker.wait().await;
+    info!("Persistence service has shut down");
+    Ok(())
+}
+
+async fn run_trading(args: TradingCommands, output: OutputFormat) -> Result<()> {
+    let client = reqwest::Client::new();
+    let (address, token, request) = match args {
+        TradingCommands::List(args) => {
+            let url = format!("http://{}/trading/controls", args.address);
+            let disabled = with_token(client.get(url), args.token.as_deref())
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<DisabledTrading>>()
+                .await?;
+            if output == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&disabled)?);
+                return Ok(());
+            }
+            if disabled.is_empty() {
+                info!("Trading is enabled for all instruments and strategies");
+            }
+            for d in disabled {
+                info!("Trading disabled for {}: {}", d.scope, d.reason);
+            }
+            return Ok(());
+        }
+        TradingCommands::Disable(args) => (args.address.clone(), args.token.clone(), control_request(args, false)),
+        TradingCommands::Enable(args) => (args.address.clone(), args.token.clone(), control_request(args, true)),
+    };
+
+    let url = format!("http://{}/trading/controls", address);
+    with_token(client.post(url), token.as_deref())
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?;
+    if output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&request)?);
+    }
+    let state = if request.enabled {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    info!("Trading {} for {}", state, request.scope);
+    Ok(())
+}
+
+const TRADE_CONSOLE_HELP: &str = "Commands:
+  buy <instrument> <quantity> [price] [reason]     market order, limit order with a price
+  sell <instrument> <quantity> [price] [reason]
+  cancel <order id> [reason]
+  help
+  quit";
+
+/// Interactive console placing and cancelling orders through the control server of a running engine.
+async fn run_trade(args: TradeArgs, output: OutputFormat) -> Result<()> {
+    let operator = args
+        .operator
+        .or_else(|| std::env::var("USER").ok())
+        .ok_or_else(|| anyhow::anyhow!("No operator given and USER is not set"))?;
+    let client = reqwest::Client::new();
+
+    // Stdin blocks, read it on its own thread so it doesn't hold up the runtime
+    let (tx, mut lines) = tokio::sync::mpsc::unbounded_channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            match line {
+                Ok(line) if tx.send(line).is_ok() => {}
+                _ => break,
+            }
+        }
+    });
+
+    println!("Trade console ready as {}, type help for the commands", operator);
+    while let Some(line) = lines.recv().await {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let res = match words.as_slice() {
+            [] => continue,
+            ["quit" | "exit"] => break,
+            [side @ ("buy" | "sell"), instrument, quantity, rest @ ..] => {
+                let side = match *side {
+                    "buy" => MarketSide::Buy,
+                    _ => MarketSide::Sell,
+                };
+                let (price, reason) = match rest.first().map(|p| p.parse::<Decimal>()) {
+                    Some(Ok(price)) => (Some(price), &rest[1..]),
+                    _ => (None, rest),
+                };
+                match quantity.parse::<Decimal>() {
+                    Ok(quantity) => {
+                        let request = ManualOrderRequest {
+                            instrument: instrument.to_string(),
+                            side,
+                            quantity,
+                            price,
+                            operator: operator.clone(),
+                            reason: reason.join(" "),
+                        };
+                        place_manual_order(&client, &args.address, args.token.as_deref(), &request, output).await
+                    }
+                    Err(_) => {
+                        println!("Invalid quantity: {}", quantity);
+                        continue;
+                    }
+                }
+            }
+            ["cancel", id, reason @ ..] => match Uuid::parse_str(id) {
+                Ok(id) => {
+                    let request = ManualCancelRequest {
+                        id,
+                        operator: operator.clone(),
+                        reason: reason.join(" "),
+                    };
+                    cancel_manual_order(&client, &args.address, args.token.as_deref(), &request, output).await
+                }
+                Err(_) => {
+                    println!("Invalid order id: {}", id);
+                    continue;
+                }
+            },
+            _ => {
+                println!("{}", TRADE_CONSOLE_HELP);
+                continue;
+            }
+        };
+        // A rejected order keeps the console open, only a lost engine ends it
+        if let Err(e) = res {
+            println!("{}", e);
+            if e.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_connect()) {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn place_manual_order(
+    client: &reqwest::Client,
+    address: &str,
+    token: Option<&str>,
+    request: &ManualOrderRequest,
+    output: OutputFormat,
+) -> Result<()> {
+    let url = format!("http://{}/orders", address);
+    let response = with_token(client.post(url), token).json(request).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Order rejected ({}): {}", response.status(), response.text().await?);
+    }
+    let accepted = response.json::<ManualOrderAccepted>().await?;
+    match output {
+        OutputFormat::Table => println!("Sent order {}", accepted.id),
+        OutputFormat::Json => println!("{}", serde_json::to_string(&accepted)?),
+    }
+    Ok(())
+}
+
+async fn cancel_manual_order(
+    client: &reqwest::Client,
+    address: &str,
+    token: Option<&str>,
+    request: &ManualCancelRequest,
+    output: OutputFormat,
+) -> Result<()> {
+    let url = format!("http://{}/orders/cancel", address);
+    let response = with_token(client.post(url), token).json(request).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("Cancel rejected ({}): {}", response.status(), response.text().await?);
+    }
+    match output {
+        OutputFormat::Table => println!("Cancelling order {}", request.id),
+        OutputFormat::Json => println!("{}", serde_json::to_string(request)?),
+    }
+    Ok(())
+}
+
+/// Send the API token of the control server along, if there is one.
+fn with_token(request: reqwest::RequestBuilder, token: Option<&str>) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+fn control_request(args: TradingControlArgs, enabled: bool) -> TradingControlRequest {
+    let scope = match (args.instrument, args.strategy) {
+        (Some(instrument), _) => TradingScope::Instrument(instrument),
+        (None, Some(strategy)) => TradingScope::Strategy(strategy),
+        (None, None) => unreachable!("clap requires an instrument or strategy"),
+    };
+    TradingControlRequest {
+        scope,
+        enabled,
+        reason: args.reason,
+    }
+}
+
+async fn run_validate_features(args: ValidateFeaturesArgs, output: OutputFormat) -> Result<()> {
+    let mut config = load::<ValidationConfig>().feature_validation;
+    if let Some(dir) = args.fixtures_dir {
+        config.fixtures_dir = dir.display().to_string();
+    }
+    if !args.cases.is_empty() {
+        config.cases.retain(|c| args.cases.contains(&c.name));
+    }
+    config.require_full_coverage |= args.strict;
+
+    let validator = FeatureValidator::from_config(&config);
+    let summary = validator.validate()?;
+    if output == OutputFormat::Json {
+        let report = json!({ "passed": summary.passed(), "summary": summary });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    for report in &summary.reports {
+        match report.passed() {
+            true => info!("{}", report),
+            false => {
+                error!("{}", report);
+                for mismatch in report.mismatches.iter().take(10) {
+                    error!("  {}", mismatch);
+                }
+            }
+        }
+    }
+    if !summary.uncovered.is_empty() {
+        warn!("No validation cases for: {}", summary.uncovered.join(", "));
+    }
+
+    if !summary.passed() {
+        anyhow::bail!(
+            "{} of {} cases failed, {} feature kinds uncovered",
+            summary.failed().len(),
+            summary.reports.len(),
+            summary.uncovered.len()
+        );
+    }
+    Ok(())
+}
+
+async fn run_evaluate_predictions(args: EvaluatePredictionsArgs, output: OutputFormat) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+    let config = load::<PersistenceConfig>();
+    let persistence = Arc::new(PersistenceService::from_config(&config, pubsub.clone()).await);
+    let config = load::<InsightsConfig>().insights_service;
+    let name = args.pipeline.as_deref().unwrap_or(&config.pipeline.name);
+    let pipeline = persistence.pipeline_store.read_by_name(name).await?;
+
+    let mut instruments = vec![];
+    for symbol in &args.instruments {
+        match persistence.symbol_registry.instrument("binance", symbol).await {
+            Ok(instr) => instruments.push(instr),
+            Err(e) => error!("Failed to read instrument {}: {}", symbol, e),
+        }
+    }
+
+    // The outcome of the last predictions lies after the range
+    let horizon = Duration::from_secs(args.horizon_secs);
+    let predictions = persistence
+        .insights_store
+        .read_range(&pipeline, &instruments, args.from, args.till)
+        .await?;
+    let trades = persistence
+        .trade_store
+        .read_range(&instruments, args.from, args.till + horizon)
+        .await?;
+    let mut insights = trades
+        .iter()
+        .flat_map(|t| t.as_ref().clone().to_insights(pipeline.clone()))
+        .chain(predictions.into_iter().filter(|i| *i.feature_id == args.prediction))
+        .collect::<Vec<_>>();
+    insights.sort_by_key(|i| i.event_time);
+    info!("Loaded {} trades and predictions", insights.len());
+
+    // Predictions in excluded periods are left out, their outcomes stay as prices of the predictions before them
+    let annotations = persistence.annotation_store.read_range(args.from, args.till).await?;
+    let excluded = |insight: &Arc<Insight>| {
+        *insight.feature_id == args.prediction
+            && insight
+                .instrument
+                .as_ref()
+                .is_some_and(|i| annotations.iter().any(|a| a.excludes(&i.symbol, insight.event_time)))
+    };
+    let before = insights.len();
+    insights.retain(|i| !excluded(i));
+    if insights.len() < before {
+        info!("Left out {} predictions in excluded periods", before - insights.len());
+    }
+
+    let evaluator = PredictionEvaluator::builder()
+        .prediction(FeatureId::new(args.prediction))
+        .price(TRADE_PRICE_FEATURE_ID.clone())
+        .horizon_secs(args.horizon_secs)
+        .threshold(args.threshold)
+        .cost_bps(args.cost_bps)
+        .calibration_buckets(args.buckets)
+        .build();
+    let scorecard = evaluator.evaluate(&frames_from_insights(&insights), (args.till + horizon).unix_timestamp());
+    let report = AnnotatedReport {
+        report: scorecard,
+        annotations,
+    };
+    print_report(output, &report)
+}
+
+async fn run_compare_predictions(args: ComparePredictionsArgs, output: OutputFormat) -> Result<()> {
+    let pubsub = Arc::new(PubSub::new());
+    let config = load::<PersistenceConfig>();
+    let persistence = PersistenceService::from_config(&config, pubsub).await;
+
+    let distributions = persistence
+        .prediction_store
+        .distributions(&args.model, args.from, args.till)
+        .await?;
+    let mut comparisons = Vec::with_capacity(args.candidates.len());
+    for candidate in &args.candidates {
+        let comparison = persistence
+            .prediction_store
+            .compare_versions(&args.model, &args.baseline, candidate, args.from, args.till)
+            .await?;
+        comparisons.push(comparison);
+    }
+
+    match output {
+        OutputFormat::Table => {
+            println!("Prediction distributions of {}:", args.model);
+            for distribution in &distributions {
+                println!("  {}", distribution);
+            }
+            for comparison in &comparisons {
+                println!("{}", comparison);
+            }
+        }
+        OutputFormat::Json => {
+            let report = json!({ "model": args.model, "distributions": distributions, "comparisons": comparisons });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+    }
+    Ok(())
+}
+
+async fn run_annotations(command: AnnotationsCommands, output: OutputFormat) -> Result<()> {
+    let config = load::<PersistenceConfig>();
+    let persistence = PersistenceService::from_config(&config, Arc::new(PubSub::new())).await;
+
+    match command {
+        AnnotationsCommands::Add(args) => {
+            let (target, target_id) = match (args.order, args.trade) {
+                (Some(order), _) => (AnnotationTarget::Order, Some(order)),
+                (None, Some(trade)) => (AnnotationTarget::Trade, Some(trade)),
+                (None, None) => (AnnotationTarget::Range, None),
+            };
+            let instrument = match &args.instrument {
+                Some(symbol) => Some(persistence.symbol_registry.instrument("binance", symbol).await?.symbol.clone()),
+                None => None,
+            };
+            let start = args.from.unwrap_or_else(OffsetDateTime::now_utc);
+            let end = args.till.unwrap_or(start);
+            if end < start {
+                anyhow::bail!("Annotation ends before it starts");
+            }
+            let author = args
+                .author
+                .or_else(|| std::env::var("USER").ok())
+                .unwrap_or_else(|| "unknown".to_string());
+            let annotation = Annotation::builder()
+                .target(target)
+                .target_id(target_id)
+                .instrument(instrument)
+                .start(start)
+                .end(end)
+                .tag(args.tag)
+                .note(args.note)
+                .exclude(args.exclude)
+                .author(author)
+                .build();
+            persistence.annotation_store.insert(&annotation).await?;
+            match output {
+                OutputFormat::Table => println!("{}", annotation),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&annotation)?),
+            }
+            Ok(())
+        }
+        AnnotationsCommands::List(args) => {
+            let annotations = persistence.annotation_store.read_range(args.from, args.till).await?;
+            match output {
+                OutputFormat::Table => annotations.iter().for_each(|a| println!("{}", a)),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&annotations)?),
+            }
+            Ok(())
+        }
+        AnnotationsCommands::Delete(args) => {
+            if !persistence.annotation_store.delete(&args.id).await? {
+                anyhow::bail!("No annotation {}", args.id);
+            }
+            print_status(output, "annotations", None);
+            Ok(())
+        }
+    }
+}
+
+async fn run_jobs(command: JobsCommands, output: OutputFormat) -> Result<()> {
+    let config = load::<PersistenceConfig>();
+    let persistence = PersistenceService::from_config(&config, Arc::new(PubSub::new())).await;
+
+    match command {
+        JobsCommands::Submit(args) => {
+            let cli = parse_job_command(&args.command)?;
+            let Some((name, start, end, instruments)) = job_spec(&cli.command) else {
+                anyhow::bail!("Only insights backfills and tardis downloads with a time range can run as jobs");
+            };
+            let (group_id, shards) = match args.shards {
+                0 | 1 => (None, vec![vec![]]),
+                n if instruments.len() < 2 => anyhow::bail!("Need more than one instrument to split over {} shards", n),
+                n => (Some(Uuid::new_v4()), shard_instruments(&instruments, n)),
+            };
+
+            let mut jobs = Vec::with_capacity(shards.len());
+            for instruments in shards {
+                let job = Job::builder()
+                    .name(name.into())
+                    .args(args.command.clone())
+                    .group_id(group_id)
+                    .instruments(instruments)
+                    .start(start)
+                    .end(end)
+                    .chunk(Duration::from_secs(args.chunk_hours.max(1) * 3600))
+                    .build();
+                persistence.job_store.insert(&job).await?;
+                jobs.push(job);
+            }
+            match output {
+                OutputFormat::Table => {
+                    if let Some(group_id) = group_id {
+                        println!("group={}", group_id);
+                    }
+                    jobs.iter().for_each(|job| println!("{}", job));
+                }
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json!({ "group_id": group_id, "jobs": jobs }))?
+                ),
+            }
+            Ok(())
+        }
+        JobsCommands::List => {
+            let jobs = persistence.job_store.read_all().await?;
+            match output {
+                OutputFormat::Table => jobs.iter().for_each(|job| println!("{}", job)),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&jobs)?),
+            }
+            Ok(())
+        }
+        JobsCommands::Pause(args) => set_job_status(&persistence, &args.id, JobStatus::Paused, output).await,
+        JobsCommands::Resume(args) => set_job_status(&persistence, &args.id, JobStatus::Queued, output).await,
+        JobsCommands::Cancel(args) => set_job_status(&persistence, &args.id, JobStatus::Cancelled, output).await,
+        JobsCommands::Run(args) => run_job_worker(&persistence, args).await,
+        JobsCommands::Report(args) => loop {
+            let jobs = persistence.job_store.read_by_group(&args.group).await?;
+            if jobs.is_empty() {
+                anyhow::bail!("No jobs in group {}", args.group);
+            }
+            let report = JobGroupReport::from_jobs(args.group, &jobs);
+            if !args.wait || report.is_finished() {
+                return print_report(output, &report);
+            }
+            info!("{}", report);
+            tokio::time::sleep(Duration::from_secs(args.poll_secs)).await;
+        },
+    }
+}
+
+fn parse_job_command(args: &[String]) -> Result<Cli> {
+    let cli = Cli::try_parse_from(std::iter::once("arkin").chain(args.iter().map(String::as_str)))?;
+    Ok(cli)
+}
+
+/// Name, time range and instruments of the commands that can run as jobs.
+fn job_spec(command: &Commands) -> Option<(&'static str, OffsetDateTime, OffsetDateTime, Vec<String>)> {
+    match command {
+        // Picking instruments needs someone at the terminal
+        Commands::Insights(args) if !args.explain && !args.select => {
+            Some(("insights", args.from?, args.till?, args.instruments.clone()))
+        }
+        Commands::Ingestors(IngestorsCommands::Tardis(args)) => {
+            Some(("tardis", args.start, args.end, args.instruments.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Deal the instruments round robin over the shards, so every shard gets a similar number of them.
+fn shard_instruments(instruments: &[String], shards: usize) -> Vec<Vec<String>> {
+    let mut sharded = vec![vec![]; shards.min(instruments.len())];
+    for (i, instrument) in instruments.iter().enumerate() {
+        let count = sharded.len();
+        sharded[i % count].push(instrument.clone());
+    }
+    sharded
+}
+
+async fn set_job_status(
+    persistence: &PersistenceService,
+    id: &Uuid,
+    status: JobStatus,
+    output: OutputFormat,
+) -> Result<()> {
+    let job = persistence.job_store.read_by_id(id).await?;
+    let allowed = match status {
+        JobStatus::Paused => job.status.can_pause(),
+        JobStatus::Queued => job.status.can_resume(),
+        JobStatus::Cancelled => job.status.can_cancel(),
+        _ => false,
+    };
+    if !allowed {
+        anyhow::bail!("Job {} is {} and can not be set to {}", id, job.status, status);
+    }
+    persistence.job_store.update_status(id, status, None).await?;
+    print_report(output, &persistence.job_store.read_by_id(id).await?)
+}
+
+async fn run_job_worker(persistence: &PersistenceService, args: JobRunArgs) -> Result<()> {
+    let worker = args
+        .worker
+        .clone()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| format!("worker-{}", std::process::id()));
+    info!("Starting job worker {}", worker);
+    loop {
+        let stale = OffsetDateTime::now_utc() - Duration::from_secs(args.stale_secs);
+        let requeued = persistence.job_store.requeue_stale(stale).await?;
+        if requeued > 0 {
+            warn!("Queued {} jobs of stopped workers again", requeued);
+        }
+
+        let Some(job) = persistence.job_store.claim_next(&worker).await? else {
+            if args.once {
+                info!("No queued jobs left");
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(args.poll_secs)).await;
+            continue;
+        };
+        info!("Running job {}", job);
+        let id = job.id;
+        if let Err(e) = run_job(persistence, job).await {
+            error!("Job {} failed: {}", id, e);
+            persistence
+                .job_store
+                .update_status(&id, JobStatus::Failed, Some(e.to_string()))
+                .await?;
+        }
+    }
+}
+
+/// Run the chunks after the cursor of the job, saving the cursor after every chunk.
+async fn run_job(persistence: &PersistenceService, mut job: Job) -> Result<()> {
+    while let Some((start, end)) = job.next_chunk() {
+        // Pause and cancel requests take effect between chunks
+        let status = persistence.job_store.read_by_id(&job.id).await?.status;
+        if status != JobStatus::Running {
+            info!("Job {} is {}, stopping at {}", job.id, status, job.cursor);
+            return Ok(());
+        }
+
+        let store = persistence.job_store.clone();
+        let id = job.id;
+        let heartbeat = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.heartbeat(&id).await {
+                    warn!("Failed to send heartbeat of job {}: {}", id, e);
+                }
+            }
+        });
+        let res = match parse_job_command(&job.args)?.command {
+            Commands::Insights(mut args) => {
+                if !job.instruments.is_empty() {
+                    args.instruments = job.instruments.clone();
+                }
+                args.from = Some(start);
+                args.till = Some(end);
+                run_insights(args).await
+            }
+            Commands::Ingestors(IngestorsCommands::Tardis(mut args)) => {
+                if !job.instruments.is_empty() {
+                    args.instruments = job.instruments.clone();
+                }
+                args.start = start;
+                args.end = end;
+                // The aggregate policy only refreshes the last days, so the bars of the backfilled trades are
+                // materialized here
+                match run_ingestor(IngestorsCommands::Tardis(args)).await {
+                    Ok(()) => persistence.trade_store.refresh_bars(start, end).await.map_err(Into::into),
+                    Err(e) => Err(e),
+                }
+            }
+            _ => Err(anyhow::anyhow!("Command of job {} can not run as a job", job.id)),
+        };
+        heartbeat.abort();
+        res?;
+
+        persistence.job_store.update_cursor(&job.id, end).await?;
+        job.cursor = end;
+        info!("Job {} at {:.1}%", job.id, job.progress() * 100.);
+    }
+    persistence.job_store.update_status(&job.id, JobStatus::Completed, None).await?;
+    info!("Job {} completed", job.id);
     Ok(())
 }
@@ -0,0 +1,9 @@
+DROP MATERIALIZED VIEW IF EXISTS trades_1m;
+
+SELECT remove_compression_policy('trades', if_exists => TRUE);
+SELECT decompress_chunk(c, if_compressed => TRUE) FROM show_chunks('trades') c;
+ALTER TABLE trades SET (timescaledb.compress = false);
+
+SELECT remove_compression_policy('ticks', if_exists => TRUE);
+SELECT decompress_chunk(c, if_compressed => TRUE) FROM show_chunks('ticks') c;
+ALTER TABLE ticks SET (timescaledb.compress = false);
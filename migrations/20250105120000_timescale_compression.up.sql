@@ -0,0 +1,42 @@
+-- TimescaleDB compression and continuous aggregates for market data.
+-- Requires the timescaledb extension (ticks and trades are already hypertables).
+
+-- COMPRESSION
+ALTER TABLE ticks SET (
+    timescaledb.compress,
+    timescaledb.compress_segmentby = 'instrument_id',
+    timescaledb.compress_orderby = 'event_time DESC, tick_id DESC'
+);
+SELECT add_compression_policy('ticks', INTERVAL '7 days', if_not_exists => TRUE);
+
+ALTER TABLE trades SET (
+    timescaledb.compress,
+    timescaledb.compress_segmentby = 'instrument_id',
+    timescaledb.compress_orderby = 'event_time DESC, trade_id DESC'
+);
+SELECT add_compression_policy('trades', INTERVAL '7 days', if_not_exists => TRUE);
+
+
+-- CONTINUOUS AGGREGATES
+CREATE MATERIALIZED VIEW IF NOT EXISTS trades_1m
+WITH (timescaledb.continuous) AS
+SELECT
+    time_bucket(INTERVAL '1 minute', event_time) AS event_time,
+    instrument_id,
+    first(price, event_time) AS open,
+    max(price) AS high,
+    min(price) AS low,
+    last(price, event_time) AS close,
+    sum(quantity) AS volume,
+    sum(price * quantity) AS notional_volume,
+    count(*) AS trade_count
+FROM trades
+GROUP BY time_bucket(INTERVAL '1 minute', event_time), instrument_id
+WITH NO DATA;
+
+SELECT add_continuous_aggregate_policy('trades_1m',
+    start_offset => INTERVAL '3 days',
+    end_offset => INTERVAL '1 minute',
+    schedule_interval => INTERVAL '1 minute',
+    if_not_exists => TRUE
+);
@@ -0,0 +1,9 @@
+-- Per venue overrides of the mapping from venue symbols to canonical instrument symbols (like perp-btc-usdt).
+CREATE TABLE IF NOT EXISTS symbol_overrides (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid (),
+    venue_id uuid NOT NULL REFERENCES venues(id),
+    venue_symbol TEXT NOT NULL,
+    canonical_symbol TEXT NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    UNIQUE (venue_id, venue_symbol)
+);
@@ -0,0 +1,2 @@
+-- Options (and futures) reference the instrument they settle against.
+ALTER TABLE instruments ADD COLUMN IF NOT EXISTS underlying_id uuid REFERENCES instruments(id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS rebalances;
+DROP TYPE IF EXISTS rebalance_trigger;
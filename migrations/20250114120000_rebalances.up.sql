@@ -0,0 +1,10 @@
+-- Every rebalance of the allocation together with what triggered it, for attribution of the resulting orders.
+CREATE TYPE rebalance_trigger AS ENUM ('schedule', 'drift', 'regime');
+CREATE TABLE IF NOT EXISTS rebalances (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid (),
+    event_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    portfolio_id uuid NOT NULL REFERENCES portfolios(id),
+    trigger rebalance_trigger NOT NULL,
+    detail TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rebalances_event_time_idx ON rebalances (event_time);
@@ -0,0 +1,14 @@
+-- Performance of every strategy per UTC day, frozen at the day boundary.
+CREATE TABLE IF NOT EXISTS daily_performance (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid (),
+    date DATE NOT NULL,
+    portfolio_id uuid NOT NULL REFERENCES portfolios(id),
+    strategy TEXT NOT NULL,
+    realized_pnl NUMERIC NOT NULL,
+    unrealized_pnl NUMERIC NOT NULL,
+    commission NUMERIC NOT NULL,
+    funding NUMERIC NOT NULL,
+    turnover NUMERIC NOT NULL,
+    trades BIGINT NOT NULL,
+    UNIQUE (date, portfolio_id, strategy)
+);
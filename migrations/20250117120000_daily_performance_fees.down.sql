@@ -0,0 +1,5 @@
+ALTER TABLE daily_performance
+    DROP COLUMN IF EXISTS nav,
+    DROP COLUMN IF EXISTS high_water_mark,
+    DROP COLUMN IF EXISTS management_fee,
+    DROP COLUMN IF EXISTS performance_fee;
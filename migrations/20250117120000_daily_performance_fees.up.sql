@@ -0,0 +1,6 @@
+-- Net asset value after hypothetical fund fees and the high-water mark the performance fee is charged above.
+ALTER TABLE daily_performance
+    ADD COLUMN IF NOT EXISTS nav NUMERIC NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS high_water_mark NUMERIC NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS management_fee NUMERIC NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS performance_fee NUMERIC NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Postgres can't drop enum values, move the instruments back to the closest remaining status instead.
+UPDATE instruments SET status = 'halted' WHERE status::text IN ('settling', 'delisted');
@@ -0,0 +1,3 @@
+-- Instruments entering delivery or settlement and instruments no longer listed by the venue.
+ALTER TYPE instrument_status ADD VALUE IF NOT EXISTS 'settling';
+ALTER TYPE instrument_status ADD VALUE IF NOT EXISTS 'delisted';
@@ -0,0 +1,15 @@
+-- Model predictions with the model version and a hash of the inputs for shadow evaluations.
+CREATE TABLE IF NOT EXISTS predictions (
+    id uuid DEFAULT gen_random_uuid (),
+    event_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    pipeline_id uuid NOT NULL REFERENCES pipelines(id),
+    instrument_id uuid NOT NULL REFERENCES instruments(id),
+    feature_id TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    model_version TEXT NOT NULL,
+    feature_hash TEXT NOT NULL,
+    value NUMERIC NOT NULL,
+    latency_us BIGINT NOT NULL,
+    PRIMARY KEY (model_name, model_version, instrument_id, event_time)
+);
+SELECT create_hypertable('predictions', by_range('event_time', interval '1 day'));
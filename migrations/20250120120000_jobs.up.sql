@@ -0,0 +1,17 @@
+-- Long running commands that work through their time range in chunks and can be paused and resumed.
+CREATE TYPE job_status AS ENUM ('queued', 'running', 'paused', 'cancelled', 'completed', 'failed');
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id uuid PRIMARY KEY,
+    name TEXT NOT NULL,
+    args TEXT[] NOT NULL,
+    start_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    end_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    cursor_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    chunk_secs BIGINT NOT NULL,
+    status job_status NOT NULL,
+    error TEXT,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL
+);
+CREATE INDEX IF NOT EXISTS jobs_status_idx ON jobs (status, created_at);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS jobs_group_idx;
+ALTER TABLE jobs DROP COLUMN IF EXISTS worker;
+ALTER TABLE jobs DROP COLUMN IF EXISTS instruments;
+ALTER TABLE jobs DROP COLUMN IF EXISTS group_id;
@@ -0,0 +1,5 @@
+-- Jobs sharded by instrument across workers, with the worker that claimed them.
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS group_id uuid;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS instruments TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS worker TEXT;
+CREATE INDEX IF NOT EXISTS jobs_group_idx ON jobs (group_id);
@@ -0,0 +1,6 @@
+-- Leases of the instances running hot/standby, the holder leads until the lease expires.
+CREATE TABLE IF NOT EXISTS leases (
+    name TEXT PRIMARY KEY,
+    holder TEXT NOT NULL,
+    expires_at TIMESTAMP(3) WITH TIME ZONE NOT NULL
+);
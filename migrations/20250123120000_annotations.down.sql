@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS annotations;
+DROP TYPE IF EXISTS annotation_target;
@@ -0,0 +1,18 @@
+-- Notes operators attach to orders, trades and periods, reports list them and leave out the excluded periods.
+CREATE TYPE annotation_target AS ENUM ('order', 'trade', 'range');
+
+CREATE TABLE IF NOT EXISTS annotations (
+    id uuid PRIMARY KEY,
+    target annotation_target NOT NULL,
+    target_id uuid,
+    instrument TEXT,
+    start_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    end_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    tag TEXT NOT NULL,
+    note TEXT NOT NULL,
+    exclude BOOLEAN NOT NULL,
+    author TEXT NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL
+);
+CREATE INDEX IF NOT EXISTS annotations_range_idx ON annotations (start_time, end_time);
+CREATE INDEX IF NOT EXISTS annotations_target_idx ON annotations (target_id);
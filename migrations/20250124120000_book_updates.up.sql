@@ -0,0 +1,11 @@
+-- Price levels of the depth stream, every update sets the quantity of its level and a zero quantity removes it.
+CREATE TABLE IF NOT EXISTS book_updates (
+    event_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    instrument_id uuid NOT NULL REFERENCES instruments(id),
+    side market_side NOT NULL,
+    price NUMERIC NOT NULL,
+    quantity NUMERIC NOT NULL,
+    PRIMARY KEY (instrument_id, event_time, side, price)
+);
+SELECT create_hypertable('book_updates', by_range('event_time', interval '1 day'));
+SELECT add_dimension('book_updates', by_hash('instrument_id', 4));
@@ -0,0 +1,3 @@
+ALTER TABLE daily_performance
+    DROP COLUMN IF EXISTS signal_pnl,
+    DROP COLUMN IF EXISTS execution_pnl;
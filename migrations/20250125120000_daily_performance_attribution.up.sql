@@ -0,0 +1,4 @@
+-- Pnl of the day attributed to the signal (at decision prices) and to the execution (slippage against them).
+ALTER TABLE daily_performance
+    ADD COLUMN IF NOT EXISTS signal_pnl NUMERIC NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS execution_pnl NUMERIC NOT NULL DEFAULT 0;
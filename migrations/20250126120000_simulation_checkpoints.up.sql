@@ -0,0 +1,9 @@
+-- Periodic checkpoints of simulation runs, a crashed backtest resumes from the latest one of its run.
+CREATE TABLE IF NOT EXISTS simulation_checkpoints (
+    id uuid PRIMARY KEY,
+    run TEXT NOT NULL,
+    clock_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    state TEXT NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL
+);
+CREATE INDEX IF NOT EXISTS simulation_checkpoints_run_idx ON simulation_checkpoints (run, clock_time DESC);
@@ -0,0 +1,18 @@
+-- Execution quality of every order once it left the book, for the analysis of backtests.
+CREATE TABLE IF NOT EXISTS execution_metrics (
+    order_id uuid PRIMARY KEY,
+    event_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    instrument_id uuid NOT NULL REFERENCES instruments(id),
+    side market_side NOT NULL,
+    order_type venue_order_type NOT NULL,
+    status venue_order_status NOT NULL,
+    quantity NUMERIC NOT NULL,
+    filled_quantity NUMERIC NOT NULL,
+    fill_ratio DOUBLE PRECISION NOT NULL,
+    time_to_fill_us BIGINT,
+    arrival_price NUMERIC,
+    avg_fill_price NUMERIC,
+    effective_spread_bps DOUBLE PRECISION,
+    implementation_shortfall_bps DOUBLE PRECISION
+);
+CREATE INDEX IF NOT EXISTS execution_metrics_event_time_idx ON execution_metrics (event_time);
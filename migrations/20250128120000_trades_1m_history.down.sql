@@ -0,0 +1 @@
+ALTER MATERIALIZED VIEW trades_1m SET (timescaledb.materialized_only = true);
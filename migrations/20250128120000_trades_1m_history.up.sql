@@ -0,0 +1,7 @@
+-- no-transaction
+-- Serve the minutes the trades_1m policy hasn't materialized yet from the raw trades, and materialize the trades
+-- written before the aggregate was created. The policy only refreshes the last 3 days, so history backfilled later
+-- is refreshed by the backfill jobs.
+ALTER MATERIALIZED VIEW trades_1m SET (timescaledb.materialized_only = false);
+
+CALL refresh_continuous_aggregate('trades_1m', NULL, now() - INTERVAL '1 minute');
@@ -0,0 +1,2 @@
+ALTER TABLE execution_metrics DROP COLUMN IF EXISTS maker_ratio;
+ALTER TABLE execution_metrics DROP COLUMN IF EXISTS commission_bps;
@@ -0,0 +1,3 @@
+-- Fees and liquidity role of the fills in the execution quality of the orders.
+ALTER TABLE execution_metrics ADD COLUMN IF NOT EXISTS commission_bps DOUBLE PRECISION;
+ALTER TABLE execution_metrics ADD COLUMN IF NOT EXISTS maker_ratio DOUBLE PRECISION;
@@ -0,0 +1,18 @@
+-- Outcome of every account transfer request, the audit trail of the funds moved between wallets and sub-accounts.
+CREATE TABLE IF NOT EXISTS account_transfers (
+    id uuid PRIMARY KEY,
+    event_time TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    requested_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    portfolio_id uuid NOT NULL,
+    asset_id uuid NOT NULL REFERENCES assets(id),
+    quantity NUMERIC NOT NULL,
+    from_account TEXT NOT NULL,
+    to_account TEXT NOT NULL,
+    from_sub_account TEXT,
+    to_sub_account TEXT,
+    requested_by TEXT NOT NULL,
+    status TEXT NOT NULL,
+    venue_transfer_id TEXT,
+    reason TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS account_transfers_event_time_idx ON account_transfers (event_time);
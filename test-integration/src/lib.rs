@@ -1 +1,7 @@
+mod mock_venue;
 
+pub use mock_venue::*;
+
+pub mod prelude {
+    pub use crate::mock_venue::*;
+}
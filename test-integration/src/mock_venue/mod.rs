@@ -0,0 +1,5 @@
+mod server;
+mod state;
+
+pub use server::*;
+pub use state::*;
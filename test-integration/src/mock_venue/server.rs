@@ -0,0 +1,407 @@
+use std::{collections::HashMap, collections::HashSet, net::SocketAddr, str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, RawQuery, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::{net::TcpListener, sync::broadcast, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use super::MockVenueState;
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn api_error(status: StatusCode, code: i64, msg: &str) -> ApiError {
+    (status, Json(json!({ "code": code, "msg": msg })))
+}
+
+/// In-process mock of the Binance USD-M futures REST and websocket API.
+///
+/// Serves the REST endpoints under `http_url()`, the market streams under `ws_url()` and the user data
+/// streams under `ws_url()/<listen key>`, so the real Binance clients can run against it unchanged.
+pub struct MockBinance {
+    pub state: Arc<MockVenueState>,
+    addr: SocketAddr,
+    shutdown: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+impl MockBinance {
+    pub const API_KEY: &'static str = "mock-api-key";
+    pub const API_SECRET: &'static str = "mock-api-secret";
+
+    /// Start the mock venue on a random local port.
+    pub async fn start() -> Self {
+        Self::start_with_credentials(Self::API_KEY, Self::API_SECRET).await
+    }
+
+    pub async fn start_with_credentials(api_key: &str, api_secret: &str) -> Self {
+        let state = Arc::new(MockVenueState::new(api_key, api_secret));
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock venue");
+        let addr = listener.local_addr().expect("Mock venue has no local address");
+
+        let router = Router::new()
+            .route("/fapi/v1/listenKey", post(new_listen_key).put(keep_alive_listen_key))
+            .route("/fapi/v1/order", post(new_order).delete(cancel_order))
+            .route("/fapi/v1/allOpenOrders", axum::routing::delete(cancel_all_open_orders))
+            .route("/fapi/v1/openOrders", get(open_orders))
+            .route("/fapi/v3/balance", get(balance))
+            .route("/fapi/v3/positionRisk", get(position_risk))
+            .route("/fapi/v3/account", get(account))
+            .route("/fapi/v1/positionSide/dual", get(position_mode))
+            .route("/sapi/v1/account/apiRestrictions", get(api_restrictions))
+            .route("/ws", get(market_stream))
+            .route("/ws/:listen_key", get(user_stream))
+            .with_state(state.clone());
+
+        let shutdown = CancellationToken::new();
+        let token = shutdown.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router)
+                .with_graceful_shutdown(async move { token.cancelled().await })
+                .await
+            {
+                error!("Mock venue server failed: {}", e);
+            }
+        });
+        info!("Mock Binance venue listening on {}", addr);
+
+        Self {
+            state,
+            addr,
+            shutdown,
+            handle,
+        }
+    }
+
+    /// Base url for the REST client, including the trailing slash the Binance client expects.
+    pub fn http_url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}/ws", self.addr)
+    }
+
+    pub async fn stop(self) {
+        self.shutdown.cancel();
+        let _ = self.handle.await;
+    }
+}
+
+impl Drop for MockBinance {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+    }
+}
+
+/// Check the API key header and, for signed endpoints, the HMAC signature over the raw query string.
+fn authenticate(
+    state: &MockVenueState,
+    headers: &HeaderMap,
+    query: &Option<String>,
+    signed: bool,
+) -> Result<HashMap<String, String>, ApiError> {
+    let api_key = headers.get("X-MBX-APIKEY").and_then(|v| v.to_str().ok());
+    if api_key != Some(state.api_key.as_str()) {
+        return Err(api_error(StatusCode::UNAUTHORIZED, -2014, "API-key format invalid."));
+    }
+
+    let query = query.clone().unwrap_or_default();
+    let params = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect::<HashMap<_, _>>();
+    if signed {
+        let (payload, signature) = match query.rsplit_once("&signature=") {
+            Some((payload, signature)) => (payload, signature),
+            None => {
+                return Err(api_error(
+                    StatusCode::BAD_REQUEST,
+                    -1102,
+                    "Mandatory parameter 'signature' was not sent.",
+                ))
+            }
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(state.api_secret.as_bytes()).expect("HMAC accepts any key size");
+        mac.update(payload.as_bytes());
+        let expected = format!("{:x}", mac.finalize().into_bytes());
+        if expected != signature {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                -1022,
+                "Signature for this request is not valid.",
+            ));
+        }
+        if !params.contains_key("timestamp") {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                -1102,
+                "Mandatory parameter 'timestamp' was not sent.",
+            ));
+        }
+    }
+    Ok(params)
+}
+
+fn required<'a>(params: &'a HashMap<String, String>, name: &str) -> Result<&'a str, ApiError> {
+    params.get(name).map(|v| v.as_str()).ok_or_else(|| {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            -1102,
+            &format!("Mandatory parameter '{}' was not sent, was empty/null, or malformed.", name),
+        )
+    })
+}
+
+fn decimal(params: &HashMap<String, String>, name: &str) -> Result<Option<Decimal>, ApiError> {
+    params
+        .get(name)
+        .map(|v| {
+            Decimal::from_str(v).map_err(|_| {
+                api_error(
+                    StatusCode::BAD_REQUEST,
+                    -1100,
+                    &format!("Illegal characters found in parameter '{}'.", name),
+                )
+            })
+        })
+        .transpose()
+}
+
+async fn new_listen_key(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, false)?;
+    let key = state.new_listen_key().await;
+    Ok(Json(json!({ "listenKey": key })))
+}
+
+async fn keep_alive_listen_key(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, false)?;
+    Ok(Json(json!({})))
+}
+
+async fn new_order(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    let params = authenticate(&state, &headers, &query, true)?;
+    let symbol = required(&params, "symbol")?;
+    let side = required(&params, "side")?;
+    let order_type = required(&params, "type")?;
+    let quantity = decimal(&params, "quantity")?.ok_or_else(|| {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            -1102,
+            "Mandatory parameter 'quantity' was not sent, was empty/null, or malformed.",
+        )
+    })?;
+    let price = decimal(&params, "price")?;
+
+    match state
+        .place_order(
+            symbol,
+            side,
+            order_type,
+            params.get("timeInForce").map(|s| s.as_str()),
+            quantity,
+            price,
+            params.get("newClientOrderId").map(|s| s.as_str()),
+        )
+        .await
+    {
+        Ok(order) => Ok(Json(order.to_json())),
+        Err((code, msg)) => Err(api_error(StatusCode::BAD_REQUEST, code, &msg)),
+    }
+}
+
+async fn cancel_order(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    let params = authenticate(&state, &headers, &query, true)?;
+    let symbol = required(&params, "symbol")?;
+    let order_id = params.get("orderId").and_then(|id| id.parse::<u64>().ok());
+    let client_order_id = params.get("origClientOrderId").map(|s| s.as_str());
+    match state.cancel_order(symbol, order_id, client_order_id).await {
+        Ok(order) => Ok(Json(order.to_json())),
+        Err((code, msg)) => Err(api_error(StatusCode::BAD_REQUEST, code, &msg)),
+    }
+}
+
+async fn cancel_all_open_orders(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    let params = authenticate(&state, &headers, &query, true)?;
+    let symbol = required(&params, "symbol")?;
+    state.cancel_open_orders(symbol).await;
+    Ok(Json(
+        json!({ "code": 200, "msg": "The operation of cancel all open order is done." }),
+    ))
+}
+
+async fn open_orders(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    let params = authenticate(&state, &headers, &query, true)?;
+    let orders = state.open_orders(params.get("symbol").map(|s| s.as_str())).await;
+    Ok(Json(Value::Array(orders.iter().map(|o| o.to_json()).collect())))
+}
+
+async fn balance(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, true)?;
+    Ok(Json(state.balances_json().await))
+}
+
+async fn position_risk(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, true)?;
+    Ok(Json(state.positions_json().await))
+}
+
+async fn account(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, true)?;
+    Ok(Json(state.account_json().await))
+}
+
+async fn position_mode(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, true)?;
+    Ok(Json(state.position_mode_json().await))
+}
+
+async fn api_restrictions(
+    State(state): State<Arc<MockVenueState>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Value>, ApiError> {
+    authenticate(&state, &headers, &query, true)?;
+    Ok(Json(state.api_restrictions_json().await))
+}
+
+async fn user_stream(
+    State(state): State<Arc<MockVenueState>>,
+    Path(listen_key): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !state.is_valid_listen_key(&listen_key).await {
+        return api_error(StatusCode::BAD_REQUEST, -1125, "This listenKey does not exist.").into_response();
+    }
+    // Subscribe before the upgrade so no events are missed between the handshake and the handler
+    let events = state.subscribe_user_stream();
+    ws.on_upgrade(move |socket| handle_user_stream(socket, events))
+}
+
+async fn handle_user_stream(socket: WebSocket, mut events: broadcast::Receiver<String>) {
+    let (mut sender, mut receiver) = socket.split();
+    loop {
+        tokio::select! {
+            Ok(event) = events.recv() => {
+                if sender.send(Message::Text(event)).await.is_err() {
+                    break;
+                }
+            }
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Ping(payload))) => {
+                    let _ = sender.send(Message::Pong(payload)).await;
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("User stream connection closed");
+}
+
+async fn market_stream(State(state): State<Arc<MockVenueState>>, ws: WebSocketUpgrade) -> Response {
+    let events = state.subscribe_market_stream();
+    ws.on_upgrade(move |socket| handle_market_stream(socket, events))
+}
+
+async fn handle_market_stream(socket: WebSocket, mut events: broadcast::Receiver<(String, String)>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut subscriptions = HashSet::new();
+    loop {
+        tokio::select! {
+            Ok((stream, payload)) = events.recv() => {
+                if subscriptions.contains(&stream) && sender.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(request) = serde_json::from_str::<Value>(&text) else {
+                        warn!("Mock venue received invalid request: {}", text);
+                        continue;
+                    };
+                    let streams = request["params"]
+                        .as_array()
+                        .map(|p| p.iter().filter_map(|s| s.as_str().map(|s| s.to_string())).collect::<Vec<_>>())
+                        .unwrap_or_default();
+                    let result = match request["method"].as_str() {
+                        Some("SUBSCRIBE") => {
+                            subscriptions.extend(streams);
+                            Value::Null
+                        }
+                        Some("UNSUBSCRIBE") => {
+                            for stream in &streams {
+                                subscriptions.remove(stream);
+                            }
+                            Value::Null
+                        }
+                        Some("LIST_SUBSCRIPTIONS") => json!(subscriptions.iter().collect::<Vec<_>>()),
+                        _ => Value::Null,
+                    };
+                    let response = json!({ "result": result, "id": request["id"] });
+                    if sender.send(Message::Text(response.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Ping(payload))) => {
+                    let _ = sender.send(Message::Pong(payload)).await;
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("Market stream connection closed");
+}
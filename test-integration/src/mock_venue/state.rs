@@ -0,0 +1,670 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast, Mutex};
+use tracing::debug;
+
+/// Order as tracked by the mock venue.
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub order_type: String,
+    pub time_in_force: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub filled_quantity: Decimal,
+    pub avg_price: Decimal,
+    pub status: String,
+    pub update_time: i64,
+}
+
+impl MockOrder {
+    pub fn is_open(&self) -> bool {
+        self.status == "NEW" || self.status == "PARTIALLY_FILLED"
+    }
+
+    fn signed_quantity(&self, quantity: Decimal) -> Decimal {
+        if self.side == "BUY" {
+            quantity
+        } else {
+            -quantity
+        }
+    }
+
+    /// REST representation of the order (`POST /fapi/v1/order`, `GET /fapi/v1/openOrders`).
+    pub fn to_json(&self) -> Value {
+        json!({
+            "clientOrderId": self.client_order_id,
+            "cumQty": self.filled_quantity.to_string(),
+            "cumQuote": (self.filled_quantity * self.avg_price).to_string(),
+            "executedQty": self.filled_quantity.to_string(),
+            "orderId": self.order_id,
+            "avgPrice": self.avg_price.to_string(),
+            "origQty": self.quantity.to_string(),
+            "price": self.price.to_string(),
+            "reduceOnly": false,
+            "side": self.side,
+            "positionSide": "BOTH",
+            "status": self.status,
+            "stopPrice": "0",
+            "closePosition": false,
+            "symbol": self.symbol,
+            "timeInForce": self.time_in_force,
+            "type": self.order_type,
+            "origType": self.order_type,
+            "updateTime": self.update_time,
+            "workingType": "CONTRACT_PRICE",
+            "priceProtect": false,
+            "priceMatch": "NONE",
+            "selfTradePreventionMode": "NONE",
+            "goodTillDate": 0
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockPosition {
+    pub symbol: String,
+    pub amount: Decimal,
+    pub entry_price: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    mark_prices: HashMap<String, Decimal>,
+    balances: HashMap<String, Decimal>,
+    positions: HashMap<String, MockPosition>,
+    orders: Vec<MockOrder>,
+    listen_keys: HashSet<String>,
+    next_order_id: u64,
+    next_trade_id: u64,
+    dual_side_position: bool,
+    withdrawals_enabled: bool,
+}
+
+/// In-memory account and matching state of the mock venue.
+///
+/// Market orders fill immediately at the mark price, limit orders rest until the mark price crosses them.
+/// Every change is pushed to the user data stream in the Binance wire format.
+#[derive(Debug)]
+pub struct MockVenueState {
+    pub api_key: String,
+    pub api_secret: String,
+    pub quote_asset: String,
+    pub taker_fee: Decimal,
+    inner: Mutex<Inner>,
+    user_stream: broadcast::Sender<String>,
+    market_stream: broadcast::Sender<(String, String)>,
+}
+
+impl MockVenueState {
+    pub fn new(api_key: &str, api_secret: &str) -> Self {
+        let (user_stream, _) = broadcast::channel(1024);
+        let (market_stream, _) = broadcast::channel(1024);
+        Self {
+            api_key: api_key.to_string(),
+            api_secret: api_secret.to_string(),
+            quote_asset: "USDT".to_string(),
+            taker_fee: dec!(0.0005),
+            inner: Mutex::new(Inner {
+                next_order_id: 1,
+                next_trade_id: 1,
+                ..Default::default()
+            }),
+            user_stream,
+            market_stream,
+        }
+    }
+
+    pub fn subscribe_user_stream(&self) -> broadcast::Receiver<String> {
+        self.user_stream.subscribe()
+    }
+
+    pub fn subscribe_market_stream(&self) -> broadcast::Receiver<(String, String)> {
+        self.market_stream.subscribe()
+    }
+
+    pub async fn set_balance(&self, asset: &str, quantity: Decimal) {
+        self.inner.lock().await.balances.insert(asset.to_string(), quantity);
+    }
+
+    /// Switch the account between hedge (`true`) and one-way mode.
+    pub async fn set_dual_side_position(&self, dual_side_position: bool) {
+        self.inner.lock().await.dual_side_position = dual_side_position;
+    }
+
+    pub async fn set_withdrawals_enabled(&self, enabled: bool) {
+        self.inner.lock().await.withdrawals_enabled = enabled;
+    }
+
+    pub async fn balance(&self, asset: &str) -> Decimal {
+        self.inner.lock().await.balances.get(asset).cloned().unwrap_or_default()
+    }
+
+    pub async fn position(&self, symbol: &str) -> Option<MockPosition> {
+        self.inner.lock().await.positions.get(symbol).cloned()
+    }
+
+    pub async fn orders(&self) -> Vec<MockOrder> {
+        self.inner.lock().await.orders.clone()
+    }
+
+    pub async fn open_orders(&self, symbol: Option<&str>) -> Vec<MockOrder> {
+        self.inner
+            .lock()
+            .await
+            .orders
+            .iter()
+            .filter(|o| o.is_open() && symbol.is_none_or(|s| o.symbol == s))
+            .cloned()
+            .collect()
+    }
+
+    pub async fn is_known_symbol(&self, symbol: &str) -> bool {
+        self.inner.lock().await.mark_prices.contains_key(symbol)
+    }
+
+    pub async fn new_listen_key(&self) -> String {
+        let key = uuid::Uuid::new_v4().simple().to_string();
+        self.inner.lock().await.listen_keys.insert(key.clone());
+        key
+    }
+
+    pub async fn is_valid_listen_key(&self, key: &str) -> bool {
+        self.inner.lock().await.listen_keys.contains(key)
+    }
+
+    /// Update the mark price of a symbol and match resting limit orders against it.
+    pub async fn set_mark_price(&self, symbol: &str, price: Decimal) {
+        let mut inner = self.inner.lock().await;
+        inner.mark_prices.insert(symbol.to_string(), price);
+
+        let crossed = inner
+            .orders
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.is_open() && o.symbol == symbol && Self::crosses(o, price))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        for idx in crossed {
+            let fill_price = inner.orders[idx].price;
+            self.fill(&mut inner, idx, fill_price);
+        }
+    }
+
+    /// Publish an aggregated trade on the market stream, which also moves the mark price.
+    pub async fn publish_agg_trade(&self, symbol: &str, price: Decimal, quantity: Decimal, maker: bool) {
+        let now = now_ms();
+        let id = {
+            let mut inner = self.inner.lock().await;
+            inner.next_trade_id += 1;
+            inner.next_trade_id
+        };
+        let payload = json!({
+            "e": "aggTrade",
+            "E": now,
+            "a": id,
+            "s": symbol,
+            "p": price.to_string(),
+            "q": quantity.to_string(),
+            "f": id,
+            "l": id,
+            "T": now,
+            "m": maker
+        });
+        let _ = self
+            .market_stream
+            .send((format!("{}@aggTrade", symbol.to_lowercase()), payload.to_string()));
+        self.set_mark_price(symbol, price).await;
+    }
+
+    /// Publish a best bid/ask update on the market stream.
+    pub async fn publish_book_ticker(
+        &self,
+        symbol: &str,
+        bid_price: Decimal,
+        bid_quantity: Decimal,
+        ask_price: Decimal,
+        ask_quantity: Decimal,
+    ) {
+        let now = now_ms();
+        let id = {
+            let mut inner = self.inner.lock().await;
+            inner.next_trade_id += 1;
+            inner.next_trade_id
+        };
+        let payload = json!({
+            "e": "bookTicker",
+            "u": id,
+            "E": now,
+            "T": now,
+            "s": symbol,
+            "b": bid_price.to_string(),
+            "B": bid_quantity.to_string(),
+            "a": ask_price.to_string(),
+            "A": ask_quantity.to_string()
+        });
+        let _ = self
+            .market_stream
+            .send((format!("{}@bookTicker", symbol.to_lowercase()), payload.to_string()));
+    }
+
+    /// Accept a new order, filling it right away if it is marketable.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_order(
+        &self,
+        symbol: &str,
+        side: &str,
+        order_type: &str,
+        time_in_force: Option<&str>,
+        quantity: Decimal,
+        price: Option<Decimal>,
+        client_order_id: Option<&str>,
+    ) -> Result<MockOrder, (i64, String)> {
+        let mut inner = self.inner.lock().await;
+        let Some(mark_price) = inner.mark_prices.get(symbol).cloned() else {
+            return Err((-1121, "Invalid symbol.".to_string()));
+        };
+        if quantity <= Decimal::ZERO {
+            return Err((-4003, "Quantity less than or equal to zero.".to_string()));
+        }
+        if order_type == "LIMIT" && price.is_none() {
+            return Err((
+                -1102,
+                "Mandatory parameter 'price' was not sent, was empty/null, or malformed.".to_string(),
+            ));
+        }
+
+        let order_id = inner.next_order_id;
+        inner.next_order_id += 1;
+        let order = MockOrder {
+            order_id,
+            client_order_id: client_order_id
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            order_type: order_type.to_string(),
+            time_in_force: time_in_force.unwrap_or("GTC").to_string(),
+            price: price.unwrap_or_default(),
+            quantity,
+            filled_quantity: Decimal::ZERO,
+            avg_price: Decimal::ZERO,
+            status: "NEW".to_string(),
+            update_time: now_ms(),
+        };
+        inner.orders.push(order);
+        let idx = inner.orders.len() - 1;
+        self.publish_order_update(&inner.orders[idx], "NEW", Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+
+        let marketable = order_type == "MARKET" || Self::crosses(&inner.orders[idx], mark_price);
+        if marketable {
+            self.fill(&mut inner, idx, mark_price);
+        } else if inner.orders[idx].time_in_force != "GTC" {
+            // IOC and FOK orders that can't fill right away expire
+            inner.orders[idx].status = "EXPIRED".to_string();
+            self.publish_order_update(&inner.orders[idx], "EXPIRED", Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+        }
+        Ok(inner.orders[idx].clone())
+    }
+
+    /// Cancel all open orders of a symbol, returns the number of cancelled orders.
+    pub async fn cancel_open_orders(&self, symbol: &str) -> usize {
+        let mut inner = self.inner.lock().await;
+        let mut cancelled = 0;
+        for idx in 0..inner.orders.len() {
+            if inner.orders[idx].is_open() && inner.orders[idx].symbol == symbol {
+                inner.orders[idx].status = "CANCELED".to_string();
+                inner.orders[idx].update_time = now_ms();
+                self.publish_order_update(&inner.orders[idx], "CANCELED", Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    /// Cancel a single order by venue or client order id.
+    pub async fn cancel_order(
+        &self,
+        symbol: &str,
+        order_id: Option<u64>,
+        client_order_id: Option<&str>,
+    ) -> Result<MockOrder, (i64, String)> {
+        let mut inner = self.inner.lock().await;
+        let idx = inner.orders.iter().position(|o| {
+            o.symbol == symbol
+                && (order_id.is_some_and(|id| o.order_id == id)
+                    || client_order_id.is_some_and(|id| o.client_order_id == id))
+        });
+        match idx {
+            Some(idx) if inner.orders[idx].is_open() => {
+                inner.orders[idx].status = "CANCELED".to_string();
+                inner.orders[idx].update_time = now_ms();
+                self.publish_order_update(&inner.orders[idx], "CANCELED", Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+                Ok(inner.orders[idx].clone())
+            }
+            _ => Err((-2011, "Unknown order sent.".to_string())),
+        }
+    }
+
+    pub async fn balances_json(&self) -> Value {
+        let inner = self.inner.lock().await;
+        let now = now_ms();
+        let balances = inner
+            .balances
+            .iter()
+            .map(|(asset, balance)| {
+                json!({
+                    "accountAlias": "mock",
+                    "asset": asset,
+                    "balance": balance.to_string(),
+                    "crossWalletBalance": balance.to_string(),
+                    "crossUnPnl": "0",
+                    "availableBalance": balance.to_string(),
+                    "maxWithdrawAmount": balance.to_string(),
+                    "marginAvailable": true,
+                    "updateTime": now
+                })
+            })
+            .collect::<Vec<_>>();
+        Value::Array(balances)
+    }
+
+    pub async fn positions_json(&self) -> Value {
+        let inner = self.inner.lock().await;
+        let now = now_ms();
+        let positions = inner
+            .positions
+            .values()
+            .filter(|p| !p.amount.is_zero())
+            .map(|p| {
+                let mark_price = inner.mark_prices.get(&p.symbol).cloned().unwrap_or(p.entry_price);
+                let unrealized = (mark_price - p.entry_price) * p.amount;
+                json!({
+                    "symbol": p.symbol,
+                    "positionSide": "BOTH",
+                    "positionAmt": p.amount.to_string(),
+                    "entryPrice": p.entry_price.to_string(),
+                    "breakEvenPrice": p.entry_price.to_string(),
+                    "markPrice": mark_price.to_string(),
+                    "unRealizedProfit": unrealized.to_string(),
+                    "liquidationPrice": "0",
+                    "isolatedMargin": "0",
+                    "notional": (mark_price * p.amount).to_string(),
+                    "marginAsset": self.quote_asset,
+                    "isolatedWallet": "0",
+                    "initialMargin": "0",
+                    "maintMargin": "0",
+                    "positionInitialMargin": "0",
+                    "openOrderInitialMargin": "0",
+                    "adl": 0,
+                    "bidNotional": "0",
+                    "askNotional": "0",
+                    "updateTime": now
+                })
+            })
+            .collect::<Vec<_>>();
+        Value::Array(positions)
+    }
+
+    /// REST representation of the api key permissions (`GET /sapi/v1/account/apiRestrictions`).
+    pub async fn api_restrictions_json(&self) -> Value {
+        let inner = self.inner.lock().await;
+        json!({
+            "ipRestrict": true,
+            "createTime": now_ms(),
+            "enableReading": true,
+            "enableFutures": true,
+            "enableWithdrawals": inner.withdrawals_enabled,
+            "enableInternalTransfer": false,
+            "permitsUniversalTransfer": false,
+            "enableMargin": false,
+            "enableSpotAndMarginTrading": false,
+            "enableVanillaOptions": false
+        })
+    }
+
+    pub async fn position_mode_json(&self) -> Value {
+        json!({ "dualSidePosition": self.inner.lock().await.dual_side_position })
+    }
+
+    pub async fn account_json(&self) -> Value {
+        let now = now_ms();
+        let assets = self
+            .balances_json()
+            .await
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|b| {
+                json!({
+                    "asset": b["asset"],
+                    "walletBalance": b["balance"],
+                    "unrealizedProfit": "0",
+                    "marginBalance": b["balance"],
+                    "maintMargin": "0",
+                    "initialMargin": "0",
+                    "positionInitialMargin": "0",
+                    "openOrderInitialMargin": "0",
+                    "crossWalletBalance": b["balance"],
+                    "crossUnPnl": "0",
+                    "availableBalance": b["balance"],
+                    "maxWithdrawAmount": b["balance"],
+                    "updateTime": now
+                })
+            })
+            .collect::<Vec<_>>();
+        let positions = self
+            .positions_json()
+            .await
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| {
+                json!({
+                    "symbol": p["symbol"],
+                    "positionSide": "BOTH",
+                    "positionAmt": p["positionAmt"],
+                    "unrealizedProfit": p["unRealizedProfit"],
+                    "isolatedMargin": "0",
+                    "notional": p["notional"],
+                    "isolatedWallet": "0",
+                    "initialMargin": "0",
+                    "maintMargin": "0",
+                    "updateTime": now
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "totalInitialMargin": "0",
+            "totalMaintMargin": "0",
+            "totalWalletBalance": "0",
+            "totalUnrealizedProfit": "0",
+            "totalMarginBalance": "0",
+            "totalPositionInitialMargin": "0",
+            "totalOpenOrderInitialMargin": "0",
+            "totalCrossWalletBalance": "0",
+            "totalCrossUnPnl": "0",
+            "availableBalance": "0",
+            "maxWithdrawAmount": "0",
+            "assets": assets,
+            "positions": positions
+        })
+    }
+
+    fn crosses(order: &MockOrder, mark_price: Decimal) -> bool {
+        order.order_type == "LIMIT"
+            && ((order.side == "BUY" && order.price >= mark_price)
+                || (order.side == "SELL" && order.price <= mark_price))
+    }
+
+    /// Fill the remaining quantity of an order and book the trade on the account.
+    fn fill(&self, inner: &mut Inner, idx: usize, price: Decimal) {
+        let order = &mut inner.orders[idx];
+        let quantity = order.quantity - order.filled_quantity;
+        order.avg_price = if order.filled_quantity.is_zero() {
+            price
+        } else {
+            (order.avg_price * order.filled_quantity + price * quantity) / order.quantity
+        };
+        order.filled_quantity = order.quantity;
+        order.status = "FILLED".to_string();
+        order.update_time = now_ms();
+        let order = order.clone();
+        let commission = (price * quantity * self.taker_fee).round_dp(8);
+
+        // Update the position, realizing pnl on the reduced part
+        let signed = order.signed_quantity(quantity);
+        let position = inner.positions.entry(order.symbol.clone()).or_insert_with(|| MockPosition {
+            symbol: order.symbol.clone(),
+            ..Default::default()
+        });
+        let mut realized = Decimal::ZERO;
+        if position.amount.is_zero() || position.amount.signum() == signed.signum() {
+            let amount = position.amount + signed;
+            position.entry_price = (position.entry_price * position.amount + price * signed) / amount;
+            position.amount = amount;
+        } else {
+            let closed = signed.abs().min(position.amount.abs());
+            realized = (price - position.entry_price) * closed * position.amount.signum();
+            position.amount += signed;
+            if position.amount.is_zero() {
+                position.entry_price = Decimal::ZERO;
+            } else if position.amount.signum() == signed.signum() {
+                // Flipped side, the remainder opens at the fill price
+                position.entry_price = price;
+            }
+        }
+        let position = position.clone();
+
+        let balance = inner.balances.entry(self.quote_asset.clone()).or_default();
+        *balance += realized - commission;
+        let balance = *balance;
+        debug!(
+            "Mock venue filled {} {} {} @ {} (realized: {}, commission: {})",
+            order.side, quantity, order.symbol, price, realized, commission
+        );
+
+        inner.next_trade_id += 1;
+        let trade_id = inner.next_trade_id;
+        self.publish_order_update_with_trade(&order, "TRADE", quantity, price, commission, realized, trade_id);
+        self.publish_account_update(&position, balance, realized - commission);
+    }
+
+    fn publish_order_update(
+        &self,
+        order: &MockOrder,
+        execution_type: &str,
+        last_quantity: Decimal,
+        last_price: Decimal,
+        commission: Decimal,
+    ) {
+        self.publish_order_update_with_trade(
+            order,
+            execution_type,
+            last_quantity,
+            last_price,
+            commission,
+            Decimal::ZERO,
+            0,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn publish_order_update_with_trade(
+        &self,
+        order: &MockOrder,
+        execution_type: &str,
+        last_quantity: Decimal,
+        last_price: Decimal,
+        commission: Decimal,
+        realized: Decimal,
+        trade_id: u64,
+    ) {
+        let now = now_ms();
+        let event = json!({
+            "e": "ORDER_TRADE_UPDATE",
+            "E": now,
+            "T": now,
+            "o": {
+                "s": order.symbol,
+                "c": order.client_order_id,
+                "S": order.side,
+                "o": order.order_type,
+                "f": order.time_in_force,
+                "q": order.quantity.to_string(),
+                "p": order.price.to_string(),
+                "ap": order.avg_price.to_string(),
+                "sp": "0",
+                "x": execution_type,
+                "X": order.status,
+                "i": order.order_id,
+                "l": last_quantity.to_string(),
+                "z": order.filled_quantity.to_string(),
+                "L": last_price.to_string(),
+                "N": self.quote_asset,
+                "n": commission.to_string(),
+                "T": now,
+                "t": trade_id,
+                "b": "0",
+                "a": "0",
+                "m": order.order_type == "LIMIT",
+                "R": false,
+                "wt": "CONTRACT_PRICE",
+                "ot": order.order_type,
+                "ps": "BOTH",
+                "cp": false,
+                "rp": realized.to_string(),
+                "pP": false,
+                "si": 0,
+                "ss": 0,
+                "V": "NONE",
+                "pm": "NONE",
+                "gtd": 0
+            }
+        });
+        let _ = self.user_stream.send(event.to_string());
+    }
+
+    fn publish_account_update(&self, position: &MockPosition, balance: Decimal, balance_change: Decimal) {
+        let now = now_ms();
+        let event = json!({
+            "e": "ACCOUNT_UPDATE",
+            "E": now,
+            "T": now,
+            "a": {
+                "m": "ORDER",
+                "B": [{
+                    "a": self.quote_asset,
+                    "wb": balance.to_string(),
+                    "cw": balance.to_string(),
+                    "bc": balance_change.to_string()
+                }],
+                "P": [{
+                    "s": position.symbol,
+                    "pa": position.amount.to_string(),
+                    "ep": position.entry_price.to_string(),
+                    "bep": position.entry_price.to_string(),
+                    "cr": "0",
+                    "up": "0",
+                    "mt": "cross",
+                    "iw": "0",
+                    "ps": "BOTH"
+                }]
+            }
+        });
+        let _ = self.user_stream.send(event.to_string());
+    }
+}
+
+pub(crate) fn now_ms() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use arkin_binance::listen_key::NewListenKey;
+use arkin_binance::prelude::*;
+use arkin_binance::trade::{
+    ApiRestrictions, ApiRestrictionsRequest, CancelOpenOrdersRequest, NewOrderRequest, OrderType, PositionModeRequest,
+    PositionModeResponse, Side, TimeInForce,
+};
+use async_tungstenite::tungstenite::Message;
+use futures_util::StreamExt;
+use rust_decimal_macros::dec;
+use serde_json::Value;
+use test_log::test;
+use url::Url;
+
+use test_integration::prelude::*;
+
+fn client(mock: &MockBinance, api_secret: &str) -> BinanceHttpClient {
+    BinanceHttpClient::builder()
+        .base_url(Url::parse(&mock.http_url()).unwrap())
+        .credentials(Some(Credentials::from_hmac(MockBinance::API_KEY, api_secret)))
+        .build()
+}
+
+async fn next_text<S>(stream: &mut S) -> Value
+where
+    S: futures_util::Stream<Item = Result<Message, async_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Timed out waiting for websocket message")
+            .expect("Websocket closed")
+            .expect("Websocket error");
+        if let Message::Text(text) = msg {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[test(tokio::test)]
+async fn test_market_order_fills_at_mark() {
+    let mock = MockBinance::start().await;
+    mock.state.set_balance("USDT", dec!(10000)).await;
+    mock.state.set_mark_price("BTCUSDT", dec!(50000)).await;
+
+    let req = NewOrderRequest::builder()
+        .symbol("BTCUSDT".to_string())
+        .side(Side::Buy)
+        .order_type(OrderType::Market)
+        .quantity(Some(dec!(0.1)))
+        .new_client_order_id(Some("test-order".to_string()))
+        .build();
+    let res = client(&mock, MockBinance::API_SECRET).send(req).await.unwrap();
+    let order: Value = serde_json::from_str(&res.body).unwrap();
+
+    assert_eq!(order["status"], "FILLED");
+    assert_eq!(order["clientOrderId"], "test-order");
+    assert_eq!(order["avgPrice"], "50000");
+
+    let position = mock.state.position("BTCUSDT").await.unwrap();
+    assert_eq!(position.amount, dec!(0.1));
+    assert_eq!(position.entry_price, dec!(50000));
+    // 0.05% taker fee on 5000 notional
+    assert_eq!(mock.state.balance("USDT").await, dec!(9997.5));
+
+    mock.stop().await;
+}
+
+#[test(tokio::test)]
+async fn test_invalid_signature_is_rejected() {
+    let mock = MockBinance::start().await;
+    mock.state.set_mark_price("BTCUSDT", dec!(50000)).await;
+
+    let req = NewOrderRequest::builder()
+        .symbol("BTCUSDT".to_string())
+        .side(Side::Buy)
+        .order_type(OrderType::Market)
+        .quantity(Some(dec!(0.1)))
+        .build();
+    let res = client(&mock, "wrong-secret").send(req).await.unwrap();
+    let error: Value = serde_json::from_str(&res.body).unwrap();
+
+    assert_eq!(error["code"], -1022);
+    assert!(mock.state.orders().await.is_empty());
+
+    mock.stop().await;
+}
+
+#[test(tokio::test)]
+async fn test_api_key_permissions_and_position_mode() {
+    let mock = MockBinance::start().await;
+    let client = client(&mock, MockBinance::API_SECRET);
+
+    let res = client.send(ApiRestrictionsRequest::new()).await.unwrap();
+    let restrictions = serde_json::from_str::<ApiRestrictions>(&res.body).unwrap();
+    assert!(restrictions.enable_futures);
+    assert!(restrictions.ip_restrict);
+    assert!(!restrictions.enable_withdrawals);
+
+    let res = client.send(PositionModeRequest::new()).await.unwrap();
+    let mode = serde_json::from_str::<PositionModeResponse>(&res.body).unwrap();
+    assert!(!mode.dual_side_position);
+
+    mock.state.set_dual_side_position(true).await;
+    mock.state.set_withdrawals_enabled(true).await;
+    let res = client.send(PositionModeRequest::new()).await.unwrap();
+    let mode = serde_json::from_str::<PositionModeResponse>(&res.body).unwrap();
+    assert!(mode.dual_side_position);
+    let res = client.send(ApiRestrictionsRequest::new()).await.unwrap();
+    let restrictions = serde_json::from_str::<ApiRestrictions>(&res.body).unwrap();
+    assert!(restrictions.enable_withdrawals);
+
+    mock.stop().await;
+}
+
+#[test(tokio::test)]
+async fn test_user_stream_reports_limit_fill_and_cancel() {
+    let mock = MockBinance::start().await;
+    mock.state.set_balance("USDT", dec!(10000)).await;
+    mock.state.set_mark_price("ETHUSDT", dec!(3000)).await;
+    let client = client(&mock, MockBinance::API_SECRET);
+
+    let res = client.send(NewListenKey::new()).await.unwrap();
+    let listen_key = serde_json::from_str::<Value>(&res.body).unwrap()["listenKey"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (mut ws, _) = BinanceWebSocketClient::connect(&format!("{}/{}", mock.ws_url(), listen_key))
+        .await
+        .unwrap();
+
+    // Resting limit order below the mark price
+    let req = NewOrderRequest::builder()
+        .symbol("ETHUSDT".to_string())
+        .side(Side::Buy)
+        .order_type(OrderType::Limit)
+        .time_in_force(Some(TimeInForce::Gtc))
+        .price(Some(dec!(2900)))
+        .quantity(Some(dec!(1)))
+        .build();
+    client.send(req).await.unwrap();
+    let update = next_text(ws.as_mut()).await;
+    assert_eq!(update["e"], "ORDER_TRADE_UPDATE");
+    assert_eq!(update["o"]["X"], "NEW");
+    assert_eq!(mock.state.open_orders(Some("ETHUSDT")).await.len(), 1);
+
+    // The mark price crosses the order
+    mock.state.set_mark_price("ETHUSDT", dec!(2890)).await;
+    let update = next_text(ws.as_mut()).await;
+    assert_eq!(update["o"]["X"], "FILLED");
+    assert_eq!(update["o"]["L"], "2900");
+    let update = next_text(ws.as_mut()).await;
+    assert_eq!(update["e"], "ACCOUNT_UPDATE");
+    assert_eq!(update["a"]["P"][0]["pa"], "1");
+
+    // A second order gets cancelled
+    let req = NewOrderRequest::builder()
+        .symbol("ETHUSDT".to_string())
+        .side(Side::Sell)
+        .order_type(OrderType::Limit)
+        .time_in_force(Some(TimeInForce::Gtc))
+        .price(Some(dec!(3100)))
+        .quantity(Some(dec!(1)))
+        .build();
+    client.send(req).await.unwrap();
+    let _ = next_text(ws.as_mut()).await;
+    client
+        .send(CancelOpenOrdersRequest::builder().symbol("ETHUSDT".to_string()).build())
+        .await
+        .unwrap();
+    let update = next_text(ws.as_mut()).await;
+    assert_eq!(update["o"]["X"], "CANCELED");
+    assert!(mock.state.open_orders(Some("ETHUSDT")).await.is_empty());
+
+    mock.stop().await;
+}
+
+#[test(tokio::test)]
+async fn test_market_stream_subscription() {
+    let mock = MockBinance::start().await;
+    let (mut ws, _) = BinanceWebSocketClient::connect(&mock.ws_url()).await.unwrap();
+
+    let id = ws.subscribe(&[Stream::new("btcusdt@aggTrade")]).await;
+    let ack = next_text(ws.as_mut()).await;
+    assert_eq!(ack["id"], id);
+
+    // Not subscribed, so this one should not arrive
+    mock.state
+        .publish_book_ticker("BTCUSDT", dec!(49999), dec!(1), dec!(50001), dec!(2))
+        .await;
+    mock.state.publish_agg_trade("BTCUSDT", dec!(50000), dec!(0.5), false).await;
+
+    let trade = next_text(ws.as_mut()).await;
+    assert_eq!(trade["e"], "aggTrade");
+    assert_eq!(trade["s"], "BTCUSDT");
+    assert_eq!(trade["p"], "50000");
+
+    mock.stop().await;
+}